@@ -11,29 +11,48 @@
 #![allow(clippy::option_map_unit_fn)]
 #![allow(clippy::explicit_counter_loop)]
 
-use crate::config::{Settings, Theme, ViewMode, WindowSize};
+use crate::cli::LaunchOptions;
+use crate::config::{Settings, Theme, ViewMode, WindowSize, RECOVERY_SNAPSHOT_INTERVAL_SECS};
 use crate::editor::{
-    extract_outline_for_file, DocumentOutline, EditorWidget, FindReplacePanel, SearchHighlights,
-    TextStats,
+    extract_outline_for_file, retain_layout_caches, DocumentOutline, EditorWidget,
+    FindReplacePanel, MergeResult, SearchHighlights, TextStats, BYTES_PER_STEP,
 };
-use crate::export::{copy_html_to_clipboard, generate_html_document};
+use crate::export::options::{ExportOptions, ImageHandling};
+use crate::export::{copy_html_to_clipboard, export_markdown_to_html};
+use crate::files::assets;
+use crate::files::autosave::{AutoSaveProblem, AutoSaveScheduler};
 use crate::files::dialogs::{open_multiple_files_dialog, save_file_dialog};
+use crate::files::encoding::{read_text_file, FileFormat, LineEnding, TextEncoding};
 use crate::fonts;
+use crate::ipc;
+use crate::markdown::images;
+use crate::markdown::wikilinks::{self, NoteResolver, WikiLink};
 use crate::markdown::{
-    apply_raw_format, detect_raw_formatting_state, get_structured_file_type, EditorMode,
-    FormattingState, MarkdownEditor, MarkdownFormatCommand, TreeViewer, TreeViewerState,
+    apply_raw_format, detect_raw_formatting_state, get_structured_file_type, DocumentCache,
+    EditorMode, FormattingState, MarkdownEditor, MarkdownFormatCommand, TreeViewer,
+    TreeViewerState,
 };
 use crate::preview::{ScrollOrigin, SyncScrollState};
-use crate::state::{AppState, FileType, PendingAction};
+use crate::state::{AppState, ExternalChange, FileType, PendingAction};
+use crate::string_utils::char_index_to_byte_index;
 use crate::theme::{ThemeColors, ThemeManager};
 use crate::ui::{
-    handle_window_resize, AboutPanel, FileOperationDialog, FileOperationResult,
-    FileTreeContextAction, FileTreePanel, OutlinePanel, QuickSwitcher, Ribbon, RibbonAction,
-    SearchPanel, SettingsPanel, WindowResizeState,
+    handle_window_resize, AboutPanel, BacklinksPanel, FileOperationDialog, FileOperationResult,
+    FileReplace, FileTreeContextAction, FileTreePanel, OutlinePanel, QuickSwitcher, ReplaceSummary,
+    ReplacedFile, Ribbon, RibbonAction, SearchPanel, SettingsPanel, WindowResizeState,
 };
+use crate::workspaces::{Backlink, BacklinkJob, SearchLocation};
 use eframe::egui;
 use log::{debug, info, warn};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+
+/// How long an opened search result stays highlighted in rendered mode.
+const SEARCH_HIGHLIGHT_SECS: f64 = 2.0;
+
+/// How often to check for backlinks while the index is working on them.
+const BACKLINKS_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
 
 /// Keyboard shortcut actions that need to be deferred.
 ///
@@ -59,6 +78,8 @@ enum KeyboardAction {
     PrevTab,
     /// Toggle view mode (Ctrl+E)
     ToggleViewMode,
+    /// Toggle split view (Ctrl+\)
+    ToggleSplitView,
     /// Cycle theme (Ctrl+Shift+T)
     CycleTheme,
     /// Undo (Ctrl+Z)
@@ -75,6 +96,10 @@ enum KeyboardAction {
     FindNext,
     /// Find previous match (Shift+F3)
     FindPrev,
+    /// Open the next search in files result (F4)
+    NextSearchResult,
+    /// Open the previous search in files result (Shift+F4)
+    PrevSearchResult,
     /// Close find panel (Escape)
     CloseFindPanel,
     /// Apply markdown formatting
@@ -107,8 +132,17 @@ pub struct FerriteApp {
     about_panel: AboutPanel,
     /// Find/replace panel component
     find_replace_panel: FindReplacePanel,
+    /// Tab id and edit version the find matches were found in
+    search_source: Option<(usize, u64)>,
     /// Outline panel component
     outline_panel: OutlinePanel,
+    /// Backlinks panel component (workspace mode, next to the outline)
+    backlinks_panel: BacklinksPanel,
+    /// Wiki-links of the workspace's notes, indexed in the background once
+    /// backlinks are first shown
+    backlink_job: Option<BacklinkJob>,
+    /// Latest backlinks reported by the index, with the note they link to
+    cached_backlinks: Option<(PathBuf, Vec<Backlink>)>,
     /// File tree panel component (for workspace mode)
     file_tree_panel: FileTreePanel,
     /// Quick file switcher (Ctrl+P) for workspace mode
@@ -123,8 +157,16 @@ pub struct FerriteApp {
     last_outline_content_hash: u64,
     /// Pending scroll-to-line request from outline navigation (1-indexed)
     pending_scroll_to_line: Option<usize>,
+    /// Pending selection in the editor (character range), from a search
+    /// in files result or a `file:line:col` launch argument
+    pending_selection: Option<(usize, usize)>,
+    /// Search result line highlighted in rendered mode: tab ID, line
+    /// (1-indexed) and the app time the highlight ends
+    search_result_highlight: Option<(usize, usize, f64)>,
     /// Tree viewer states per tab (keyed by tab ID)
     tree_viewer_states: HashMap<usize, TreeViewerState>,
+    /// Parsed markdown for rendered mode per tab (keyed by tab ID)
+    markdown_documents: HashMap<usize, DocumentCache>,
     /// Sync scroll states per tab (keyed by tab ID)
     sync_scroll_states: HashMap<usize, SyncScrollState>,
     /// Track if we should exit (after confirmation)
@@ -140,20 +182,47 @@ pub struct FerriteApp {
     previous_view_mode: Option<ViewMode>,
     /// Window resize state for borderless window edge dragging
     window_resize_state: WindowResizeState,
+    /// Auto-save trigger tracking (interval, focus loss, tab switch)
+    auto_save: AutoSaveScheduler,
+    /// App time of the last hot-exit recovery snapshot
+    last_recovery_snapshot: f64,
+    /// Merge preview for a tab whose file changed on disk (tab ID, result)
+    pending_merge: Option<(usize, MergeResult)>,
+    /// Tab IDs opened with `--wait`; the app exits once all are closed
+    wait_for_tabs: Option<HashSet<usize>>,
+    /// Open requests forwarded by later launches (primary instance only)
+    ipc_requests: Option<Receiver<ipc::Incoming>>,
+    /// Tab IDs opened for a forwarded `--wait` launch, and its connection
+    remote_waiters: Vec<(HashSet<usize>, ipc::Waiter)>,
 }
 
 impl FerriteApp {
     /// Create a new FerriteApp instance.
     ///
-    /// This initializes the application state from the config file and applies
-    /// the saved theme preference.
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    /// This initializes the application state from the config file, applies
+    /// the saved theme preference, and opens whatever was passed on the
+    /// command line. `stdin_text` is the document piped in for `-`. With a
+    /// `listener`, this is the primary instance and opens files forwarded by
+    /// later launches.
+    pub fn new(
+        cc: &eframe::CreationContext<'_>,
+        launch: LaunchOptions,
+        stdin_text: Option<String>,
+        listener: Option<ipc::Listener>,
+    ) -> Self {
         info!("Initializing Ferrite");
 
         // Set up custom fonts with proper bold/italic variants
         fonts::setup_fonts(&cc.egui_ctx);
 
-        let state = AppState::new();
+        // Decode inline images in rendered mode off the UI thread
+        images::install_image_loader(&cc.egui_ctx);
+
+        let state = if launch.new_window {
+            AppState::new_without_session()
+        } else {
+            AppState::new()
+        };
 
         // Initialize theme manager with saved theme preference
         let mut theme_manager = ThemeManager::new(state.settings.theme);
@@ -167,14 +236,18 @@ impl FerriteApp {
             .with_width(state.settings.outline_width)
             .with_side(state.settings.outline_side);
 
-        Self {
+        let mut app = Self {
             state,
             theme_manager,
             ribbon: Ribbon::new(),
             settings_panel: SettingsPanel::new(),
             about_panel: AboutPanel::new(),
             find_replace_panel: FindReplacePanel::new(),
+            search_source: None,
             outline_panel,
+            backlinks_panel: BacklinksPanel::new(),
+            backlink_job: None,
+            cached_backlinks: None,
             file_tree_panel: FileTreePanel::new(),
             quick_switcher: QuickSwitcher::new(),
             file_operation_dialog: None,
@@ -182,7 +255,10 @@ impl FerriteApp {
             cached_outline: DocumentOutline::new(),
             last_outline_content_hash: 0,
             pending_scroll_to_line: None,
+            pending_selection: None,
+            search_result_highlight: None,
             tree_viewer_states: HashMap::new(),
+            markdown_documents: HashMap::new(),
             sync_scroll_states: HashMap::new(),
             should_exit: false,
             last_window_size: None,
@@ -190,7 +266,160 @@ impl FerriteApp {
             start_time: std::time::Instant::now(),
             previous_view_mode: None,
             window_resize_state: WindowResizeState::new(),
+            auto_save: AutoSaveScheduler::new(),
+            last_recovery_snapshot: 0.0,
+            pending_merge: None,
+            wait_for_tabs: None,
+            ipc_requests: listener.map(|listener| listener.spawn(cc.egui_ctx.clone())),
+            remote_waiters: Vec::new(),
+        };
+        let opened = app.open_launch_targets(&launch, stdin_text);
+        if launch.wait {
+            if opened.is_empty() {
+                warn!("--wait given without any files to open; ignoring");
+            } else {
+                app.wait_for_tabs = Some(opened.into_iter().collect());
+            }
+        }
+        app
+    }
+
+    /// Open the files, folder and stdin document given on the command line.
+    ///
+    /// The last file opened becomes the active tab and is scrolled to its
+    /// `file:line:col` position. If the only other tab is the blank untitled
+    /// tab created at startup, it is closed.
+    fn open_launch_targets(
+        &mut self,
+        launch: &LaunchOptions,
+        stdin_text: Option<String>,
+    ) -> Vec<usize> {
+        let blank_tab_id = match self.state.tabs() {
+            [tab] if tab.path.is_none() && !tab.is_modified() => Some(tab.id),
+            _ => None,
+        };
+
+        if let Some(folder) = &launch.folder {
+            if let Err(e) = self.state.open_workspace(folder.clone()) {
+                warn!("Failed to open workspace from command line: {}", e);
+                self.state
+                    .show_error(format!("Failed to open workspace:\n{}", e));
+            }
+        }
+
+        let mut opened = Vec::new();
+        for target in &launch.files {
+            match self.state.open_or_create_file(target.path.clone()) {
+                Ok(index) => {
+                    if let (Some(tab), Some(line)) = (self.state.tab_mut(index), target.line) {
+                        let column = target.column.unwrap_or(1);
+                        let cursor = launch_cursor(&tab.content, line, column);
+                        tab.cursor_position = char_index_to_line_col(&tab.content, cursor);
+                        tab.selection = None;
+                        self.pending_selection = Some((cursor, cursor));
+                        self.pending_scroll_to_line = Some(line);
+                    }
+                    opened.extend(self.state.tab(index).map(|tab| tab.id));
+                }
+                Err(e) => {
+                    warn!("Failed to open {}: {}", target.path.display(), e);
+                    self.state.show_error(format!(
+                        "Failed to open {}:\n{}",
+                        target.path.display(),
+                        e
+                    ));
+                }
+            }
+        }
+
+        if let Some(text) = stdin_text {
+            let index = self.state.open_untitled(text);
+            opened.extend(self.state.tab(index).map(|tab| tab.id));
         }
+
+        // The active tab is the last one opened; only it scrolls on startup
+        if let Some(&last) = opened.last() {
+            let active_id = self.state.active_tab().map(|tab| tab.id);
+            if active_id != Some(last) {
+                self.pending_scroll_to_line = None;
+                self.pending_selection = None;
+            }
+        }
+
+        if !opened.is_empty() {
+            if let Some(index) =
+                blank_tab_id.and_then(|id| self.state.tabs().iter().position(|tab| tab.id == id))
+            {
+                self.state.force_close_tab(index);
+            }
+        }
+
+        opened
+    }
+
+    /// Open files forwarded by later launches and raise the window.
+    fn handle_ipc_requests(&mut self, ctx: &egui::Context) {
+        let Some(receiver) = &self.ipc_requests else {
+            return;
+        };
+        let requests: Vec<ipc::Incoming> = receiver.try_iter().collect();
+        if requests.is_empty() {
+            return;
+        }
+
+        for ipc::Incoming { request, waiter } in requests {
+            info!(
+                "Opening {} file(s) from another launch",
+                request.files.len()
+            );
+            let launch = request.launch_options();
+            let opened = self.open_launch_targets(&launch, request.stdin_text);
+            if let Some(waiter) = waiter {
+                if opened.is_empty() {
+                    waiter.finish();
+                } else {
+                    self.remote_waiters
+                        .push((opened.into_iter().collect(), waiter));
+                }
+            }
+        }
+
+        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+    }
+
+    /// Close the window once every tab opened with `--wait` has been closed,
+    /// and release forwarded `--wait` launches whose tabs are all closed.
+    ///
+    /// Goes through the normal close request, so other unsaved tabs still
+    /// prompt before exiting.
+    fn handle_wait_for_tabs(&mut self, ctx: &egui::Context) {
+        if !self.remote_waiters.is_empty() {
+            let open: HashSet<usize> = self.state.tabs().iter().map(|tab| tab.id).collect();
+            let (done, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.remote_waiters)
+                .into_iter()
+                .partition(|(ids, _)| ids.is_disjoint(&open));
+            self.remote_waiters = pending;
+            for (_, waiter) in done {
+                waiter.finish();
+            }
+        }
+
+        let Some(waiting) = &self.wait_for_tabs else {
+            return;
+        };
+        if self
+            .state
+            .tabs()
+            .iter()
+            .any(|tab| waiting.contains(&tab.id))
+        {
+            return;
+        }
+
+        info!("All waited-on tabs closed, exiting");
+        self.wait_for_tabs = None;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
     }
 
     /// Get elapsed time since app start in seconds.
@@ -672,6 +901,7 @@ impl FerriteApp {
                     });
                 }
 
+                let mut format_change: Option<FileFormat> = None;
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     // Help button (rightmost in right-to-left layout)
                     if ui
@@ -691,8 +921,50 @@ impl FerriteApp {
 
                         ui.separator();
 
-                        // Encoding (Rust strings are always UTF-8)
-                        ui.label("UTF-8");
+                        // Line endings and encoding used when saving
+                        let format = tab.format();
+                        ui.menu_button(format.line_ending.label(), |ui| {
+                            for line_ending in [LineEnding::Lf, LineEnding::Crlf] {
+                                if ui
+                                    .radio(format.line_ending == line_ending, line_ending.label())
+                                    .clicked()
+                                {
+                                    format_change = Some(FileFormat { line_ending, ..format });
+                                    ui.close_menu();
+                                }
+                            }
+                        })
+                        .response
+                        .on_hover_text("Line endings");
+
+                        ui.menu_button(format.encoding_label(), |ui| {
+                            for &encoding in TextEncoding::all() {
+                                if ui
+                                    .radio(format.encoding == encoding, encoding.label())
+                                    .clicked()
+                                {
+                                    format_change = Some(FileFormat {
+                                        encoding,
+                                        bom: format.bom && encoding.supports_bom(),
+                                        ..format
+                                    });
+                                    ui.close_menu();
+                                }
+                            }
+                            ui.separator();
+                            let mut bom = format.bom;
+                            if ui
+                                .add_enabled(
+                                    format.encoding.supports_bom(),
+                                    egui::Checkbox::new(&mut bom, "Byte-order mark"),
+                                )
+                                .changed()
+                            {
+                                format_change = Some(FileFormat { bom, ..format });
+                            }
+                        })
+                        .response
+                        .on_hover_text("Encoding");
 
                         ui.separator();
 
@@ -701,6 +973,14 @@ impl FerriteApp {
                         ui.label(stats.format_compact());
                     }
                 });
+
+                // Apply an encoding / line-ending change (marks the tab modified)
+                if let Some(format) = format_change {
+                    if let Some(tab) = self.state.active_tab_mut() {
+                        tab.set_line_ending(format.line_ending);
+                        tab.set_encoding(format.encoding, format.bom);
+                    }
+                }
             });
         });
 
@@ -759,6 +1039,27 @@ impl FerriteApp {
             self.state.mark_settings_dirty();
         }
 
+        // ═══════════════════════════════════════════════════════════════════
+        // Backlinks Panel (workspace mode, shown with the outline)
+        // ═══════════════════════════════════════════════════════════════════
+        let mut backlink_to_open: Option<(PathBuf, usize)> = None;
+
+        if self.state.settings.outline_enabled && self.update_backlinks(ctx) {
+            if let (Some(job), Some((_, backlinks))) = (&self.backlink_job, &self.cached_backlinks)
+            {
+                self.backlinks_panel
+                    .set_side(self.state.settings.outline_side);
+                let output = self
+                    .backlinks_panel
+                    .show(ctx, backlinks, job.root(), is_dark);
+                backlink_to_open = output.open;
+            }
+        }
+
+        if let Some((path, line)) = backlink_to_open {
+            self.open_note_at_line(path, Some(line));
+        }
+
         // ═══════════════════════════════════════════════════════════════════
         // File Tree Panel (workspace mode only)
         // ═══════════════════════════════════════════════════════════════════
@@ -831,6 +1132,7 @@ impl FerriteApp {
         }
 
         // Central panel for editor content
+        let mut wiki_link_clicked: Option<WikiLink> = None;
         egui::CentralPanel::default().show(ctx, |ui| {
             // Tab bar - uses custom wrapping layout for multi-line support
             let mut tab_to_close: Option<usize> = None;
@@ -1045,6 +1347,9 @@ impl FerriteApp {
 
             ui.separator();
 
+            // Non-modal bar when the file changed on disk under unsaved edits
+            self.render_disk_conflict_bar(ui);
+
             // Editor widget - extract settings values to avoid borrow conflicts
             let font_size = self.state.settings.font_size;
             let font_family = self.state.settings.font_family;
@@ -1073,6 +1378,19 @@ impl FerriteApp {
 
             // Extract pending scroll request before mutable borrow
             let scroll_to_line = self.pending_scroll_to_line.take();
+            let pending_selection = self.pending_selection.take();
+
+            // Keep a search result's block highlighted for a moment
+            let now = self.get_app_time();
+            let active_id = self.state.active_tab().map(|t| t.id);
+            let highlight_line = match self.search_result_highlight {
+                Some((tab_id, line, until)) if now < until && Some(tab_id) == active_id => {
+                    ui.ctx()
+                        .request_repaint_after(std::time::Duration::from_secs_f64(until - now));
+                    Some(line)
+                }
+                _ => None,
+            };
 
             // Get tab metadata before mutable borrow
             let tab_info = self.state.active_tab().map(|t| {
@@ -1084,6 +1402,18 @@ impl FerriteApp {
             });
 
             if let Some((tab_id, view_mode, structured_type)) = tab_info {
+                // Drop parsed documents and raw layouts of closed tabs
+                let tabs = self.state.tabs();
+                self.markdown_documents
+                    .retain(|id, _| tabs.iter().any(|t| t.id == *id));
+                retain_layout_caches(|id| tabs.iter().any(|t| t.id == id));
+
+                // Split view is only available for markdown
+                let view_mode = match (view_mode, structured_type) {
+                    (ViewMode::Split, Some(_)) => ViewMode::Raw,
+                    (mode, _) => mode,
+                };
+
                 match view_mode {
                     ViewMode::Raw => {
                         // Raw mode: use the plain EditorWidget
@@ -1095,7 +1425,8 @@ impl FerriteApp {
                                 .show_line_numbers(show_line_numbers)
                                 .theme_colors(theme_colors.clone())
                                 .id(egui::Id::new("main_editor_raw"))
-                                .scroll_to_line(scroll_to_line);
+                                .scroll_to_line(scroll_to_line)
+                                .select_range(pending_selection);
 
                             // Add search highlights if available
                             if let Some(highlights) = search_highlights.clone() {
@@ -1118,13 +1449,18 @@ impl FerriteApp {
                             let tree_state = self.tree_viewer_states.entry(tab_id).or_default();
 
                             if let Some(tab) = self.state.active_tab_mut() {
+                                let original_content = tab.content.clone();
+                                let before = tab.cursor_snapshot();
                                 let output =
                                     TreeViewer::new(&mut tab.content, file_type, tree_state)
                                         .font_size(font_size)
                                         .show(ui);
 
-                                if output.changed {
+                                // The viewer edits the content directly, so the
+                                // change is recorded for undo here
+                                if output.changed || tab.content != original_content {
                                     debug!("Content modified in tree viewer");
+                                    tab.record_edit(original_content, before);
                                 }
 
                                 // Update scroll offset for sync scrolling
@@ -1132,8 +1468,17 @@ impl FerriteApp {
                             }
                         } else {
                             // Markdown file: use the WYSIWYG MarkdownEditor
+                            let wiki_links = self.state.workspace().is_some();
+                            let document = self.markdown_documents.entry(tab_id).or_default();
+                            document.set_wiki_links(wiki_links);
+
                             if let Some(tab) = self.state.active_tab_mut() {
+                                let original_content = tab.content.clone();
+                                let before = tab.cursor_snapshot();
+                                let content_version = tab.content_version();
                                 let editor_output = MarkdownEditor::new(&mut tab.content)
+                                    .document_cache(document, content_version)
+                                    .base_dir(tab.path.as_deref().and_then(Path::parent))
                                     .mode(EditorMode::Rendered)
                                     .font_size(font_size)
                                     .font_family(font_family)
@@ -1141,15 +1486,29 @@ impl FerriteApp {
                                     .theme(theme)
                                     .id(egui::Id::new("main_editor_rendered"))
                                     .scroll_to_line(scroll_to_line)
+                                    .highlight_line(highlight_line)
+                                    .select_range(pending_selection)
                                     .show(ui);
 
-                                if editor_output.changed {
-                                    // Content is already modified through the mutable reference
+                                // Content is already modified through the mutable
+                                // reference; record it so undo stays in step
+                                if editor_output.changed || tab.content != original_content {
                                     debug!("Content modified in rendered editor");
+                                    tab.record_edit(original_content, before);
                                 }
-
-                                // Update cursor position from rendered editor
-                                tab.cursor_position = editor_output.cursor_position;
+                                wiki_link_clicked = editor_output.wiki_link_clicked;
+
+                                // Keep the block positions for sync scrolling
+                                let sync_state = self.sync_scroll_states.entry(tab_id).or_default();
+                                let rendered_height = editor_output
+                                    .block_mappings
+                                    .last()
+                                    .map_or(0.0, |m| m.rendered_range.1);
+                                sync_state.set_source_metadata(
+                                    tab.content.lines().count().max(1),
+                                    rendered_height,
+                                );
+                                sync_state.build_mappings_from_blocks(editor_output.block_mappings);
 
                                 // Update scroll offset for sync scrolling
                                 let old_scroll = tab.scroll_offset;
@@ -1171,7 +1530,11 @@ impl FerriteApp {
                                             let abs_end = focused.start_char + sel_end;
                                             tab.selection = Some((abs_start, abs_end));
                                         } else {
-                                            // Just cursor, no selection
+                                            // Just cursor, no selection; keep it so
+                                            // pasted and dropped images land there
+                                            let cursor = focused.start_char + sel_start;
+                                            tab.cursor_position =
+                                                char_index_to_line_col(&tab.content, cursor);
                                             tab.selection = None;
                                         }
                                     } else {
@@ -1185,10 +1548,130 @@ impl FerriteApp {
                             }
                         }
                     }
+                    ViewMode::Split => {
+                        // Raw editor and read-only preview side by side
+                        let rect = ui.available_rect_before_wrap();
+                        ui.allocate_rect(rect, egui::Sense::hover());
+
+                        let divider_width = 6.0;
+                        let ratio = self.state.settings.split_ratio.clamp(0.2, 0.8);
+                        let editor_width = ((rect.width() - divider_width) * ratio).round();
+                        let editor_rect = egui::Rect::from_min_size(
+                            rect.min,
+                            egui::vec2(editor_width, rect.height()),
+                        );
+                        let divider_rect = egui::Rect::from_min_size(
+                            egui::pos2(editor_rect.right(), rect.top()),
+                            egui::vec2(divider_width, rect.height()),
+                        );
+                        let preview_rect = egui::Rect::from_min_max(
+                            egui::pos2(divider_rect.right(), rect.top()),
+                            rect.max,
+                        );
+
+                        // Draggable divider between the panes
+                        let divider = ui.interact(
+                            divider_rect,
+                            egui::Id::new("split_view_divider"),
+                            egui::Sense::drag(),
+                        );
+                        if divider.hovered() || divider.dragged() {
+                            ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+                        }
+                        let divider_color = if divider.dragged() {
+                            theme_colors.ui.accent
+                        } else {
+                            theme_colors.base.border
+                        };
+                        ui.painter().vline(
+                            divider_rect.center().x,
+                            rect.y_range(),
+                            egui::Stroke::new(1.0, divider_color),
+                        );
+                        if divider.dragged() {
+                            if let Some(pointer) = divider.interact_pointer_pos() {
+                                let ratio = (pointer.x - rect.left()) / rect.width();
+                                self.state.settings.split_ratio = ratio.clamp(0.2, 0.8);
+                            }
+                        }
+                        if divider.drag_stopped() {
+                            self.state.mark_settings_dirty();
+                        }
+
+                        // Scroll offsets the views are being animated to
+                        let sync_enabled = self.state.settings.sync_scroll_enabled;
+                        let sync_state = self.sync_scroll_states.entry(tab_id).or_default();
+                        sync_state.set_enabled(sync_enabled);
+                        let raw_target = sync_state.get_animated_raw_offset();
+                        let rendered_target = sync_state.get_animated_rendered_offset();
+
+                        let wiki_links = self.state.workspace().is_some();
+                        let document = self.markdown_documents.entry(tab_id).or_default();
+                        document.set_wiki_links(wiki_links);
+                        if let Some(tab) = self.state.active_tab_mut() {
+                            let mut editor_ui =
+                                ui.child_ui(editor_rect, egui::Layout::default(), None);
+                            let mut editor = EditorWidget::new(tab)
+                                .font_size(font_size)
+                                .font_family(font_family)
+                                .word_wrap(word_wrap)
+                                .show_line_numbers(show_line_numbers)
+                                .theme_colors(theme_colors.clone())
+                                .id(egui::Id::new("main_editor_raw"))
+                                .scroll_to_line(scroll_to_line)
+                                .select_range(pending_selection)
+                                .scroll_offset(raw_target);
+                            if let Some(highlights) = search_highlights.clone() {
+                                editor = editor.search_highlights(highlights);
+                            }
+                            let editor_output = editor.show(&mut editor_ui);
+                            let raw_offset = tab.scroll_offset;
+
+                            let mut preview_ui =
+                                ui.child_ui(preview_rect, egui::Layout::default(), None);
+                            let content_version = tab.content_version();
+                            let preview_output = MarkdownEditor::new(&mut tab.content)
+                                .document_cache(document, content_version)
+                                .base_dir(tab.path.as_deref().and_then(Path::parent))
+                                .mode(EditorMode::Rendered)
+                                .read_only(true)
+                                .font_size(font_size)
+                                .font_family(font_family)
+                                .word_wrap(word_wrap)
+                                .theme(theme)
+                                .id(egui::Id::new("split_preview"))
+                                .scroll_offset(rendered_target)
+                                .highlight_line(highlight_line)
+                                .show(&mut preview_ui);
+                            wiki_link_clicked = preview_output.wiki_link_clicked;
+
+                            let rendered_height = preview_output
+                                .block_mappings
+                                .last()
+                                .map_or(0.0, |m| m.rendered_range.1);
+                            sync_state.set_source_metadata(
+                                tab.content.lines().count().max(1),
+                                rendered_height,
+                            );
+                            sync_state.build_mappings_from_blocks(preview_output.block_mappings);
+                            sync_state.sync_panes(
+                                raw_offset,
+                                preview_output.scroll_offset,
+                                editor_output.line_height,
+                            );
+                            if sync_state.is_animating() {
+                                ui.ctx().request_repaint();
+                            }
+                        }
+                    }
                 }
             }
         });
 
+        if let Some(link) = wiki_link_clicked {
+            self.open_wiki_link(link);
+        }
+
         // Render dialogs
         self.render_dialogs(ctx);
 
@@ -1264,37 +1747,27 @@ impl FerriteApp {
         if self.search_panel.is_open() {
             if let Some(workspace) = &self.state.workspace {
                 let workspace_root = workspace.root_path.clone();
-                let hidden_patterns = workspace.hidden_patterns.clone();
-                let all_files = workspace.all_files();
 
+                self.search_panel
+                    .set_context_lines(self.state.settings.search_context_lines);
                 let output = self.search_panel.show(ctx, &workspace_root, is_dark);
 
                 // Trigger search when requested
                 if output.should_search {
-                    self.search_panel.search(&all_files, &hidden_patterns);
+                    self.search_workspace();
                 }
 
-                // Handle navigation to file
-                if let Some((file_path, line_number)) = output.navigate_to {
-                    match self.state.open_file(file_path.clone()) {
-                        Ok(_) => {
-                            debug!(
-                                "Opened file from search: {} at line {}",
-                                file_path.display(),
-                                line_number
-                            );
-                            // TODO(enhancement): Navigate to line_number in the editor
-                            // This would scroll the editor to the specific line from search results
-                            if let Some(workspace) = self.state.workspace_mut() {
-                                workspace.add_recent_file(file_path);
-                            }
-                        }
-                        Err(e) => {
-                            warn!("Failed to open file: {}", e);
-                            self.state
-                                .show_error(format!("Failed to open file:\n{}", e));
-                        }
-                    }
+                if output.replace_requested {
+                    self.handle_replace_in_files();
+                }
+
+                if output.revert_requested {
+                    self.handle_revert_replace_in_files();
+                }
+
+                // Open the clicked result at its match
+                if let Some(location) = output.navigate_to {
+                    self.open_search_result(location);
                 }
             }
         }
@@ -1368,6 +1841,27 @@ impl FerriteApp {
             .unwrap_or(false);
 
         if has_path {
+            // Never silently overwrite a newer version written by someone else
+            let index = self.state.active_tab_index();
+            if let Some(path) = self.state.active_tab().and_then(|t| t.path.clone()) {
+                self.state.check_external_change(&path);
+            }
+            if self
+                .state
+                .active_tab()
+                .map(|t| t.disk_conflict().is_some())
+                .unwrap_or(false)
+            {
+                let time = self.get_app_time();
+                self.state.show_toast(
+                    "File changed on disk - choose Reload, Keep Mine or Compare first",
+                    time,
+                    4.0,
+                );
+                debug!("Save blocked by pending disk conflict on tab {}", index);
+                return;
+            }
+
             // Save to existing path
             let path_display = self
                 .state
@@ -1533,16 +2027,206 @@ impl FerriteApp {
         }
     }
 
+    /// Start the search in files panel's query over the workspace, in the
+    /// background.
+    ///
+    /// Open tabs are searched with their unsaved text.
+    fn search_workspace(&mut self) {
+        let Some(workspace) = &self.state.workspace else {
+            return;
+        };
+        let root = workspace.root_path.clone();
+        let hidden_patterns = workspace.hidden_patterns.clone();
+        let open_documents: HashMap<PathBuf, String> = self
+            .state
+            .tabs()
+            .iter()
+            .filter_map(|tab| Some((tab.path.clone()?, tab.content.clone())))
+            .collect();
+        self.search_panel
+            .search(&root, &hidden_patterns, open_documents);
+    }
+
+    /// Open a search in files result: open or focus its file, scroll to the
+    /// match and select it, and highlight its block in rendered mode.
+    fn open_search_result(&mut self, location: SearchLocation) {
+        if let Err(e) = self.state.open_file(location.path.clone()) {
+            warn!("Failed to open file: {}", e);
+            self.state
+                .show_error(format!("Failed to open file:\n{}", e));
+            return;
+        }
+        debug!(
+            "Opened file from search: {} at line {}",
+            location.path.display(),
+            location.line_number
+        );
+
+        let until = self.get_app_time() + SEARCH_HIGHLIGHT_SECS;
+        if let Some(tab) = self.state.active_tab_mut() {
+            let range = location.char_range(&tab.content);
+            if let Some((start, end)) = range {
+                tab.cursor_position = char_index_to_line_col(&tab.content, start);
+                tab.selection = (start != end).then_some((start, end));
+            }
+            self.pending_selection = range;
+            self.pending_scroll_to_line = Some(location.line_number);
+            self.search_result_highlight = Some((tab.id, location.line_number, until));
+        }
+        if let Some(workspace) = self.state.workspace_mut() {
+            workspace.add_recent_file(location.path);
+        }
+    }
+
+    /// Open the next (or previous) search in files result, across files.
+    fn handle_next_search_result(&mut self, forward: bool) {
+        match self.search_panel.next_result(forward) {
+            Some(location) => self.open_search_result(location),
+            None => {
+                let time = self.get_app_time();
+                self.state
+                    .show_toast("No search results (Ctrl+Shift+F to search)", time, 2.0);
+            }
+        }
+    }
+
+    /// Replace the matches checked in the search in files panel.
+    ///
+    /// Open files are changed in their tabs, as one undo step each, and left
+    /// unsaved; other files are rewritten on disk in their own encoding.
+    fn handle_replace_in_files(&mut self) {
+        let mut summary = ReplaceSummary::default();
+        for path in self.search_panel.replace_targets() {
+            let tab_index = self.state.find_tab_by_path(&path);
+            let (original, format) = match tab_index.and_then(|i| self.state.tab(i)) {
+                Some(tab) => (tab.content.clone(), None),
+                None => match read_text_file(&path) {
+                    Ok((content, format)) => (content, Some(format)),
+                    Err(e) => {
+                        warn!("Failed to read {} for replace: {}", path.display(), e);
+                        summary.skipped.push(path);
+                        continue;
+                    }
+                },
+            };
+
+            let (replaced, count) = match self.search_panel.replace_in(&path, &original) {
+                FileReplace::Replaced(replaced, count) => (replaced, count),
+                FileReplace::Unselected => continue,
+                FileReplace::Stale => {
+                    summary.skipped.push(path);
+                    continue;
+                }
+            };
+
+            if let Some(tab) = tab_index.and_then(|i| self.state.tab_mut(i)) {
+                tab.set_content(replaced.clone());
+            } else if let Some(format) = &format {
+                if let Err(e) = self.state.rewrite_file(&path, &replaced, format) {
+                    warn!("Failed to write {}: {}", path.display(), e);
+                    summary.skipped.push(path);
+                    continue;
+                }
+            }
+
+            summary.replaced += count;
+            summary.files.push(ReplacedFile {
+                path,
+                original,
+                replaced,
+                format,
+            });
+        }
+
+        info!(
+            "Replaced {} matches in {} files ({} skipped)",
+            summary.replaced,
+            summary.files.len(),
+            summary.skipped.len()
+        );
+        let time = self.get_app_time();
+        let mut message = format!(
+            "Replaced {} occurrence{} in {} file{}",
+            summary.replaced,
+            if summary.replaced == 1 { "" } else { "s" },
+            summary.files.len(),
+            if summary.files.len() == 1 { "" } else { "s" }
+        );
+        if !summary.skipped.is_empty() {
+            message.push_str(&format!(
+                "; skipped {} (changed since the search or not writable)",
+                summary.skipped.len()
+            ));
+        }
+        self.state.show_toast(message, time, 3.0);
+
+        self.search_panel.set_last_replace(summary);
+        self.search_workspace();
+    }
+
+    /// Undo the last replace in files.
+    ///
+    /// Files edited again since the replace are left alone.
+    fn handle_revert_replace_in_files(&mut self) {
+        let Some(summary) = self.search_panel.take_last_replace() else {
+            return;
+        };
+
+        let mut reverted = 0;
+        let mut skipped = 0;
+        for file in summary.files {
+            let done = match &file.format {
+                None => match self.state.find_tab_by_path(&file.path) {
+                    Some(index) => match self.state.tab_mut(index) {
+                        Some(tab) if tab.content == file.replaced => {
+                            tab.set_content(file.original);
+                            true
+                        }
+                        _ => false,
+                    },
+                    None => false,
+                },
+                Some(format) => match read_text_file(&file.path) {
+                    Ok((current, _)) if current == file.replaced => {
+                        match self.state.rewrite_file(&file.path, &file.original, format) {
+                            Ok(()) => true,
+                            Err(e) => {
+                                warn!("Failed to revert {}: {}", file.path.display(), e);
+                                false
+                            }
+                        }
+                    }
+                    _ => false,
+                },
+            };
+            if done {
+                reverted += 1;
+            } else {
+                skipped += 1;
+            }
+        }
+
+        let time = self.get_app_time();
+        let mut message = format!(
+            "Reverted {} file{}",
+            reverted,
+            if reverted == 1 { "" } else { "s" }
+        );
+        if skipped > 0 {
+            message.push_str(&format!("; {} changed since and left alone", skipped));
+        }
+        self.state.show_toast(message, time, 3.0);
+
+        self.search_workspace();
+    }
+
     /// Handle opening the search in files panel.
     fn handle_search_in_files(&mut self) {
         if self.state.is_workspace_mode() {
             self.search_panel.toggle();
             // Trigger search if panel is now open
             if self.search_panel.is_open() {
-                if let Some(workspace) = &self.state.workspace {
-                    let files = workspace.all_files();
-                    self.search_panel.search(&files, &workspace.hidden_patterns);
-                }
+                self.search_workspace();
             }
         } else {
             // Not in workspace mode - show a hint
@@ -1566,17 +2250,25 @@ impl FerriteApp {
         }
 
         let mut need_tree_refresh = false;
-        let mut modified_files: Vec<std::path::PathBuf> = Vec::new();
+        let mut changed_files: Vec<std::path::PathBuf> = Vec::new();
 
         for event in events {
             match event {
                 WorkspaceEvent::FileCreated(path) => {
                     debug!("File created: {}", path.display());
                     need_tree_refresh = true;
+                    if let Some(job) = &self.backlink_job {
+                        job.update_file(&path);
+                    }
+                    // Editors that save via rename show up as a create
+                    changed_files.push(path);
                 }
                 WorkspaceEvent::FileDeleted(path) => {
                     debug!("File deleted: {}", path.display());
                     need_tree_refresh = true;
+                    if let Some(job) = &self.backlink_job {
+                        job.remove_file(&path);
+                    }
 
                     // Check if this file is open in a tab and mark it
                     for tab in self.state.tabs() {
@@ -1589,13 +2281,10 @@ impl FerriteApp {
                 }
                 WorkspaceEvent::FileModified(path) => {
                     debug!("File modified: {}", path.display());
-                    // Check if this file is open in a tab
-                    for tab in self.state.tabs() {
-                        if tab.path.as_ref() == Some(&path) {
-                            modified_files.push(path.clone());
-                            break;
-                        }
+                    if let Some(job) = &self.backlink_job {
+                        job.update_file(&path);
                     }
+                    changed_files.push(path);
                 }
                 WorkspaceEvent::FileRenamed(old_path, new_path) => {
                     debug!(
@@ -1604,6 +2293,10 @@ impl FerriteApp {
                         new_path.display()
                     );
                     need_tree_refresh = true;
+                    if let Some(job) = &self.backlink_job {
+                        job.remove_file(&old_path);
+                        job.update_file(&new_path);
+                    }
                 }
                 WorkspaceEvent::Error(msg) => {
                     warn!("File watcher error: {}", msg);
@@ -1611,26 +2304,170 @@ impl FerriteApp {
             }
         }
 
-        // Refresh file tree if needed
-        if need_tree_refresh {
-            self.state.refresh_workspace();
+        // Refresh file tree if needed (only meaningful in workspace mode)
+        if need_tree_refresh && self.state.is_workspace_mode() {
+            self.state.refresh_workspace();
+        }
+
+        // Reload clean tabs; flag conflicts on tabs with unsaved edits
+        changed_files.sort();
+        changed_files.dedup();
+        let mut reloaded = Vec::new();
+        for path in changed_files {
+            if self.state.check_external_change(&path) == ExternalChange::Reloaded {
+                reloaded.push(path);
+            }
+        }
+
+        if !reloaded.is_empty() {
+            let time = self.get_app_time();
+            let msg = if reloaded.len() == 1 {
+                format!(
+                    "Reloaded {} (changed on disk)",
+                    reloaded[0]
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or("unknown")
+                )
+            } else {
+                format!("Reloaded {} files changed on disk", reloaded.len())
+            };
+            self.state.show_toast(msg, time, 3.0);
+        }
+    }
+
+    /// Render the "changed on disk" bar for the active tab, if it has a
+    /// pending conflict between unsaved edits and a newer disk version.
+    fn render_disk_conflict_bar(&mut self, ui: &mut egui::Ui) {
+        let index = self.state.active_tab_index();
+        let has_conflict = self
+            .state
+            .active_tab()
+            .map(|t| t.disk_conflict().is_some())
+            .unwrap_or(false);
+        if !has_conflict {
+            return;
+        }
+
+        let is_dark = ui.visuals().dark_mode;
+        let bg = if is_dark {
+            egui::Color32::from_rgb(80, 65, 20)
+        } else {
+            egui::Color32::from_rgb(255, 243, 205)
+        };
+
+        egui::Frame::none()
+            .fill(bg)
+            .rounding(4.0)
+            .inner_margin(egui::Margin::symmetric(8.0, 4.0))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("⚠ This file changed on disk and you have unsaved changes.");
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui
+                            .button("Compare")
+                            .on_hover_text("Merge your changes with the disk version")
+                            .clicked()
+                        {
+                            if let Some(result) = self.state.merge_tab_with_disk(index) {
+                                let tab_id = self.state.tab(index).map(|t| t.id).unwrap_or(0);
+                                self.pending_merge = Some((tab_id, result));
+                            }
+                        }
+                        if ui
+                            .button("Keep Mine")
+                            .on_hover_text("Keep your version; saving will overwrite the file")
+                            .clicked()
+                        {
+                            self.state.keep_tab_changes(index);
+                        }
+                        if ui
+                            .button("Reload")
+                            .on_hover_text("Load the disk version (your edits stay in undo)")
+                            .clicked()
+                        {
+                            self.state.reload_tab_from_disk(index);
+                        }
+                    });
+                });
+            });
+        ui.add_space(4.0);
+    }
+
+    /// Run auto-save if one of its triggers fired this frame.
+    ///
+    /// Writes every modified tab that has a path. Files changed on disk by
+    /// another program are skipped and announced once; write failures are
+    /// shown in the error modal once per file until it saves again.
+    fn handle_auto_save(&mut self, ctx: &egui::Context) {
+        let now = self.get_app_time();
+        let focused = ctx.input(|i| i.viewport().focused).unwrap_or(true);
+        let active_tab_id = self.state.active_tab().map(|t| t.id);
+
+        let trigger = self
+            .auto_save
+            .poll(&self.state.settings, now, focused, active_tab_id);
+
+        // Keep the interval ticking while the app is idle
+        if let Some(wait) = self.auto_save.time_until_next(&self.state.settings, now) {
+            ctx.request_repaint_after(wait);
+        }
+
+        let Some(trigger) = trigger else {
+            return;
+        };
+        if !self.state.has_unsaved_changes() {
+            return;
+        }
+
+        let report = self.state.auto_save_modified_tabs();
+        if report.is_empty() {
+            return;
+        }
+        debug!(
+            "Auto-save ({:?}): {} saved, {} skipped, {} failed",
+            trigger,
+            report.saved.len(),
+            report.skipped_external.len(),
+            report.failed.len()
+        );
+
+        for path in &report.saved {
+            self.auto_save.clear_reported(path);
         }
 
-        // Show toast for modified files
-        if !modified_files.is_empty() {
-            let time = self.get_app_time();
-            let msg = if modified_files.len() == 1 {
+        let skipped: Vec<_> = report
+            .skipped_external
+            .iter()
+            .filter(|path| {
+                self.auto_save
+                    .should_report(path, AutoSaveProblem::SkippedExternal)
+            })
+            .collect();
+        if !skipped.is_empty() {
+            let msg = if skipped.len() == 1 {
                 format!(
-                    "File changed externally: {}",
-                    modified_files[0]
+                    "Auto-save skipped {}: changed on disk",
+                    skipped[0]
                         .file_name()
                         .and_then(|n| n.to_str())
                         .unwrap_or("unknown")
                 )
             } else {
-                format!("{} files changed externally", modified_files.len())
+                format!("Auto-save skipped {} files changed on disk", skipped.len())
             };
-            self.state.show_toast(msg, time, 3.0);
+            self.state.show_toast(msg, now, 4.0);
+        }
+
+        let failures: Vec<String> = report
+            .failed
+            .iter()
+            .filter(|(path, _)| self.auto_save.should_report(path, AutoSaveProblem::Failed))
+            .map(|(_, e)| e.to_string())
+            .collect();
+        if !failures.is_empty() {
+            self.state
+                .show_error(format!("Auto-save failed:\n{}", failures.join("\n")));
         }
     }
 
@@ -1682,6 +2519,18 @@ impl FerriteApp {
             return; // Prioritize folder over files
         }
 
+        // Images dropped onto a Markdown document become assets linked at the cursor
+        let (images, files): (Vec<_>, Vec<_>) =
+            files.into_iter().partition(|f| assets::is_image_file(f));
+        if !images.is_empty() {
+            self.insert_image_assets(|dir| {
+                images
+                    .iter()
+                    .map(|image| assets::store_image_file(image, dir))
+                    .collect()
+            });
+        }
+
         // If files were dropped, open them in tabs
         for file in files {
             if let Some(ext) = file.extension().and_then(|e| e.to_str()) {
@@ -1707,6 +2556,298 @@ impl FerriteApp {
         }
     }
 
+    /// Store a pasted clipboard image as an asset and link it at the cursor.
+    ///
+    /// egui only turns Ctrl+V into a paste event when the clipboard holds
+    /// text, so the shortcut is detected from its key release instead.
+    fn handle_image_paste(&mut self, ctx: &egui::Context) {
+        let pasted = ctx.input(|i| {
+            i.events.iter().any(|e| {
+                matches!(
+                    e,
+                    egui::Event::Key {
+                        key: egui::Key::V,
+                        pressed: false,
+                        modifiers,
+                        ..
+                    } if modifiers.command
+                )
+            })
+        });
+        let markdown_tab = self
+            .state
+            .active_tab()
+            .is_some_and(|t| t.file_type().is_markdown());
+        if !pasted || !markdown_tab {
+            return;
+        }
+
+        let mut clipboard = match arboard::Clipboard::new() {
+            Ok(clipboard) => clipboard,
+            Err(e) => {
+                warn!("Failed to access clipboard: {}", e);
+                return;
+            }
+        };
+        // Text was already pasted by the editor
+        if clipboard.get_text().is_ok_and(|t| !t.is_empty()) {
+            return;
+        }
+        let Ok(image) = clipboard.get_image() else {
+            return;
+        };
+        self.insert_image_assets(|dir| {
+            assets::store_rgba_image(image.width, image.height, &image.bytes, dir)
+                .map(|path| vec![path])
+        });
+    }
+
+    /// Store images in the active document's assets folder and insert links to them.
+    ///
+    /// `store` writes the images into the given folder and returns their paths.
+    fn insert_image_assets(
+        &mut self,
+        store: impl FnOnce(&Path) -> std::io::Result<Vec<std::path::PathBuf>>,
+    ) {
+        let time = self.get_app_time();
+        let Some(tab) = self.state.active_tab() else {
+            return;
+        };
+        if !tab.file_type().is_markdown() {
+            return;
+        }
+        let Some(document) = tab.path.clone() else {
+            self.state
+                .show_toast("Save the document before adding images", time, 3.0);
+            return;
+        };
+        let document_dir = document.parent().unwrap_or(Path::new("")).to_path_buf();
+        let assets_dir = assets::assets_dir_for(&document, self.state.workspace());
+
+        let stored = match store(&assets_dir) {
+            Ok(stored) => stored,
+            Err(e) => {
+                warn!("Failed to store image in {}: {}", assets_dir.display(), e);
+                self.state
+                    .show_error(format!("Failed to store image:\n{}", e));
+                return;
+            }
+        };
+        let links: Vec<String> = stored
+            .iter()
+            .map(|path| assets::image_link(&document_dir, path))
+            .collect();
+        self.insert_at_cursor(&links.join("\n"));
+
+        let message = match stored.as_slice() {
+            [path] => format!(
+                "Added image {}",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            paths => format!("Added {} images", paths.len()),
+        };
+        self.state.show_toast(message, time, 2.5);
+    }
+
+    /// Insert text at the cursor of the active tab, replacing any selection.
+    fn insert_at_cursor(&mut self, text: &str) {
+        if let Some(tab) = self.state.active_tab_mut() {
+            let (start, end) = tab.selection.unwrap_or_else(|| {
+                let (line, col) = tab.cursor_position;
+                let index = line_col_to_char_index(&tab.content, line, col);
+                (index, index)
+            });
+            let byte_at = |index: usize| {
+                tab.content
+                    .char_indices()
+                    .nth(index)
+                    .map_or(tab.content.len(), |(i, _)| i)
+            };
+            let (start_byte, end_byte) = (byte_at(start), byte_at(end));
+
+            let mut content = tab.content.clone();
+            content.replace_range(start_byte..end_byte, text);
+            let cursor = start + text.chars().count();
+            tab.set_content(content);
+            tab.cursor_position = char_index_to_line_col(&tab.content, cursor);
+            tab.selection = None;
+        }
+    }
+
+    /// Bring the backlinks index and the active note's backlinks up to date.
+    ///
+    /// The index reads the workspace's notes on its own thread; until it has
+    /// reported on the active note, the last note's backlinks stay shown.
+    /// Returns false when there are no backlinks to show: outside workspace
+    /// mode, when the active tab isn't a saved note, or while the index is
+    /// first being built.
+    fn update_backlinks(&mut self, ctx: &egui::Context) -> bool {
+        let Some(workspace) = self.state.workspace() else {
+            self.backlink_job = None;
+            self.cached_backlinks = None;
+            return false;
+        };
+        let Some(note) = self
+            .state
+            .active_tab()
+            .and_then(|tab| tab.path.clone())
+            .filter(|path| wikilinks::is_note(path))
+        else {
+            return false;
+        };
+
+        // (Re)build the index for a newly opened workspace
+        let root = &workspace.root_path;
+        if self.backlink_job.as_ref().map(BacklinkJob::root) != Some(root.as_path()) {
+            self.cached_backlinks = None;
+            self.backlink_job = match BacklinkJob::spawn(root, workspace.all_files()) {
+                Ok(job) => Some(job),
+                Err(e) => {
+                    warn!("Failed to start backlinks index: {}", e);
+                    None
+                }
+            };
+        }
+        let Some(job) = &mut self.backlink_job else {
+            return false;
+        };
+
+        job.show(&note);
+        if let Some(reported) = job.poll() {
+            self.cached_backlinks = Some(reported);
+        }
+        if self.cached_backlinks.as_ref().map(|(shown, _)| shown) != Some(&note) {
+            ctx.request_repaint_after(BACKLINKS_POLL_INTERVAL);
+        }
+        self.cached_backlinks.is_some()
+    }
+
+    /// Open the note a wiki-link points at, creating it if it doesn't exist.
+    ///
+    /// Targets resolve against the workspace's files, or the files next to
+    /// the active document outside workspace mode.
+    fn open_wiki_link(&mut self, link: WikiLink) {
+        let current = self.state.active_tab().and_then(|tab| tab.path.clone());
+
+        // `[[#Heading]]` points into the current note
+        if link.target.is_empty() {
+            if let Some(line) = self.active_heading_line(link.heading.as_deref()) {
+                self.pending_scroll_to_line = Some(line);
+                self.scroll_to_line(line);
+            }
+            return;
+        }
+
+        let resolver = match (self.state.workspace(), current.as_deref()) {
+            (Some(workspace), _) => {
+                NoteResolver::new(Some(&workspace.root_path), &workspace.all_files())
+            }
+            (None, Some(current)) => {
+                let dir = current.parent().unwrap_or(Path::new(""));
+                let files: Vec<PathBuf> = std::fs::read_dir(dir)
+                    .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+                    .unwrap_or_default();
+                NoteResolver::new(Some(dir), &files)
+            }
+            (None, None) => NoteResolver::default(),
+        };
+
+        if let Some(path) = resolver.resolve(&link.target) {
+            let path = path.to_path_buf();
+            if self.open_note_at_line(path, None) {
+                if let Some(line) = self.active_heading_line(link.heading.as_deref()) {
+                    self.pending_scroll_to_line = Some(line);
+                    self.scroll_to_line(line);
+                }
+            }
+            return;
+        }
+
+        // Create the note next to the current one (or at the workspace root)
+        let time = self.get_app_time();
+        let dir = current
+            .as_deref()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+            .or_else(|| self.state.workspace().map(|w| w.root_path.clone()));
+        let Some(dir) = dir else {
+            self.state.show_toast(
+                format!(
+                    "No note named \"{}\" - save this document to create it",
+                    link.target
+                ),
+                time,
+                3.0,
+            );
+            return;
+        };
+
+        let path = wikilinks::new_note_path(&link.target, &dir);
+        let title = path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let created = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| {
+                std::fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&path)
+            })
+            .and_then(|mut file| {
+                use std::io::Write;
+                writeln!(file, "# {}", title)
+            });
+        if let Err(e) = created {
+            warn!("Failed to create note {}: {}", path.display(), e);
+            self.state
+                .show_error(format!("Failed to create note:\n{}", e));
+            return;
+        }
+
+        info!("Created note {} from wiki-link", path.display());
+        if self.state.is_workspace_mode() {
+            self.state.refresh_workspace();
+        }
+        if self.open_note_at_line(path, None) {
+            self.state
+                .show_toast(format!("Created note {}", title), time, 2.5);
+        }
+    }
+
+    /// Find the line of a heading in the active tab.
+    fn active_heading_line(&self, heading: Option<&str>) -> Option<usize> {
+        let tab = self.state.active_tab()?;
+        wikilinks::heading_line(&tab.content, heading?)
+    }
+
+    /// Open a note (or switch to its tab), scrolling to a line if given.
+    ///
+    /// Returns false if the file couldn't be opened.
+    fn open_note_at_line(&mut self, path: PathBuf, line: Option<usize>) -> bool {
+        match self.state.open_file(path.clone()) {
+            Ok(_) => {
+                if let Some(workspace) = self.state.workspace_mut() {
+                    workspace.add_recent_file(path);
+                }
+                if let Some(line) = line {
+                    self.pending_scroll_to_line = Some(line);
+                    self.scroll_to_line(line);
+                }
+                true
+            }
+            Err(e) => {
+                warn!("Failed to open file: {}", e);
+                self.state
+                    .show_error(format!("Failed to open file:\n{}", e));
+                false
+            }
+        }
+    }
+
     /// Handle file tree context menu actions.
     fn handle_file_tree_context_action(&mut self, action: FileTreeContextAction) {
         match action {
@@ -1920,6 +3061,12 @@ impl FerriteApp {
                 return Some(KeyboardAction::ToggleViewMode);
             }
 
+            // Ctrl+\: Toggle Split View
+            if i.modifiers.ctrl && i.key_pressed(egui::Key::Backslash) {
+                debug!("Keyboard shortcut: Ctrl+\\ (Toggle Split View)");
+                return Some(KeyboardAction::ToggleSplitView);
+            }
+
             // Ctrl+Shift+T: Cycle Theme
             if i.modifiers.ctrl && i.modifiers.shift && i.key_pressed(egui::Key::T) {
                 debug!("Keyboard shortcut: Ctrl+Shift+T (Cycle Theme)");
@@ -2022,6 +3169,18 @@ impl FerriteApp {
                 return Some(KeyboardAction::FindPrev);
             }
 
+            // F4: Next search in files result
+            if i.key_pressed(egui::Key::F4) && !i.modifiers.shift {
+                debug!("Keyboard shortcut: F4 (Next Search Result)");
+                return Some(KeyboardAction::NextSearchResult);
+            }
+
+            // Shift+F4: Previous search in files result
+            if i.key_pressed(egui::Key::F4) && i.modifiers.shift {
+                debug!("Keyboard shortcut: Shift+F4 (Previous Search Result)");
+                return Some(KeyboardAction::PrevSearchResult);
+            }
+
             // ═══════════════════════════════════════════════════════════════════
             // Formatting shortcuts (editor-scoped)
             // ═══════════════════════════════════════════════════════════════════
@@ -2168,6 +3327,9 @@ impl FerriteApp {
             KeyboardAction::ToggleViewMode => {
                 self.handle_toggle_view_mode();
             }
+            KeyboardAction::ToggleSplitView => {
+                self.handle_toggle_split_view();
+            }
             KeyboardAction::CycleTheme => {
                 self.handle_cycle_theme(ctx);
             }
@@ -2195,6 +3357,12 @@ impl FerriteApp {
             KeyboardAction::FindPrev => {
                 self.handle_find_prev();
             }
+            KeyboardAction::NextSearchResult => {
+                self.handle_next_search_result(true);
+            }
+            KeyboardAction::PrevSearchResult => {
+                self.handle_next_search_result(false);
+            }
             KeyboardAction::CloseFindPanel => {
                 if self.state.ui.show_find_replace {
                     self.state.ui.show_find_replace = false;
@@ -2305,13 +3473,9 @@ impl FerriteApp {
                         sync_state.update_rendered_offset(current_scroll);
                         sync_state.mark_scroll(ScrollOrigin::Rendered);
 
-                        // For Rendered→Raw, use proportional estimation based on scroll position
-                        // Since rendered content may have variable heights (headings, code blocks),
-                        // we estimate using the same approach as Raw: offset / estimated_line_height
-                        let estimated_line_height = 20.0;
-                        let source_line = ((current_scroll / estimated_line_height) as usize)
-                            .saturating_add(1)
-                            .max(1);
+                        // For Rendered→Raw, map the scroll position through the block
+                        // positions of the last rendered frame
+                        let source_line = sync_state.rendered_offset_to_line(current_scroll).max(1);
 
                         // Set pending scroll target for raw view
                         self.pending_scroll_to_line = Some(source_line);
@@ -2330,6 +3494,47 @@ impl FerriteApp {
         }
     }
 
+    /// Toggle the split view (raw editor beside the rendered preview) for the
+    /// active markdown tab.
+    ///
+    /// Entering split view from rendered mode keeps the same source line in view.
+    fn handle_toggle_split_view(&mut self) {
+        let is_markdown = self
+            .state
+            .active_tab()
+            .map(|tab| {
+                tab.path
+                    .as_ref()
+                    .and_then(|p| get_structured_file_type(p))
+                    .is_none()
+            })
+            .unwrap_or(false);
+        if !is_markdown {
+            return;
+        }
+
+        if let Some(tab) = self.state.active_tab_mut() {
+            let old_mode = tab.view_mode;
+            let tab_id = tab.id;
+            let current_scroll = tab.scroll_offset;
+            let new_mode = tab.toggle_split_view();
+            debug!(
+                "Split view toggled: {:?} → {:?} for tab {}",
+                old_mode, new_mode, tab_id
+            );
+
+            if old_mode == ViewMode::Rendered {
+                if let Some(sync_state) = self.sync_scroll_states.get(&tab_id) {
+                    let source_line = sync_state.rendered_offset_to_line(current_scroll).max(1);
+                    self.pending_scroll_to_line = Some(source_line);
+                }
+            }
+
+            // Mark settings dirty to save per-tab view mode on exit
+            self.state.mark_settings_dirty();
+        }
+    }
+
     /// Set the application theme and apply it immediately.
     #[allow(dead_code)]
     fn handle_set_theme(&mut self, theme: Theme, ctx: &egui::Context) {
@@ -2523,13 +3728,35 @@ impl FerriteApp {
                 .and_then(|s| s.to_str())
                 .unwrap_or("Exported Document");
 
+            // Embed images, or link them relative to the exported file
+            let image_handling = if self.state.settings.export_embed_images {
+                ImageHandling::EmbedBase64
+            } else {
+                ImageHandling::RelativePaths
+            };
+            let options = ExportOptions::html_file().with_image_handling(image_handling);
+            let base_dir = source_path
+                .as_deref()
+                .and_then(Path::parent)
+                .unwrap_or(Path::new("."));
+
             // Generate HTML
-            match generate_html_document(&content, Some(title), &theme_colors, true) {
-                Ok(html) => {
+            match export_markdown_to_html(
+                &content,
+                Some(title),
+                base_dir,
+                path.parent(),
+                &theme_colors,
+                &options,
+            ) {
+                Ok(export) => {
                     // Write to file
-                    match std::fs::write(&path, html) {
+                    match std::fs::write(&path, export.html) {
                         Ok(()) => {
                             info!("Exported HTML to: {}", path.display());
+                            for image in &export.unresolved_images {
+                                warn!("Image not exported: {}", image);
+                            }
 
                             // Update last export directory
                             if let Some(parent) = path.parent() {
@@ -2538,12 +3765,21 @@ impl FerriteApp {
                                 self.state.mark_settings_dirty();
                             }
 
+                            let message = match export.unresolved_images.len() {
+                                0 => format!("Exported to {}", path.display()),
+                                1 => format!(
+                                    "Exported to {} (image not found: {})",
+                                    path.display(),
+                                    export.unresolved_images[0].url
+                                ),
+                                n => format!(
+                                    "Exported to {} ({} images not found)",
+                                    path.display(),
+                                    n
+                                ),
+                            };
                             let time = self.get_app_time();
-                            self.state.show_toast(
-                                format!("Exported to {}", path.display()),
-                                time,
-                                2.5,
-                            );
+                            self.state.show_toast(message, time, 3.0);
 
                             // Optionally open the file
                             if self.state.settings.open_after_export {
@@ -2633,8 +3869,9 @@ impl FerriteApp {
                         // Update the tab content
                         if let Some(tab) = self.state.active_tab_mut() {
                             let old_content = tab.content.clone();
+                            let before = tab.cursor_snapshot();
                             tab.content = formatted;
-                            tab.record_edit(old_content);
+                            tab.record_edit(old_content, before);
                         }
                         let time = self.get_app_time();
                         self.state.show_toast("Document formatted", time, 2.0);
@@ -2784,13 +4021,7 @@ impl FerriteApp {
 
         // Trigger initial search if there's already a search term
         if !self.state.ui.find_state.search_term.is_empty() {
-            if let Some(tab) = self.state.active_tab() {
-                let content = tab.content.clone();
-                let count = self.state.ui.find_state.find_matches(&content);
-                if count > 0 {
-                    self.state.ui.scroll_to_match = true;
-                }
-            }
+            self.restart_search();
         }
 
         debug!("Find panel opened, replace_mode: {}", replace_mode);
@@ -2802,6 +4033,7 @@ impl FerriteApp {
             return;
         }
 
+        self.record_find_history(false);
         if let Some(idx) = self.state.ui.find_state.next_match() {
             self.state.ui.scroll_to_match = true;
             debug!("Find next: moved to match {}", idx + 1);
@@ -2814,14 +4046,99 @@ impl FerriteApp {
             return;
         }
 
+        self.record_find_history(false);
         if let Some(idx) = self.state.ui.find_state.prev_match() {
             self.state.ui.scroll_to_match = true;
             debug!("Find prev: moved to match {}", idx + 1);
         }
     }
 
+    /// Start a new incremental search of the active tab.
+    ///
+    /// When "Find in Selection" has just been turned on, the tab's current
+    /// selection becomes the search scope. Matches are then filled in a few at
+    /// a time by [`Self::continue_search`].
+    fn restart_search(&mut self) {
+        // Taken out of the UI state so it can be updated alongside the tab
+        let mut find_state = std::mem::take(&mut self.state.ui.find_state);
+        let Some(tab) = self.state.active_tab() else {
+            self.state.ui.find_state = find_state;
+            return;
+        };
+        if !find_state.in_selection {
+            find_state.scope = None;
+        } else if find_state.scope.is_none() {
+            find_state.scope = tab.selection.map(|(a, b)| {
+                (
+                    char_index_to_byte_index(&tab.content, a.min(b)),
+                    char_index_to_byte_index(&tab.content, a.max(b)),
+                )
+            });
+        }
+        find_state.start_search(&tab.content);
+        self.search_source = Some((tab.id, tab.edit_version()));
+        self.state.ui.find_state = find_state;
+        self.continue_search();
+    }
+
+    /// Restart the search if the active tab's text is no longer the text the
+    /// matches were found in (after typing, a reload or a tab switch).
+    fn restart_search_if_stale(&mut self) {
+        let source = self
+            .state
+            .active_tab()
+            .map(|tab| (tab.id, tab.edit_version()));
+        let stale = source.is_some()
+            && source != self.search_source
+            && !self.state.ui.find_state.search_term.is_empty();
+        if stale {
+            self.restart_search();
+        }
+    }
+
+    /// Find the next batch of matches of a running search.
+    fn continue_search(&mut self) {
+        if !self.state.ui.find_state.is_searching() {
+            return;
+        }
+        let mut find_state = std::mem::take(&mut self.state.ui.find_state);
+        let Some(tab) = self.state.active_tab() else {
+            self.state.ui.find_state = find_state;
+            return;
+        };
+        let had_matches = !find_state.matches.is_empty();
+        if find_state.search_step(&tab.content, BYTES_PER_STEP) {
+            debug!(
+                "Search complete, found {} matches",
+                find_state.match_count()
+            );
+        }
+        if !had_matches && !find_state.matches.is_empty() {
+            self.state.ui.scroll_to_match = true;
+        }
+        self.state.ui.find_state = find_state;
+    }
+
+    /// Remember the current find (and optionally replace) terms in the
+    /// search history.
+    fn record_find_history(&mut self, include_replace: bool) {
+        let find_state = &self.state.ui.find_state;
+        let search_term = find_state.search_term.clone();
+        let replace_term = find_state.replace_term.clone();
+        let settings = &mut self.state.settings;
+        let mut changed = settings.add_find_history(&search_term);
+        if include_replace {
+            changed |= settings.add_replace_history(&replace_term);
+        }
+        if changed {
+            self.state.mark_settings_dirty();
+        }
+    }
+
     /// Handle replace current match action.
     fn handle_replace_current(&mut self) {
+        self.restart_search_if_stale();
+        self.record_find_history(true);
         if let Some(tab) = self.state.active_tab() {
             let content = tab.content.clone();
             if let Some(new_content) = self.state.ui.find_state.replace_current(&content) {
@@ -2831,7 +4148,13 @@ impl FerriteApp {
                 }
 
                 // Re-search to update matches
-                self.state.ui.find_state.find_matches(&new_content);
+                let find_state = &mut self.state.ui.find_state;
+                find_state.adjust_scope(content.len(), new_content.len());
+                find_state.find_matches(&new_content);
+                self.search_source = self
+                    .state
+                    .active_tab()
+                    .map(|tab| (tab.id, tab.edit_version()));
 
                 let time = self.get_app_time();
                 self.state.show_toast("Replaced", time, 1.5);
@@ -2842,6 +4165,8 @@ impl FerriteApp {
 
     /// Handle replace all matches action.
     fn handle_replace_all(&mut self) {
+        self.restart_search_if_stale();
+        self.record_find_history(true);
         if let Some(tab) = self.state.active_tab() {
             let content = tab.content.clone();
             let match_count = self.state.ui.find_state.match_count();
@@ -2855,7 +4180,13 @@ impl FerriteApp {
                 }
 
                 // Re-search (will find 0 matches after replace all)
-                self.state.ui.find_state.find_matches(&new_content);
+                let find_state = &mut self.state.ui.find_state;
+                find_state.adjust_scope(content.len(), new_content.len());
+                find_state.find_matches(&new_content);
+                self.search_source = self
+                    .state
+                    .active_tab()
+                    .map(|tab| (tab.id, tab.edit_version()));
 
                 let time = self.get_app_time();
                 self.state.show_toast(
@@ -2929,6 +4260,10 @@ impl FerriteApp {
                 debug!("Ribbon: Toggle view mode");
                 self.handle_toggle_view_mode();
             }
+            RibbonAction::ToggleSplitView => {
+                debug!("Ribbon: Toggle split view");
+                self.handle_toggle_split_view();
+            }
             RibbonAction::ToggleLineNumbers => {
                 debug!("Ribbon: Toggle line numbers");
                 self.state.settings.show_line_numbers = !self.state.settings.show_line_numbers;
@@ -3075,6 +4410,102 @@ impl FerriteApp {
                 });
         }
 
+        // Merge preview for a file changed on disk under unsaved edits
+        if let Some((tab_id, result)) = &self.pending_merge {
+            let tab_id = *tab_id;
+            let mut apply = false;
+            let mut cancel = false;
+
+            egui::Window::new("Compare with Disk")
+                .collapsible(false)
+                .resizable(true)
+                .default_size([640.0, 420.0])
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    if result.is_clean() {
+                        ui.label("Your changes and the disk changes merge cleanly.");
+                    } else {
+                        ui.label(format!(
+                            "{} conflicting region(s) are marked with <<<<<<< / >>>>>>>. \
+                             Edit them after applying the merge.",
+                            result.conflicts
+                        ));
+                    }
+                    ui.separator();
+                    egui::ScrollArea::vertical()
+                        .max_height(320.0)
+                        .show(ui, |ui| {
+                            let mut preview = result.text.as_str();
+                            ui.add(
+                                egui::TextEdit::multiline(&mut preview)
+                                    .font(egui::TextStyle::Monospace)
+                                    .desired_width(f32::INFINITY),
+                            );
+                        });
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.button("Apply Merge").clicked() {
+                            apply = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            cancel = true;
+                        }
+                    });
+                });
+
+            if apply {
+                if let Some((_, result)) = self.pending_merge.take() {
+                    if let Some(index) = self.state.tabs().iter().position(|t| t.id == tab_id) {
+                        self.state.apply_tab_merge(index, result.text);
+                    }
+                }
+            } else if cancel {
+                self.pending_merge = None;
+            }
+        }
+
+        // Recovered tab whose file changed on disk while we weren't running
+        if let Some(conflict) = self.state.ui.recovery_conflicts.first().cloned() {
+            egui::Window::new("Recovered Changes")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    let name = conflict
+                        .path
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or("unknown");
+                    ui.label(format!(
+                        "Unsaved changes to '{}' were recovered, but the file was \
+                         modified on disk since they were made.",
+                        name
+                    ));
+                    ui.label(
+                        egui::RichText::new(conflict.path.display().to_string())
+                            .small()
+                            .weak(),
+                    );
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui
+                            .button("Keep Recovered")
+                            .on_hover_text("Keep your unsaved edits in the tab")
+                            .clicked()
+                        {
+                            self.state.resolve_recovery_conflict(true);
+                        }
+                        if ui
+                            .button("Use Disk Version")
+                            .on_hover_text("Reload the file (your edits stay in undo history)")
+                            .clicked()
+                        {
+                            self.state.resolve_recovery_conflict(false);
+                        }
+                    });
+                });
+        }
+
         // Error modal
         if self.state.ui.show_error_modal {
             egui::Window::new("Error")
@@ -3136,23 +4567,42 @@ impl FerriteApp {
         // Find/Replace panel
         if self.state.ui.show_find_replace {
             let is_dark = ctx.style().visuals.dark_mode;
-            let output = self
-                .find_replace_panel
-                .show(ctx, &mut self.state.ui.find_state, is_dark);
+
+            // Count matches in large documents over several frames
+            self.restart_search_if_stale();
+            if self.state.ui.find_state.is_searching() {
+                self.continue_search();
+                ctx.request_repaint();
+            }
+
+            let find_state = &self.state.ui.find_state;
+            let previews = match self.state.active_tab() {
+                Some(tab) if find_state.is_replace_mode => {
+                    find_state.replace_previews(&tab.content)
+                }
+                _ => Vec::new(),
+            };
+            let output = self.find_replace_panel.show(
+                ctx,
+                &mut self.state.ui.find_state,
+                &previews,
+                &self.state.settings.find_history,
+                &self.state.settings.replace_history,
+                is_dark,
+            );
 
             // Handle search changes - re-search when term or options change
             if output.search_changed {
-                if let Some(tab) = self.state.active_tab() {
-                    let content = tab.content.clone();
-                    let match_count = self.state.ui.find_state.find_matches(&content);
-                    if match_count > 0 {
-                        self.state.ui.scroll_to_match = true;
-                    }
-                    debug!("Search changed, found {} matches", match_count);
-                }
+                self.restart_search();
+                ctx.request_repaint();
             }
 
             // Handle navigation
+            if let Some(index) = output.selected_match {
+                self.state.ui.find_state.current_match = index;
+                self.state.ui.scroll_to_match = true;
+            }
+
             if output.next_requested {
                 self.handle_find_next();
             }
@@ -3202,6 +4652,12 @@ impl eframe::App for FerriteApp {
         // Handle drag-drop of files and folders
         self.handle_dropped_files(ctx);
 
+        // Store images pasted from the clipboard as assets
+        self.handle_image_paste(ctx);
+
+        // Open files forwarded by other launches
+        self.handle_ipc_requests(ctx);
+
         // Poll file watcher for workspace changes
         self.handle_file_watcher_events();
 
@@ -3214,6 +4670,9 @@ impl eframe::App for FerriteApp {
         // Render the main UI (this updates editor selection)
         let deferred_format = self.render_ui(ctx);
 
+        // Draw a zoomed inline image over everything else
+        images::show_zoomed_image(ctx);
+
         // Handle keyboard shortcuts AFTER render so selection is up-to-date
         self.handle_keyboard_shortcuts(ctx);
 
@@ -3223,6 +4682,19 @@ impl eframe::App for FerriteApp {
             self.handle_format_command(cmd);
         }
 
+        // Auto-save modified tabs (interval, focus loss, tab switch)
+        self.handle_auto_save(ctx);
+
+        // Snapshot unsaved tabs for crash recovery
+        let now = self.get_app_time();
+        if now - self.last_recovery_snapshot >= RECOVERY_SNAPSHOT_INTERVAL_SECS {
+            self.state.snapshot_recovery();
+            self.last_recovery_snapshot = now;
+        }
+
+        // Exit once the tabs opened with --wait are closed
+        self.handle_wait_for_tabs(ctx);
+
         // Request exit if confirmed
         if self.should_exit {
             ctx.send_viewport_cmd(egui::ViewportCommand::Close);
@@ -3278,6 +4750,12 @@ fn char_index_to_line_col(text: &str, char_index: usize) -> (usize, usize) {
     (line, col)
 }
 
+/// Character index of a 1-indexed `file:line:col` launch position, clamped
+/// to the end of the line (or text).
+fn launch_cursor(text: &str, line: usize, column: usize) -> usize {
+    line_col_to_char_index(text, line.saturating_sub(1), column.saturating_sub(1))
+}
+
 /// Convert line and column (0-indexed) to a character index.
 fn line_col_to_char_index(text: &str, target_line: usize, target_col: usize) -> usize {
     let mut current_line = 0;
@@ -3304,3 +4782,23 @@ fn line_col_to_char_index(text: &str, target_line: usize, target_col: usize) ->
     // Return end of text if target position is beyond text
     char_index
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_launch_cursor() {
+        let text = "first\nsécond line\nlast";
+        assert_eq!(launch_cursor(text, 1, 1), 0);
+        assert_eq!(launch_cursor(text, 2, 3), 8);
+        // Columns past the end of the line stop at the line end
+        assert_eq!(launch_cursor(text, 2, 99), 17);
+        // Lines past the end stop at the end of the text
+        assert_eq!(launch_cursor(text, 9, 1), text.chars().count());
+        assert_eq!(
+            char_index_to_line_col(text, launch_cursor(text, 3, 2)),
+            (2, 1)
+        );
+    }
+}
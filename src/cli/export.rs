@@ -0,0 +1,278 @@
+//! `ferrite export` - headless HTML export
+//!
+//! Converts a markdown file, or every markdown file in a folder, to themed
+//! standalone HTML using the same pipeline as the editor's export action.
+//! No window is opened, so it works in CI without a display server.
+
+use super::Command;
+use crate::error::{Error, Result};
+use crate::export::batch::{plan_export, run_export};
+use crate::export::html::export_file_to_html;
+use crate::export::images::UnresolvedImage;
+use crate::export::options::{ExportOptions, ImageHandling};
+use crate::theme::ThemeColors;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Usage text printed by `ferrite export --help`.
+pub const USAGE: &str = "\
+Usage: ferrite export [OPTIONS] <INPUT>
+
+Arguments:
+  <INPUT>              Markdown file, or folder to mirror into HTML
+
+Options:
+  -o, --output <PATH>  Output file or folder ('-' writes a single file to stdout)
+      --theme <THEME>  Color theme: light (default) or dark
+      --images <MODE>  Local images: relative (default, relative to the output),
+                       embed (inline as data URIs) or absolute (file:// URLs)
+      --embed-images   Same as --images embed
+  -h, --help           Print this help";
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Arguments
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Color theme for exported HTML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportTheme {
+    /// Light colors (default)
+    #[default]
+    Light,
+    /// Dark colors
+    Dark,
+}
+
+impl ExportTheme {
+    /// Get the theme colors for this theme.
+    pub fn colors(&self) -> ThemeColors {
+        match self {
+            ExportTheme::Light => ThemeColors::light(),
+            ExportTheme::Dark => ThemeColors::dark(),
+        }
+    }
+}
+
+/// Parsed `ferrite export` arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportArgs {
+    /// Markdown file or folder to export
+    pub input: PathBuf,
+    /// Output file or folder (None = next to the input)
+    pub output: Option<PathBuf>,
+    /// Color theme
+    pub theme: ExportTheme,
+    /// How local images are written
+    pub images: ImageHandling,
+}
+
+impl ExportArgs {
+    /// Build the export options for these arguments.
+    pub fn export_options(&self) -> ExportOptions {
+        ExportOptions {
+            // Links between files only resolve when a whole tree is exported
+            rewrite_markdown_links: self.input.is_dir(),
+            ..ExportOptions::html_file().with_image_handling(self.images)
+        }
+    }
+
+    /// Check if the HTML should be written to stdout.
+    fn to_stdout(&self) -> bool {
+        self.output.as_deref() == Some(Path::new("-"))
+    }
+}
+
+/// Parse the arguments following `export`.
+///
+/// # Errors
+///
+/// Returns `Error::Application` for unknown options or missing values.
+pub fn parse(args: &[String]) -> Result<Command> {
+    let mut input = None;
+    let mut output = None;
+    let mut theme = ExportTheme::default();
+    let mut images = ImageHandling::RelativePaths;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help(USAGE)),
+            "-o" | "--output" => output = Some(PathBuf::from(value(&mut args, arg)?)),
+            "--theme" => {
+                theme = match value(&mut args, arg)?.as_str() {
+                    "light" => ExportTheme::Light,
+                    "dark" => ExportTheme::Dark,
+                    other => return Err(usage_error(format!("Unknown theme '{}'", other))),
+                }
+            }
+            "--images" => {
+                images = match value(&mut args, arg)?.as_str() {
+                    "embed" => ImageHandling::EmbedBase64,
+                    "relative" => ImageHandling::RelativePaths,
+                    "absolute" => ImageHandling::AbsolutePaths,
+                    other => return Err(usage_error(format!("Unknown image mode '{}'", other))),
+                }
+            }
+            "--embed-images" => images = ImageHandling::EmbedBase64,
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(usage_error(format!("Unknown option '{}'", arg)))
+            }
+            _ if input.is_none() => input = Some(PathBuf::from(arg)),
+            _ => return Err(usage_error(format!("Unexpected argument '{}'", arg))),
+        }
+    }
+
+    let input = input.ok_or_else(|| usage_error("Missing <INPUT>".to_string()))?;
+    let args = ExportArgs {
+        input,
+        output,
+        theme,
+        images,
+    };
+    if args.to_stdout() && args.input.is_dir() {
+        return Err(usage_error(
+            "A folder can't be exported to stdout".to_string(),
+        ));
+    }
+    Ok(Command::Export(args))
+}
+
+/// Take the value following an option.
+fn value<'a>(args: &mut impl Iterator<Item = &'a String>, option: &str) -> Result<&'a String> {
+    args.next()
+        .ok_or_else(|| usage_error(format!("Missing value for '{}'", option)))
+}
+
+/// Build a usage error with the subcommand's help text appended.
+fn usage_error(message: String) -> Error {
+    Error::Application(format!("{}\n\n{}", message, USAGE))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Running
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Run the export and return the process exit code.
+///
+/// Exported files are listed on stdout, and failures and images that
+/// couldn't be resolved on stderr; the exit code is 1 if any file failed.
+pub fn run(args: &ExportArgs) -> i32 {
+    let options = args.export_options();
+    let theme_colors = args.theme.colors();
+
+    if args.to_stdout() {
+        return match export_file_to_html(&args.input, None, &theme_colors, &options) {
+            Ok(export) => {
+                report_unresolved(&args.input, &export.unresolved_images);
+                // A closed pipe (e.g. `| head`) isn't an export failure
+                let _ = std::io::stdout().write_all(export.html.as_bytes());
+                0
+            }
+            Err(e) => {
+                eprintln!("{}: {}", args.input.display(), e);
+                1
+            }
+        };
+    }
+
+    let jobs = plan_export(&args.input, args.output.as_deref());
+    if jobs.is_empty() {
+        eprintln!("No markdown files found in {}", args.input.display());
+        return 1;
+    }
+
+    let failed = run_export(&jobs, &theme_colors, &options, |job, result| match result {
+        Ok(unresolved) => {
+            println!("{} -> {}", job.source.display(), job.output.display());
+            report_unresolved(&job.source, unresolved);
+        }
+        Err(e) => eprintln!("{}: {}", job.source.display(), e),
+    });
+    i32::from(failed > 0)
+}
+
+/// Warn about images that were left as written in the exported HTML.
+fn report_unresolved(source: &Path, images: &[UnresolvedImage]) {
+    for image in images {
+        eprintln!("{}: warning: image {}", source.display(), image);
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_export(args: &[&str]) -> Result<ExportArgs> {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        match parse(&args)? {
+            Command::Export(args) => Ok(args),
+            other => panic!("expected Export, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_export_args() {
+        let args = parse_export(&[
+            "README.md",
+            "-o",
+            "README.html",
+            "--theme",
+            "dark",
+            "--embed-images",
+        ])
+        .unwrap();
+        assert_eq!(args.input, PathBuf::from("README.md"));
+        assert_eq!(args.output, Some(PathBuf::from("README.html")));
+        assert_eq!(args.theme, ExportTheme::Dark);
+        assert_eq!(args.images, ImageHandling::EmbedBase64);
+        assert_eq!(
+            args.export_options().image_handling,
+            ImageHandling::EmbedBase64
+        );
+
+        let args = parse_export(&["README.md", "--images", "absolute"]).unwrap();
+        assert_eq!(args.images, ImageHandling::AbsolutePaths);
+    }
+
+    #[test]
+    fn test_parse_export_defaults() {
+        let args = parse_export(&["notes.md"]).unwrap();
+        assert_eq!(args.output, None);
+        assert_eq!(args.theme, ExportTheme::Light);
+        assert_eq!(
+            args.export_options().image_handling,
+            ImageHandling::RelativePaths
+        );
+    }
+
+    #[test]
+    fn test_parse_export_errors() {
+        assert!(parse_export(&[]).is_err());
+        assert!(parse_export(&["a.md", "b.md"]).is_err());
+        assert!(parse_export(&["a.md", "--theme", "solarized"]).is_err());
+        assert!(parse_export(&["a.md", "-o"]).is_err());
+        assert!(parse_export(&["a.md", "--pdf"]).is_err());
+        assert!(parse_export(&["a.md", "--images", "copy"]).is_err());
+    }
+
+    #[test]
+    fn test_run_export_single_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input = temp_dir.path().join("README.md");
+        std::fs::write(&input, "# Hello").unwrap();
+
+        let args = ExportArgs {
+            input,
+            output: None,
+            theme: ExportTheme::Light,
+            images: ImageHandling::RelativePaths,
+        };
+        assert_eq!(run(&args), 0);
+        let html = std::fs::read_to_string(temp_dir.path().join("README.html")).unwrap();
+        assert!(html.contains("Hello</h1>"));
+    }
+}
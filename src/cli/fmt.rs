@@ -0,0 +1,708 @@
+//! `ferrite fmt` and `ferrite check` - headless structured data tools
+//!
+//! Runs the editor's Format Document and Validate Syntax actions over JSON,
+//! YAML and TOML files, so CI jobs and pre-commit hooks produce exactly the
+//! output the editor would. Files keep their encoding, BOM and line endings.
+
+use super::Command;
+use crate::error::{Error, Result};
+use crate::files::encoding::{read_text_file, write_text_file, FileFormat};
+use crate::files::glob::{expand, has_glob_chars, walk_files};
+use crate::markdown::tree_viewer::{
+    parse_structured_content, serialize_tree, StructuredFileType, TreeNode,
+};
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Usage text printed by `ferrite fmt --help`.
+pub const FMT_USAGE: &str = "\
+Usage: ferrite fmt [OPTIONS] <PATH>...
+
+Arguments:
+  <PATH>...    JSON, YAML or TOML files, folders, or glob patterns
+
+Options:
+      --check  Don't write anything; exit with 1 if a file would change
+      --write  Format files in place
+  -h, --help   Print this help
+
+Without --check or --write, the formatted file is printed to stdout.
+Formatting removes YAML and TOML comments, so --check and --write skip
+files that have them (with a warning, not a failure).";
+
+/// Usage text printed by `ferrite check --help`.
+pub const CHECK_USAGE: &str = "\
+Usage: ferrite check <PATH>...
+
+Arguments:
+  <PATH>...   JSON, YAML or TOML files, folders, or glob patterns
+
+Options:
+  -h, --help  Print this help
+
+Syntax errors are printed as 'path:line:col: message'.";
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Arguments
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// What `ferrite fmt` does with the formatted output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FmtMode {
+    /// Print the formatted file to stdout (single file only)
+    #[default]
+    Stdout,
+    /// Report files that would change
+    Check,
+    /// Rewrite files in place
+    Write,
+}
+
+/// Parsed `ferrite fmt` arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FmtArgs {
+    /// Files, folders and glob patterns as given
+    pub paths: Vec<String>,
+    /// Output mode
+    pub mode: FmtMode,
+}
+
+/// Parsed `ferrite check` arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckArgs {
+    /// Files, folders and glob patterns as given
+    pub paths: Vec<String>,
+}
+
+/// Parse the arguments following `fmt`.
+///
+/// # Errors
+///
+/// Returns `Error::Application` for unknown options, conflicting modes or
+/// missing paths.
+pub fn parse_fmt(args: &[String]) -> Result<Command> {
+    let mut mode = FmtMode::default();
+    let mut paths = Vec::new();
+
+    for arg in args {
+        let requested = match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help(FMT_USAGE)),
+            "--check" => FmtMode::Check,
+            "--write" => FmtMode::Write,
+            _ if arg.starts_with('-') => {
+                return Err(usage_error(format!("Unknown option '{}'", arg), FMT_USAGE))
+            }
+            _ => {
+                paths.push(arg.clone());
+                continue;
+            }
+        };
+        if mode != FmtMode::Stdout && mode != requested {
+            return Err(usage_error(
+                "--check and --write can't be combined".to_string(),
+                FMT_USAGE,
+            ));
+        }
+        mode = requested;
+    }
+
+    if paths.is_empty() {
+        return Err(usage_error("Missing <PATH>".to_string(), FMT_USAGE));
+    }
+    Ok(Command::Fmt(FmtArgs { paths, mode }))
+}
+
+/// Parse the arguments following `check`.
+///
+/// # Errors
+///
+/// Returns `Error::Application` for unknown options or missing paths.
+pub fn parse_check(args: &[String]) -> Result<Command> {
+    let mut paths = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help(CHECK_USAGE)),
+            _ if arg.starts_with('-') => {
+                return Err(usage_error(
+                    format!("Unknown option '{}'", arg),
+                    CHECK_USAGE,
+                ))
+            }
+            _ => paths.push(arg.clone()),
+        }
+    }
+
+    if paths.is_empty() {
+        return Err(usage_error("Missing <PATH>".to_string(), CHECK_USAGE));
+    }
+    Ok(Command::Check(CheckArgs { paths }))
+}
+
+/// Build a usage error with the subcommand's help text appended.
+fn usage_error(message: String, usage: &str) -> Error {
+    Error::Application(format!("{}\n\n{}", message, usage))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Files
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Get the structured data type of a file from its extension.
+fn structured_type(path: &Path) -> Option<StructuredFileType> {
+    StructuredFileType::from_extension(path.extension()?.to_str()?)
+}
+
+/// Resolve path arguments into a list of structured data files.
+///
+/// Folders and globs only contribute JSON, YAML and TOML files; a file named
+/// explicitly is always included, so an unsupported type is reported rather
+/// than silently skipped. Returns the files and a diagnostic for every
+/// argument that matched nothing.
+pub fn collect_files(paths: &[String]) -> (Vec<PathBuf>, Vec<Diagnostic>) {
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+
+    for arg in paths {
+        let path = Path::new(arg);
+        let found = if path.is_dir() {
+            walk_files(path)
+                .into_iter()
+                .filter(|p| structured_type(p).is_some())
+                .collect()
+        } else if path.exists() {
+            vec![path.to_path_buf()]
+        } else if has_glob_chars(arg) {
+            match expand(arg) {
+                Ok(matches) => matches
+                    .into_iter()
+                    .filter(|p| structured_type(p).is_some())
+                    .collect(),
+                Err(message) => {
+                    errors.push(Diagnostic::new(path, message));
+                    continue;
+                }
+            }
+        } else {
+            errors.push(Diagnostic::new(path, "No such file or directory"));
+            continue;
+        };
+
+        if found.is_empty() {
+            errors.push(Diagnostic::new(path, "No JSON, YAML or TOML files found"));
+        }
+        for file in found {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+
+    (files, errors)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Formatting and Validation
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A problem with a file, printed as `path:line:col: message`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// File the problem is in
+    pub path: PathBuf,
+    /// 1-indexed line, if known
+    pub line: Option<usize>,
+    /// 1-indexed column, if known
+    pub column: Option<usize>,
+    /// Description of the problem
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Create a diagnostic without a location.
+    fn new(path: &Path, message: impl Into<String>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            line: None,
+            column: None,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{}:{}", line, self.column.unwrap_or(1))?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// A file read from disk, with its formatted content.
+#[derive(Debug, Clone)]
+pub struct FormattedFile {
+    /// Content as read (line endings normalized to `\n`)
+    pub original: String,
+    /// Content after formatting
+    pub formatted: String,
+    /// Encoding, BOM and line endings to write back with
+    pub format: FileFormat,
+    /// Whether the file has comments, which formatting removes
+    pub has_comments: bool,
+}
+
+impl FormattedFile {
+    /// Check if formatting changes the file.
+    pub fn changed(&self) -> bool {
+        self.original != self.formatted
+    }
+}
+
+/// A structured data file read from disk and parsed.
+struct ParsedFile {
+    content: String,
+    format: FileFormat,
+    file_type: StructuredFileType,
+    tree: TreeNode,
+}
+
+/// Read and parse a structured data file.
+fn read_and_parse(path: &Path) -> std::result::Result<ParsedFile, Diagnostic> {
+    let file_type = structured_type(path)
+        .ok_or_else(|| Diagnostic::new(path, "Not a JSON, YAML or TOML file"))?;
+    let (content, format) =
+        read_text_file(path).map_err(|e| Diagnostic::new(path, e.to_string()))?;
+    let tree = parse_structured_content(&content, file_type).map_err(|e| Diagnostic {
+        path: path.to_path_buf(),
+        line: e.line,
+        column: e.column,
+        message: e.message,
+    })?;
+    Ok(ParsedFile {
+        content,
+        format,
+        file_type,
+        tree,
+    })
+}
+
+/// Validate a file's syntax (the editor's Validate Syntax action).
+pub fn check_file(path: &Path) -> std::result::Result<(), Diagnostic> {
+    read_and_parse(path).map(|_| ())
+}
+
+/// Format a file the way the editor's Format Document action does.
+pub fn format_file(path: &Path) -> std::result::Result<FormattedFile, Diagnostic> {
+    let parsed = read_and_parse(path)?;
+    let formatted =
+        serialize_tree(&parsed.tree, parsed.file_type).map_err(|e| Diagnostic::new(path, e))?;
+    Ok(FormattedFile {
+        has_comments: has_comments(&parsed.content, parsed.file_type),
+        original: parsed.content,
+        formatted,
+        format: parsed.format,
+    })
+}
+
+/// Check if a YAML or TOML file has comments, which formatting would drop.
+///
+/// A single pass skips over quoted strings (and YAML block scalars), so a
+/// `#` inside a value isn't taken for a comment.
+fn has_comments(content: &str, file_type: StructuredFileType) -> bool {
+    match file_type {
+        StructuredFileType::Json => false,
+        StructuredFileType::Yaml => yaml_has_comments(content),
+        StructuredFileType::Toml => toml_has_comments(content),
+    }
+}
+
+/// Find a TOML comment: a `#` outside basic, literal and multi-line strings.
+fn toml_has_comments(content: &str) -> bool {
+    let bytes = content.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let quote = match bytes[i] {
+            b'#' => return true,
+            quote @ (b'"' | b'\'') => quote,
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        let multi_line = bytes[i..].starts_with(&[quote; 3]);
+        let open = if multi_line { 3 } else { 1 };
+        i = toml_string_end(bytes, i + open, quote, multi_line);
+    }
+    false
+}
+
+/// Find the index just past a TOML string whose contents start at `i`.
+fn toml_string_end(bytes: &[u8], mut i: usize, quote: u8, multi_line: bool) -> usize {
+    while i < bytes.len() {
+        match bytes[i] {
+            // Only basic strings have escapes
+            b'\\' if quote == b'"' => i += 2,
+            b'\n' if !multi_line => return i,
+            c if c == quote && !multi_line => return i + 1,
+            c if c == quote && bytes[i..].starts_with(&[quote; 3]) => {
+                // Up to two quotes right before the delimiter are content
+                let run = bytes[i..].iter().take_while(|&&b| b == quote).count();
+                return i + run.min(5);
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Find a YAML comment: a `#` at the start of a line or after whitespace,
+/// outside quoted scalars and block scalars.
+fn yaml_has_comments(content: &str) -> bool {
+    // Quote of a scalar continuing from an earlier line
+    let mut open_quote: Option<char> = None;
+    // Indentation a block scalar's lines must exceed
+    let mut block_indent: Option<usize> = None;
+
+    for line in content.lines() {
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        if let Some(parent) = block_indent {
+            if line.trim().is_empty() || indent > parent {
+                continue;
+            }
+            block_indent = None;
+        }
+
+        let mut prev: Option<char> = None;
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match open_quote {
+                Some('\'') if c == '\'' => {
+                    // `''` is an escaped quote
+                    if chars.peek() == Some(&'\'') {
+                        chars.next();
+                    } else {
+                        open_quote = None;
+                    }
+                }
+                Some('"') if c == '\\' => {
+                    chars.next();
+                }
+                Some('"') if c == '"' => open_quote = None,
+                Some(_) => {}
+                None => {
+                    let starts_token =
+                        prev.map_or(true, |p| p.is_whitespace() || "[{,".contains(p));
+                    if c == '#' && prev.map_or(true, char::is_whitespace) {
+                        return true;
+                    }
+                    if (c == '"' || c == '\'') && starts_token {
+                        open_quote = Some(c);
+                    }
+                }
+            }
+            prev = Some(c);
+        }
+
+        if open_quote.is_none() {
+            block_indent = block_scalar_parent(line, indent);
+        }
+    }
+    false
+}
+
+/// If `line` starts a block scalar (`key: |`, `- >-`), get the indentation
+/// its content lines must exceed.
+fn block_scalar_parent(line: &str, indent: usize) -> Option<usize> {
+    let body = line.trim_end();
+    let header = body.rsplit(char::is_whitespace).next().unwrap_or(body);
+    let mut chars = header.chars();
+    if !matches!(chars.next(), Some('|' | '>'))
+        || !chars.all(|c| matches!(c, '-' | '+' | '0'..='9'))
+    {
+        return None;
+    }
+    // After a key, an entry marker, a tag or an anchor (`a: x |` is text)
+    let before = body[..body.len() - header.len()].trim_end();
+    let last_token = before.rsplit(char::is_whitespace).next().unwrap_or("");
+    if !(before.is_empty()
+        || before.ends_with(':')
+        || before.ends_with('-')
+        || last_token.starts_with(['!', '&']))
+    {
+        return None;
+    }
+
+    // The content is nested in the node after any `- ` entry markers, or in
+    // the last entry's sequence when the scalar is the entry itself
+    let mut node = &body[indent..];
+    let mut node_indent = indent;
+    let mut entry_indent = indent;
+    while let Some(rest) = node.strip_prefix('-').filter(|r| r.starts_with(' ')) {
+        let entry = rest.trim_start_matches(' ');
+        entry_indent = node_indent;
+        node_indent += node.len() - entry.len();
+        node = entry;
+    }
+    Some(if node == header {
+        entry_indent
+    } else {
+        node_indent
+    })
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Running
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Run `ferrite fmt` and return the process exit code.
+///
+/// Exits with 1 if any file failed to format, or with `--check` if any file
+/// would change, and with 2 for unusable arguments. Files whose comments
+/// formatting would remove are skipped with a warning.
+pub fn run_fmt(args: &FmtArgs) -> i32 {
+    let (files, errors) = collect_files(&args.paths);
+    let mut failed = !errors.is_empty();
+    for error in &errors {
+        eprintln!("{}", error);
+    }
+
+    if args.mode == FmtMode::Stdout && files.len() > 1 {
+        eprintln!("Use --check or --write to format more than one file");
+        return 2;
+    }
+
+    for path in &files {
+        let file = match format_file(path) {
+            Ok(file) => file,
+            Err(diagnostic) => {
+                eprintln!("{}", diagnostic);
+                failed = true;
+                continue;
+            }
+        };
+
+        match args.mode {
+            FmtMode::Stdout => {
+                // A closed pipe (e.g. `| head`) isn't a formatting failure
+                let _ = std::io::stdout().write_all(file.formatted.as_bytes());
+            }
+            // Formatting would lose the comments, so the file is left as is
+            FmtMode::Check | FmtMode::Write if file.changed() && file.has_comments => {
+                eprintln!(
+                    "{}",
+                    Diagnostic::new(path, "Skipped: formatting would remove its comments")
+                );
+            }
+            FmtMode::Check if file.changed() => {
+                println!("Would reformat {}", path.display());
+                failed = true;
+            }
+            FmtMode::Write if file.changed() => {
+                match write_text_file(path, &file.formatted, &file.format) {
+                    Ok(()) => println!("Formatted {}", path.display()),
+                    Err(e) => {
+                        eprintln!("{}", Diagnostic::new(path, e.to_string()));
+                        failed = true;
+                    }
+                }
+            }
+            FmtMode::Check | FmtMode::Write => {}
+        }
+    }
+
+    i32::from(failed)
+}
+
+/// Run `ferrite check` and return the process exit code.
+///
+/// Every syntax error is printed to stdout as `path:line:col: message`; the
+/// exit code is 1 if any file is invalid or unreadable.
+pub fn run_check(args: &CheckArgs) -> i32 {
+    let (files, errors) = collect_files(&args.paths);
+    let mut failed = !errors.is_empty();
+    for error in &errors {
+        eprintln!("{}", error);
+    }
+
+    for path in &files {
+        if let Err(diagnostic) = check_file(path) {
+            println!("{}", diagnostic);
+            failed = true;
+        }
+    }
+
+    i32::from(failed)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_fmt_args() {
+        match parse_fmt(&strings(&["--check", "a.json", "conf/"])).unwrap() {
+            Command::Fmt(args) => {
+                assert_eq!(args.mode, FmtMode::Check);
+                assert_eq!(args.paths, strings(&["a.json", "conf/"]));
+            }
+            other => panic!("expected Fmt, got {:?}", other),
+        }
+        assert!(parse_fmt(&strings(&["--check", "--write", "a.json"])).is_err());
+        assert!(parse_fmt(&strings(&["--check"])).is_err());
+        assert!(parse_check(&strings(&["--fix", "a.json"])).is_err());
+        assert_eq!(
+            parse_check(&strings(&["-h"])).unwrap(),
+            Command::Help(CHECK_USAGE)
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_display() {
+        let diagnostic = Diagnostic {
+            path: PathBuf::from("conf/app.json"),
+            line: Some(3),
+            column: Some(14),
+            message: "expected value".to_string(),
+        };
+        assert_eq!(diagnostic.to_string(), "conf/app.json:3:14: expected value");
+        assert_eq!(
+            Diagnostic::new(Path::new("x.json"), "No such file or directory").to_string(),
+            "x.json: No such file or directory"
+        );
+    }
+
+    #[test]
+    fn test_check_file_reports_location() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("bad.json");
+        fs::write(&path, "{\n  \"a\": 1,\n  \"b\": ]\n}").unwrap();
+
+        let diagnostic = check_file(&path).unwrap_err();
+        assert_eq!(diagnostic.line, Some(3));
+        assert!(diagnostic.column.is_some());
+    }
+
+    #[test]
+    fn test_fmt_write_preserves_line_endings() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("app.json");
+        fs::write(&path, "{\"b\":1,\r\n\"a\":[true]}\r\n").unwrap();
+
+        let args = FmtArgs {
+            paths: vec![path.to_string_lossy().to_string()],
+            mode: FmtMode::Check,
+        };
+        assert_eq!(run_fmt(&args), 1);
+
+        let args = FmtArgs {
+            mode: FmtMode::Write,
+            ..args
+        };
+        assert_eq!(run_fmt(&args), 0);
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(written.contains("\r\n"));
+        assert!(!format_file(&path).unwrap().changed());
+
+        let args = FmtArgs {
+            mode: FmtMode::Check,
+            ..args
+        };
+        assert_eq!(run_fmt(&args), 0);
+    }
+
+    #[test]
+    fn test_fmt_write_skips_files_with_comments() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("app.yaml");
+        let source = "b:   1\nnote: don't   # keep this comment\n";
+        fs::write(&path, source).unwrap();
+
+        // --check agrees with --write: skipped, not failed
+        let args = FmtArgs {
+            paths: vec![path.to_string_lossy().to_string()],
+            mode: FmtMode::Check,
+        };
+        assert_eq!(run_fmt(&args), 0);
+        let args = FmtArgs {
+            mode: FmtMode::Write,
+            ..args
+        };
+        assert_eq!(run_fmt(&args), 0);
+        assert_eq!(fs::read_to_string(&path).unwrap(), source);
+    }
+
+    #[test]
+    fn test_has_comments() {
+        use StructuredFileType::{Json, Toml, Yaml};
+        let commented = |content: &str, file_type| {
+            // Only files that parse are checked
+            parse_structured_content(content, file_type).unwrap();
+            has_comments(content, file_type)
+        };
+        assert!(commented("a: 1 # note", Yaml));
+        assert!(commented("  # note\na: 1", Yaml));
+        assert!(commented("note: don't   # keep this comment", Yaml));
+        assert!(commented("- it's\n- b # note", Yaml));
+        assert!(!commented("a: b#c", Yaml));
+        assert!(!commented("a: \"# text\"", Yaml));
+        assert!(!commented("a: 'it''s # text'", Yaml));
+        assert!(!commented("a: |\n  # heading\n  text\n", Yaml));
+        assert!(commented("a = 1#note", Toml));
+        assert!(commented("a = \"it's\" # note", Toml));
+        assert!(!commented("a = \"\\\" # text\"", Toml));
+        assert!(!commented("a = '''\n# text\n'''", Toml));
+        assert!(!commented("{\"a\": \"#\"}", Json));
+
+        // `#` inside quoted strings and block scalars
+        assert!(!commented("a: \"x \\\" # y\"\nb: 'c # d'\n", Yaml));
+        assert!(!commented("a: \"first # line\n  second # line\"\n", Yaml));
+        assert!(!commented("- \"#\"\n- ['#', \"# x\"]\n", Yaml));
+        assert!(!commented(
+            "- |\n  # one\n  # two\n- >-\n   # three\n",
+            Yaml
+        ));
+        assert!(commented("- a: |\n    # text\n  b: 1 # note\n", Yaml));
+        assert!(commented("a: 'x' # note\nb: \"y\"\n", Yaml));
+        assert!(commented("a: x |\n  # note\n", Yaml));
+        assert!(!commented("a = \"# x\"\nb = 'c # d'\n", Toml));
+        assert!(!commented("a = \"\"\"\n# x \"\"\"\"\nb = 1\n", Toml));
+        assert!(commented("a = \"\"\"x\"\"\"\"\"\n# note\n", Toml));
+        assert!(commented("[t] # note\na = '#'\n", Toml));
+    }
+
+    #[test]
+    fn test_collect_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("conf")).unwrap();
+        fs::write(root.join("conf/a.yaml"), "a: 1").unwrap();
+        fs::write(root.join("conf/b.toml"), "b = 1").unwrap();
+        fs::write(root.join("conf/notes.md"), "# Notes").unwrap();
+
+        let dir = root.join("conf").to_string_lossy().to_string();
+        let glob = format!("{}/*.toml", dir);
+        let (files, errors) = collect_files(&[dir, glob]);
+        assert_eq!(
+            files,
+            vec![root.join("conf/a.yaml"), root.join("conf/b.toml")]
+        );
+        assert!(errors.is_empty());
+
+        let missing = root.join("missing.json").to_string_lossy().to_string();
+        let (files, errors) = collect_files(&[missing]);
+        assert!(files.is_empty());
+        assert_eq!(errors.len(), 1);
+    }
+}
@@ -0,0 +1,353 @@
+//! Command-line interface for Ferrite
+//!
+//! This module parses the process arguments into a [`Command`]. Files are
+//! opened as tabs (optionally at a `file.md:line:col` position), a folder is
+//! opened as a workspace, and `-` reads an untitled buffer from stdin.
+//! Subcommands run headless, without opening a window.
+//!
+//! ```text
+//! ferrite notes/todo.md:42:7
+//! ferrite ~/vault
+//! git config core.editor "ferrite --wait"
+//! cat draft.md | ferrite -
+//! ferrite export docs/ -o site/ --embed-images
+//! ferrite fmt --check 'config/**/*.yaml'
+//! ```
+
+pub mod export;
+pub mod fmt;
+
+use crate::error::{Error, Result};
+use crate::files::encoding::decode;
+use serde::{Deserialize, Serialize};
+use std::ffi::{OsStr, OsString};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Usage text printed by `--help`.
+pub const USAGE: &str = "\
+Usage: ferrite [OPTIONS] [PATH[:LINE[:COL]]... | FOLDER | -]
+       ferrite <COMMAND> [ARGS]
+
+Commands:
+  export             Convert markdown to HTML without opening a window
+  fmt                Format JSON, YAML and TOML files like Format Document
+  check              Report JSON, YAML and TOML syntax errors
+
+Arguments:
+  PATH[:LINE[:COL]]  Open a file in a tab, optionally at a line and column
+  FOLDER             Open a folder as a workspace
+  -                  Read an untitled document from stdin
+
+Options:
+  -w, --wait         Wait for the opened tabs to be closed before exiting
+  -n, --new-window   Start a separate window instead of opening in the running
+                     one, without restoring the last session
+  -h, --help         Print this help
+  -V, --version      Print the version
+
+Run 'ferrite <COMMAND> --help' for command options.";
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Parsed Arguments
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A file to open, with an optional 1-indexed cursor position.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileTarget {
+    /// Absolute path to the file (it may not exist yet)
+    #[serde(with = "crate::ipc::os_path")]
+    pub path: PathBuf,
+    /// Line to place the cursor on (1-indexed)
+    pub line: Option<usize>,
+    /// Column to place the cursor on (1-indexed, requires `line`)
+    pub column: Option<usize>,
+}
+
+impl FileTarget {
+    /// Parse a `path[:line[:col]]` argument.
+    ///
+    /// The suffix is only treated as a position when the argument as a whole
+    /// isn't an existing path, so files with colons in their names still open.
+    /// Arguments that aren't valid UTF-8 are always taken as a plain path.
+    pub fn parse(arg: impl AsRef<OsStr>) -> Self {
+        let arg = arg.as_ref();
+        match arg.to_str() {
+            Some(text) if !Path::new(arg).exists() => {
+                if let Some((rest, last)) = split_number(text) {
+                    if let Some((path, line)) = split_number(rest) {
+                        return Self::at(path, Some(line), Some(last));
+                    }
+                    return Self::at(rest, Some(last), None);
+                }
+            }
+            _ => {}
+        }
+        Self::at(arg, None, None)
+    }
+
+    fn at(path: impl AsRef<Path>, line: Option<usize>, column: Option<usize>) -> Self {
+        Self {
+            path: absolute(path.as_ref()),
+            line,
+            column,
+        }
+    }
+}
+
+/// Split a trailing `:<positive number>` off `arg`.
+fn split_number(arg: &str) -> Option<(&str, usize)> {
+    let (rest, number) = arg.rsplit_once(':')?;
+    let number = number.parse().ok().filter(|&n| n > 0)?;
+    (!rest.is_empty()).then_some((rest, number))
+}
+
+/// Make a path absolute against the current directory, without touching
+/// symlinks (unlike `canonicalize`, this works for files that don't exist).
+fn absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    std::env::current_dir()
+        .map(|dir| dir.join(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// What to open when the editor window starts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchOptions {
+    /// Files to open as tabs, in order (the last one becomes active)
+    pub files: Vec<FileTarget>,
+    /// Folder to open as a workspace
+    pub folder: Option<PathBuf>,
+    /// Read an untitled document from stdin
+    pub stdin: bool,
+    /// Exit once every tab opened from the command line has been closed
+    pub wait: bool,
+    /// Don't restore the previous session's tabs
+    pub new_window: bool,
+}
+
+/// A parsed command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Start the editor
+    Open(LaunchOptions),
+    /// Export markdown to HTML headlessly
+    Export(export::ExportArgs),
+    /// Format structured data files headlessly
+    Fmt(fmt::FmtArgs),
+    /// Validate structured data files headlessly
+    Check(fmt::CheckArgs),
+    /// Print the given usage text and exit
+    Help(&'static str),
+    /// Print the version and exit
+    Version,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Parsing
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Parse command-line arguments (without the program name).
+///
+/// Paths are kept as `OsString`s, so files whose names aren't valid UTF-8
+/// still open.
+///
+/// # Errors
+///
+/// Returns `Error::Application` for unknown options or more than one folder,
+/// and for subcommand arguments that aren't valid UTF-8.
+pub fn parse_args<I>(args: I) -> Result<Command>
+where
+    I: IntoIterator,
+    I::Item: Into<OsString>,
+{
+    let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+    if let Some((command, rest)) = args.split_first() {
+        match command.to_str() {
+            Some("export") => return export::parse(&utf8_args(rest)?),
+            Some("fmt") => return fmt::parse_fmt(&utf8_args(rest)?),
+            Some("check") => return fmt::parse_check(&utf8_args(rest)?),
+            _ => {}
+        }
+    }
+
+    let mut options = LaunchOptions::default();
+    let mut only_paths = false;
+
+    for arg in args {
+        let option = arg
+            .to_str()
+            .filter(|a| !only_paths && *a != "-" && a.starts_with('-'));
+        let Some(option) = option else {
+            if arg == "-" && !only_paths {
+                options.stdin = true;
+            } else if Path::new(&arg).is_dir() {
+                if options.folder.is_some() {
+                    return Err(Error::Application(
+                        "Only one folder can be opened as a workspace".to_string(),
+                    ));
+                }
+                options.folder = Some(absolute(Path::new(&arg)));
+            } else {
+                options.files.push(FileTarget::parse(&arg));
+            }
+            continue;
+        };
+
+        match option {
+            "--" => only_paths = true,
+            "-w" | "--wait" => options.wait = true,
+            "-n" | "--new-window" => options.new_window = true,
+            "-h" | "--help" => return Ok(Command::Help(USAGE)),
+            "-V" | "--version" => return Ok(Command::Version),
+            _ => {
+                return Err(Error::Application(format!(
+                    "Unknown option '{}'\n\n{}",
+                    option, USAGE
+                )))
+            }
+        }
+    }
+
+    Ok(Command::Open(options))
+}
+
+/// Convert subcommand arguments, which are parsed as text, to strings.
+fn utf8_args(args: &[OsString]) -> Result<Vec<String>> {
+    args.iter()
+        .map(|arg| {
+            arg.to_str().map(str::to_string).ok_or_else(|| {
+                Error::Application(format!(
+                    "Argument '{}' is not valid UTF-8",
+                    arg.to_string_lossy()
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Read an entire document from stdin, detecting its encoding.
+pub fn read_stdin() -> std::io::Result<String> {
+    let mut bytes = Vec::new();
+    std::io::stdin().read_to_end(&mut bytes)?;
+    Ok(decode(&bytes)?.0)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn parse(args: &[&str]) -> Result<Command> {
+        parse_args(args.iter().copied())
+    }
+
+    fn open(args: &[&str]) -> LaunchOptions {
+        match parse(args).unwrap() {
+            Command::Open(options) => options,
+            other => panic!("expected Open, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_no_arguments() {
+        let options = open(&[]);
+        assert_eq!(options, LaunchOptions::default());
+    }
+
+    #[test]
+    fn test_flags() {
+        let options = open(&["--wait", "-n", "-"]);
+        assert!(options.wait);
+        assert!(options.new_window);
+        assert!(options.stdin);
+
+        assert_eq!(parse(&["-h"]).unwrap(), Command::Help(USAGE));
+        assert_eq!(parse(&["--version"]).unwrap(), Command::Version);
+        assert!(parse(&["--bogus"]).is_err());
+    }
+
+    #[test]
+    fn test_file_positions() {
+        let target = FileTarget::parse("notes/todo.md:42:7");
+        assert!(target.path.is_absolute());
+        assert!(target.path.ends_with("notes/todo.md"));
+        assert_eq!((target.line, target.column), (Some(42), Some(7)));
+
+        let target = FileTarget::parse("todo.md:3");
+        assert!(target.path.ends_with("todo.md"));
+        assert_eq!((target.line, target.column), (Some(3), None));
+
+        let target = FileTarget::parse("todo.md:0");
+        assert!(target.path.ends_with("todo.md:0"));
+        assert_eq!(target.line, None);
+    }
+
+    #[test]
+    fn test_existing_file_with_colon_is_not_a_position() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("log:12");
+        std::fs::write(&path, "").unwrap();
+
+        let target = FileTarget::parse(&path);
+        assert_eq!(target.path, path);
+        assert_eq!(target.line, None);
+    }
+
+    #[test]
+    fn test_folder_becomes_workspace() {
+        let temp_dir = TempDir::new().unwrap();
+        let folder = temp_dir.path().to_str().unwrap();
+        let options = open(&[folder, "readme.md"]);
+        assert_eq!(options.folder.as_deref(), Some(temp_dir.path()));
+        assert_eq!(options.files.len(), 1);
+
+        assert!(parse(&[folder, folder]).is_err());
+    }
+
+    #[test]
+    fn test_export_subcommand() {
+        assert!(matches!(
+            parse(&["export", "README.md"]).unwrap(),
+            Command::Export(_)
+        ));
+        assert_eq!(
+            parse(&["export", "--help"]).unwrap(),
+            Command::Help(export::USAGE)
+        );
+        // Only the first argument selects a subcommand
+        assert_eq!(open(&["notes.md", "export"]).files.len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_paths() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let name = OsString::from_vec(b"caf\xe9.md:3".to_vec());
+        let options = match parse_args([OsString::from("--wait"), name.clone()]).unwrap() {
+            Command::Open(options) => options,
+            other => panic!("expected Open, got {:?}", other),
+        };
+        assert!(options.wait);
+        assert_eq!(options.files.len(), 1);
+        assert!(options.files[0].path.ends_with(&name));
+        assert_eq!(options.files[0].line, None);
+
+        assert!(parse_args([OsString::from("export"), name]).is_err());
+    }
+
+    #[test]
+    fn test_double_dash_ends_options() {
+        let options = open(&["--", "--wait", "-"]);
+        assert!(!options.wait);
+        assert!(!options.stdin);
+        assert_eq!(options.files.len(), 2);
+    }
+}
@@ -2,10 +2,13 @@
 //!
 //! This module handles user preferences and application settings,
 //! including serialization/deserialization to/from JSON and
-//! persistent storage to platform-specific directories.
+//! persistent storage to platform-specific directories, plus the
+//! hot-exit recovery store for unsaved tabs.
 
 mod persistence;
+mod recovery;
 mod settings;
 
 pub use persistence::*;
+pub use recovery::*;
 pub use settings::*;
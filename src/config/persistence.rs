@@ -500,6 +500,6 @@ mod tests {
 
         // Result depends on whether we have write permissions
         // Just verify it doesn't panic and returns a bool
-        assert!(result == true || result == false);
+        let _: bool = result;
     }
 }
@@ -0,0 +1,372 @@
+//! Hot-exit crash recovery for Ferrite
+//!
+//! This module persists the contents of tabs with unsaved changes
+//! (including untitled buffers) to `recovery.json` in the config directory.
+//! The app snapshots dirty tabs periodically; if it crashes or is killed,
+//! the next launch restores those buffers. A clean exit clears the store.
+//!
+//! Snapshots are serialized and written by [`RecoveryWriter`] on a background
+//! thread, so large buffers don't stall the UI.
+
+use crate::config::TabInfo;
+use crate::error::{Error, Result};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::thread::JoinHandle;
+use std::time::SystemTime;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Constants
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Recovery file name (stored next to config.json)
+const RECOVERY_FILE_NAME: &str = "recovery.json";
+
+/// Temporary file name used for atomic writes of the recovery file
+const RECOVERY_TEMP_NAME: &str = "recovery.json.tmp";
+
+/// How often dirty tabs are snapshotted, in seconds
+pub const RECOVERY_SNAPSHOT_INTERVAL_SECS: f64 = 5.0;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Recovery Data
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A snapshot of one tab with unsaved changes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecoveryEntry {
+    /// Session info for the tab (path is None for untitled buffers)
+    pub tab: TabInfo,
+    /// Full unsaved document content
+    pub content: String,
+    /// Modification time of the file on disk when the tab last loaded or
+    /// saved it; used to detect changes made while Ferrite wasn't running
+    #[serde(default)]
+    pub disk_mtime: Option<SystemTime>,
+}
+
+/// The on-disk format of the recovery file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct RecoveryFile {
+    entries: Vec<RecoveryEntry>,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Recovery Store
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Reads and writes the recovery file.
+///
+/// Writes are skipped when the snapshot hasn't changed since the last write,
+/// so calling [`RecoveryStore::write`] on a timer is cheap.
+#[derive(Debug)]
+pub struct RecoveryStore {
+    /// Full path to the recovery file
+    path: PathBuf,
+    /// Serialized form of the last snapshot written (empty after a clear)
+    last_written: Option<String>,
+}
+
+impl RecoveryStore {
+    /// Open the recovery store in the default config directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::ConfigDirNotFound` if the config directory cannot be determined.
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::at(super::get_config_dir()?.join(RECOVERY_FILE_NAME)))
+    }
+
+    /// Create a recovery store backed by a specific file.
+    pub fn at(path: PathBuf) -> Self {
+        Self {
+            path,
+            last_written: None,
+        }
+    }
+
+    /// Get the path of the recovery file.
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Load recovery entries left behind by a previous session.
+    ///
+    /// A missing or corrupted recovery file yields no entries.
+    pub fn load(&self) -> Vec<RecoveryEntry> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(_) => return Vec::new(),
+        };
+
+        match serde_json::from_str::<RecoveryFile>(&contents) {
+            Ok(file) => {
+                if !file.entries.is_empty() {
+                    info!(
+                        "Found {} recoverable tab(s) in {}",
+                        file.entries.len(),
+                        self.path.display()
+                    );
+                }
+                file.entries
+            }
+            Err(e) => {
+                warn!(
+                    "Ignoring corrupted recovery file {}: {}",
+                    self.path.display(),
+                    e
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    /// Write a snapshot of dirty tabs, replacing the previous one.
+    ///
+    /// An empty snapshot removes the recovery file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::FileWrite` if the recovery file cannot be written.
+    pub fn write(&mut self, entries: &[RecoveryEntry]) -> Result<()> {
+        if entries.is_empty() {
+            return self.clear();
+        }
+
+        let json = serde_json::to_string(&RecoveryFile {
+            entries: entries.to_vec(),
+        })?;
+        if self.last_written.as_deref() == Some(json.as_str()) {
+            return Ok(());
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| Error::FileWrite {
+                path: dir.to_path_buf(),
+                source: e,
+            })?;
+        }
+
+        // Write to a temporary file first so a crash mid-write can't
+        // corrupt the previous snapshot
+        let temp_path = self.path.with_file_name(RECOVERY_TEMP_NAME);
+        fs::write(&temp_path, &json).map_err(|e| Error::FileWrite {
+            path: temp_path.clone(),
+            source: e,
+        })?;
+        fs::rename(&temp_path, &self.path).map_err(|e| Error::FileWrite {
+            path: self.path.clone(),
+            source: e,
+        })?;
+
+        debug!("Wrote {} recovery entries", entries.len());
+        self.last_written = Some(json);
+        Ok(())
+    }
+
+    /// Remove the recovery file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Io` if the file exists but cannot be removed.
+    pub fn clear(&mut self) -> Result<()> {
+        if self.last_written.as_deref() == Some("") {
+            return Ok(());
+        }
+        match fs::remove_file(&self.path) {
+            Ok(()) => debug!("Cleared recovery file {}", self.path.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(Error::Io(e)),
+        }
+        self.last_written = Some(String::new());
+        Ok(())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Background Writer
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Message to the recovery writer thread.
+#[derive(Debug)]
+enum RecoveryCommand {
+    /// Replace the snapshot with these entries
+    Write(Vec<RecoveryEntry>),
+    /// Remove the recovery file
+    Clear,
+}
+
+/// Writes recovery snapshots to a [`RecoveryStore`] on a background thread.
+///
+/// When snapshots arrive faster than they can be written, only the latest
+/// one is written.
+#[derive(Debug)]
+pub struct RecoveryWriter {
+    sender: Sender<RecoveryCommand>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RecoveryWriter {
+    /// Start a writer thread that owns `store`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Io` if the thread can't be started.
+    pub fn spawn(mut store: RecoveryStore) -> Result<Self> {
+        let (sender, commands) = mpsc::channel();
+
+        let thread = std::thread::Builder::new()
+            .name("recovery-writer".to_string())
+            .spawn(move || {
+                while let Ok(command) = commands.recv() {
+                    // Skip snapshots already replaced by a newer one
+                    let command = commands.try_iter().last().unwrap_or(command);
+                    let result = match command {
+                        RecoveryCommand::Write(entries) => store.write(&entries),
+                        RecoveryCommand::Clear => store.clear(),
+                    };
+                    if let Err(e) = result {
+                        warn!(
+                            "Failed to update recovery snapshot {}: {}",
+                            store.path().display(),
+                            e
+                        );
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            sender,
+            thread: Some(thread),
+        })
+    }
+
+    /// Queue a snapshot of dirty tabs, replacing the previous one.
+    pub fn write(&self, entries: Vec<RecoveryEntry>) {
+        let _ = self.sender.send(RecoveryCommand::Write(entries));
+    }
+
+    /// Remove the recovery file after any queued snapshots, and wait for
+    /// the writer thread to finish.
+    pub fn clear(mut self) {
+        let _ = self.sender.send(RecoveryCommand::Clear);
+        // Closing the channel ends the thread once the queue is drained
+        drop(self.sender);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(path: Option<&str>, content: &str) -> RecoveryEntry {
+        RecoveryEntry {
+            tab: TabInfo {
+                path: path.map(PathBuf::from),
+                modified: true,
+                ..TabInfo::default()
+            },
+            content: content.to_string(),
+            disk_mtime: Some(SystemTime::UNIX_EPOCH),
+        }
+    }
+
+    #[test]
+    fn test_load_missing_file_is_empty() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = RecoveryStore::at(temp_dir.path().join(RECOVERY_FILE_NAME));
+        assert!(store.load().is_empty());
+    }
+
+    #[test]
+    fn test_write_and_load_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut store = RecoveryStore::at(temp_dir.path().join(RECOVERY_FILE_NAME));
+        let entries = vec![
+            entry(Some("/notes/a.md"), "# Draft"),
+            entry(None, "scratch"),
+        ];
+
+        store.write(&entries).unwrap();
+
+        let loaded = RecoveryStore::at(store.path().clone()).load();
+        assert_eq!(loaded, entries);
+    }
+
+    #[test]
+    fn test_write_creates_missing_config_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("ferrite").join(RECOVERY_FILE_NAME);
+        let mut store = RecoveryStore::at(path.clone());
+
+        store.write(&[entry(None, "text")]).unwrap();
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_empty_snapshot_clears_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut store = RecoveryStore::at(temp_dir.path().join(RECOVERY_FILE_NAME));
+        store.write(&[entry(None, "text")]).unwrap();
+        assert!(store.path().exists());
+
+        store.write(&[]).unwrap();
+        assert!(!store.path().exists());
+        assert!(store.load().is_empty());
+    }
+
+    #[test]
+    fn test_corrupted_file_is_ignored() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(RECOVERY_FILE_NAME);
+        fs::write(&path, "{ not json").unwrap();
+
+        assert!(RecoveryStore::at(path).load().is_empty());
+    }
+
+    #[test]
+    fn test_unchanged_snapshot_is_not_rewritten() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut store = RecoveryStore::at(temp_dir.path().join(RECOVERY_FILE_NAME));
+        let entries = vec![entry(None, "text")];
+        store.write(&entries).unwrap();
+
+        // Remove the file behind the store's back; an identical snapshot
+        // should be treated as already written
+        fs::remove_file(store.path()).unwrap();
+        store.write(&entries).unwrap();
+        assert!(!store.path().exists());
+
+        store.write(&[entry(None, "changed")]).unwrap();
+        assert!(store.path().exists());
+    }
+
+    #[test]
+    fn test_writer_clears_after_queued_snapshots() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(RECOVERY_FILE_NAME);
+        let writer = RecoveryWriter::spawn(RecoveryStore::at(path.clone())).unwrap();
+
+        writer.write(vec![entry(None, "first")]);
+        writer.write(vec![entry(None, "second")]);
+        let store = RecoveryStore::at(path.clone());
+        while store.load() != vec![entry(None, "second")] {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        writer.clear();
+        assert!(!path.exists());
+    }
+}
@@ -7,6 +7,7 @@
 // labels and settings that may not all be used yet but provide consistent API
 #![allow(dead_code)]
 
+use crate::files::encoding::FileFormat;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -29,7 +30,7 @@ pub enum Theme {
 // ─────────────────────────────────────────────────────────────────────────────
 
 /// Available font families for the editor.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum EditorFont {
     /// Inter - Modern, clean UI font (default)
@@ -68,9 +69,10 @@ impl EditorFont {
 
 /// Editor view modes for markdown editing.
 ///
-/// Two modes are available:
+/// Three modes are available:
 /// - `Raw`: Plain markdown text editing using a standard text editor
 /// - `Rendered`: WYSIWYG editing with rendered markdown elements
+/// - `Split`: The raw editor and a read-only rendered preview side by side
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum ViewMode {
@@ -79,14 +81,24 @@ pub enum ViewMode {
     Raw,
     /// WYSIWYG rendered editing (MarkdownEditor)
     Rendered,
+    /// Raw editor with a live rendered preview beside it
+    Split,
 }
 
 impl ViewMode {
-    /// Toggle between Raw and Rendered modes.
+    /// Toggle between Raw and Rendered modes (Split goes back to Raw).
     pub fn toggle(&self) -> Self {
         match self {
             ViewMode::Raw => ViewMode::Rendered,
-            ViewMode::Rendered => ViewMode::Raw,
+            ViewMode::Rendered | ViewMode::Split => ViewMode::Raw,
+        }
+    }
+
+    /// Toggle Split mode on or off (off goes back to Raw).
+    pub fn toggle_split(&self) -> Self {
+        match self {
+            ViewMode::Split => ViewMode::Raw,
+            ViewMode::Raw | ViewMode::Rendered => ViewMode::Split,
         }
     }
 
@@ -95,6 +107,7 @@ impl ViewMode {
         match self {
             ViewMode::Raw => "Raw",
             ViewMode::Rendered => "Rendered",
+            ViewMode::Split => "Split",
         }
     }
 
@@ -104,6 +117,7 @@ impl ViewMode {
         match self {
             ViewMode::Raw => "📝",
             ViewMode::Rendered => "👁",
+            ViewMode::Split => "◫",
         }
     }
 }
@@ -143,6 +157,48 @@ impl OutlinePanelSide {
     }
 }
 
+// ─────────────────────────────────────────────────────────────────────────────
+// Backup Configuration
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Where to keep a copy of the previous version of a file when saving.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupMode {
+    /// No backups (default)
+    #[default]
+    None,
+    /// A single `file.md~` next to the file
+    Sibling,
+    /// Numbered generations in a `.ferrite/backups/` folder
+    Folder,
+}
+
+impl BackupMode {
+    /// Get the display label for the mode.
+    pub fn label(&self) -> &'static str {
+        match self {
+            BackupMode::None => "None",
+            BackupMode::Sibling => "file.md~",
+            BackupMode::Folder => "Backups folder",
+        }
+    }
+
+    /// Get a description of the mode.
+    pub fn description(&self) -> &'static str {
+        match self {
+            BackupMode::None => "Don't keep backup copies",
+            BackupMode::Sibling => "Keep the previous version next to the file",
+            BackupMode::Folder => "Keep several versions in .ferrite/backups/",
+        }
+    }
+
+    /// Get all backup modes.
+    pub fn all() -> &'static [BackupMode] {
+        &[BackupMode::None, BackupMode::Sibling, BackupMode::Folder]
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Window Size Configuration
 // ─────────────────────────────────────────────────────────────────────────────
@@ -195,9 +251,12 @@ pub struct TabInfo {
     /// Scroll position
     #[serde(default)]
     pub scroll_offset: f32,
-    /// View mode for this tab (raw or rendered)
+    /// View mode for this tab (raw, rendered or split)
     #[serde(default)]
     pub view_mode: ViewMode,
+    /// On-disk encoding, BOM and line endings of the file
+    #[serde(default)]
+    pub format: FileFormat,
 }
 
 impl Default for TabInfo {
@@ -208,6 +267,7 @@ impl Default for TabInfo {
             cursor_position: (0, 0),
             scroll_offset: 0.0,
             view_mode: ViewMode::Raw, // New documents default to raw mode
+            format: FileFormat::default(),
         }
     }
 }
@@ -259,6 +319,18 @@ pub struct Settings {
     /// Auto-save interval in seconds (if auto_save is enabled)
     pub auto_save_interval_secs: u32,
 
+    /// Also auto-save when the window loses focus (if auto_save is enabled)
+    pub auto_save_on_focus_loss: bool,
+
+    /// Also auto-save when switching tabs (if auto_save is enabled)
+    pub auto_save_on_tab_switch: bool,
+
+    /// Backup copy to keep when a file is saved explicitly (not on auto-save)
+    pub backup_mode: BackupMode,
+
+    /// Number of backup generations kept in `BackupMode::Folder`
+    pub backup_generations: u32,
+
     // ─────────────────────────────────────────────────────────────────────────
     // Session & History
     // ─────────────────────────────────────────────────────────────────────────
@@ -274,13 +346,20 @@ pub struct Settings {
     /// Index of the active tab (for session restoration)
     pub active_tab_index: usize,
 
+    /// Recent find queries (most recent first)
+    pub find_history: Vec<String>,
+
+    /// Recent replacement texts (most recent first)
+    pub replace_history: Vec<String>,
+
     // ─────────────────────────────────────────────────────────────────────────
     // Window State
     // ─────────────────────────────────────────────────────────────────────────
     /// Window size and position
     pub window_size: WindowSize,
 
-    /// Split ratio for the editor/preview panes (0.0 to 1.0)
+    /// Width of the editor pane in split view, as a fraction of the
+    /// editor area (0.0 to 1.0)
     pub split_ratio: f32,
 
     // ─────────────────────────────────────────────────────────────────────────
@@ -327,6 +406,9 @@ pub struct Settings {
 
     /// Maximum number of recent workspaces to remember
     pub max_recent_workspaces: usize,
+
+    /// Lines shown before and after each search in files result
+    pub search_context_lines: usize,
 }
 
 impl Default for Settings {
@@ -345,12 +427,18 @@ impl Default for Settings {
             use_spaces: true,
             auto_save: false,
             auto_save_interval_secs: 60,
+            auto_save_on_focus_loss: true,
+            auto_save_on_tab_switch: true,
+            backup_mode: BackupMode::None,
+            backup_generations: 5,
 
             // Session & History
             recent_files: Vec::new(),
             max_recent_files: 10,
             last_open_tabs: Vec::new(),
             active_tab_index: 0,
+            find_history: Vec::new(),
+            replace_history: Vec::new(),
 
             // Window State
             window_size: WindowSize::default(),
@@ -375,6 +463,7 @@ impl Default for Settings {
             // Workspace Settings
             recent_workspaces: Vec::new(),
             max_recent_workspaces: 10,
+            search_context_lines: 1,
         }
     }
 }
@@ -406,6 +495,20 @@ impl Settings {
         self.recent_workspaces.truncate(self.max_recent_workspaces);
     }
 
+    /// Add a find query to the find history.
+    ///
+    /// Returns true if the history changed.
+    pub fn add_find_history(&mut self, term: &str) -> bool {
+        push_history(&mut self.find_history, term)
+    }
+
+    /// Add a replacement text to the replace history.
+    ///
+    /// Returns true if the history changed.
+    pub fn add_replace_history(&mut self, term: &str) -> bool {
+        push_history(&mut self.replace_history, term)
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Validation Constants and Sanitization
     // ─────────────────────────────────────────────────────────────────────────
@@ -426,6 +529,12 @@ impl Settings {
     pub const MIN_OUTLINE_WIDTH: f32 = 120.0;
     /// Maximum outline panel width.
     pub const MAX_OUTLINE_WIDTH: f32 = 500.0;
+    /// Maximum number of backup generations.
+    pub const MAX_BACKUP_GENERATIONS: u32 = 20;
+    /// Maximum number of remembered find and replace terms.
+    pub const MAX_FIND_HISTORY: usize = 20;
+    /// Maximum number of context lines around search in files results.
+    pub const MAX_SEARCH_CONTEXT_LINES: usize = 5;
 
     /// Sanitize settings by clamping values to valid ranges.
     ///
@@ -453,6 +562,11 @@ impl Settings {
         // Clamp split ratio
         self.split_ratio = self.split_ratio.clamp(0.0, 1.0);
 
+        // Clamp backup generations
+        self.backup_generations = self
+            .backup_generations
+            .clamp(1, Self::MAX_BACKUP_GENERATIONS);
+
         // Ensure max_recent_files is reasonable
         if self.max_recent_files == 0 {
             self.max_recent_files = 10;
@@ -463,6 +577,15 @@ impl Settings {
         // Trim recent files to max
         self.recent_files.truncate(self.max_recent_files);
 
+        // Trim find/replace history
+        self.find_history.truncate(Self::MAX_FIND_HISTORY);
+        self.replace_history.truncate(Self::MAX_FIND_HISTORY);
+
+        // Clamp search context
+        self.search_context_lines = self
+            .search_context_lines
+            .min(Self::MAX_SEARCH_CONTEXT_LINES);
+
         // Ensure auto-save interval is reasonable
         if self.auto_save && self.auto_save_interval_secs < 5 {
             self.auto_save_interval_secs = 5;
@@ -489,6 +612,18 @@ impl Settings {
     }
 }
 
+/// Move `term` to the front of a history list, trimmed to
+/// [`Settings::MAX_FIND_HISTORY`]. Empty terms are ignored.
+fn push_history(history: &mut Vec<String>, term: &str) -> bool {
+    if term.is_empty() || history.first().is_some_and(|first| first == term) {
+        return false;
+    }
+    history.retain(|t| t != term);
+    history.insert(0, term.to_string());
+    history.truncate(Settings::MAX_FIND_HISTORY);
+    true
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Tests
 // ─────────────────────────────────────────────────────────────────────────────
@@ -510,12 +645,19 @@ mod tests {
         assert_eq!(settings.window_size.width, 1200.0);
         assert_eq!(settings.window_size.height, 800.0);
         assert_eq!(settings.split_ratio, 0.5);
+        assert!(!settings.auto_save);
+        assert!(settings.auto_save_on_focus_loss);
+        assert!(settings.auto_save_on_tab_switch);
+        assert_eq!(settings.backup_mode, BackupMode::None);
+        assert_eq!(settings.backup_generations, 5);
     }
 
     #[test]
     fn test_add_recent_file() {
-        let mut settings = Settings::default();
-        settings.max_recent_files = 3;
+        let mut settings = Settings {
+            max_recent_files: 3,
+            ..Default::default()
+        };
 
         settings.add_recent_file(PathBuf::from("/file1.md"));
         settings.add_recent_file(PathBuf::from("/file2.md"));
@@ -537,6 +679,30 @@ mod tests {
         assert!(!settings.recent_files.contains(&PathBuf::from("/file2.md")));
     }
 
+    #[test]
+    fn test_find_history() {
+        let mut settings = Settings::default();
+
+        assert!(settings.add_find_history("foo"));
+        assert!(settings.add_find_history("bar"));
+        assert!(!settings.add_find_history("bar"));
+        assert!(!settings.add_find_history(""));
+        assert!(settings.add_find_history("foo"));
+        assert_eq!(settings.find_history, vec!["foo", "bar"]);
+
+        for i in 0..30 {
+            settings.add_replace_history(&format!("r{}", i));
+        }
+        assert_eq!(settings.replace_history.len(), Settings::MAX_FIND_HISTORY);
+        assert_eq!(settings.replace_history[0], "r29");
+
+        // Multi-line terms survive a round trip
+        settings.add_find_history("line one\nline two");
+        let json = serde_json::to_string(&settings).unwrap();
+        let loaded = Settings::from_json_sanitized(&json).unwrap();
+        assert_eq!(loaded.find_history[0], "line one\nline two");
+    }
+
     #[test]
     fn test_theme_serialization() {
         assert_eq!(serde_json::to_string(&Theme::Light).unwrap(), "\"light\"");
@@ -567,12 +733,24 @@ mod tests {
             serde_json::to_string(&ViewMode::Rendered).unwrap(),
             "\"rendered\""
         );
+        assert_eq!(
+            serde_json::to_string(&ViewMode::Split).unwrap(),
+            "\"split\""
+        );
     }
 
     #[test]
     fn test_view_mode_toggle() {
         assert_eq!(ViewMode::Raw.toggle(), ViewMode::Rendered);
         assert_eq!(ViewMode::Rendered.toggle(), ViewMode::Raw);
+        assert_eq!(ViewMode::Split.toggle(), ViewMode::Raw);
+    }
+
+    #[test]
+    fn test_view_mode_toggle_split() {
+        assert_eq!(ViewMode::Raw.toggle_split(), ViewMode::Split);
+        assert_eq!(ViewMode::Rendered.toggle_split(), ViewMode::Split);
+        assert_eq!(ViewMode::Split.toggle_split(), ViewMode::Raw);
     }
 
     #[test]
@@ -639,6 +817,7 @@ mod tests {
             cursor_position: (10, 5),
             scroll_offset: 100.0,
             view_mode: ViewMode::Rendered,
+            format: FileFormat::default(),
         };
 
         let json = serde_json::to_string(&tab).unwrap();
@@ -665,8 +844,10 @@ mod tests {
     // ─────────────────────────────────────────────────────────────────────────
     #[test]
     fn test_sanitize_font_size() {
-        let mut settings = Settings::default();
-        settings.font_size = 4.0;
+        let mut settings = Settings {
+            font_size: 4.0,
+            ..Default::default()
+        };
         settings.sanitize();
         assert_eq!(settings.font_size, Settings::MIN_FONT_SIZE);
 
@@ -677,8 +858,10 @@ mod tests {
 
     #[test]
     fn test_sanitize_tab_size() {
-        let mut settings = Settings::default();
-        settings.tab_size = 0;
+        let mut settings = Settings {
+            tab_size: 0,
+            ..Default::default()
+        };
         settings.sanitize();
         assert_eq!(settings.tab_size, Settings::MIN_TAB_SIZE);
 
@@ -689,8 +872,10 @@ mod tests {
 
     #[test]
     fn test_sanitize_split_ratio() {
-        let mut settings = Settings::default();
-        settings.split_ratio = -0.5;
+        let mut settings = Settings {
+            split_ratio: -0.5,
+            ..Default::default()
+        };
         settings.sanitize();
         assert_eq!(settings.split_ratio, 0.0);
 
@@ -700,23 +885,56 @@ mod tests {
     }
 
     #[test]
-    fn test_sanitize_recent_files() {
+    fn test_sanitize_backup_generations() {
+        let mut settings = Settings {
+            backup_generations: 0,
+            ..Default::default()
+        };
+        settings.sanitize();
+        assert_eq!(settings.backup_generations, 1);
+
+        settings.backup_generations = 500;
+        settings.sanitize();
+        assert_eq!(
+            settings.backup_generations,
+            Settings::MAX_BACKUP_GENERATIONS
+        );
+    }
+
+    #[test]
+    fn test_sanitize_search_context_lines() {
         let mut settings = Settings::default();
-        settings.max_recent_files = 2;
-        settings.recent_files = vec![
-            PathBuf::from("/file1.md"),
-            PathBuf::from("/file2.md"),
-            PathBuf::from("/file3.md"),
-        ];
+        assert_eq!(settings.search_context_lines, 1);
+        settings.search_context_lines = 50;
+        settings.sanitize();
+        assert_eq!(
+            settings.search_context_lines,
+            Settings::MAX_SEARCH_CONTEXT_LINES
+        );
+    }
+
+    #[test]
+    fn test_sanitize_recent_files() {
+        let mut settings = Settings {
+            max_recent_files: 2,
+            recent_files: vec![
+                PathBuf::from("/file1.md"),
+                PathBuf::from("/file2.md"),
+                PathBuf::from("/file3.md"),
+            ],
+            ..Default::default()
+        };
         settings.sanitize();
         assert_eq!(settings.recent_files.len(), 2);
     }
 
     #[test]
     fn test_sanitize_active_tab_index() {
-        let mut settings = Settings::default();
-        settings.last_open_tabs = vec![TabInfo::default()];
-        settings.active_tab_index = 5;
+        let mut settings = Settings {
+            last_open_tabs: vec![TabInfo::default()],
+            active_tab_index: 5,
+            ..Default::default()
+        };
         settings.sanitize();
         assert_eq!(settings.active_tab_index, 0);
     }
@@ -3,16 +3,29 @@
 //! This module provides comprehensive search and replace capabilities including:
 //! - Real-time incremental search with match highlighting
 //! - Case-sensitive, whole word, and regex matching modes
+//! - Multi-line queries and searching within the selection
+//! - Incremental matching, so large files don't block the UI while typing
+//! - Search and replace history (Up/Down in the query fields)
 //! - Keyboard navigation (F3/Shift+F3) between matches
-//! - Replace and Replace All functionality
+//! - Replace and Replace All functionality, with regex capture groups
+//!   (`$1`, `${name}`), escape sequences (`\n`, `\t`) and case preservation
 //! - Integration with the undo/redo system
 
 // Allow dead code - some methods are for future use or completeness
 #![allow(dead_code)]
 
-use eframe::egui::{self, Color32, Key, RichText, Ui, Vec2};
+use eframe::egui::{self, Color32, Key, KeyboardShortcut, Modifiers, RichText, Ui, Vec2};
 use log::debug;
-use regex::Regex;
+use regex::{Regex, RegexBuilder};
+
+/// Most matches listed in the replace preview
+const MAX_REPLACE_PREVIEWS: usize = 100;
+
+/// Bytes of text searched per call to [`FindState::search_step`] from the UI
+pub const BYTES_PER_STEP: usize = 1 << 20;
+
+/// Most matches found per call to [`FindState::search_step`]
+const MATCHES_PER_STEP: usize = 20_000;
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Find State
@@ -34,6 +47,13 @@ pub struct FindState {
     pub whole_word: bool,
     /// Whether to use regex matching
     pub use_regex: bool,
+    /// Whether replacements follow the case of the text they replace
+    pub preserve_case: bool,
+    /// Whether to search only within `scope`
+    pub in_selection: bool,
+    /// Byte range searched when `in_selection` is on (the selection when the
+    /// option was turned on); `None` searches the whole text
+    pub scope: Option<(usize, usize)>,
     /// Current match index (0-indexed)
     pub current_match: usize,
     /// All matches as (start, end) byte positions
@@ -41,11 +61,26 @@ pub struct FindState {
     /// Whether replace mode is active (vs. find-only)
     pub is_replace_mode: bool,
     /// Cached regex (to avoid recompilation)
-    #[allow(dead_code)]
     cached_regex: Option<Regex>,
-    /// Last search term used to build cache
-    #[allow(dead_code)]
+    /// Last pattern used to build cache
     last_search_term: String,
+    /// Where an incremental search continues, if one is in progress
+    scan_pos: Option<usize>,
+    /// Length of the text being searched, to notice edits mid-search
+    scan_len: usize,
+}
+
+/// A match and the text that would replace it, for the replace preview.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplacePreview {
+    /// Index of the match in `FindState::matches`
+    pub index: usize,
+    /// Line of the match (1-indexed)
+    pub line: usize,
+    /// The matched text
+    pub matched: String,
+    /// The text it would be replaced with
+    pub replacement: String,
 }
 
 impl FindState {
@@ -59,98 +94,187 @@ impl FindState {
     /// Updates `self.matches` with the positions of all matches.
     /// Returns the number of matches found.
     pub fn find_matches(&mut self, text: &str) -> usize {
-        self.matches.clear();
-
-        if self.search_term.is_empty() {
-            return 0;
-        }
+        self.start_search(text);
+        while !self.search_step(text, usize::MAX) {}
+        self.matches.len()
+    }
 
-        if self.use_regex {
-            self.find_regex_matches(text);
-        } else {
-            self.find_literal_matches(text);
-        }
+    /// Start an incremental search, clearing the previous matches.
+    ///
+    /// Call [`search_step`](Self::search_step) until it returns true.
+    pub fn start_search(&mut self, text: &str) {
+        self.matches.clear();
+        self.scan_pos = None;
+        self.scan_len = text.len();
 
-        // Clamp current_match to valid range
-        if !self.matches.is_empty() && self.current_match >= self.matches.len() {
-            self.current_match = 0;
+        if self.search_term.is_empty() || !self.compile_regex() {
+            return;
         }
 
-        self.matches.len()
+        let start = match self.active_scope() {
+            Some((start, _)) if start <= text.len() && text.is_char_boundary(start) => start,
+            _ => 0,
+        };
+        self.scan_pos = Some(start);
     }
 
-    /// Find literal (non-regex) matches.
-    fn find_literal_matches(&mut self, text: &str) {
-        let search_text = if self.case_sensitive {
-            text.to_string()
-        } else {
-            text.to_lowercase()
+    /// Search roughly `max_bytes` more of the text, up to the end of a line.
+    ///
+    /// Queries that can match across lines search the rest of the text at
+    /// once. Either way a step stops after a bounded number of matches.
+    /// Returns true once the search is complete. If the text changed length
+    /// since the search started, the search starts over.
+    pub fn search_step(&mut self, text: &str, max_bytes: usize) -> bool {
+        let Some(mut pos) = self.scan_pos else {
+            return true;
         };
-
-        let search_term = if self.case_sensitive {
-            self.search_term.clone()
+        if text.len() != self.scan_len {
+            self.start_search(text);
+            return self.search_step(text, max_bytes);
+        }
+        let Some(re) = self.cached_regex.clone() else {
+            self.scan_pos = None;
+            return true;
+        };
+        // The end of the scope acts as the end of the text
+        let end_limit = match self.active_scope() {
+            Some((_, end)) if end <= text.len() && text.is_char_boundary(end) => end,
+            _ => text.len(),
+        };
+        // Single-line matches can't cross the line break a chunk ends after
+        let target = pos.saturating_add(max_bytes);
+        let chunk_end = if self.may_span_lines() || target >= end_limit {
+            end_limit
         } else {
-            self.search_term.to_lowercase()
+            text.as_bytes()[target..end_limit]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(end_limit, |i| target + i + 1)
         };
+        let last_chunk = chunk_end == end_limit;
+        let haystack = &text[..chunk_end];
+
+        let mut found = 0;
+        let mut complete = false;
+        while found < MATCHES_PER_STEP {
+            let Some(m) = re.find_at(haystack, pos) else {
+                complete = last_chunk;
+                pos = chunk_end;
+                break;
+            };
+            // An empty match at the cut (`$`, `\b`) is left to the next chunk,
+            // which sees the text after it
+            if m.start() == chunk_end && !last_chunk {
+                pos = chunk_end;
+                break;
+            }
+
+            // Like `find_iter`, skip an empty match right after the previous one
+            let after_previous = self.matches.last().is_some_and(|&(_, e)| e == m.start());
+            let accepted = !(m.is_empty() && after_previous)
+                && (self.use_regex || !self.whole_word || is_whole_word(text, m.start(), m.end()));
 
-        let mut start = 0;
-        while let Some(pos) = search_text[start..].find(&search_term) {
-            let match_start = start + pos;
-            let match_end = match_start + self.search_term.len();
-
-            // Check whole word boundary if enabled
-            if self.whole_word {
-                let is_start_boundary = match_start == 0
-                    || !text[..match_start]
-                        .chars()
-                        .last()
-                        .map(|c| c.is_alphanumeric() || c == '_')
-                        .unwrap_or(false);
-
-                let is_end_boundary = match_end >= text.len()
-                    || !text[match_end..]
-                        .chars()
-                        .next()
-                        .map(|c| c.is_alphanumeric() || c == '_')
-                        .unwrap_or(false);
-
-                if !is_start_boundary || !is_end_boundary {
-                    start = match_start + 1;
-                    continue;
+            if accepted {
+                self.matches.push((m.start(), m.end()));
+                found += 1;
+            }
+            pos = if accepted && !m.is_empty() {
+                m.end()
+            } else {
+                // Retry one character later
+                match haystack[m.start()..].chars().next() {
+                    Some(c) => m.start() + c.len_utf8(),
+                    None => {
+                        complete = last_chunk;
+                        pos = chunk_end;
+                        break;
+                    }
                 }
+            };
+        }
+
+        if complete {
+            self.scan_pos = None;
+            // Clamp current_match to valid range
+            if !self.matches.is_empty() && self.current_match >= self.matches.len() {
+                self.current_match = 0;
             }
+        } else {
+            self.scan_pos = Some(pos);
+        }
+        complete
+    }
+
+    /// Check if an incremental search is still in progress.
+    pub fn is_searching(&self) -> bool {
+        self.scan_pos.is_some()
+    }
 
-            self.matches.push((match_start, match_end));
-            start = match_end;
+    /// Keep the search scope covering the same text after a replacement
+    /// inside it changed the text's length.
+    pub fn adjust_scope(&mut self, old_len: usize, new_len: usize) {
+        if let Some((_, end)) = &mut self.scope {
+            *end = (*end + new_len).saturating_sub(old_len);
         }
     }
 
-    /// Find regex matches.
-    fn find_regex_matches(&mut self, text: &str) {
-        let pattern = if self.case_sensitive {
-            self.search_term.clone()
-        } else {
-            format!("(?i){}", self.search_term)
+    /// Check if the query could match a line break, so the text can't be
+    /// searched in line-aligned chunks.
+    fn may_span_lines(&self) -> bool {
+        // Escapes and classes that can match `\n`; anything else can't
+        const LINE_BREAKING: &[&str] = &[
+            "\\n", "\\r", "\\s", "\\W", "\\D", "\\p", "\\P", "\\x", "\\u", "\\U", "[^", "[[:",
+        ];
+        let term = &self.search_term;
+        // A flag group like `(?s)` or `(?is:...)` lets `.` match `\n`
+        let dot_all = || {
+            term.match_indices("(?").any(|(i, _)| {
+                term[i + 2..]
+                    .chars()
+                    .take_while(|c| c.is_ascii_alphabetic() || *c == '-')
+                    .any(|c| c == 's')
+            })
         };
+        term.contains('\n')
+            || (self.use_regex && (LINE_BREAKING.iter().any(|s| term.contains(s)) || dot_all()))
+    }
+
+    /// The scope searched, if limited to the selection.
+    fn active_scope(&self) -> Option<(usize, usize)> {
+        self.scope.filter(|_| self.in_selection)
+    }
 
-        // Apply whole word boundaries if needed
-        let pattern = if self.whole_word {
-            format!(r"\b{}\b", pattern)
+    /// Compile (or reuse) the regex for the current term and options.
+    ///
+    /// Literal terms are escaped. `^` and `$` match at line boundaries; a
+    /// `(?s)` flag in a regex lets `.` match newlines. Returns false if the
+    /// regex is invalid.
+    fn compile_regex(&mut self) -> bool {
+        let pattern = if !self.use_regex {
+            regex::escape(&self.search_term)
+        } else if self.whole_word {
+            format!(r"\b(?:{})\b", self.search_term)
         } else {
-            pattern
+            self.search_term.clone()
         };
-
-        match Regex::new(&pattern) {
-            Ok(re) => {
-                for m in re.find_iter(text) {
-                    self.matches.push((m.start(), m.end()));
+        let key = format!("{}\0{}", self.case_sensitive, pattern);
+
+        if self.cached_regex.is_none() || self.last_search_term != key {
+            let built = RegexBuilder::new(&pattern)
+                .case_insensitive(!self.case_sensitive)
+                .multi_line(true)
+                .build();
+            self.cached_regex = match built {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    debug!("Invalid regex pattern '{}': {}", self.search_term, e);
+                    // Invalid regex - return no matches
+                    None
                 }
-            }
-            Err(e) => {
-                debug!("Invalid regex pattern '{}': {}", self.search_term, e);
-                // Invalid regex - return no matches
-            }
+            };
+            self.last_search_term = key;
         }
+        self.cached_regex.is_some()
     }
 
     /// Move to the next match.
@@ -186,15 +310,81 @@ impl FindState {
         self.matches.get(self.current_match).copied()
     }
 
+    /// Get the text that replaces the match at `start..end` of `text`.
+    ///
+    /// In regex mode, `$1`/`${name}` insert capture groups (`$$` is a literal
+    /// `$`) and `\n`, `\t`, `\r` and `\\` are unescaped. With `preserve_case`,
+    /// the result follows the case of the matched text.
+    pub fn replacement_for(&self, text: &str, start: usize, end: usize) -> String {
+        let replacement = match self.cached_regex.as_ref().filter(|_| self.use_regex) {
+            Some(re) => {
+                let template = unescape_replacement(&self.replace_term);
+                // `captures_at` sees the whole text, so anchors and `\b` behave
+                // as they did when searching
+                let caps = Some(start)
+                    .filter(|&start| start <= text.len())
+                    .and_then(|start| re.captures_at(text, start));
+                match caps {
+                    Some(caps) if caps.get(0).is_some_and(|m| m.start() == start) => {
+                        let mut expanded = String::new();
+                        caps.expand(&template, &mut expanded);
+                        expanded
+                    }
+                    _ => template,
+                }
+            }
+            None => self.replace_term.clone(),
+        };
+
+        if self.preserve_case {
+            match_case(text.get(start..end).unwrap_or_default(), &replacement)
+        } else {
+            replacement
+        }
+    }
+
+    /// Preview the replacement of the matches around the current one.
+    pub fn replace_previews(&self, text: &str) -> Vec<ReplacePreview> {
+        let limit = MAX_REPLACE_PREVIEWS;
+        let first = self
+            .current_match
+            .saturating_sub(limit / 2)
+            .min(self.matches.len().saturating_sub(limit));
+
+        // Count lines incrementally from the first previewed match
+        let mut line = 1;
+        let mut counted_to = 0;
+        self.matches
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(limit)
+            .filter(|&(_, &(start, end))| is_valid_match(text, start, end))
+            .map(|(index, &(start, end))| {
+                line += text[counted_to..start].matches('\n').count();
+                counted_to = start;
+                ReplacePreview {
+                    index,
+                    line,
+                    matched: text[start..end].to_string(),
+                    replacement: self.replacement_for(text, start, end),
+                }
+            })
+            .collect()
+    }
+
     /// Replace the current match in the text.
     ///
     /// Returns the new text if a replacement was made, or None if no current match.
     pub fn replace_current(&self, text: &str) -> Option<String> {
         let (start, end) = self.current_match_position()?;
+        if !is_valid_match(text, start, end) {
+            return None;
+        }
 
         let mut new_text = String::with_capacity(text.len());
         new_text.push_str(&text[..start]);
-        new_text.push_str(&self.replace_term);
+        new_text.push_str(&self.replacement_for(text, start, end));
         new_text.push_str(&text[end..]);
 
         Some(new_text)
@@ -212,8 +402,12 @@ impl FindState {
         let mut last_end = 0;
 
         for &(start, end) in &self.matches {
+            // Skip matches that don't fit the text (left over from an edit)
+            if start < last_end || !is_valid_match(text, start, end) {
+                continue;
+            }
             new_text.push_str(&text[last_end..start]);
-            new_text.push_str(&self.replace_term);
+            new_text.push_str(&self.replacement_for(text, start, end));
             last_end = end;
         }
 
@@ -238,6 +432,66 @@ impl FindState {
     }
 }
 
+/// Check that `start..end` is an in-bounds range of `text` on char boundaries.
+fn is_valid_match(text: &str, start: usize, end: usize) -> bool {
+    start <= end && text.get(start..end).is_some()
+}
+
+/// Check that the text at `start..end` isn't part of a longer word.
+fn is_whole_word(text: &str, start: usize, end: usize) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    !text[..start].chars().next_back().is_some_and(is_word)
+        && !text[end..].chars().next().is_some_and(is_word)
+}
+
+/// Turn `\n`, `\t`, `\r` and `\\` in a regex replacement into the characters
+/// they name. Other backslashes are kept as typed.
+pub fn unescape_replacement(replacement: &str) -> String {
+    let mut result = String::with_capacity(replacement.len());
+    let mut chars = replacement.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('\\') => result.push('\\'),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Give `replacement` the case pattern of `matched`: all uppercase, all
+/// lowercase, or capitalized. Mixed case (`camelCase`) leaves it unchanged.
+fn match_case(matched: &str, replacement: &str) -> String {
+    let letters: Vec<char> = matched.chars().filter(|c| c.is_alphabetic()).collect();
+    let Some(&first) = letters.first() else {
+        return replacement.to_string();
+    };
+
+    if letters.len() > 1 && letters.iter().all(|c| !c.is_lowercase()) {
+        replacement.to_uppercase()
+    } else if letters.iter().all(|c| !c.is_uppercase()) {
+        replacement.to_lowercase()
+    } else if first.is_uppercase() && letters[1..].iter().all(|c| !c.is_uppercase()) {
+        let mut chars = replacement.chars();
+        chars
+            .next()
+            .map(|c| c.to_uppercase().chain(chars).collect())
+            .unwrap_or_default()
+    } else {
+        replacement.to_string()
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Find/Replace Panel
 // ─────────────────────────────────────────────────────────────────────────────
@@ -257,20 +511,66 @@ pub struct FindReplacePanelOutput {
     pub replace_all_requested: bool,
     /// Whether to close the panel
     pub close_requested: bool,
+    /// Match selected in the replace preview
+    pub selected_match: Option<usize>,
 }
 
 /// A floating find/replace panel for the editor.
 ///
 /// This panel provides a modern search interface with:
-/// - Search input with real-time incremental search
+/// - Multi-line search input with real-time incremental search
 /// - Replace input (in replace mode)
+/// - Search and replace history (Up/Down)
 /// - Match counter showing current/total
 /// - Navigation buttons (Next/Previous)
 /// - Replace/Replace All buttons
-/// - Option toggles (Case, Word, Regex)
+/// - Option toggles (Case, Word, Regex, In Selection, Preserve Case)
+/// - Preview of what each match will be replaced with
 pub struct FindReplacePanel {
     /// Whether the search input should be focused
     focus_search: bool,
+    /// Position in the find history
+    search_history: HistoryCursor,
+    /// Position in the replace history
+    replace_history: HistoryCursor,
+}
+
+/// Position while stepping through a history list with Up/Down.
+#[derive(Debug, Default)]
+struct HistoryCursor {
+    /// Entry shown, or None while showing the user's own text
+    index: Option<usize>,
+    /// What the user had typed before stepping into the history
+    draft: String,
+}
+
+impl HistoryCursor {
+    /// Show an older (`older`) or newer entry in `term`.
+    ///
+    /// Stepping past the newest entry restores the user's own text.
+    /// Returns true if `term` changed.
+    fn step(&mut self, history: &[String], older: bool, term: &mut String) -> bool {
+        let index = match (self.index, older) {
+            (None, true) if !history.is_empty() => {
+                self.draft = term.clone();
+                Some(0)
+            }
+            (Some(i), true) => Some((i + 1).min(history.len().saturating_sub(1))),
+            (Some(0), false) => None,
+            (Some(i), false) => Some(i - 1),
+            (None, _) => return false,
+        };
+        self.index = index;
+        let text = match index {
+            Some(i) => history.get(i).cloned().unwrap_or_default(),
+            None => std::mem::take(&mut self.draft),
+        };
+        if *term == text {
+            return false;
+        }
+        *term = text;
+        true
+    }
 }
 
 impl Default for FindReplacePanel {
@@ -282,7 +582,11 @@ impl Default for FindReplacePanel {
 impl FindReplacePanel {
     /// Create a new find/replace panel.
     pub fn new() -> Self {
-        Self { focus_search: true }
+        Self {
+            focus_search: true,
+            search_history: HistoryCursor::default(),
+            replace_history: HistoryCursor::default(),
+        }
     }
 
     /// Request focus on the search input.
@@ -292,11 +596,17 @@ impl FindReplacePanel {
 
     /// Show the find/replace panel.
     ///
+    /// `previews` are listed below the options in replace mode (see
+    /// [`FindState::replace_previews`]). The histories are most recent first.
+    ///
     /// Returns output indicating any actions to perform.
     pub fn show(
         &mut self,
         ctx: &egui::Context,
         find_state: &mut FindState,
+        previews: &[ReplacePreview],
+        search_history: &[String],
+        replace_history: &[String],
         is_dark: bool,
     ) -> FindReplacePanelOutput {
         let mut output = FindReplacePanelOutput::default();
@@ -360,14 +670,55 @@ impl FindReplacePanel {
                 let input = ui.input(|i| {
                     (
                         i.key_pressed(Key::Escape),
-                        i.key_pressed(Key::Enter),
                         i.key_pressed(Key::F3) && !i.modifiers.shift,
                         i.key_pressed(Key::F3) && i.modifiers.shift,
                         i.modifiers.ctrl && i.key_pressed(Key::H),
                     )
                 });
 
-                let (escape, enter, f3_next, f3_prev, ctrl_h) = input;
+                let (escape, f3_next, f3_prev, ctrl_h) = input;
+
+                // Keys for the query fields are taken before the fields see
+                // them: Enter searches (Ctrl+Enter adds a line) and Up/Down
+                // step through the history of single-line terms
+                let search_id = egui::Id::new("find_replace_search_input");
+                let replace_id = egui::Id::new("find_replace_replace_input");
+                let (search_focused, replace_focused) =
+                    ui.memory(|m| (m.has_focus(search_id), m.has_focus(replace_id)));
+                let (enter, shift_enter) = if search_focused || replace_focused {
+                    ui.input_mut(|i| {
+                        (
+                            i.consume_key(Modifiers::NONE, Key::Enter),
+                            i.consume_key(Modifiers::SHIFT, Key::Enter),
+                        )
+                    })
+                } else {
+                    (false, false)
+                };
+                let (history_cursor, history, term) = if search_focused {
+                    (
+                        &mut self.search_history,
+                        search_history,
+                        &mut find_state.search_term,
+                    )
+                } else {
+                    (
+                        &mut self.replace_history,
+                        replace_history,
+                        &mut find_state.replace_term,
+                    )
+                };
+                if (search_focused || replace_focused) && !term.contains('\n') {
+                    let (up, down) = ui.input_mut(|i| {
+                        (
+                            i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                            i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                        )
+                    });
+                    if (up || down) && history_cursor.step(history, up, term) && search_focused {
+                        output.search_changed = true;
+                    }
+                }
 
                 if escape {
                     output.close_requested = true;
@@ -375,7 +726,7 @@ impl FindReplacePanel {
                 if enter || f3_next {
                     output.next_requested = true;
                 }
-                if f3_prev {
+                if shift_enter || f3_prev {
                     output.prev_requested = true;
                 }
                 if ctrl_h {
@@ -436,11 +787,12 @@ impl FindReplacePanel {
                 ui.horizontal(|ui| {
                     ui.label(RichText::new("🔍").size(14.0));
 
-                    let search_id = egui::Id::new("find_replace_search_input");
-                    let search_response = ui.add_sized(
-                        Vec2::new(280.0, 24.0),
-                        egui::TextEdit::singleline(&mut find_state.search_term)
+                    let search_response = ui.add(
+                        egui::TextEdit::multiline(&mut find_state.search_term)
                             .id(search_id)
+                            .desired_width(280.0)
+                            .desired_rows(1)
+                            .return_key(KeyboardShortcut::new(Modifiers::COMMAND, Key::Enter))
                             .hint_text("Search...")
                             .font(egui::FontId::proportional(13.0)),
                     );
@@ -453,20 +805,25 @@ impl FindReplacePanel {
 
                     if search_response.changed() {
                         output.search_changed = true;
+                        self.search_history.index = None;
                     }
 
-                    // Match counter
+                    // Match counter ("+" while a search is still running)
+                    let more = if find_state.is_searching() { "+" } else { "" };
                     let match_text = if find_state.matches.is_empty() {
-                        if find_state.search_term.is_empty() {
+                        if find_state.is_searching() {
+                            "Searching...".to_string()
+                        } else if find_state.search_term.is_empty() {
                             String::new()
                         } else {
                             "No matches".to_string()
                         }
                     } else {
                         format!(
-                            "{} of {}",
+                            "{} of {}{}",
                             find_state.current_match + 1,
-                            find_state.matches.len()
+                            find_state.matches.len(),
+                            more
                         )
                     };
 
@@ -479,12 +836,35 @@ impl FindReplacePanel {
                     ui.horizontal(|ui| {
                         ui.label(RichText::new("↳").size(14.0).color(muted_color));
 
-                        ui.add_sized(
-                            Vec2::new(280.0, 24.0),
-                            egui::TextEdit::singleline(&mut find_state.replace_term)
-                                .hint_text("Replace with...")
+                        let hint = if find_state.use_regex {
+                            "Replace with... ($1, ${name}, \\n)"
+                        } else {
+                            "Replace with..."
+                        };
+                        let replace_response = ui.add(
+                            egui::TextEdit::multiline(&mut find_state.replace_term)
+                                .id(replace_id)
+                                .desired_width(280.0)
+                                .desired_rows(1)
+                                .return_key(KeyboardShortcut::new(Modifiers::COMMAND, Key::Enter))
+                                .hint_text(hint)
                                 .font(egui::FontId::proportional(13.0)),
                         );
+                        if replace_response.changed() {
+                            self.replace_history.index = None;
+                        }
+
+                        // Preserve case toggle
+                        let case_btn = ui.add(toggle_button(
+                            "AB",
+                            "Preserve Case",
+                            find_state.preserve_case,
+                            is_dark,
+                            accent_color,
+                        ));
+                        if case_btn.clicked() {
+                            find_state.preserve_case = !find_state.preserve_case;
+                        }
                     });
                 }
 
@@ -535,6 +915,21 @@ impl FindReplacePanel {
                         output.search_changed = true;
                     }
 
+                    ui.add_space(4.0);
+
+                    // Limit to selection toggle
+                    let selection_btn = ui.add(toggle_button(
+                        "Sel",
+                        "Find in Selection",
+                        find_state.in_selection,
+                        is_dark,
+                        accent_color,
+                    ));
+                    if selection_btn.clicked() {
+                        find_state.in_selection = !find_state.in_selection;
+                        output.search_changed = true;
+                    }
+
                     ui.add_space(16.0);
 
                     // Navigation buttons
@@ -592,10 +987,35 @@ impl FindReplacePanel {
                     }
                 });
 
+                // Replace preview
+                if find_state.is_replace_mode && !previews.is_empty() {
+                    ui.add_space(6.0);
+                    ui.separator();
+                    egui::ScrollArea::vertical()
+                        .max_height(120.0)
+                        .auto_shrink([false, true])
+                        .show(ui, |ui| {
+                            for preview in previews {
+                                let is_current = preview.index == find_state.current_match;
+                                let text = preview_line(preview, muted_color, accent_color);
+                                let response = ui.add(
+                                    egui::Button::new(text)
+                                        .frame(is_current)
+                                        .wrap_mode(egui::TextWrapMode::Truncate),
+                                );
+                                if response.clicked() {
+                                    output.selected_match = Some(preview.index);
+                                }
+                            }
+                        });
+                }
+
                 // Keyboard hints
                 ui.add_space(4.0);
                 ui.label(
-                    RichText::new("Enter/F3: Next • Shift+F3: Prev • Esc: Close")
+                    RichText::new(
+                        "Enter/F3: Next • Shift+Enter/Shift+F3: Prev • Ctrl+Enter: New line • ↑↓: History • Esc: Close",
+                    )
                         .size(10.0)
                         .color(muted_color),
                 );
@@ -605,6 +1025,32 @@ impl FindReplacePanel {
     }
 }
 
+/// Lay out one replace preview as `line: matched → replacement`.
+fn preview_line(
+    preview: &ReplacePreview,
+    muted_color: Color32,
+    accent_color: Color32,
+) -> egui::text::LayoutJob {
+    // Show line breaks and tabs in the match and replacement visibly
+    let visible = |text: &str| text.replace('\n', "⏎").replace('\t', "⇥");
+    let font = egui::FontId::monospace(11.0);
+    let format = |color: Color32| egui::TextFormat::simple(font.clone(), color);
+
+    let mut job = egui::text::LayoutJob::default();
+    job.append(&format!("{}: ", preview.line), 0.0, format(muted_color));
+    job.append(
+        &visible(&preview.matched),
+        0.0,
+        egui::TextFormat {
+            strikethrough: egui::Stroke::new(1.0, muted_color),
+            ..format(muted_color)
+        },
+    );
+    job.append(" → ", 0.0, format(muted_color));
+    job.append(&visible(&preview.replacement), 0.0, format(accent_color));
+    job
+}
+
 /// Create a toggle button widget.
 fn toggle_button<'a>(
     label: &'a str,
@@ -889,6 +1335,98 @@ mod tests {
         assert_eq!(state.match_count(), 2);
     }
 
+    // ─────────────────────────────────────────────────────────────────────────
+    // Replacement Expansion Tests
+    // ─────────────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_regex_replace_capture_groups() {
+        let mut state = FindState::new();
+        state.use_regex = true;
+        state.search_term = r"(\w+)@(?P<host>\w+)".to_string();
+        state.replace_term = "${host}:$1 ($$)".to_string();
+        let text = "mail ann@home and bob@work";
+        state.find_matches(text);
+
+        assert_eq!(
+            state.replace_all(text),
+            "mail home:ann ($) and work:bob ($)"
+        );
+        state.next_match();
+        assert_eq!(
+            state.replace_current(text),
+            Some("mail ann@home and work:bob ($)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_regex_replace_escapes() {
+        let mut state = FindState::new();
+        state.use_regex = true;
+        state.search_term = r", ".to_string();
+        state.replace_term = r"\n\t\\n\q".to_string();
+        state.find_matches("a, b");
+        assert_eq!(state.replace_all("a, b"), "a\n\t\\n\\qb");
+
+        // Literal mode inserts the replacement as typed
+        state.use_regex = false;
+        state.replace_term = r"$1\n".to_string();
+        state.find_matches("a, b");
+        assert_eq!(state.replace_all("a, b"), r"a$1\nb");
+    }
+
+    #[test]
+    fn test_regex_replace_respects_word_boundaries() {
+        let mut state = FindState::new();
+        state.use_regex = true;
+        state.whole_word = true;
+        state.search_term = r"(o+)".to_string();
+        state.replace_term = "[$1]".to_string();
+        let text = "oo foo o";
+        state.find_matches(text);
+        assert_eq!(state.replace_all(text), "[oo] foo [o]");
+    }
+
+    #[test]
+    fn test_preserve_case() {
+        let mut state = FindState::new();
+        state.search_term = "foo".to_string();
+        state.replace_term = "bar".to_string();
+        state.preserve_case = true;
+        let text = "foo Foo FOO fOo";
+        state.find_matches(text);
+        assert_eq!(state.replace_all(text), "bar Bar BAR bar");
+
+        assert_eq!(match_case("HTTP", "Gopher"), "GOPHER");
+        assert_eq!(match_case("A", "the"), "The");
+        assert_eq!(match_case("camelCase", "other"), "other");
+        assert_eq!(match_case("123", "Keep"), "Keep");
+    }
+
+    #[test]
+    fn test_replace_previews() {
+        let mut state = FindState::new();
+        state.use_regex = true;
+        state.search_term = r"v(\d)".to_string();
+        state.replace_term = "version $1".to_string();
+        let text = "v1\nnothing\nv2 and v3";
+        state.find_matches(text);
+
+        let previews = state.replace_previews(text);
+        let summary: Vec<_> = previews
+            .iter()
+            .map(|p| (p.index, p.line, p.matched.as_str(), p.replacement.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (0, 1, "v1", "version 1"),
+                (1, 3, "v2", "version 2"),
+                (2, 3, "v3", "version 3"),
+            ]
+        );
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // FindReplacePanel Tests
     // ─────────────────────────────────────────────────────────────────────────
@@ -1019,4 +1557,152 @@ mod tests {
                                   // current_match should be clamped to valid range
         assert_eq!(state.current_match, 0);
     }
+
+    #[test]
+    fn test_find_in_selection() {
+        let mut state = FindState::new();
+        state.search_term = "a".to_string();
+        state.scope = Some((2, 5));
+        // Scope is ignored until the option is on
+        assert_eq!(state.find_matches("a a a a"), 4);
+
+        state.in_selection = true;
+        assert_eq!(state.find_matches("a a a a"), 2);
+        assert_eq!(state.matches, vec![(2, 3), (4, 5)]);
+
+        // A match may not run past the end of the selection
+        state.search_term = "a a a".to_string();
+        assert_eq!(state.find_matches("a a a a"), 0);
+    }
+
+    #[test]
+    fn test_adjust_scope_after_replace() {
+        let mut state = FindState::new();
+        state.search_term = "a".to_string();
+        state.replace_term = "xyz".to_string();
+        state.in_selection = true;
+        state.scope = Some((0, 3));
+        let text = "a a a";
+        state.find_matches(text);
+        let new_text = state.replace_all(text);
+        assert_eq!(new_text, "xyz xyz a");
+        state.adjust_scope(text.len(), new_text.len());
+        assert_eq!(state.scope, Some((0, 7)));
+        assert_eq!(state.find_matches(&new_text), 0);
+    }
+
+    #[test]
+    fn test_stale_matches_after_edit() {
+        let mut state = FindState::new();
+        state.search_term = "world".to_string();
+        state.replace_term = "there".to_string();
+        state.find_matches("hello world");
+
+        // The text shrank under the matches
+        assert!(state.replace_previews("hello").is_empty());
+        assert_eq!(state.replace_current("hello"), None);
+        assert_eq!(state.replace_all("hello"), "hello");
+
+        // An earlier character grew, so the match is off a char boundary
+        let edited = "héllo world";
+        state.matches = vec![(2, 7)];
+        assert!(state.replace_previews(edited).is_empty());
+        assert_eq!(state.replace_current(edited), None);
+
+        // Restarting the search finds the match again
+        state.find_matches(edited);
+        assert_eq!(state.replace_all(edited), "héllo there");
+    }
+
+    #[test]
+    fn test_incremental_search_matches_full_search() {
+        let text = "ab\n".repeat(100);
+        let mut full = FindState::new();
+        full.search_term = "ab".to_string();
+        full.find_matches(&text);
+
+        let mut state = FindState::new();
+        state.search_term = "ab".to_string();
+        state.start_search(&text);
+        assert!(state.is_searching());
+        let mut steps = 0;
+        while !state.search_step(&text, 7) {
+            steps += 1;
+        }
+        assert!(!state.is_searching());
+        // Each step runs on to the end of the line it stops in: 3 lines
+        assert_eq!(steps, 100 / 3);
+        assert_eq!(state.matches, full.matches);
+    }
+
+    #[test]
+    fn test_incremental_search_at_chunk_edges() {
+        let text = "one two\n\nthree end\nstart four\n";
+        let cases = [
+            ("^", true),
+            ("$", true),
+            (r"\b", true),
+            ("o$", true),
+            (r"end\nstart", true),
+            ("(?is)E.S", true),
+            ("end\nstart", false),
+            ("o", false),
+        ];
+        for (term, use_regex) in cases {
+            let mut full = FindState::new();
+            full.search_term = term.to_string();
+            full.use_regex = use_regex;
+            full.find_matches(text);
+
+            let mut state = full.clone();
+            state.start_search(text);
+            while !state.search_step(text, 1) {}
+            assert_eq!(state.matches, full.matches, "{}", term);
+        }
+    }
+
+    #[test]
+    fn test_incremental_search_restarts_on_edit() {
+        let mut state = FindState::new();
+        state.search_term = "b".to_string();
+        state.start_search("ab\nab\n");
+        assert!(!state.search_step("ab\nab\n", 1));
+        // The text changed length mid-search
+        assert!(state.search_step("bb\nabb\n", usize::MAX));
+        assert_eq!(state.matches, vec![(0, 1), (1, 2), (4, 5), (5, 6)]);
+    }
+
+    #[test]
+    fn test_multiline_query() {
+        let mut state = FindState::new();
+        state.search_term = "end\nstart".to_string();
+        assert_eq!(state.find_matches("the end\nstart again"), 1);
+
+        // Regex: `.` crosses lines only with (?s), `^` matches at each line
+        state.use_regex = true;
+        state.search_term = "a.b".to_string();
+        assert_eq!(state.find_matches("a\nb"), 0);
+        state.search_term = "(?s)a.b".to_string();
+        assert_eq!(state.find_matches("a\nb"), 1);
+        state.search_term = "^x".to_string();
+        assert_eq!(state.find_matches("x\nx\n x"), 2);
+    }
+
+    #[test]
+    fn test_history_cursor() {
+        let history = vec!["newest".to_string(), "older".to_string()];
+        let mut cursor = HistoryCursor::default();
+        let mut term = "draft".to_string();
+
+        assert!(cursor.step(&history, true, &mut term));
+        assert_eq!(term, "newest");
+        assert!(cursor.step(&history, true, &mut term));
+        assert_eq!(term, "older");
+        // Stays on the oldest entry
+        assert!(!cursor.step(&history, true, &mut term));
+        assert!(cursor.step(&history, false, &mut term));
+        assert!(cursor.step(&history, false, &mut term));
+        assert_eq!(term, "draft");
+        assert!(!cursor.step(&history, false, &mut term));
+    }
 }
@@ -0,0 +1,1594 @@
+//! Syntax-colored layout for the raw editor
+//!
+//! This module replaces the single-color layout of the raw text editor with
+//! a Markdown-aware one. Each line is tokenized (headings, emphasis, links,
+//! code spans, lists, quotes, tables, inline HTML) and colored from the
+//! theme's `EditorThemeColors` and `SyntaxColors`; fenced code blocks are
+//! highlighted for their language with the shared `SyntaxHighlighter`.
+//! Structured and source files are highlighted as code.
+//!
+//! Every line is laid out as its own galley and cached by its text and the
+//! state it starts in (normal text, or a position inside a code block), so
+//! an edit only re-tokenizes and re-lays out the lines it touched. The line
+//! galleys are then stitched into the single galley `TextEdit` expects.
+
+use crate::config::EditorFont;
+use crate::fonts;
+use crate::markdown::syntax::{get_highlighter, CodeLineState};
+use crate::state::{FileType, Tab};
+use crate::theme::ThemeColors;
+use eframe::egui::epaint::text::{Fonts, Row};
+use eframe::egui::text::{LayoutJob, LayoutSection, TextFormat, TextWrapping};
+use eframe::egui::{vec2, Color32, FontId, Galley, Rect, Stroke};
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::sync::Arc;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Highlighting Mode
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// How a document is colored in the raw editor.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Highlighting {
+    /// No coloring
+    Plain,
+    /// Markdown syntax, with fenced code blocks highlighted for their language
+    Markdown,
+    /// The whole document is code in the given language
+    Code(String),
+}
+
+impl Highlighting {
+    /// Pick the highlighting for a tab from its file type and extension.
+    pub fn for_tab(tab: &Tab) -> Self {
+        match tab.file_type() {
+            FileType::Markdown => Self::Markdown,
+            FileType::Json => Self::Code("json".to_string()),
+            FileType::Yaml => Self::Code("yaml".to_string()),
+            FileType::Toml => Self::Code("toml".to_string()),
+            FileType::Unknown => tab
+                .path
+                .as_deref()
+                .and_then(|path| path.extension()?.to_str())
+                .filter(|ext| get_highlighter().supports_language(ext))
+                .map_or(Self::Plain, |ext| Self::Code(ext.to_string())),
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tokens
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// What a piece of a line is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Ordinary text
+    Text,
+    /// Syntax characters (`#`, `*`, `` ` ``, `[`, `](`, `\`, ...)
+    Marker,
+    /// Heading text
+    Heading,
+    /// Block quote text
+    Quote,
+    /// List bullet or number
+    ListMarker,
+    /// Task list checkbox (`[ ]` / `[x]`)
+    TaskBox,
+    /// Horizontal rule
+    Rule,
+    /// Inline code span, including its backticks
+    CodeSpan,
+    /// Code fence line (```` ```rust ````)
+    Fence,
+    /// Code in a language without a syntax definition
+    Code,
+    /// Code colored by the syntax highlighter
+    Highlighted(Color32),
+    /// Link or image text
+    LinkText,
+    /// Link destination or bare URL
+    LinkUrl,
+    /// Inline HTML tag or comment
+    Html,
+    /// Table cell separator or delimiter row
+    TablePipe,
+}
+
+/// Style of a piece of a line: its kind plus inline emphasis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    /// What the text is
+    pub kind: TokenKind,
+    /// Inside `**strong**`
+    pub strong: bool,
+    /// Inside `*emphasis*`
+    pub emphasis: bool,
+    /// Inside `~~strikethrough~~`
+    pub strike: bool,
+}
+
+impl Token {
+    /// A token of the given kind without emphasis.
+    pub fn new(kind: TokenKind) -> Self {
+        Self {
+            kind,
+            strong: false,
+            emphasis: false,
+            strike: false,
+        }
+    }
+
+    /// The same emphasis, with a different kind.
+    fn with_kind(self, kind: TokenKind) -> Self {
+        Self { kind, ..self }
+    }
+}
+
+/// Byte ranges of a line with their tokens, in order and without gaps.
+pub type Spans = Vec<(Range<usize>, Token)>;
+
+/// Append a span, merging it with the previous one if the token matches.
+fn push_span(spans: &mut Spans, range: Range<usize>, token: Token) {
+    if range.is_empty() {
+        return;
+    }
+    if let Some((last, last_token)) = spans.last_mut() {
+        if last.end == range.start && *last_token == token {
+            last.end = range.end;
+            return;
+        }
+    }
+    spans.push((range, token));
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Line State
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Where a line starts: in normal text or inside a code block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LineState {
+    /// Uncolored document
+    Plain,
+    /// Markdown text
+    Markdown,
+    /// Inside a fenced code block, or a document that is all code
+    Code(Box<CodeBlock>),
+}
+
+/// Progress through a code block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CodeBlock {
+    /// Fence character and length that close the block (`None` when the
+    /// whole document is code)
+    fence: Option<(char, usize)>,
+    /// Syntax highlighter state, if the language is known
+    syntax: Option<CodeLineState>,
+}
+
+impl LineState {
+    /// Initial state for a document.
+    fn start(highlighting: &Highlighting, dark_mode: bool) -> Self {
+        match highlighting {
+            Highlighting::Plain => Self::Plain,
+            Highlighting::Markdown => Self::Markdown,
+            Highlighting::Code(language) => Self::Code(Box::new(CodeBlock {
+                fence: None,
+                syntax: start_code(language, dark_mode),
+            })),
+        }
+    }
+}
+
+fn start_code(language: &str, dark_mode: bool) -> Option<CodeLineState> {
+    let highlighter = get_highlighter();
+    highlighter.start_code_lines(language, highlighter.get_theme_for_mode(dark_mode))
+}
+
+fn hash_of(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Tokenize one line (without its newline) and compute the next line's state.
+fn highlight_line(line: &str, state: &LineState, dark_mode: bool) -> (Spans, LineState) {
+    let mut spans = Spans::new();
+    match state {
+        LineState::Plain => {
+            push_span(&mut spans, 0..line.len(), Token::new(TokenKind::Text));
+            (spans, LineState::Plain)
+        }
+        LineState::Code(block) => {
+            if let Some((fence_char, fence_len)) = block.fence {
+                if is_closing_fence(line, fence_char, fence_len) {
+                    push_span(&mut spans, 0..line.len(), Token::new(TokenKind::Fence));
+                    return (spans, LineState::Markdown);
+                }
+            }
+            let mut next = block.as_ref().clone();
+            match &mut next.syntax {
+                Some(syntax) => {
+                    let highlighter = get_highlighter();
+                    let theme = highlighter.get_theme_for_mode(dark_mode);
+                    let mut start = 0;
+                    for segment in highlighter.highlight_code_line(syntax, line, theme) {
+                        let end = start + segment.text.len();
+                        let kind = TokenKind::Highlighted(segment.foreground);
+                        push_span(&mut spans, start..end, Token::new(kind));
+                        start = end;
+                    }
+                }
+                None => push_span(&mut spans, 0..line.len(), Token::new(TokenKind::Code)),
+            }
+            (spans, LineState::Code(Box::new(next)))
+        }
+        LineState::Markdown => {
+            if let Some((fence_char, fence_len, language)) = opening_fence(line) {
+                push_span(&mut spans, 0..line.len(), Token::new(TokenKind::Fence));
+                let block = CodeBlock {
+                    fence: Some((fence_char, fence_len)),
+                    syntax: start_code(language, dark_mode),
+                };
+                return (spans, LineState::Code(Box::new(block)));
+            }
+            markdown_line(line, &mut spans);
+            (spans, LineState::Markdown)
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Markdown Blocks
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Parse a code fence opening: fence character, length and info string.
+fn opening_fence(line: &str) -> Option<(char, usize, &str)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let rest = &line[indent..];
+    let fence_char = rest.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let fence_len = rest.len() - rest.trim_start_matches(fence_char).len();
+    if fence_len < 3 {
+        return None;
+    }
+    let info = rest[fence_len..].trim();
+    // A backtick fence's info string can't contain backticks
+    if fence_char == '`' && info.contains('`') {
+        return None;
+    }
+    let language = info.split_whitespace().next().unwrap_or("");
+    Some((
+        fence_char,
+        fence_len,
+        language.trim_matches(['{', '}', '.']),
+    ))
+}
+
+/// Check if a line closes a fence opened with `fence_len` × `fence_char`.
+fn is_closing_fence(line: &str, fence_char: char, fence_len: usize) -> bool {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return false;
+    }
+    let run = trimmed.len() - trimmed.trim_start_matches(fence_char).len();
+    run >= fence_len && trimmed[run..].trim().is_empty()
+}
+
+/// Check if a line is a thematic break (`---`, `***`, `___`).
+fn is_thematic_break(rest: &str) -> bool {
+    let mut chars = rest.chars().filter(|c| !c.is_whitespace());
+    let Some(first) = chars.next().filter(|c| matches!(c, '-' | '*' | '_')) else {
+        return false;
+    };
+    let mut count = 1;
+    for c in chars {
+        if c != first {
+            return false;
+        }
+        count += 1;
+    }
+    count >= 3
+}
+
+/// Length of an ATX heading marker (`#` to `######` plus one space).
+fn heading_marker_len(rest: &str) -> Option<usize> {
+    let hashes = rest.len() - rest.trim_start_matches('#').len();
+    if !(1..=6).contains(&hashes) {
+        return None;
+    }
+    match rest[hashes..].chars().next() {
+        None => Some(hashes),
+        Some(' ') | Some('\t') => Some(hashes + 1),
+        Some(_) => None,
+    }
+}
+
+/// Length of a list marker plus its trailing space (`- `, `12. `).
+fn list_marker_len(rest: &str) -> Option<usize> {
+    let bytes = rest.as_bytes();
+    let marker = match bytes.first()? {
+        b'-' | b'*' | b'+' => 1,
+        b'0'..=b'9' => {
+            let digits = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+            if digits > 9 || !matches!(bytes.get(digits), Some(b'.') | Some(b')')) {
+                return None;
+            }
+            digits + 1
+        }
+        _ => return None,
+    };
+    match bytes.get(marker) {
+        None => Some(marker),
+        Some(b' ') | Some(b'\t') => Some(marker + 1),
+        Some(_) => None,
+    }
+}
+
+/// Length of a task checkbox plus its trailing space (`[ ] `, `[x] `).
+fn task_box_len(rest: &str) -> Option<usize> {
+    let bytes = rest.as_bytes();
+    let checkbox = bytes.len() >= 3
+        && bytes[0] == b'['
+        && matches!(bytes[1], b' ' | b'x' | b'X')
+        && bytes[2] == b']';
+    if !checkbox {
+        return None;
+    }
+    match bytes.get(3) {
+        None => Some(3),
+        Some(b' ') => Some(4),
+        Some(_) => None,
+    }
+}
+
+/// Check if a line is a table delimiter row (`|---|:--:|`).
+fn is_table_delimiter(rest: &str) -> bool {
+    rest.contains('-')
+        && rest.contains('|')
+        && rest
+            .chars()
+            .all(|c| matches!(c, '|' | '-' | ':' | ' ' | '\t'))
+}
+
+/// Tokenize a line of Markdown outside code blocks.
+fn markdown_line(line: &str, spans: &mut Spans) {
+    let mut pos = 0;
+    let mut base = Token::new(TokenKind::Text);
+
+    // Block quote markers, possibly nested (`> > text`)
+    loop {
+        let rest = &line[pos..];
+        let indent = rest.len() - rest.trim_start_matches(' ').len();
+        if indent > 3 || !rest[indent..].starts_with('>') {
+            break;
+        }
+        let mut end = pos + indent + 1;
+        if line[end..].starts_with(' ') {
+            end += 1;
+        }
+        push_span(spans, pos..end, Token::new(TokenKind::Marker));
+        base = Token::new(TokenKind::Quote);
+        pos = end;
+    }
+
+    let rest = &line[pos..];
+    let indent = rest.len() - rest.trim_start().len();
+    push_span(spans, pos..pos + indent, base);
+    pos += indent;
+    let rest = &line[pos..];
+
+    if indent <= 3 {
+        if let Some(marker_len) = heading_marker_len(rest) {
+            push_span(spans, pos..pos + marker_len, Token::new(TokenKind::Marker));
+            let heading = Token::new(TokenKind::Heading);
+            parse_inline(line, pos + marker_len..line.len(), heading, false, spans);
+            return;
+        }
+        if is_thematic_break(rest) {
+            push_span(spans, pos..line.len(), Token::new(TokenKind::Rule));
+            return;
+        }
+    }
+
+    if let Some(marker_len) = list_marker_len(rest) {
+        push_span(
+            spans,
+            pos..pos + marker_len,
+            Token::new(TokenKind::ListMarker),
+        );
+        pos += marker_len;
+        if let Some(box_len) = task_box_len(&line[pos..]) {
+            push_span(spans, pos..pos + box_len, Token::new(TokenKind::TaskBox));
+            pos += box_len;
+        }
+    }
+
+    let rest = &line[pos..];
+    if rest.starts_with('|') && is_table_delimiter(rest) {
+        push_span(spans, pos..line.len(), Token::new(TokenKind::TablePipe));
+        return;
+    }
+    let table = rest.starts_with('|');
+    parse_inline(line, pos..line.len(), base, table, spans);
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Markdown Inlines
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Tokenize inline Markdown in `line[range]`, with `base` as the style of
+/// plain text. `table` makes `|` a cell separator.
+fn parse_inline(line: &str, range: Range<usize>, base: Token, table: bool, spans: &mut Spans) {
+    let bytes = line.as_bytes();
+    let end = range.end;
+    let mut plain_start = range.start;
+    let mut i = range.start;
+
+    while i < end {
+        let next = match bytes[i] {
+            b'\\' if i + 1 < end && bytes[i + 1].is_ascii_punctuation() => {
+                push_span(spans, plain_start..i, base);
+                push_span(spans, i..i + 1, base.with_kind(TokenKind::Marker));
+                push_span(spans, i + 1..i + 2, base);
+                Some(i + 2)
+            }
+            b'`' => code_span(line, i, end).map(|close| {
+                push_span(spans, plain_start..i, base);
+                push_span(spans, i..close, base.with_kind(TokenKind::CodeSpan));
+                close
+            }),
+            b'*' | b'_' | b'~' => emphasis(line, i, end).map(|(run, close)| {
+                push_span(spans, plain_start..i, base);
+                let marker = base.with_kind(TokenKind::Marker);
+                let mut inner = base;
+                match (bytes[i], run) {
+                    (b'~', _) => inner.strike = true,
+                    (_, 1) => inner.emphasis = true,
+                    (_, 2) => inner.strong = true,
+                    _ => {
+                        inner.strong = true;
+                        inner.emphasis = true;
+                    }
+                }
+                push_span(spans, i..i + run, marker);
+                parse_inline(line, i + run..close, inner, table, spans);
+                push_span(spans, close..close + run, marker);
+                close + run
+            }),
+            b'!' if bytes.get(i + 1) == Some(&b'[') && i + 1 < end => {
+                link(line, i + 1, end).map(|parts| {
+                    push_span(spans, plain_start..i, base);
+                    push_span(spans, i..i + 1, base.with_kind(TokenKind::Marker));
+                    push_link(line, parts, base, table, spans)
+                })
+            }
+            b'[' => link(line, i, end).map(|parts| {
+                push_span(spans, plain_start..i, base);
+                push_link(line, parts, base, table, spans)
+            }),
+            b'<' => angle_bracket(line, i, end).map(|(close, kind)| {
+                push_span(spans, plain_start..i, base);
+                push_span(spans, i..close, base.with_kind(kind));
+                close
+            }),
+            b'h' => bare_url(line, i, end).map(|close| {
+                push_span(spans, plain_start..i, base);
+                push_span(spans, i..close, base.with_kind(TokenKind::LinkUrl));
+                close
+            }),
+            b'|' if table => {
+                push_span(spans, plain_start..i, base);
+                push_span(spans, i..i + 1, base.with_kind(TokenKind::TablePipe));
+                Some(i + 1)
+            }
+            _ => None,
+        };
+
+        match next {
+            Some(next) => {
+                i = next;
+                plain_start = next;
+            }
+            None => i += 1,
+        }
+    }
+    push_span(spans, plain_start..end, base);
+}
+
+/// Find the end of a code span starting at `start` (a run of backticks).
+fn code_span(line: &str, start: usize, end: usize) -> Option<usize> {
+    let bytes = line.as_bytes();
+    let run = bytes[start..end].iter().take_while(|&&b| b == b'`').count();
+    let mut i = start + run;
+    while i < end {
+        if bytes[i] == b'`' {
+            let close_run = bytes[i..end].iter().take_while(|&&b| b == b'`').count();
+            if close_run == run {
+                return Some(i + run);
+            }
+            i += close_run;
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Find emphasis starting at `start`: the delimiter run length and the
+/// position of the matching closing run.
+fn emphasis(line: &str, start: usize, end: usize) -> Option<(usize, usize)> {
+    let bytes = line.as_bytes();
+    let delimiter = bytes[start];
+    let run = bytes[start..end]
+        .iter()
+        .take_while(|&&b| b == delimiter)
+        .count();
+    if run > 3 || (delimiter == b'~' && run != 2) {
+        return None;
+    }
+    // Opening run must be followed by text, and `_` can't open inside a word
+    let after = line[start + run..end].chars().next()?;
+    if after.is_whitespace() {
+        return None;
+    }
+    let before = line[..start].chars().next_back();
+    if delimiter == b'_' && before.is_some_and(char::is_alphanumeric) {
+        return None;
+    }
+
+    let mut i = start + run + 1;
+    while i + run <= end {
+        let close_run = bytes[i..end]
+            .iter()
+            .take_while(|&&b| b == delimiter)
+            .count();
+        if close_run == 0 {
+            i += 1;
+            continue;
+        }
+        let preceded_by_space = line[..i]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_whitespace);
+        let followed_by_word = line[i + close_run..end]
+            .chars()
+            .next()
+            .is_some_and(char::is_alphanumeric);
+        if close_run == run && !preceded_by_space && !(delimiter == b'_' && followed_by_word) {
+            return Some((run, i));
+        }
+        i += close_run;
+    }
+    None
+}
+
+/// The parts of a link: `[text](url)` or `[text][ref]`.
+struct LinkParts {
+    /// Position of the opening `[`
+    open: usize,
+    /// Position of the `]` closing the text
+    text_close: usize,
+    /// Destination (inside the parentheses or second brackets)
+    target: Range<usize>,
+    /// Position just past the closing `)` or `]`
+    end: usize,
+}
+
+/// Find a link whose text starts with the `[` at `start`.
+fn link(line: &str, start: usize, end: usize) -> Option<LinkParts> {
+    let bytes = line.as_bytes();
+    let mut depth = 0;
+    let mut text_close = None;
+    let mut i = start;
+    while i < end {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'[' => depth += 1,
+            b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    text_close = Some(i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    let text_close = text_close?;
+
+    let (open, close) = match bytes.get(text_close + 1) {
+        Some(b'(') => (b'(', b')'),
+        Some(b'[') => (b'[', b']'),
+        _ => return None,
+    };
+    let target_start = text_close + 2;
+    let mut depth = 1;
+    let mut i = target_start;
+    while i < end {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b if b == open && open == b'(' => depth += 1,
+            b if b == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(LinkParts {
+                        open: start,
+                        text_close,
+                        target: target_start..i,
+                        end: i + 1,
+                    });
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Emit the spans of a link and return the position after it.
+fn push_link(line: &str, parts: LinkParts, base: Token, table: bool, spans: &mut Spans) -> usize {
+    let marker = base.with_kind(TokenKind::Marker);
+    push_span(spans, parts.open..parts.open + 1, marker);
+    let text = base.with_kind(TokenKind::LinkText);
+    parse_inline(line, parts.open + 1..parts.text_close, text, table, spans);
+    push_span(spans, parts.text_close..parts.target.start, marker);
+    let url = base.with_kind(TokenKind::LinkUrl);
+    push_span(spans, parts.target.clone(), url);
+    push_span(spans, parts.target.end..parts.end, marker);
+    parts.end
+}
+
+/// Find an autolink (`<https://...>`) or inline HTML starting at `start`.
+fn angle_bracket(line: &str, start: usize, end: usize) -> Option<(usize, TokenKind)> {
+    let rest = &line[start..end];
+    if rest.starts_with("<!--") {
+        let close = rest.find("-->").map_or(end, |i| start + i + 3);
+        return Some((close, TokenKind::Html));
+    }
+    let close = start + rest.find('>')? + 1;
+    let inner = &line[start + 1..close - 1];
+    if inner.is_empty() || inner.contains(char::is_whitespace) && !is_tag_start(inner) {
+        return None;
+    }
+    if inner.contains("://") || (inner.contains('@') && !inner.contains(' ')) {
+        Some((close, TokenKind::LinkUrl))
+    } else if is_tag_start(inner) {
+        Some((close, TokenKind::Html))
+    } else {
+        None
+    }
+}
+
+/// Check if the text after `<` starts an HTML tag (`div`, `/div`, `!DOCTYPE`).
+fn is_tag_start(inner: &str) -> bool {
+    let name = inner.trim_start_matches(['/', '!']);
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+}
+
+/// Find the end of a bare `http://` or `https://` URL starting at `start`.
+fn bare_url(line: &str, start: usize, end: usize) -> Option<usize> {
+    let rest = &line[start..end];
+    if !(rest.starts_with("http://") || rest.starts_with("https://")) {
+        return None;
+    }
+    if line[..start]
+        .chars()
+        .next_back()
+        .is_some_and(|c| c.is_alphanumeric())
+    {
+        return None;
+    }
+    let len = rest
+        .find(|c: char| c.is_whitespace() || c == '<')
+        .unwrap_or(rest.len());
+    // Trailing punctuation belongs to the sentence, not the URL
+    let url = rest[..len].trim_end_matches(['.', ',', ':', ';', '!', '?', ')', '*', '_']);
+    Some(start + url.len())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Style
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Colors and font used to draw raw text, taken from the theme.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct RawStyle {
+    /// How the document is tokenized
+    pub highlighting: Highlighting,
+    /// Whether the theme is dark (selects the code highlighting theme)
+    pub dark_mode: bool,
+    /// Editor font family
+    pub font: EditorFont,
+    /// Font size in points, as bits so the style can be hashed
+    font_size_bits: u32,
+    /// Plain text
+    text: Color32,
+    /// Syntax characters and fences
+    marker: Color32,
+    /// Headings
+    heading: Color32,
+    /// Block quote text
+    quote: Color32,
+    /// List bullets and numbers
+    list_marker: Color32,
+    /// Task checkboxes
+    checkbox: Color32,
+    /// Horizontal rules
+    rule: Color32,
+    /// Code spans and unhighlighted code
+    code: Color32,
+    /// Code span background
+    code_bg: Color32,
+    /// Link text
+    link: Color32,
+    /// Link destinations
+    url: Color32,
+    /// Inline HTML
+    html: Color32,
+    /// Table pipes
+    table: Color32,
+}
+
+impl RawStyle {
+    /// Build the style for a document from the theme colors.
+    pub fn new(
+        colors: &ThemeColors,
+        highlighting: Highlighting,
+        font: EditorFont,
+        font_size: f32,
+    ) -> Self {
+        Self {
+            highlighting,
+            dark_mode: colors.is_dark(),
+            font,
+            font_size_bits: font_size.to_bits(),
+            text: colors.text.primary,
+            marker: colors.text.muted,
+            heading: colors.editor.heading,
+            quote: colors.editor.blockquote_text,
+            list_marker: colors.editor.list_marker,
+            checkbox: colors.editor.checkbox,
+            rule: colors.editor.horizontal_rule,
+            code: colors.text.code,
+            code_bg: colors.editor.code_block_bg,
+            link: colors.text.link,
+            url: colors.syntax.string,
+            html: colors.syntax.keyword,
+            table: colors.editor.table_border,
+        }
+    }
+
+    /// Font size in points.
+    pub fn font_size(&self) -> f32 {
+        f32::from_bits(self.font_size_bits)
+    }
+
+    /// Text format for a token.
+    fn format(&self, token: Token) -> TextFormat {
+        let bold = token.strong || token.kind == TokenKind::Heading;
+        let family = fonts::get_styled_font_family(bold, token.emphasis, self.font);
+        let color = match token.kind {
+            TokenKind::Text => self.text,
+            TokenKind::Marker | TokenKind::Fence => self.marker,
+            TokenKind::Heading => self.heading,
+            TokenKind::Quote => self.quote,
+            TokenKind::ListMarker => self.list_marker,
+            TokenKind::TaskBox => self.checkbox,
+            TokenKind::Rule => self.rule,
+            TokenKind::CodeSpan | TokenKind::Code => self.code,
+            TokenKind::Highlighted(color) => color,
+            TokenKind::LinkText => self.link,
+            TokenKind::LinkUrl => self.url,
+            TokenKind::Html => self.html,
+            TokenKind::TablePipe => self.table,
+        };
+        let mut format = TextFormat::simple(FontId::new(self.font_size(), family), color);
+        if token.kind == TokenKind::CodeSpan {
+            format.background = self.code_bg;
+        }
+        if token.strike {
+            format.strikethrough = Stroke::new(1.0, color);
+        }
+        format
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Layout Cache
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A laid-out line with the states it starts and ends in.
+struct CachedLine {
+    start_state: LineState,
+    galley: Arc<Galley>,
+    end_state: LineState,
+}
+
+impl CachedLine {
+    /// Highlight and lay out one line starting in `state`.
+    fn new(fonts: &Fonts, line: &str, state: LineState, style: &RawStyle, wrap_width: f32) -> Self {
+        let (spans, end_state) = highlight_line(line, &state, style.dark_mode);
+        let job = line_job(line, &spans, style, wrap_width);
+        Self {
+            start_state: state,
+            galley: fonts.layout_job(job),
+            end_state,
+        }
+    }
+}
+
+/// A stitched layout and the line galleys it was built from.
+struct Stitched {
+    galley: Arc<Galley>,
+    /// Line galleys, in order
+    lines: Vec<Arc<Galley>>,
+}
+
+/// Per-line galley cache for the raw editor's layouter.
+///
+/// One cache is kept per tab (see [`with_layout_cache`]), so it survives
+/// between frames. After an edit, only the changed lines are highlighted
+/// again, starting from the state cached for the first of them; the lines
+/// below follow only until one starts in the same state as before.
+///
+/// An earlier stitched galley is then patched in place: only the rows of
+/// changed lines are replaced and the rows below them shifted. Two layouts
+/// are kept because `TextEdit` still holds the latest galley while it lays
+/// out an edit.
+#[derive(Default)]
+pub struct RawLayoutCache {
+    /// Hash of the style, wrap width and scale the cache was built with
+    style_key: u64,
+    /// Lines of the last layout, in order
+    lines: Vec<CachedLine>,
+    /// Byte offset of each line in the last layout's text
+    line_starts: Vec<usize>,
+    /// The last layout
+    last: Option<Stitched>,
+    /// The layout before it
+    spare: Option<Stitched>,
+}
+
+impl RawLayoutCache {
+    /// Lay out `text` with syntax colors, reusing cached lines.
+    pub fn layout(
+        &mut self,
+        fonts: &Fonts,
+        text: &str,
+        style: &RawStyle,
+        wrap_width: f32,
+    ) -> Arc<Galley> {
+        let style_key = hash_of((
+            style,
+            wrap_width.to_bits(),
+            fonts.pixels_per_point().to_bits(),
+        ));
+        if style_key != self.style_key {
+            self.style_key = style_key;
+            self.lines.clear();
+            self.line_starts.clear();
+            self.last = None;
+            self.spare = None;
+        }
+
+        let previous = self.last.as_ref().map(|last| last.galley.job.clone());
+        match previous {
+            Some(job) if job.text == text => return self.last.as_ref().unwrap().galley.clone(),
+            Some(job) => self.update_lines(fonts, &job.text, text, style, wrap_width),
+            None => self.highlight_all(fonts, text, style, wrap_width),
+        }
+        let galleys: Vec<Arc<Galley>> = self.lines.iter().map(|l| l.galley.clone()).collect();
+
+        // Patch whichever earlier layout nobody else holds
+        let mut last = self.last.take();
+        let mut spare = self.spare.take();
+        if !is_unshared(&mut last) {
+            std::mem::swap(&mut last, &mut spare);
+        }
+        let galley = match last {
+            Some(mut stitched) => match Arc::get_mut(&mut stitched.galley) {
+                Some(galley) => {
+                    patch_galley(galley, text, &stitched.lines, &galleys);
+                    stitched.galley
+                }
+                None => Arc::new(stitch_galleys(text, &galleys)),
+            },
+            None => Arc::new(stitch_galleys(text, &galleys)),
+        };
+        self.spare = spare;
+        self.last = Some(Stitched {
+            galley: galley.clone(),
+            lines: galleys,
+        });
+        galley
+    }
+
+    /// Highlight every line of `text`.
+    fn highlight_all(&mut self, fonts: &Fonts, text: &str, style: &RawStyle, wrap_width: f32) {
+        self.lines.clear();
+        self.line_starts.clear();
+        let mut state = LineState::start(&style.highlighting, style.dark_mode);
+        let mut start = 0;
+        for line in text.split('\n') {
+            let cached = CachedLine::new(fonts, line, state, style, wrap_width);
+            state = cached.end_state.clone();
+            self.lines.push(cached);
+            self.line_starts.push(start);
+            start += line.len() + 1;
+        }
+    }
+
+    /// Update the cached lines of `old` for an edit that turned it into
+    /// `text`.
+    ///
+    /// The lines covering the changed bytes are highlighted again from the
+    /// state the first of them started in. The lines after them keep their
+    /// text, and are only highlighted again while they start in a different
+    /// state than before (e.g. below a new code fence).
+    fn update_lines(
+        &mut self,
+        fonts: &Fonts,
+        old: &str,
+        text: &str,
+        style: &RawStyle,
+        wrap_width: f32,
+    ) {
+        let (old_bytes, bytes) = (old.as_bytes(), text.as_bytes());
+        let prefix = common_prefix(old_bytes, bytes);
+        let suffix = common_suffix(&old_bytes[prefix..], &bytes[prefix..]);
+        let first = self.line_at(prefix);
+        let last = self.line_at(old.len() - suffix);
+
+        // The changed lines in the new text, up to the newline (or end) the
+        // old last changed line ended at
+        let new_end = text.len() - suffix;
+        let region_end = bytes[new_end..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(text.len(), |i| new_end + i);
+        let region_start = self.line_starts[first];
+
+        let mut state = self.lines[first].start_state.clone();
+        let mut lines = Vec::new();
+        let mut starts = Vec::new();
+        let mut start = region_start;
+        for line in text[region_start..region_end].split('\n') {
+            let cached = CachedLine::new(fonts, line, state, style, wrap_width);
+            state = cached.end_state.clone();
+            lines.push(cached);
+            starts.push(start);
+            start += line.len() + 1;
+        }
+
+        // Unchanged lines below follow until the states line up again
+        let mut end = last + 1;
+        while end < self.lines.len() && self.lines[end].start_state != state {
+            let galley = self.lines[end].galley.clone();
+            let cached = CachedLine::new(fonts, &galley.job.text, state, style, wrap_width);
+            state = cached.end_state.clone();
+            lines.push(cached);
+            starts.push(start);
+            start += galley.job.text.len() + 1;
+            end += 1;
+        }
+
+        let added = lines.len();
+        self.lines.splice(first..end, lines);
+        self.line_starts.splice(first..end, starts);
+        for line_start in &mut self.line_starts[first + added..] {
+            *line_start = *line_start + text.len() - old.len();
+        }
+    }
+
+    /// Index of the line containing byte `offset` of the last layout's text.
+    fn line_at(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset) - 1
+    }
+}
+
+/// Length of the common prefix of two byte strings.
+fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+    // Whole chunks first, which compare much faster than single bytes
+    const CHUNK: usize = 64;
+    let len = a.len().min(b.len());
+    let mut n = 0;
+    while n + CHUNK <= len && a[n..n + CHUNK] == b[n..n + CHUNK] {
+        n += CHUNK;
+    }
+    n + a[n..]
+        .iter()
+        .zip(&b[n..])
+        .take_while(|(x, y)| x == y)
+        .count()
+}
+
+/// Length of the common suffix of two byte strings.
+fn common_suffix(a: &[u8], b: &[u8]) -> usize {
+    const CHUNK: usize = 64;
+    let len = a.len().min(b.len());
+    let mut n = 0;
+    while n + CHUNK <= len
+        && a[a.len() - n - CHUNK..a.len() - n] == b[b.len() - n - CHUNK..b.len() - n]
+    {
+        n += CHUNK;
+    }
+    n + a[..a.len() - n]
+        .iter()
+        .rev()
+        .zip(b[..b.len() - n].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count()
+}
+
+/// Whether a stored layout's galley can be modified in place.
+fn is_unshared(stitched: &mut Option<Stitched>) -> bool {
+    stitched
+        .as_mut()
+        .is_some_and(|s| Arc::get_mut(&mut s.galley).is_some())
+}
+
+thread_local! {
+    /// Layout caches by tab id. Syntax highlighter state holds regex
+    /// scratch space that can't be sent between threads, so the caches live
+    /// on the UI thread rather than in egui's memory.
+    static LAYOUT_CACHES: RefCell<HashMap<usize, RawLayoutCache>> = RefCell::new(HashMap::new());
+}
+
+/// Run `f` with the layout cache for a tab.
+pub fn with_layout_cache<R>(tab_id: usize, f: impl FnOnce(&mut RawLayoutCache) -> R) -> R {
+    LAYOUT_CACHES.with(|caches| f(caches.borrow_mut().entry(tab_id).or_default()))
+}
+
+/// Drop the layout caches of tabs for which `keep` returns false.
+pub fn retain_layout_caches(mut keep: impl FnMut(usize) -> bool) {
+    LAYOUT_CACHES.with(|caches| caches.borrow_mut().retain(|tab_id, _| keep(*tab_id)));
+}
+
+/// Build the layout job for one line.
+fn line_job(line: &str, spans: &Spans, style: &RawStyle, wrap_width: f32) -> LayoutJob {
+    let mut job = LayoutJob {
+        text: line.to_string(),
+        wrap: TextWrapping {
+            max_width: wrap_width,
+            ..Default::default()
+        },
+        // Line heights must add up exactly when the galleys are stitched
+        round_output_size_to_nearest_ui_point: false,
+        ..Default::default()
+    };
+    for (range, token) in spans {
+        job.sections.push(LayoutSection {
+            leading_space: 0.0,
+            byte_range: range.clone(),
+            format: style.format(*token),
+        });
+    }
+    if job.sections.is_empty() {
+        // An empty line still needs a font to get its height
+        job.sections.push(LayoutSection {
+            leading_space: 0.0,
+            byte_range: 0..0,
+            format: style.format(Token::new(TokenKind::Text)),
+        });
+    }
+    job
+}
+
+/// Where the next line galley goes when stacking them.
+#[derive(Debug, Clone, Copy, Default)]
+struct StackOffset {
+    y: f32,
+    section: u32,
+    byte: usize,
+}
+
+impl StackOffset {
+    /// The offset just below `galley`.
+    fn after(self, galley: &Galley) -> Self {
+        Self {
+            y: self.y + galley.rect.height(),
+            section: self.section + galley.job.sections.len() as u32,
+            byte: self.byte + galley.job.text.len() + 1,
+        }
+    }
+}
+
+/// Append a line galley's rows and sections, moved down to `at`.
+fn push_line(
+    rows: &mut Vec<Row>,
+    sections: &mut Vec<LayoutSection>,
+    galley: &Galley,
+    at: StackOffset,
+    is_last: bool,
+) {
+    for section in &galley.job.sections {
+        let mut section = section.clone();
+        section.byte_range = section.byte_range.start + at.byte..section.byte_range.end + at.byte;
+        sections.push(section);
+    }
+
+    let offset = vec2(0.0, at.y);
+    let row_count = galley.rows.len();
+    for (row_index, row) in galley.rows.iter().enumerate() {
+        let mut row = row.clone();
+        row.section_index_at_start += at.section;
+        row.rect = row.rect.translate(offset);
+        for glyph in &mut row.glyphs {
+            glyph.pos.y += at.y;
+            glyph.section_index += at.section;
+        }
+        row.visuals.mesh.translate(offset);
+        row.visuals.mesh_bounds = row.visuals.mesh_bounds.translate(offset);
+        if row_index + 1 == row_count {
+            row.ends_with_newline = !is_last;
+        }
+        rows.push(row);
+    }
+}
+
+/// Stack line galleys into one galley for the whole text.
+///
+/// Rows, glyphs and meshes are shifted down by the height of the lines
+/// above them, and section indices are offset into the combined job.
+fn stitch_galleys(text: &str, galleys: &[Arc<Galley>]) -> Galley {
+    let mut job = LayoutJob {
+        text: text.to_string(),
+        round_output_size_to_nearest_ui_point: false,
+        ..Default::default()
+    };
+    if let Some(first) = galleys.first() {
+        job.wrap = first.job.wrap.clone();
+    }
+
+    let mut rows = Vec::new();
+    let mut at = StackOffset::default();
+    for (index, galley) in galleys.iter().enumerate() {
+        push_line(
+            &mut rows,
+            &mut job.sections,
+            galley,
+            at,
+            index + 1 == galleys.len(),
+        );
+        at = at.after(galley);
+    }
+
+    let mut galley = Galley {
+        job: Arc::new(job),
+        rows,
+        elided: false,
+        rect: Rect::ZERO,
+        mesh_bounds: Rect::NOTHING,
+        num_vertices: 0,
+        num_indices: 0,
+        pixels_per_point: galleys.first().map_or(1.0, |g| g.pixels_per_point),
+    };
+    update_bounds(&mut galley, galleys);
+    galley
+}
+
+/// Turn a galley stitched from `old_lines` into one for `text`, made of
+/// `new_lines`.
+///
+/// Lines shared at the start and end of both are kept: the rows before the
+/// first changed line stay as they are, and the rows after the last one are
+/// only moved.
+fn patch_galley(
+    galley: &mut Galley,
+    text: &str,
+    old_lines: &[Arc<Galley>],
+    new_lines: &[Arc<Galley>],
+) {
+    let prefix = old_lines
+        .iter()
+        .zip(new_lines)
+        .take_while(|(old, new)| Arc::ptr_eq(old, new))
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(old, new)| Arc::ptr_eq(old, new))
+        .count();
+    let old_changed = &old_lines[prefix..old_lines.len() - suffix];
+    let new_changed = &new_lines[prefix..new_lines.len() - suffix];
+
+    let start = old_lines[..prefix]
+        .iter()
+        .fold(StackOffset::default(), |at, line| at.after(line));
+    let old_end = old_changed.iter().fold(start, |at, line| at.after(line));
+    let first_row: usize = old_lines[..prefix].iter().map(|g| g.rows.len()).sum();
+    let old_row_count: usize = old_changed.iter().map(|g| g.rows.len()).sum();
+
+    let mut rows = Vec::new();
+    let mut sections = Vec::new();
+    let mut new_end = start;
+    for (index, line) in new_changed.iter().enumerate() {
+        let is_last = prefix + index + 1 == new_lines.len();
+        push_line(&mut rows, &mut sections, line, new_end, is_last);
+        new_end = new_end.after(line);
+    }
+    let new_row_count = rows.len();
+
+    let job = Arc::make_mut(&mut galley.job);
+    job.text.clear();
+    job.text.push_str(text);
+    job.sections
+        .splice(start.section as usize..old_end.section as usize, sections);
+    for section in &mut job.sections[new_end.section as usize..] {
+        section.byte_range = section.byte_range.start - old_end.byte + new_end.byte
+            ..section.byte_range.end - old_end.byte + new_end.byte;
+    }
+
+    galley
+        .rows
+        .splice(first_row..first_row + old_row_count, rows);
+    if prefix > 0 {
+        galley.rows[first_row - 1].ends_with_newline = prefix < new_lines.len();
+    }
+    // Rows below the change only move if its height or section count changed
+    let dy = new_end.y - old_end.y;
+    let renumbered = new_end.section != old_end.section;
+    if dy != 0.0 || renumbered {
+        let offset = vec2(0.0, dy);
+        for row in &mut galley.rows[first_row + new_row_count..] {
+            row.section_index_at_start =
+                row.section_index_at_start - old_end.section + new_end.section;
+            row.rect = row.rect.translate(offset);
+            if dy != 0.0 {
+                row.visuals.mesh.translate(offset);
+            }
+            row.visuals.mesh_bounds = row.visuals.mesh_bounds.translate(offset);
+            for glyph in &mut row.glyphs {
+                glyph.pos.y += dy;
+                glyph.section_index = glyph.section_index - old_end.section + new_end.section;
+            }
+        }
+    }
+
+    update_bounds(galley, new_lines);
+}
+
+/// Recompute a stitched galley's size and mesh totals.
+fn update_bounds(galley: &mut Galley, lines: &[Arc<Galley>]) {
+    let mut rect = Rect::ZERO;
+    let mut y = 0.0;
+    for line in lines {
+        rect = rect.union(line.rect.translate(vec2(0.0, y)));
+        y += line.rect.height();
+    }
+    galley.rect = rect;
+
+    galley.mesh_bounds = Rect::NOTHING;
+    galley.num_vertices = 0;
+    galley.num_indices = 0;
+    for row in &galley.rows {
+        galley.mesh_bounds = galley.mesh_bounds.union(row.visuals.mesh_bounds);
+        galley.num_vertices += row.visuals.mesh.vertices.len();
+        galley.num_indices += row.visuals.mesh.indices.len();
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tokenize a Markdown line into (text, kind) pairs.
+    fn tokens(line: &str) -> Vec<(&str, TokenKind)> {
+        let (spans, _) = highlight_line(line, &LineState::Markdown, false);
+        spans
+            .into_iter()
+            .map(|(range, token)| (&line[range], token.kind))
+            .collect()
+    }
+
+    fn token_at(line: &str, needle: &str) -> Token {
+        let (spans, _) = highlight_line(line, &LineState::Markdown, false);
+        let pos = line.find(needle).unwrap();
+        spans
+            .into_iter()
+            .find(|(range, _)| range.contains(&pos))
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn test_spans_cover_line() {
+        for line in [
+            "# Title with `code`",
+            "- [x] done **bold** and [link](http://a.b)",
+            "> quoted _text_ <span>html</span>",
+            "| a | b |",
+            "plain ünïcödé text ~~gone~~",
+        ] {
+            let (spans, _) = highlight_line(line, &LineState::Markdown, false);
+            let mut pos = 0;
+            for (range, _) in spans {
+                assert_eq!(range.start, pos, "gap in {:?}", line);
+                pos = range.end;
+            }
+            assert_eq!(pos, line.len());
+        }
+    }
+
+    #[test]
+    fn test_block_tokens() {
+        assert_eq!(
+            tokens("## Heading"),
+            vec![("## ", TokenKind::Marker), ("Heading", TokenKind::Heading)]
+        );
+        assert_eq!(tokens("---"), vec![("---", TokenKind::Rule)]);
+        assert_eq!(
+            tokens("- [ ] task"),
+            vec![
+                ("- ", TokenKind::ListMarker),
+                ("[ ] ", TokenKind::TaskBox),
+                ("task", TokenKind::Text)
+            ]
+        );
+        assert_eq!(
+            tokens("> quote"),
+            vec![("> ", TokenKind::Marker), ("quote", TokenKind::Quote)]
+        );
+        assert_eq!(
+            tokens("|---|:-:|"),
+            vec![("|---|:-:|", TokenKind::TablePipe)]
+        );
+        assert_eq!(tokens("#hashtag"), vec![("#hashtag", TokenKind::Text)]);
+    }
+
+    #[test]
+    fn test_inline_tokens() {
+        assert!(token_at("a **bold** b", "bold").strong);
+        assert!(token_at("a *it* b", "it").emphasis);
+        assert!(token_at("a ***both*** b", "both").strong);
+        assert!(token_at("a ~~gone~~ b", "gone").strike);
+        assert!(!token_at("snake_case_name", "case").emphasis);
+        assert!(!token_at("2 * 3 * 4", "3").emphasis);
+        assert_eq!(token_at("use `x * y` here", "y").kind, TokenKind::CodeSpan);
+        assert_eq!(token_at(r"\*not\*", "not").kind, TokenKind::Text);
+    }
+
+    #[test]
+    fn test_link_tokens() {
+        assert_eq!(
+            tokens("[docs](https://x.org)"),
+            vec![
+                ("[", TokenKind::Marker),
+                ("docs", TokenKind::LinkText),
+                ("](", TokenKind::Marker),
+                ("https://x.org", TokenKind::LinkUrl),
+                (")", TokenKind::Marker)
+            ]
+        );
+        assert_eq!(token_at("![alt](a.png)", "a.png").kind, TokenKind::LinkUrl);
+        assert_eq!(
+            token_at("see https://x.org/a.", "x.org").kind,
+            TokenKind::LinkUrl
+        );
+        assert_eq!(
+            token_at("see https://x.org/a.", ".").kind,
+            TokenKind::LinkUrl
+        );
+        assert_eq!(token_at("a <br/> b", "br").kind, TokenKind::Html);
+        assert_eq!(token_at("x < y > z", "y").kind, TokenKind::Text);
+    }
+
+    #[test]
+    fn test_fenced_code_block_state() {
+        let (_, state) = highlight_line("```rust", &LineState::Markdown, false);
+        let LineState::Code(block) = &state else {
+            panic!("expected code state");
+        };
+        assert!(block.syntax.is_some());
+
+        let (spans, inside) = highlight_line("let x = \"# not a heading\";", &state, false);
+        assert!(spans
+            .iter()
+            .all(|(_, t)| matches!(t.kind, TokenKind::Highlighted(_))));
+
+        let (spans, after) = highlight_line("```", &inside, false);
+        assert_eq!(spans[0].1.kind, TokenKind::Fence);
+        assert_eq!(after, LineState::Markdown);
+
+        // A shorter fence doesn't close a longer one
+        let (_, state) = highlight_line("````", &LineState::Markdown, false);
+        let (_, still) = highlight_line("```", &state, false);
+        assert!(matches!(still, LineState::Code(_)));
+    }
+
+    #[test]
+    fn test_highlighting_for_tab() {
+        let mut tab = Tab::new(0);
+        assert_eq!(Highlighting::for_tab(&tab), Highlighting::Markdown);
+        tab.set_path("config.yml".into());
+        assert_eq!(
+            Highlighting::for_tab(&tab),
+            Highlighting::Code("yaml".into())
+        );
+        tab.set_path("main.rs".into());
+        assert_eq!(Highlighting::for_tab(&tab), Highlighting::Code("rs".into()));
+        tab.set_path("notes.unknownext".into());
+        assert_eq!(Highlighting::for_tab(&tab), Highlighting::Plain);
+    }
+
+    #[test]
+    fn test_stitched_layout_matches_text() {
+        let fonts = Fonts::new(1.0, 2048, fonts::create_font_definitions());
+        let style = RawStyle::new(
+            &ThemeColors::dark(),
+            Highlighting::Markdown,
+            EditorFont::default(),
+            14.0,
+        );
+        let text = "# Title\n\nSome **bold** text\n```rust\nfn main() {}\n```\n";
+
+        let mut cache = RawLayoutCache::default();
+        let galley = cache.layout(&fonts, text, &style, f32::INFINITY);
+        assert_eq!(galley.job.text, text);
+        assert_eq!(galley.rows.len(), text.split('\n').count());
+        assert_eq!(
+            galley
+                .rows
+                .iter()
+                .filter(|row| row.ends_with_newline)
+                .count(),
+            text.matches('\n').count()
+        );
+        let chars: usize = galley
+            .rows
+            .iter()
+            .map(|r| r.char_count_including_newline())
+            .sum();
+        assert_eq!(chars, text.chars().count());
+
+        // Rows are stacked without overlap
+        for pair in galley.rows.windows(2) {
+            assert!((pair[0].rect.max.y - pair[1].rect.min.y).abs() < 0.01);
+        }
+
+        // Editing one line lays out only that line again
+        let before: Vec<Arc<Galley>> = cache.lines.iter().map(|l| l.galley.clone()).collect();
+        let edited = text.replace("Some", "More");
+        let again = cache.layout(&fonts, &edited, &style, f32::INFINITY);
+        assert_eq!(again.job.text, edited);
+        let relaid: Vec<usize> = (0..before.len())
+            .filter(|&i| !Arc::ptr_eq(&cache.lines[i].galley, &before[i]))
+            .collect();
+        assert_eq!(relaid, vec![2]);
+    }
+
+    #[test]
+    fn test_edits_rehighlight_until_states_match() {
+        let fonts = Fonts::new(1.0, 2048, fonts::create_font_definitions());
+        let style = RawStyle::new(
+            &ThemeColors::dark(),
+            Highlighting::Markdown,
+            EditorFont::default(),
+            14.0,
+        );
+        let layout = |cache: &mut RawLayoutCache, text: &str| {
+            drop(cache.layout(&fonts, text, &style, f32::INFINITY));
+            cache
+                .lines
+                .iter()
+                .map(|l| l.galley.clone())
+                .collect::<Vec<_>>()
+        };
+        let mut cache = RawLayoutCache::default();
+        let texts = [
+            "# Title\n\ntext\n\n```rust\nfn main() {}\n```\n\nlast *line*",
+            // Opening a fence restyles the lines below it
+            "# Title\n\n```\n\n```rust\nfn main() {}\n```\n\nlast *line*",
+            // Closing it again restores them
+            "# Title\n\n```\n```\n\n```rust\nfn main() {}\n```\n\nlast *line*",
+            // Multi-byte text, and edits joining and splitting lines
+            "# Tïtle é\n\n```\n```\n\n```rust\nfn main() {}\n```\n\nlast *line*",
+            "# Tïtle é```\n```\n\n```rust\nfn main() {}\n```\n\nlast *line*",
+            "# T\nïtle\né```\n```\n\n```rust\nfn main() {}\n```\n\nlast *line*",
+            "",
+            "\n\n",
+        ];
+
+        for text in texts {
+            let lines = layout(&mut cache, text);
+            let mut fresh = RawLayoutCache::default();
+            let expected = layout(&mut fresh, text);
+
+            // Same highlighting as a layout from scratch
+            assert_eq!(lines.len(), expected.len());
+            for (a, b) in lines.iter().zip(&expected) {
+                assert_eq!(a.job.text, b.job.text);
+                assert_eq!(a.job.sections, b.job.sections);
+            }
+            for (i, line) in cache.lines.iter().enumerate() {
+                assert_eq!(line.start_state, fresh.lines[i].start_state);
+                assert_eq!(cache.line_starts[i], fresh.line_starts[i]);
+            }
+        }
+
+        // Indices of the lines laid out again by an edit keeping the count
+        let mut relaid = |from: &str, to: &str| {
+            let before = layout(&mut cache, from);
+            let after = layout(&mut cache, to);
+            (0..after.len())
+                .filter(|&i| !Arc::ptr_eq(&before[i], &after[i]))
+                .collect::<Vec<_>>()
+        };
+        let doc = "a\nb\nc\nd";
+        assert_eq!(relaid(doc, "A\nb\nc\nd"), vec![0]);
+        // A new fence restyles every line below it, closing it only the
+        // lines up to the close
+        assert_eq!(relaid(doc, "a\n```\nc\nd"), vec![1, 2, 3]);
+        assert_eq!(relaid("a\n```\nc\nd", "a\n```\n```\nd"), vec![2, 3]);
+        // Inside a highlighted code block, lines that end in the same parser
+        // state stop the re-highlighting
+        let code = "```rust\nlet a = 1;\nlet b = 2;\n```\nafter";
+        assert_eq!(relaid(code, &code.replace("1;", "3;")), vec![1]);
+    }
+
+    #[test]
+    fn test_patched_layout_matches_full_stitch() {
+        let fonts = Fonts::new(1.0, 2048, fonts::create_font_definitions());
+        let style = RawStyle::new(
+            &ThemeColors::dark(),
+            Highlighting::Markdown,
+            EditorFont::default(),
+            14.0,
+        );
+        let mut cache = RawLayoutCache::default();
+        let texts = [
+            "# Title\n\nSome **bold** text\n\nend",
+            // Edit in the middle
+            "# Title\n\nMore **bold** text\n\nend",
+            // Insert and remove lines above unchanged ones
+            "# Title\n\nNew line\n- item\nMore **bold** text\n\nend",
+            "# Title\n\nMore **bold** text\n\nend",
+            // Insert lines, one opening a code block that restyles the rest
+            "# Title\n\n```rust\nMore **bold** text\n\nend",
+            // Append a line
+            "# Title\n\n```rust\nMore **bold** text\n\nend\nmore",
+            // Remove lines at the end
+            "# Title",
+            "",
+        ];
+
+        for text in texts {
+            // Dropping the result lets the cache patch its galley in place
+            drop(cache.layout(&fonts, text, &style, f32::INFINITY));
+            let patched = cache.layout(&fonts, text, &style, f32::INFINITY);
+            let full = stitch_galleys(text, &cache.last.as_ref().unwrap().lines);
+
+            assert_eq!(patched.job.text, text);
+            assert_eq!(patched.job.sections, full.job.sections);
+            assert_eq!(patched.rect, full.rect);
+            assert_eq!(patched.num_vertices, full.num_vertices);
+            assert_eq!(patched.rows.len(), full.rows.len());
+            for (a, b) in patched.rows.iter().zip(&full.rows) {
+                assert_eq!(a.section_index_at_start, b.section_index_at_start);
+                assert_eq!(a.ends_with_newline, b.ends_with_newline);
+                assert!((a.rect.min.y - b.rect.min.y).abs() < 0.01);
+                assert_eq!(a.visuals.mesh.vertices.len(), b.visuals.mesh.vertices.len());
+                let glyphs = a.glyphs.iter().zip(&b.glyphs);
+                for (ga, gb) in glyphs {
+                    assert_eq!(ga.section_index, gb.section_index);
+                    assert!((ga.pos.y - gb.pos.y).abs() < 0.01);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,738 @@
+//! Operation-based undo/redo history for Ferrite
+//!
+//! Instead of storing a full copy of the document for every edit, the history
+//! records text deltas: the byte range that was replaced, the text that was
+//! removed and the text that was inserted, together with the cursor and
+//! selection before and after the edit.
+//!
+//! Key features:
+//! - Memory usage proportional to the size of the edits, not the document
+//! - Consecutive typing and deleting merge into a single undo step
+//! - History is capped by a byte budget instead of a fixed entry count
+
+use log::warn;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Default memory budget for a single tab's undo history (8 MB).
+pub const DEFAULT_HISTORY_BUDGET: usize = 8 * 1024 * 1024;
+
+/// Maximum time between two edits for them to merge into one undo step.
+const MERGE_WINDOW: Duration = Duration::from_millis(1000);
+
+/// Maximum number of characters a single edit may change and still be
+/// considered "typing" (fast typists can produce several chars per frame).
+const MAX_MERGE_CHARS: usize = 4;
+
+/// Fixed bookkeeping cost charged per delta against the byte budget.
+const DELTA_OVERHEAD: usize = 64;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Cursor Snapshot
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Cursor and selection state captured around an edit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CursorSnapshot {
+    /// Cursor position (line, column) - 0-indexed
+    pub cursor: (usize, usize),
+    /// Selection range (start_char_index, end_char_index)
+    pub selection: Option<(usize, usize)>,
+}
+
+impl CursorSnapshot {
+    /// Create a snapshot from a cursor position and optional selection.
+    pub fn new(cursor: (usize, usize), selection: Option<(usize, usize)>) -> Self {
+        Self { cursor, selection }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Edit Delta
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// The kind of change a delta represents (used for merge decisions).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeltaKind {
+    /// Text was only inserted
+    Insert,
+    /// Text was only deleted
+    Delete,
+    /// Text was replaced (or a non-mergeable edit such as formatting)
+    Replace,
+}
+
+/// A single recorded text change.
+///
+/// Applying the delta replaces `deleted` at byte offset `start` with
+/// `inserted`; reverting it does the opposite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditDelta {
+    /// Byte offset where the change starts
+    pub start: usize,
+    /// Text that was removed by the edit
+    pub deleted: String,
+    /// Text that was inserted by the edit
+    pub inserted: String,
+    /// Cursor and selection before the edit
+    pub before: CursorSnapshot,
+    /// Cursor and selection after the edit
+    pub after: CursorSnapshot,
+}
+
+impl EditDelta {
+    /// Compute the delta that turns `old` into `new`.
+    ///
+    /// Uses the longest common prefix and suffix, so the result is a single
+    /// contiguous replacement. Returns `None` if the texts are identical.
+    pub fn diff(old: &str, new: &str) -> Option<Self> {
+        if old == new {
+            return None;
+        }
+
+        let prefix = common_prefix_len(old, new);
+        let suffix = common_suffix_len(&old[prefix..], &new[prefix..]);
+
+        let start = prefix;
+        let deleted = old[prefix..old.len() - suffix].to_string();
+        let inserted = new[prefix..new.len() - suffix].to_string();
+
+        // Place the cursor after the inserted text
+        let cursor_char = new[..start].chars().count() + inserted.chars().count();
+        let after = CursorSnapshot::new(char_index_to_line_col(new, cursor_char), None);
+
+        Some(Self {
+            start,
+            deleted,
+            inserted,
+            before: CursorSnapshot::default(),
+            after,
+        })
+    }
+
+    /// Classify this delta.
+    pub fn kind(&self) -> DeltaKind {
+        match (self.deleted.is_empty(), self.inserted.is_empty()) {
+            (true, false) => DeltaKind::Insert,
+            (false, true) => DeltaKind::Delete,
+            _ => DeltaKind::Replace,
+        }
+    }
+
+    /// Approximate memory cost of this delta in bytes.
+    pub fn cost(&self) -> usize {
+        self.deleted.len() + self.inserted.len() + DELTA_OVERHEAD
+    }
+
+    /// Apply the delta to `text` (redo direction).
+    ///
+    /// Returns `false` and leaves `text` alone if it doesn't hold the
+    /// deleted text at `start` (it was changed without being recorded).
+    pub fn apply(&self, text: &mut String) -> bool {
+        replace_checked(text, self.start, &self.deleted, &self.inserted)
+    }
+
+    /// Revert the delta on `text` (undo direction).
+    ///
+    /// Returns `false` and leaves `text` alone if it doesn't hold the
+    /// inserted text at `start`.
+    pub fn revert(&self, text: &mut String) -> bool {
+        replace_checked(text, self.start, &self.inserted, &self.deleted)
+    }
+
+    /// Whether this delta looks like a single keystroke of typing or deleting.
+    fn is_typing(&self) -> bool {
+        match self.kind() {
+            DeltaKind::Insert => {
+                !self.inserted.contains('\n') && self.inserted.chars().count() <= MAX_MERGE_CHARS
+            }
+            DeltaKind::Delete => {
+                !self.deleted.contains('\n') && self.deleted.chars().count() <= MAX_MERGE_CHARS
+            }
+            DeltaKind::Replace => false,
+        }
+    }
+
+    /// Try to merge `next` (which happened right after `self`) into `self`.
+    ///
+    /// Returns `true` if the deltas were merged.
+    fn try_merge(&mut self, next: &EditDelta) -> bool {
+        if !next.is_typing() || self.kind() != next.kind() {
+            return false;
+        }
+
+        match next.kind() {
+            DeltaKind::Insert => {
+                // Typing continues where the previous insert ended
+                if next.start != self.start + self.inserted.len() {
+                    return false;
+                }
+                // Break the step at word boundaries, like most editors
+                let ends_word = self
+                    .inserted
+                    .chars()
+                    .last()
+                    .map(|c| c.is_whitespace())
+                    .unwrap_or(false);
+                let starts_word = next
+                    .inserted
+                    .chars()
+                    .next()
+                    .map(|c| !c.is_whitespace())
+                    .unwrap_or(false);
+                if ends_word && starts_word {
+                    return false;
+                }
+                self.inserted.push_str(&next.inserted);
+            }
+            DeltaKind::Delete => {
+                if next.start + next.deleted.len() == self.start {
+                    // Backspace: deleting towards the start of the document
+                    self.start = next.start;
+                    self.deleted.insert_str(0, &next.deleted);
+                } else if next.start == self.start {
+                    // Forward delete: deleting at the same position
+                    self.deleted.push_str(&next.deleted);
+                } else {
+                    return false;
+                }
+            }
+            DeltaKind::Replace => return false,
+        }
+
+        self.after = next.after;
+        true
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Edit History
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Undo/redo history made of text deltas, limited by a byte budget.
+#[derive(Debug, Clone)]
+pub struct EditHistory {
+    /// Undo steps (oldest first)
+    undo_stack: VecDeque<EditDelta>,
+    /// Redo steps (most recently undone last)
+    redo_stack: Vec<EditDelta>,
+    /// Maximum bytes the undo and redo stacks may use together
+    budget: usize,
+    /// Bytes currently used by both stacks
+    used: usize,
+    /// When the last edit was recorded (for typing merges)
+    last_edit_at: Option<Instant>,
+    /// Whether the next recorded edit may merge with the previous one
+    merge_allowed: bool,
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EditHistory {
+    /// Create an empty history with the default byte budget.
+    pub fn new() -> Self {
+        Self::with_budget(DEFAULT_HISTORY_BUDGET)
+    }
+
+    /// Create an empty history with a custom byte budget.
+    pub fn with_budget(budget: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            budget,
+            used: 0,
+            last_edit_at: None,
+            merge_allowed: false,
+        }
+    }
+
+    /// Record a change from `old` to `new`.
+    ///
+    /// When `mergeable` is true and the edit continues a typing burst
+    /// (adjacent, same kind, within the merge window), it is folded into
+    /// the previous undo step. Returns `true` if anything was recorded.
+    pub fn record(
+        &mut self,
+        old: &str,
+        new: &str,
+        before: CursorSnapshot,
+        mergeable: bool,
+        now: Instant,
+    ) -> bool {
+        let Some(mut delta) = EditDelta::diff(old, new) else {
+            return false;
+        };
+        delta.before = before;
+        let typing = delta.is_typing();
+
+        // Any new edit invalidates the redo history
+        self.clear_redo();
+
+        let within_window = self
+            .last_edit_at
+            .map(|t| now.saturating_duration_since(t) <= MERGE_WINDOW)
+            .unwrap_or(false);
+
+        let merged = mergeable
+            && self.merge_allowed
+            && within_window
+            && match self.undo_stack.back_mut() {
+                Some(last) => {
+                    let old_cost = last.cost();
+                    if last.try_merge(&delta) {
+                        self.used = self.used - old_cost + last.cost();
+                        true
+                    } else {
+                        false
+                    }
+                }
+                None => false,
+            };
+
+        if !merged {
+            self.used += delta.cost();
+            self.undo_stack.push_back(delta);
+        }
+
+        self.last_edit_at = Some(now);
+        // Only typing can be continued by the next edit (not pastes)
+        self.merge_allowed = mergeable && typing;
+        self.enforce_budget();
+        true
+    }
+
+    /// Undo the most recent step on `text`.
+    ///
+    /// Returns the cursor state to restore, or `None` if there is nothing to undo.
+    ///
+    /// If `text` no longer matches the history (it was changed without
+    /// being recorded), nothing is undone and the history is cleared.
+    pub fn undo(&mut self, text: &mut String) -> Option<CursorSnapshot> {
+        let delta = self.undo_stack.pop_back()?;
+        if !delta.revert(text) {
+            warn!("Undo history doesn't match the text, clearing it");
+            self.clear();
+            return None;
+        }
+        let cursor = delta.before;
+        self.redo_stack.push(delta);
+        self.break_merge();
+        Some(cursor)
+    }
+
+    /// Redo the most recently undone step on `text`.
+    ///
+    /// Returns the cursor state to restore, or `None` if there is nothing to redo.
+    ///
+    /// Like [`Self::undo`], clears the history if `text` doesn't match it.
+    pub fn redo(&mut self, text: &mut String) -> Option<CursorSnapshot> {
+        let delta = self.redo_stack.pop()?;
+        if !delta.apply(text) {
+            warn!("Redo history doesn't match the text, clearing it");
+            self.clear();
+            return None;
+        }
+        let cursor = delta.after;
+        self.undo_stack.push_back(delta);
+        self.break_merge();
+        self.enforce_budget();
+        Some(cursor)
+    }
+
+    /// Prevent the next edit from merging into the current undo step.
+    pub fn break_merge(&mut self) {
+        self.merge_allowed = false;
+    }
+
+    /// Remove all undo and redo steps.
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.used = 0;
+        self.last_edit_at = None;
+        self.merge_allowed = false;
+    }
+
+    /// Check if undo is available.
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// Check if redo is available.
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Number of undo steps.
+    pub fn undo_count(&self) -> usize {
+        self.undo_stack.len()
+    }
+
+    /// Number of redo steps.
+    pub fn redo_count(&self) -> usize {
+        self.redo_stack.len()
+    }
+
+    /// Bytes currently used by the history.
+    pub fn used_bytes(&self) -> usize {
+        self.used
+    }
+
+    /// Change the byte budget, evicting old steps if necessary.
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        self.enforce_budget();
+    }
+
+    /// Drop the redo stack and release its memory.
+    fn clear_redo(&mut self) {
+        let freed: usize = self.redo_stack.iter().map(|d| d.cost()).sum();
+        self.used -= freed;
+        self.redo_stack.clear();
+    }
+
+    /// Evict the steps furthest from the current text until the history
+    /// fits the budget: the oldest undo steps, then the last redo steps.
+    ///
+    /// The nearest undo and redo steps are always kept so that a single
+    /// large edit can still be undone and redone.
+    fn enforce_budget(&mut self) {
+        while self.used > self.budget && self.undo_stack.len() > 1 {
+            if let Some(oldest) = self.undo_stack.pop_front() {
+                self.used -= oldest.cost();
+            }
+        }
+        while self.used > self.budget && self.redo_stack.len() > 1 {
+            let furthest = self.redo_stack.remove(0);
+            self.used -= furthest.cost();
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Helper Functions
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Replace `expected` at byte offset `start` with `replacement`, if `text`
+/// holds exactly `expected` there.
+fn replace_checked(text: &mut String, start: usize, expected: &str, replacement: &str) -> bool {
+    let end = start + expected.len();
+    if text.get(start..end) != Some(expected) {
+        return false;
+    }
+    text.replace_range(start..end, replacement);
+    true
+}
+
+/// Length in bytes of the common prefix of two strings (on a char boundary).
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, ca), cb)| ca != cb)
+        .map(|((i, _), _)| i)
+        .unwrap_or_else(|| a.len().min(b.len()))
+}
+
+/// Length in bytes of the common suffix of two strings (on a char boundary).
+fn common_suffix_len(a: &str, b: &str) -> usize {
+    let mut len = 0;
+    for (ca, cb) in a.chars().rev().zip(b.chars().rev()) {
+        if ca != cb {
+            break;
+        }
+        len += ca.len_utf8();
+    }
+    len
+}
+
+/// Convert a character index to (line, column), both 0-indexed.
+fn char_index_to_line_col(text: &str, char_index: usize) -> (usize, usize) {
+    let mut line = 0;
+    let mut col = 0;
+
+    for ch in text.chars().take(char_index) {
+        if ch == '\n' {
+            line += 1;
+            col = 0;
+        } else {
+            col += 1;
+        }
+    }
+
+    (line, col)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_chars(history: &mut EditHistory, text: &mut String, chars: &str, start: Instant) {
+        for (i, ch) in chars.chars().enumerate() {
+            let old = text.clone();
+            text.push(ch);
+            let now = start + Duration::from_millis(50 * i as u64);
+            history.record(&old, text, CursorSnapshot::default(), true, now);
+        }
+    }
+
+    #[test]
+    fn test_diff_insert() {
+        let delta = EditDelta::diff("Hello World", "Hello, World").unwrap();
+        assert_eq!(delta.start, 5);
+        assert_eq!(delta.deleted, "");
+        assert_eq!(delta.inserted, ",");
+        assert_eq!(delta.kind(), DeltaKind::Insert);
+        assert_eq!(delta.after.cursor, (0, 6));
+    }
+
+    #[test]
+    fn test_diff_delete_and_replace() {
+        let delta = EditDelta::diff("abcdef", "abef").unwrap();
+        assert_eq!(delta.start, 2);
+        assert_eq!(delta.deleted, "cd");
+        assert_eq!(delta.kind(), DeltaKind::Delete);
+
+        let delta = EditDelta::diff("foo bar", "foo baz").unwrap();
+        assert_eq!(delta.deleted, "r");
+        assert_eq!(delta.inserted, "z");
+        assert_eq!(delta.kind(), DeltaKind::Replace);
+
+        assert!(EditDelta::diff("same", "same").is_none());
+    }
+
+    #[test]
+    fn test_diff_multibyte() {
+        let delta = EditDelta::diff("héllo", "hé—llo").unwrap();
+        assert_eq!(delta.inserted, "—");
+        let mut text = "héllo".to_string();
+        delta.apply(&mut text);
+        assert_eq!(text, "hé—llo");
+        delta.revert(&mut text);
+        assert_eq!(text, "héllo");
+    }
+
+    #[test]
+    fn test_diff_repeated_chars() {
+        // Inserting into a run of identical characters must still round-trip
+        let delta = EditDelta::diff("aaa", "aaaa").unwrap();
+        let mut text = "aaa".to_string();
+        delta.apply(&mut text);
+        assert_eq!(text, "aaaa");
+        delta.revert(&mut text);
+        assert_eq!(text, "aaa");
+    }
+
+    #[test]
+    fn test_typing_merges_into_one_step() {
+        let mut history = EditHistory::new();
+        let mut text = String::new();
+        type_chars(&mut history, &mut text, "hello", Instant::now());
+
+        assert_eq!(history.undo_count(), 1);
+        assert!(history.undo(&mut text).is_some());
+        assert_eq!(text, "");
+    }
+
+    #[test]
+    fn test_typing_breaks_at_word_boundary() {
+        let mut history = EditHistory::new();
+        let mut text = String::new();
+        type_chars(&mut history, &mut text, "hello world", Instant::now());
+
+        assert_eq!(history.undo_count(), 2);
+        history.undo(&mut text);
+        assert_eq!(text, "hello ");
+    }
+
+    #[test]
+    fn test_typing_pause_starts_new_step() {
+        let mut history = EditHistory::new();
+        let mut text = String::new();
+        let start = Instant::now();
+        type_chars(&mut history, &mut text, "ab", start);
+        type_chars(
+            &mut history,
+            &mut text,
+            "cd",
+            start + Duration::from_secs(5),
+        );
+
+        assert_eq!(history.undo_count(), 2);
+    }
+
+    #[test]
+    fn test_backspace_merges() {
+        let mut history = EditHistory::new();
+        let mut text = "hello".to_string();
+        let start = Instant::now();
+        for i in 0..3 {
+            let old = text.clone();
+            text.pop();
+            let now = start + Duration::from_millis(50 * i);
+            history.record(&old, &text, CursorSnapshot::default(), true, now);
+        }
+
+        assert_eq!(text, "he");
+        assert_eq!(history.undo_count(), 1);
+        history.undo(&mut text);
+        assert_eq!(text, "hello");
+    }
+
+    #[test]
+    fn test_non_mergeable_edit_is_separate_step() {
+        let mut history = EditHistory::new();
+        let now = Instant::now();
+        history.record("", "a", CursorSnapshot::default(), true, now);
+        history.record("a", "ab", CursorSnapshot::default(), false, now);
+        history.record("ab", "abc", CursorSnapshot::default(), true, now);
+
+        assert_eq!(history.undo_count(), 3);
+    }
+
+    #[test]
+    fn test_paste_is_not_merged_with_typing() {
+        let mut history = EditHistory::new();
+        let now = Instant::now();
+        history.record("", "pasted text", CursorSnapshot::default(), true, now);
+        history.record(
+            "pasted text",
+            "pasted texts",
+            CursorSnapshot::default(),
+            true,
+            now,
+        );
+
+        assert_eq!(history.undo_count(), 2);
+    }
+
+    #[test]
+    fn test_undo_redo_restores_cursor() {
+        let mut history = EditHistory::new();
+        let before = CursorSnapshot::new((0, 3), Some((1, 3)));
+        history.record("abcdef", "aXdef", before, false, Instant::now());
+
+        let mut text = "aXdef".to_string();
+        let restored = history.undo(&mut text).unwrap();
+        assert_eq!(text, "abcdef");
+        assert_eq!(restored, before);
+
+        let restored = history.redo(&mut text).unwrap();
+        assert_eq!(text, "aXdef");
+        assert_eq!(restored.cursor, (0, 2));
+        assert_eq!(restored.selection, None);
+    }
+
+    #[test]
+    fn test_mismatched_text_clears_history() {
+        let mut history = EditHistory::new();
+        let now = Instant::now();
+        history.record(
+            "héllo",
+            "héllo world",
+            CursorSnapshot::default(),
+            false,
+            now,
+        );
+        history.record(
+            "héllo world",
+            "héllo",
+            CursorSnapshot::default(),
+            false,
+            now,
+        );
+
+        // Out of range
+        let mut text = "hé".to_string();
+        assert!(history.undo(&mut text).is_none());
+        assert_eq!(text, "hé");
+        assert!(!history.can_undo() && !history.can_redo());
+
+        // Redo into text where the offset is off a char boundary
+        history.record("é", "éx", CursorSnapshot::default(), false, now);
+        let mut text = "éx".to_string();
+        history.undo(&mut text).unwrap();
+        let mut other = "aé".to_string();
+        assert!(history.redo(&mut other).is_none());
+        assert_eq!(other, "aé");
+        assert!(!history.can_undo() && !history.can_redo());
+    }
+
+    #[test]
+    fn test_new_edit_clears_redo() {
+        let mut history = EditHistory::new();
+        let now = Instant::now();
+        let mut text = "b".to_string();
+        history.record("a", "b", CursorSnapshot::default(), false, now);
+        history.undo(&mut text);
+        assert!(history.can_redo());
+
+        history.record("a", "c", CursorSnapshot::default(), false, now);
+        assert!(!history.can_redo());
+        assert_eq!(history.used_bytes(), history.undo_stack[0].cost());
+    }
+
+    #[test]
+    fn test_byte_budget_evicts_oldest() {
+        let budget = 3 * (DELTA_OVERHEAD + 10);
+        let mut history = EditHistory::with_budget(budget);
+        let now = Instant::now();
+        let mut text = String::new();
+        for i in 0..10 {
+            let old = text.clone();
+            text.push_str(&format!("{:010}", i));
+            history.record(&old, &text, CursorSnapshot::default(), false, now);
+        }
+
+        assert_eq!(history.undo_count(), 3);
+        assert!(history.used_bytes() <= budget);
+
+        while history.undo(&mut text).is_some() {}
+        assert_eq!(
+            text,
+            "0000000000000000000100000000020000000003000000000400000000050000000006"
+        );
+    }
+
+    #[test]
+    fn test_budget_limits_redo_steps() {
+        let mut history = EditHistory::new();
+        let now = Instant::now();
+        let mut text = String::new();
+        for i in 0..10 {
+            let old = text.clone();
+            text.push_str(&format!("{:010}", i));
+            history.record(&old, &text, CursorSnapshot::default(), false, now);
+        }
+        while history.undo(&mut text).is_some() {}
+        assert_eq!(history.redo_count(), 10);
+
+        let budget = 3 * (DELTA_OVERHEAD + 10);
+        history.set_budget(budget);
+        assert_eq!(history.redo_count(), 3);
+        assert!(history.used_bytes() <= budget);
+
+        // The steps nearest the current text are kept
+        while history.redo(&mut text).is_some() {}
+        assert_eq!(text, "000000000000000000010000000002");
+    }
+
+    #[test]
+    fn test_budget_keeps_single_large_edit() {
+        let mut history = EditHistory::with_budget(16);
+        let big = "x".repeat(1000);
+        history.record("", &big, CursorSnapshot::default(), false, Instant::now());
+
+        assert_eq!(history.undo_count(), 1);
+        let mut text = big;
+        history.undo(&mut text);
+        assert!(text.is_empty());
+    }
+}
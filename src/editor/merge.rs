@@ -0,0 +1,394 @@
+//! Line-based three-way merge for Ferrite
+//!
+//! Used when an open file with unsaved edits changes on disk. The content at
+//! the last load/save is the common base, the editor buffer is "mine", and
+//! the new disk content is "theirs". Changes that don't overlap merge
+//! cleanly; overlapping changes are wrapped in git-style conflict markers.
+
+/// Marker opening the "mine" side of a conflict
+pub const CONFLICT_START: &str = "<<<<<<< Mine";
+/// Marker separating the two sides of a conflict
+pub const CONFLICT_SEPARATOR: &str = "=======";
+/// Marker closing the "on disk" side of a conflict
+pub const CONFLICT_END: &str = ">>>>>>> On disk";
+
+/// Result of a three-way merge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeResult {
+    /// Merged text (with conflict markers if `conflicts > 0`)
+    pub text: String,
+    /// Number of conflicting regions
+    pub conflicts: usize,
+}
+
+impl MergeResult {
+    /// Check if the merge completed without conflicts.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts == 0
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Three-way Merge
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Merge `mine` and `theirs`, both derived from `base`.
+///
+/// Works on whole lines (line endings included). Regions changed on only one
+/// side take that side; regions changed identically on both sides are taken
+/// once; anything else becomes a conflict.
+pub fn three_way_merge(base: &str, mine: &str, theirs: &str) -> MergeResult {
+    let base_lines = split_lines(base);
+    let mine_lines = split_lines(mine);
+    let theirs_lines = split_lines(theirs);
+
+    let mine_map = base_to_other(&base_lines, &mine_lines);
+    let theirs_map = base_to_other(&base_lines, &theirs_lines);
+
+    let mut result = MergeResult {
+        text: String::with_capacity(mine.len().max(theirs.len())),
+        conflicts: 0,
+    };
+    let (mut b, mut m, mut t) = (0, 0, 0);
+
+    loop {
+        // Next base line kept unchanged by both sides (a stable anchor)
+        let anchor = (b..base_lines.len())
+            .find_map(|i| Some((i, mine_map[i]?, theirs_map[i]?)))
+            .unwrap_or((base_lines.len(), mine_lines.len(), theirs_lines.len()));
+
+        merge_chunk(
+            &base_lines[b..anchor.0],
+            &mine_lines[m..anchor.1],
+            &theirs_lines[t..anchor.2],
+            &mut result,
+        );
+
+        if anchor.0 >= base_lines.len() {
+            break;
+        }
+        result.text.push_str(base_lines[anchor.0]);
+        (b, m, t) = (anchor.0 + 1, anchor.1 + 1, anchor.2 + 1);
+    }
+
+    result
+}
+
+/// Merge one unstable region between two anchors.
+fn merge_chunk(base: &[&str], mine: &[&str], theirs: &[&str], result: &mut MergeResult) {
+    if mine == theirs || theirs == base {
+        push_lines(&mut result.text, mine);
+    } else if mine == base {
+        push_lines(&mut result.text, theirs);
+    } else {
+        result.conflicts += 1;
+        push_marker(&mut result.text, CONFLICT_START);
+        push_lines(&mut result.text, mine);
+        push_marker(&mut result.text, CONFLICT_SEPARATOR);
+        push_lines(&mut result.text, theirs);
+        push_marker(&mut result.text, CONFLICT_END);
+    }
+}
+
+/// Append lines verbatim.
+fn push_lines(text: &mut String, lines: &[&str]) {
+    for line in lines {
+        text.push_str(line);
+    }
+}
+
+/// Append a conflict marker on its own line.
+fn push_marker(text: &mut String, marker: &str) {
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push_str(marker);
+    text.push('\n');
+}
+
+/// Split text into lines, keeping each line's terminator.
+fn split_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Line Diff
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Map each base line to its matching line in `other`, if it was kept.
+///
+/// Matches come from a longest-common-subsequence diff, so they are
+/// strictly increasing on both sides.
+fn base_to_other(base: &[&str], other: &[&str]) -> Vec<Option<usize>> {
+    let mut map = vec![None; base.len()];
+    for (b, o) in matching_lines(base, other) {
+        map[b] = Some(o);
+    }
+    map
+}
+
+/// Compute matching line pairs between `a` and `b`.
+///
+/// Uses the linear-space variant of Myers' O((N+M)D) diff, so memory stays
+/// proportional to the input even for very different files.
+pub fn matching_lines(a: &[&str], b: &[&str]) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    collect_matches(a, b, 0, 0, &mut matches);
+    matches
+}
+
+/// Append the matches between `a` and `b`, which start at `a_start` and
+/// `b_start` of the full inputs, in order.
+///
+/// Trims the common prefix and suffix, then splits the rest at the middle
+/// snake of the shortest edit script and diffs each side recursively.
+fn collect_matches(
+    a: &[&str],
+    b: &[&str],
+    a_start: usize,
+    b_start: usize,
+    matches: &mut Vec<(usize, usize)>,
+) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    matches.extend((0..prefix).map(|i| (a_start + i, b_start + i)));
+
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+    if !a_mid.is_empty() && !b_mid.is_empty() {
+        // With the ends trimmed, the edit script has at least two edits, so
+        // both halves are smaller than the whole
+        let (x, y, u, v) = middle_snake(a_mid, b_mid);
+        let (a_mid_start, b_mid_start) = (a_start + prefix, b_start + prefix);
+        collect_matches(&a_mid[..x], &b_mid[..y], a_mid_start, b_mid_start, matches);
+        matches.extend((0..u - x).map(|i| (a_mid_start + x + i, b_mid_start + y + i)));
+        collect_matches(
+            &a_mid[u..],
+            &b_mid[v..],
+            a_mid_start + u,
+            b_mid_start + v,
+            matches,
+        );
+    }
+
+    let (a_end, b_end) = (a_start + a.len(), b_start + b.len());
+    matches.extend((0..suffix).map(|i| (a_end - suffix + i, b_end - suffix + i)));
+}
+
+/// Find the middle snake of the shortest edit script from `a` to `b`: the
+/// run of matching lines `(x, y)..(u, v)` that a shortest path passes
+/// through halfway, found by searching from both ends at once.
+fn middle_snake(a: &[&str], b: &[&str]) -> (usize, usize, usize, usize) {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2;
+
+    // Furthest x reached on each diagonal k = x - y, from the start and
+    // (counting from the ends of both inputs) from the end
+    let offset = max + 1;
+    let index = |k: isize| (k + offset) as usize;
+    let mut forward = vec![0isize; 2 * offset as usize + 1];
+    let mut backward = vec![0isize; 2 * offset as usize + 1];
+
+    for d in 0..=max {
+        // Diagonals that stay inside the edit graph after d edits
+        let low = -(d - 2 * (d - m).max(0));
+        let high = d - 2 * (d - n).max(0);
+
+        for k in (low..=high).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[index(k - 1)] < forward[index(k + 1)]) {
+                forward[index(k + 1)]
+            } else {
+                forward[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let (start_x, start_y) = (x, y);
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[index(k)] = x;
+
+            let c = delta - k;
+            if odd && (-(d - 1)..=d - 1).contains(&c) && x + backward[index(c)] >= n {
+                return (start_x as usize, start_y as usize, x as usize, y as usize);
+            }
+        }
+
+        for c in (low..=high).step_by(2) {
+            let mut x = if c == -d || (c != d && backward[index(c - 1)] < backward[index(c + 1)]) {
+                backward[index(c + 1)]
+            } else {
+                backward[index(c - 1)] + 1
+            };
+            let mut y = x - c;
+            let (start_x, start_y) = (x, y);
+            while x < n && y < m && a[(n - x - 1) as usize] == b[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[index(c)] = x;
+
+            let k = delta - c;
+            if !odd && (-d..=d).contains(&k) && x + forward[index(k)] >= n {
+                return (
+                    (n - x) as usize,
+                    (m - y) as usize,
+                    (n - start_x) as usize,
+                    (m - start_y) as usize,
+                );
+            }
+        }
+    }
+
+    unreachable!("the searches from both ends always meet")
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching_lines_lcs() {
+        let a = ["a\n", "b\n", "c\n", "d\n"];
+        let b = ["a\n", "x\n", "c\n", "d\n", "e\n"];
+        assert_eq!(matching_lines(&a, &b), vec![(0, 0), (2, 2), (3, 3)]);
+    }
+
+    #[test]
+    fn test_matching_lines_empty() {
+        assert!(matching_lines(&[], &[]).is_empty());
+        assert!(matching_lines(&["a\n"], &[]).is_empty());
+        assert!(matching_lines(&[], &["a\n"]).is_empty());
+    }
+
+    #[test]
+    fn test_matching_lines_reordered() {
+        let a = ["a\n", "b\n", "c\n"];
+        let b = ["c\n", "a\n", "b\n"];
+        assert_eq!(matching_lines(&a, &b), vec![(0, 1), (1, 2)]);
+    }
+
+    /// Length of the longest common subsequence, by dynamic programming.
+    fn lcs_len(a: &[&str], b: &[&str]) -> usize {
+        let mut row = vec![0; b.len() + 1];
+        for x in a {
+            let mut diagonal = 0;
+            for (j, y) in b.iter().enumerate() {
+                let above = row[j + 1];
+                row[j + 1] = if x == y {
+                    diagonal + 1
+                } else {
+                    above.max(row[j])
+                };
+                diagonal = above;
+            }
+        }
+        row[b.len()]
+    }
+
+    #[test]
+    fn test_matching_lines_random_is_longest() {
+        let mut seed: u64 = 7;
+        let mut next = |n: usize| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) as usize) % n
+        };
+        let pool = ["a\n", "b\n", "c\n", "\n"];
+
+        for _ in 0..2000 {
+            let a: Vec<&str> = (0..next(12)).map(|_| pool[next(pool.len())]).collect();
+            let b: Vec<&str> = (0..next(12)).map(|_| pool[next(pool.len())]).collect();
+            let matches = matching_lines(&a, &b);
+            assert_eq!(matches.len(), lcs_len(&a, &b), "{:?} {:?}", a, b);
+            for (&(x, y), next) in matches.iter().zip(matches.iter().skip(1)) {
+                assert!(x < next.0 && y < next.1, "{:?}", matches);
+            }
+            assert!(matches.iter().all(|&(x, y)| a[x] == b[y]));
+        }
+    }
+
+    #[test]
+    fn test_matching_lines_large_inputs() {
+        // Nothing in common: the edit script is as long as both inputs
+        let a: Vec<String> = (0..2_000).map(|i| format!("a{}\n", i)).collect();
+        let b: Vec<String> = (0..2_000).map(|i| format!("b{}\n", i)).collect();
+        let a: Vec<&str> = a.iter().map(String::as_str).collect();
+        let b: Vec<&str> = b.iter().map(String::as_str).collect();
+        assert!(matching_lines(&a, &b).is_empty());
+    }
+
+    #[test]
+    fn test_merge_only_mine_changed() {
+        let result = three_way_merge("a\nb\nc\n", "a\nB\nc\n", "a\nb\nc\n");
+        assert!(result.is_clean());
+        assert_eq!(result.text, "a\nB\nc\n");
+    }
+
+    #[test]
+    fn test_merge_only_theirs_changed() {
+        let result = three_way_merge("a\nb\nc\n", "a\nb\nc\n", "a\nb\nC\n");
+        assert!(result.is_clean());
+        assert_eq!(result.text, "a\nb\nC\n");
+    }
+
+    #[test]
+    fn test_merge_non_overlapping_changes() {
+        let base = "# Title\n\nintro\n\n## One\n\nbody\n";
+        let mine = "# Title\n\nintro, edited\n\n## One\n\nbody\n";
+        let theirs = "# Title\n\nintro\n\n## One\n\nbody\nmore\n";
+        let result = three_way_merge(base, mine, theirs);
+        assert!(result.is_clean());
+        assert_eq!(
+            result.text,
+            "# Title\n\nintro, edited\n\n## One\n\nbody\nmore\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_identical_changes() {
+        let result = three_way_merge("a\nb\n", "a\nX\n", "a\nX\n");
+        assert!(result.is_clean());
+        assert_eq!(result.text, "a\nX\n");
+    }
+
+    #[test]
+    fn test_merge_conflict_markers() {
+        let result = three_way_merge("a\nb\nc\n", "a\nmine\nc\n", "a\ntheirs\nc\n");
+        assert_eq!(result.conflicts, 1);
+        assert_eq!(
+            result.text,
+            "a\n<<<<<<< Mine\nmine\n=======\ntheirs\n>>>>>>> On disk\nc\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_conflict_without_trailing_newline() {
+        let result = three_way_merge("a", "b", "c");
+        assert_eq!(result.conflicts, 1);
+        assert_eq!(
+            result.text,
+            "<<<<<<< Mine\nb\n=======\nc\n>>>>>>> On disk\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_both_deleted_everything() {
+        let result = three_way_merge("a\nb\n", "", "");
+        assert!(result.is_clean());
+        assert_eq!(result.text, "");
+    }
+}
@@ -4,14 +4,20 @@
 //! for editing markdown documents.
 
 mod find_replace;
+mod highlight;
+mod history;
 mod line_numbers;
+mod merge;
 mod outline;
 mod stats;
 mod widget;
 
 // Only export what's actually used by the app
-pub use find_replace::{FindReplacePanel, FindState};
+pub use find_replace::{unescape_replacement, FindReplacePanel, FindState, BYTES_PER_STEP};
+pub use highlight::retain_layout_caches;
+pub use history::{CursorSnapshot, EditHistory};
 pub use line_numbers::count_lines;
+pub use merge::{three_way_merge, MergeResult};
 pub use outline::{
     extract_outline_for_file, DocumentOutline, OutlineItem, OutlineType, StructuredStats,
 };
@@ -255,7 +255,7 @@ mod tests {
     #[test]
     fn test_stats_clone() {
         let stats = TextStats::from_text("Hello World");
-        let cloned = stats.clone();
+        let cloned = stats;
         assert_eq!(stats, cloned);
     }
 
@@ -4,10 +4,13 @@
 //! with support for text input, cursor movement, selection, clipboard operations,
 //! scrolling, and optional line numbers.
 
+use super::highlight::{with_layout_cache, Highlighting, RawStyle};
+use super::history::CursorSnapshot;
 use crate::config::EditorFont;
 use crate::fonts;
 use crate::state::Tab;
 use crate::theme::ThemeColors;
+use eframe::egui::text::{CCursor, CCursorRange};
 use eframe::egui::{self, FontId, ScrollArea, TextEdit, Ui};
 use log::debug;
 use std::sync::Arc;
@@ -16,6 +19,8 @@ use std::sync::Arc;
 pub struct EditorOutput {
     /// Whether the content was modified.
     pub changed: bool,
+    /// Height of one line in pixels (for sync scrolling).
+    pub line_height: f32,
 }
 
 /// Search match highlight information.
@@ -39,6 +44,7 @@ pub struct SearchHighlights {
 /// - Optional line number gutter
 /// - Search match highlighting
 /// - Scroll-to-line navigation (for outline panel)
+/// - Selecting a range (for search results)
 ///
 /// # Example
 ///
@@ -71,6 +77,10 @@ pub struct EditorWidget<'a> {
     font_family: EditorFont,
     /// Line number to scroll to (1-indexed, from outline navigation).
     scroll_to_line: Option<usize>,
+    /// Scroll offset to apply (from sync scrolling).
+    scroll_offset: Option<f32>,
+    /// Character range to select and focus (from search in files).
+    select_range: Option<(usize, usize)>,
 }
 
 impl<'a> EditorWidget<'a> {
@@ -87,6 +97,8 @@ impl<'a> EditorWidget<'a> {
             search_highlights: None,
             font_family: EditorFont::default(),
             scroll_to_line: None,
+            scroll_offset: None,
+            select_range: None,
         }
     }
 
@@ -146,6 +158,22 @@ impl<'a> EditorWidget<'a> {
         self
     }
 
+    /// Set a scroll offset to apply (for sync scrolling). Line and search
+    /// match navigation take precedence.
+    #[must_use]
+    pub fn scroll_offset(mut self, offset: Option<f32>) -> Self {
+        self.scroll_offset = offset;
+        self
+    }
+
+    /// Select a character range and focus the editor (for search results).
+    /// Combine with [`Self::scroll_to_line`] to bring it into view.
+    #[must_use]
+    pub fn select_range(mut self, range: Option<(usize, usize)>) -> Self {
+        self.select_range = range;
+        self
+    }
+
     /// Show the editor widget and return the output.
     pub fn show(self, ui: &mut Ui) -> EditorOutput {
         // Include content_version in the ID so that egui treats the TextEdit as
@@ -160,8 +188,33 @@ impl<'a> EditorWidget<'a> {
             self.tab.needs_focus = false;
         }
 
-        // Store original content for change detection
+        // Put the requested selection in the TextEdit's state before it's shown
+        if let Some((start, end)) = self.select_range {
+            let mut state = egui::text_edit::TextEditState::load(ui.ctx(), id).unwrap_or_default();
+            state.cursor.set_char_range(Some(CCursorRange::two(
+                CCursor::new(start),
+                CCursor::new(end),
+            )));
+            state.store(ui.ctx(), id);
+        }
+        let needs_focus = needs_focus || self.select_range.is_some();
+
+        // Store original content for change detection, and the cursor before
+        // any edit this frame for the undo history
         let original_content = self.tab.content.clone();
+        let before = egui::text_edit::TextEditState::load(ui.ctx(), id)
+            .and_then(|state| state.cursor.char_range())
+            .map_or_else(
+                || self.tab.cursor_snapshot(),
+                |range| {
+                    let (cursor, selection) = cursor_and_selection(
+                        &original_content,
+                        range.primary.index,
+                        range.secondary.index,
+                    );
+                    CursorSnapshot::new(cursor, selection)
+                },
+            );
 
         // Capture values for closures
         let font_size = self.font_size;
@@ -185,15 +238,29 @@ impl<'a> EditorWidget<'a> {
             0.0
         };
 
+        let raw_highlighting = Highlighting::for_tab(self.tab);
+
         // Create a mutable reference to the content
         let content = &mut self.tab.content;
 
         // Get font family for the editor
         let font_family = fonts::get_styled_font_family(false, false, self.font_family);
 
+        // Syntax-colored layout, with line galleys cached across frames
+        let raw_style = theme_colors
+            .as_ref()
+            .map(|colors| RawStyle::new(colors, raw_highlighting, self.font_family, font_size));
+        let tab_id = self.tab.id;
+
         // Configure the text layout based on word wrap
         let font_family_clone = font_family.clone();
         let mut layouter = move |ui: &Ui, text: &str, wrap_width: f32| -> Arc<egui::Galley> {
+            let wrap_width = if word_wrap { wrap_width } else { f32::INFINITY };
+            if let Some(style) = &raw_style {
+                return ui.fonts(|f| {
+                    with_layout_cache(tab_id, |cache| cache.layout(f, text, style, wrap_width))
+                });
+            }
             let font_id = FontId::new(font_size, font_family_clone.clone());
             let layout_job = if word_wrap {
                 egui::text::LayoutJob::simple(
@@ -241,6 +308,10 @@ impl<'a> EditorWidget<'a> {
                 }
             }
         }
+        // Priority 3: Follow the other pane of the split view
+        if target_scroll_offset.is_none() {
+            target_scroll_offset = self.scroll_offset;
+        }
 
         // Use ScrollArea for viewport management - line numbers scroll with content
         let mut scroll_area = ScrollArea::vertical()
@@ -491,31 +562,17 @@ impl<'a> EditorWidget<'a> {
         if changed {
             // TextEdit modifies content directly, so we need to manually
             // record the edit for undo/redo functionality
-            self.tab.record_edit(original_content);
+            self.tab.record_edit(original_content, before);
             debug!("Editor content changed, recorded for undo");
         }
 
         // Calculate cursor position (line, column) and selection from cursor range
         let (cursor_position, selection) = if let Some(cursor_range) = cursor_range_opt {
-            let primary = cursor_range.primary.ccursor.index;
-            let secondary = cursor_range.secondary.ccursor.index;
-
-            // Convert character index to (line, column)
-            let pos = char_index_to_line_col(&self.tab.content, primary);
-
-            // Check if there's a selection (primary != secondary)
-            let sel = if primary != secondary {
-                let (start, end) = if primary < secondary {
-                    (primary, secondary)
-                } else {
-                    (secondary, primary)
-                };
-                Some((start, end))
-            } else {
-                None
-            };
-
-            (pos, sel)
+            cursor_and_selection(
+                &self.tab.content,
+                cursor_range.primary.ccursor.index,
+                cursor_range.secondary.ccursor.index,
+            )
         } else {
             (self.tab.cursor_position, self.tab.selection)
         };
@@ -527,10 +584,26 @@ impl<'a> EditorWidget<'a> {
         // Update scroll offset from ScrollArea state
         self.tab.scroll_offset = scroll_output.state.offset.y;
 
-        EditorOutput { changed }
+        let line_height = ui.fonts(|f| f.row_height(&FontId::new(font_size, font_family)));
+        EditorOutput {
+            changed,
+            line_height,
+        }
     }
 }
 
+/// Get the cursor (line, column) and the selection (sorted character range,
+/// if any) for a text cursor range.
+fn cursor_and_selection(
+    text: &str,
+    primary: usize,
+    secondary: usize,
+) -> ((usize, usize), Option<(usize, usize)>) {
+    let selection =
+        (primary != secondary).then(|| (primary.min(secondary), primary.max(secondary)));
+    (char_index_to_line_col(text, primary), selection)
+}
+
 /// Convert a character index to (line, column) position.
 ///
 /// Both line and column are 0-indexed.
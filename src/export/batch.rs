@@ -0,0 +1,195 @@
+//! Batch HTML Export
+//!
+//! This module exports markdown files to HTML without opening a window, for
+//! the `ferrite export` subcommand. A single file is written next to its
+//! source (or to a chosen path); a folder is mirrored into an output folder,
+//! with each `.md` file becoming an `.html` file at the same relative path.
+
+use super::html::{export_to_html_file, HtmlExportError};
+use super::images::UnresolvedImage;
+use super::options::ExportOptions;
+use crate::files::glob::walk_files;
+use crate::state::FileType;
+use crate::theme::ThemeColors;
+use std::path::{Path, PathBuf};
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Export Plan
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// One markdown file to export and where its HTML goes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportJob {
+    /// Markdown source file
+    pub source: PathBuf,
+    /// HTML output file
+    pub output: PathBuf,
+}
+
+/// Work out which files to export and where each one goes.
+///
+/// For a file, `output` may be a file path or an existing folder; it
+/// defaults to the source with an `.html` extension. For a folder, every
+/// markdown file below it (skipping hidden and build folders) is mirrored
+/// into `output`, which defaults to the input folder itself.
+pub fn plan_export(input: &Path, output: Option<&Path>) -> Vec<ExportJob> {
+    if !input.is_dir() {
+        let output = match output {
+            Some(out) if out.is_dir() => out.join(html_file_name(input)),
+            Some(out) => out.to_path_buf(),
+            None => input.with_extension("html"),
+        };
+        return vec![ExportJob {
+            source: input.to_path_buf(),
+            output,
+        }];
+    }
+
+    let output_root = output.unwrap_or(input);
+    markdown_files(input)
+        .into_iter()
+        .map(|source| {
+            let relative = source.strip_prefix(input).unwrap_or(&source);
+            let output = output_root.join(relative).with_extension("html");
+            ExportJob { source, output }
+        })
+        .collect()
+}
+
+/// Find all markdown files below `dir`, sorted by path.
+pub fn markdown_files(dir: &Path) -> Vec<PathBuf> {
+    walk_files(dir)
+        .into_iter()
+        .filter(|path| FileType::from_path(path).is_markdown())
+        .collect()
+}
+
+/// `notes.md` -> `notes.html`
+fn html_file_name(source: &Path) -> PathBuf {
+    PathBuf::from(source.file_name().unwrap_or_default()).with_extension("html")
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Running
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Export every job, continuing past failures.
+///
+/// `on_done` is called after each job with its result (the images that
+/// couldn't be resolved, or the error), so callers can report progress.
+/// Returns the number of failed jobs.
+pub fn run_export<F>(
+    jobs: &[ExportJob],
+    theme_colors: &ThemeColors,
+    options: &ExportOptions,
+    mut on_done: F,
+) -> usize
+where
+    F: FnMut(&ExportJob, &Result<Vec<UnresolvedImage>, HtmlExportError>),
+{
+    let mut failed = 0;
+    for job in jobs {
+        let result = export_to_html_file(&job.source, &job.output, theme_colors, options);
+        if result.is_err() {
+            failed += 1;
+        }
+        on_done(job, &result);
+    }
+    failed
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_plan_single_file() {
+        let jobs = plan_export(Path::new("/docs/README.md"), None);
+        assert_eq!(jobs[0].output, PathBuf::from("/docs/README.html"));
+
+        let jobs = plan_export(
+            Path::new("/docs/README.md"),
+            Some(Path::new("/out/index.html")),
+        );
+        assert_eq!(jobs[0].output, PathBuf::from("/out/index.html"));
+    }
+
+    #[test]
+    fn test_plan_file_into_existing_folder() {
+        let temp_dir = TempDir::new().unwrap();
+        let jobs = plan_export(Path::new("/docs/guide.md"), Some(temp_dir.path()));
+        assert_eq!(jobs[0].output, temp_dir.path().join("guide.html"));
+    }
+
+    #[test]
+    fn test_plan_folder_mirrors_tree() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("guide")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::create_dir_all(root.join("node_modules")).unwrap();
+        fs::write(root.join("index.md"), "# Home").unwrap();
+        fs::write(root.join("guide/setup.markdown"), "# Setup").unwrap();
+        fs::write(root.join("guide/notes.txt"), "text").unwrap();
+        fs::write(root.join(".git/HEAD.md"), "").unwrap();
+        fs::write(root.join("node_modules/pkg.md"), "").unwrap();
+
+        let out = Path::new("/site");
+        let jobs = plan_export(root, Some(out));
+        let outputs: Vec<_> = jobs.iter().map(|j| j.output.clone()).collect();
+        assert_eq!(
+            outputs,
+            vec![out.join("guide/setup.html"), out.join("index.html")]
+        );
+    }
+
+    #[test]
+    fn test_run_export_writes_html() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(
+            root.join("index.md"),
+            "# Home\n\nSee [setup](setup.md#top).",
+        )
+        .unwrap();
+        fs::write(root.join("setup.md"), "# Setup").unwrap();
+
+        let out = root.join("site");
+        let jobs = plan_export(root, Some(&out));
+        let options = ExportOptions {
+            rewrite_markdown_links: true,
+            ..ExportOptions::html_file()
+        };
+        let mut done = Vec::new();
+        let failed = run_export(&jobs, &ThemeColors::light(), &options, |job, result| {
+            assert!(result.is_ok());
+            done.push(job.output.clone());
+        });
+
+        assert_eq!(failed, 0);
+        assert_eq!(done.len(), 2);
+        let html = fs::read_to_string(out.join("index.html")).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>index</title>"));
+        assert!(html.contains(r#"href="setup.html#top""#));
+    }
+
+    #[test]
+    fn test_run_export_reports_missing_source() {
+        let temp_dir = TempDir::new().unwrap();
+        let jobs = plan_export(&temp_dir.path().join("missing.md"), None);
+        let failed = run_export(
+            &jobs,
+            &ThemeColors::dark(),
+            &ExportOptions::default(),
+            |_, result| assert!(result.is_err()),
+        );
+        assert_eq!(failed, 1);
+    }
+}
@@ -7,8 +7,14 @@
 // and error variants for future export enhancements
 #![allow(dead_code)]
 
+use super::images::{export_image_url, UnresolvedImage};
+use super::options::ExportOptions;
+use crate::files::encoding::read_text_file;
+use crate::markdown::math::to_mathml;
+use crate::markdown::mermaid;
 use crate::theme::ThemeColors;
-use comrak::{markdown_to_html, Options};
+use comrak::nodes::{AstNode, NodeHtmlBlock, NodeValue};
+use comrak::{format_html, parse_document, Arena, Options};
 use std::path::Path;
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -66,6 +72,23 @@ pub fn generate_html_document(
     // Convert markdown to HTML body
     let html_body = markdown_to_html_body(markdown)?;
 
+    Ok(wrap_html_document(
+        &html_body,
+        title,
+        theme_colors,
+        include_syntax_css,
+        None,
+    ))
+}
+
+/// Wrap an HTML body in a complete, themed HTML document.
+fn wrap_html_document(
+    html_body: &str,
+    title: Option<&str>,
+    theme_colors: &ThemeColors,
+    include_syntax_css: bool,
+    custom_css: Option<&str>,
+) -> String {
     // Generate CSS from theme
     let theme_css = generate_theme_css(theme_colors);
 
@@ -93,6 +116,8 @@ pub fn generate_html_document(
 {theme_css}
 
 {syntax_css}
+
+{custom_css}
     </style>
 </head>
 <body>
@@ -105,10 +130,11 @@ pub fn generate_html_document(
         base_css = BASE_CSS,
         theme_css = theme_css,
         syntax_css = syntax_css,
+        custom_css = custom_css.unwrap_or_default(),
         body = html_body,
     );
 
-    Ok(html)
+    html
 }
 
 /// Generate HTML fragment (no doctype, head, etc.) for clipboard.
@@ -126,6 +152,51 @@ pub fn generate_html_fragment(markdown: &str) -> Result<String, HtmlExportError>
 
 /// Convert markdown to HTML body content.
 fn markdown_to_html_body(markdown: &str) -> Result<String, HtmlExportError> {
+    let arena = Arena::new();
+    let options = comrak_options();
+    let root = parse_document(&arena, markdown, &options);
+
+    for node in root.descendants() {
+        render_embedded(&mut node.data.borrow_mut().value);
+    }
+
+    format_ast(root, &options)
+}
+
+/// Replace math with MathML and Mermaid code blocks with inline SVG.
+fn render_embedded(value: &mut NodeValue) {
+    match value {
+        NodeValue::Math(math) => {
+            *value = NodeValue::HtmlInline(to_mathml(&math.literal, math.display_math));
+        }
+        NodeValue::CodeBlock(code) if code.info.split_whitespace().next() == Some("mermaid") => {
+            let literal = match mermaid::to_svg(&code.literal) {
+                Ok(svg) => format!("<div class=\"mermaid-diagram\">{}</div>\n", svg),
+                // Keep the source visible below the error so nothing is lost
+                Err(error) => format!(
+                    "<div class=\"mermaid-error\">Mermaid syntax error — {}</div>\n<pre><code class=\"language-mermaid\">{}</code></pre>\n",
+                    html_escape(&error.to_string()),
+                    html_escape(&code.literal)
+                ),
+            };
+            *value = NodeValue::HtmlBlock(NodeHtmlBlock {
+                block_type: 6,
+                literal,
+            });
+        }
+        _ => {}
+    }
+}
+
+/// Render a parsed document to HTML.
+fn format_ast<'a>(root: &'a AstNode<'a>, options: &Options) -> Result<String, HtmlExportError> {
+    let mut html = Vec::new();
+    format_html(root, options, &mut html)?;
+    String::from_utf8(html).map_err(|e| HtmlExportError::ConversionError(e.to_string()))
+}
+
+/// Comrak options shared by all HTML export paths.
+fn comrak_options() -> Options {
     let mut options = Options::default();
 
     // Enable common extensions
@@ -135,12 +206,152 @@ fn markdown_to_html_body(markdown: &str) -> Result<String, HtmlExportError> {
     options.extension.tasklist = true;
     options.extension.footnotes = true;
     options.extension.header_ids = Some(String::new());
+    options.extension.math_dollars = true;
 
     // Render options
     options.render.unsafe_ = true; // Allow raw HTML
 
-    let html = markdown_to_html(markdown, &options);
-    Ok(html)
+    options
+}
+
+/// Convert a markdown file's content to HTML body content for export.
+///
+/// Local images are resolved against `base_dir` and embedded or rewritten
+/// for `output_dir` according to the `ImageHandling` option; images that
+/// can't be found are left as written and returned. With
+/// `rewrite_markdown_links`, relative links to other markdown files point at
+/// their `.html` exports instead. Math is written out as MathML and Mermaid
+/// diagrams as inline SVG.
+fn export_html_body(
+    markdown: &str,
+    base_dir: &Path,
+    output_dir: Option<&Path>,
+    export_options: &ExportOptions,
+) -> Result<(String, Vec<UnresolvedImage>), HtmlExportError> {
+    let arena = Arena::new();
+    let options = comrak_options();
+    let root = parse_document(&arena, markdown, &options);
+    let mut unresolved = Vec::new();
+
+    for node in root.descendants() {
+        match &mut node.data.borrow_mut().value {
+            NodeValue::Image(link) => {
+                match export_image_url(
+                    &link.url,
+                    base_dir,
+                    output_dir,
+                    export_options.image_handling,
+                ) {
+                    Ok(Some(url)) => link.url = url,
+                    Ok(None) => {}
+                    Err(image) => unresolved.push(image),
+                }
+            }
+            NodeValue::Link(link) if export_options.rewrite_markdown_links => {
+                if let Some(url) = markdown_link_to_html(&link.url) {
+                    link.url = url;
+                }
+            }
+            value => render_embedded(value),
+        }
+    }
+
+    Ok((format_ast(root, &options)?, unresolved))
+}
+
+/// Rewrite a relative link to a markdown file (`guide.md#setup`) to its
+/// exported HTML file (`guide.html#setup`).
+fn markdown_link_to_html(url: &str) -> Option<String> {
+    if super::images::is_remote_url(url) || url.starts_with('/') {
+        return None;
+    }
+    let (path, fragment) = match url.find(['#', '?']) {
+        Some(i) => url.split_at(i),
+        None => (url, ""),
+    };
+    let stem = [".md", ".markdown"]
+        .iter()
+        .find_map(|ext| path.strip_suffix(ext))?;
+    Some(format!("{}.html{}", stem, fragment))
+}
+
+/// An exported HTML document.
+#[derive(Debug, Clone)]
+pub struct HtmlExport {
+    /// The complete HTML document
+    pub html: String,
+    /// Local images that couldn't be found or embedded (left as written)
+    pub unresolved_images: Vec<UnresolvedImage>,
+}
+
+/// Render markdown to a complete HTML document, handling images per `options`.
+///
+/// # Arguments
+///
+/// * `markdown` - The markdown source text
+/// * `title` - Optional document title
+/// * `base_dir` - Folder relative image paths are resolved against
+/// * `output_dir` - Folder the HTML will be written to, for relative image paths
+/// * `theme_colors` - Theme colors for styling
+/// * `options` - Export options (syntax CSS, images, custom CSS)
+pub fn export_markdown_to_html(
+    markdown: &str,
+    title: Option<&str>,
+    base_dir: &Path,
+    output_dir: Option<&Path>,
+    theme_colors: &ThemeColors,
+    options: &ExportOptions,
+) -> Result<HtmlExport, HtmlExportError> {
+    let (body, unresolved_images) = export_html_body(markdown, base_dir, output_dir, options)?;
+    let html = wrap_html_document(
+        &body,
+        title,
+        theme_colors,
+        options.include_syntax_highlighting,
+        options.custom_css.as_deref(),
+    );
+    Ok(HtmlExport {
+        html,
+        unresolved_images,
+    })
+}
+
+/// Render a markdown file to a complete HTML document.
+///
+/// # Arguments
+///
+/// * `source_path` - Path to the markdown file
+/// * `output_dir` - Folder the HTML will be written to (None = not written to a file)
+/// * `theme_colors` - Theme colors for styling
+/// * `options` - Export options (title, syntax CSS, images, custom CSS)
+///
+/// # Returns
+///
+/// The HTML document and any images that couldn't be resolved.
+pub fn export_file_to_html(
+    source_path: &Path,
+    output_dir: Option<&Path>,
+    theme_colors: &ThemeColors,
+    options: &ExportOptions,
+) -> Result<HtmlExport, HtmlExportError> {
+    let (markdown, _) = read_text_file(source_path)?;
+
+    let title = options.include_title.then(|| {
+        source_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Document")
+    });
+    let base_dir = source_path.parent().unwrap_or(Path::new("."));
+
+    export_markdown_to_html(
+        &markdown,
+        title,
+        base_dir,
+        output_dir,
+        theme_colors,
+        options,
+    )
 }
 
 /// Export markdown file to HTML file.
@@ -148,29 +359,28 @@ fn markdown_to_html_body(markdown: &str) -> Result<String, HtmlExportError> {
 /// # Arguments
 ///
 /// * `source_path` - Path to the markdown file
-/// * `output_path` - Path for the output HTML file
+/// * `output_path` - Path for the output HTML file (parent folders are created)
 /// * `theme_colors` - Theme colors for styling
+/// * `options` - Export options
 ///
 /// # Returns
 ///
-/// Ok(()) on success, or an error.
+/// The images that couldn't be resolved on success, or an error.
 pub fn export_to_html_file(
     source_path: &Path,
     output_path: &Path,
     theme_colors: &ThemeColors,
-) -> Result<(), HtmlExportError> {
-    let markdown = std::fs::read_to_string(source_path)?;
-
-    let title = source_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("Document");
+    options: &ExportOptions,
+) -> Result<Vec<UnresolvedImage>, HtmlExportError> {
+    let output_dir = output_path.parent().unwrap_or(Path::new(""));
+    let export = export_file_to_html(source_path, Some(output_dir), theme_colors, options)?;
 
-    let html = generate_html_document(&markdown, Some(title), theme_colors, true)?;
-
-    std::fs::write(output_path, html)?;
+    if !output_dir.as_os_str().is_empty() {
+        std::fs::create_dir_all(output_dir)?;
+    }
+    std::fs::write(output_path, export.html)?;
 
-    Ok(())
+    Ok(export.unresolved_images)
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -355,6 +565,25 @@ body {
 .markdown-body del {
     text-decoration: line-through;
 }
+
+/* Math */
+.markdown-body math[display="block"] {
+    margin: 12px 0;
+    overflow-x: auto;
+}
+
+/* Mermaid diagrams */
+.markdown-body .mermaid-diagram {
+    margin-bottom: 16px;
+    overflow-x: auto;
+    text-align: center;
+}
+
+.markdown-body .mermaid-error {
+    color: #cf222e;
+    font-size: 0.9em;
+    margin-bottom: 4px;
+}
 "#;
 
 /// Generate theme-specific CSS from ThemeColors.
@@ -418,6 +647,10 @@ body {{
 .markdown-body hr {{
     background-color: {hr};
 }}
+
+.markdown-body .mermaid-background {{
+    fill: {bg};
+}}
 "#,
         color_scheme = if is_dark { "dark" } else { "light" },
         bg = color32_to_css(colors.base.background),
@@ -473,8 +706,9 @@ fn color32_to_css(color: eframe::egui::Color32) -> String {
     format!("rgb({}, {}, {})", color.r(), color.g(), color.b())
 }
 
-/// HTML-escape a string.
-fn html_escape(s: &str) -> String {
+/// HTML-escape a string. The output is also valid XML, for the SVG and
+/// MathML embedded in rendered documents.
+pub fn html_escape(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
@@ -489,6 +723,7 @@ fn html_escape(s: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::export::options::ImageHandling;
 
     #[test]
     fn test_markdown_to_html_body() {
@@ -529,6 +764,34 @@ mod tests {
         assert!(html.contains("<em>"));
     }
 
+    #[test]
+    fn test_math_exports_as_mathml() {
+        let html = generate_html_fragment("Area $\\pi r^2$\n\n$$\\frac{a}{b}$$").unwrap();
+
+        assert!(
+            html.contains("<math xmlns=\"http://www.w3.org/1998/Math/MathML\" display=\"inline\">")
+        );
+        assert!(html.contains("<msup><mi>r</mi><mn>2</mn></msup>"));
+        assert!(html.contains("display=\"block\""));
+        assert!(html.contains("<mfrac><mi>a</mi><mi>b</mi></mfrac>"));
+        assert!(!html.contains('$'));
+    }
+
+    #[test]
+    fn test_mermaid_exports_as_svg() {
+        let html =
+            generate_html_fragment("```mermaid\ngraph TD\n  A[Write] --> B[Export]\n```").unwrap();
+        assert!(html.contains("<div class=\"mermaid-diagram\"><svg class=\"mermaid\""));
+        assert!(html.contains(">Export</text>"));
+        assert!(!html.contains("<code"));
+
+        let html = generate_html_fragment("```mermaid\ngraph TD\n  A[Oops --> B\n```").unwrap();
+        assert!(html.contains("<div class=\"mermaid-error\">Mermaid syntax error — Line 2:"));
+        assert!(
+            html.contains("<code class=\"language-mermaid\">graph TD\n  A[Oops --&gt; B\n</code>")
+        );
+    }
+
     #[test]
     fn test_color32_to_css() {
         let color = eframe::egui::Color32::from_rgb(255, 128, 64);
@@ -560,4 +823,60 @@ mod tests {
 
         assert!(css.contains("color-scheme: dark"));
     }
+
+    #[test]
+    fn test_markdown_link_to_html() {
+        assert_eq!(
+            markdown_link_to_html("guide/setup.md#install"),
+            Some("guide/setup.html#install".to_string())
+        );
+        assert_eq!(
+            markdown_link_to_html("notes.markdown"),
+            Some("notes.html".to_string())
+        );
+        assert_eq!(markdown_link_to_html("https://example.com/a.md"), None);
+        assert_eq!(markdown_link_to_html("image.png"), None);
+    }
+
+    #[test]
+    fn test_export_file_embeds_images() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let source = temp_dir.path().join("doc.md");
+        std::fs::write(temp_dir.path().join("pic.png"), b"foo").unwrap();
+        std::fs::write(&source, "![pic](pic.png) ![web](https://x.org/a.png)").unwrap();
+
+        let options = ExportOptions::html_file();
+        let export = export_file_to_html(&source, None, &ThemeColors::light(), &options).unwrap();
+        assert!(export.html.contains(r#"src="data:image/png;base64,Zm9v""#));
+        assert!(export.html.contains(r#"src="https://x.org/a.png""#));
+        assert!(export.unresolved_images.is_empty());
+
+        let options = options.with_image_handling(ImageHandling::RelativePaths);
+        let export = export_file_to_html(&source, None, &ThemeColors::light(), &options).unwrap();
+        assert!(export.html.contains(r#"src="pic.png""#));
+    }
+
+    #[test]
+    fn test_export_rewrites_image_paths() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let source = temp_dir.path().join("doc.md");
+        std::fs::write(temp_dir.path().join("pic.png"), b"foo").unwrap();
+        std::fs::write(&source, "![pic](pic.png)\n\n![lost](missing.png)").unwrap();
+        let output = temp_dir.path().join("out/site/doc.html");
+
+        let options = ExportOptions::html_file().with_image_handling(ImageHandling::RelativePaths);
+        let unresolved =
+            export_to_html_file(&source, &output, &ThemeColors::light(), &options).unwrap();
+        let html = std::fs::read_to_string(&output).unwrap();
+        assert!(html.contains(r#"src="../../pic.png""#));
+        assert!(html.contains(r#"src="missing.png""#));
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].url, "missing.png");
+
+        let options = options.with_image_handling(ImageHandling::AbsolutePaths);
+        export_to_html_file(&source, &output, &ThemeColors::light(), &options).unwrap();
+        let html = std::fs::read_to_string(&output).unwrap();
+        assert!(html.contains(r#"src="file://"#));
+        assert!(html.contains(r#"/pic.png""#));
+    }
 }
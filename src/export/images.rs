@@ -0,0 +1,403 @@
+//! Image Handling for HTML Export
+//!
+//! This module rewrites the local images referenced by a document for
+//! [`ImageHandling`]: inlined as base64 `data:` URIs (so an exported HTML file
+//! keeps working when it is moved away from its images), linked relative to
+//! the output file, or linked by absolute `file://` URL. Images that can't be
+//! found are reported as [`UnresolvedImage`]s.
+
+use super::options::ImageHandling;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Image References
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Check if an image URL points somewhere other than the local filesystem
+/// (`https:`, `data:`, `mailto:`, ...) or is a fragment-only reference.
+pub fn is_remote_url(url: &str) -> bool {
+    if url.starts_with('#') || url.starts_with("//") {
+        return true;
+    }
+    // A scheme is letters followed by ':'; a single letter is a Windows drive
+    match url.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.len() > 1
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+/// Resolve a local image URL against the document's directory.
+///
+/// Percent-escapes (`%20`, `%23`, UTF-8 sequences) are decoded, and
+/// `file://` URLs are accepted. Returns `None` for remote URLs.
+pub fn resolve_local_image(url: &str, base_dir: &Path) -> Option<PathBuf> {
+    let url = url.strip_prefix("file://").unwrap_or(url);
+    if is_remote_url(url) {
+        return None;
+    }
+    let path = percent_decode(url.split(['?', '#']).next().unwrap_or(url));
+    let path = Path::new(&path);
+    Some(if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base_dir.join(path)
+    })
+}
+
+/// Decode `%XX` escapes in a URL path.
+///
+/// Malformed escapes are kept as written, and so is the whole path if the
+/// decoded bytes aren't valid UTF-8.
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|hex| bytes[i] == b'%' && hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| path.to_string())
+}
+
+/// Get the MIME type for an image file based on its extension.
+pub fn image_mime_type(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        "avif" => "image/avif",
+        _ => return None,
+    })
+}
+
+/// Read an image file and encode it as a `data:` URI.
+pub fn embed_image_file(path: &Path) -> Result<String, String> {
+    let mime = image_mime_type(path).ok_or_else(|| "not a known image type".to_string())?;
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    Ok(format!("data:{};base64,{}", mime, base64_encode(&bytes)))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Export Rewriting
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A local image an export couldn't find or embed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedImage {
+    /// The image URL as written in the document
+    pub url: String,
+    /// Why the image couldn't be exported
+    pub reason: String,
+}
+
+impl fmt::Display for UnresolvedImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.url, self.reason)
+    }
+}
+
+/// Rewrite an image URL for an exported document.
+///
+/// `base_dir` is the document's folder and `output_dir` the folder the HTML
+/// is written to (`None` when it isn't written to a file, in which case
+/// relative paths are kept as they are). Returns `Ok(None)` when the URL
+/// should be left unchanged, such as for remote images.
+pub fn export_image_url(
+    url: &str,
+    base_dir: &Path,
+    output_dir: Option<&Path>,
+    handling: ImageHandling,
+) -> Result<Option<String>, UnresolvedImage> {
+    let Some(path) = resolve_local_image(url, base_dir) else {
+        return Ok(None);
+    };
+    let unresolved = |reason: String| UnresolvedImage {
+        url: url.to_string(),
+        reason,
+    };
+    if !path.is_file() {
+        return Err(unresolved("file not found".to_string()));
+    }
+
+    // Keep a `?query` or `#fragment` when the path is rewritten
+    let suffix = url.find(['?', '#']).map_or("", |i| &url[i..]);
+    match handling {
+        ImageHandling::EmbedBase64 => embed_image_file(&path).map(Some).map_err(unresolved),
+        ImageHandling::RelativePaths => {
+            Ok(output_dir.map(|dir| format!("{}{}", relative_image_url(dir, &path), suffix)))
+        }
+        ImageHandling::AbsolutePaths => Ok(Some(format!("{}{}", file_url(&path), suffix))),
+    }
+}
+
+/// Build a URL for `target` relative to the directory `from`.
+///
+/// Components are joined with `/` and percent-encoded, so names with spaces,
+/// `#`, `?` or `%` stay part of the path.
+pub fn relative_image_url(from: &Path, target: &Path) -> String {
+    let from = normalize_path(&absolute_path(from));
+    let target = normalize_path(&absolute_path(target));
+    let from: Vec<_> = from.components().collect();
+    let target: Vec<_> = target.components().collect();
+    let common = from.iter().zip(&target).take_while(|(a, b)| a == b).count();
+
+    let parents = std::iter::repeat("..".into()).take(from.len() - common);
+    let rest = target[common..]
+        .iter()
+        .map(|c| c.as_os_str().to_string_lossy());
+    percent_encode(&parents.chain(rest).collect::<Vec<_>>().join("/"))
+}
+
+/// Build a `file://` URL for a path.
+fn file_url(path: &Path) -> String {
+    let path = normalize_path(&absolute_path(path))
+        .to_string_lossy()
+        .replace('\\', "/");
+    if path.starts_with('/') {
+        format!("file://{}", percent_encode(&path))
+    } else {
+        // Windows drive paths (`C:/...`) keep the drive's colon
+        let (drive, rest) = path.split_at(path.find('/').unwrap_or(path.len()));
+        format!("file:///{}{}", drive, percent_encode(rest))
+    }
+}
+
+/// Encode a URL path as `%XX` escapes, except for unreserved characters
+/// and `/`; the inverse of [`percent_decode`].
+fn percent_encode(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Make a path absolute against the current directory, without touching the disk.
+fn absolute_path(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    std::env::current_dir()
+        .map(|dir| dir.join(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Remove `.` components and resolve `..` where possible, without touching the disk.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Base64
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Standard base64 alphabet (RFC 4648)
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode bytes as padded standard base64.
+pub fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64_encode(&[0xFF, 0xFE]), "//4=");
+    }
+
+    #[test]
+    fn test_is_remote_url() {
+        assert!(is_remote_url("https://example.com/a.png"));
+        assert!(is_remote_url("data:image/png;base64,AAAA"));
+        assert!(is_remote_url("//cdn.example.com/a.png"));
+        assert!(!is_remote_url("images/a.png"));
+        assert!(!is_remote_url("C:\\images\\a.png"));
+    }
+
+    #[test]
+    fn test_resolve_local_image() {
+        let base = Path::new("/docs");
+        assert_eq!(
+            resolve_local_image("img/my%20pic.png?v=2", base),
+            Some(PathBuf::from("/docs/img/my pic.png"))
+        );
+        assert_eq!(
+            resolve_local_image("img/%23%281%29%20caf%C3%A9.png#top", base),
+            Some(PathBuf::from("/docs/img/#(1) café.png"))
+        );
+        assert_eq!(
+            resolve_local_image("100%25%zz.png", base),
+            Some(PathBuf::from("/docs/100%%zz.png"))
+        );
+        assert_eq!(resolve_local_image("https://x.org/a.png", base), None);
+    }
+
+    #[test]
+    fn test_image_mime_type() {
+        assert_eq!(image_mime_type(Path::new("a.PNG")), Some("image/png"));
+        assert_eq!(image_mime_type(Path::new("a.svg")), Some("image/svg+xml"));
+        assert_eq!(image_mime_type(Path::new("a.txt")), None);
+    }
+
+    #[test]
+    fn test_embed_image_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dot = temp_dir.path().join("dot.png");
+        std::fs::write(&dot, b"foo").unwrap();
+        let notes = temp_dir.path().join("notes.txt");
+        std::fs::write(&notes, b"foo").unwrap();
+
+        assert_eq!(
+            embed_image_file(&dot),
+            Ok("data:image/png;base64,Zm9v".to_string())
+        );
+        assert!(embed_image_file(&notes).is_err());
+        assert!(embed_image_file(&temp_dir.path().join("missing.png")).is_err());
+    }
+
+    #[test]
+    fn test_export_image_url() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let docs = temp_dir.path().join("docs");
+        std::fs::create_dir_all(docs.join("img")).unwrap();
+        std::fs::write(docs.join("img/my pic.png"), b"foo").unwrap();
+        let out = temp_dir.path().join("site/pages");
+        let url = "./img/my%20pic.png#small";
+
+        let export = |handling| export_image_url(url, &docs, Some(&out), handling);
+        assert_eq!(
+            export(ImageHandling::EmbedBase64),
+            Ok(Some("data:image/png;base64,Zm9v".to_string()))
+        );
+        assert_eq!(
+            export(ImageHandling::RelativePaths),
+            Ok(Some("../../docs/img/my%20pic.png#small".to_string()))
+        );
+        let absolute = export(ImageHandling::AbsolutePaths).unwrap().unwrap();
+        assert!(absolute.starts_with("file://"), "{}", absolute);
+        assert!(absolute.ends_with("/docs/img/my%20pic.png#small"));
+
+        // Without an output file relative paths stay as written
+        assert_eq!(
+            export_image_url(url, &docs, None, ImageHandling::RelativePaths),
+            Ok(None)
+        );
+        assert_eq!(
+            export_image_url(
+                "https://x.org/a.png",
+                &docs,
+                Some(&out),
+                ImageHandling::EmbedBase64
+            ),
+            Ok(None)
+        );
+        let missing = export_image_url("gone.png", &docs, Some(&out), ImageHandling::RelativePaths)
+            .unwrap_err();
+        assert_eq!(missing.to_string(), "gone.png: file not found");
+    }
+
+    #[test]
+    fn test_relative_image_url() {
+        assert_eq!(
+            relative_image_url(
+                Path::new("/notes/daily"),
+                Path::new("/notes/daily/assets/a.png")
+            ),
+            "assets/a.png"
+        );
+        assert_eq!(
+            relative_image_url(
+                Path::new("/notes/./daily"),
+                Path::new("/notes/my media/a.png")
+            ),
+            "../my%20media/a.png"
+        );
+        assert_eq!(
+            relative_image_url(Path::new("/notes"), Path::new("/notes/#1 50%?.png")),
+            "%231%2050%25%3F.png"
+        );
+    }
+
+    #[test]
+    fn test_percent_encode_round_trips() {
+        let path = "/notes/café #1/50% off?.png";
+        let encoded = percent_encode(path);
+        assert_eq!(encoded, "/notes/caf%C3%A9%20%231/50%25%20off%3F.png");
+        assert_eq!(percent_decode(&encoded), path);
+        assert_eq!(
+            resolve_local_image(&format!("file://{}", encoded), Path::new("/")),
+            Some(PathBuf::from(path))
+        );
+    }
+}
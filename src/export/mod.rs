@@ -12,11 +12,15 @@
 //!
 //! - `options.rs` - Export configuration and options
 //! - `html.rs` - HTML document generation with theme styling
+//! - `images.rs` - Embedding or rewriting local image references
+//! - `batch.rs` - Exporting files and folder trees without the GUI
 //! - `clipboard.rs` - Platform clipboard operations
 
+pub mod batch;
 pub mod clipboard;
 pub mod html;
+pub mod images;
 pub mod options;
 
 pub use clipboard::copy_html_to_clipboard;
-pub use html::generate_html_document;
+pub use html::export_markdown_to_html;
@@ -133,6 +133,10 @@ pub struct ExportOptions {
 
     /// Whether to open the exported file after export
     pub open_after_export: bool,
+
+    /// Whether relative links to `.md` files should point at their `.html`
+    /// exports (used when exporting a whole folder)
+    pub rewrite_markdown_links: bool,
 }
 
 impl Default for ExportOptions {
@@ -146,6 +150,7 @@ impl Default for ExportOptions {
             custom_css: None,
             last_export_directory: None,
             open_after_export: false,
+            rewrite_markdown_links: false,
         }
     }
 }
@@ -0,0 +1,283 @@
+//! Image assets for Ferrite
+//!
+//! Images pasted from the clipboard or dropped onto a document are copied into
+//! an assets folder next to the document (`assets/` by default, configurable
+//! per workspace with `assets_dir` in `.ferrite/settings.json`) and linked
+//! with a relative `![](assets/…)` reference.
+//!
+//! Stored files get a timestamped name (`image-20250101-120000.png`). An image
+//! whose bytes already exist in the assets folder is not copied again; the
+//! existing file is linked instead.
+
+use crate::export::images::{normalize_path, relative_image_url};
+use crate::files::save::atomic_write;
+use crate::workspaces::Workspace;
+use log::debug;
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Assets folder used when the workspace doesn't set one
+pub const DEFAULT_ASSETS_DIR: &str = "assets";
+
+/// Extensions of image files that are stored as assets when dropped
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "bmp", "svg"];
+
+/// File name stem for clipboard images, which have no name of their own
+const CLIPBOARD_STEM: &str = "image";
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Storing Assets
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Check if a path names an image file by its extension.
+pub fn is_image_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            IMAGE_EXTENSIONS
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(ext))
+        })
+}
+
+/// Get the assets folder for a document.
+///
+/// The workspace's `assets_dir` (or [`DEFAULT_ASSETS_DIR`]) is resolved
+/// against the document's folder unless it is absolute.
+pub fn assets_dir_for(document: &Path, workspace: Option<&Workspace>) -> PathBuf {
+    let setting = workspace
+        .map(|w| w.settings.assets_dir.trim())
+        .filter(|dir| !dir.is_empty())
+        .unwrap_or(DEFAULT_ASSETS_DIR);
+    let base = document.parent().unwrap_or(Path::new(""));
+    normalize_path(&base.join(setting))
+}
+
+/// Copy an image file into the assets folder.
+///
+/// Returns the path of the stored (or already present) asset.
+pub fn store_image_file(source: &Path, assets_dir: &Path) -> io::Result<PathBuf> {
+    let bytes = fs::read(source)?;
+    let stem = source
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .map(sanitize_stem)
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| CLIPBOARD_STEM.to_string());
+    let ext = source
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("png")
+        .to_lowercase();
+    store_asset(&bytes, &stem, &ext, assets_dir, SystemTime::now())
+}
+
+/// Encode clipboard pixels (RGBA, 8 bits per channel) as PNG and store them.
+pub fn store_rgba_image(
+    width: usize,
+    height: usize,
+    rgba: &[u8],
+    assets_dir: &Path,
+) -> io::Result<PathBuf> {
+    let wrong_size = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "clipboard image has the wrong size",
+        )
+    };
+    let image = image::RgbaImage::from_raw(width as u32, height as u32, rgba.to_vec())
+        .ok_or_else(wrong_size)?;
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    store_asset(&png, CLIPBOARD_STEM, "png", assets_dir, SystemTime::now())
+}
+
+/// Write `bytes` to a new timestamped file, or find an identical existing one.
+fn store_asset(
+    bytes: &[u8],
+    stem: &str,
+    ext: &str,
+    assets_dir: &Path,
+    now: SystemTime,
+) -> io::Result<PathBuf> {
+    fs::create_dir_all(assets_dir)?;
+    if let Some(existing) = find_duplicate(bytes, assets_dir)? {
+        debug!("Reusing identical asset {}", existing.display());
+        return Ok(existing);
+    }
+
+    let base = format!("{}-{}", stem, timestamp(now));
+    let mut path = assets_dir.join(format!("{}.{}", base, ext));
+    let mut counter = 2;
+    while path.exists() {
+        path = assets_dir.join(format!("{}-{}.{}", base, counter, ext));
+        counter += 1;
+    }
+    atomic_write(&path, bytes)?;
+    debug!("Stored asset {}", path.display());
+    Ok(path)
+}
+
+/// Find a file in the assets folder with exactly these bytes.
+fn find_duplicate(bytes: &[u8], assets_dir: &Path) -> io::Result<Option<PathBuf>> {
+    for entry in fs::read_dir(assets_dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let same_size = entry
+            .metadata()
+            .is_ok_and(|m| m.is_file() && m.len() == bytes.len() as u64);
+        if same_size && is_image_file(&path) && fs::read(&path)? == bytes {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+/// Replace characters that would need escaping in a Markdown link.
+fn sanitize_stem(stem: &str) -> String {
+    stem.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>()
+        .trim_matches('-')
+        .to_string()
+}
+
+/// Format a time as `YYYYMMDD-HHMMSS` (UTC).
+fn timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Links
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Build the Markdown image reference for an asset, relative to the document's folder.
+pub fn image_link(document_dir: &Path, asset: &Path) -> String {
+    format!("![]({})", relative_image_url(document_dir, asset))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_is_image_file() {
+        assert!(is_image_file(Path::new("shot.PNG")));
+        assert!(is_image_file(Path::new("a/b/diagram.svg")));
+        assert!(!is_image_file(Path::new("notes.md")));
+        assert!(!is_image_file(Path::new("png")));
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(SystemTime::UNIX_EPOCH), "19700101-000000");
+        // 2024-02-29 13:45:07 UTC
+        let leap_day = SystemTime::UNIX_EPOCH + Duration::from_secs(1_709_214_307);
+        assert_eq!(timestamp(leap_day), "20240229-134507");
+    }
+
+    #[test]
+    fn test_store_asset_names_and_dedupes() {
+        let dir = tempfile::tempdir().unwrap();
+        let assets = dir.path().join("assets");
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_709_214_307);
+
+        let first = store_asset(b"one", "image", "png", &assets, now).unwrap();
+        assert_eq!(first, assets.join("image-20240229-134507.png"));
+        assert_eq!(fs::read(&first).unwrap(), b"one");
+
+        // Different bytes at the same second get a counter
+        let second = store_asset(b"two", "image", "png", &assets, now).unwrap();
+        assert_eq!(second, assets.join("image-20240229-134507-2.png"));
+
+        // Identical bytes reuse the existing file
+        let again = store_asset(b"one", "photo", "png", &assets, now).unwrap();
+        assert_eq!(again, first);
+        assert_eq!(fs::read_dir(&assets).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_store_image_file_and_rgba() {
+        let dir = tempfile::tempdir().unwrap();
+        let assets = dir.path().join("assets");
+        let source = dir.path().join("My Screenshot (1).PNG");
+        fs::write(&source, b"png bytes").unwrap();
+
+        let stored = store_image_file(&source, &assets).unwrap();
+        let name = stored.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("My-Screenshot--1-2"), "{}", name);
+        assert!(name.ends_with(".png"));
+
+        let pixels = [255u8, 0, 0, 255].repeat(4);
+        let png = store_rgba_image(2, 2, &pixels, &assets).unwrap();
+        let decoded = image::open(&png).unwrap().to_rgba8();
+        assert_eq!(decoded.dimensions(), (2, 2));
+        assert!(store_rgba_image(3, 3, &pixels, &assets).is_err());
+    }
+
+    #[test]
+    fn test_assets_dir_and_links() {
+        let document = Path::new("/notes/daily/today.md");
+        assert_eq!(
+            assets_dir_for(document, None),
+            PathBuf::from("/notes/daily/assets")
+        );
+
+        let root = tempfile::tempdir().unwrap();
+        let mut workspace = Workspace::new(root.path().to_path_buf());
+        workspace.settings.assets_dir = "../media".to_string();
+        assert_eq!(
+            assets_dir_for(document, Some(&workspace)),
+            PathBuf::from("/notes/media")
+        );
+
+        let doc_dir = Path::new("/notes/daily");
+        assert_eq!(
+            image_link(doc_dir, Path::new("/notes/daily/assets/image-1.png")),
+            "![](assets/image-1.png)"
+        );
+        assert_eq!(
+            image_link(doc_dir, Path::new("/notes/my media/a.png")),
+            "![](../my%20media/a.png)"
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! Auto-save scheduling for Ferrite
+//!
+//! This module decides *when* modified tabs should be written to disk
+//! automatically. It supports three triggers, all gated by `Settings::auto_save`:
+//!
+//! - **Interval**: every `auto_save_interval_secs` seconds
+//! - **Focus loss**: when the application window loses focus
+//! - **Tab switch**: when the active tab changes
+//!
+//! The actual writing is done by `AppState::auto_save_modified_tabs`, which
+//! returns an [`AutoSaveReport`] describing what happened to each tab.
+
+use crate::config::Settings;
+use crate::error::Error;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Triggers
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// The reason an auto-save pass was started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoSaveTrigger {
+    /// The configured interval elapsed
+    Interval,
+    /// The application window lost focus
+    FocusLost,
+    /// The user switched to another tab
+    TabSwitch,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Report
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Outcome of a single auto-save pass over all open tabs.
+#[derive(Debug, Default)]
+pub struct AutoSaveReport {
+    /// Files that were written successfully
+    pub saved: Vec<PathBuf>,
+    /// Files that were left alone because they changed on disk since they
+    /// were last loaded or saved
+    pub skipped_external: Vec<PathBuf>,
+    /// Files that could not be written
+    pub failed: Vec<(PathBuf, Error)>,
+}
+
+impl AutoSaveReport {
+    /// Check if nothing was saved, skipped, or failed.
+    pub fn is_empty(&self) -> bool {
+        self.saved.is_empty() && self.skipped_external.is_empty() && self.failed.is_empty()
+    }
+}
+
+/// A kind of auto-save problem reported to the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AutoSaveProblem {
+    /// The file was skipped because it changed on disk
+    SkippedExternal,
+    /// The file could not be written
+    Failed,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Scheduler
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Tracks auto-save timing, focus, and active tab between frames.
+///
+/// Call [`AutoSaveScheduler::poll`] once per frame; it returns a trigger
+/// when an auto-save pass should run.
+#[derive(Debug)]
+pub struct AutoSaveScheduler {
+    /// App time (seconds) of the last auto-save pass
+    last_run: f64,
+    /// Window focus state from the previous poll
+    was_focused: bool,
+    /// Active tab ID from the previous poll
+    last_tab_id: Option<usize>,
+    /// Problems already reported to the user for each path, so a persistent
+    /// failure is not re-announced on every interval
+    reported: HashSet<(PathBuf, AutoSaveProblem)>,
+}
+
+impl Default for AutoSaveScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AutoSaveScheduler {
+    /// Create a new scheduler. The first interval starts at app time zero.
+    pub fn new() -> Self {
+        Self {
+            last_run: 0.0,
+            was_focused: true,
+            last_tab_id: None,
+            reported: HashSet::new(),
+        }
+    }
+
+    /// Check whether an auto-save pass should run this frame.
+    ///
+    /// `now` is the app time in seconds, `focused` the current window focus
+    /// state, and `active_tab_id` the ID of the active tab. Focus and tab
+    /// changes are always tracked, even when auto-save is disabled, so that
+    /// enabling it later doesn't fire a stale trigger.
+    pub fn poll(
+        &mut self,
+        settings: &Settings,
+        now: f64,
+        focused: bool,
+        active_tab_id: Option<usize>,
+    ) -> Option<AutoSaveTrigger> {
+        let lost_focus = self.was_focused && !focused;
+        let switched_tab = self.last_tab_id.is_some() && self.last_tab_id != active_tab_id;
+        self.was_focused = focused;
+        self.last_tab_id = active_tab_id;
+
+        if !settings.auto_save {
+            self.last_run = now;
+            return None;
+        }
+
+        let trigger = if lost_focus && settings.auto_save_on_focus_loss {
+            Some(AutoSaveTrigger::FocusLost)
+        } else if switched_tab && settings.auto_save_on_tab_switch {
+            Some(AutoSaveTrigger::TabSwitch)
+        } else if now - self.last_run >= settings.auto_save_interval_secs as f64 {
+            Some(AutoSaveTrigger::Interval)
+        } else {
+            None
+        };
+
+        if trigger.is_some() {
+            self.last_run = now;
+        }
+        trigger
+    }
+
+    /// Time remaining until the next interval trigger, if auto-save is enabled.
+    ///
+    /// Used to schedule a repaint so the interval fires even when idle.
+    pub fn time_until_next(&self, settings: &Settings, now: f64) -> Option<Duration> {
+        if !settings.auto_save {
+            return None;
+        }
+        let remaining = settings.auto_save_interval_secs as f64 - (now - self.last_run);
+        Some(Duration::from_secs_f64(remaining.max(0.0)))
+    }
+
+    /// Record that a problem with `path` is being reported.
+    ///
+    /// Returns `true` the first time for a given path and kind of problem,
+    /// `false` while that problem persists. Call
+    /// [`AutoSaveScheduler::clear_reported`] once the file saves
+    /// successfully again.
+    pub fn should_report(&mut self, path: &Path, problem: AutoSaveProblem) -> bool {
+        self.reported.insert((path.to_path_buf(), problem))
+    }
+
+    /// Forget the previously reported problems for `path`.
+    pub fn clear_reported(&mut self, path: &Path) {
+        self.reported.retain(|(reported, _)| reported != path);
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled_settings() -> Settings {
+        Settings {
+            auto_save: true,
+            auto_save_interval_secs: 30,
+            ..Settings::default()
+        }
+    }
+
+    #[test]
+    fn test_disabled_never_triggers() {
+        let settings = Settings::default();
+        let mut scheduler = AutoSaveScheduler::new();
+        assert_eq!(scheduler.poll(&settings, 0.0, true, Some(1)), None);
+        assert_eq!(scheduler.poll(&settings, 1000.0, false, Some(2)), None);
+        assert_eq!(scheduler.time_until_next(&settings, 1000.0), None);
+    }
+
+    #[test]
+    fn test_interval_trigger() {
+        let settings = enabled_settings();
+        let mut scheduler = AutoSaveScheduler::new();
+        assert_eq!(scheduler.poll(&settings, 10.0, true, Some(1)), None);
+        assert_eq!(
+            scheduler.poll(&settings, 30.0, true, Some(1)),
+            Some(AutoSaveTrigger::Interval)
+        );
+        // Interval restarts after a pass
+        assert_eq!(scheduler.poll(&settings, 45.0, true, Some(1)), None);
+        assert_eq!(
+            scheduler.time_until_next(&settings, 45.0),
+            Some(Duration::from_secs(15))
+        );
+    }
+
+    #[test]
+    fn test_focus_loss_trigger() {
+        let settings = enabled_settings();
+        let mut scheduler = AutoSaveScheduler::new();
+        assert_eq!(scheduler.poll(&settings, 1.0, true, Some(1)), None);
+        assert_eq!(
+            scheduler.poll(&settings, 2.0, false, Some(1)),
+            Some(AutoSaveTrigger::FocusLost)
+        );
+        // Staying unfocused doesn't re-trigger
+        assert_eq!(scheduler.poll(&settings, 3.0, false, Some(1)), None);
+    }
+
+    #[test]
+    fn test_tab_switch_trigger() {
+        let settings = enabled_settings();
+        let mut scheduler = AutoSaveScheduler::new();
+        // First poll only records the active tab
+        assert_eq!(scheduler.poll(&settings, 1.0, true, Some(1)), None);
+        assert_eq!(
+            scheduler.poll(&settings, 2.0, true, Some(2)),
+            Some(AutoSaveTrigger::TabSwitch)
+        );
+        assert_eq!(scheduler.poll(&settings, 3.0, true, Some(2)), None);
+    }
+
+    #[test]
+    fn test_triggers_respect_settings() {
+        let settings = Settings {
+            auto_save_on_focus_loss: false,
+            auto_save_on_tab_switch: false,
+            ..enabled_settings()
+        };
+        let mut scheduler = AutoSaveScheduler::new();
+        assert_eq!(scheduler.poll(&settings, 1.0, true, Some(1)), None);
+        assert_eq!(scheduler.poll(&settings, 2.0, false, Some(2)), None);
+    }
+
+    #[test]
+    fn test_enabling_does_not_fire_stale_triggers() {
+        let mut settings = Settings::default();
+        let mut scheduler = AutoSaveScheduler::new();
+        scheduler.poll(&settings, 100.0, true, Some(1));
+        scheduler.poll(&settings, 200.0, false, Some(2));
+
+        settings.auto_save = true;
+        settings.auto_save_interval_secs = 30;
+        assert_eq!(scheduler.poll(&settings, 201.0, false, Some(2)), None);
+    }
+
+    #[test]
+    fn test_should_report_once_until_cleared() {
+        let mut scheduler = AutoSaveScheduler::new();
+        let path = PathBuf::from("/tmp/notes.md");
+        assert!(scheduler.should_report(&path, AutoSaveProblem::Failed));
+        assert!(!scheduler.should_report(&path, AutoSaveProblem::Failed));
+        scheduler.clear_reported(&path);
+        assert!(scheduler.should_report(&path, AutoSaveProblem::Failed));
+    }
+
+    #[test]
+    fn test_skipped_then_failed_reports_both() {
+        let mut scheduler = AutoSaveScheduler::new();
+        let path = PathBuf::from("/tmp/notes.md");
+        assert!(scheduler.should_report(&path, AutoSaveProblem::SkippedExternal));
+        assert!(!scheduler.should_report(&path, AutoSaveProblem::SkippedExternal));
+
+        // A failure after a skip is a new problem
+        assert!(scheduler.should_report(&path, AutoSaveProblem::Failed));
+        assert!(!scheduler.should_report(&path, AutoSaveProblem::Failed));
+
+        scheduler.clear_reported(&path);
+        assert!(scheduler.should_report(&path, AutoSaveProblem::SkippedExternal));
+        assert!(scheduler.should_report(&path, AutoSaveProblem::Failed));
+    }
+}
@@ -0,0 +1,572 @@
+//! Text encoding and line-ending handling for Ferrite
+//!
+//! Files are decoded into normalized (LF) UTF-8 strings for editing, and the
+//! original encoding, byte-order mark and line-ending style are remembered as
+//! a [`FileFormat`] so saving writes the file back the way it was found.
+//!
+//! Supported encodings are UTF-8, UTF-16 (LE/BE) and Latin-1 (ISO-8859-1).
+//! Files that look binary are refused rather than decoded.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// UTF-8 byte-order mark
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+/// UTF-16 little-endian byte-order mark
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+/// UTF-16 big-endian byte-order mark
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// Number of leading bytes inspected by the binary/UTF-16 heuristics
+const SNIFF_LEN: usize = 8192;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Format Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Character encoding of a file on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextEncoding {
+    /// UTF-8 (the default for new files)
+    #[default]
+    Utf8,
+    /// UTF-16 little-endian
+    Utf16Le,
+    /// UTF-16 big-endian
+    Utf16Be,
+    /// ISO-8859-1 (each byte is one character)
+    Latin1,
+}
+
+impl TextEncoding {
+    /// All supported encodings, in menu order.
+    pub fn all() -> &'static [TextEncoding] {
+        &[
+            TextEncoding::Utf8,
+            TextEncoding::Utf16Le,
+            TextEncoding::Utf16Be,
+            TextEncoding::Latin1,
+        ]
+    }
+
+    /// Get the display label for this encoding.
+    pub fn label(&self) -> &'static str {
+        match self {
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Utf16Le => "UTF-16 LE",
+            TextEncoding::Utf16Be => "UTF-16 BE",
+            TextEncoding::Latin1 => "Latin-1",
+        }
+    }
+
+    /// Get the byte-order mark for this encoding (empty for Latin-1).
+    pub fn bom(&self) -> &'static [u8] {
+        match self {
+            TextEncoding::Utf8 => UTF8_BOM,
+            TextEncoding::Utf16Le => UTF16LE_BOM,
+            TextEncoding::Utf16Be => UTF16BE_BOM,
+            TextEncoding::Latin1 => &[],
+        }
+    }
+
+    /// Whether this encoding can carry a byte-order mark.
+    pub fn supports_bom(&self) -> bool {
+        !self.bom().is_empty()
+    }
+}
+
+/// Line-ending style of a file on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    /// Unix-style `\n`
+    #[default]
+    Lf,
+    /// Windows-style `\r\n`
+    Crlf,
+}
+
+impl LineEnding {
+    /// Get the display label for this line ending.
+    pub fn label(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::Crlf => "CRLF",
+        }
+    }
+
+    /// Get the line terminator string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+/// How a file's text is stored on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileFormat {
+    /// Character encoding
+    pub encoding: TextEncoding,
+    /// Whether the file starts with a byte-order mark
+    pub bom: bool,
+    /// Line-ending style
+    pub line_ending: LineEnding,
+}
+
+impl FileFormat {
+    /// Get a compact label for the encoding, e.g. "UTF-8 BOM".
+    pub fn encoding_label(&self) -> String {
+        if self.bom && self.encoding == TextEncoding::Utf8 {
+            "UTF-8 BOM".to_string()
+        } else {
+            self.encoding.label().to_string()
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Errors
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Errors from decoding or encoding file contents.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EncodingError {
+    /// The file appears to be binary
+    Binary,
+    /// The bytes are not valid in the encoding announced by the BOM
+    Invalid(TextEncoding),
+    /// A character cannot be represented in the target encoding
+    Unrepresentable { ch: char, encoding: TextEncoding },
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodingError::Binary => write!(f, "File appears to be binary and cannot be edited"),
+            EncodingError::Invalid(encoding) => {
+                write!(f, "File is not valid {}", encoding.label())
+            }
+            EncodingError::Unrepresentable { ch, encoding } => write!(
+                f,
+                "Character '{}' (U+{:04X}) cannot be saved as {}",
+                ch,
+                *ch as u32,
+                encoding.label()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+impl From<EncodingError> for io::Error {
+    fn from(err: EncodingError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Decoding
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Decode raw file bytes into LF-normalized text and its on-disk format.
+///
+/// Detection order: byte-order mark, BOM-less UTF-16, binary check, UTF-8,
+/// and finally Latin-1 (which accepts any byte sequence).
+pub fn decode(bytes: &[u8]) -> Result<(String, FileFormat), EncodingError> {
+    let (encoding, bom, body) = if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+        (TextEncoding::Utf8, true, rest)
+    } else if let Some(rest) = bytes.strip_prefix(UTF16LE_BOM) {
+        (TextEncoding::Utf16Le, true, rest)
+    } else if let Some(rest) = bytes.strip_prefix(UTF16BE_BOM) {
+        (TextEncoding::Utf16Be, true, rest)
+    } else if let Some(encoding) = sniff_utf16(bytes) {
+        (encoding, false, bytes)
+    } else if looks_binary(bytes) {
+        return Err(EncodingError::Binary);
+    } else if std::str::from_utf8(bytes).is_ok() {
+        (TextEncoding::Utf8, false, bytes)
+    } else {
+        (TextEncoding::Latin1, false, bytes)
+    };
+
+    let raw = match encoding {
+        TextEncoding::Utf8 => std::str::from_utf8(body)
+            .map_err(|_| EncodingError::Invalid(encoding))?
+            .to_string(),
+        TextEncoding::Utf16Le | TextEncoding::Utf16Be => decode_utf16(body, encoding)?,
+        TextEncoding::Latin1 => body.iter().map(|&b| b as char).collect(),
+    };
+
+    let line_ending = detect_line_ending(&raw);
+    let text = normalize_line_endings(&raw);
+    Ok((
+        text,
+        FileFormat {
+            encoding,
+            bom,
+            line_ending,
+        },
+    ))
+}
+
+/// Decode UTF-16 code units, rejecting odd lengths and unpaired surrogates.
+fn decode_utf16(body: &[u8], encoding: TextEncoding) -> Result<String, EncodingError> {
+    if body.len() % 2 != 0 {
+        return Err(EncodingError::Invalid(encoding));
+    }
+    let units = body.chunks_exact(2).map(|pair| match encoding {
+        TextEncoding::Utf16Be => u16::from_be_bytes([pair[0], pair[1]]),
+        _ => u16::from_le_bytes([pair[0], pair[1]]),
+    });
+    char::decode_utf16(units)
+        .collect::<Result<String, _>>()
+        .map_err(|_| EncodingError::Invalid(encoding))
+}
+
+/// Guess BOM-less UTF-16 from the pattern of zero bytes in mostly-ASCII text.
+fn sniff_utf16(bytes: &[u8]) -> Option<TextEncoding> {
+    let sample = &bytes[..bytes.len().min(SNIFF_LEN) & !1];
+    if sample.len() < 4 {
+        return None;
+    }
+    let pairs = sample.len() / 2;
+    let even_zeros = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_zeros = sample
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .filter(|&&b| b == 0)
+        .count();
+
+    // ASCII text in UTF-16 has a zero in every other byte and none in between
+    if odd_zeros * 10 >= pairs * 7 && even_zeros * 20 <= pairs {
+        Some(TextEncoding::Utf16Le)
+    } else if even_zeros * 10 >= pairs * 7 && odd_zeros * 20 <= pairs {
+        Some(TextEncoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+/// Check if data looks binary: any NUL byte, or many control characters.
+fn looks_binary(bytes: &[u8]) -> bool {
+    let sample = &bytes[..bytes.len().min(SNIFF_LEN)];
+    if sample.contains(&0) {
+        return true;
+    }
+    let control = sample
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\n' | b'\r' | b'\t' | 0x0C | 0x1B))
+        .count();
+    control * 10 > sample.len()
+}
+
+/// Detect the dominant line ending (ties and files without newlines are LF).
+pub fn detect_line_ending(text: &str) -> LineEnding {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    if crlf > lf {
+        LineEnding::Crlf
+    } else {
+        LineEnding::Lf
+    }
+}
+
+/// Convert all CRLF line endings to LF.
+pub fn normalize_line_endings(text: &str) -> String {
+    if text.contains("\r\n") {
+        text.replace("\r\n", "\n")
+    } else {
+        text.to_string()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Encoding
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Encode editor text into bytes using the given on-disk format.
+///
+/// Line endings are normalized first so mixed endings (e.g. from pasted
+/// text) all come out in the file's style.
+pub fn encode(text: &str, format: &FileFormat) -> Result<Vec<u8>, EncodingError> {
+    let normalized = normalize_line_endings(text);
+    let text = match format.line_ending {
+        LineEnding::Lf => normalized,
+        ending => normalized.replace('\n', ending.as_str()),
+    };
+
+    let mut bytes = Vec::with_capacity(text.len() + 3);
+    if format.bom {
+        bytes.extend_from_slice(format.encoding.bom());
+    }
+
+    match format.encoding {
+        TextEncoding::Utf8 => bytes.extend_from_slice(text.as_bytes()),
+        TextEncoding::Utf16Le => {
+            for unit in text.encode_utf16() {
+                bytes.extend_from_slice(&unit.to_le_bytes());
+            }
+        }
+        TextEncoding::Utf16Be => {
+            for unit in text.encode_utf16() {
+                bytes.extend_from_slice(&unit.to_be_bytes());
+            }
+        }
+        TextEncoding::Latin1 => {
+            for ch in text.chars() {
+                let code = ch as u32;
+                if code > 0xFF {
+                    return Err(EncodingError::Unrepresentable {
+                        ch,
+                        encoding: format.encoding,
+                    });
+                }
+                bytes.push(code as u8);
+            }
+        }
+    }
+
+    Ok(bytes)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// File Helpers
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Read a text file, detecting its format.
+///
+/// Binary or undecodable files fail with `io::ErrorKind::InvalidData`.
+pub fn read_text_file(path: &Path) -> io::Result<(String, FileFormat)> {
+    let bytes = std::fs::read(path)?;
+    Ok(decode(&bytes)?)
+}
+
+/// Read a text file of at most `max_len` bytes, detecting its format.
+///
+/// Only the first bytes are read to reject binary files, so large binaries
+/// aren't loaded just to be skipped. Larger files fail with
+/// `io::ErrorKind::InvalidInput`, binary or undecodable ones with
+/// `io::ErrorKind::InvalidData`.
+pub fn read_text_file_limited(path: &Path, max_len: u64) -> io::Result<(String, FileFormat)> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    if len > max_len {
+        return Err(too_large(max_len));
+    }
+
+    let mut bytes = Vec::with_capacity(len as usize);
+    (&mut file).take(SNIFF_LEN as u64).read_to_end(&mut bytes)?;
+    let has_bom = [UTF8_BOM, UTF16LE_BOM, UTF16BE_BOM]
+        .iter()
+        .any(|bom| bytes.starts_with(bom));
+    if !has_bom && sniff_utf16(&bytes).is_none() && looks_binary(&bytes) {
+        return Err(EncodingError::Binary.into());
+    }
+
+    // The file may have grown since its size was read
+    let rest = max_len.saturating_sub(bytes.len() as u64).saturating_add(1);
+    file.take(rest).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > max_len {
+        return Err(too_large(max_len));
+    }
+    Ok(decode(&bytes)?)
+}
+
+/// The error for a file over the size limit of [`read_text_file_limited`].
+fn too_large(max_len: u64) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("File is larger than {} bytes", max_len),
+    )
+}
+
+/// Encode and atomically write a text file in the given format.
+///
+/// Characters that the encoding cannot represent fail with
+/// `io::ErrorKind::InvalidData` before anything is written.
+pub fn write_text_file(path: &Path, text: &str, format: &FileFormat) -> io::Result<()> {
+    let bytes = encode(text, format)?;
+    super::save::atomic_write(path, &bytes)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(bytes: &[u8]) -> Vec<u8> {
+        let (text, format) = decode(bytes).unwrap();
+        encode(&text, &format).unwrap()
+    }
+
+    #[test]
+    fn test_decode_plain_utf8() {
+        let (text, format) = decode("# Héllo\n".as_bytes()).unwrap();
+        assert_eq!(text, "# Héllo\n");
+        assert_eq!(format, FileFormat::default());
+    }
+
+    #[test]
+    fn test_decode_utf8_bom() {
+        let (text, format) = decode(b"\xEF\xBB\xBF# Title\n").unwrap();
+        assert_eq!(text, "# Title\n");
+        assert_eq!(format.encoding, TextEncoding::Utf8);
+        assert!(format.bom);
+        assert_eq!(format.encoding_label(), "UTF-8 BOM");
+    }
+
+    #[test]
+    fn test_decode_crlf_normalizes_to_lf() {
+        let (text, format) = decode(b"a\r\nb\r\nc").unwrap();
+        assert_eq!(text, "a\nb\nc");
+        assert_eq!(format.line_ending, LineEnding::Crlf);
+    }
+
+    #[test]
+    fn test_decode_utf16_with_bom() {
+        let le: Vec<u8> = [0xFF, 0xFE, b'h', 0, b'i', 0].to_vec();
+        let (text, format) = decode(&le).unwrap();
+        assert_eq!(text, "hi");
+        assert_eq!(format.encoding, TextEncoding::Utf16Le);
+        assert!(format.bom);
+
+        let be: Vec<u8> = [0xFE, 0xFF, 0, b'h', 0, b'i'].to_vec();
+        let (text, format) = decode(&be).unwrap();
+        assert_eq!(text, "hi");
+        assert_eq!(format.encoding, TextEncoding::Utf16Be);
+    }
+
+    #[test]
+    fn test_decode_utf16_without_bom() {
+        let bytes: Vec<u8> = "hello world\n"
+            .encode_utf16()
+            .flat_map(|u| u.to_le_bytes())
+            .collect();
+        let (text, format) = decode(&bytes).unwrap();
+        assert_eq!(text, "hello world\n");
+        assert_eq!(format.encoding, TextEncoding::Utf16Le);
+        assert!(!format.bom);
+    }
+
+    #[test]
+    fn test_decode_latin1_fallback() {
+        let (text, format) = decode(b"caf\xE9\n").unwrap();
+        assert_eq!(text, "café\n");
+        assert_eq!(format.encoding, TextEncoding::Latin1);
+    }
+
+    #[test]
+    fn test_decode_rejects_binary() {
+        assert_eq!(
+            decode(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+            Err(EncodingError::Binary)
+        );
+        assert_eq!(
+            decode(&[1, 2, 3, 4, 5, 6, 7, 8]),
+            Err(EncodingError::Binary)
+        );
+    }
+
+    #[test]
+    fn test_read_text_file_limited() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let text = temp_dir.path().join("notes.md");
+        std::fs::write(&text, "a\r\nb\r\n").unwrap();
+        let (content, format) = read_text_file_limited(&text, 100).unwrap();
+        assert_eq!(content, "a\nb\n");
+        assert_eq!(format.line_ending, LineEnding::Crlf);
+
+        let err = read_text_file_limited(&text, 5).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        // Binary files are refused from their first bytes
+        let binary = temp_dir.path().join("image.png");
+        let mut bytes = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        bytes.resize(SNIFF_LEN * 4, 0);
+        std::fs::write(&binary, &bytes).unwrap();
+        let err = read_text_file_limited(&binary, u64::MAX).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_decode_empty_file() {
+        let (text, format) = decode(b"").unwrap();
+        assert_eq!(text, "");
+        assert_eq!(format, FileFormat::default());
+    }
+
+    #[test]
+    fn test_roundtrip_preserves_bytes() {
+        let samples: &[&[u8]] = &[
+            b"plain\nlf\n",
+            b"windows\r\nfile\r\n",
+            b"\xEF\xBB\xBFbom\r\n",
+            b"caf\xE9 cr\xE8me\r\n",
+            &[0xFF, 0xFE, b'a', 0, b'\r', 0, b'\n', 0],
+        ];
+        for sample in samples {
+            assert_eq!(roundtrip(sample), sample.to_vec());
+        }
+    }
+
+    #[test]
+    fn test_encode_converts_line_endings() {
+        let format = FileFormat {
+            line_ending: LineEnding::Crlf,
+            ..FileFormat::default()
+        };
+        assert_eq!(encode("a\nb\r\nc", &format).unwrap(), b"a\r\nb\r\nc");
+        assert_eq!(
+            encode("a\r\nb\n", &FileFormat::default()).unwrap(),
+            b"a\nb\n"
+        );
+    }
+
+    #[test]
+    fn test_encode_latin1_unrepresentable() {
+        let format = FileFormat {
+            encoding: TextEncoding::Latin1,
+            ..FileFormat::default()
+        };
+        let err = encode("price: €5", &format).unwrap_err();
+        assert!(matches!(
+            err,
+            EncodingError::Unrepresentable { ch: '€', .. }
+        ));
+        assert!(err.to_string().contains("U+20AC"));
+    }
+
+    #[test]
+    fn test_detect_line_ending_majority() {
+        assert_eq!(detect_line_ending("a\r\nb\r\nc\n"), LineEnding::Crlf);
+        assert_eq!(detect_line_ending("a\nb\nc\r\n"), LineEnding::Lf);
+        assert_eq!(detect_line_ending("no newline"), LineEnding::Lf);
+    }
+
+    #[test]
+    fn test_file_format_serde_defaults() {
+        let format: FileFormat = serde_json::from_str("{}").unwrap();
+        assert_eq!(format, FileFormat::default());
+
+        let json = serde_json::to_string(&FileFormat {
+            encoding: TextEncoding::Utf16Le,
+            bom: true,
+            line_ending: LineEnding::Crlf,
+        })
+        .unwrap();
+        assert!(json.contains("utf16_le"));
+        assert!(json.contains("crlf"));
+    }
+}
@@ -0,0 +1,222 @@
+//! `.gitignore` rules
+//!
+//! Workspace search skips what git ignores. This implements the commonly
+//! used part of the syntax on top of [`Glob`]:
+//!
+//! - blank lines and `#` comments are skipped
+//! - `\` escapes the next character (`\#`, `\!`, `\*`, a trailing `\ `)
+//! - `!pattern` re-includes a path excluded by an earlier rule
+//! - a trailing `/` only matches folders
+//! - a leading or inner `/` anchors the pattern to the `.gitignore`'s folder;
+//!   otherwise it matches a name at any depth
+//!
+//! Each folder's `.gitignore` applies below it, and deeper files override
+//! their parents. The last matching rule wins.
+
+use super::glob::Glob;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the ignore file read in each folder
+const GITIGNORE: &str = ".gitignore";
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Ignore File
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// One rule of a `.gitignore` file.
+#[derive(Debug, Clone)]
+struct Rule {
+    /// Pattern, matched against paths relative to the file's folder
+    glob: Glob,
+    /// Whether the rule re-includes matching paths (`!pattern`)
+    negated: bool,
+    /// Whether the rule only matches folders (`pattern/`)
+    dir_only: bool,
+}
+
+/// The rules of one `.gitignore` file.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreFile {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreFile {
+    /// Parse the contents of a `.gitignore` file. Invalid patterns are
+    /// skipped.
+    pub fn parse(text: &str) -> Self {
+        Self {
+            rules: text.lines().filter_map(parse_rule).collect(),
+        }
+    }
+
+    /// Check a path relative to the file's folder.
+    ///
+    /// Returns `Some(true)` if it's ignored, `Some(false)` if a `!` rule
+    /// re-includes it, and `None` if no rule mentions it.
+    pub fn matched(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.dir_only) && rule.glob.is_match(path))
+            .map(|rule| !rule.negated)
+    }
+}
+
+fn parse_rule(line: &str) -> Option<Rule> {
+    let line = trim_unescaped_end(line);
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (negated, pattern) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (dir_only, pattern) = match pattern.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+    let anchored = pattern.contains('/');
+    let pattern = pattern.trim_start_matches('/');
+    if pattern.is_empty() {
+        return None;
+    }
+
+    Some(Rule {
+        glob: Glob::new_escaped(pattern, anchored).ok()?,
+        negated,
+        dir_only,
+    })
+}
+
+/// Remove trailing whitespace, except a space escaped with `\`.
+fn trim_unescaped_end(line: &str) -> &str {
+    let trimmed = line.trim_end();
+    match line[trimmed.len()..].chars().next() {
+        Some(space) if trimmed.ends_with('\\') => &line[..trimmed.len() + space.len_utf8()],
+        _ => trimmed,
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Folder Tree
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// The `.gitignore` files of a folder tree, read as folders are visited.
+#[derive(Debug, Clone)]
+pub struct GitIgnore {
+    /// Top folder; `.gitignore` files above it are not read
+    root: PathBuf,
+    /// Parsed file per folder (None if the folder has none)
+    files: HashMap<PathBuf, Option<IgnoreFile>>,
+}
+
+impl GitIgnore {
+    /// Create the rules for the tree below `root`.
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            files: HashMap::new(),
+        }
+    }
+
+    /// Check if a path below the root is ignored.
+    ///
+    /// Parent folders are not checked: callers walking the tree skip the
+    /// contents of an ignored folder anyway.
+    pub fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let components: Vec<_> = relative.components().collect();
+
+        let mut ignored = false;
+        let mut dir = self.root.clone();
+        for (i, component) in components.iter().enumerate() {
+            let below: PathBuf = components[i..].iter().collect();
+            if let Some(matched) = self.load(&dir).and_then(|f| f.matched(&below, is_dir)) {
+                ignored = matched;
+            }
+            dir.push(component);
+        }
+        ignored
+    }
+
+    fn load(&mut self, dir: &Path) -> Option<&IgnoreFile> {
+        self.files
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| {
+                fs::read_to_string(dir.join(GITIGNORE))
+                    .ok()
+                    .map(|text| IgnoreFile::parse(&text))
+            })
+            .as_ref()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_ignore_file_rules() {
+        let file = IgnoreFile::parse(
+            "# build output\n\
+             *.log\n\
+             !keep.log\n\
+             build/\n\
+             /todo.md\n\
+             docs/*.tmp\n\
+             \\#notes\n",
+        );
+        let check = |path: &str, is_dir: bool| file.matched(Path::new(path), is_dir);
+
+        assert_eq!(check("debug.log", false), Some(true));
+        assert_eq!(check("nested/debug.log", false), Some(true));
+        assert_eq!(check("keep.log", false), Some(false));
+        assert_eq!(check("build", true), Some(true));
+        assert_eq!(check("build", false), None);
+        assert_eq!(check("todo.md", false), Some(true));
+        assert_eq!(check("sub/todo.md", false), None);
+        assert_eq!(check("docs/a.tmp", false), Some(true));
+        assert_eq!(check("other/docs/a.tmp", false), None);
+        assert_eq!(check("#notes", false), Some(true));
+        assert_eq!(check("readme.md", false), None);
+    }
+
+    #[test]
+    fn test_escaped_patterns() {
+        let file = IgnoreFile::parse("\\#file\n\\!important\nwild\\*card\ntrailing\\ \n");
+        let check = |path: &str| file.matched(Path::new(path), false);
+
+        assert_eq!(check("#file"), Some(true));
+        assert_eq!(check("!important"), Some(true));
+        assert_eq!(check("important"), None);
+        assert_eq!(check("wild*card"), Some(true));
+        assert_eq!(check("wildcard"), None);
+        assert_eq!(check("trailing "), Some(true));
+        assert_eq!(check("trailing"), None);
+    }
+
+    #[test]
+    fn test_nested_gitignore() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("notes")).unwrap();
+        fs::write(root.join(GITIGNORE), "*.draft\n").unwrap();
+        fs::write(root.join("notes").join(GITIGNORE), "!*.draft\n/private\n").unwrap();
+
+        let mut ignore = GitIgnore::new(root);
+        assert!(ignore.is_ignored(&root.join("a.draft"), false));
+        assert!(!ignore.is_ignored(&root.join("notes/a.draft"), false));
+        assert!(ignore.is_ignored(&root.join("notes/private"), true));
+        assert!(!ignore.is_ignored(&root.join("private"), true));
+        assert!(!ignore.is_ignored(&root.join("a.md"), false));
+    }
+}
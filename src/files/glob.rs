@@ -0,0 +1,300 @@
+//! Glob patterns and file tree walking
+//!
+//! Headless subcommands accept globs such as `config/**/*.yaml` so they
+//! behave the same on shells that don't expand them (Windows `cmd`, quoted
+//! arguments in CI). Supported syntax:
+//!
+//! - `*` matches any run of characters within a path component
+//! - `?` matches a single character within a path component
+//! - `**` matches any number of directories
+//! - `[abc]` / `[!abc]` match (or exclude) a set of characters
+//! - `{a,b}` matches either alternative
+//!
+//! A pattern without a `/` matches the file name at any depth, like a
+//! `.gitignore` entry.
+
+use crate::workspaces::Workspace;
+use regex::Regex;
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Glob Pattern
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A compiled glob pattern.
+#[derive(Debug, Clone)]
+pub struct Glob {
+    /// Equivalent anchored regex
+    regex: Regex,
+    /// Whether the pattern only applies to file names
+    name_only: bool,
+}
+
+impl Glob {
+    /// Compile a glob pattern.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message for unbalanced `[` or `{`.
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let normalized = pattern.replace('\\', "/");
+        Self::compile(pattern, &normalized, !normalized.contains('/'))
+    }
+
+    /// Compile a `.gitignore` pattern, where `\` escapes the next character
+    /// instead of separating folders.
+    ///
+    /// An anchored pattern always matches the whole relative path, even
+    /// without a `/` (like an entry starting with `/`).
+    pub fn new_escaped(pattern: &str, anchored: bool) -> Result<Self, String> {
+        Self::compile(pattern, pattern, !anchored && !pattern.contains('/'))
+    }
+
+    fn compile(pattern: &str, normalized: &str, name_only: bool) -> Result<Self, String> {
+        let regex = Regex::new(&glob_to_regex(normalized)?)
+            .map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))?;
+        Ok(Self { regex, name_only })
+    }
+
+    /// Check if a relative path matches the pattern.
+    pub fn is_match(&self, path: &Path) -> bool {
+        if self.name_only {
+            return path
+                .file_name()
+                .is_some_and(|name| self.regex.is_match(&name.to_string_lossy()));
+        }
+        self.regex.is_match(&path_to_slash(path))
+    }
+}
+
+/// Check if a string contains glob metacharacters.
+pub fn has_glob_chars(s: &str) -> bool {
+    s.contains(['*', '?', '[', '{'])
+}
+
+/// Translate a glob (with `/` separators) into an anchored regex.
+///
+/// A `\` outside a `[...]` set escapes the next character.
+fn glob_to_regex(glob: &str) -> Result<String, String> {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    let mut in_braces = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                let mut class = String::from("[");
+                if matches!(chars.peek(), Some('!') | Some('^')) {
+                    chars.next();
+                    class.push('^');
+                }
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        closed = true;
+                        break;
+                    }
+                    if c == '\\' || c == '[' {
+                        class.push('\\');
+                    }
+                    class.push(c);
+                }
+                if !closed {
+                    return Err(format!("Unclosed '[' in pattern '{}'", glob));
+                }
+                class.push(']');
+                regex.push_str(&class);
+            }
+            '{' if !in_braces => {
+                in_braces = true;
+                regex.push_str("(?:");
+            }
+            ',' if in_braces => regex.push('|'),
+            '}' if in_braces => {
+                in_braces = false;
+                regex.push(')');
+            }
+            '\\' => match chars.next() {
+                Some(c) => regex.push_str(&regex::escape(&c.to_string())),
+                None => return Err(format!("Trailing '\\' in pattern '{}'", glob)),
+            },
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    if in_braces {
+        return Err(format!("Unclosed '{{' in pattern '{}'", glob));
+    }
+    regex.push('$');
+    Ok(regex)
+}
+
+/// Render a path with `/` separators for matching.
+fn path_to_slash(path: &Path) -> String {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Walking
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Check if a folder or file should be skipped when walking a tree
+/// (dotfiles and the workspace's default hidden folders such as `target`).
+pub fn is_ignored(name: &OsStr) -> bool {
+    let name = name.to_string_lossy();
+    name.starts_with('.') || Workspace::DEFAULT_HIDDEN_PATTERNS.contains(&name.as_ref())
+}
+
+/// Find all files below `dir`, skipping ignored entries, sorted by path.
+pub fn walk_files(dir: &Path) -> Vec<PathBuf> {
+    walk(dir, usize::MAX)
+}
+
+fn walk(dir: &Path, max_depth: usize) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = WalkDir::new(dir)
+        .max_depth(max_depth)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !is_ignored(entry.file_name()))
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .collect();
+    files.sort();
+    files
+}
+
+/// Expand a glob pattern against the filesystem.
+///
+/// The pattern's leading components without metacharacters are the folder
+/// to search from; the rest is matched against paths relative to it. Without
+/// `**`, only as many levels as the pattern has components are searched, so
+/// `*.json` doesn't descend into subfolders.
+///
+/// # Errors
+///
+/// Returns a message if the pattern is malformed.
+pub fn expand(pattern: &str) -> Result<Vec<PathBuf>, String> {
+    let normalized = pattern.replace('\\', "/");
+    let components: Vec<&str> = normalized.split('/').collect();
+    let split = components
+        .iter()
+        .position(|c| has_glob_chars(c))
+        .unwrap_or(components.len());
+
+    let base = match components[..split].join("/") {
+        b if b.is_empty() && normalized.starts_with('/') => PathBuf::from("/"),
+        b if b.is_empty() => PathBuf::from("."),
+        b => PathBuf::from(b),
+    };
+    let rest = components[split..].join("/");
+    if rest.is_empty() {
+        return Ok(if base.is_file() {
+            vec![base]
+        } else {
+            Vec::new()
+        });
+    }
+
+    // Match the whole relative path, even for a single component
+    let glob = Glob::compile(pattern, &rest, false)?;
+    let max_depth = if rest.contains("**") {
+        usize::MAX
+    } else {
+        components.len() - split
+    };
+
+    Ok(walk(&base, max_depth)
+        .into_iter()
+        .filter(|path| {
+            path.strip_prefix(&base)
+                .is_ok_and(|relative| glob.is_match(relative))
+        })
+        .map(|path| {
+            path.strip_prefix("./")
+                .map(Path::to_path_buf)
+                .unwrap_or(path)
+        })
+        .collect())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        Glob::new(pattern).unwrap().is_match(Path::new(path))
+    }
+
+    #[test]
+    fn test_glob_matching() {
+        assert!(matches("*.json", "config/app.json"));
+        assert!(!matches("*.json", "config/app.yaml"));
+        assert!(matches("config/*.y?ml", "config/app.yaml"));
+        assert!(!matches("config/*.yaml", "config/nested/app.yaml"));
+        assert!(matches("config/**/*.yaml", "config/app.yaml"));
+        assert!(matches("config/**/*.yaml", "config/a/b/app.yaml"));
+        assert!(matches("*.{json,toml}", "Cargo.toml"));
+        assert!(matches("file[0-9].md", "file3.md"));
+        assert!(!matches("file[!0-9].md", "file3.md"));
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        assert!(Glob::new("file[0-9.md").is_err());
+        assert!(Glob::new("*.{json").is_err());
+    }
+
+    #[test]
+    fn test_expand() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("conf/nested")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        for file in ["a.json", "conf/b.json", "conf/nested/c.json", ".git/d.json"] {
+            fs::write(root.join(file), "{}").unwrap();
+        }
+
+        let base = root.to_str().unwrap();
+        assert_eq!(
+            expand(&format!("{}/*.json", base)).unwrap(),
+            vec![root.join("a.json")]
+        );
+        assert_eq!(
+            expand(&format!("{}/**/*.json", base)).unwrap(),
+            vec![
+                root.join("a.json"),
+                root.join("conf/b.json"),
+                root.join("conf/nested/c.json"),
+            ]
+        );
+        assert_eq!(
+            expand(&format!("{}/conf/*/*.json", base)).unwrap(),
+            vec![root.join("conf/nested/c.json")]
+        );
+    }
+}
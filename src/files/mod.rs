@@ -1,6 +1,14 @@
 //! File operations module for Ferrite
 //!
 //! This module provides functionality for file dialogs,
-//! including opening and saving files using native system dialogs.
+//! including opening and saving files using native system dialogs,
+//! text encoding detection, atomic saves with backups, glob expansion,
+//! `.gitignore` rules, image assets, and the auto-save scheduler.
 
+pub mod assets;
+pub mod autosave;
 pub mod dialogs;
+pub mod encoding;
+pub mod gitignore;
+pub mod glob;
+pub mod save;
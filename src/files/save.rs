@@ -0,0 +1,333 @@
+//! Safe file saving for Ferrite
+//!
+//! Saves never write over the target in place. The new content goes to a
+//! temporary file in the same directory, is flushed to disk, and is then
+//! renamed over the original, so a crash or a full disk mid-save leaves the
+//! previous version intact. The original file's permissions are preserved.
+//!
+//! Before a file is overwritten, a [`BackupPolicy`] can keep a copy of the
+//! previous version: either a single `file.md~` next to it, or numbered
+//! generations under `.ferrite/backups/`.
+
+use crate::config::{BackupMode, Settings};
+use crate::workspaces::Workspace;
+use log::debug;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Folder (relative to the workspace root or the file's directory) that
+/// holds numbered backups
+const BACKUP_DIR: &str = ".ferrite/backups";
+
+/// Check if a file or folder name is Ferrite's own data rather than a
+/// document: a `.ferrite` folder (workspace settings, state and numbered
+/// backups) or a sibling backup ending in `~`.
+pub fn is_ferrite_data(name: &str, is_dir: bool) -> bool {
+    if is_dir {
+        name == ".ferrite"
+    } else {
+        name.ends_with('~')
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Atomic Write
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Write `bytes` to `path` via write-to-temp + fsync + rename.
+///
+/// If `path` is a symlink, the file it points to is replaced and the link
+/// is kept. The temporary file is removed if any step fails.
+pub fn atomic_write(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let target = resolve_symlink(path);
+    let file_name = target
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let dir = match target.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let mut temp_name = OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = dir.join(temp_name);
+
+    let permissions = fs::metadata(&target).ok().map(|m| m.permissions());
+
+    let result = (|| {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        drop(file);
+        if let Some(permissions) = permissions {
+            fs::set_permissions(&temp_path, permissions)?;
+        }
+        fs::rename(&temp_path, &target)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result?;
+
+    sync_dir(&dir);
+    Ok(())
+}
+
+/// Follow a symlink to its target so saving doesn't replace the link itself.
+fn resolve_symlink(path: &Path) -> PathBuf {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_symlink() => {
+            fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Flush the directory entry so the rename itself survives a crash.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) {
+    if let Err(e) = File::open(dir).and_then(|d| d.sync_all()) {
+        debug!("Could not sync directory {}: {}", dir.display(), e);
+    }
+}
+
+/// Directory syncing isn't available on this platform; the rename is enough.
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) {}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Backups
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Effective backup settings for a save, after workspace overrides.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupPolicy {
+    /// Where to keep backups
+    pub mode: BackupMode,
+    /// Number of generations kept in `BackupMode::Folder`
+    pub generations: u32,
+    /// Workspace root; folder backups of files inside it go to its
+    /// `.ferrite/backups/`, mirroring the relative path
+    pub workspace_root: Option<PathBuf>,
+}
+
+impl BackupPolicy {
+    /// Combine the global settings with the open workspace's overrides.
+    pub fn resolve(settings: &Settings, workspace: Option<&Workspace>) -> Self {
+        let overrides = workspace.map(|w| &w.settings);
+        Self {
+            mode: overrides
+                .and_then(|s| s.backup_mode)
+                .unwrap_or(settings.backup_mode),
+            generations: overrides
+                .and_then(|s| s.backup_generations)
+                .unwrap_or(settings.backup_generations)
+                .clamp(1, Settings::MAX_BACKUP_GENERATIONS),
+            workspace_root: workspace.map(|w| w.root_path.clone()),
+        }
+    }
+
+    /// Get the path of a backup of `path` (`generation` starts at 1, newest).
+    ///
+    /// Returns `None` when backups are disabled or `path` has no file name.
+    pub fn backup_path(&self, path: &Path, generation: u32) -> Option<PathBuf> {
+        let file_name = path.file_name()?;
+        match self.mode {
+            BackupMode::None => None,
+            BackupMode::Sibling => {
+                let mut name = file_name.to_os_string();
+                name.push("~");
+                Some(path.with_file_name(name))
+            }
+            BackupMode::Folder => {
+                let relative = self
+                    .workspace_root
+                    .as_deref()
+                    .and_then(|root| Some((root, path.strip_prefix(root).ok()?)));
+                let base = match relative {
+                    Some((root, relative)) => root.join(BACKUP_DIR).join(relative),
+                    None => path.parent()?.join(BACKUP_DIR).join(file_name),
+                };
+                let mut name = base.file_name()?.to_os_string();
+                name.push(format!(".{}", generation));
+                Some(base.with_file_name(name))
+            }
+        }
+    }
+
+    /// Copy the current version of `path` to its backup location.
+    ///
+    /// Older folder generations are shifted up by one and the oldest is
+    /// dropped. Does nothing if backups are disabled or the file doesn't
+    /// exist yet. Returns the path of the new backup.
+    pub fn backup(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let Some(newest) = self.backup_path(path, 1) else {
+            return Ok(None);
+        };
+
+        if self.mode == BackupMode::Folder {
+            if let Some(dir) = newest.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            for generation in (1..self.generations).rev() {
+                let (Some(from), Some(to)) = (
+                    self.backup_path(path, generation),
+                    self.backup_path(path, generation + 1),
+                ) else {
+                    continue;
+                };
+                if from.exists() {
+                    fs::rename(&from, &to)?;
+                }
+            }
+        }
+
+        fs::copy(path, &newest)?;
+        debug!("Backed up {} to {}", path.display(), newest.display());
+        Ok(Some(newest))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn policy(mode: BackupMode, generations: u32) -> BackupPolicy {
+        BackupPolicy {
+            mode,
+            generations,
+            workspace_root: None,
+        }
+    }
+
+    #[test]
+    fn test_atomic_write_replaces_content_without_leftovers() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("notes.md");
+        atomic_write(&path, b"first").unwrap();
+        atomic_write(&path, b"second").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        let entries: Vec<_> = fs::read_dir(temp_dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_atomic_write_preserves_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("script.md");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        atomic_write(&path, b"new").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_atomic_write_keeps_symlink() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("real.md");
+        let link = temp_dir.path().join("link.md");
+        fs::write(&target, "old").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        atomic_write(&link, b"new").unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read(&target).unwrap(), b"new");
+    }
+
+    #[test]
+    fn test_backup_disabled_or_missing_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("notes.md");
+        assert_eq!(policy(BackupMode::Sibling, 1).backup(&path).unwrap(), None);
+
+        fs::write(&path, "text").unwrap();
+        assert_eq!(policy(BackupMode::None, 1).backup(&path).unwrap(), None);
+    }
+
+    #[test]
+    fn test_sibling_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("notes.md");
+        fs::write(&path, "v1").unwrap();
+
+        let backup = policy(BackupMode::Sibling, 1).backup(&path).unwrap();
+        assert_eq!(backup, Some(temp_dir.path().join("notes.md~")));
+        assert_eq!(fs::read_to_string(backup.unwrap()).unwrap(), "v1");
+    }
+
+    #[test]
+    fn test_folder_backup_rotates_generations() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("notes.md");
+        let policy = policy(BackupMode::Folder, 2);
+
+        for version in ["v1", "v2", "v3"] {
+            fs::write(&path, version).unwrap();
+            policy.backup(&path).unwrap();
+        }
+
+        let dir = temp_dir.path().join(".ferrite").join("backups");
+        assert_eq!(fs::read_to_string(dir.join("notes.md.1")).unwrap(), "v3");
+        assert_eq!(fs::read_to_string(dir.join("notes.md.2")).unwrap(), "v2");
+        assert!(!dir.join("notes.md.3").exists());
+    }
+
+    #[test]
+    fn test_folder_backup_mirrors_workspace_path() {
+        let policy = BackupPolicy {
+            workspace_root: Some(PathBuf::from("/ws")),
+            ..policy(BackupMode::Folder, 3)
+        };
+        assert_eq!(
+            policy.backup_path(Path::new("/ws/docs/a.md"), 2),
+            Some(PathBuf::from("/ws/.ferrite/backups/docs/a.md.2"))
+        );
+        assert_eq!(
+            policy.backup_path(Path::new("/elsewhere/b.md"), 1),
+            Some(PathBuf::from("/elsewhere/.ferrite/backups/b.md.1"))
+        );
+    }
+
+    #[test]
+    fn test_resolve_workspace_overrides() {
+        let temp_dir = TempDir::new().unwrap();
+        let settings = Settings {
+            backup_mode: BackupMode::Sibling,
+            backup_generations: 3,
+            ..Settings::default()
+        };
+        assert_eq!(
+            BackupPolicy::resolve(&settings, None),
+            policy(BackupMode::Sibling, 3)
+        );
+
+        let mut workspace = Workspace::new(temp_dir.path().to_path_buf());
+        workspace.settings.backup_mode = Some(BackupMode::Folder);
+        let resolved = BackupPolicy::resolve(&settings, Some(&workspace));
+        assert_eq!(resolved.mode, BackupMode::Folder);
+        assert_eq!(resolved.generations, 3);
+        assert_eq!(resolved.workspace_root.as_deref(), Some(temp_dir.path()));
+    }
+}
@@ -0,0 +1,600 @@
+//! Single-instance hand-off for Ferrite
+//!
+//! The first Ferrite process listens on a local endpoint: a Unix domain
+//! socket in the config directory, or a named pipe on Windows. A later
+//! launch connects to it, forwards the files and folder from its command
+//! line as an [`OpenRequest`], and exits; the running instance opens them
+//! and raises its window. This keeps a single process writing the config
+//! file, so sessions and recent files aren't clobbered.
+//!
+//! The protocol is one JSON request per connection, terminated by a
+//! newline. The server answers `ok`; for `--wait` requests it answers
+//! `done` once the forwarded tabs have been closed, and the client blocks
+//! until then.
+
+use crate::cli::{FileTarget, LaunchOptions};
+use crate::config::get_config_dir;
+use eframe::egui;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+/// Reply sent once a request has been accepted.
+const REPLY_OK: &str = "ok";
+
+/// Reply sent to a `--wait` client once its tabs have been closed.
+const REPLY_DONE: &str = "done";
+
+/// How long the server waits for a connected client to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Pause after a failed accept so a persistent error doesn't spin.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Times a launch tries to connect or listen while another launch is
+/// becoming the primary instance.
+const CLAIM_ATTEMPTS: u32 = 20;
+
+/// Pause between attempts to claim the instance.
+const CLAIM_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Request
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// What a second launch asks the running instance to open.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenRequest {
+    /// Files to open, with absolute paths and optional positions
+    pub files: Vec<FileTarget>,
+    /// Folder to open as a workspace (absolute)
+    #[serde(with = "os_path::option")]
+    pub folder: Option<PathBuf>,
+    /// Document piped to the second launch's stdin
+    pub stdin_text: Option<String>,
+    /// Reply `done` once the opened tabs are closed
+    pub wait: bool,
+}
+
+impl OpenRequest {
+    /// Build a request from a parsed command line and its stdin document.
+    pub fn new(launch: &LaunchOptions, stdin_text: Option<String>) -> Self {
+        Self {
+            files: launch.files.clone(),
+            folder: launch.folder.clone(),
+            stdin_text,
+            wait: launch.wait,
+        }
+    }
+
+    /// Get the launch options to open this request with.
+    pub fn launch_options(&self) -> LaunchOptions {
+        LaunchOptions {
+            files: self.files.clone(),
+            folder: self.folder.clone(),
+            stdin: self.stdin_text.is_some(),
+            wait: self.wait,
+            new_window: false,
+        }
+    }
+}
+
+/// Serde adapter that sends paths as `OsString`s.
+///
+/// Paths serialize as strings by default, which fails for file names that
+/// aren't valid UTF-8; an `OsString` carries the platform's raw encoding.
+pub mod os_path {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::ffi::OsString;
+    use std::path::{Path, PathBuf};
+
+    pub fn serialize<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+        path.as_os_str().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+        OsString::deserialize(deserializer).map(PathBuf::from)
+    }
+
+    /// The same adapter for an optional path.
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            path: &Option<PathBuf>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            path.as_deref().map(Path::as_os_str).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<PathBuf>, D::Error> {
+            Option::<OsString>::deserialize(deserializer).map(|path| path.map(PathBuf::from))
+        }
+    }
+}
+
+/// A request received from another launch.
+pub struct Incoming {
+    /// What to open
+    pub request: OpenRequest,
+    /// Connection to notify when the tabs close (only for `--wait`)
+    pub waiter: Option<Waiter>,
+}
+
+/// An open connection from a `--wait` client.
+pub struct Waiter(platform::Stream);
+
+impl Waiter {
+    /// Tell the client its tabs were closed, letting it exit.
+    pub fn finish(mut self) {
+        if let Err(e) = send_line(&mut self.0, REPLY_DONE) {
+            debug!("Waiting client already gone: {}", e);
+        }
+    }
+}
+
+fn send_line(stream: &mut platform::Stream, line: &str) -> io::Result<()> {
+    stream.write_all(line.as_bytes())?;
+    stream.write_all(b"\n")?;
+    platform::flush(stream)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Claiming the Instance
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Outcome of looking for a running instance at startup.
+pub enum Instance {
+    /// The request was handed to a running instance; this process can exit
+    HandedOff,
+    /// No other instance is running; this one accepts later launches
+    Primary(Listener),
+    /// Run on our own without single-instance support
+    Standalone,
+}
+
+/// Hand `request` to a running instance, or become the primary instance.
+///
+/// A `--wait` launch blocks here until the running instance reports that
+/// its tabs were closed. A `--wait` launch that finds no running instance
+/// runs standalone, since it exits as soon as its own tabs close; it then
+/// runs like `--new-window`, without the session or crash recovery.
+pub fn hand_off_or_listen(request: &OpenRequest) -> Instance {
+    let endpoint = match get_config_dir() {
+        Ok(dir) => platform::endpoint(&dir),
+        Err(e) => {
+            warn!("Single-instance mode unavailable: {}", e);
+            return Instance::Standalone;
+        }
+    };
+
+    for _ in 0..CLAIM_ATTEMPTS {
+        match platform::connect(&endpoint) {
+            Ok(stream) => match send_request(stream, request) {
+                Ok(()) => return Instance::HandedOff,
+                // Open the files here rather than losing them
+                Err(e) => {
+                    warn!("Failed to hand off to running instance: {}", e);
+                    return Instance::Standalone;
+                }
+            },
+            Err(e) => debug!("No running instance at {}: {}", endpoint.display(), e),
+        }
+
+        if request.wait {
+            return Instance::Standalone;
+        }
+
+        match platform::Listener::bind(&endpoint) {
+            Ok(inner) => {
+                info!("Listening for other launches at {}", endpoint.display());
+                return Instance::Primary(Listener(inner));
+            }
+            // Another launch is starting up; connect to it once it listens
+            Err(e) if platform::is_claimed(&e) => {
+                debug!("Another instance is starting: {}", e);
+                std::thread::sleep(CLAIM_RETRY_DELAY);
+            }
+            Err(e) => {
+                warn!("Failed to listen at {}: {}", endpoint.display(), e);
+                return Instance::Standalone;
+            }
+        }
+    }
+
+    warn!(
+        "Timed out waiting for the instance at {}",
+        endpoint.display()
+    );
+    Instance::Standalone
+}
+
+/// Send a request and wait for the replies it needs.
+fn send_request(mut stream: platform::Stream, request: &OpenRequest) -> io::Result<()> {
+    let json =
+        serde_json::to_string(request).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    send_line(&mut stream, &json)?;
+
+    let mut reader = BufReader::new(stream);
+    let mut reply = String::new();
+    reader.read_line(&mut reply)?;
+    if reply.trim() != REPLY_OK {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("unexpected reply '{}'", reply.trim()),
+        ));
+    }
+
+    if request.wait {
+        info!("Waiting for the running instance to close the files");
+        // `done`, or EOF if the running instance exits first
+        reply.clear();
+        reader.read_line(&mut reply)?;
+    }
+    Ok(())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Listening
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// The primary instance's endpoint, before it starts accepting.
+pub struct Listener(platform::Listener);
+
+impl Listener {
+    /// Accept requests on a background thread.
+    ///
+    /// Each request is delivered through the returned channel and wakes the
+    /// UI with a repaint so it's handled promptly. Requests are read on a
+    /// thread per connection, so a client that stalls (pipe reads on
+    /// Windows can't time out) never holds up later launches.
+    pub fn spawn(self, ctx: egui::Context) -> Receiver<Incoming> {
+        let (sender, receiver) = mpsc::channel();
+        let mut listener = self.0;
+
+        let spawned = std::thread::Builder::new()
+            .name("ferrite-ipc".to_string())
+            .spawn(move || loop {
+                let stream = match listener.accept() {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        warn!("Failed to accept connection: {}", e);
+                        std::thread::sleep(ACCEPT_RETRY_DELAY);
+                        continue;
+                    }
+                };
+                let sender = sender.clone();
+                let ctx = ctx.clone();
+                let reader = std::thread::Builder::new()
+                    .name("ferrite-ipc-request".to_string())
+                    .spawn(move || match read_request(stream) {
+                        Ok(incoming) => {
+                            if sender.send(incoming).is_ok() {
+                                ctx.request_repaint();
+                            }
+                        }
+                        Err(e) => warn!("Ignoring malformed request: {}", e),
+                    });
+                if let Err(e) = reader {
+                    warn!("Failed to start IPC request thread: {}", e);
+                }
+            });
+        if let Err(e) = spawned {
+            warn!("Failed to start IPC thread: {}", e);
+        }
+        receiver
+    }
+}
+
+/// Read one request from a new connection and acknowledge it.
+fn read_request(stream: platform::Stream) -> io::Result<Incoming> {
+    platform::set_read_timeout(&stream, Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let request: OpenRequest =
+        serde_json::from_str(&line).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+    let mut stream = reader.into_inner();
+    send_line(&mut stream, REPLY_OK)?;
+    debug!("Received request to open {} file(s)", request.files.len());
+
+    let waiter = request.wait.then_some(Waiter(stream));
+    Ok(Incoming { request, waiter })
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Platform Endpoints
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(unix)]
+mod platform {
+    use fs2::FileExt;
+    use std::fs::{File, OpenOptions};
+    use std::io;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    /// Socket file name inside the config directory
+    const SOCKET_NAME: &str = "ferrite.sock";
+
+    /// Lock file held by the primary instance, next to the socket
+    const LOCK_NAME: &str = "ferrite.lock";
+
+    pub type Stream = UnixStream;
+
+    pub fn endpoint(config_dir: &Path) -> PathBuf {
+        config_dir.join(SOCKET_NAME)
+    }
+
+    pub fn connect(endpoint: &Path) -> io::Result<Stream> {
+        UnixStream::connect(endpoint)
+    }
+
+    pub fn flush(_stream: &mut Stream) -> io::Result<()> {
+        Ok(())
+    }
+
+    pub fn set_read_timeout(stream: &Stream, timeout: Option<Duration>) -> io::Result<()> {
+        stream.set_read_timeout(timeout)
+    }
+
+    /// Check if binding failed because another instance holds the lock.
+    pub fn is_claimed(error: &io::Error) -> bool {
+        error.kind() == io::ErrorKind::WouldBlock
+    }
+
+    pub struct Listener {
+        socket: UnixListener,
+        /// Exclusive lock on the lock file, released when the process exits
+        _lock: File,
+    }
+
+    impl Listener {
+        /// Take the instance lock and bind the socket, replacing one left
+        /// behind by a crashed instance.
+        ///
+        /// The socket file is only replaced while holding the lock, so two
+        /// launches starting together can't both become the primary. Fails
+        /// with `io::ErrorKind::WouldBlock` if another process holds it.
+        pub fn bind(endpoint: &Path) -> io::Result<Self> {
+            let dir = endpoint.parent().unwrap_or(Path::new("."));
+            std::fs::create_dir_all(dir)?;
+            let lock = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(dir.join(LOCK_NAME))?;
+            lock.try_lock_exclusive()?;
+
+            if endpoint.exists() {
+                std::fs::remove_file(endpoint)?;
+            }
+            Ok(Self {
+                socket: UnixListener::bind(endpoint)?,
+                _lock: lock,
+            })
+        }
+
+        pub fn accept(&mut self) -> io::Result<Stream> {
+            self.socket.accept().map(|(stream, _)| stream)
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::fs::{File, OpenOptions};
+    use std::io;
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::FromRawHandle;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+    use windows_sys::Win32::Foundation::{
+        CloseHandle, GetLastError, ERROR_PIPE_CONNECTED, HANDLE, INVALID_HANDLE_VALUE,
+    };
+    use windows_sys::Win32::Storage::FileSystem::{
+        FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX,
+    };
+    use windows_sys::Win32::System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+        PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+    };
+
+    /// Pipe buffer size in bytes
+    const BUFFER_SIZE: u32 = 64 * 1024;
+
+    pub type Stream = File;
+
+    /// Named pipes live in their own namespace; derive a per-user name from
+    /// the config directory. The hash (64-bit FNV-1a) must stay the same
+    /// across builds so different versions find each other.
+    pub fn endpoint(config_dir: &Path) -> PathBuf {
+        let hash = config_dir
+            .as_os_str()
+            .encode_wide()
+            .flat_map(u16::to_le_bytes)
+            .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+            });
+        PathBuf::from(format!(r"\\.\pipe\ferrite-{:016x}", hash))
+    }
+
+    pub fn connect(endpoint: &Path) -> io::Result<Stream> {
+        OpenOptions::new().read(true).write(true).open(endpoint)
+    }
+
+    /// Make sure the client has read everything before the handle closes.
+    pub fn flush(stream: &mut Stream) -> io::Result<()> {
+        stream.sync_all()
+    }
+
+    /// Pipe reads block until the client writes or disconnects; each
+    /// request is read on its own thread instead.
+    pub fn set_read_timeout(_stream: &Stream, _timeout: Option<Duration>) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Check if binding failed because another process owns the pipe name.
+    pub fn is_claimed(error: &io::Error) -> bool {
+        error.kind() == io::ErrorKind::PermissionDenied
+    }
+
+    pub struct Listener {
+        name: Vec<u16>,
+        /// Instance created by `bind`, waiting for the first client
+        pending: Option<HANDLE>,
+    }
+
+    // The pending handle is only used by the thread that owns the listener
+    unsafe impl Send for Listener {}
+
+    impl Listener {
+        /// Create the first pipe instance; this fails if another process
+        /// already owns the name.
+        pub fn bind(endpoint: &Path) -> io::Result<Self> {
+            let name: Vec<u16> = endpoint.as_os_str().encode_wide().chain([0]).collect();
+            let handle = create_instance(&name, true)?;
+            Ok(Self {
+                name,
+                pending: Some(handle),
+            })
+        }
+
+        pub fn accept(&mut self) -> io::Result<Stream> {
+            let handle = match self.pending.take() {
+                Some(handle) => handle,
+                None => create_instance(&self.name, false)?,
+            };
+            // SAFETY: `handle` is a valid pipe handle created above
+            let connected = unsafe { ConnectNamedPipe(handle, std::ptr::null_mut()) } != 0
+                || unsafe { GetLastError() } == ERROR_PIPE_CONNECTED;
+            if !connected {
+                let error = io::Error::last_os_error();
+                // SAFETY: the handle is not used after this
+                unsafe { CloseHandle(handle) };
+                return Err(error);
+            }
+            // SAFETY: the handle is owned by the returned File from here on
+            Ok(unsafe { File::from_raw_handle(handle as _) })
+        }
+    }
+
+    fn create_instance(name: &[u16], first: bool) -> io::Result<HANDLE> {
+        let mut open_mode = PIPE_ACCESS_DUPLEX;
+        if first {
+            open_mode |= FILE_FLAG_FIRST_PIPE_INSTANCE;
+        }
+        // SAFETY: `name` is NUL-terminated and outlives the call
+        let handle = unsafe {
+            CreateNamedPipeW(
+                name.as_ptr(),
+                open_mode,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                BUFFER_SIZE,
+                BUFFER_SIZE,
+                0,
+                std::ptr::null(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(handle)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn request(wait: bool) -> OpenRequest {
+        OpenRequest {
+            files: vec![FileTarget {
+                path: PathBuf::from("/notes/todo.md"),
+                line: Some(42),
+                column: Some(7),
+            }],
+            folder: Some(PathBuf::from("/notes")),
+            stdin_text: None,
+            wait,
+        }
+    }
+
+    #[test]
+    fn test_request_round_trips_launch_options() {
+        let launch = request(true).launch_options();
+        assert_eq!(launch.files[0].line, Some(42));
+        assert!(launch.wait);
+        assert!(!launch.stdin);
+        assert_eq!(OpenRequest::new(&launch, None), request(true));
+    }
+
+    #[test]
+    fn test_request_keeps_non_utf8_paths() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut request = request(false);
+        request.files[0].path = PathBuf::from(std::ffi::OsStr::from_bytes(b"/notes/caf\xe9.md"));
+        request.folder = Some(PathBuf::from(std::ffi::OsStr::from_bytes(b"/caf\xe9")));
+
+        let json = serde_json::to_string(&request).unwrap();
+        let decoded: OpenRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, request);
+    }
+
+    #[test]
+    fn test_hand_off_over_socket() {
+        let temp_dir = TempDir::new().unwrap();
+        let endpoint = platform::endpoint(temp_dir.path());
+        let mut listener = platform::Listener::bind(&endpoint).unwrap();
+
+        let server = std::thread::spawn(move || {
+            let incoming = read_request(listener.accept().unwrap()).unwrap();
+            assert_eq!(incoming.request, request(true));
+            incoming
+                .waiter
+                .expect("wait requests keep a waiter")
+                .finish();
+        });
+
+        let stream = platform::connect(&endpoint).unwrap();
+        send_request(stream, &request(true)).unwrap();
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_bind_is_exclusive() {
+        let temp_dir = TempDir::new().unwrap();
+        let endpoint = platform::endpoint(temp_dir.path());
+        let _first = platform::Listener::bind(&endpoint).unwrap();
+
+        // A second launch doesn't replace the live socket
+        let err = platform::Listener::bind(&endpoint).err().unwrap();
+        assert!(platform::is_claimed(&err));
+        assert!(platform::connect(&endpoint).is_ok());
+    }
+
+    #[test]
+    fn test_bind_replaces_stale_socket() {
+        let temp_dir = TempDir::new().unwrap();
+        let endpoint = platform::endpoint(temp_dir.path());
+        drop(platform::Listener::bind(&endpoint).unwrap());
+
+        assert!(platform::connect(&endpoint).is_err());
+        assert!(platform::Listener::bind(&endpoint).is_ok());
+    }
+}
@@ -6,12 +6,14 @@
 //! A fast, lightweight text editor for Markdown, JSON, and more. Built with Rust and egui.
 
 mod app;
+mod cli;
 mod config;
 mod editor;
 mod error;
 mod export;
 mod files;
 mod fonts;
+mod ipc;
 mod markdown;
 mod preview;
 mod state;
@@ -21,8 +23,9 @@ mod ui;
 mod workspaces;
 
 use app::FerriteApp;
+use cli::Command;
 use config::load_config;
-use log::info;
+use log::{info, warn};
 use ui::get_app_icon;
 
 // Note: Native window decorations are disabled for custom title bar styling.
@@ -31,10 +34,85 @@ use ui::get_app_icon;
 /// Application name constant.
 const APP_NAME: &str = "Ferrite";
 
+/// Connect stdout and stderr to the console of the shell that started us.
+///
+/// Release builds on Windows use the GUI subsystem and start without a
+/// console, so CLI commands would otherwise print nothing.
+#[cfg(windows)]
+fn attach_parent_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+    // Fails harmlessly when there is no parent console or one is already attached
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
+
 fn main() -> eframe::Result<()> {
     // Initialize logging
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
 
+    // Parse command-line arguments before starting the GUI
+    let command = cli::parse_args(std::env::args_os().skip(1));
+    if !matches!(command, Ok(Command::Open(_))) {
+        attach_parent_console();
+    }
+    let mut launch = match command {
+        Ok(Command::Open(launch)) => launch,
+        Ok(Command::Help(usage)) => {
+            println!("{}", usage);
+            return Ok(());
+        }
+        Ok(Command::Export(args)) => std::process::exit(cli::export::run(&args)),
+        Ok(Command::Fmt(args)) => std::process::exit(cli::fmt::run_fmt(&args)),
+        Ok(Command::Check(args)) => std::process::exit(cli::fmt::run_check(&args)),
+        Ok(Command::Version) => {
+            println!("{} {}", APP_NAME, env!("CARGO_PKG_VERSION"));
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    // Read a piped document for `-` now, before the event loop takes over
+    let stdin_text = if launch.stdin {
+        match cli::read_stdin() {
+            Ok(text) => Some(text),
+            Err(e) => {
+                warn!("Failed to read stdin: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // Open the files in an already running window instead of starting another
+    let listener = if launch.new_window {
+        None
+    } else {
+        match ipc::hand_off_or_listen(&ipc::OpenRequest::new(&launch, stdin_text.clone())) {
+            ipc::Instance::HandedOff => {
+                info!("Opened in the running instance");
+                return Ok(());
+            }
+            ipc::Instance::Primary(listener) => Some(listener),
+            ipc::Instance::Standalone => {
+                // A `--wait` window only edits its own files, so like an
+                // extra window it leaves the session and settings alone
+                if launch.wait {
+                    launch.new_window = true;
+                }
+                None
+            }
+        }
+    };
+
     info!("Starting {}", APP_NAME);
 
     // Load settings to get window configuration
@@ -90,7 +168,7 @@ fn main() -> eframe::Result<()> {
         Box::new(|cc| {
             // Configure egui visuals based on theme (basic setup)
             // Full theme support will be implemented in a later task
-            Ok(Box::new(FerriteApp::new(cc)))
+            Ok(Box::new(FerriteApp::new(cc, launch, stdin_text, listener)))
         }),
     )
 }
@@ -0,0 +1,746 @@
+//! Incrementally updated markdown AST for rendered mode
+//!
+//! Rendered mode used to parse the whole document on every frame. A
+//! `DocumentCache` keeps the parsed top-level blocks of one tab and, when the
+//! text changes, re-parses only the blocks around the edited lines. Every
+//! block carries a `BlockId` that survives edits, so the editor can scope
+//! widget IDs by block and keep focus and widget state while typing.
+//!
+//! Constructs whose meaning depends on text outside a block (code fences,
+//! setext underlines, HTML blocks, front matter, footnotes, link reference
+//! definitions) make the cache fall back to parsing the whole document; IDs
+//! are still carried over for blocks that didn't change.
+
+use crate::error::Result;
+use crate::markdown::parser::{
+    parse_markdown_with_options, MarkdownNode, MarkdownNodeType, MarkdownOptions,
+};
+use std::collections::HashMap;
+use std::ops::Range;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Stable identity of a top-level block across edits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockId(u64);
+
+/// A parsed top-level block.
+#[derive(Debug, Clone)]
+pub struct Block {
+    /// Identity that stays the same while the block is edited
+    pub id: BlockId,
+    /// The block's AST (line numbers are 1-indexed in the full document)
+    pub node: MarkdownNode,
+}
+
+/// Parsed markdown for one document, updated incrementally as it changes.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentCache {
+    /// Source text the blocks were parsed from
+    source: String,
+    /// Content version of the source (None before the first parse)
+    version: Option<u64>,
+    /// Incremented whenever the content is replaced from outside the
+    /// rendered editor (undo, redo, reload), so widgets re-read their text
+    generation: u64,
+    /// Top-level blocks in document order
+    blocks: Vec<Block>,
+    /// Next unused block ID
+    next_id: u64,
+    /// Number of source lines parsed by the last update (for tests)
+    parsed_lines: usize,
+    /// Rendered heights of blocks, measured by the editor
+    heights: HashMap<BlockId, f32>,
+    /// Layout parameters (width, font) the heights were measured with
+    layout_key: u64,
+    /// Parse `[[...]]` as wiki-links (only inside a workspace)
+    wiki_links: bool,
+}
+
+impl DocumentCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bring the cache up to date with `source`.
+    ///
+    /// `version` is the tab's content version: when it changes, the content
+    /// was replaced externally and every block gets a new ID. Either way,
+    /// only the blocks touched by the change are re-parsed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the markdown fails to parse.
+    pub fn update(&mut self, source: &str, version: u64) -> Result<()> {
+        if self.version == Some(version) && self.source == source {
+            return Ok(());
+        }
+
+        let replaced = self.version.is_some_and(|v| v != version);
+
+        let old_lines: Vec<&str> = self.source.split('\n').collect();
+        let new_lines: Vec<&str> = source.split('\n').collect();
+        let edit = LineEdit::between(&old_lines, &new_lines);
+        let blocks = self.affected_blocks(&edit, &old_lines, &new_lines);
+
+        let region = if blocks == (0..self.blocks.len()) {
+            None
+        } else {
+            self.reparse_region(&blocks, &edit, &new_lines)?
+        };
+        let (blocks, lines, nodes) = match region {
+            Some((lines, nodes)) => (blocks, lines, nodes),
+            None => (
+                0..self.blocks.len(),
+                0..new_lines.len(),
+                parse_blocks(source, 0, self.wiki_links)?,
+            ),
+        };
+        self.splice(blocks, nodes, edit.line_delta());
+        if replaced {
+            self.generation += 1;
+            self.renumber();
+        }
+        self.parsed_lines = lines.len();
+        self.source = source.to_string();
+        self.version = Some(version);
+        Ok(())
+    }
+
+    /// The parsed top-level blocks.
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    /// Counter that changes when the content was replaced externally.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The measured rendered height of a block, if it has been rendered
+    /// with the current layout.
+    pub fn block_height(&self, id: BlockId) -> Option<f32> {
+        self.heights.get(&id).copied()
+    }
+
+    /// Record the rendered height of a block.
+    pub fn set_block_height(&mut self, id: BlockId, height: f32) {
+        self.heights.insert(id, height);
+    }
+
+    /// Set the layout parameters heights are measured with, forgetting all
+    /// measured heights when they change.
+    pub fn set_layout_key(&mut self, key: u64) {
+        if self.layout_key != key {
+            self.layout_key = key;
+            self.heights.clear();
+        }
+    }
+
+    /// Enable or disable wiki-link parsing, re-parsing the whole document on
+    /// the next update when it changes.
+    pub fn set_wiki_links(&mut self, enabled: bool) {
+        if self.wiki_links != enabled {
+            self.wiki_links = enabled;
+            self.version = None;
+            self.source.clear();
+            self.blocks.clear();
+            self.heights.clear();
+            self.generation += 1;
+        }
+    }
+
+    /// Give every block a new ID, keeping measured heights.
+    fn renumber(&mut self) {
+        for block in &mut self.blocks {
+            self.next_id += 1;
+            let id = BlockId(self.next_id);
+            if let Some(height) = self.heights.remove(&block.id) {
+                self.heights.insert(id, height);
+            }
+            block.id = id;
+        }
+    }
+
+    /// Find the range of blocks to re-parse for an edit: the blocks
+    /// overlapping the changed lines plus one neighbor on each side, since
+    /// an edit can merge or split adjacent blocks. Returns every block if
+    /// the edit involves context-sensitive syntax.
+    fn affected_blocks(
+        &self,
+        edit: &LineEdit,
+        old_lines: &[&str],
+        new_lines: &[&str],
+    ) -> Range<usize> {
+        let all = 0..self.blocks.len();
+        let changed = old_lines[edit.old.clone()]
+            .iter()
+            .chain(&new_lines[edit.new.clone()]);
+        // Footnote references and definitions pair up across the document,
+        // and comrak drops definitions nothing refers to, so they can't be
+        // found from the blocks either
+        let has_footnotes = new_lines.iter().any(|l| l.contains("[^"));
+        // Comrak numbers the lines after front matter from the end of the
+        // front matter, so block lines can't be mapped back to the source
+        let front_matter = matches!(
+            self.blocks.first().map(|b| &b.node.node_type),
+            Some(MarkdownNodeType::FrontMatter(_))
+        );
+        let starts_front_matter = |lines: &[&str]| lines[0].trim_end() == "---";
+        // Definitions resolve links anywhere in the document, and comrak
+        // doesn't report where they are
+        let has_definitions = new_lines.iter().any(|l| is_reference_definition(l));
+        if has_footnotes
+            || has_definitions
+            || front_matter
+            || starts_front_matter(old_lines)
+            || starts_front_matter(new_lines)
+            || changed.into_iter().any(|l| is_context_sensitive(l))
+        {
+            return all;
+        }
+
+        // Changed lines as 1-indexed, inclusive source lines
+        let first = edit.old.start + 1;
+        let last = edit.old.end;
+        let start = self
+            .blocks
+            .iter()
+            .position(|b| b.node.end_line >= first)
+            .unwrap_or(self.blocks.len());
+        let end = self
+            .blocks
+            .iter()
+            .position(|b| b.node.start_line > last)
+            .unwrap_or(self.blocks.len());
+        let mut range = start.saturating_sub(1)..(end + 1).min(self.blocks.len());
+
+        // Setext headings report the line after their underline as their
+        // end, overlapping the next block; keep overlapping blocks together
+        let overlaps = |a: &Block, b: &Block| a.node.end_line >= b.node.start_line;
+        while range.start > 0 && overlaps(&self.blocks[range.start - 1], &self.blocks[range.start])
+        {
+            range.start -= 1;
+        }
+        while range.end < self.blocks.len()
+            && overlaps(&self.blocks[range.end - 1], &self.blocks[range.end])
+        {
+            range.end += 1;
+        }
+
+        range
+    }
+
+    /// Parse the lines covering `blocks` and the edit. Returns the parsed
+    /// 0-indexed line range of the new source and the new blocks, or `None`
+    /// if the edit reaches past the region and the whole document needs to
+    /// be parsed.
+    fn reparse_region(
+        &self,
+        blocks: &Range<usize>,
+        edit: &LineEdit,
+        new_lines: &[&str],
+    ) -> Result<Option<(Range<usize>, Vec<MarkdownNode>)>> {
+        let first = &self.blocks[blocks.start].node;
+        let start = (first.start_line - 1).min(edit.old.start);
+        let delta = edit.line_delta();
+
+        // A block's end line depends on what follows it (lists take in
+        // trailing blank lines, paragraphs lazily continue), so the region
+        // runs up to the next block and the parse includes that block's
+        // first line; the block itself is dropped afterwards
+        let (end, context) = match self.blocks.get(blocks.end) {
+            Some(next) => ((next.node.start_line as isize - 1 + delta) as usize, 1),
+            None => (new_lines.len(), 0),
+        };
+
+        // The leading blank line keeps a `---` at the start of the region
+        // from being read as front matter
+        let text = format!("\n{}", new_lines[start..end + context].join("\n"));
+        let offset = start as isize - 1;
+        let mut nodes = parse_blocks(&text, offset, self.wiki_links)?;
+        nodes.retain(|node| node.start_line <= end);
+
+        // The region's last block took in the next one (e.g. a list that
+        // now continues into the following list)
+        if nodes.last().is_some_and(|node| node.end_line > end) {
+            return Ok(None);
+        }
+
+        Ok(Some((start..end, nodes)))
+    }
+
+    /// Replace `range` of the blocks with `nodes`, keeping IDs of blocks
+    /// that are unchanged (matched from both ends) and reusing the rest in
+    /// order, then shift the blocks after the region by `delta` lines.
+    /// Changed and removed blocks lose their measured heights.
+    fn splice(&mut self, range: Range<usize>, nodes: Vec<MarkdownNode>, delta: isize) {
+        let old = &self.blocks[range.clone()];
+        let prefix = old
+            .iter()
+            .zip(&nodes)
+            .take_while(|(block, node)| same_content(&block.node, node))
+            .count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(nodes[prefix..].iter().rev())
+            .take_while(|(block, node)| same_content(&block.node, node))
+            .count();
+
+        let mut ids: Vec<BlockId> = Vec::with_capacity(nodes.len());
+        ids.extend(old[..prefix].iter().map(|b| b.id));
+        let middle = nodes.len() - prefix - suffix;
+        let changed = &old[prefix..old.len() - suffix];
+        for block in changed {
+            self.heights.remove(&block.id);
+        }
+        let mut reusable = changed.iter().map(|b| b.id);
+        for _ in 0..middle {
+            let id = reusable.next().unwrap_or_else(|| {
+                self.next_id += 1;
+                BlockId(self.next_id)
+            });
+            ids.push(id);
+        }
+        ids.extend(old[old.len() - suffix..].iter().map(|b| b.id));
+
+        let replacement: Vec<Block> = ids
+            .into_iter()
+            .zip(nodes)
+            .map(|(id, node)| Block { id, node })
+            .collect();
+        let after = range.start + replacement.len();
+        self.blocks.splice(range, replacement);
+        if delta != 0 {
+            for block in &mut self.blocks[after..] {
+                shift_lines(&mut block.node, delta);
+            }
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Helpers
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// The lines that differ between two versions of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LineEdit {
+    /// Changed 0-indexed lines in the old text
+    old: Range<usize>,
+    /// Changed 0-indexed lines in the new text
+    new: Range<usize>,
+}
+
+impl LineEdit {
+    /// Find the changed lines by trimming the common prefix and suffix.
+    fn between(old: &[&str], new: &[&str]) -> Self {
+        let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+        let max_suffix = old.len().min(new.len()) - prefix;
+        let suffix = old
+            .iter()
+            .rev()
+            .zip(new.iter().rev())
+            .take(max_suffix)
+            .take_while(|(a, b)| a == b)
+            .count();
+        Self {
+            old: prefix..old.len() - suffix,
+            new: prefix..new.len() - suffix,
+        }
+    }
+
+    /// Number of lines added (positive) or removed (negative).
+    fn line_delta(&self) -> isize {
+        self.new.len() as isize - self.old.len() as isize
+    }
+}
+
+/// Parse markdown and return its top-level blocks, with line numbers moved
+/// down by `offset`.
+fn parse_blocks(text: &str, offset: isize, wiki_links: bool) -> Result<Vec<MarkdownNode>> {
+    let options = MarkdownOptions {
+        wiki_links,
+        ..Default::default()
+    };
+    let mut blocks = parse_markdown_with_options(text, &options)?.root.children;
+    if offset != 0 {
+        for block in &mut blocks {
+            shift_lines(block, offset);
+        }
+    }
+    Ok(blocks)
+}
+
+/// Move a node and its descendants by `delta` lines.
+fn shift_lines(node: &mut MarkdownNode, delta: isize) {
+    node.start_line = (node.start_line as isize + delta) as usize;
+    node.end_line = (node.end_line as isize + delta) as usize;
+    for child in &mut node.children {
+        shift_lines(child, delta);
+    }
+}
+
+/// Compare two nodes ignoring their line numbers.
+fn same_content(a: &MarkdownNode, b: &MarkdownNode) -> bool {
+    a.node_type == b.node_type
+        && a.children.len() == b.children.len()
+        && a.children
+            .iter()
+            .zip(&b.children)
+            .all(|(a, b)| same_content(a, b))
+}
+
+/// Check if a line can change how lines outside its own block parse.
+fn is_context_sensitive(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with("```")
+        || trimmed.starts_with("~~~")
+        || trimmed.starts_with('<')
+        || trimmed.contains("[^")
+        || is_reference_definition(line)
+        || (!trimmed.is_empty() && trimmed.chars().all(|c| c == '=' || c == '-'))
+}
+
+/// Check if a line is a link reference definition (`[label]: url`).
+fn is_reference_definition(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    line.len() - trimmed.len() <= 3
+        && trimmed.starts_with('[')
+        && !trimmed.starts_with("[^")
+        && trimmed.contains("]:")
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::parser::parse_markdown;
+
+    const DOC: &str = "# Title
+
+First paragraph
+continues here.
+
+- one
+- two
+- three
+
+> quote
+
+Second [link][ref] paragraph.
+
+```rust
+fn main() {}
+```
+
+Last paragraph.
+
+[ref]: https://example.com
+";
+
+    fn full_parse(source: &str) -> Vec<MarkdownNode> {
+        parse_markdown(source).unwrap().root.children
+    }
+
+    fn assert_matches_full_parse(cache: &DocumentCache, source: &str) {
+        let expected = full_parse(source);
+        let actual: Vec<&MarkdownNode> = cache.blocks().iter().map(|b| &b.node).collect();
+        assert_eq!(actual.len(), expected.len(), "block count for {:?}", source);
+        for (actual, expected) in actual.iter().zip(&expected) {
+            assert!(
+                same_content(actual, expected),
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+            assert_eq!(
+                (actual.start_line, actual.end_line),
+                (expected.start_line, expected.end_line),
+                "{:?} in {:?}",
+                actual.node_type,
+                source
+            );
+        }
+    }
+
+    fn ids(cache: &DocumentCache) -> Vec<BlockId> {
+        cache.blocks().iter().map(|b| b.id).collect()
+    }
+
+    #[test]
+    fn test_line_edit() {
+        let edit = LineEdit::between(&["a", "b", "c"], &["a", "x", "y", "c"]);
+        assert_eq!(edit.old, 1..2);
+        assert_eq!(edit.new, 1..3);
+        assert_eq!(edit.line_delta(), 1);
+
+        let edit = LineEdit::between(&["a", "a"], &["a", "a", "a"]);
+        assert_eq!(edit.old, 2..2);
+        assert_eq!(edit.new, 2..3);
+    }
+
+    #[test]
+    fn test_edits_match_full_parse() {
+        let edits = [
+            ("continues here.", "continues here, and more."),
+            ("- two\n", "- two\n- two and a half\n"),
+            ("> quote\n", "> quote\n> more\n"),
+            ("\n\n> quote", "\n> quote"),
+            ("First paragraph\n", ""),
+            ("Last paragraph.", "Last paragraph.\n\nNew paragraph"),
+            (
+                "Second [link][ref]",
+                "Second [link][ref] and [another][ref]",
+            ),
+            ("fn main() {}", "fn main() {\n    println!(\"hi\");\n}"),
+            ("```rust\n", ""),
+            ("# Title", "Title\n====="),
+            ("- three\n", "- three\n\n      indented\n"),
+        ];
+        for (from, to) in edits {
+            let mut cache = DocumentCache::new();
+            cache.update(DOC, 0).unwrap();
+            assert_matches_full_parse(&cache, DOC);
+
+            let edited = DOC.replacen(from, to, 1);
+            assert_ne!(edited, DOC);
+            cache.update(&edited, 0).unwrap();
+            assert_matches_full_parse(&cache, &edited);
+        }
+    }
+
+    #[test]
+    fn test_only_touched_blocks_are_reparsed() {
+        let doc = DOC.replace("\n[ref]: https://example.com\n", "");
+        let mut cache = DocumentCache::new();
+        cache.update(&doc, 0).unwrap();
+        let total = doc.split('\n').count();
+        assert_eq!(cache.parsed_lines, total);
+
+        let edited = doc.replace("Second [link]", "Second edited [link]");
+        cache.update(&edited, 0).unwrap();
+        assert!(cache.parsed_lines < total / 2, "{}", cache.parsed_lines);
+
+        // Reference definitions can resolve links anywhere
+        let mut cache = DocumentCache::new();
+        cache.update(DOC, 0).unwrap();
+        let edited = DOC.replace("Second [link]", "Second edited [link]");
+        cache.update(&edited, 0).unwrap();
+        assert_eq!(cache.parsed_lines, edited.split('\n').count());
+    }
+
+    #[test]
+    fn test_block_ids_are_stable() {
+        let mut cache = DocumentCache::new();
+        cache.update(DOC, 0).unwrap();
+        let before = ids(&cache);
+        let generation = cache.generation();
+
+        // Editing a block keeps every ID, including the edited block's
+        let edited = DOC.replace("First paragraph", "First edited paragraph");
+        cache.update(&edited, 0).unwrap();
+        assert_eq!(ids(&cache), before);
+
+        // Inserting a block keeps the IDs around it
+        let inserted = edited.replace("> quote\n", "> quote\n\nNew block\n");
+        cache.update(&inserted, 0).unwrap();
+        let after = ids(&cache);
+        assert_eq!(after.len(), before.len() + 1);
+        assert_eq!(&after[..4], &before[..4]);
+        assert_eq!(&after[5..], &before[4..]);
+        assert!(!before.contains(&after[4]));
+        assert_eq!(cache.generation(), generation);
+    }
+
+    #[test]
+    fn test_version_change_replaces_ids() {
+        let mut cache = DocumentCache::new();
+        cache.update(DOC, 0).unwrap();
+        let before = ids(&cache);
+        let generation = cache.generation();
+
+        cache.update(DOC, 1).unwrap();
+        assert_matches_full_parse(&cache, DOC);
+        assert!(ids(&cache).iter().all(|id| !before.contains(id)));
+        assert_ne!(cache.generation(), generation);
+    }
+
+    #[test]
+    fn test_block_heights() {
+        let mut cache = DocumentCache::new();
+        cache.update(DOC, 0).unwrap();
+        for (i, id) in ids(&cache).into_iter().enumerate() {
+            cache.set_block_height(id, i as f32);
+        }
+
+        // Editing a block forgets only its height
+        let edited = DOC.replace("> quote", "> quote edited");
+        cache.update(&edited, 0).unwrap();
+        let heights: Vec<Option<f32>> = ids(&cache)
+            .into_iter()
+            .map(|id| cache.block_height(id))
+            .collect();
+        assert_eq!(heights[3], None);
+        assert_eq!(heights[4], Some(4.0));
+
+        // Heights follow blocks to their new IDs
+        cache.update(&edited, 1).unwrap();
+        assert_eq!(cache.block_height(cache.blocks()[4].id), Some(4.0));
+
+        cache.set_layout_key(7);
+        assert_eq!(cache.block_height(cache.blocks()[4].id), None);
+    }
+
+    #[test]
+    fn test_wiki_links_toggle_reparses() {
+        let source = "See [[Note]].\n";
+        let is_link = |cache: &DocumentCache| {
+            matches!(
+                cache.blocks()[0].node.children[1].node_type,
+                MarkdownNodeType::WikiLink(_)
+            )
+        };
+
+        let mut cache = DocumentCache::new();
+        cache.update(source, 0).unwrap();
+        assert_eq!(cache.blocks()[0].node.children.len(), 1);
+
+        let generation = cache.generation();
+        cache.set_wiki_links(true);
+        cache.update(source, 0).unwrap();
+        assert!(is_link(&cache));
+        assert_ne!(cache.generation(), generation);
+
+        cache.set_wiki_links(false);
+        cache.update(source, 0).unwrap();
+        assert_matches_full_parse(&cache, source);
+    }
+
+    #[test]
+    fn test_thematic_break_region_is_not_front_matter() {
+        let source = "Intro\n\n---\n\ntext\n\n---\n\nmore\n";
+        let mut cache = DocumentCache::new();
+        cache.update(source, 0).unwrap();
+        let edited = source.replace("more", "more text");
+        cache.update(&edited, 0).unwrap();
+        assert_matches_full_parse(&cache, &edited);
+    }
+
+    #[test]
+    fn test_unreferenced_footnote_edits_match_full_parse() {
+        // Comrak drops the unreferenced definitions, and the indented line
+        // inserted after the first continues it rather than starting a code
+        // block
+        let source = "[^1]: f\n\n[^2]: g\n# Heading\n\ntext\n\n# Next\n\nmore text\n";
+        let mut cache = DocumentCache::new();
+        cache.update(source, 0).unwrap();
+        assert_matches_full_parse(&cache, source);
+        for edited in [
+            source.replace("\n[^2]", "    code\n[^2]"),
+            source.replace("more text", "see [^1]"),
+        ] {
+            cache.update(&edited, 0).unwrap();
+            assert_matches_full_parse(&cache, &edited);
+        }
+    }
+
+    #[test]
+    fn test_random_footnote_edits_match_full_parse() {
+        let pool = [
+            "",
+            "",
+            "text",
+            "[^1]: f",
+            "[^2]: second note",
+            "    indented note",
+            "see [^1]",
+            "and [^2] too",
+            "$$",
+            "x^2",
+            "    code",
+            "- item",
+            "> quoted",
+            "# Heading",
+        ];
+        for start in 0..8u64 {
+            let mut seed = start;
+            let mut next = |n: usize| {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                ((seed >> 33) as usize) % n
+            };
+
+            let mut lines: Vec<&str> = vec!["text"];
+            let mut cache = DocumentCache::new();
+            cache.update(&lines.join("\n"), 0).unwrap();
+            for _ in 0..150 {
+                let at = next(lines.len() + 1);
+                match next(3) {
+                    0 if at < lines.len() => lines[at] = pool[next(pool.len())],
+                    1 if at < lines.len() && lines.len() > 1 => {
+                        lines.remove(at);
+                    }
+                    _ => lines.insert(at.min(lines.len()), pool[next(pool.len())]),
+                }
+                let source = lines.join("\n");
+                cache.update(&source, 0).unwrap();
+                assert_matches_full_parse(&cache, &source);
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_line_edits_match_full_parse() {
+        let pool = [
+            "",
+            "",
+            "text",
+            "more text with **bold**",
+            "- item",
+            "1. first",
+            "  - nested",
+            "> quoted",
+            "# Heading",
+            "    code",
+            "| a | b |",
+            "|---|---|",
+            "[label]: /url",
+            "see [label]",
+            "```",
+            "---",
+        ];
+        let mut seed: u64 = 42;
+        let mut next = |n: usize| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) as usize) % n
+        };
+
+        let mut lines: Vec<&str> = DOC.split('\n').collect();
+        let mut cache = DocumentCache::new();
+        cache.update(DOC, 0).unwrap();
+        for _ in 0..400 {
+            let at = next(lines.len() + 1);
+            match next(3) {
+                0 if at < lines.len() => lines[at] = pool[next(pool.len())],
+                1 if at < lines.len() && lines.len() > 1 => {
+                    lines.remove(at);
+                }
+                _ => lines.insert(at.min(lines.len()), pool[next(pool.len())]),
+            }
+            let source = lines.join("\n");
+            cache.update(&source, 0).unwrap();
+            assert_matches_full_parse(&cache, &source);
+        }
+    }
+}
@@ -51,6 +51,9 @@ use crate::markdown::ast_ops::{
     exit_list_to_paragraph, heading_enter, indent_list_item, merge_with_previous_list_item,
     outdent_list_item, split_list_item, split_paragraph, EditContext, EditNodeType, StructuralEdit,
 };
+use crate::markdown::cache::{Block, BlockId, DocumentCache};
+use crate::markdown::images::{self, InlineImage};
+use crate::markdown::math::MathView;
 use crate::markdown::parser::{
     parse_markdown, HeadingLevel, ListType, MarkdownNode, MarkdownNodeType,
 };
@@ -58,10 +61,18 @@ use crate::markdown::widgets::{
     CodeBlockData, EditableCodeBlock, EditableTable, RenderedLinkState, RenderedLinkWidget,
     TableData, WidgetColors,
 };
+use crate::markdown::wikilinks::WikiLink;
+use crate::preview::{BlockMapping, BlockType};
+use eframe::egui::text::{CCursor, CCursorRange};
+use eframe::egui::text_edit::TextEditState;
 use eframe::egui::{
     self, Color32, FontId, Key, Response, RichText, ScrollArea, TextEdit, Ui, Vec2,
 };
 use log::debug;
+use std::path::{Path, PathBuf};
+
+/// Frames a requested selection waits for the element that shows it
+const SELECTION_FRAMES: u32 = 5;
 
 // â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€
 // Editor Mode
@@ -95,6 +106,11 @@ pub struct MarkdownEditorOutput {
     pub focused_element: Option<FocusedElement>,
     /// Current scroll offset (for sync scrolling)
     pub scroll_offset: f32,
+    /// Source lines and content offsets of the top-level blocks in rendered
+    /// mode (for sync scrolling; empty in raw mode)
+    pub block_mappings: Vec<BlockMapping>,
+    /// Wiki-link the user clicked in rendered mode, for the app to open
+    pub wiki_link_clicked: Option<WikiLink>,
 }
 
 /// Information about the currently focused element in rendered mode.
@@ -137,6 +153,8 @@ pub struct EditorColors {
     pub list_marker: Color32,
     /// Task list checkbox color
     pub checkbox: Color32,
+    /// Background of a block highlighted by search navigation
+    pub match_highlight: Color32,
 }
 
 impl EditorColors {
@@ -169,6 +187,7 @@ impl EditorColors {
             hr: Color32::from_rgb(80, 80, 80),
             list_marker: Color32::from_rgb(150, 150, 150),
             checkbox: Color32::from_rgb(100, 180, 255),
+            match_highlight: Color32::from_rgba_unmultiplied(255, 200, 0, 40),
         }
     }
 
@@ -186,6 +205,7 @@ impl EditorColors {
             hr: Color32::from_rgb(200, 200, 200),
             list_marker: Color32::from_rgb(100, 100, 100),
             checkbox: Color32::from_rgb(0, 100, 180),
+            match_highlight: Color32::from_rgba_unmultiplied(255, 220, 0, 70),
         }
     }
 }
@@ -221,6 +241,12 @@ struct EditState {
     focused_node: Option<usize>,
     /// Selection within the focused node (start, end) - relative to node text
     focused_selection: Option<(usize, usize)>,
+    /// Directory relative image paths are resolved against
+    image_base_dir: Option<PathBuf>,
+    /// Wiki-link clicked this frame
+    clicked_wiki_link: Option<WikiLink>,
+    /// Source character range to select in the widget showing it
+    pending_selection: Option<(usize, usize)>,
 }
 
 impl EditState {
@@ -262,6 +288,31 @@ impl EditState {
         self.focused_selection = selection;
     }
 
+    /// Check if the pending selection starts in source lines
+    /// `start_line..=end_line` (1-indexed).
+    fn selection_pending_in(&self, source: &str, start_line: usize, end_line: usize) -> bool {
+        self.pending_selection.is_some_and(|(start, _)| {
+            let first = line_to_char_index(source, start_line);
+            let last = line_to_char_index(source, end_line + 1);
+            (first..last).contains(&start)
+        })
+    }
+
+    /// Take the pending selection if it starts in source lines
+    /// `start_line..=end_line` (1-indexed).
+    fn take_selection_in(
+        &mut self,
+        source: &str,
+        start_line: usize,
+        end_line: usize,
+    ) -> Option<(usize, usize)> {
+        if self.selection_pending_in(source, start_line, end_line) {
+            self.pending_selection.take()
+        } else {
+            None
+        }
+    }
+
     /// Get focused element info for the output
     fn get_focused_element(&self, source: &str) -> Option<FocusedElement> {
         let node_id = self.focused_node?;
@@ -279,6 +330,66 @@ impl EditState {
     }
 }
 
+/// Map a source character range into the text of a widget showing the
+/// source from `node_start` on.
+///
+/// Widgets leave out markers such as `# ` or `- `, so the same occurrence of
+/// the selected text is looked up in the widget's text instead of using the
+/// offset from the start of the node.
+fn selection_in_widget(
+    source: &str,
+    node_start: usize,
+    (start, end): (usize, usize),
+    text: &str,
+) -> (usize, usize) {
+    let byte_at = |index: usize| {
+        source
+            .char_indices()
+            .nth(index)
+            .map_or(source.len(), |(i, _)| i)
+    };
+    let (node_byte, start_byte, end_byte) = (byte_at(node_start), byte_at(start), byte_at(end));
+    let needle = &source[start_byte..end_byte];
+    let fallback = start.saturating_sub(node_start).min(text.chars().count());
+    if needle.is_empty() {
+        return (fallback, fallback);
+    }
+
+    let nth = source[node_byte..start_byte].matches(needle).count();
+    let found = text
+        .match_indices(needle)
+        .nth(nth)
+        .or_else(|| text.match_indices(needle).last());
+    match found {
+        Some((i, _)) => {
+            let start = text[..i].chars().count();
+            (start, start + needle.chars().count())
+        }
+        None => (fallback, fallback),
+    }
+}
+
+/// Select a source character range in the text edit that shows the source
+/// from `start_line` (1-indexed) on as `text`, and give it focus.
+fn select_in_text_edit(
+    ui: &Ui,
+    response: &Response,
+    source: &str,
+    start_line: usize,
+    range: (usize, usize),
+    text: &str,
+) {
+    let node_start = line_to_char_index(source, start_line);
+    let (start, end) = selection_in_widget(source, node_start, range, text);
+    let mut state = TextEditState::load(ui.ctx(), response.id).unwrap_or_default();
+    state.cursor.set_char_range(Some(CCursorRange::two(
+        CCursor::new(start),
+        CCursor::new(end),
+    )));
+    state.store(ui.ctx(), response.id);
+    response.request_focus();
+}
+
 // â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€
 // Structural Edit State
 // â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€
@@ -403,6 +514,18 @@ pub struct MarkdownEditor<'a> {
     id: Option<egui::Id>,
     /// Line number to scroll to (1-indexed, from outline navigation)
     scroll_to_line: Option<usize>,
+    /// Scroll offset to apply (from sync scrolling)
+    scroll_offset: Option<f32>,
+    /// Source line whose block is highlighted (1-indexed, from search results)
+    highlight_line: Option<usize>,
+    /// Source character range to select (from search results)
+    select_range: Option<(usize, usize)>,
+    /// Parsed document kept between frames, with the tab's content version
+    document: Option<(&'a mut DocumentCache, u64)>,
+    /// Whether rendered mode is a preview that ignores edits
+    read_only: bool,
+    /// Directory of the document, for resolving relative image paths
+    base_dir: Option<&'a Path>,
 }
 
 impl<'a> MarkdownEditor<'a> {
@@ -417,6 +540,12 @@ impl<'a> MarkdownEditor<'a> {
             theme: Theme::Light,
             id: None,
             scroll_to_line: None,
+            scroll_offset: None,
+            highlight_line: None,
+            select_range: None,
+            document: None,
+            read_only: false,
+            base_dir: None,
         }
     }
 
@@ -469,6 +598,61 @@ impl<'a> MarkdownEditor<'a> {
         self
     }
 
+    /// Set a scroll offset to apply (for sync scrolling). Line navigation
+    /// takes precedence.
+    #[must_use]
+    pub fn scroll_offset(mut self, offset: Option<f32>) -> Self {
+        self.scroll_offset = offset;
+        self
+    }
+
+    /// Highlight the block holding a source line (1-indexed), e.g. the line
+    /// of a search result.
+    #[must_use]
+    pub fn highlight_line(mut self, line: Option<usize>) -> Self {
+        self.highlight_line = line;
+        self
+    }
+
+    /// Select a source character range in the text edit of the element that
+    /// covers it, e.g. a search result. Elements without a text edit (code
+    /// blocks, tables) are only highlighted with [`Self::highlight_line`].
+    #[must_use]
+    pub fn select_range(mut self, range: Option<(usize, usize)>) -> Self {
+        self.select_range = range;
+        self
+    }
+
+    /// Show rendered mode as a read-only preview: widgets can't take
+    /// keyboard focus and any edit is discarded.
+    #[must_use]
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Reuse a parsed document between frames.
+    ///
+    /// `content_version` is the tab's content version; the cache re-parses
+    /// only the blocks touched by edits and keeps block identities stable so
+    /// widgets keep focus while typing. Without a cache, rendered mode parses
+    /// the whole document every frame.
+    #[must_use]
+    pub fn document_cache(mut self, cache: &'a mut DocumentCache, content_version: u64) -> Self {
+        self.document = Some((cache, content_version));
+        self
+    }
+
+    /// Set the directory relative image paths are resolved against.
+    ///
+    /// This is normally the directory of the open file; without it, rendered
+    /// mode only shows images with absolute paths.
+    #[must_use]
+    pub fn base_dir(mut self, dir: Option<&'a Path>) -> Self {
+        self.base_dir = dir;
+        self
+    }
+
     /// Apply settings to the editor widget.
     #[must_use]
     pub fn with_settings(mut self, settings: &Settings) -> Self {
@@ -554,45 +738,60 @@ impl<'a> MarkdownEditor<'a> {
             mode: EditorMode::Raw,
             focused_element: None, // Raw mode doesn't use element tracking
             scroll_offset: scroll_output.state.offset.y,
+            block_mappings: Vec::new(),
+            wiki_link_clicked: None,
         }
     }
 
     /// Show the WYSIWYG rendered editor.
     fn show_rendered_editor(
-        self,
+        mut self,
         ui: &mut Ui,
         id: egui::Id,
         colors: &EditorColors,
     ) -> MarkdownEditorOutput {
         let original_content = self.content.clone();
         let mut edit_state = EditState::new();
+        edit_state.image_base_dir = self.base_dir.map(Path::to_path_buf);
+
+        // A selection waits a few frames for its element to be laid out (and
+        // for a formatted paragraph to switch to editing)
+        let selection_id = id.with("pending_selection");
+        if let Some(range) = self.select_range.filter(|_| !self.read_only) {
+            ui.data_mut(|d| d.insert_temp(selection_id, (range, SELECTION_FRAMES)));
+        }
+        let pending_selection: Option<((usize, usize), u32)> =
+            ui.data(|d| d.get_temp(selection_id));
+        edit_state.pending_selection = pending_selection.map(|(range, _)| range);
         let mut structural_state = StructuralEditState::new();
 
-        // Parse the markdown content
-        let doc = match parse_markdown(self.content) {
-            Ok(doc) => doc,
-            Err(e) => {
-                // On parse error, show error and fall back to raw editing
-                ui.colored_label(Color32::RED, format!("Parse error: {}", e));
-                return self.show_raw_editor(ui, id);
-            }
-        };
+        // Parse the markdown content, or bring the cached document up to date
+        let mut frame_document = DocumentCache::new();
+        let cached = self.document.is_some();
+        let (document, content_version) = self.document.take().unwrap_or((&mut frame_document, 0));
+        if let Err(e) = document.update(self.content, content_version) {
+            // On parse error, show error and fall back to raw editing
+            ui.colored_label(Color32::RED, format!("Parse error: {}", e));
+            return self.show_raw_editor(ui, id);
+        }
+
+        let font_id = FontId::new(
+            self.font_size,
+            fonts::get_styled_font_family(false, false, self.font_family),
+        );
+        let row_height = ui.fonts(|f| f.row_height(&font_id));
 
         // Calculate scroll offset for outline navigation if needed
-        let target_scroll_offset: Option<f32> = if let Some(target_line) = self.scroll_to_line {
-            let font_id = FontId::new(
-                self.font_size,
-                fonts::get_styled_font_family(false, false, self.font_family),
-            );
-            let line_height = ui.fonts(|f| f.row_height(&font_id));
-            // Target scroll position: put the line roughly 1/3 from top of viewport
-            let viewport_height = ui.available_height();
-            // target_line is 1-indexed, convert to 0-indexed for calculation
-            let target_y = (target_line.saturating_sub(1)) as f32 * line_height;
-            Some((target_y - viewport_height / 3.0).max(0.0))
-        } else {
-            None
-        };
+        let target_scroll_offset: Option<f32> = self
+            .scroll_to_line
+            .map(|target_line| {
+                let layout = BlockLayout::new(document, row_height);
+                let target_y = layout.line_offset(document.blocks(), target_line);
+                // Target scroll position: put the line roughly 1/3 from top of viewport
+                let viewport_height = ui.available_height();
+                (target_y - viewport_height / 3.0).max(0.0)
+            })
+            .or(self.scroll_offset);
 
         // Render the document in a scroll area
         let mut scroll_area = ScrollArea::vertical()
@@ -604,11 +803,15 @@ impl<'a> MarkdownEditor<'a> {
             scroll_area = scroll_area.vertical_scroll_offset(offset);
         }
 
-        // Compute content hash to use as a unique ID scope.
-        // This ensures that when content changes (e.g., edited in raw mode),
-        // all inner TextEdit widgets get new IDs and re-read their content
-        // instead of using cached internal state.
-        let content_hash = {
+        // Compute an ID scope that changes when content is replaced from
+        // outside (undo, raw mode edits, reload), so all inner TextEdit
+        // widgets get new IDs and re-read their content instead of using
+        // cached internal state. A cached document only changes it for
+        // external changes, so widgets keep focus while typing here;
+        // without one, every content change starts a new scope.
+        let content_scope = if cached {
+            document.generation()
+        } else {
             use std::collections::hash_map::DefaultHasher;
             use std::hash::{Hash, Hasher};
             let mut hasher = DefaultHasher::new();
@@ -616,37 +819,163 @@ impl<'a> MarkdownEditor<'a> {
             hasher.finish()
         };
 
-        let scroll_output = scroll_area.show(ui, |ui| {
-            // Push the content hash as an ID scope so all inner widgets
-            // get unique IDs when content changes
-            ui.push_id(content_hash, |ui| {
-                // Minimal spacing - let individual elements control their margins
-                ui.spacing_mut().item_spacing = Vec2::new(4.0, 1.0);
-
-                // Render all children of the document root
-                // Note: Using original render_node (not the structural_keys version) since
-                // structural key handling is currently disabled due to compatibility issues
-                for node in &doc.root.children {
-                    render_node(
-                        ui,
-                        node,
-                        self.content,
-                        &mut edit_state,
-                        colors,
-                        self.font_size,
-                        self.font_family,
-                        0,
-                    );
-                }
+        // The block holding keyboard focus is always rendered, since egui
+        // drops focus from widgets that aren't shown
+        let focused_block_id = id.with("focused_block");
+        let focused_block: Option<BlockId> = ui.data(|d| d.get_temp(focused_block_id));
+
+        let scroll_output = scroll_area.show_viewport(ui, |ui, viewport| {
+            // Measured heights are only valid for the same width and font
+            let layout_key = {
+                use std::collections::hash_map::DefaultHasher;
+                use std::hash::{Hash, Hasher};
+                let mut hasher = DefaultHasher::new();
+                ui.available_width().to_bits().hash(&mut hasher);
+                self.font_size.to_bits().hash(&mut hasher);
+                self.font_family.hash(&mut hasher);
+                hasher.finish()
+            };
+            document.set_layout_key(layout_key);
+            let layout = BlockLayout::new(document, row_height);
+
+            // Only blocks within a screen of the viewport are laid out; the
+            // others are replaced by empty space of their measured height
+            let margin = viewport.height();
+            let render_range = (viewport.min.y - margin)..(viewport.max.y + margin);
+            let origin = ui.max_rect().top();
+            let mut rendered: Vec<(usize, f32, f32)> = Vec::new();
+
+            // Push the content scope so all inner widgets get unique IDs
+            // when content changes externally
+            let response = ui
+                .push_id(content_scope, |ui| {
+                    // Minimal spacing - let individual elements control their margins
+                    ui.spacing_mut().item_spacing = Vec2::new(4.0, 1.0);
+
+                    // Render the visible children of the document root
+                    // Note: Using original render_node (not the structural_keys version) since
+                    // structural key handling is currently disabled due to compatibility issues
+                    // Each top-level block gets its own stable ID scope
+                    let mut skipped = 0.0;
+                    for (index, block) in document.blocks().iter().enumerate() {
+                        let (top, height) = layout.span(index);
+                        let visible = top < render_range.end && top + height > render_range.start;
+                        if !visible && focused_block != Some(block.id) {
+                            skipped += height;
+                            continue;
+                        }
+                        if skipped > 0.0 {
+                            ui.add_space(skipped);
+                            skipped = 0.0;
+                        }
 
-                // Keep structural_state alive to avoid unused variable warning
-                let _ = &structural_state;
+                        // Reserve a shape behind the block for the highlight
+                        let highlighted = self.highlight_line.is_some_and(|line| {
+                            (block.node.start_line..=block.node.end_line).contains(&line)
+                        });
+                        let highlight = highlighted.then(|| ui.painter().add(egui::Shape::Noop));
 
-                // Return a response from the scroll area content
-                ui.allocate_response(Vec2::ZERO, egui::Sense::hover())
-            })
-            .inner
+                        let block_top = ui.cursor().top();
+                        ui.push_id(block.id, |ui| {
+                            render_node(
+                                ui,
+                                &block.node,
+                                self.content,
+                                &mut edit_state,
+                                colors,
+                                self.font_size,
+                                self.font_family,
+                                0,
+                            );
+                        });
+                        if let Some(shape) = highlight {
+                            let rect = egui::Rect::from_x_y_ranges(
+                                ui.max_rect().x_range(),
+                                block_top..=ui.cursor().top(),
+                            );
+                            ui.painter().set(
+                                shape,
+                                egui::Shape::rect_filled(rect, 3.0, colors.match_highlight),
+                            );
+                        }
+                        rendered.push((index, block_top, ui.cursor().top()));
+                    }
+                    ui.add_space(skipped);
+
+                    // Keep structural_state alive to avoid unused variable warning
+                    let _ = &structural_state;
+
+                    // Return a response from the scroll area content
+                    ui.allocate_response(Vec2::ZERO, egui::Sense::hover())
+                })
+                .inner;
+
+            // Remember which block has focus
+            let focus_y = ui
+                .memory(|m| m.focused())
+                .and_then(|focused| ui.ctx().read_response(focused))
+                .map(|response| response.rect.center().y);
+            let focused = focus_y.and_then(|y| {
+                rendered
+                    .iter()
+                    .find(|(_, top, bottom)| (*top..*bottom).contains(&y))
+                    .map(|(index, _, _)| document.blocks()[*index].id)
+            });
+            ui.data_mut(|d| match focused {
+                Some(block) if !self.read_only => d.insert_temp(focused_block_id, block),
+                _ => d.remove::<BlockId>(focused_block_id),
+            });
+            // A preview doesn't keep focus, so it can't be typed into
+            if self.read_only && focused.is_some() {
+                ui.memory_mut(|m| m.stop_text_input());
+            }
+
+            // Store measured heights. Blocks above the viewport whose height
+            // changed would move the visible content, so the scroll offset
+            // is corrected by the difference.
+            let mut correction = 0.0;
+            for (index, top, bottom) in rendered {
+                let height = bottom - top;
+                if bottom - origin <= viewport.min.y {
+                    correction += height - layout.span(index).1;
+                }
+                let id = document.blocks()[index].id;
+                document.set_block_height(id, height);
+            }
+            let layout = BlockLayout::new(document, row_height);
+            (response, layout.mappings(document.blocks()), correction)
         });
+        let (response, block_mappings, correction) = scroll_output.inner;
+        match (pending_selection, edit_state.pending_selection) {
+            (Some((range, frames)), Some(_)) if frames > 1 => {
+                ui.data_mut(|d| d.insert_temp(selection_id, (range, frames - 1)));
+                ui.ctx().request_repaint();
+            }
+            _ => ui.data_mut(|d| d.remove::<((usize, usize), u32)>(selection_id)),
+        }
+        let mut scroll_state = scroll_output.state;
+        if correction.abs() > 0.5 && target_scroll_offset.is_none() {
+            scroll_state.offset.y = (scroll_state.offset.y + correction).max(0.0);
+            scroll_state.store(ui.ctx(), scroll_output.id);
+            ui.ctx().request_repaint();
+        }
+
+        // A preview discards anything its widgets changed
+        if self.read_only {
+            if *self.content != original_content {
+                *self.content = original_content;
+            }
+            return MarkdownEditorOutput {
+                response,
+                changed: false,
+                cursor_position: (0, 0),
+                mode: EditorMode::Rendered,
+                focused_element: None,
+                scroll_offset: scroll_state.offset.y,
+                block_mappings,
+                wiki_link_clicked: edit_state.clicked_wiki_link,
+            };
+        }
 
         // Apply any pending structural edits
         let mut structural_changed = false;
@@ -674,13 +1003,121 @@ impl<'a> MarkdownEditor<'a> {
         let focused_element = edit_state.get_focused_element(&original_content);
 
         MarkdownEditorOutput {
-            response: scroll_output.inner,
+            response,
             changed,
             cursor_position: (0, 0), // Position tracking is simplified in WYSIWYG mode
             mode: EditorMode::Rendered,
             focused_element,
-            scroll_offset: scroll_output.state.offset.y,
+            scroll_offset: scroll_state.offset.y,
+            block_mappings,
+            wiki_link_clicked: edit_state.clicked_wiki_link,
+        }
+    }
+}
+
+// â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€
+// Block Layout
+// â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€
+
+/// Vertical positions of the top-level blocks in rendered mode, from measured
+/// heights or, for blocks that haven't been rendered yet, estimates.
+struct BlockLayout {
+    /// Top of each block, relative to the top of the content
+    tops: Vec<f32>,
+    /// Height of each block
+    heights: Vec<f32>,
+}
+
+impl BlockLayout {
+    fn new(document: &DocumentCache, row_height: f32) -> Self {
+        let heights: Vec<f32> = document
+            .blocks()
+            .iter()
+            .map(|block| {
+                document
+                    .block_height(block.id)
+                    .unwrap_or_else(|| estimate_block_height(&block.node, row_height))
+            })
+            .collect();
+        let tops = heights
+            .iter()
+            .scan(0.0, |top, height| {
+                let block_top = *top;
+                *top += height;
+                Some(block_top)
+            })
+            .collect();
+        Self { tops, heights }
+    }
+
+    /// Top and height of a block.
+    fn span(&self, index: usize) -> (f32, f32) {
+        (self.tops[index], self.heights[index])
+    }
+
+    /// Height of the whole document.
+    fn total_height(&self) -> f32 {
+        self.tops
+            .last()
+            .zip(self.heights.last())
+            .map_or(0.0, |(top, height)| top + height)
+    }
+
+    /// Content offset of a source line (1-indexed), interpolated within the
+    /// block containing it.
+    fn line_offset(&self, blocks: &[Block], line: usize) -> f32 {
+        let Some(index) = blocks.iter().position(|b| b.node.end_line >= line) else {
+            return self.total_height();
+        };
+        let node = &blocks[index].node;
+        let (top, height) = self.span(index);
+        if line <= node.start_line {
+            return top;
         }
+        let lines = (node.end_line - node.start_line + 1) as f32;
+        top + height * (line - node.start_line) as f32 / lines
+    }
+
+    /// Block-to-offset mappings for sync scrolling.
+    fn mappings(&self, blocks: &[Block]) -> Vec<BlockMapping> {
+        blocks
+            .iter()
+            .enumerate()
+            .map(|(index, block)| {
+                let (top, height) = self.span(index);
+                BlockMapping::new(
+                    block.node.start_line,
+                    block.node.end_line,
+                    top,
+                    top + height,
+                    block_type(&block.node.node_type),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Guess the rendered height of a block that hasn't been rendered yet.
+fn estimate_block_height(node: &MarkdownNode, row_height: f32) -> f32 {
+    let lines = (node.end_line.saturating_sub(node.start_line) + 1) as f32;
+    match node.node_type {
+        MarkdownNodeType::Heading { .. } => row_height * 2.0,
+        MarkdownNodeType::ThematicBreak => row_height,
+        _ => (lines + 0.5) * row_height,
+    }
+}
+
+/// The sync scroll block type of a top-level node.
+fn block_type(node_type: &MarkdownNodeType) -> BlockType {
+    match node_type {
+        MarkdownNodeType::Heading { .. } => BlockType::Heading,
+        MarkdownNodeType::Paragraph => BlockType::Paragraph,
+        MarkdownNodeType::CodeBlock { .. } => BlockType::CodeBlock,
+        MarkdownNodeType::List { .. } => BlockType::List,
+        MarkdownNodeType::BlockQuote => BlockType::BlockQuote,
+        MarkdownNodeType::Table { .. } => BlockType::Table,
+        MarkdownNodeType::ThematicBreak => BlockType::HorizontalRule,
+        _ => BlockType::Other,
     }
 }
 
@@ -1030,6 +1467,7 @@ fn render_heading(
         _ => 4.0,
     };
     ui.add_space(top_margin);
+    let pending_selection = edit_state.take_selection_in(source, node.start_line, node.end_line);
 
     // Editable heading text with left indent
     let (has_focus, selection, changed, new_text) = ui
@@ -1044,6 +1482,16 @@ fn render_heading(
                     .desired_width(f32::INFINITY);
 
                 let output = text_edit.show(ui);
+                if let Some(range) = pending_selection {
+                    select_in_text_edit(
+                        ui,
+                        &output.response,
+                        source,
+                        node.start_line,
+                        range,
+                        &editable.text,
+                    );
+                }
 
                 let changed = output.response.changed();
                 let has_focus = output.response.has_focus();
@@ -1176,6 +1624,9 @@ fn render_paragraph_with_structural_keys(
                 | MarkdownNodeType::Emphasis
                 | MarkdownNodeType::Strikethrough
                 | MarkdownNodeType::Code(_)
+                | MarkdownNodeType::Math { .. }
+                | MarkdownNodeType::Image { .. }
+                | MarkdownNodeType::WikiLink(_)
         )
     });
 
@@ -1296,7 +1747,10 @@ fn render_paragraph_with_structural_keys(
                     egui::Sense::click(),
                 );
 
-                if sense_response.clicked() {
+                if sense_response.clicked()
+                    && !images::zoom_opened_this_frame(ui.ctx())
+                    && edit_state.clicked_wiki_link.is_none()
+                {
                     para_edit_state.editing = true;
                     para_edit_state.needs_focus = true;
                     para_edit_state.edit_text =
@@ -1471,6 +1925,9 @@ fn render_list_item_with_structural_keys(
                         | MarkdownNodeType::Strikethrough
                         | MarkdownNodeType::Link { .. }
                         | MarkdownNodeType::Code(_)
+                        | MarkdownNodeType::Math { .. }
+                        | MarkdownNodeType::Image { .. }
+                        | MarkdownNodeType::WikiLink(_)
                 )
             })
         })
@@ -1668,7 +2125,10 @@ fn render_list_item_with_structural_keys(
                         egui::Sense::click(),
                     );
 
-                    if sense_response.clicked() {
+                    if sense_response.clicked()
+                        && !images::zoom_opened_this_frame(ui.ctx())
+                        && edit_state.clicked_wiki_link.is_none()
+                    {
                         // Enter edit mode
                         item_edit_state.editing = true;
                         item_edit_state.needs_focus = true;
@@ -1762,6 +2222,9 @@ fn render_paragraph(
                 | MarkdownNodeType::Emphasis
                 | MarkdownNodeType::Strikethrough
                 | MarkdownNodeType::Code(_)
+                | MarkdownNodeType::Math { .. }
+                | MarkdownNodeType::Image { .. }
+                | MarkdownNodeType::WikiLink(_)
         )
     });
 
@@ -1796,6 +2259,16 @@ fn render_paragraph(
 
         let widget_id = formatted_para_id.with("text_edit");
 
+        // A search result in this paragraph is selected in its raw markdown
+        if !para_edit_state.editing
+            && edit_state.selection_pending_in(source, node.start_line, node.end_line)
+        {
+            para_edit_state.editing = true;
+            para_edit_state.needs_focus = true;
+            para_edit_state.edit_text =
+                extract_paragraph_content(source, node.start_line, node.end_line);
+        }
+
         ui.horizontal(|ui| {
             ui.add_space(4.0 + indent_level as f32 * 20.0);
 
@@ -1816,6 +2289,18 @@ fn render_paragraph(
                     response.request_focus();
                     para_edit_state.needs_focus = false;
                 }
+                if let Some(range) =
+                    edit_state.take_selection_in(source, node.start_line, node.end_line)
+                {
+                    select_in_text_edit(
+                        ui,
+                        &response,
+                        source,
+                        node.start_line,
+                        range,
+                        &para_edit_state.edit_text,
+                    );
+                }
 
                 // Check for exit conditions
                 let enter_pressed = response.has_focus()
@@ -1889,7 +2374,10 @@ fn render_paragraph(
                     egui::Sense::click(),
                 );
 
-                if sense_response.clicked() {
+                if sense_response.clicked()
+                    && !images::zoom_opened_this_frame(ui.ctx())
+                    && edit_state.clicked_wiki_link.is_none()
+                {
                     // Enter edit mode
                     para_edit_state.editing = true;
                     para_edit_state.needs_focus = true;
@@ -1915,6 +2403,8 @@ fn render_paragraph(
         // Simple text-only paragraph - use editable text directly
         let text = node.text_content();
         let node_id = edit_state.add_node(text.clone(), node.start_line, node.end_line);
+        let pending_selection =
+            edit_state.take_selection_in(source, node.start_line, node.end_line);
 
         let (has_focus, selection, changed, new_text) = ui
             .horizontal(|ui| {
@@ -1930,6 +2420,16 @@ fn render_paragraph(
                         .desired_rows(1);
 
                     let output = text_edit.show(ui);
+                    if let Some(range) = pending_selection {
+                        select_in_text_edit(
+                            ui,
+                            &output.response,
+                            source,
+                            node.start_line,
+                            range,
+                            &editable.text,
+                        );
+                    }
 
                     let changed = output.response.changed();
                     let has_focus = output.response.has_focus();
@@ -2151,6 +2651,52 @@ fn render_inline_node(
             );
         }
 
+        MarkdownNodeType::Math { latex, display } => {
+            // Math doesn't inherit text styles either
+            let math = MathView::new(latex)
+                .display(*display)
+                .font_size(font_size)
+                .color(colors.text);
+            if *display {
+                // Display math gets its own centered line
+                ui.end_row();
+                ui.allocate_ui_with_layout(
+                    Vec2::new(ui.available_width(), 0.0),
+                    egui::Layout::top_down(egui::Align::Center),
+                    |ui| math.show(ui),
+                );
+                ui.end_row();
+            } else {
+                math.show(ui);
+            }
+        }
+
+        MarkdownNodeType::Image { url, title } => {
+            // Alt text is stored as the image's children
+            let alt = node.text_content();
+            InlineImage::new(url, &alt)
+                .title(title)
+                .base_dir(edit_state.image_base_dir.as_deref())
+                .max_width(ui.max_rect().width())
+                .show(ui);
+        }
+
+        MarkdownNodeType::WikiLink(link) => {
+            let rich_text = RichText::new(link.display_text())
+                .color(colors.link)
+                .underline();
+            let response = ui.label(style.apply(rich_text, font_size, editor_font));
+            // Detect the click by hand: the paragraph's click-to-edit area
+            // is registered after its children and would take it
+            if ui.rect_contains_pointer(response.rect) {
+                ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+                if ui.input(|i| i.pointer.primary_clicked()) {
+                    edit_state.clicked_wiki_link = Some(link.clone());
+                }
+            }
+            response.on_hover_text(link.to_markdown());
+        }
+
         MarkdownNodeType::SoftBreak => {
             ui.label(" ");
         }
@@ -2427,6 +2973,9 @@ fn render_list_item(
                         | MarkdownNodeType::Strikethrough
                         | MarkdownNodeType::Link { .. }
                         | MarkdownNodeType::Code(_)
+                        | MarkdownNodeType::Math { .. }
+                        | MarkdownNodeType::Image { .. }
+                        | MarkdownNodeType::WikiLink(_)
                 )
             })
         })
@@ -2533,6 +3082,15 @@ fn render_list_item(
 
                 let widget_id = formatted_item_id.with("text_edit");
 
+                // A search result in this item is selected in its raw markdown
+                if !item_edit_state.editing
+                    && edit_state.selection_pending_in(source, para.start_line, para.end_line)
+                {
+                    item_edit_state.editing = true;
+                    item_edit_state.needs_focus = true;
+                    item_edit_state.edit_text = extract_list_item_content(source, para.start_line);
+                }
+
                 if item_edit_state.editing {
                     // EDIT MODE: Show TextEdit with raw markdown
                     let text_edit = TextEdit::singleline(&mut item_edit_state.edit_text)
@@ -2550,6 +3108,9 @@ fn render_list_item(
                         response.request_focus();
                         item_edit_state.needs_focus = false;
                     }
+                    if let Some(range) = edit_state.take_selection_in(source, para.start_line, para.end_line) {
+                        select_in_text_edit(ui, &response, source, para.start_line, range, &item_edit_state.edit_text);
+                    }
 
                     // Check for exit conditions:
                     // 1. Focus lost (clicked elsewhere)
@@ -2597,7 +3158,10 @@ fn render_list_item(
                         egui::Sense::click(),
                     );
 
-                    if sense_response.clicked() {
+                    if sense_response.clicked()
+                    && !images::zoom_opened_this_frame(ui.ctx())
+                    && edit_state.clicked_wiki_link.is_none()
+                {
                         // Enter edit mode
                         item_edit_state.editing = true;
                         item_edit_state.needs_focus = true;
@@ -2619,6 +3183,12 @@ fn render_list_item(
                 }
             }
         } else if let Some((node_id, start_line, end_line)) = simple_text_node_id {
+            // Nested lists select their own results
+            let text_end_line = nested_lists
+                .first()
+                .map_or(end_line, |list| list.start_line.saturating_sub(1).max(start_line));
+            let pending_selection = edit_state.take_selection_in(source, start_line, text_end_line);
+
             // Simple text - editable
             if let Some(editable) = edit_state.get_node_mut(node_id) {
                 let widget_id = ui.id().with("list_item_text").with(start_line);
@@ -2632,6 +3202,9 @@ fn render_list_item(
                     .clip_text(false);
 
                 let output = text_edit.show(ui);
+                if let Some(range) = pending_selection {
+                    select_in_text_edit(ui, &output.response, source, start_line, range, &editable.text);
+                }
 
                 let changed = output.response.changed();
                 let has_focus = output.response.has_focus();
@@ -3739,4 +4312,74 @@ mod tests {
         // Just URL replaced, no markdown syntax added
         assert_eq!(source, "Visit https://new-url.com today.");
     }
+
+    // â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€
+    // Block Layout Tests
+    // â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€â”€
+
+    #[test]
+    fn test_block_layout_uses_measured_heights() {
+        let mut document = DocumentCache::new();
+        document
+            .update("# Title\n\nOne\ntwo\nthree\n\n- item\n", 0)
+            .unwrap();
+        let ids: Vec<BlockId> = document.blocks().iter().map(|b| b.id).collect();
+        document.set_block_height(ids[0], 40.0);
+        document.set_block_height(ids[1], 60.0);
+
+        let layout = BlockLayout::new(&document, 10.0);
+        assert_eq!(layout.span(0), (0.0, 40.0));
+        assert_eq!(layout.span(1), (40.0, 60.0));
+        // The list hasn't been measured: one line plus spacing
+        assert_eq!(layout.span(2), (100.0, 15.0));
+        assert_eq!(layout.total_height(), 115.0);
+    }
+
+    #[test]
+    fn test_block_layout_line_offsets_and_mappings() {
+        let mut document = DocumentCache::new();
+        document
+            .update("# Title\n\nOne\ntwo\nthree\n\n- item\n", 0)
+            .unwrap();
+        let ids: Vec<BlockId> = document.blocks().iter().map(|b| b.id).collect();
+        for id in ids {
+            document.set_block_height(id, 30.0);
+        }
+        let layout = BlockLayout::new(&document, 10.0);
+        let blocks = document.blocks();
+
+        assert_eq!(layout.line_offset(blocks, 1), 0.0);
+        // Blank lines map to the start of the next block
+        assert_eq!(layout.line_offset(blocks, 2), 30.0);
+        // Lines inside a block are interpolated
+        assert_eq!(layout.line_offset(blocks, 4), 40.0);
+        assert_eq!(layout.line_offset(blocks, 100), 90.0);
+
+        let mappings = layout.mappings(blocks);
+        assert_eq!(mappings.len(), 3);
+        assert_eq!(mappings[1].source_lines, (3, 5));
+        assert_eq!(mappings[1].rendered_range, (30.0, 60.0));
+        assert_eq!(mappings[0].block_type, BlockType::Heading);
+        assert_eq!(mappings[2].block_type, BlockType::List);
+    }
+
+    #[test]
+    fn test_selection_in_widget_skips_markers() {
+        let source = "Intro\n\n## Find the word\n";
+        let start = source.find("word").unwrap();
+        // The heading widget shows "Find the word" without "## "
+        assert_eq!(
+            selection_in_widget(source, 7, (start, start + 4), "Find the word"),
+            (9, 13)
+        );
+    }
+
+    #[test]
+    fn test_selection_in_widget_picks_same_occurrence() {
+        let source = "- a b a\n";
+        // The second "a" is selected, not the first
+        assert_eq!(selection_in_widget(source, 0, (6, 7), "a b a"), (4, 5));
+        // An empty range keeps the offset from the start of the node
+        assert_eq!(selection_in_widget(source, 0, (4, 4), "a b a"), (4, 4));
+    }
 }
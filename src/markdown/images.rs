@@ -0,0 +1,683 @@
+//! Inline Images
+//!
+//! Rendered mode shows local images referenced with `![alt](path)`. Paths are
+//! resolved against the document's directory and handed to egui as `file://`
+//! URIs, which [`FileImageLoader`] decodes on a few background threads so
+//! large pictures never stall the UI. egui caches the resulting textures;
+//! images that fail to load or change on disk are loaded again.
+//!
+//! Supported formats are PNG, JPEG, GIF (first frame), WebP, BMP and SVG.
+//! SVGs are rasterized without a font database, so text inside them is not
+//! drawn.
+//!
+//! # Example
+//! ```ignore
+//! use crate::markdown::images::{install_image_loader, InlineImage};
+//!
+//! install_image_loader(&ctx);
+//! InlineImage::new("diagram.png", "Diagram")
+//!     .base_dir(Some(document_dir))
+//!     .max_width(ui.available_width())
+//!     .show(ui);
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::SystemTime;
+
+use eframe::egui::{
+    self, pos2, vec2, Color32, ColorImage, Context, CursorIcon, Rect, Response, RichText, Sense,
+    TextureOptions, Ui, Vec2,
+};
+use egui::load::{ImageLoadResult, ImageLoader, ImagePoll, LoadError, SizeHint, TexturePoll};
+use log::{debug, warn};
+
+use crate::export::images::{is_remote_url, resolve_local_image};
+
+/// URI scheme handled by [`FileImageLoader`].
+const FILE_SCHEME: &str = "file://";
+
+/// Longest side a decoded image is scaled down to, to stay within GPU texture limits.
+const MAX_TEXTURE_SIDE: u32 = 4096;
+
+/// SVGs are rasterized at this multiple of their size so they stay sharp when zoomed.
+const SVG_SCALE: f32 = 2.0;
+
+/// How far a zoomed image may be scaled beyond its natural size.
+const MAX_ZOOM: f32 = 2.0;
+
+/// Number of threads decoding images.
+const DECODE_WORKERS: usize = 2;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Decoding
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Check if a path names an SVG file.
+fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"))
+}
+
+/// The file's modification time, or `None` if it can't be read.
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Read and decode an image file.
+pub fn decode_image_file(path: &Path) -> Result<ColorImage, String> {
+    let bytes = std::fs::read(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => "file not found".to_string(),
+        _ => e.to_string(),
+    })?;
+    if is_svg(path) {
+        rasterize_svg(&bytes)
+    } else {
+        decode_raster(&bytes)
+    }
+}
+
+/// Decode a raster image, scaling it down if it is larger than a texture may be.
+fn decode_raster(bytes: &[u8]) -> Result<ColorImage, String> {
+    let image = image::load_from_memory(bytes).map_err(|e| e.to_string())?;
+    let image = if image.width().max(image.height()) > MAX_TEXTURE_SIDE {
+        image.thumbnail(MAX_TEXTURE_SIDE, MAX_TEXTURE_SIDE)
+    } else {
+        image
+    };
+    let rgba = image.to_rgba8();
+    let size = [rgba.width() as usize, rgba.height() as usize];
+    Ok(ColorImage::from_rgba_unmultiplied(size, rgba.as_raw()))
+}
+
+/// Rasterize an SVG at [`SVG_SCALE`] times its size.
+fn rasterize_svg(bytes: &[u8]) -> Result<ColorImage, String> {
+    use resvg::{tiny_skia, usvg};
+
+    let tree =
+        usvg::Tree::from_data(bytes, &usvg::Options::default()).map_err(|e| e.to_string())?;
+    let size = tree.size();
+    let scale = SVG_SCALE.min(MAX_TEXTURE_SIDE as f32 / size.width().max(size.height()));
+    let width = (size.width() * scale).ceil().max(1.0) as u32;
+    let height = (size.height() * scale).ceil().max(1.0) as u32;
+    let mut pixmap =
+        tiny_skia::Pixmap::new(width, height).ok_or_else(|| "SVG has no size".to_string())?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    Ok(ColorImage::from_rgba_premultiplied(
+        [width as usize, height as usize],
+        pixmap.data(),
+    ))
+}
+
+/// Size an image is shown at before any scaling to fit, in points.
+fn natural_size(path: &Path, texture_size: Vec2) -> Vec2 {
+    if is_svg(path) {
+        texture_size / SVG_SCALE
+    } else {
+        texture_size
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Image Loader
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Decoding state of one image.
+enum Entry {
+    Pending,
+    Ready(Arc<ColorImage>),
+    /// Decoding failed; retried once the file's modification time changes
+    Failed {
+        error: String,
+        modified: Option<SystemTime>,
+    },
+}
+
+/// Images by URI, shared with the decoding threads.
+type Cache = Arc<Mutex<HashMap<String, Entry>>>;
+
+/// An image waiting for a decoding thread.
+struct DecodeJob {
+    uri: String,
+    path: PathBuf,
+    ctx: Context,
+}
+
+/// egui image loader for `file://` URIs that decodes on background threads.
+#[derive(Default)]
+pub struct FileImageLoader {
+    cache: Cache,
+    /// Queue feeding the decoding threads, started with the first image
+    jobs: OnceLock<Sender<DecodeJob>>,
+}
+
+/// Lock a mutex, recovering it if a decoding thread panicked.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Start the decoding threads, returning the queue that feeds them.
+///
+/// If no thread could be started the queue is closed, and sending to it
+/// fails.
+fn start_workers(cache: &Cache) -> Sender<DecodeJob> {
+    let (sender, receiver) = mpsc::channel();
+    let receiver = Arc::new(Mutex::new(receiver));
+    for i in 0..DECODE_WORKERS {
+        let receiver = receiver.clone();
+        let cache = cache.clone();
+        let spawned = std::thread::Builder::new()
+            .name(format!("ferrite-image-{}", i))
+            .spawn(move || decode_jobs(&receiver, &cache));
+        if let Err(e) = spawned {
+            warn!("Failed to start image decoding thread: {}", e);
+        }
+    }
+    sender
+}
+
+/// Decode queued images until the loader is dropped.
+fn decode_jobs(receiver: &Mutex<Receiver<DecodeJob>>, cache: &Cache) {
+    loop {
+        let Ok(job) = lock(receiver).recv() else {
+            return;
+        };
+        let modified = modified_time(&job.path);
+        let entry = match decode_image_file(&job.path) {
+            Ok(image) => {
+                debug!("Decoded image {} ({:?})", job.path.display(), image.size);
+                Entry::Ready(Arc::new(image))
+            }
+            Err(e) => {
+                warn!("Failed to load image {}: {}", job.path.display(), e);
+                Entry::Failed { error: e, modified }
+            }
+        };
+        lock(cache).insert(job.uri, entry);
+        job.ctx.request_repaint();
+    }
+}
+
+impl ImageLoader for FileImageLoader {
+    fn id(&self) -> &str {
+        concat!(module_path!(), "::FileImageLoader")
+    }
+
+    fn load(&self, ctx: &Context, uri: &str, _size_hint: SizeHint) -> ImageLoadResult {
+        let Some(path) = uri.strip_prefix(FILE_SCHEME) else {
+            return Err(LoadError::NotSupported);
+        };
+        let path = PathBuf::from(path);
+
+        let mut cache = lock(&self.cache);
+        match cache.get(uri) {
+            Some(Entry::Pending) => return Ok(ImagePoll::Pending { size: None }),
+            Some(Entry::Ready(image)) => {
+                return Ok(ImagePoll::Ready {
+                    image: image.clone(),
+                })
+            }
+            Some(Entry::Failed { error, modified }) if *modified == modified_time(&path) => {
+                return Err(LoadError::Loading(error.clone()))
+            }
+            // The file was created, fixed or replaced since it failed
+            Some(Entry::Failed { .. }) | None => {}
+        }
+        cache.insert(uri.to_string(), Entry::Pending);
+        drop(cache);
+
+        let job = DecodeJob {
+            uri: uri.to_string(),
+            path,
+            ctx: ctx.clone(),
+        };
+        let jobs = self.jobs.get_or_init(|| start_workers(&self.cache));
+        if jobs.send(job).is_err() {
+            let error = "no thread to decode images".to_string();
+            lock(&self.cache).insert(
+                uri.to_string(),
+                Entry::Failed {
+                    error: error.clone(),
+                    modified: None,
+                },
+            );
+            return Err(LoadError::Loading(error));
+        }
+        Ok(ImagePoll::Pending { size: None })
+    }
+
+    fn forget(&self, uri: &str) {
+        lock(&self.cache).remove(uri);
+    }
+
+    fn forget_all(&self) {
+        lock(&self.cache).clear();
+    }
+
+    fn byte_size(&self) -> usize {
+        lock(&self.cache)
+            .values()
+            .map(|entry| match entry {
+                Entry::Ready(image) => image.pixels.len() * std::mem::size_of::<Color32>(),
+                Entry::Pending | Entry::Failed { .. } => 0,
+            })
+            .sum()
+    }
+}
+
+/// Make egui load an image again if its file changed since it was shown.
+///
+/// egui keeps textures until told to forget them, so the modification time
+/// seen when the image was first shown is remembered and compared.
+fn forget_if_changed(ctx: &Context, uri: &str, path: &Path) {
+    let id = egui::Id::new("image_modified").with(uri);
+    let modified = modified_time(path);
+    match ctx.data(|data| data.get_temp::<Option<SystemTime>>(id)) {
+        Some(seen) if seen != modified => {
+            debug!("Image {} changed on disk, reloading", path.display());
+            ctx.forget_image(uri);
+            ctx.data_mut(|data| data.remove::<Option<SystemTime>>(id));
+        }
+        Some(_) => {}
+        None => ctx.data_mut(|data| data.insert_temp(id, modified)),
+    }
+}
+
+/// Register [`FileImageLoader`] with egui.
+pub fn install_image_loader(ctx: &Context) {
+    ctx.add_image_loader(Arc::new(FileImageLoader::default()));
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Inline Image Widget
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Widget that shows a Markdown image scaled to fit its column.
+///
+/// Clicking the image opens it in a zoomed overlay (see [`show_zoomed_image`]).
+/// Remote, missing or undecodable images are shown as a placeholder with the
+/// alt text and the reason.
+pub struct InlineImage<'a> {
+    url: &'a str,
+    alt: &'a str,
+    title: &'a str,
+    base_dir: Option<&'a Path>,
+    max_width: f32,
+}
+
+impl<'a> InlineImage<'a> {
+    /// Create a widget for the image at `url` with the given alt text.
+    pub fn new(url: &'a str, alt: &'a str) -> Self {
+        Self {
+            url,
+            alt,
+            title: "",
+            base_dir: None,
+            max_width: f32::INFINITY,
+        }
+    }
+
+    /// Set the image title (shown on hover).
+    #[must_use]
+    pub fn title(mut self, title: &'a str) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// Set the directory relative paths are resolved against.
+    #[must_use]
+    pub fn base_dir(mut self, base_dir: Option<&'a Path>) -> Self {
+        self.base_dir = base_dir;
+        self
+    }
+
+    /// Set the widest the image may be shown.
+    #[must_use]
+    pub fn max_width(mut self, width: f32) -> Self {
+        self.max_width = width;
+        self
+    }
+
+    /// Show the image, or a placeholder.
+    pub fn show(self, ui: &mut Ui) -> Response {
+        if is_remote_url(self.url) {
+            return self.placeholder(ui, "Remote images are not loaded", false);
+        }
+        let path = match resolve_local_image(self.url, self.base_dir.unwrap_or(Path::new(""))) {
+            Some(path) if path.is_absolute() => path,
+            _ => {
+                return self.placeholder(
+                    ui,
+                    "Save the document to show images with relative paths",
+                    false,
+                )
+            }
+        };
+        let uri = format!("{}{}", FILE_SCHEME, path.display());
+
+        match ui
+            .ctx()
+            .try_load_texture(&uri, TextureOptions::LINEAR, SizeHint::default())
+        {
+            Ok(TexturePoll::Ready { texture }) => {
+                forget_if_changed(ui.ctx(), &uri, &path);
+                let natural = natural_size(&path, texture.size);
+                let width = natural.x.min(self.max_width.max(16.0));
+                let size = natural * (width / natural.x.max(1.0));
+                let (rect, response) = ui.allocate_exact_size(size, Sense::hover());
+                if ui.is_rect_visible(rect) {
+                    ui.painter().image(
+                        texture.id,
+                        rect,
+                        Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0)),
+                        Color32::WHITE,
+                    );
+                }
+                // Detect the click by hand: click-to-edit areas drawn over
+                // formatted paragraphs would otherwise take it
+                if ui.rect_contains_pointer(rect) {
+                    ui.ctx().set_cursor_icon(CursorIcon::ZoomIn);
+                    if ui.input(|i| i.pointer.primary_clicked()) {
+                        open_zoom(ui.ctx(), uri, natural, self.alt.to_string());
+                    }
+                }
+                let hover = if self.title.is_empty() {
+                    self.alt
+                } else {
+                    self.title
+                };
+                if hover.is_empty() {
+                    response
+                } else {
+                    response.on_hover_text(hover)
+                }
+            }
+            Ok(TexturePoll::Pending { .. }) => self.placeholder(ui, "Loading…", true),
+            Err(LoadError::Loading(reason)) if reason == "file not found" => {
+                let detail = format!("Image not found: {}", path.display());
+                self.placeholder(ui, &detail, false)
+            }
+            Err(LoadError::Loading(reason)) => {
+                let detail = format!("Couldn't load {}: {}", path.display(), reason);
+                self.placeholder(ui, &detail, false)
+            }
+            Err(e) => self.placeholder(ui, &e.to_string(), false),
+        }
+    }
+
+    /// Framed box with the alt text and why the image isn't shown.
+    fn placeholder(&self, ui: &mut Ui, detail: &str, loading: bool) -> Response {
+        let visuals = ui.visuals();
+        let stroke = egui::Stroke::new(1.0, visuals.widgets.noninteractive.bg_stroke.color);
+        let muted = visuals.weak_text_color();
+        egui::Frame::none()
+            .stroke(stroke)
+            .rounding(4.0)
+            .inner_margin(egui::Margin::symmetric(10.0, 6.0))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if loading {
+                        ui.spinner();
+                    } else {
+                        ui.label(RichText::new("🖼").size(20.0).color(muted));
+                    }
+                    ui.vertical(|ui| {
+                        let alt = if self.alt.is_empty() {
+                            "Image"
+                        } else {
+                            self.alt
+                        };
+                        ui.label(RichText::new(alt).strong());
+                        ui.label(RichText::new(detail).small().color(muted));
+                    });
+                });
+            })
+            .response
+            .on_hover_text(self.url)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Zoom Overlay
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// The image shown in the zoom overlay.
+#[derive(Debug, Clone)]
+struct ZoomedImage {
+    uri: String,
+    size: Vec2,
+    caption: String,
+    /// Frame the overlay was opened on, so the opening click doesn't close it
+    opened_frame: u64,
+}
+
+fn zoom_id() -> egui::Id {
+    egui::Id::new("zoomed_image")
+}
+
+fn open_zoom(ctx: &Context, uri: String, size: Vec2, caption: String) {
+    let zoomed = ZoomedImage {
+        uri,
+        size,
+        caption,
+        opened_frame: ctx.frame_nr(),
+    };
+    ctx.data_mut(|data| data.insert_temp(zoom_id(), zoomed));
+}
+
+/// Whether an image was clicked to zoom this frame.
+///
+/// Click-to-edit handlers check this so clicking an image inside a paragraph
+/// doesn't also start editing it.
+pub fn zoom_opened_this_frame(ctx: &Context) -> bool {
+    ctx.data(|data| data.get_temp::<ZoomedImage>(zoom_id()))
+        .is_some_and(|zoomed| zoomed.opened_frame == ctx.frame_nr())
+}
+
+/// Draw the zoomed image over the whole window, if one is open.
+///
+/// Clicking anywhere or pressing Escape closes it.
+pub fn show_zoomed_image(ctx: &Context) {
+    let Some(zoomed) = ctx.data(|data| data.get_temp::<ZoomedImage>(zoom_id())) else {
+        return;
+    };
+    let texture =
+        match ctx.try_load_texture(&zoomed.uri, TextureOptions::LINEAR, SizeHint::default()) {
+            Ok(TexturePoll::Ready { texture }) => texture,
+            _ => {
+                ctx.data_mut(|data| data.remove::<ZoomedImage>(zoom_id()));
+                return;
+            }
+        };
+
+    let screen = ctx.screen_rect();
+    let caption_height = if zoomed.caption.is_empty() { 0.0 } else { 28.0 };
+    let room = vec2(screen.width(), screen.height() - caption_height) * 0.9;
+    let scale = (room.x / zoomed.size.x)
+        .min(room.y / zoomed.size.y)
+        .min(MAX_ZOOM);
+    let image_rect = Rect::from_center_size(
+        screen.center() - vec2(0.0, caption_height / 2.0),
+        zoomed.size * scale,
+    );
+
+    egui::Area::new(zoom_id().with("area"))
+        .order(egui::Order::Foreground)
+        .fixed_pos(screen.min)
+        .show(ctx, |ui| {
+            let painter = ui.painter();
+            painter.rect_filled(screen, 0.0, Color32::from_black_alpha(200));
+            painter.image(
+                texture.id,
+                image_rect,
+                Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0)),
+                Color32::WHITE,
+            );
+            if !zoomed.caption.is_empty() {
+                painter.text(
+                    image_rect.center_bottom() + vec2(0.0, 8.0),
+                    egui::Align2::CENTER_TOP,
+                    &zoomed.caption,
+                    egui::FontId::proportional(15.0),
+                    Color32::from_gray(230),
+                );
+            }
+            // Cover the window so clicks don't reach the document underneath
+            ui.allocate_rect(screen, Sense::click());
+        });
+
+    let close = ctx.input(|i| {
+        i.key_pressed(egui::Key::Escape)
+            || (i.pointer.primary_clicked() && ctx.frame_nr() > zoomed.opened_frame)
+    });
+    if close {
+        ctx.data_mut(|data| data.remove::<ZoomedImage>(zoom_id()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn write_png(path: &Path, width: u32, height: u32) {
+        let image = image::RgbaImage::from_pixel(width, height, image::Rgba([255, 0, 0, 255]));
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .unwrap();
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn test_decode_raster_and_svg() {
+        let dir = tempfile::tempdir().unwrap();
+        let png = dir.path().join("red.png");
+        write_png(&png, 3, 2);
+        let image = decode_image_file(&png).unwrap();
+        assert_eq!(image.size, [3, 2]);
+        assert_eq!(image.pixels[0], Color32::from_rgb(255, 0, 0));
+
+        let svg = dir.path().join("box.svg");
+        std::fs::write(
+            &svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10"><rect width="20" height="10" fill="blue"/></svg>"#,
+        )
+        .unwrap();
+        let image = decode_image_file(&svg).unwrap();
+        assert_eq!(image.size, [40, 20]);
+        assert_eq!(natural_size(&svg, vec2(40.0, 20.0)), vec2(20.0, 10.0));
+        assert_eq!(image.pixels[0], Color32::from_rgb(0, 0, 255));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            decode_image_file(&dir.path().join("missing.png")).unwrap_err(),
+            "file not found"
+        );
+        let broken = dir.path().join("broken.jpg");
+        std::fs::write(&broken, b"not an image").unwrap();
+        assert!(decode_image_file(&broken).is_err());
+    }
+
+    /// Load an image, waiting for it to be decoded.
+    fn load_and_wait(loader: &FileImageLoader, ctx: &Context, uri: &str) -> ImageLoadResult {
+        let mut result = loader.load(ctx, uri, SizeHint::default());
+        for _ in 0..200 {
+            if !matches!(result, Ok(ImagePoll::Pending { .. })) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            result = loader.load(ctx, uri, SizeHint::default());
+        }
+        result
+    }
+
+    #[test]
+    fn test_loader_decodes_many_images() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = Context::default();
+        let loader = FileImageLoader::default();
+        let uris: Vec<String> = (0..10)
+            .map(|i| {
+                let png = dir.path().join(format!("{}.png", i));
+                write_png(&png, i + 1, 1);
+                format!("{}{}", FILE_SCHEME, png.display())
+            })
+            .collect();
+        for uri in &uris {
+            loader.load(&ctx, uri, SizeHint::default()).unwrap();
+        }
+        for (i, uri) in uris.iter().enumerate() {
+            match load_and_wait(&loader, &ctx, uri) {
+                Ok(ImagePoll::Ready { image }) => assert_eq!(image.size, [i + 1, 1]),
+                other => panic!("image did not load: {:?}", other.err()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_loader_retries_changed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let png = dir.path().join("later.png");
+        let uri = format!("{}{}", FILE_SCHEME, png.display());
+        let ctx = Context::default();
+        let loader = FileImageLoader::default();
+
+        // Missing files fail until they appear
+        assert!(matches!(
+            load_and_wait(&loader, &ctx, &uri),
+            Err(LoadError::Loading(_))
+        ));
+        std::fs::write(&png, b"not an image").unwrap();
+        assert!(matches!(
+            load_and_wait(&loader, &ctx, &uri),
+            Err(LoadError::Loading(_))
+        ));
+
+        // Broken files are retried once they are rewritten
+        write_png(&png, 2, 2);
+        let file = std::fs::File::options().write(true).open(&png).unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(60))
+            .unwrap();
+        match load_and_wait(&loader, &ctx, &uri) {
+            Ok(ImagePoll::Ready { image }) => assert_eq!(image.size, [2, 2]),
+            other => panic!("image did not load: {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_loader_decodes_in_background() {
+        let dir = tempfile::tempdir().unwrap();
+        let png = dir.path().join("photo.png");
+        write_png(&png, 4, 4);
+
+        let ctx = Context::default();
+        let loader = FileImageLoader::default();
+        assert!(matches!(
+            loader.load(&ctx, "https://example.com/a.png", SizeHint::default()),
+            Err(LoadError::NotSupported)
+        ));
+
+        let uri = format!("{}{}", FILE_SCHEME, png.display());
+        match load_and_wait(&loader, &ctx, &uri) {
+            Ok(ImagePoll::Ready { image }) => assert_eq!(image.size, [4, 4]),
+            other => panic!("image did not load: {:?}", other.err()),
+        }
+        assert_eq!(loader.byte_size(), 4 * 4 * 4);
+
+        loader.forget(&uri);
+        assert_eq!(loader.byte_size(), 0);
+    }
+}
@@ -0,0 +1,1913 @@
+//! LaTeX Math Rendering
+//!
+//! This module renders the LaTeX subset used in `$inline$` and `$$display$$`
+//! math. Expressions are parsed into a small syntax tree which is then either
+//! laid out natively with egui (rendered mode) or written out as MathML (HTML
+//! export, so exported documents need no scripts or web fonts).
+//!
+//! # Supported Syntax
+//! - Sub/superscripts (`x_i^2`, `f'`), fractions (`\frac`, `\dfrac`, `\binom`)
+//! - Roots (`\sqrt{x}`, `\sqrt[n]{x}`) and accents (`\hat`, `\bar`, `\vec`, ...)
+//! - Greek letters, common operators, relations, arrows and big operators
+//!   (`\sum`, `\int`, `\prod`, `\lim` with limits in display math)
+//! - Function names (`\sin`, `\log`, `\operatorname{...}`), `\text{...}`
+//! - `\left( ... \right)` and the `matrix`, `pmatrix`, `bmatrix`, `vmatrix`,
+//!   `cases` and `aligned` environments
+//!
+//! Parsing never fails: unknown commands are shown as their source text.
+//!
+//! # Example
+//! ```ignore
+//! use crate::markdown::math::{to_mathml, MathView};
+//!
+//! let html = to_mathml(r"\frac{a}{b}", true);
+//! MathView::new(r"e^{i\pi} + 1 = 0").font_size(16.0).show(ui);
+//! ```
+
+use std::sync::Arc;
+
+use eframe::egui::{
+    self, pos2, vec2, Color32, FontFamily, FontId, Galley, Painter, Pos2, Rect, Response, Sense,
+    Shape, Stroke, Ui,
+};
+
+use crate::export::html::html_escape;
+use crate::fonts::FONT_INTER_ITALIC;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Syntax Tree
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A node of a parsed math expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MathNode {
+    /// A sequence of nodes laid out left to right
+    Row(Vec<MathNode>),
+    /// Variable, drawn in italics
+    Ident(String),
+    /// Upright identifier (function names, capital Greek, `\mathrm`)
+    Function(String),
+    /// Number literal
+    Number(String),
+    /// Operator, relation, fence or punctuation
+    Operator { symbol: String, kind: OperatorKind },
+    /// Upright text from `\text{...}`
+    Text(String),
+    /// Fraction (`bar` is false for `\binom`)
+    Fraction {
+        numerator: Box<MathNode>,
+        denominator: Box<MathNode>,
+        bar: bool,
+    },
+    /// Square root or n-th root
+    Root {
+        radicand: Box<MathNode>,
+        index: Option<Box<MathNode>>,
+    },
+    /// Base with optional subscript and superscript
+    Scripts {
+        base: Box<MathNode>,
+        sub: Option<Box<MathNode>>,
+        sup: Option<Box<MathNode>>,
+    },
+    /// Accent drawn above its base
+    Accent { base: Box<MathNode>, accent: Accent },
+    /// Body between stretchy delimiters (empty string for none)
+    Fenced {
+        open: String,
+        close: String,
+        body: Box<MathNode>,
+    },
+    /// Grid of cells from a matrix-like environment
+    Matrix {
+        rows: Vec<Vec<MathNode>>,
+        align: MatrixAlign,
+    },
+    /// Horizontal space in em
+    Space(f32),
+}
+
+/// How an operator is spaced and laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperatorKind {
+    /// Binary operator (`+`, `\times`)
+    Binary,
+    /// Relation (`=`, `\leq`, `\to`)
+    Relation,
+    /// Punctuation (`,`, `;`)
+    Punctuation,
+    /// Bracket or bar
+    Fence,
+    /// Big operator (`\sum`, `\int`); `limits` places scripts above/below in display math
+    Large { limits: bool },
+    /// Function name that takes limits (`\lim`, `\max`)
+    Limits,
+    /// Ordinary symbol (`\infty`, `/`)
+    Ordinary,
+}
+
+/// Accent kinds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Accent {
+    Hat,
+    Bar,
+    Vec,
+    Dot,
+    DoubleDot,
+    Tilde,
+}
+
+/// Column alignment of a matrix-like environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatrixAlign {
+    /// All columns centered (matrices)
+    Center,
+    /// All columns left-aligned (`cases`)
+    Left,
+    /// Alternating right/left columns (`aligned`)
+    Alternating,
+}
+
+impl MathNode {
+    fn empty() -> Self {
+        MathNode::Row(Vec::new())
+    }
+
+    fn is_empty(&self) -> bool {
+        matches!(self, MathNode::Row(nodes) if nodes.is_empty())
+    }
+
+    fn operator(symbol: &str, kind: OperatorKind) -> Self {
+        MathNode::Operator {
+            symbol: symbol.to_string(),
+            kind,
+        }
+    }
+}
+
+/// Markdown source for a math span, used when math is written back to text.
+pub fn to_markdown(latex: &str, display: bool) -> String {
+    if display {
+        format!("$${}$$", latex)
+    } else {
+        format!("${}$", latex)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Parser
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Parse a LaTeX math expression.
+pub fn parse(latex: &str) -> MathNode {
+    let mut parser = Parser {
+        chars: latex.chars().collect(),
+        pos: 0,
+    };
+    MathNode::Row(parser.parse_row(Stop::End))
+}
+
+/// What ends the row currently being parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stop {
+    /// End of input
+    End,
+    /// Closing `}`
+    Brace,
+    /// Closing `]` of a root index
+    Bracket,
+    /// `\right`
+    Right,
+    /// `&`, `\\` or `\end` of an environment
+    Cell,
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Read a command name after its backslash: letters, or a single symbol.
+    fn read_command(&mut self) -> String {
+        let start = self.pos;
+        if self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+                self.pos += 1;
+            }
+        } else if self.peek().is_some() {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// The command at the cursor without consuming it.
+    fn peek_command(&mut self) -> Option<String> {
+        if self.peek() != Some('\\') {
+            return None;
+        }
+        let start = self.pos;
+        self.pos += 1;
+        let name = self.read_command();
+        self.pos = start;
+        Some(name)
+    }
+
+    /// Consume the command at the cursor.
+    fn skip_command(&mut self) {
+        self.pos += 1;
+        self.read_command();
+    }
+
+    fn parse_row(&mut self, stop: Stop) -> Vec<MathNode> {
+        let mut nodes = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let Some(c) = self.peek() else { break };
+            match c {
+                '}' => {
+                    self.bump();
+                    if stop == Stop::Brace {
+                        break;
+                    }
+                }
+                ']' if stop == Stop::Bracket => {
+                    self.bump();
+                    break;
+                }
+                '&' => {
+                    if stop == Stop::Cell {
+                        break;
+                    }
+                    self.bump();
+                }
+                '^' | '_' => {
+                    self.bump();
+                    let script = self.parse_argument();
+                    let base = nodes.pop().unwrap_or_else(MathNode::empty);
+                    nodes.push(attach_script(base, c == '^', script));
+                }
+                '\'' => {
+                    self.bump();
+                    let base = nodes.pop().unwrap_or_else(MathNode::empty);
+                    let prime = MathNode::operator("′", OperatorKind::Ordinary);
+                    nodes.push(attach_script(base, true, prime));
+                }
+                '\\' => match self.peek_command().as_deref() {
+                    Some("\\") | Some("cr") => {
+                        if stop == Stop::Cell {
+                            break;
+                        }
+                        self.skip_command();
+                    }
+                    Some("end") => {
+                        if stop == Stop::Cell {
+                            break;
+                        }
+                        self.skip_command();
+                        self.read_group_text();
+                    }
+                    Some("right") => {
+                        if stop == Stop::Right {
+                            break;
+                        }
+                        self.skip_command();
+                        self.parse_delimiter();
+                    }
+                    _ => nodes.extend(self.parse_atom()),
+                },
+                _ => nodes.extend(self.parse_atom()),
+            }
+        }
+        nodes
+    }
+
+    /// Parse one atom: a group, command, number, letter or symbol.
+    fn parse_atom(&mut self) -> Option<MathNode> {
+        self.skip_whitespace();
+        let c = self.bump()?;
+        let node = match c {
+            '{' => group(self.parse_row(Stop::Brace)),
+            '\\' => {
+                let name = self.read_command();
+                self.parse_command(&name)
+            }
+            c if c.is_ascii_digit()
+                || (c == '.' && self.peek().is_some_and(|n| n.is_ascii_digit())) =>
+            {
+                let mut number = c.to_string();
+                while let Some(n) = self.peek().filter(|n| n.is_ascii_digit() || *n == '.') {
+                    number.push(n);
+                    self.pos += 1;
+                }
+                MathNode::Number(number)
+            }
+            c if c.is_alphabetic() => MathNode::Ident(c.to_string()),
+            '~' => MathNode::Space(0.25),
+            c => char_operator(c),
+        };
+        Some(node)
+    }
+
+    /// Parse a command or script argument: a braced group or a single token.
+    fn parse_argument(&mut self) -> MathNode {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() => {
+                self.bump();
+                MathNode::Number(c.to_string())
+            }
+            _ => self.parse_atom().unwrap_or_else(MathNode::empty),
+        }
+    }
+
+    /// Read the raw text of a braced group (or a single character).
+    fn read_group_text(&mut self) -> String {
+        self.skip_whitespace();
+        if self.peek() != Some('{') {
+            return self.bump().map(String::from).unwrap_or_default();
+        }
+        self.bump();
+        let mut depth = 1;
+        let mut text = String::new();
+        while let Some(c) = self.bump() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            text.push(c);
+        }
+        text
+    }
+
+    /// Parse the delimiter after `\left` or `\right` (`.` means none).
+    fn parse_delimiter(&mut self) -> String {
+        self.skip_whitespace();
+        match self.bump() {
+            Some('\\') => {
+                let name = self.read_command();
+                match name.as_str() {
+                    "{" | "lbrace" => "{",
+                    "}" | "rbrace" => "}",
+                    "langle" => "⟨",
+                    "rangle" => "⟩",
+                    "|" | "Vert" | "lVert" | "rVert" => "‖",
+                    "vert" | "lvert" | "rvert" => "|",
+                    "lfloor" => "⌊",
+                    "rfloor" => "⌋",
+                    "lceil" => "⌈",
+                    "rceil" => "⌉",
+                    _ => "",
+                }
+                .to_string()
+            }
+            Some('.') | None => String::new(),
+            Some(c) => c.to_string(),
+        }
+    }
+
+    fn parse_command(&mut self, name: &str) -> MathNode {
+        match name {
+            "," | "thinspace" => MathNode::Space(3.0 / 18.0),
+            ":" | ">" | "medspace" => MathNode::Space(4.0 / 18.0),
+            ";" | "thickspace" => MathNode::Space(5.0 / 18.0),
+            "!" => MathNode::Space(-3.0 / 18.0),
+            " " => MathNode::Space(0.25),
+            "quad" => MathNode::Space(1.0),
+            "qquad" => MathNode::Space(2.0),
+            "{" | "lbrace" => MathNode::operator("{", OperatorKind::Fence),
+            "}" | "rbrace" => MathNode::operator("}", OperatorKind::Fence),
+            "|" => MathNode::operator("‖", OperatorKind::Fence),
+            "%" | "$" | "#" | "&" | "_" => MathNode::operator(name, OperatorKind::Ordinary),
+            "frac" | "dfrac" | "tfrac" | "cfrac" => MathNode::Fraction {
+                numerator: Box::new(self.parse_argument()),
+                denominator: Box::new(self.parse_argument()),
+                bar: true,
+            },
+            "binom" => MathNode::Fenced {
+                open: "(".to_string(),
+                close: ")".to_string(),
+                body: Box::new(MathNode::Fraction {
+                    numerator: Box::new(self.parse_argument()),
+                    denominator: Box::new(self.parse_argument()),
+                    bar: false,
+                }),
+            },
+            "sqrt" => {
+                self.skip_whitespace();
+                let index = if self.peek() == Some('[') {
+                    self.bump();
+                    Some(Box::new(group(self.parse_row(Stop::Bracket))))
+                } else {
+                    None
+                };
+                MathNode::Root {
+                    radicand: Box::new(self.parse_argument()),
+                    index,
+                }
+            }
+            "text" | "textrm" | "textit" | "textbf" | "textnormal" | "mbox" => {
+                MathNode::Text(self.read_group_text())
+            }
+            "mathrm" | "operatorname" => {
+                if self.peek() == Some('*') {
+                    self.bump();
+                }
+                MathNode::Function(self.read_group_text())
+            }
+            "mathbb" => {
+                MathNode::Function(self.read_group_text().chars().map(double_struck).collect())
+            }
+            "mathbf" | "boldsymbol" | "mathit" | "mathsf" | "mathtt" | "mathcal" => {
+                self.parse_argument()
+            }
+            "displaystyle" | "textstyle" | "scriptstyle" | "limits" | "nolimits" | "left." => {
+                MathNode::empty()
+            }
+            "left" => {
+                let open = self.parse_delimiter();
+                let body = group(self.parse_row(Stop::Right));
+                let close = if self.peek_command().as_deref() == Some("right") {
+                    self.skip_command();
+                    self.parse_delimiter()
+                } else {
+                    String::new()
+                };
+                MathNode::Fenced {
+                    open,
+                    close,
+                    body: Box::new(body),
+                }
+            }
+            "begin" => {
+                let environment = self.read_group_text();
+                self.parse_environment(&environment)
+            }
+            "hat" | "widehat" => self.accent(Accent::Hat),
+            "bar" | "overline" => self.accent(Accent::Bar),
+            "vec" | "overrightarrow" => self.accent(Accent::Vec),
+            "dot" => self.accent(Accent::Dot),
+            "ddot" => self.accent(Accent::DoubleDot),
+            "tilde" | "widetilde" => self.accent(Accent::Tilde),
+            "not" => match self.parse_argument() {
+                MathNode::Operator { symbol, kind } => {
+                    let negated = match symbol.as_str() {
+                        "=" => "≠",
+                        "∈" => "∉",
+                        "⊂" => "⊄",
+                        "≡" => "≢",
+                        _ => &symbol,
+                    };
+                    MathNode::operator(negated, kind)
+                }
+                other => other,
+            },
+            _ => symbol(name).unwrap_or_else(|| MathNode::Text(format!("\\{}", name))),
+        }
+    }
+
+    fn accent(&mut self, accent: Accent) -> MathNode {
+        MathNode::Accent {
+            base: Box::new(self.parse_argument()),
+            accent,
+        }
+    }
+
+    /// Parse the body of `\begin{environment}` up to its `\end`.
+    fn parse_environment(&mut self, environment: &str) -> MathNode {
+        let (open, close, align) = match environment {
+            "pmatrix" => ("(", ")", MatrixAlign::Center),
+            "bmatrix" => ("[", "]", MatrixAlign::Center),
+            "Bmatrix" => ("{", "}", MatrixAlign::Center),
+            "vmatrix" => ("|", "|", MatrixAlign::Center),
+            "Vmatrix" => ("‖", "‖", MatrixAlign::Center),
+            "cases" => ("{", "", MatrixAlign::Left),
+            "aligned" | "align" | "align*" | "alignat" | "split" => {
+                ("", "", MatrixAlign::Alternating)
+            }
+            _ => ("", "", MatrixAlign::Center),
+        };
+        if environment == "array" {
+            // Skip the column specification
+            self.read_group_text();
+        }
+
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        loop {
+            row.push(group(self.parse_row(Stop::Cell)));
+            match self.peek() {
+                Some('&') => {
+                    self.bump();
+                }
+                Some('\\') if self.peek_command().as_deref() != Some("end") => {
+                    self.skip_command();
+                    rows.push(std::mem::take(&mut row));
+                }
+                Some('\\') => {
+                    self.skip_command();
+                    self.read_group_text();
+                    break;
+                }
+                _ => break,
+            }
+        }
+        // A trailing `\\` leaves an empty last row
+        if !(row.len() == 1 && row[0].is_empty()) {
+            rows.push(row);
+        }
+
+        let matrix = MathNode::Matrix { rows, align };
+        if open.is_empty() && close.is_empty() {
+            matrix
+        } else {
+            MathNode::Fenced {
+                open: open.to_string(),
+                close: close.to_string(),
+                body: Box::new(matrix),
+            }
+        }
+    }
+}
+
+/// Collapse a single-node row to the node itself.
+fn group(mut nodes: Vec<MathNode>) -> MathNode {
+    if nodes.len() == 1 {
+        nodes.pop().unwrap()
+    } else {
+        MathNode::Row(nodes)
+    }
+}
+
+/// Attach a sub- or superscript to `base`, filling an existing empty slot.
+fn attach_script(base: MathNode, is_sup: bool, script: MathNode) -> MathNode {
+    match base {
+        MathNode::Scripts {
+            base,
+            sub,
+            sup: None,
+        } if is_sup => MathNode::Scripts {
+            base,
+            sub,
+            sup: Some(Box::new(script)),
+        },
+        MathNode::Scripts {
+            base,
+            sub: None,
+            sup,
+        } if !is_sup => MathNode::Scripts {
+            base,
+            sub: Some(Box::new(script)),
+            sup,
+        },
+        base => {
+            let script = Some(Box::new(script));
+            let (sub, sup) = if is_sup {
+                (None, script)
+            } else {
+                (script, None)
+            };
+            MathNode::Scripts {
+                base: Box::new(base),
+                sub,
+                sup,
+            }
+        }
+    }
+}
+
+/// Operator node for a plain character.
+fn char_operator(c: char) -> MathNode {
+    use OperatorKind::*;
+    let (symbol, kind) = match c {
+        '+' => ("+", Binary),
+        '-' => ("−", Binary),
+        '*' => ("∗", Binary),
+        '=' => ("=", Relation),
+        '<' => ("<", Relation),
+        '>' => (">", Relation),
+        ':' => (":", Relation),
+        ',' => (",", Punctuation),
+        ';' => (";", Punctuation),
+        '(' | ')' | '[' | ']' | '|' => return MathNode::operator(&c.to_string(), Fence),
+        _ => return MathNode::operator(&c.to_string(), Ordinary),
+    };
+    MathNode::operator(symbol, kind)
+}
+
+/// Double-struck letter for `\mathbb`.
+fn double_struck(c: char) -> char {
+    match c {
+        'C' => 'ℂ',
+        'H' => 'ℍ',
+        'N' => 'ℕ',
+        'P' => 'ℙ',
+        'Q' => 'ℚ',
+        'R' => 'ℝ',
+        'Z' => 'ℤ',
+        c => c,
+    }
+}
+
+/// Node for a symbol command (`\alpha`, `\leq`, `\sum`, `\sin`, ...).
+fn symbol(name: &str) -> Option<MathNode> {
+    use OperatorKind::*;
+
+    let greek = match name {
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" => "ϵ",
+        "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" => "θ",
+        "vartheta" => "ϑ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "pi" => "π",
+        "varpi" => "ϖ",
+        "rho" => "ρ",
+        "varrho" => "ϱ",
+        "sigma" => "σ",
+        "varsigma" => "ς",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" => "ϕ",
+        "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        _ => "",
+    };
+    if !greek.is_empty() {
+        return Some(MathNode::Ident(greek.to_string()));
+    }
+
+    let upright = match name {
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Upsilon" => "Υ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        "sin" | "cos" | "tan" | "cot" | "sec" | "csc" | "arcsin" | "arccos" | "arctan" | "sinh"
+        | "cosh" | "tanh" | "coth" | "log" | "ln" | "lg" | "exp" | "dim" | "ker" | "deg"
+        | "arg" | "hom" => name,
+        _ => "",
+    };
+    if !upright.is_empty() {
+        return Some(MathNode::Function(upright.to_string()));
+    }
+
+    if matches!(
+        name,
+        "lim" | "liminf" | "limsup" | "max" | "min" | "sup" | "inf" | "det" | "gcd" | "Pr"
+    ) {
+        let symbol = match name {
+            "liminf" => "lim inf",
+            "limsup" => "lim sup",
+            _ => name,
+        };
+        return Some(MathNode::operator(symbol, Limits));
+    }
+
+    let (symbol, kind) = match name {
+        // Big operators
+        "sum" => ("∑", Large { limits: true }),
+        "prod" => ("∏", Large { limits: true }),
+        "coprod" => ("∐", Large { limits: true }),
+        "bigcup" => ("⋃", Large { limits: true }),
+        "bigcap" => ("⋂", Large { limits: true }),
+        "bigoplus" => ("⨁", Large { limits: true }),
+        "bigotimes" => ("⨂", Large { limits: true }),
+        "int" => ("∫", Large { limits: false }),
+        "iint" => ("∬", Large { limits: false }),
+        "iiint" => ("∭", Large { limits: false }),
+        "oint" => ("∮", Large { limits: false }),
+
+        // Binary operators
+        "pm" => ("±", Binary),
+        "mp" => ("∓", Binary),
+        "times" => ("×", Binary),
+        "div" => ("÷", Binary),
+        "cdot" => ("⋅", Binary),
+        "ast" => ("∗", Binary),
+        "star" => ("⋆", Binary),
+        "circ" => ("∘", Binary),
+        "bullet" => ("∙", Binary),
+        "oplus" => ("⊕", Binary),
+        "ominus" => ("⊖", Binary),
+        "otimes" => ("⊗", Binary),
+        "cup" => ("∪", Binary),
+        "cap" => ("∩", Binary),
+        "setminus" => ("∖", Binary),
+        "wedge" | "land" => ("∧", Binary),
+        "vee" | "lor" => ("∨", Binary),
+        "bmod" | "mod" => ("mod", Binary),
+
+        // Relations and arrows
+        "leq" | "le" => ("≤", Relation),
+        "geq" | "ge" => ("≥", Relation),
+        "neq" | "ne" => ("≠", Relation),
+        "approx" => ("≈", Relation),
+        "equiv" => ("≡", Relation),
+        "sim" => ("∼", Relation),
+        "simeq" => ("≃", Relation),
+        "cong" => ("≅", Relation),
+        "propto" => ("∝", Relation),
+        "ll" => ("≪", Relation),
+        "gg" => ("≫", Relation),
+        "in" => ("∈", Relation),
+        "notin" => ("∉", Relation),
+        "ni" => ("∋", Relation),
+        "subset" => ("⊂", Relation),
+        "subseteq" => ("⊆", Relation),
+        "supset" => ("⊃", Relation),
+        "supseteq" => ("⊇", Relation),
+        "perp" => ("⊥", Relation),
+        "parallel" => ("∥", Relation),
+        "mid" => ("∣", Relation),
+        "models" => ("⊨", Relation),
+        "vdash" => ("⊢", Relation),
+        "to" | "rightarrow" => ("→", Relation),
+        "leftarrow" | "gets" => ("←", Relation),
+        "leftrightarrow" => ("↔", Relation),
+        "Rightarrow" => ("⇒", Relation),
+        "Leftarrow" => ("⇐", Relation),
+        "Leftrightarrow" => ("⇔", Relation),
+        "implies" | "Longrightarrow" => ("⟹", Relation),
+        "impliedby" | "Longleftarrow" => ("⟸", Relation),
+        "iff" | "Longleftrightarrow" => ("⟺", Relation),
+        "longrightarrow" => ("⟶", Relation),
+        "mapsto" => ("↦", Relation),
+        "uparrow" => ("↑", Relation),
+        "downarrow" => ("↓", Relation),
+
+        // Fences
+        "langle" => ("⟨", Fence),
+        "rangle" => ("⟩", Fence),
+        "lfloor" => ("⌊", Fence),
+        "rfloor" => ("⌋", Fence),
+        "lceil" => ("⌈", Fence),
+        "rceil" => ("⌉", Fence),
+        "vert" | "lvert" | "rvert" => ("|", Fence),
+        "Vert" | "lVert" | "rVert" => ("‖", Fence),
+
+        // Ordinary symbols
+        "infty" => ("∞", Ordinary),
+        "partial" => ("∂", Ordinary),
+        "nabla" => ("∇", Ordinary),
+        "forall" => ("∀", Ordinary),
+        "exists" => ("∃", Ordinary),
+        "nexists" => ("∄", Ordinary),
+        "emptyset" | "varnothing" => ("∅", Ordinary),
+        "neg" | "lnot" => ("¬", Ordinary),
+        "angle" => ("∠", Ordinary),
+        "triangle" => ("△", Ordinary),
+        "hbar" => ("ℏ", Ordinary),
+        "ell" => ("ℓ", Ordinary),
+        "Re" => ("ℜ", Ordinary),
+        "Im" => ("ℑ", Ordinary),
+        "aleph" => ("ℵ", Ordinary),
+        "prime" => ("′", Ordinary),
+        "degree" => ("°", Ordinary),
+        "cdots" => ("⋯", Ordinary),
+        "ldots" | "dots" => ("…", Ordinary),
+        "vdots" => ("⋮", Ordinary),
+        "ddots" => ("⋱", Ordinary),
+        _ => return None,
+    };
+    Some(MathNode::operator(symbol, kind))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// MathML Output
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Convert a LaTeX math expression to a self-contained MathML `<math>` element.
+///
+/// The LaTeX source is kept as an annotation so it survives copy and paste.
+pub fn to_mathml(latex: &str, display: bool) -> String {
+    let mut body = String::new();
+    write_mathml(&parse(latex), &mut body);
+    format!(
+        "<math xmlns=\"http://www.w3.org/1998/Math/MathML\" display=\"{}\"><semantics>{}<annotation encoding=\"application/x-tex\">{}</annotation></semantics></math>",
+        if display { "block" } else { "inline" },
+        body,
+        html_escape(latex)
+    )
+}
+
+fn write_mathml(node: &MathNode, out: &mut String) {
+    match node {
+        MathNode::Row(nodes) => {
+            out.push_str("<mrow>");
+            for node in nodes {
+                write_mathml(node, out);
+            }
+            out.push_str("</mrow>");
+        }
+        MathNode::Ident(name) => write_element(out, "mi", "", name),
+        MathNode::Function(name) if name.chars().count() == 1 => {
+            write_element(out, "mi", " mathvariant=\"normal\"", name)
+        }
+        MathNode::Function(name) => write_element(out, "mi", "", name),
+        MathNode::Number(number) => write_element(out, "mn", "", number),
+        MathNode::Text(text) => write_element(out, "mtext", "", text),
+        MathNode::Operator { symbol, kind } => {
+            let attributes = match kind {
+                OperatorKind::Fence => " stretchy=\"false\"",
+                OperatorKind::Limits => " movablelimits=\"true\" form=\"prefix\"",
+                OperatorKind::Large { limits: true } => " movablelimits=\"true\"",
+                _ => "",
+            };
+            write_element(out, "mo", attributes, symbol);
+        }
+        MathNode::Fraction {
+            numerator,
+            denominator,
+            bar,
+        } => {
+            out.push_str(if *bar {
+                "<mfrac>"
+            } else {
+                "<mfrac linethickness=\"0\">"
+            });
+            write_mathml(numerator, out);
+            write_mathml(denominator, out);
+            out.push_str("</mfrac>");
+        }
+        MathNode::Root { radicand, index } => match index {
+            Some(index) => {
+                out.push_str("<mroot>");
+                write_mathml(radicand, out);
+                write_mathml(index, out);
+                out.push_str("</mroot>");
+            }
+            None => {
+                out.push_str("<msqrt>");
+                write_mathml(radicand, out);
+                out.push_str("</msqrt>");
+            }
+        },
+        MathNode::Scripts { base, sub, sup } => {
+            let limits = matches!(
+                **base,
+                MathNode::Operator {
+                    kind: OperatorKind::Large { limits: true } | OperatorKind::Limits,
+                    ..
+                }
+            );
+            let tag = match (sub.is_some(), sup.is_some(), limits) {
+                (true, true, false) => "msubsup",
+                (true, false, false) => "msub",
+                (false, _, false) => "msup",
+                (true, true, true) => "munderover",
+                (true, false, true) => "munder",
+                (false, _, true) => "mover",
+            };
+            out.push_str(&format!("<{}>", tag));
+            write_mathml(base, out);
+            for script in [sub, sup].into_iter().flatten() {
+                write_mathml(script, out);
+            }
+            out.push_str(&format!("</{}>", tag));
+        }
+        MathNode::Accent { base, accent } => {
+            let symbol = match accent {
+                Accent::Hat => "^",
+                Accent::Bar => "‾",
+                Accent::Vec => "→",
+                Accent::Dot => "˙",
+                Accent::DoubleDot => "¨",
+                Accent::Tilde => "~",
+            };
+            out.push_str("<mover accent=\"true\">");
+            write_mathml(base, out);
+            write_element(out, "mo", "", symbol);
+            out.push_str("</mover>");
+        }
+        MathNode::Fenced { open, close, body } => {
+            out.push_str("<mrow>");
+            if !open.is_empty() {
+                write_element(out, "mo", " fence=\"true\" stretchy=\"true\"", open);
+            }
+            write_mathml(body, out);
+            if !close.is_empty() {
+                write_element(out, "mo", " fence=\"true\" stretchy=\"true\"", close);
+            }
+            out.push_str("</mrow>");
+        }
+        MathNode::Matrix { rows, align } => {
+            out.push_str("<mtable>");
+            for row in rows {
+                out.push_str("<mtr>");
+                for (column, cell) in row.iter().enumerate() {
+                    let cell_align = match align {
+                        MatrixAlign::Center => "",
+                        MatrixAlign::Left => " columnalign=\"left\"",
+                        MatrixAlign::Alternating if column % 2 == 0 => " columnalign=\"right\"",
+                        MatrixAlign::Alternating => " columnalign=\"left\"",
+                    };
+                    out.push_str(&format!("<mtd{}>", cell_align));
+                    write_mathml(cell, out);
+                    out.push_str("</mtd>");
+                }
+                out.push_str("</mtr>");
+            }
+            out.push_str("</mtable>");
+        }
+        MathNode::Space(width) => {
+            out.push_str(&format!("<mspace width=\"{:.3}em\"/>", width));
+        }
+    }
+}
+
+fn write_element(out: &mut String, tag: &str, attributes: &str, text: &str) {
+    out.push_str(&format!(
+        "<{}{}>{}</{}>",
+        tag,
+        attributes,
+        html_escape(text),
+        tag
+    ));
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Native Layout
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Height of the math axis (fraction bars, centered operators) above the baseline, in em.
+const AXIS: f32 = 0.25;
+
+/// Smallest font size used for nested scripts.
+const MIN_FONT_SIZE: f32 = 6.0;
+
+/// Something to draw, positioned relative to a box's origin (left edge, on the baseline).
+#[derive(Clone)]
+enum MathItem {
+    /// Text galley with its top-left corner at `pos`
+    Text { pos: Pos2, galley: Arc<Galley> },
+    /// Filled rectangle (fraction bars)
+    Rule(Rect),
+    /// Stroked polyline (radicals, delimiters, accents)
+    Line { points: Vec<Pos2>, width: f32 },
+    /// Filled dot (dot accents)
+    Dot { center: Pos2, radius: f32 },
+}
+
+impl MathItem {
+    fn translate(&mut self, offset: egui::Vec2) {
+        match self {
+            MathItem::Text { pos, .. } => *pos += offset,
+            MathItem::Rule(rect) => *rect = rect.translate(offset),
+            MathItem::Line { points, .. } => points.iter_mut().for_each(|p| *p += offset),
+            MathItem::Dot { center, .. } => *center += offset,
+        }
+    }
+}
+
+/// A laid-out expression: its metrics around the baseline and what to draw.
+#[derive(Clone, Default)]
+pub struct MathBox {
+    /// Total advance width
+    pub width: f32,
+    /// Extent above the baseline
+    pub ascent: f32,
+    /// Extent below the baseline
+    pub descent: f32,
+    items: Vec<MathItem>,
+}
+
+impl MathBox {
+    /// Total height of the box.
+    pub fn height(&self) -> f32 {
+        self.ascent + self.descent
+    }
+
+    /// Add another box's items with its origin at (`x`, `y`) in this box.
+    fn place(&mut self, other: MathBox, x: f32, y: f32) {
+        for mut item in other.items {
+            item.translate(vec2(x, y));
+            self.items.push(item);
+        }
+    }
+
+    /// Paint the box with its origin (left edge, baseline) at `origin`.
+    pub fn paint(&self, painter: &Painter, origin: Pos2, color: Color32) {
+        let offset = origin.to_vec2();
+        for item in &self.items {
+            match item {
+                MathItem::Text { pos, galley } => {
+                    painter.galley(*pos + offset, galley.clone(), color);
+                }
+                MathItem::Rule(rect) => {
+                    painter.rect_filled(rect.translate(offset), 0.0, color);
+                }
+                MathItem::Line { points, width } => {
+                    let points = points.iter().map(|p| *p + offset).collect();
+                    painter.add(Shape::line(points, Stroke::new(*width, color)));
+                }
+                MathItem::Dot { center, radius } => {
+                    painter.circle_filled(*center + offset, *radius, color);
+                }
+            }
+        }
+    }
+}
+
+/// Spacing class of a node, following TeX's atom types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Ord,
+    Op,
+    Bin,
+    Rel,
+    Open,
+    Close,
+    Punct,
+}
+
+fn class_of(node: &MathNode) -> Class {
+    match node {
+        MathNode::Operator { symbol, kind } => match kind {
+            OperatorKind::Binary => Class::Bin,
+            OperatorKind::Relation => Class::Rel,
+            OperatorKind::Punctuation => Class::Punct,
+            OperatorKind::Large { .. } | OperatorKind::Limits => Class::Op,
+            OperatorKind::Fence if "([{⟨⌊⌈".contains(symbol.as_str()) => Class::Open,
+            OperatorKind::Fence if ")]}⟩⌋⌉".contains(symbol.as_str()) => Class::Close,
+            OperatorKind::Fence | OperatorKind::Ordinary => Class::Ord,
+        },
+        MathNode::Function(name) if name.chars().count() > 1 => Class::Op,
+        MathNode::Scripts { base, .. } => match class_of(base) {
+            Class::Bin => Class::Ord,
+            class => class,
+        },
+        _ => Class::Ord,
+    }
+}
+
+/// Space between two adjacent atoms, in em.
+fn class_space(left: Class, right: Class) -> f32 {
+    use Class::*;
+    match (left, right) {
+        (Bin, _) | (_, Bin) => 4.0 / 18.0,
+        (Rel, Rel) | (Open, Rel) | (Rel, Close) => 0.0,
+        (Rel, _) | (_, Rel) => 5.0 / 18.0,
+        (Punct, _) => 3.0 / 18.0,
+        (Op, Ord) | (Ord, Op) | (Close, Op) | (Op, Op) => 3.0 / 18.0,
+        _ => 0.0,
+    }
+}
+
+/// Spacing classes of a row, with binary operators that have no left
+/// operand (unary minus) or no right operand treated as ordinary.
+fn row_classes(nodes: &[MathNode]) -> Vec<Class> {
+    let mut classes: Vec<Class> = nodes.iter().map(class_of).collect();
+    for i in 0..classes.len() {
+        if classes[i] != Class::Bin {
+            continue;
+        }
+        let prev = i.checked_sub(1).map(|p| classes[p]);
+        let next = classes.get(i + 1).copied();
+        let no_left = matches!(
+            prev,
+            None | Some(Class::Bin | Class::Op | Class::Rel | Class::Open | Class::Punct)
+        );
+        let no_right = matches!(next, None | Some(Class::Rel | Class::Close | Class::Punct));
+        if no_left || no_right {
+            classes[i] = Class::Ord;
+        }
+    }
+    classes
+}
+
+/// Lays out math nodes using the fonts of an egui context.
+pub struct MathLayouter<'a> {
+    fonts: &'a egui::text::Fonts,
+    color: Color32,
+}
+
+impl<'a> MathLayouter<'a> {
+    /// Create a layouter that draws text in `color`.
+    pub fn new(fonts: &'a egui::text::Fonts, color: Color32) -> Self {
+        Self { fonts, color }
+    }
+
+    /// Lay out `node` at `size` points; `display` selects display style
+    /// (full-size fractions, limits above and below big operators).
+    pub fn layout(&self, node: &MathNode, size: f32, display: bool) -> MathBox {
+        match node {
+            MathNode::Row(nodes) => self.row(nodes, size, display),
+            MathNode::Ident(name) => {
+                let italic = name
+                    .chars()
+                    .all(|c| c.is_ascii_alphabetic() || ('\u{0370}'..='\u{03FF}').contains(&c));
+                self.text(name, size, italic)
+            }
+            MathNode::Function(name) | MathNode::Number(name) | MathNode::Text(name) => {
+                self.text(name, size, false)
+            }
+            MathNode::Operator { symbol, kind } => match kind {
+                OperatorKind::Large { .. } => self.large_operator(symbol, size, display),
+                _ => self.text(symbol, size, false),
+            },
+            MathNode::Fraction {
+                numerator,
+                denominator,
+                bar,
+            } => self.fraction(numerator, denominator, *bar, size, display),
+            MathNode::Root { radicand, index } => self.root(radicand, index.as_deref(), size),
+            MathNode::Scripts { base, sub, sup } => {
+                self.scripts(base, sub.as_deref(), sup.as_deref(), size, display)
+            }
+            MathNode::Accent { base, accent } => self.accent(base, *accent, size, display),
+            MathNode::Fenced { open, close, body } => self.fenced(open, close, body, size, display),
+            MathNode::Matrix { rows, align } => self.matrix(rows, *align, size),
+            MathNode::Space(em) => MathBox {
+                width: em * size,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn text(&self, text: &str, size: f32, italic: bool) -> MathBox {
+        let family = if italic {
+            FontFamily::Name(FONT_INTER_ITALIC.into())
+        } else {
+            FontFamily::Proportional
+        };
+        let galley =
+            self.fonts
+                .layout_no_wrap(text.to_string(), FontId::new(size, family), self.color);
+        let baseline = galley
+            .rows
+            .first()
+            .and_then(|row| row.glyphs.first())
+            .map_or(size * 0.8, |glyph| glyph.pos.y);
+        MathBox {
+            width: galley.size().x,
+            ascent: size * 0.72,
+            descent: size * 0.22,
+            items: vec![MathItem::Text {
+                pos: pos2(0.0, -baseline),
+                galley,
+            }],
+        }
+    }
+
+    fn row(&self, nodes: &[MathNode], size: f32, display: bool) -> MathBox {
+        let classes = row_classes(nodes);
+        let mut row = MathBox::default();
+        for (i, node) in nodes.iter().enumerate() {
+            if i > 0 {
+                row.width += class_space(classes[i - 1], classes[i]) * size;
+            }
+            let child = self.layout(node, size, display);
+            row.ascent = row.ascent.max(child.ascent);
+            row.descent = row.descent.max(child.descent);
+            let x = row.width;
+            row.width += child.width;
+            row.place(child, x, 0.0);
+        }
+        row.width = row.width.max(0.0);
+        row
+    }
+
+    fn large_operator(&self, symbol: &str, size: f32, display: bool) -> MathBox {
+        let big = size * if display { 1.6 } else { 1.2 };
+        let glyph = self.text(symbol, big, false);
+        // Center the glyph's ink (roughly 0.75em above to 0.05em below its
+        // baseline) on the math axis
+        let shift = 0.35 * big - AXIS * size;
+        let mut operator = MathBox {
+            width: glyph.width + 0.1 * size,
+            ascent: 0.75 * big - shift,
+            descent: 0.05 * big + shift,
+            items: Vec::new(),
+        };
+        operator.place(glyph, 0.05 * size, shift);
+        operator
+    }
+
+    fn fraction(
+        &self,
+        numerator: &MathNode,
+        denominator: &MathNode,
+        bar: bool,
+        size: f32,
+        display: bool,
+    ) -> MathBox {
+        let part_size = if display {
+            size
+        } else {
+            (size * 0.8).max(MIN_FONT_SIZE)
+        };
+        let num = self.layout(numerator, part_size, false);
+        let den = self.layout(denominator, part_size, false);
+
+        let thickness = (size * 0.05).max(1.0);
+        let gap = size * 0.15;
+        let padding = size * 0.1;
+        let axis = AXIS * size;
+        let width = num.width.max(den.width) + 2.0 * padding;
+
+        let num_baseline = -(axis + thickness / 2.0 + gap + num.descent);
+        let den_baseline = -axis + thickness / 2.0 + gap + den.ascent;
+
+        let mut fraction = MathBox {
+            width,
+            ascent: -num_baseline + num.ascent,
+            descent: den_baseline + den.descent,
+            items: Vec::new(),
+        };
+        if bar {
+            fraction.items.push(MathItem::Rule(Rect::from_min_max(
+                pos2(padding * 0.5, -axis - thickness / 2.0),
+                pos2(width - padding * 0.5, -axis + thickness / 2.0),
+            )));
+        }
+        let num_x = (width - num.width) / 2.0;
+        let den_x = (width - den.width) / 2.0;
+        fraction.place(num, num_x, num_baseline);
+        fraction.place(den, den_x, den_baseline);
+        fraction
+    }
+
+    fn root(&self, radicand: &MathNode, index: Option<&MathNode>, size: f32) -> MathBox {
+        let body = self.layout(radicand, size, false);
+        let thickness = (size * 0.05).max(1.0);
+        let gap = size * 0.12;
+        let sign_width = size * 0.6;
+
+        let top = -(body.ascent + gap);
+        let bottom = body.descent;
+        let tick = bottom - (bottom - top) * 0.45;
+
+        let index = index.map(|index| self.layout(index, (size * 0.55).max(MIN_FONT_SIZE), false));
+        let indent = index
+            .as_ref()
+            .map_or(0.0, |index| (index.width - size * 0.2).max(0.0));
+
+        let mut root = MathBox {
+            width: indent + sign_width + body.width + size * 0.1,
+            ascent: -top + thickness,
+            descent: bottom,
+            items: vec![MathItem::Line {
+                points: vec![
+                    pos2(indent, tick + size * 0.08),
+                    pos2(indent + size * 0.15, tick),
+                    pos2(indent + size * 0.32, bottom),
+                    pos2(indent + size * 0.55, top),
+                    pos2(indent + sign_width + body.width + size * 0.1, top),
+                ],
+                width: thickness,
+            }],
+        };
+        if let Some(index) = index {
+            let index_baseline = tick - size * 0.1 - index.descent;
+            root.ascent = root.ascent.max(index.ascent - index_baseline);
+            root.place(index, 0.0, index_baseline);
+        }
+        root.place(body, indent + sign_width, 0.0);
+        root
+    }
+
+    fn scripts(
+        &self,
+        base: &MathNode,
+        sub: Option<&MathNode>,
+        sup: Option<&MathNode>,
+        size: f32,
+        display: bool,
+    ) -> MathBox {
+        let script_size = (size * 0.7).max(MIN_FONT_SIZE);
+        let base_box = self.layout(base, size, display);
+        let sub = sub.map(|sub| self.layout(sub, script_size, false));
+        let sup = sup.map(|sup| self.layout(sup, script_size, false));
+
+        let limits = display
+            && matches!(
+                base,
+                MathNode::Operator {
+                    kind: OperatorKind::Large { limits: true } | OperatorKind::Limits,
+                    ..
+                }
+            );
+        if limits {
+            return self.limits(base_box, sub, sup, size);
+        }
+
+        let mut sup_shift = (base_box.ascent - 0.3 * size).max(0.38 * size);
+        let mut sub_shift = (base_box.descent + 0.05 * size).max(0.2 * size);
+        if let (Some(sub), Some(sup)) = (&sub, &sup) {
+            // Keep the scripts from colliding
+            let clearance = (sub_shift - sub.ascent) - (sup.descent - sup_shift);
+            let min_gap = 0.1 * size;
+            if clearance < min_gap {
+                sup_shift += (min_gap - clearance) / 2.0;
+                sub_shift += (min_gap - clearance) / 2.0;
+            }
+        }
+        if sup.is_none() {
+            sub_shift = sub_shift.min(0.25 * size).max(base_box.descent * 0.5);
+        }
+
+        let x = base_box.width + 0.05 * size;
+        let mut scripts = MathBox {
+            width: base_box.width,
+            ascent: base_box.ascent,
+            descent: base_box.descent,
+            items: Vec::new(),
+        };
+        scripts.place(base_box, 0.0, 0.0);
+        if let Some(sup) = sup {
+            scripts.ascent = scripts.ascent.max(sup_shift + sup.ascent);
+            scripts.descent = scripts.descent.max(sup.descent - sup_shift);
+            scripts.width = scripts.width.max(x + sup.width);
+            scripts.place(sup, x, -sup_shift);
+        }
+        if let Some(sub) = sub {
+            scripts.ascent = scripts.ascent.max(sub.ascent - sub_shift);
+            scripts.descent = scripts.descent.max(sub_shift + sub.descent);
+            scripts.width = scripts.width.max(x + sub.width);
+            scripts.place(sub, x, sub_shift);
+        }
+        scripts
+    }
+
+    /// Scripts of a big operator in display math, centered above and below it.
+    fn limits(
+        &self,
+        base: MathBox,
+        under: Option<MathBox>,
+        over: Option<MathBox>,
+        size: f32,
+    ) -> MathBox {
+        let gap = 0.1 * size;
+        let width = [Some(&base), under.as_ref(), over.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(|b| b.width)
+            .fold(0.0, f32::max);
+        let mut limits = MathBox {
+            width,
+            ascent: base.ascent,
+            descent: base.descent,
+            items: Vec::new(),
+        };
+        if let Some(over) = over {
+            let baseline = -(base.ascent + gap + over.descent);
+            limits.ascent = -baseline + over.ascent;
+            limits.place(over.clone(), (width - over.width) / 2.0, baseline);
+        }
+        if let Some(under) = under {
+            let baseline = base.descent + gap + under.ascent;
+            limits.descent = baseline + under.descent;
+            limits.place(under.clone(), (width - under.width) / 2.0, baseline);
+        }
+        let base_x = (width - base.width) / 2.0;
+        limits.place(base, base_x, 0.0);
+        limits
+    }
+
+    fn accent(&self, base: &MathNode, accent: Accent, size: f32, display: bool) -> MathBox {
+        let body = self.layout(base, size, display);
+        let thickness = (size * 0.05).max(1.0);
+        let center = body.width / 2.0 + 0.05 * size;
+        let bottom = -(body.ascent + 0.08 * size);
+        let top = bottom - 0.18 * size;
+        let middle = (top + bottom) / 2.0;
+        let half = (body.width / 2.0).clamp(0.2 * size, 0.5 * size);
+
+        let line = |points: Vec<Pos2>| MathItem::Line {
+            points,
+            width: thickness,
+        };
+        let dot = |x: f32| MathItem::Dot {
+            center: pos2(x, middle),
+            radius: thickness * 1.2,
+        };
+        let items = match accent {
+            Accent::Hat => vec![line(vec![
+                pos2(center - half * 0.6, bottom),
+                pos2(center, top),
+                pos2(center + half * 0.6, bottom),
+            ])],
+            Accent::Bar => vec![line(vec![
+                pos2(center - half, middle),
+                pos2(center + half, middle),
+            ])],
+            Accent::Vec => vec![
+                line(vec![
+                    pos2(center - half, middle),
+                    pos2(center + half, middle),
+                ]),
+                line(vec![
+                    pos2(center + half - 0.12 * size, top),
+                    pos2(center + half, middle),
+                    pos2(center + half - 0.12 * size, bottom),
+                ]),
+            ],
+            Accent::Dot => vec![dot(center)],
+            Accent::DoubleDot => vec![dot(center - 0.12 * size), dot(center + 0.12 * size)],
+            Accent::Tilde => vec![line(
+                (0..=12)
+                    .map(|i| {
+                        let t = i as f32 / 12.0;
+                        let y = middle - (t * std::f32::consts::TAU).sin() * 0.06 * size;
+                        pos2(center - half * 0.7 + t * half * 1.4, y)
+                    })
+                    .collect(),
+            )],
+        };
+
+        let mut accented = MathBox {
+            width: body.width,
+            ascent: -top + thickness,
+            descent: body.descent,
+            items,
+        };
+        accented.place(body, 0.0, 0.0);
+        accented
+    }
+
+    fn fenced(
+        &self,
+        open: &str,
+        close: &str,
+        body: &MathNode,
+        size: f32,
+        display: bool,
+    ) -> MathBox {
+        let body = self.layout(body, size, display);
+        let axis = AXIS * size;
+        // Delimiters are symmetric around the axis and at least one line tall
+        let half = (body.ascent - axis)
+            .max(body.descent + axis)
+            .max(0.5 * size)
+            + 0.1 * size;
+        let top = -axis - half;
+        let bottom = -axis + half;
+        let thickness = (size * 0.05).max(1.0);
+
+        let open_width = delimiter_width(open, size);
+        let close_width = delimiter_width(close, size);
+        let mut fenced = MathBox {
+            width: open_width + body.width + close_width,
+            ascent: body.ascent.max(-top),
+            descent: body.descent.max(bottom),
+            items: Vec::new(),
+        };
+        for points in delimiter_lines(open, open_width, top, bottom, false) {
+            fenced.items.push(MathItem::Line {
+                points,
+                width: thickness,
+            });
+        }
+        let close_x = open_width + body.width;
+        for mut points in delimiter_lines(close, close_width, top, bottom, true) {
+            points.iter_mut().for_each(|p| p.x += close_x);
+            fenced.items.push(MathItem::Line {
+                points,
+                width: thickness,
+            });
+        }
+        fenced.place(body, open_width, 0.0);
+        fenced
+    }
+
+    fn matrix(&self, rows: &[Vec<MathNode>], align: MatrixAlign, size: f32) -> MathBox {
+        let cells: Vec<Vec<MathBox>> = rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| self.layout(cell, size, false))
+                    .collect()
+            })
+            .collect();
+        let columns = cells.iter().map(Vec::len).max().unwrap_or(0);
+        let mut column_widths = vec![0.0f32; columns];
+        for row in &cells {
+            for (column, cell) in row.iter().enumerate() {
+                column_widths[column] = column_widths[column].max(cell.width);
+            }
+        }
+        let column_gap = match align {
+            MatrixAlign::Alternating => 0.0,
+            _ => 0.8 * size,
+        };
+        let row_gap = 0.25 * size;
+        let row_metrics: Vec<(f32, f32)> = cells
+            .iter()
+            .map(|row| {
+                row.iter().fold((0.72 * size, 0.22 * size), |(a, d), cell| {
+                    (a.max(cell.ascent), d.max(cell.descent))
+                })
+            })
+            .collect();
+        let height = row_metrics.iter().map(|(a, d)| a + d).sum::<f32>()
+            + row_gap * row_metrics.len().saturating_sub(1) as f32;
+        let width = column_widths.iter().sum::<f32>()
+            + column_gap * columns.saturating_sub(1) as f32
+            + 0.2 * size;
+
+        // Center the grid on the math axis
+        let top = -AXIS * size - height / 2.0;
+        let mut matrix = MathBox {
+            width,
+            ascent: -top,
+            descent: top + height,
+            items: Vec::new(),
+        };
+        let mut y = top;
+        for (row, (ascent, descent)) in cells.into_iter().zip(row_metrics) {
+            let baseline = y + ascent;
+            let mut x = 0.1 * size;
+            for (column, cell) in row.into_iter().enumerate() {
+                let free = column_widths[column] - cell.width;
+                let offset = match align {
+                    MatrixAlign::Center => free / 2.0,
+                    MatrixAlign::Left => 0.0,
+                    MatrixAlign::Alternating if column % 2 == 0 => free,
+                    MatrixAlign::Alternating => 0.0,
+                };
+                matrix.place(cell, x + offset, baseline);
+                x += column_widths[column] + column_gap;
+            }
+            y = baseline + descent + row_gap;
+        }
+        matrix
+    }
+}
+
+/// Horizontal space taken by a stretchy delimiter.
+fn delimiter_width(delimiter: &str, size: f32) -> f32 {
+    match delimiter {
+        "" => 0.0,
+        "|" => 0.25 * size,
+        _ => 0.4 * size,
+    }
+}
+
+/// Polylines drawing a delimiter spanning `top..bottom`; `closing`
+/// delimiters are drawn mirrored.
+fn delimiter_lines(
+    delimiter: &str,
+    width: f32,
+    top: f32,
+    bottom: f32,
+    closing: bool,
+) -> Vec<Vec<Pos2>> {
+    let middle = (top + bottom) / 2.0;
+    let (left, right) = (width * 0.3, width * 0.75);
+    let curve = |from: Pos2, control: Pos2, to: Pos2| -> Vec<Pos2> {
+        (0..=12)
+            .map(|i| {
+                let t = i as f32 / 12.0;
+                let u = 1.0 - t;
+                pos2(
+                    u * u * from.x + 2.0 * u * t * control.x + t * t * to.x,
+                    u * u * from.y + 2.0 * u * t * control.y + t * t * to.y,
+                )
+            })
+            .collect()
+    };
+
+    let lines = match delimiter {
+        "(" | ")" => vec![curve(
+            pos2(right, top),
+            pos2(width * 0.05, middle),
+            pos2(right, bottom),
+        )],
+        "[" | "]" => vec![vec![
+            pos2(right, top),
+            pos2(left, top),
+            pos2(left, bottom),
+            pos2(right, bottom),
+        ]],
+        "{" | "}" => {
+            let mut upper = curve(
+                pos2(right, top),
+                pos2(left, top),
+                pos2(left, top + 0.2 * (bottom - top)),
+            );
+            upper.extend(curve(
+                pos2(left, middle - 0.15 * (bottom - top)),
+                pos2(left, middle),
+                pos2(width * 0.1, middle),
+            ));
+            let mut lower = curve(
+                pos2(width * 0.1, middle),
+                pos2(left, middle),
+                pos2(left, middle + 0.15 * (bottom - top)),
+            );
+            lower.extend(curve(
+                pos2(left, bottom - 0.2 * (bottom - top)),
+                pos2(left, bottom),
+                pos2(right, bottom),
+            ));
+            vec![upper, lower]
+        }
+        "⟨" | "⟩" => vec![vec![
+            pos2(right, top),
+            pos2(left, middle),
+            pos2(right, bottom),
+        ]],
+        "⌊" | "⌋" => vec![vec![
+            pos2(left, top),
+            pos2(left, bottom),
+            pos2(right, bottom),
+        ]],
+        "⌈" | "⌉" => vec![vec![pos2(right, top), pos2(left, top), pos2(left, bottom)]],
+        "|" => vec![vec![pos2(width / 2.0, top), pos2(width / 2.0, bottom)]],
+        "‖" => vec![
+            vec![pos2(width * 0.35, top), pos2(width * 0.35, bottom)],
+            vec![pos2(width * 0.65, top), pos2(width * 0.65, bottom)],
+        ],
+        _ => Vec::new(),
+    };
+
+    if closing {
+        lines
+            .into_iter()
+            .map(|line| line.into_iter().map(|p| pos2(width - p.x, p.y)).collect())
+            .collect()
+    } else {
+        lines
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Widget
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Displays a LaTeX math expression laid out natively.
+///
+/// Hovering the formula shows its LaTeX source.
+pub struct MathView<'a> {
+    latex: &'a str,
+    display: bool,
+    font_size: f32,
+    color: Color32,
+}
+
+impl<'a> MathView<'a> {
+    /// Create a view for an inline math expression.
+    pub fn new(latex: &'a str) -> Self {
+        Self {
+            latex,
+            display: false,
+            font_size: 14.0,
+            color: Color32::GRAY,
+        }
+    }
+
+    /// Use display style (`$$...$$`).
+    #[must_use]
+    pub fn display(mut self, display: bool) -> Self {
+        self.display = display;
+        self
+    }
+
+    /// Set the base font size.
+    #[must_use]
+    pub fn font_size(mut self, size: f32) -> Self {
+        self.font_size = size;
+        self
+    }
+
+    /// Set the color used for text and lines.
+    #[must_use]
+    pub fn color(mut self, color: Color32) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Lay out and paint the expression.
+    pub fn show(self, ui: &mut Ui) -> Response {
+        let node = parse(self.latex);
+        let math = ui.fonts(|fonts| {
+            MathLayouter::new(fonts, self.color).layout(&node, self.font_size, self.display)
+        });
+        let (rect, response) =
+            ui.allocate_exact_size(vec2(math.width, math.height()), Sense::hover());
+        if ui.is_rect_visible(rect) {
+            let origin = pos2(rect.left(), rect.top() + math.ascent);
+            math.paint(ui.painter(), origin, self.color);
+        }
+        response.on_hover_text_at_pointer(self.latex)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn children(latex: &str) -> Vec<MathNode> {
+        match parse(latex) {
+            MathNode::Row(nodes) => nodes,
+            other => panic!("expected row, got {:?}", other),
+        }
+    }
+
+    /// Run `f` with the application's fonts loaded.
+    fn with_fonts<R>(f: impl FnOnce(&egui::text::Fonts) -> R) -> R {
+        let ctx = egui::Context::default();
+        ctx.set_fonts(crate::fonts::create_font_definitions());
+        let _ = ctx.run(Default::default(), |_| {});
+        let mut result = None;
+        let _ = ctx.run(Default::default(), |ctx| {
+            result = Some(ctx.fonts(f));
+        });
+        result.unwrap()
+    }
+
+    fn layout(latex: &str, display: bool) -> MathBox {
+        let node = parse(latex);
+        with_fonts(|fonts| MathLayouter::new(fonts, Color32::WHITE).layout(&node, 16.0, display))
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Parser Tests
+    // ─────────────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_scripts_and_numbers() {
+        let nodes = children("x_i^2 + 10");
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(
+            nodes[0],
+            MathNode::Scripts {
+                base: Box::new(MathNode::Ident("x".into())),
+                sub: Some(Box::new(MathNode::Ident("i".into()))),
+                sup: Some(Box::new(MathNode::Number("2".into()))),
+            }
+        );
+        assert_eq!(nodes[1], MathNode::operator("+", OperatorKind::Binary));
+        assert_eq!(nodes[2], MathNode::Number("10".into()));
+
+        // A script without braces takes a single digit
+        let nodes = children("x^23");
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[1], MathNode::Number("3".into()));
+    }
+
+    #[test]
+    fn test_parse_fraction_root_and_symbols() {
+        let nodes = children(r"\frac{\alpha}{2} \leq \sqrt[3]{x} \sin\theta");
+        assert!(matches!(
+            &nodes[0],
+            MathNode::Fraction { numerator, bar: true, .. }
+                if **numerator == MathNode::Ident("α".into())
+        ));
+        assert_eq!(nodes[1], MathNode::operator("≤", OperatorKind::Relation));
+        assert!(matches!(&nodes[2], MathNode::Root { index: Some(_), .. }));
+        assert_eq!(nodes[3], MathNode::Function("sin".into()));
+        assert_eq!(nodes[4], MathNode::Ident("θ".into()));
+    }
+
+    #[test]
+    fn test_parse_environments_and_fences() {
+        let nodes = children(r"\begin{pmatrix} a & b \\ c & d \\ \end{pmatrix}");
+        let MathNode::Fenced { open, close, body } = &nodes[0] else {
+            panic!("expected fenced matrix, got {:?}", nodes);
+        };
+        assert_eq!((open.as_str(), close.as_str()), ("(", ")"));
+        let MathNode::Matrix { rows, .. } = body.as_ref() else {
+            panic!("expected matrix");
+        };
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[1],
+            vec![MathNode::Ident("c".into()), MathNode::Ident("d".into())]
+        );
+
+        let nodes = children(r"\left\{ x \right.");
+        assert!(matches!(
+            &nodes[0],
+            MathNode::Fenced { open, close, .. } if open == "{" && close.is_empty()
+        ));
+    }
+
+    #[test]
+    fn test_parse_tolerates_malformed_input() {
+        for latex in [
+            r"\frac{a}",
+            "x^",
+            "}{",
+            r"\left(",
+            r"\begin{matrix} a &",
+            r"\unknown",
+            "\\",
+        ] {
+            let _ = parse(latex);
+        }
+        assert_eq!(children(r"\foo"), vec![MathNode::Text(r"\foo".into())]);
+        assert_eq!(
+            children(r"\text{if } x"),
+            vec![MathNode::Text("if ".into()), MathNode::Ident("x".into())]
+        );
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // MathML Tests
+    // ─────────────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_mathml_output() {
+        let html = to_mathml(r"\frac{a}{b} + x_i^2", true);
+        assert!(html
+            .starts_with("<math xmlns=\"http://www.w3.org/1998/Math/MathML\" display=\"block\">"));
+        assert!(html.contains("<mfrac><mi>a</mi><mi>b</mi></mfrac>"));
+        assert!(html.contains("<msubsup><mi>x</mi><mi>i</mi><mn>2</mn></msubsup>"));
+        assert!(html.contains(
+            "<annotation encoding=\"application/x-tex\">\\frac{a}{b} + x_i^2</annotation>"
+        ));
+
+        let html = to_mathml(r"\sum_{k=1}^n k < \infty", false);
+        assert!(html.contains("display=\"inline\""));
+        assert!(html.contains("<munderover><mo movablelimits=\"true\">∑</mo>"));
+        assert!(html.contains("<mo>&lt;</mo>"));
+
+        let html = to_mathml(r"\begin{bmatrix}1 & 0\\0 & 1\end{bmatrix}", true);
+        assert!(html.contains("<mtable><mtr><mtd><mn>1</mn></mtd><mtd><mn>0</mn></mtd></mtr>"));
+        assert!(html.contains("<mo fence=\"true\" stretchy=\"true\">[</mo>"));
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Layout Tests
+    // ─────────────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_layout_metrics() {
+        let plain = layout("x", false);
+        assert!(plain.width > 0.0);
+
+        // Superscripts raise the ascent, fractions extend both ways
+        let squared = layout("x^2", false);
+        assert!(squared.ascent > plain.ascent);
+        assert!(squared.width > plain.width);
+        let fraction = layout(r"\frac{x}{y}", false);
+        assert!(fraction.ascent > plain.ascent && fraction.descent > plain.descent);
+
+        // Display style uses larger fractions and big operators
+        assert!(layout(r"\frac{x}{y}", true).height() > fraction.height());
+        assert!(
+            layout(r"\sum_{i=1}^n i", true).height() > layout(r"\sum_{i=1}^n i", false).height()
+        );
+
+        // Matrix rows stack
+        let one_row = layout(r"\begin{pmatrix} a \end{pmatrix}", false);
+        let two_rows = layout(r"\begin{pmatrix} a \\ b \end{pmatrix}", false);
+        assert!(two_rows.height() > one_row.height() * 1.5);
+    }
+
+    #[test]
+    fn test_binary_operator_spacing() {
+        // Unary minus is not spaced like subtraction
+        let unary = row_classes(&children("-x"));
+        assert_eq!(unary[0], Class::Ord);
+        let binary = row_classes(&children("a - x"));
+        assert_eq!(binary[1], Class::Bin);
+        assert!(layout("a-x", false).width > layout("a", false).width + layout("-x", false).width);
+    }
+}
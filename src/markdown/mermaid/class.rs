@@ -0,0 +1,522 @@
+//! Class diagrams (`classDiagram`)
+//!
+//! Supports classes with member blocks or `Class : member` lines, generics
+//! (`Class~T~`), annotations (`<<interface>>`), and inheritance, composition,
+//! aggregation, association, dependency and realization relations with
+//! cardinalities and labels.
+
+use eframe::egui::{pos2, vec2, Pos2, Rect, Vec2};
+
+use super::layout::{self, Direction};
+use super::scene::{
+    label_size, text_width, Fill, Marker, Scene, TextAlign, FONT_SIZE, LINE_HEIGHT,
+};
+use super::MermaidError;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Model
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A class box.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Class {
+    pub id: String,
+    /// Generic parameters shown as `Name<T>`
+    pub generic: Option<String>,
+    pub annotation: Option<String>,
+    pub attributes: Vec<String>,
+    pub methods: Vec<String>,
+}
+
+impl Class {
+    fn title(&self) -> String {
+        match &self.generic {
+            Some(generic) => format!("{}<{}>", self.id, generic),
+            None => self.id.clone(),
+        }
+    }
+
+    fn add_member(&mut self, member: &str) {
+        let member = member.trim();
+        if member.is_empty() {
+            return;
+        }
+        if let Some(annotation) = member.strip_prefix("<<").and_then(|m| m.strip_suffix(">>")) {
+            self.annotation = Some(annotation.trim().to_string());
+        } else if member.contains('(') {
+            self.methods.push(generics(member));
+        } else {
+            self.attributes.push(generics(member));
+        }
+    }
+}
+
+/// A relation between two classes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relation {
+    pub from: usize,
+    pub to: usize,
+    pub start: Marker,
+    pub end: Marker,
+    pub dashed: bool,
+    pub label: Option<String>,
+    pub from_cardinality: Option<String>,
+    pub to_cardinality: Option<String>,
+}
+
+/// A parsed class diagram.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClassDiagram {
+    pub direction: Direction,
+    pub classes: Vec<Class>,
+    pub relations: Vec<Relation>,
+}
+
+impl ClassDiagram {
+    /// Index of class `name` (which may carry `~generic~`), creating it if needed.
+    fn class(&mut self, name: &str) -> usize {
+        let (id, generic) = split_generic(name);
+        let index = match self.classes.iter().position(|c| c.id == id) {
+            Some(index) => index,
+            None => {
+                self.classes.push(Class {
+                    id: id.to_string(),
+                    ..Class::default()
+                });
+                self.classes.len() - 1
+            }
+        };
+        if let Some(generic) = generic {
+            self.classes[index].generic = Some(generic);
+        }
+        index
+    }
+}
+
+/// Split `List~int~` into `("List", Some("int"))`.
+fn split_generic(name: &str) -> (&str, Option<String>) {
+    match name.split_once('~') {
+        Some((id, generic)) => (
+            id,
+            Some(generics(generic.strip_suffix('~').unwrap_or(generic))),
+        ),
+        None => (name, None),
+    }
+}
+
+/// Turn Mermaid's `~T~` generic notation into `<T>`.
+fn generics(text: &str) -> String {
+    let mut open = false;
+    text.chars()
+        .map(|c| {
+            if c != '~' {
+                return c;
+            }
+            open = !open;
+            if open {
+                '<'
+            } else {
+                '>'
+            }
+        })
+        .collect()
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Parser
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Parse a class diagram from the lines after its header.
+pub fn parse(lines: &[(usize, &str)]) -> Result<ClassDiagram, MermaidError> {
+    let mut diagram = ClassDiagram::default();
+    // Class whose `{ ... }` body is open, with the line it was opened on
+    let mut body: Option<(usize, usize)> = None;
+
+    for &(line_number, line) in lines {
+        let line = line.trim().trim_end_matches(';');
+        let error = |message: String| MermaidError {
+            line: line_number,
+            message,
+        };
+
+        if let Some((class, _)) = body {
+            if line == "}" {
+                body = None;
+            } else {
+                diagram.classes[class].add_member(line);
+            }
+            continue;
+        }
+
+        let keyword = line.split_whitespace().next().unwrap_or("");
+        match keyword {
+            "direction" => {
+                let value = line[keyword.len()..].trim();
+                diagram.direction = Direction::parse(value)
+                    .ok_or_else(|| error(format!("unknown direction '{}'", value)))?;
+                continue;
+            }
+            "note" | "link" | "click" | "callback" | "style" | "classDef" | "cssClass" => continue,
+            "class" => {
+                let rest = line[keyword.len()..].trim();
+                let (name, opens) = match rest.strip_suffix('{') {
+                    Some(name) => (name.trim(), true),
+                    None => (rest, false),
+                };
+                // Drop `["Label"]` and `:::style` suffixes
+                let name = name.split(['[', ':']).next().unwrap_or("").trim();
+                if !is_class_name(name) {
+                    return Err(error(format!("invalid class name '{}'", name)));
+                }
+                let index = diagram.class(name);
+                if opens {
+                    body = Some((index, line_number));
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        // `<<interface>> Shape`
+        if let Some(rest) = line.strip_prefix("<<") {
+            let (annotation, name) = rest
+                .split_once(">>")
+                .ok_or_else(|| error("missing '>>' after the annotation".to_string()))?;
+            let index = diagram.class(name.trim());
+            diagram.classes[index].annotation = Some(annotation.trim().to_string());
+            continue;
+        }
+
+        if let Some(relation) = parse_relation(&mut diagram, line) {
+            diagram.relations.push(relation.map_err(error)?);
+            continue;
+        }
+
+        // `Class : member`
+        if let Some((name, member)) = line.split_once(':') {
+            let name = name.trim();
+            if is_class_name(name) {
+                let index = diagram.class(name);
+                diagram.classes[index].add_member(member);
+                continue;
+            }
+        }
+        return Err(error(format!("unrecognized statement '{}'", line)));
+    }
+
+    if let Some((class, line)) = body {
+        return Err(MermaidError {
+            line,
+            message: format!(
+                "class '{}' is never closed with '}}'",
+                diagram.classes[class].id
+            ),
+        });
+    }
+    Ok(diagram)
+}
+
+fn is_class_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '~' || c == ',' || c == '.')
+}
+
+/// Parse `A "1" <|-- "many" B : label`. Returns `None` if the line is not a
+/// relation at all, and an error message if it is a malformed one.
+fn parse_relation(diagram: &mut ClassDiagram, line: &str) -> Option<Result<Relation, String>> {
+    let (statement, label) = match line.split_once(':') {
+        Some((statement, label)) => (statement, Some(label.trim().to_string())),
+        None => (line, None),
+    };
+    let line_at = statement.find("--").or_else(|| statement.find(".."))?;
+
+    // Arrow token: marker characters around the `--`/`..` line
+    let is_marker = |c: char| matches!(c, '<' | '|' | '*' | 'o' | '>');
+    let bytes = statement.as_bytes();
+    let mut start = line_at;
+    while start > 0 && is_marker(bytes[start - 1] as char) {
+        start -= 1;
+    }
+    // An `o` directly after an identifier belongs to the identifier
+    if bytes.get(start) == Some(&b'o') && start > 0 && (bytes[start - 1] as char).is_alphanumeric()
+    {
+        start += 1;
+    }
+    let mut end = line_at + 2;
+    while end < bytes.len() && is_marker(bytes[end] as char) {
+        end += 1;
+    }
+    if bytes.get(end - 1) == Some(&b'o')
+        && end < bytes.len()
+        && (bytes[end] as char).is_alphanumeric()
+    {
+        end -= 1;
+    }
+
+    let arrow = &statement[start..end];
+    let (left_marker, rest) = arrow.split_at(line_at - start);
+    let dashed = rest.starts_with("..");
+    let right_marker = &rest[2..];
+    let marker = |token: &str| match token {
+        "" => Ok(Marker::None),
+        "<|" | "|>" => Ok(Marker::Triangle),
+        "*" => Ok(Marker::FilledDiamond),
+        "o" => Ok(Marker::Diamond),
+        "<" | ">" => Ok(Marker::Arrow),
+        other => Err(format!("unknown relation marker '{}'", other)),
+    };
+
+    let (left, left_cardinality) = split_cardinality(statement[..start].trim(), true);
+    let (right, right_cardinality) = split_cardinality(statement[end..].trim(), false);
+    if !is_class_name(left) || !is_class_name(right) {
+        return Some(Err(format!(
+            "expected a class on both sides of '{}'",
+            arrow
+        )));
+    }
+    let start_marker = match marker(left_marker) {
+        Ok(marker) => marker,
+        Err(message) => return Some(Err(message)),
+    };
+    let end_marker = match marker(right_marker) {
+        Ok(marker) => marker,
+        Err(message) => return Some(Err(message)),
+    };
+
+    Some(Ok(Relation {
+        from: diagram.class(left),
+        to: diagram.class(right),
+        start: start_marker,
+        end: end_marker,
+        dashed,
+        label: label.filter(|l| !l.is_empty()),
+        from_cardinality: left_cardinality,
+        to_cardinality: right_cardinality,
+    }))
+}
+
+/// Split a quoted cardinality off the side of a class name next to the arrow.
+fn split_cardinality(side: &str, trailing: bool) -> (&str, Option<String>) {
+    let quoted = if trailing {
+        side.strip_suffix('"').and_then(|s| s.rsplit_once('"'))
+    } else {
+        side.strip_prefix('"')
+            .and_then(|s| s.split_once('"'))
+            .map(|(card, name)| (name, card))
+    };
+    match quoted {
+        Some((name, cardinality)) => (name.trim(), Some(cardinality.to_string())),
+        None => (side, None),
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Layout
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Height of an empty member compartment.
+const EMPTY_COMPARTMENT: f32 = 10.0;
+
+fn title_height(class: &Class) -> f32 {
+    let lines = if class.annotation.is_some() { 2.0 } else { 1.0 };
+    lines * LINE_HEIGHT + 12.0
+}
+
+fn compartment_height(members: &[String]) -> f32 {
+    if members.is_empty() {
+        EMPTY_COMPARTMENT
+    } else {
+        members.len() as f32 * LINE_HEIGHT + 10.0
+    }
+}
+
+fn class_size(class: &Class) -> Vec2 {
+    let title = label_size(&class.title(), FONT_SIZE).x;
+    let annotation = class
+        .annotation
+        .as_ref()
+        .map_or(0.0, |a| text_width(&format!("«{}»", a), FONT_SIZE));
+    let width = class
+        .attributes
+        .iter()
+        .chain(&class.methods)
+        .map(|m| text_width(m, FONT_SIZE))
+        .fold(title.max(annotation), f32::max);
+    vec2(
+        (width + 24.0).max(90.0),
+        title_height(class)
+            + compartment_height(&class.attributes)
+            + compartment_height(&class.methods),
+    )
+}
+
+/// Lay out a class diagram.
+pub fn layout(diagram: &ClassDiagram) -> Scene {
+    let sizes: Vec<Vec2> = diagram.classes.iter().map(class_size).collect();
+    let edges: Vec<(usize, usize)> = diagram.relations.iter().map(|r| (r.from, r.to)).collect();
+    let graph = layout::layered(&sizes, &edges, diagram.direction);
+
+    let mut scene = Scene::default();
+    let mut labels = Vec::new();
+    for (i, relation) in diagram.relations.iter().enumerate() {
+        let (from, to) = (relation.from, relation.to);
+        let (a, b) = (graph.centers[from], graph.centers[to]);
+        let points = if from == to {
+            let right = a.x + sizes[from].x / 2.0;
+            vec![
+                pos2(right, a.y - 10.0),
+                pos2(right + 28.0, a.y - 10.0),
+                pos2(right + 28.0, a.y + 10.0),
+                pos2(right, a.y + 10.0),
+            ]
+        } else {
+            let bends = &graph.bends[i];
+            let first = bends.first().copied().unwrap_or(b);
+            let last = bends.last().copied().unwrap_or(a);
+            let mut points = vec![layout::clip_to_box(a, sizes[from], first)];
+            points.extend(bends.iter().copied());
+            points.push(layout::clip_to_box(b, sizes[to], last));
+            points
+        };
+        if let Some(label) = &relation.label {
+            labels.push((layout::midpoint(&points), label.clone()));
+        }
+        let n = points.len();
+        if let Some(cardinality) = &relation.from_cardinality {
+            scene.text(
+                cardinality_position(points[0], points[1]),
+                cardinality,
+                false,
+                TextAlign::Center,
+            );
+        }
+        if let Some(cardinality) = &relation.to_cardinality {
+            scene.text(
+                cardinality_position(points[n - 1], points[n - 2]),
+                cardinality,
+                false,
+                TextAlign::Center,
+            );
+        }
+        scene.connector(points, relation.dashed, 1.3, relation.start, relation.end);
+    }
+
+    for (class, (&center, &size)) in diagram.classes.iter().zip(graph.centers.iter().zip(&sizes)) {
+        draw_class(&mut scene, class, Rect::from_center_size(center, size));
+    }
+    for (center, label) in labels {
+        scene.boxed_label(center, &label);
+    }
+    scene.fit(8.0);
+    scene
+}
+
+/// Where to put a cardinality next to the end of a relation at `end`.
+fn cardinality_position(end: Pos2, toward: Pos2) -> Pos2 {
+    let along = (toward - end).normalized();
+    if !along.x.is_finite() || !along.y.is_finite() {
+        return end;
+    }
+    end + along * 26.0 + layout::perpendicular(end, toward, 12.0)
+}
+
+fn draw_class(scene: &mut Scene, class: &Class, rect: Rect) {
+    scene.rect(rect, 0.0, Fill::Node);
+    let mut y = rect.top() + 6.0;
+    if let Some(annotation) = &class.annotation {
+        scene.text(
+            pos2(rect.center().x, y + LINE_HEIGHT / 2.0),
+            &format!("«{}»", annotation),
+            false,
+            TextAlign::Center,
+        );
+        y += LINE_HEIGHT;
+    }
+    scene.text(
+        pos2(rect.center().x, y + LINE_HEIGHT / 2.0),
+        &class.title(),
+        true,
+        TextAlign::Center,
+    );
+
+    let mut divider = rect.top() + title_height(class);
+    for members in [&class.attributes, &class.methods] {
+        scene.line(
+            vec![pos2(rect.left(), divider), pos2(rect.right(), divider)],
+            false,
+            1.0,
+        );
+        for (i, member) in members.iter().enumerate() {
+            let y = divider + 5.0 + (i as f32 + 0.5) * LINE_HEIGHT;
+            scene.text(pos2(rect.left() + 10.0, y), member, false, TextAlign::Left);
+        }
+        divider += compartment_height(members);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::mermaid::tests::lines;
+
+    #[test]
+    fn test_parse_classes_and_members() {
+        let diagram = parse(&lines(
+            "class Animal {\n  <<abstract>>\n  +String name\n  +speak() String\n}\nAnimal : +List~int~ ages\nclass List~T~\n<<interface>> Shape",
+        ))
+        .unwrap();
+        let animal = &diagram.classes[0];
+        assert_eq!(animal.annotation.as_deref(), Some("abstract"));
+        assert_eq!(
+            animal.attributes,
+            vec!["+String name".to_string(), "+List<int> ages".to_string()]
+        );
+        assert_eq!(animal.methods, vec!["+speak() String".to_string()]);
+        assert_eq!(diagram.classes[1].title(), "List<T>");
+        assert_eq!(diagram.classes[2].annotation.as_deref(), Some("interface"));
+    }
+
+    #[test]
+    fn test_parse_relations() {
+        let diagram = parse(&lines(
+            "Animal <|-- Duck\nCar *-- Wheel\nPond o-- Duck\nA --> B\nC ..> D\nE ..|> F\nCustomer \"1\" --> \"*\" Ticket : buys\nfoo -- bar",
+        ))
+        .unwrap();
+        let markers: Vec<(Marker, Marker, bool)> = diagram
+            .relations
+            .iter()
+            .map(|r| (r.start, r.end, r.dashed))
+            .collect();
+        assert_eq!(
+            markers,
+            vec![
+                (Marker::Triangle, Marker::None, false),
+                (Marker::FilledDiamond, Marker::None, false),
+                (Marker::Diamond, Marker::None, false),
+                (Marker::None, Marker::Arrow, false),
+                (Marker::None, Marker::Arrow, true),
+                (Marker::None, Marker::Triangle, true),
+                (Marker::None, Marker::Arrow, false),
+                (Marker::None, Marker::None, false),
+            ]
+        );
+        let buys = &diagram.relations[6];
+        assert_eq!(buys.label.as_deref(), Some("buys"));
+        assert_eq!(buys.from_cardinality.as_deref(), Some("1"));
+        assert_eq!(buys.to_cardinality.as_deref(), Some("*"));
+        assert_eq!(diagram.classes[buys.from].id, "Customer");
+        assert!(diagram.classes.iter().any(|c| c.id == "foo"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse(&lines("class Open {\n  +field")).unwrap_err();
+        assert_eq!(error.line, 2);
+        let error = parse(&lines("A <|-- B\nA <--> ")).unwrap_err();
+        assert_eq!(error.line, 3);
+        let error = parse(&lines("nonsense here")).unwrap_err();
+        assert_eq!(error.line, 2);
+    }
+}
@@ -0,0 +1,680 @@
+//! Flowcharts (`graph` / `flowchart`)
+//!
+//! Supports node shapes (`[rect]`, `(round)`, `([stadium])`, `((circle))`,
+//! `{diamond}`, `{{hexagon}}`, `[(database)]`, `[[subroutine]]`, `>flag]`),
+//! solid, dotted and thick links with or without arrowheads, link labels
+//! (`-->|text|` and `-- text -->`), chains (`a --> b --> c`) and `&` groups.
+//! Styling statements and subgraph boundaries are accepted but not drawn.
+
+use eframe::egui::{pos2, vec2, Pos2, Rect, Vec2};
+
+use super::layout::{self, Direction};
+use super::scene::{label_size, Fill, Marker, Scene, FONT_SIZE};
+use super::MermaidError;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Model
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Shape of a flowchart node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeShape {
+    Rect,
+    Round,
+    Stadium,
+    Circle,
+    Diamond,
+    Hexagon,
+    Database,
+    Subroutine,
+    Flag,
+}
+
+/// A flowchart node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowNode {
+    pub id: String,
+    pub label: String,
+    pub shape: NodeShape,
+}
+
+/// Line style of a link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkStyle {
+    Solid,
+    Dotted,
+    Thick,
+}
+
+/// A link between two nodes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowEdge {
+    pub from: usize,
+    pub to: usize,
+    pub label: Option<String>,
+    pub style: LinkStyle,
+    pub start: Marker,
+    pub end: Marker,
+}
+
+/// A parsed flowchart.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Flowchart {
+    pub direction: Direction,
+    pub nodes: Vec<FlowNode>,
+    pub edges: Vec<FlowEdge>,
+}
+
+impl Flowchart {
+    /// Index of the node `id`, creating it (as a rectangle labeled with its id) if needed.
+    fn node(&mut self, id: &str) -> usize {
+        if let Some(i) = self.nodes.iter().position(|n| n.id == id) {
+            return i;
+        }
+        self.nodes.push(FlowNode {
+            id: id.to_string(),
+            label: id.to_string(),
+            shape: NodeShape::Rect,
+        });
+        self.nodes.len() - 1
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Parser
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Statements that are accepted but have no visual effect here.
+const IGNORED_KEYWORDS: &[&str] = &[
+    "subgraph",
+    "end",
+    "style",
+    "classDef",
+    "class",
+    "click",
+    "linkStyle",
+    "direction",
+];
+
+/// Parse a flowchart from its numbered header line and the lines after it.
+pub fn parse(header: (usize, &str), lines: &[(usize, &str)]) -> Result<Flowchart, MermaidError> {
+    let mut chart = Flowchart::default();
+    if let Some(keyword) = header.1.split_whitespace().nth(1) {
+        let keyword = keyword.trim_end_matches(';');
+        chart.direction = Direction::parse(keyword).ok_or_else(|| MermaidError {
+            line: header.0,
+            message: format!("unknown direction '{}'", keyword),
+        })?;
+    }
+
+    for &(line_number, line) in lines {
+        for statement in line.split(';') {
+            let statement = statement.trim();
+            if statement.is_empty() {
+                continue;
+            }
+            let keyword = statement.split_whitespace().next().unwrap_or("");
+            if IGNORED_KEYWORDS.contains(&keyword) {
+                continue;
+            }
+            StatementParser {
+                chars: statement.chars().collect(),
+                pos: 0,
+                line: line_number,
+            }
+            .parse(&mut chart)?;
+        }
+    }
+    Ok(chart)
+}
+
+struct StatementParser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl StatementParser {
+    fn error(&self, message: impl Into<String>) -> MermaidError {
+        MermaidError {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn rest(&self) -> String {
+        self.chars[self.pos..].iter().collect()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.rest().starts_with(token) {
+            self.pos += token.chars().count();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// `group (link group)*`
+    fn parse(mut self, chart: &mut Flowchart) -> Result<(), MermaidError> {
+        let mut sources = self.parse_group(chart)?;
+        loop {
+            self.skip_whitespace();
+            if self.peek().is_none() {
+                return Ok(());
+            }
+            let link = self.parse_link()?;
+            self.skip_whitespace();
+            if self.peek().is_none() {
+                return Err(self.error("expected a node after the link"));
+            }
+            let targets = self.parse_group(chart)?;
+            for &from in &sources {
+                for &to in &targets {
+                    chart.edges.push(FlowEdge {
+                        from,
+                        to,
+                        label: link.label.clone(),
+                        style: link.style,
+                        start: link.start,
+                        end: link.end,
+                    });
+                }
+            }
+            sources = targets;
+        }
+    }
+
+    /// `node (& node)*`
+    fn parse_group(&mut self, chart: &mut Flowchart) -> Result<Vec<usize>, MermaidError> {
+        let mut nodes = vec![self.parse_node(chart)?];
+        loop {
+            self.skip_whitespace();
+            if !self.eat("&") {
+                return Ok(nodes);
+            }
+            self.skip_whitespace();
+            nodes.push(self.parse_node(chart)?);
+        }
+    }
+
+    fn parse_node(&mut self, chart: &mut Flowchart) -> Result<usize, MermaidError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.')
+        {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(match self.peek() {
+                Some(c) => self.error(format!("expected a node id, found '{}'", c)),
+                None => self.error("expected a node id"),
+            });
+        }
+        let id: String = self.chars[start..self.pos].iter().collect();
+        let index = chart.node(&id);
+
+        const SHAPES: &[(&str, &str, NodeShape)] = &[
+            ("(((", ")))", NodeShape::Circle),
+            ("((", "))", NodeShape::Circle),
+            ("([", "])", NodeShape::Stadium),
+            ("[(", ")]", NodeShape::Database),
+            ("[[", "]]", NodeShape::Subroutine),
+            ("{{", "}}", NodeShape::Hexagon),
+            ("[", "]", NodeShape::Rect),
+            ("(", ")", NodeShape::Round),
+            ("{", "}", NodeShape::Diamond),
+            (">", "]", NodeShape::Flag),
+        ];
+        for &(open, close, shape) in SHAPES {
+            // `>` only opens a flag shape directly after the id
+            if !self.rest().starts_with(open) {
+                continue;
+            }
+            self.pos += open.chars().count();
+            let label = self.parse_label(close)?;
+            chart.nodes[index].label = label;
+            chart.nodes[index].shape = shape;
+            break;
+        }
+
+        // Class shorthand: id:::className
+        if self.eat(":::") {
+            while self
+                .peek()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-')
+            {
+                self.pos += 1;
+            }
+        }
+        Ok(index)
+    }
+
+    /// Read a node label up to `close`, which is consumed.
+    fn parse_label(&mut self, close: &str) -> Result<String, MermaidError> {
+        self.skip_whitespace();
+        if self.eat("\"") {
+            let start = self.pos;
+            while self.peek().is_some_and(|c| c != '"') {
+                self.pos += 1;
+            }
+            let label: String = self.chars[start..self.pos].iter().collect();
+            self.eat("\"");
+            self.skip_whitespace();
+            if !self.eat(close) {
+                return Err(self.error(format!("expected '{}' after the label", close)));
+            }
+            return Ok(label);
+        }
+        let rest = self.rest();
+        match rest.find(close) {
+            Some(end) => {
+                let label = rest[..end].trim().to_string();
+                self.pos += rest[..end].chars().count() + close.chars().count();
+                Ok(label)
+            }
+            None => Err(self.error(format!("missing '{}' to close the node label", close))),
+        }
+    }
+
+    fn parse_link(&mut self) -> Result<Link, MermaidError> {
+        let rest = self.rest();
+        let token_len = rest
+            .chars()
+            .take_while(|c| matches!(c, '<' | '-' | '=' | '.' | '>' | 'x' | 'o'))
+            .count();
+        let mut token: String = rest.chars().take(token_len).collect();
+        // `x`/`o` only end a link when it isn't the start of the next node id
+        while token.ends_with(['x', 'o'])
+            && rest
+                .chars()
+                .nth(token.chars().count())
+                .is_some_and(|c| c.is_alphanumeric() || c == '_')
+        {
+            token.pop();
+        }
+        // Trailing x/o that follow a '>' belong to the next node
+        while token.len() > 1
+            && token.ends_with(['x', 'o'])
+            && token[..token.len() - 1].ends_with('>')
+        {
+            token.pop();
+        }
+        if !token.contains(['-', '='])
+            || token
+                .chars()
+                .filter(|c| matches!(c, '-' | '=' | '.'))
+                .count()
+                < 2
+        {
+            return Err(match self.peek() {
+                Some(c) => self.error(format!("expected a link such as '-->', found '{}'", c)),
+                None => self.error("expected a link"),
+            });
+        }
+        self.pos += token.chars().count();
+
+        let mut label = None;
+        // `-- text -->` style labels: an open link token followed by text
+        if matches!(token.as_str(), "--" | "==" | "-.") {
+            let closers: &[&str] = match token.as_str() {
+                "--" => &["-->", "---", "--x", "--o"],
+                "==" => &["==>", "===", "==x", "==o"],
+                _ => &[".->", ".-"],
+            };
+            let rest = self.rest();
+            let found = closers
+                .iter()
+                .filter_map(|closer| rest.find(closer).map(|at| (at, *closer)))
+                .min_by_key(|(at, _)| *at);
+            let Some((at, closer)) = found else {
+                return Err(self.error(format!("unterminated link label after '{}'", token)));
+            };
+            label = Some(rest[..at].trim().to_string());
+            self.pos += rest[..at].chars().count() + closer.chars().count();
+            token = format!("{}{}", token, closer);
+        }
+
+        let style = if token.contains('.') {
+            LinkStyle::Dotted
+        } else if token.contains('=') {
+            LinkStyle::Thick
+        } else {
+            LinkStyle::Solid
+        };
+        let marker = |c: Option<char>| match c {
+            Some('>') | Some('<') => Marker::Arrow,
+            Some('x') => Marker::Cross,
+            Some('o') => Marker::Circle,
+            _ => Marker::None,
+        };
+        let start = marker(
+            token
+                .chars()
+                .next()
+                .filter(|c| matches!(c, '<' | 'x' | 'o')),
+        );
+        let end = marker(token.chars().last());
+
+        self.skip_whitespace();
+        if self.eat("|") {
+            let rest = self.rest();
+            let Some(end) = rest.find('|') else {
+                return Err(self.error("missing '|' to close the link label"));
+            };
+            label = Some(rest[..end].trim().trim_matches('"').to_string());
+            self.pos += rest[..end].chars().count() + 1;
+        }
+
+        Ok(Link {
+            label,
+            style,
+            start,
+            end,
+        })
+    }
+}
+
+struct Link {
+    label: Option<String>,
+    style: LinkStyle,
+    start: Marker,
+    end: Marker,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Layout
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Size of a node's shape around its label.
+fn node_size(node: &FlowNode) -> Vec2 {
+    let label = label_size(&node.label, FONT_SIZE);
+    let padded = label + vec2(30.0, 18.0);
+    match node.shape {
+        NodeShape::Circle => Vec2::splat(padded.x.max(padded.y)),
+        NodeShape::Diamond => vec2((label.x + 20.0) * 1.6, (label.y + 20.0) * 1.6),
+        NodeShape::Hexagon => padded + vec2(24.0, 0.0),
+        NodeShape::Database => padded + vec2(0.0, 16.0),
+        NodeShape::Subroutine | NodeShape::Flag => padded + vec2(16.0, 0.0),
+        _ => padded,
+    }
+    .max(vec2(60.0, 38.0))
+}
+
+/// Where a line from `node`'s center towards `toward` meets its outline.
+fn clip(shape: NodeShape, center: Pos2, size: Vec2, toward: Pos2) -> Pos2 {
+    match shape {
+        NodeShape::Circle => layout::clip_to_circle(center, size.x / 2.0, toward),
+        NodeShape::Diamond => layout::clip_to_diamond(center, size, toward),
+        _ => layout::clip_to_box(center, size, toward),
+    }
+}
+
+/// Lay out a flowchart.
+pub fn layout(chart: &Flowchart) -> Scene {
+    let sizes: Vec<Vec2> = chart.nodes.iter().map(node_size).collect();
+    let edges: Vec<(usize, usize)> = chart.edges.iter().map(|e| (e.from, e.to)).collect();
+    let graph = layout::layered(&sizes, &edges, chart.direction);
+
+    let mut scene = Scene::default();
+    let mut labels = Vec::new();
+    for (i, edge) in chart.edges.iter().enumerate() {
+        let from = graph.centers[edge.from];
+        let to = graph.centers[edge.to];
+        let (width, dashed) = match edge.style {
+            LinkStyle::Solid => (1.3, false),
+            LinkStyle::Dotted => (1.3, true),
+            LinkStyle::Thick => (2.6, false),
+        };
+
+        let points = if edge.from == edge.to {
+            self_loop(from, sizes[edge.from])
+        } else {
+            // Links between the same two nodes are spread apart
+            let siblings: Vec<usize> = chart
+                .edges
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| {
+                    (e.from == edge.from && e.to == edge.to)
+                        || (e.from == edge.to && e.to == edge.from)
+                })
+                .map(|(j, _)| j)
+                .collect();
+            let rank = siblings.iter().position(|&j| j == i).unwrap_or(0) as f32;
+            let spread = (rank - (siblings.len() as f32 - 1.0) / 2.0) * 14.0;
+            let offset = if edge.from < edge.to {
+                layout::perpendicular(from, to, spread)
+            } else {
+                layout::perpendicular(to, from, spread)
+            };
+
+            let bends = &graph.bends[i];
+            let first = bends.first().copied().unwrap_or(to + offset);
+            let last = bends.last().copied().unwrap_or(from + offset);
+            let start = clip(
+                chart.nodes[edge.from].shape,
+                from + offset,
+                sizes[edge.from],
+                first,
+            );
+            let end = clip(
+                chart.nodes[edge.to].shape,
+                to + offset,
+                sizes[edge.to],
+                last,
+            );
+            let mut points = vec![start];
+            points.extend(bends.iter().copied());
+            points.push(end);
+            points
+        };
+
+        if let Some(label) = edge.label.as_deref().filter(|l| !l.is_empty()) {
+            labels.push((layout::midpoint(&points), label.to_string()));
+        }
+        scene.connector(points, dashed, width, edge.start, edge.end);
+    }
+
+    for (node, (&center, &size)) in chart.nodes.iter().zip(graph.centers.iter().zip(&sizes)) {
+        draw_node(&mut scene, node, center, size);
+    }
+    for (center, label) in labels {
+        scene.boxed_label(center, &label);
+    }
+    scene.fit(8.0);
+    scene
+}
+
+/// Loop from the right side of a node back into its top.
+fn self_loop(center: Pos2, size: Vec2) -> Vec<Pos2> {
+    let right = center.x + size.x / 2.0;
+    let top = center.y - size.y / 2.0;
+    vec![
+        pos2(right, center.y - size.y * 0.15),
+        pos2(right + 24.0, center.y - size.y * 0.15),
+        pos2(right + 24.0, top - 20.0),
+        pos2(center.x + size.x * 0.25, top - 20.0),
+        pos2(center.x + size.x * 0.25, top),
+    ]
+}
+
+fn draw_node(scene: &mut Scene, node: &FlowNode, center: Pos2, size: Vec2) {
+    let rect = Rect::from_center_size(center, size);
+    let (l, r, t, b) = (rect.left(), rect.right(), rect.top(), rect.bottom());
+    match node.shape {
+        NodeShape::Rect => scene.rect(rect, 2.0, Fill::Node),
+        NodeShape::Round => scene.rect(rect, 8.0, Fill::Node),
+        NodeShape::Stadium => scene.rect(rect, size.y / 2.0, Fill::Node),
+        NodeShape::Circle => scene.circle(center, size.x / 2.0, Fill::Node, true),
+        NodeShape::Diamond => scene.polygon(
+            vec![
+                pos2(center.x, t),
+                pos2(r, center.y),
+                pos2(center.x, b),
+                pos2(l, center.y),
+            ],
+            Fill::Node,
+        ),
+        NodeShape::Hexagon => scene.polygon(
+            vec![
+                pos2(l + 12.0, t),
+                pos2(r - 12.0, t),
+                pos2(r, center.y),
+                pos2(r - 12.0, b),
+                pos2(l + 12.0, b),
+                pos2(l, center.y),
+            ],
+            Fill::Node,
+        ),
+        NodeShape::Flag => scene.polygon(
+            // Start at the notch so the fill's triangle fan stays inside the outline
+            vec![
+                pos2(l + 12.0, center.y),
+                pos2(l, t),
+                pos2(r, t),
+                pos2(r, b),
+                pos2(l, b),
+            ],
+            Fill::Node,
+        ),
+        NodeShape::Subroutine => {
+            scene.rect(rect, 0.0, Fill::Node);
+            scene.line(vec![pos2(l + 8.0, t), pos2(l + 8.0, b)], false, 1.2);
+            scene.line(vec![pos2(r - 8.0, t), pos2(r - 8.0, b)], false, 1.2);
+        }
+        NodeShape::Database => {
+            // Cylinder: a body with elliptical caps approximated by polylines
+            let cap = 8.0;
+            let ellipse = |y: f32, upper: bool| -> Vec<Pos2> {
+                (0..=16)
+                    .map(|i| {
+                        let a = std::f32::consts::PI * i as f32 / 16.0;
+                        let dy = a.sin() * cap;
+                        pos2(
+                            l + (1.0 - a.cos()) * size.x / 2.0,
+                            if upper { y - dy } else { y + dy },
+                        )
+                    })
+                    .collect()
+            };
+            scene.rect(
+                Rect::from_min_max(pos2(l, t + cap), pos2(r, b - cap)),
+                0.0,
+                Fill::Node,
+            );
+            let mut outline = ellipse(t + cap, true);
+            outline.extend(ellipse(t + cap, false).into_iter().rev());
+            scene.polygon(outline, Fill::Node);
+            scene.line(ellipse(b - cap, false), false, 1.2);
+            scene.line(vec![pos2(l, t + cap), pos2(l, b - cap)], false, 1.2);
+            scene.line(vec![pos2(r, t + cap), pos2(r, b - cap)], false, 1.2);
+        }
+    }
+    let label_center = match node.shape {
+        NodeShape::Flag => center + vec2(6.0, 0.0),
+        NodeShape::Database => center + vec2(0.0, 4.0),
+        _ => center,
+    };
+    scene.label(label_center, &node.label);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_chart(source: &str) -> Result<Flowchart, MermaidError> {
+        let mut lines = source.lines().enumerate().map(|(i, l)| (i + 1, l));
+        let header = lines.next().unwrap();
+        let rest: Vec<(usize, &str)> = lines.collect();
+        parse(header, &rest)
+    }
+
+    #[test]
+    fn test_parse_nodes_and_links() {
+        let chart = parse_chart(
+            "graph LR\n  A[Start] --> B{Is it?}\n  B -->|Yes| C((Done))\n  B -- No --> D([Retry]);D -.-> A\n  C & D ==> E[(Store)]",
+        )
+        .unwrap();
+        assert_eq!(chart.direction, Direction::LeftRight);
+        let shapes: Vec<(&str, &str, NodeShape)> = chart
+            .nodes
+            .iter()
+            .map(|n| (n.id.as_str(), n.label.as_str(), n.shape))
+            .collect();
+        assert_eq!(
+            shapes,
+            vec![
+                ("A", "Start", NodeShape::Rect),
+                ("B", "Is it?", NodeShape::Diamond),
+                ("C", "Done", NodeShape::Circle),
+                ("D", "Retry", NodeShape::Stadium),
+                ("E", "Store", NodeShape::Database),
+            ]
+        );
+        assert_eq!(chart.edges.len(), 6);
+        assert_eq!(chart.edges[1].label.as_deref(), Some("Yes"));
+        assert_eq!(chart.edges[2].label.as_deref(), Some("No"));
+        assert_eq!(chart.edges[3].style, LinkStyle::Dotted);
+        assert_eq!(chart.edges[4].style, LinkStyle::Thick);
+        assert_eq!((chart.edges[4].from, chart.edges[5].from), (2, 3));
+        assert!(chart.edges.iter().all(|e| e.end == Marker::Arrow));
+    }
+
+    #[test]
+    fn test_parse_link_variants() {
+        let chart = parse_chart(
+            "flowchart TD\nA --- B\nB <--> C\nC --x D\nD --o xray\nE[\"A [quoted] label\"]",
+        )
+        .unwrap();
+        assert_eq!(chart.edges[0].end, Marker::None);
+        assert_eq!(
+            (chart.edges[1].start, chart.edges[1].end),
+            (Marker::Arrow, Marker::Arrow)
+        );
+        assert_eq!(chart.edges[2].end, Marker::Cross);
+        assert_eq!(chart.edges[3].end, Marker::Circle);
+        assert_eq!(chart.nodes[4].id, "xray");
+        assert_eq!(chart.nodes[5].label, "A [quoted] label");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse_chart("graph TD\nA --> B\nA[Unclosed --> C").unwrap_err();
+        assert_eq!(error.line, 3);
+        assert!(error.message.contains("']'"), "{}", error.message);
+
+        let error = parse_chart("graph TD\nA -->").unwrap_err();
+        assert_eq!(error.line, 2);
+
+        assert!(parse_chart("graph XY\nA").is_err());
+        assert!(parse_chart("graph TD\nA ? B").is_err());
+    }
+
+    #[test]
+    fn test_layout_places_every_node() {
+        let chart = parse_chart("graph TD\nA --> B\nA --> C\nB --> D\nC --> D\nD --> A").unwrap();
+        let scene = layout(&chart);
+        assert!(scene.width > 0.0 && scene.height > 0.0);
+        let bounds = scene.bounds();
+        assert!(bounds.min.x >= 0.0 && bounds.max.x <= scene.width + 0.5);
+        assert!(bounds.max.y <= scene.height + 0.5);
+        for label in ["A", "B", "C", "D"] {
+            assert!(scene.shapes.iter().any(
+                |s| matches!(s, super::super::scene::Shape::Text { text, .. } if text == label)
+            ));
+        }
+    }
+}
@@ -0,0 +1,460 @@
+//! Layered graph layout
+//!
+//! A compact Sugiyama-style layout used by flowcharts, state diagrams and
+//! class diagrams: cycles are broken, nodes are assigned to ranks by longest
+//! path, long edges are split with virtual nodes, ranks are ordered with the
+//! barycenter heuristic to reduce crossings, and nodes are then pulled towards
+//! their neighbors.
+
+use eframe::egui::{pos2, vec2, Pos2, Vec2};
+
+/// Space between ranks.
+const RANK_GAP: f32 = 56.0;
+
+/// Space between neighboring nodes of a rank.
+const NODE_GAP: f32 = 36.0;
+
+/// Cross-axis size reserved for an edge passing through a rank.
+const VIRTUAL_SIZE: f32 = 12.0;
+
+/// Direction in which ranks are stacked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direction {
+    #[default]
+    TopDown,
+    BottomUp,
+    LeftRight,
+    RightLeft,
+}
+
+impl Direction {
+    /// Parse a Mermaid direction keyword (`TD`, `TB`, `BT`, `LR`, `RL`).
+    pub fn parse(keyword: &str) -> Option<Self> {
+        match keyword {
+            "TD" | "TB" => Some(Direction::TopDown),
+            "BT" => Some(Direction::BottomUp),
+            "LR" => Some(Direction::LeftRight),
+            "RL" => Some(Direction::RightLeft),
+            _ => None,
+        }
+    }
+
+    fn is_vertical(self) -> bool {
+        matches!(self, Direction::TopDown | Direction::BottomUp)
+    }
+}
+
+/// Node positions and edge routes.
+#[derive(Debug, Clone, Default)]
+pub struct GraphLayout {
+    /// Center of each node
+    pub centers: Vec<Pos2>,
+    /// Bend points of each edge, from its source towards its target
+    /// (empty for edges between neighboring ranks and for self-loops)
+    pub bends: Vec<Vec<Pos2>>,
+}
+
+/// Lay out nodes of the given sizes connected by `edges` (source, target).
+pub fn layered(sizes: &[Vec2], edges: &[(usize, usize)], direction: Direction) -> GraphLayout {
+    let n = sizes.len();
+    if n == 0 {
+        return GraphLayout {
+            centers: Vec::new(),
+            bends: vec![Vec::new(); edges.len()],
+        };
+    }
+
+    // Cycle removal: reverse edges that point back up the DFS stack
+    let reversed = back_edges(n, edges);
+    let dag: Vec<(usize, usize)> = edges
+        .iter()
+        .zip(&reversed)
+        .map(|(&(from, to), &rev)| if rev { (to, from) } else { (from, to) })
+        .collect();
+
+    let mut rank = assign_ranks(n, &dag);
+
+    // Split long edges with virtual nodes
+    let mut cross_size: Vec<f32> = Vec::with_capacity(n);
+    let mut main_size: Vec<f32> = Vec::with_capacity(n);
+    for size in sizes {
+        if direction.is_vertical() {
+            cross_size.push(size.x);
+            main_size.push(size.y);
+        } else {
+            cross_size.push(size.y);
+            main_size.push(size.x);
+        }
+    }
+    let mut chains: Vec<Vec<usize>> = Vec::with_capacity(dag.len());
+    let mut links: Vec<(usize, usize)> = Vec::new();
+    for &(from, to) in &dag {
+        if from == to {
+            chains.push(vec![from, to]);
+            continue;
+        }
+        let mut chain = vec![from];
+        for r in rank[from] + 1..rank[to] {
+            rank.push(r);
+            cross_size.push(VIRTUAL_SIZE);
+            main_size.push(0.0);
+            chain.push(rank.len() - 1);
+        }
+        chain.push(to);
+        links.extend(chain.windows(2).map(|w| (w[0], w[1])));
+        chains.push(chain);
+    }
+    let total = rank.len();
+
+    let mut predecessors = vec![Vec::new(); total];
+    let mut successors = vec![Vec::new(); total];
+    for &(from, to) in &links {
+        successors[from].push(to);
+        predecessors[to].push(from);
+    }
+
+    // Order nodes within ranks
+    let rank_count = rank.iter().max().map_or(0, |r| r + 1);
+    let mut layers: Vec<Vec<usize>> = vec![Vec::new(); rank_count];
+    for v in 0..total {
+        layers[rank[v]].push(v);
+    }
+    let mut position = vec![0.0f32; total];
+    let update_positions = |layers: &Vec<Vec<usize>>, position: &mut Vec<f32>| {
+        for layer in layers {
+            for (i, &v) in layer.iter().enumerate() {
+                position[v] = i as f32;
+            }
+        }
+    };
+    update_positions(&layers, &mut position);
+    for sweep in 0..8 {
+        let downward = sweep % 2 == 0;
+        let order: Vec<usize> = if downward {
+            (1..rank_count).collect()
+        } else {
+            (0..rank_count.saturating_sub(1)).rev().collect()
+        };
+        for r in order {
+            let neighbors = if downward { &predecessors } else { &successors };
+            let mut keyed: Vec<(f32, usize)> = layers[r]
+                .iter()
+                .map(|&v| {
+                    (
+                        barycenter(&neighbors[v], &position).unwrap_or(position[v]),
+                        v,
+                    )
+                })
+                .collect();
+            keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+            layers[r] = keyed.into_iter().map(|(_, v)| v).collect();
+            for (i, &v) in layers[r].iter().enumerate() {
+                position[v] = i as f32;
+            }
+        }
+    }
+
+    // Cross-axis coordinates: pack each rank, then pull nodes towards their neighbors
+    let mut cross = vec![0.0f32; total];
+    for layer in &layers {
+        let mut x = 0.0;
+        for &v in layer {
+            cross[v] = x + cross_size[v] / 2.0;
+            x += cross_size[v] + NODE_GAP;
+        }
+        let shift = (x - NODE_GAP) / 2.0;
+        for &v in layer {
+            cross[v] -= shift;
+        }
+    }
+    for iteration in 0..6 {
+        let downward = iteration % 2 == 0;
+        let ranks: Vec<usize> = if downward {
+            (1..rank_count).collect()
+        } else {
+            (0..rank_count.saturating_sub(1)).rev().collect()
+        };
+        for r in ranks {
+            let neighbors = if downward { &predecessors } else { &successors };
+            let layer = &layers[r];
+            let desired: Vec<f32> = layer
+                .iter()
+                .map(|&v| barycenter(&neighbors[v], &cross).unwrap_or(cross[v]))
+                .collect();
+            let mut placed = desired.clone();
+            for i in 1..layer.len() {
+                let min_gap = (cross_size[layer[i - 1]] + cross_size[layer[i]]) / 2.0 + NODE_GAP;
+                placed[i] = placed[i].max(placed[i - 1] + min_gap);
+            }
+            let drift = (desired.iter().sum::<f32>() - placed.iter().sum::<f32>())
+                / layer.len().max(1) as f32;
+            for (i, &v) in layer.iter().enumerate() {
+                cross[v] = placed[i] + drift;
+            }
+        }
+    }
+
+    // Main-axis coordinates from rank thickness
+    let mut thickness = vec![0.0f32; rank_count];
+    for v in 0..total {
+        thickness[rank[v]] = thickness[rank[v]].max(main_size[v]);
+    }
+    let mut rank_center = Vec::with_capacity(rank_count);
+    let mut offset = 0.0;
+    for t in &thickness {
+        rank_center.push(offset + t / 2.0);
+        offset += t + RANK_GAP;
+    }
+    let main_extent = offset - RANK_GAP;
+
+    let min_cross = (0..total)
+        .map(|v| cross[v] - cross_size[v] / 2.0)
+        .fold(f32::INFINITY, f32::min);
+    let point = |v: usize| -> Pos2 {
+        let c = cross[v] - min_cross;
+        let m = rank_center[rank[v]];
+        match direction {
+            Direction::TopDown => pos2(c, m),
+            Direction::BottomUp => pos2(c, main_extent - m),
+            Direction::LeftRight => pos2(m, c),
+            Direction::RightLeft => pos2(main_extent - m, c),
+        }
+    };
+
+    let centers = (0..n).map(point).collect();
+    let bends = chains
+        .iter()
+        .zip(&reversed)
+        .map(|(chain, &rev)| {
+            let mut bends: Vec<Pos2> = chain[1..chain.len() - 1]
+                .iter()
+                .map(|&v| point(v))
+                .collect();
+            if rev {
+                bends.reverse();
+            }
+            bends
+        })
+        .collect();
+
+    GraphLayout { centers, bends }
+}
+
+/// Mean position of `nodes`, if there are any.
+fn barycenter(nodes: &[usize], position: &[f32]) -> Option<f32> {
+    if nodes.is_empty() {
+        return None;
+    }
+    Some(nodes.iter().map(|&v| position[v]).sum::<f32>() / nodes.len() as f32)
+}
+
+/// Flags the edges that close a cycle in a depth-first search.
+fn back_edges(n: usize, edges: &[(usize, usize)]) -> Vec<bool> {
+    let mut outgoing = vec![Vec::new(); n];
+    for (i, &(from, to)) in edges.iter().enumerate() {
+        if from != to {
+            outgoing[from].push((i, to));
+        }
+    }
+    let mut reversed = vec![false; edges.len()];
+    // 0 = unvisited, 1 = on the stack, 2 = done
+    let mut state = vec![0u8; n];
+    for root in 0..n {
+        if state[root] != 0 {
+            continue;
+        }
+        let mut stack = vec![(root, 0usize)];
+        state[root] = 1;
+        while let Some((v, next)) = stack.pop() {
+            if let Some(&(edge, to)) = outgoing[v].get(next) {
+                stack.push((v, next + 1));
+                match state[to] {
+                    0 => {
+                        state[to] = 1;
+                        stack.push((to, 0));
+                    }
+                    1 => reversed[edge] = true,
+                    _ => {}
+                }
+            } else {
+                state[v] = 2;
+            }
+        }
+    }
+    reversed
+}
+
+/// Longest-path ranking of an acyclic graph, with sources moved down next
+/// to their first successor.
+fn assign_ranks(n: usize, dag: &[(usize, usize)]) -> Vec<usize> {
+    let mut indegree = vec![0usize; n];
+    let mut outgoing = vec![Vec::new(); n];
+    for &(from, to) in dag {
+        if from != to {
+            indegree[to] += 1;
+            outgoing[from].push(to);
+        }
+    }
+    let mut rank = vec![0usize; n];
+    let mut queue: Vec<usize> = (0..n).filter(|&v| indegree[v] == 0).collect();
+    let mut order = Vec::with_capacity(n);
+    let mut i = 0;
+    while i < queue.len() {
+        let v = queue[i];
+        i += 1;
+        order.push(v);
+        for &to in &outgoing[v] {
+            rank[to] = rank[to].max(rank[v] + 1);
+            indegree[to] -= 1;
+            if indegree[to] == 0 {
+                queue.push(to);
+            }
+        }
+    }
+
+    let has_predecessor: Vec<bool> = {
+        let mut has = vec![false; n];
+        for &(from, to) in dag {
+            if from != to {
+                has[to] = true;
+            }
+        }
+        has
+    };
+    for &v in order.iter().rev() {
+        if !has_predecessor[v] && !outgoing[v].is_empty() {
+            let closest = outgoing[v].iter().map(|&to| rank[to]).min().unwrap_or(1);
+            rank[v] = closest.saturating_sub(1);
+        }
+    }
+    rank
+}
+
+/// Point where the ray from `center` towards `toward` leaves a box of `size`.
+pub fn clip_to_box(center: Pos2, size: Vec2, toward: Pos2) -> Pos2 {
+    let d = toward - center;
+    if d.x.abs() < f32::EPSILON && d.y.abs() < f32::EPSILON {
+        return center;
+    }
+    let half = size / 2.0;
+    let tx = if d.x.abs() > f32::EPSILON {
+        half.x / d.x.abs()
+    } else {
+        f32::INFINITY
+    };
+    let ty = if d.y.abs() > f32::EPSILON {
+        half.y / d.y.abs()
+    } else {
+        f32::INFINITY
+    };
+    center + d * tx.min(ty)
+}
+
+/// Point where the ray from `center` towards `toward` leaves a diamond of `size`.
+pub fn clip_to_diamond(center: Pos2, size: Vec2, toward: Pos2) -> Pos2 {
+    let d = toward - center;
+    let denominator = d.x.abs() / (size.x / 2.0) + d.y.abs() / (size.y / 2.0);
+    if denominator <= f32::EPSILON {
+        return center;
+    }
+    center + d / denominator
+}
+
+/// Point where the ray from `center` towards `toward` leaves a circle.
+pub fn clip_to_circle(center: Pos2, radius: f32, toward: Pos2) -> Pos2 {
+    let d = toward - center;
+    let length = d.length();
+    if length <= f32::EPSILON {
+        return center;
+    }
+    center + d * (radius / length)
+}
+
+/// Offset perpendicular to the segment `from`→`to`.
+pub fn perpendicular(from: Pos2, to: Pos2, distance: f32) -> Vec2 {
+    let d = (to - from).normalized();
+    if !d.x.is_finite() || !d.y.is_finite() {
+        return Vec2::ZERO;
+    }
+    vec2(-d.y, d.x) * distance
+}
+
+/// Point halfway along a polyline.
+pub fn midpoint(points: &[Pos2]) -> Pos2 {
+    let total: f32 = points.windows(2).map(|w| (w[1] - w[0]).length()).sum();
+    let mut remaining = total / 2.0;
+    for w in points.windows(2) {
+        let length = (w[1] - w[0]).length();
+        if length >= remaining && length > 0.0 {
+            return w[0] + (w[1] - w[0]) * (remaining / length);
+        }
+        remaining -= length;
+    }
+    points.first().copied().unwrap_or(Pos2::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sizes(n: usize) -> Vec<Vec2> {
+        vec![vec2(60.0, 30.0); n]
+    }
+
+    #[test]
+    fn test_ranks_follow_edges() {
+        // a -> b -> d, a -> c -> d
+        let layout = layered(
+            &sizes(4),
+            &[(0, 1), (0, 2), (1, 3), (2, 3)],
+            Direction::TopDown,
+        );
+        let y: Vec<f32> = layout.centers.iter().map(|p| p.y).collect();
+        assert!(y[0] < y[1] && y[1] == y[2] && y[2] < y[3]);
+        // Siblings don't overlap
+        assert!((layout.centers[1].x - layout.centers[2].x).abs() >= 60.0);
+
+        let layout = layered(&sizes(2), &[(0, 1)], Direction::LeftRight);
+        assert!(layout.centers[0].x < layout.centers[1].x);
+        assert_eq!(layout.centers[0].y, layout.centers[1].y);
+
+        let layout = layered(&sizes(2), &[(0, 1)], Direction::BottomUp);
+        assert!(layout.centers[0].y > layout.centers[1].y);
+    }
+
+    #[test]
+    fn test_cycles_and_long_edges() {
+        // a -> b -> c -> a, plus a -> c skipping a rank
+        let edges = [(0, 1), (1, 2), (2, 0), (0, 2), (1, 1)];
+        let layout = layered(&sizes(3), &edges, Direction::TopDown);
+        assert_eq!(layout.centers.len(), 3);
+        assert_eq!(layout.bends.len(), edges.len());
+        assert!(layout.bends[0].is_empty());
+        // The rank-skipping edges bend through the middle rank
+        assert_eq!(layout.bends[3].len(), 1);
+        assert_eq!(layout.bends[3][0].y, layout.centers[1].y);
+        assert_eq!(layout.bends[2].len(), 1);
+        assert!(layout.bends[4].is_empty());
+    }
+
+    #[test]
+    fn test_clipping() {
+        let center = pos2(0.0, 0.0);
+        assert_eq!(
+            clip_to_box(center, vec2(40.0, 20.0), pos2(100.0, 0.0)),
+            pos2(20.0, 0.0)
+        );
+        assert_eq!(
+            clip_to_box(center, vec2(40.0, 20.0), pos2(0.0, -50.0)),
+            pos2(0.0, -10.0)
+        );
+        assert_eq!(
+            clip_to_diamond(center, vec2(40.0, 20.0), pos2(0.0, 50.0)),
+            pos2(0.0, 10.0)
+        );
+        assert_eq!(
+            clip_to_circle(center, 5.0, pos2(30.0, 40.0)),
+            pos2(3.0, 4.0)
+        );
+    }
+}
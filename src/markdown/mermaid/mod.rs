@@ -0,0 +1,224 @@
+//! Mermaid Diagram Rendering
+//!
+//! This module renders ```` ```mermaid ```` code blocks without a browser or
+//! JavaScript. Diagram source is parsed and laid out into a [`Scene`], which is
+//! painted with egui in rendered mode and written out as inline SVG for HTML
+//! export.
+//!
+//! # Supported Diagrams
+//! - Flowcharts (`graph` / `flowchart`)
+//! - Sequence diagrams (`sequenceDiagram`)
+//! - State diagrams (`stateDiagram` / `stateDiagram-v2`)
+//! - Class diagrams (`classDiagram`)
+//!
+//! Syntax errors are reported with the offending line so they can be shown in
+//! place of the diagram.
+//!
+//! # Example
+//! ```ignore
+//! use crate::markdown::mermaid::{to_svg, MermaidView};
+//!
+//! let svg = to_svg("graph LR\n  A --> B")?;
+//! MermaidView::new(source).colors(colors).show(ui);
+//! ```
+
+mod class;
+mod flowchart;
+mod layout;
+mod scene;
+mod sequence;
+mod state;
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use eframe::egui::{self, vec2, Color32, Response, RichText, Sense, Ui};
+
+pub use scene::{DiagramColors, Scene};
+
+/// A Mermaid syntax error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MermaidError {
+    /// 1-based line number within the diagram source
+    pub line: usize,
+    /// Description of the problem
+    pub message: String,
+}
+
+impl fmt::Display for MermaidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for MermaidError {}
+
+/// Parse and lay out a diagram.
+pub fn render(source: &str) -> Result<Scene, MermaidError> {
+    let mut lines = source
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .peekable();
+
+    // Skip a `---` front matter block (diagram title and config)
+    if lines.peek().is_some_and(|(_, line)| line.trim() == "---") {
+        lines.next();
+        for (_, line) in lines.by_ref() {
+            if line.trim() == "---" {
+                break;
+            }
+        }
+    }
+
+    // Comments (`%%`, including `%%{init}%%` directives) and blank lines are skipped
+    let lines: Vec<(usize, &str)> = lines
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with("%%")
+        })
+        .collect();
+    let Some(&(header_line, header)) = lines.first() else {
+        return Err(MermaidError {
+            line: 1,
+            message: "empty diagram".to_string(),
+        });
+    };
+    let body = &lines[1..];
+    let header = header.trim();
+    let keyword = header
+        .split(|c: char| c.is_whitespace() || c == ';')
+        .next()
+        .unwrap_or("");
+
+    match keyword {
+        "graph" | "flowchart" => Ok(flowchart::layout(&flowchart::parse((header_line, header), body)?)),
+        "sequenceDiagram" => Ok(sequence::layout(&sequence::parse(body)?)),
+        "stateDiagram" | "stateDiagram-v2" => Ok(state::layout(&state::parse(body)?)),
+        "classDiagram" | "classDiagram-v2" => Ok(class::layout(&class::parse(body)?)),
+        _ => Err(MermaidError {
+            line: header_line,
+            message: format!(
+                "unsupported diagram type '{}' (expected flowchart, sequenceDiagram, stateDiagram or classDiagram)",
+                keyword
+            ),
+        }),
+    }
+}
+
+/// Render a diagram as an inline SVG element.
+pub fn to_svg(source: &str) -> Result<String, MermaidError> {
+    render(source).map(|scene| scene::to_svg(&scene))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// egui Widget
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Widget that draws a Mermaid diagram, or the syntax error in its place.
+pub struct MermaidView<'a> {
+    source: &'a str,
+    font_size: f32,
+    colors: DiagramColors,
+}
+
+impl<'a> MermaidView<'a> {
+    /// Create a view for the given diagram source.
+    pub fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            font_size: scene::FONT_SIZE,
+            colors: DiagramColors::new(false, Color32::BLACK, Color32::WHITE),
+        }
+    }
+
+    /// Set the font size labels are drawn at; the diagram scales with it.
+    #[must_use]
+    pub fn font_size(mut self, size: f32) -> Self {
+        self.font_size = size;
+        self
+    }
+
+    /// Set the diagram colors.
+    #[must_use]
+    pub fn colors(mut self, colors: DiagramColors) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Lay out (cached per source) and paint the diagram.
+    pub fn show(self, ui: &mut Ui) -> Response {
+        let mut hasher = DefaultHasher::new();
+        self.source.hash(&mut hasher);
+        let id = egui::Id::new("mermaid_scene").with(hasher.finish());
+        let result = ui.ctx().data_mut(|data| {
+            data.get_temp_mut_or_insert_with(id, || Arc::new(render(self.source)))
+                .clone()
+        });
+
+        match result.as_ref() {
+            Ok(scene) => {
+                // Scale with the editor font, shrinking to fit narrow panes
+                let scale = (self.font_size / scene::FONT_SIZE)
+                    .min(ui.available_width() / scene.width.max(1.0))
+                    .max(0.1);
+                let (rect, response) =
+                    ui.allocate_exact_size(vec2(scene.width, scene.height) * scale, Sense::hover());
+                if ui.is_rect_visible(rect) {
+                    scene::paint(scene, ui.painter(), rect.min, scale, &self.colors);
+                }
+                response
+            }
+            Err(error) => ui.label(
+                RichText::new(format!("Mermaid syntax error — {}", error))
+                    .color(ui.visuals().error_fg_color)
+                    .size(self.font_size),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Number the lines of a diagram body, which starts below the header line.
+    pub fn lines(source: &str) -> Vec<(usize, &str)> {
+        source
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 2, l))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_dispatches_on_header() {
+        assert!(render("%% comment\n\ngraph TD\n  A --> B").is_ok());
+        assert!(render("---\ntitle: Example\n---\nsequenceDiagram\n  A->>B: hi").is_ok());
+        assert!(render("stateDiagram-v2\n  [*] --> Idle").is_ok());
+        assert!(render("classDiagram\n  Animal <|-- Dog").is_ok());
+    }
+
+    #[test]
+    fn test_render_errors() {
+        assert_eq!(render("").unwrap_err().message, "empty diagram");
+
+        let error = render("\npie title Pets\n  \"Dogs\" : 3").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert!(error.message.contains("'pie'"));
+
+        let error = render("graph TD\n  A --> B\n  B --> ").unwrap_err();
+        assert_eq!(error.to_string(), "Line 3: expected a node after the link");
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let svg = to_svg("graph LR\n  A[Start & stop] --> B").unwrap();
+        assert!(svg.starts_with("<svg class=\"mermaid\""));
+        assert!(svg.ends_with("</svg>"));
+        assert!(svg.contains(">Start &amp; stop</text>"));
+        assert!(svg.contains("<polygon"));
+    }
+}
@@ -0,0 +1,622 @@
+//! Diagram scenes
+//!
+//! Diagrams are laid out into a [`Scene`]: a flat list of primitive shapes in
+//! diagram coordinates. A scene is drawn either with the egui painter
+//! (rendered mode) or written out as SVG (HTML export), so both outputs share
+//! one layout.
+
+use eframe::egui::{self, pos2, vec2, Align2, Color32, FontId, Painter, Pos2, Rect, Stroke, Vec2};
+
+use crate::export::html::html_escape;
+
+/// Font size diagrams are laid out at.
+pub const FONT_SIZE: f32 = 14.0;
+
+/// Height of one line of label text.
+pub const LINE_HEIGHT: f32 = FONT_SIZE * 1.3;
+
+/// Estimated width of `text` at `size`.
+///
+/// Layout has to work without a font system (headless export), so text is
+/// measured with average glyph widths; wide (CJK) characters count double.
+pub fn text_width(text: &str, size: f32) -> f32 {
+    text.chars()
+        .map(|c| match c {
+            'i' | 'l' | 'j' | 't' | 'f' | 'r' | '.' | ',' | ':' | ';' | '\'' | '|' | '!' => 0.35,
+            'm' | 'w' | 'M' | 'W' => 0.85,
+            c if c.is_uppercase() => 0.68,
+            c if (c as u32) >= 0x1100 => 1.0,
+            _ => 0.56,
+        })
+        .sum::<f32>()
+        * size
+}
+
+/// Split a label into lines at `<br>` tags and newlines.
+pub fn label_lines(label: &str) -> Vec<String> {
+    label
+        .replace("<br/>", "\n")
+        .replace("<br />", "\n")
+        .replace("<br>", "\n")
+        .lines()
+        .map(|line| line.trim().to_string())
+        .collect()
+}
+
+/// Size of a (possibly multi-line) label.
+pub fn label_size(label: &str, size: f32) -> Vec2 {
+    let lines = label_lines(label);
+    let width = lines
+        .iter()
+        .map(|line| text_width(line, size))
+        .fold(0.0, f32::max);
+    vec2(width, lines.len().max(1) as f32 * size * 1.3)
+}
+
+/// How a shape is filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fill {
+    /// Outline only
+    None,
+    /// Node background
+    Node,
+    /// Note background
+    Note,
+    /// Solid line color (arrowheads, start states)
+    Line,
+    /// Page background (behind edge labels)
+    Background,
+}
+
+/// Horizontal alignment of text relative to its position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextAlign {
+    Left,
+    Center,
+}
+
+/// A primitive shape in diagram coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    /// Rectangle, optionally with rounded corners
+    Rect {
+        rect: Rect,
+        rounding: f32,
+        fill: Fill,
+        stroke: bool,
+        dashed: bool,
+    },
+    /// Closed convex polygon
+    Polygon {
+        points: Vec<Pos2>,
+        fill: Fill,
+        stroke: bool,
+    },
+    /// Circle
+    Circle {
+        center: Pos2,
+        radius: f32,
+        fill: Fill,
+        stroke: bool,
+    },
+    /// Open polyline
+    Line {
+        points: Vec<Pos2>,
+        dashed: bool,
+        width: f32,
+    },
+    /// Single line of text, vertically centered on `pos`
+    Text {
+        pos: Pos2,
+        text: String,
+        size: f32,
+        bold: bool,
+        align: TextAlign,
+    },
+}
+
+/// A laid-out diagram.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scene {
+    /// Width of the diagram
+    pub width: f32,
+    /// Height of the diagram
+    pub height: f32,
+    /// Shapes in drawing order
+    pub shapes: Vec<Shape>,
+}
+
+/// End decoration of a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Marker {
+    None,
+    /// Filled arrowhead
+    Arrow,
+    /// Two-stroke open arrowhead
+    OpenArrow,
+    /// Hollow triangle (inheritance)
+    Triangle,
+    /// Filled diamond (composition)
+    FilledDiamond,
+    /// Hollow diamond (aggregation)
+    Diamond,
+    /// Cross (destroyed / lost message)
+    Cross,
+    /// Small hollow circle
+    Circle,
+}
+
+impl Scene {
+    /// Add a rectangle with a line-color border.
+    pub fn rect(&mut self, rect: Rect, rounding: f32, fill: Fill) {
+        self.shapes.push(Shape::Rect {
+            rect,
+            rounding,
+            fill,
+            stroke: true,
+            dashed: false,
+        });
+    }
+
+    /// Add a filled polygon with a border.
+    pub fn polygon(&mut self, points: Vec<Pos2>, fill: Fill) {
+        self.shapes.push(Shape::Polygon {
+            points,
+            fill,
+            stroke: true,
+        });
+    }
+
+    /// Add a circle.
+    pub fn circle(&mut self, center: Pos2, radius: f32, fill: Fill, stroke: bool) {
+        self.shapes.push(Shape::Circle {
+            center,
+            radius,
+            fill,
+            stroke,
+        });
+    }
+
+    /// Add a polyline.
+    pub fn line(&mut self, points: Vec<Pos2>, dashed: bool, width: f32) {
+        self.shapes.push(Shape::Line {
+            points,
+            dashed,
+            width,
+        });
+    }
+
+    /// Add a single line of text.
+    pub fn text(&mut self, pos: Pos2, text: &str, bold: bool, align: TextAlign) {
+        self.shapes.push(Shape::Text {
+            pos,
+            text: text.to_string(),
+            size: FONT_SIZE,
+            bold,
+            align,
+        });
+    }
+
+    /// Add a (possibly multi-line) label centered on `center`.
+    pub fn label(&mut self, center: Pos2, label: &str) {
+        let lines = label_lines(label);
+        let top = center.y - lines.len() as f32 * LINE_HEIGHT / 2.0;
+        for (i, line) in lines.iter().enumerate() {
+            let y = top + (i as f32 + 0.5) * LINE_HEIGHT;
+            self.text(pos2(center.x, y), line, false, TextAlign::Center);
+        }
+    }
+
+    /// Add a label on a background box so it stays readable over lines.
+    pub fn boxed_label(&mut self, center: Pos2, label: &str) {
+        let size = label_size(label, FONT_SIZE) + vec2(8.0, 2.0);
+        self.shapes.push(Shape::Rect {
+            rect: Rect::from_center_size(center, size),
+            rounding: 2.0,
+            fill: Fill::Background,
+            stroke: false,
+            dashed: false,
+        });
+        self.label(center, label);
+    }
+
+    /// Add a line with end markers. The line stops short of markers that
+    /// have a body so it doesn't poke through them.
+    pub fn connector(
+        &mut self,
+        points: Vec<Pos2>,
+        dashed: bool,
+        width: f32,
+        start: Marker,
+        end: Marker,
+    ) {
+        if points.len() < 2 {
+            return;
+        }
+        let mut line = points.clone();
+        let n = line.len();
+        if let Some(inset) = marker_inset(end) {
+            line[n - 1] = step_back(line[n - 1], line[n - 2], inset);
+        }
+        if let Some(inset) = marker_inset(start) {
+            line[0] = step_back(line[0], line[1], inset);
+        }
+        self.line(line, dashed, width);
+        self.marker(points[n - 1], points[n - 2], end);
+        self.marker(points[0], points[1], start);
+    }
+
+    /// Add a marker whose tip is at `tip`, pointing away from `from`.
+    fn marker(&mut self, tip: Pos2, from: Pos2, marker: Marker) {
+        let direction = (tip - from).normalized();
+        if !direction.x.is_finite() || !direction.y.is_finite() {
+            return;
+        }
+        let normal = vec2(-direction.y, direction.x);
+        let at = |back: f32, side: f32| tip - direction * back + normal * side;
+        match marker {
+            Marker::None => {}
+            Marker::Arrow => self.polygon(vec![tip, at(10.0, 4.5), at(10.0, -4.5)], Fill::Line),
+            Marker::OpenArrow => self.line(vec![at(9.0, 4.5), tip, at(9.0, -4.5)], false, 1.5),
+            Marker::Triangle => {
+                self.polygon(vec![tip, at(14.0, 7.0), at(14.0, -7.0)], Fill::Background)
+            }
+            Marker::FilledDiamond | Marker::Diamond => {
+                let fill = if marker == Marker::Diamond {
+                    Fill::Background
+                } else {
+                    Fill::Line
+                };
+                self.polygon(vec![tip, at(8.0, 5.0), at(16.0, 0.0), at(8.0, -5.0)], fill);
+            }
+            Marker::Cross => {
+                self.line(vec![at(10.0, 5.0), at(0.0, -5.0)], false, 1.5);
+                self.line(vec![at(10.0, -5.0), at(0.0, 5.0)], false, 1.5);
+            }
+            Marker::Circle => self.circle(at(4.0, 0.0), 4.0, Fill::Background, true),
+        }
+    }
+
+    /// Move every shape by `offset`.
+    pub fn translate(&mut self, offset: Vec2) {
+        for shape in &mut self.shapes {
+            match shape {
+                Shape::Rect { rect, .. } => *rect = rect.translate(offset),
+                Shape::Polygon { points, .. } | Shape::Line { points, .. } => {
+                    points.iter_mut().for_each(|p| *p += offset)
+                }
+                Shape::Circle { center, .. } => *center += offset,
+                Shape::Text { pos, .. } => *pos += offset,
+            }
+        }
+    }
+
+    /// Bounding box of all shapes (text estimated).
+    pub fn bounds(&self) -> Rect {
+        let mut bounds = Rect::NOTHING;
+        for shape in &self.shapes {
+            match shape {
+                Shape::Rect { rect, .. } => bounds = bounds.union(*rect),
+                Shape::Polygon { points, .. } | Shape::Line { points, .. } => {
+                    points.iter().for_each(|p| bounds.extend_with(*p))
+                }
+                Shape::Circle { center, radius, .. } => {
+                    bounds =
+                        bounds.union(Rect::from_center_size(*center, Vec2::splat(radius * 2.0)))
+                }
+                Shape::Text {
+                    pos,
+                    text,
+                    size,
+                    align,
+                    ..
+                } => {
+                    let width = text_width(text, *size);
+                    let left = match align {
+                        TextAlign::Left => pos.x,
+                        TextAlign::Center => pos.x - width / 2.0,
+                    };
+                    bounds = bounds.union(Rect::from_min_size(
+                        pos2(left, pos.y - size * 0.65),
+                        vec2(width, size * 1.3),
+                    ));
+                }
+            }
+        }
+        bounds
+    }
+
+    /// Move the diagram to the origin with `margin` around it and set its size.
+    pub fn fit(&mut self, margin: f32) {
+        let bounds = self.bounds();
+        if !bounds.is_positive() {
+            self.width = 2.0 * margin;
+            self.height = 2.0 * margin;
+            return;
+        }
+        self.translate(vec2(margin, margin) - bounds.min.to_vec2());
+        self.width = bounds.width() + 2.0 * margin;
+        self.height = bounds.height() + 2.0 * margin;
+    }
+}
+
+/// How far a line stops short of a marker's tip.
+fn marker_inset(marker: Marker) -> Option<f32> {
+    match marker {
+        Marker::Arrow => Some(8.0),
+        Marker::Triangle => Some(14.0),
+        Marker::FilledDiamond | Marker::Diamond => Some(16.0),
+        Marker::Circle => Some(8.0),
+        _ => None,
+    }
+}
+
+/// Point `distance` back from `point` towards `toward`.
+fn step_back(point: Pos2, toward: Pos2, distance: f32) -> Pos2 {
+    let length = (toward - point).length();
+    if length <= distance {
+        return point;
+    }
+    point + (toward - point) * (distance / length)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// egui Painting
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Colors for painting a scene.
+#[derive(Debug, Clone, Copy)]
+pub struct DiagramColors {
+    /// Lines, borders and arrowheads
+    pub line: Color32,
+    /// Label text
+    pub text: Color32,
+    /// Node background
+    pub node: Color32,
+    /// Note background
+    pub note: Color32,
+    /// Page background
+    pub background: Color32,
+}
+
+impl DiagramColors {
+    /// Colors for a light or dark page.
+    pub fn new(dark_mode: bool, text: Color32, background: Color32) -> Self {
+        if dark_mode {
+            Self {
+                line: Color32::from_rgb(150, 160, 175),
+                text,
+                node: Color32::from_rgb(45, 55, 72),
+                note: Color32::from_rgb(80, 72, 40),
+                background,
+            }
+        } else {
+            Self {
+                line: Color32::from_rgb(90, 100, 115),
+                text,
+                node: Color32::from_rgb(236, 241, 252),
+                note: Color32::from_rgb(255, 248, 197),
+                background,
+            }
+        }
+    }
+
+    fn fill(&self, fill: Fill) -> Color32 {
+        match fill {
+            Fill::None => Color32::TRANSPARENT,
+            Fill::Node => self.node,
+            Fill::Note => self.note,
+            Fill::Line => self.line,
+            Fill::Background => self.background,
+        }
+    }
+}
+
+/// Paint `scene` scaled by `scale` with its top-left corner at `origin`.
+pub fn paint(scene: &Scene, painter: &Painter, origin: Pos2, scale: f32, colors: &DiagramColors) {
+    let map = |p: Pos2| origin + p.to_vec2() * scale;
+    let stroke = Stroke::new(1.2 * scale.max(0.5), colors.line);
+    for shape in &scene.shapes {
+        match shape {
+            Shape::Rect {
+                rect,
+                rounding,
+                fill,
+                stroke: has_stroke,
+                dashed,
+            } => {
+                let rect = Rect::from_min_max(map(rect.min), map(rect.max));
+                painter.rect_filled(rect, rounding * scale, colors.fill(*fill));
+                if *dashed {
+                    let corners = [
+                        rect.left_top(),
+                        rect.right_top(),
+                        rect.right_bottom(),
+                        rect.left_bottom(),
+                        rect.left_top(),
+                    ];
+                    painter.extend(egui::Shape::dashed_line(&corners, stroke, 5.0, 4.0));
+                } else if *has_stroke {
+                    painter.rect_stroke(rect, rounding * scale, stroke);
+                }
+            }
+            Shape::Polygon {
+                points,
+                fill,
+                stroke: has_stroke,
+            } => {
+                let points = points.iter().map(|p| map(*p)).collect();
+                let outline = if *has_stroke { stroke } else { Stroke::NONE };
+                painter.add(egui::Shape::convex_polygon(
+                    points,
+                    colors.fill(*fill),
+                    outline,
+                ));
+            }
+            Shape::Circle {
+                center,
+                radius,
+                fill,
+                stroke: has_stroke,
+            } => {
+                let outline = if *has_stroke { stroke } else { Stroke::NONE };
+                painter.circle(map(*center), radius * scale, colors.fill(*fill), outline);
+            }
+            Shape::Line {
+                points,
+                dashed,
+                width,
+            } => {
+                let points: Vec<Pos2> = points.iter().map(|p| map(*p)).collect();
+                let stroke = Stroke::new(width * scale.max(0.5), colors.line);
+                if *dashed {
+                    painter.extend(egui::Shape::dashed_line(&points, stroke, 5.0, 4.0));
+                } else {
+                    painter.add(egui::Shape::line(points, stroke));
+                }
+            }
+            Shape::Text {
+                pos,
+                text,
+                size,
+                bold,
+                align,
+            } => {
+                let anchor = match align {
+                    TextAlign::Left => Align2::LEFT_CENTER,
+                    TextAlign::Center => Align2::CENTER_CENTER,
+                };
+                let font = FontId::proportional(size * scale);
+                let galley = painter.layout_no_wrap(text.clone(), font, colors.text);
+                let rect = anchor.anchor_size(map(*pos), galley.size());
+                painter.galley(rect.min, galley.clone(), colors.text);
+                if *bold {
+                    // Fake bold by drawing the text twice, slightly offset
+                    painter.galley(rect.min + vec2(0.6 * scale, 0.0), galley, colors.text);
+                }
+            }
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// SVG Output
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Write `scene` as a standalone inline SVG element.
+///
+/// Lines and text use `currentColor`, so the diagram follows the text color
+/// of the page it is embedded in.
+pub fn to_svg(scene: &Scene) -> String {
+    let mut svg = format!(
+        "<svg class=\"mermaid\" xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {w:.1} {h:.1}\" width=\"{w:.1}\" height=\"{h:.1}\" style=\"max-width: 100%; height: auto;\" font-family=\"-apple-system, BlinkMacSystemFont, 'Segoe UI', Helvetica, Arial, sans-serif\" font-size=\"{size}\">",
+        w = scene.width,
+        h = scene.height,
+        size = FONT_SIZE
+    );
+    for shape in &scene.shapes {
+        match shape {
+            Shape::Rect {
+                rect,
+                rounding,
+                fill,
+                stroke,
+                dashed,
+            } => svg.push_str(&format!(
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" rx=\"{:.1}\" {}{}/>",
+                rect.min.x,
+                rect.min.y,
+                rect.width(),
+                rect.height(),
+                rounding,
+                svg_fill(*fill),
+                svg_stroke(*stroke, *dashed, 1.2),
+            )),
+            Shape::Polygon {
+                points,
+                fill,
+                stroke,
+            } => svg.push_str(&format!(
+                "<polygon points=\"{}\" {}{}/>",
+                svg_points(points),
+                svg_fill(*fill),
+                svg_stroke(*stroke, false, 1.2),
+            )),
+            Shape::Circle {
+                center,
+                radius,
+                fill,
+                stroke,
+            } => svg.push_str(&format!(
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" {}{}/>",
+                center.x,
+                center.y,
+                radius,
+                svg_fill(*fill),
+                svg_stroke(*stroke, false, 1.2),
+            )),
+            Shape::Line {
+                points,
+                dashed,
+                width,
+            } => svg.push_str(&format!(
+                "<polyline points=\"{}\" fill=\"none\"{}/>",
+                svg_points(points),
+                svg_stroke(true, *dashed, *width),
+            )),
+            Shape::Text {
+                pos,
+                text,
+                size,
+                bold,
+                align,
+            } => svg.push_str(&format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"{}\" text-anchor=\"{}\" dominant-baseline=\"central\" fill=\"currentColor\"{}>{}</text>",
+                pos.x,
+                pos.y,
+                size,
+                match align {
+                    TextAlign::Left => "start",
+                    TextAlign::Center => "middle",
+                },
+                if *bold { " font-weight=\"bold\"" } else { "" },
+                html_escape(text),
+            )),
+        }
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn svg_points(points: &[Pos2]) -> String {
+    points
+        .iter()
+        .map(|p| format!("{:.1},{:.1}", p.x, p.y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn svg_fill(fill: Fill) -> &'static str {
+    match fill {
+        Fill::None => "fill=\"none\"",
+        Fill::Node => "fill=\"currentColor\" fill-opacity=\"0.08\"",
+        Fill::Note => "fill=\"#f5d90a\" fill-opacity=\"0.25\"",
+        Fill::Line => "fill=\"currentColor\"",
+        Fill::Background => "class=\"mermaid-background\" fill=\"#ffffff\"",
+    }
+}
+
+fn svg_stroke(stroke: bool, dashed: bool, width: f32) -> String {
+    if !stroke {
+        return String::new();
+    }
+    let dash = if dashed {
+        " stroke-dasharray=\"5 4\""
+    } else {
+        ""
+    };
+    format!(
+        " stroke=\"currentColor\" stroke-width=\"{}\"{}",
+        width, dash
+    )
+}
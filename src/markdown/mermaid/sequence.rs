@@ -0,0 +1,773 @@
+//! Sequence diagrams (`sequenceDiagram`)
+//!
+//! Supports participants and actors (with `as` aliases), messages with solid
+//! or dotted lines and arrow, open, cross or async ends, activations (`+`/`-`
+//! and `activate`/`deactivate`), notes, and `loop`/`alt`/`opt`/`par`/
+//! `critical`/`break`/`rect` blocks.
+
+use eframe::egui::{pos2, vec2, Rect};
+
+use super::scene::{label_size, text_width, Fill, Marker, Scene, Shape, TextAlign, FONT_SIZE};
+use super::MermaidError;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Model
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A participant (lifeline).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Participant {
+    pub id: String,
+    pub label: String,
+    /// Drawn as a stick figure instead of a box
+    pub actor: bool,
+}
+
+/// Where a note is placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotePlacement {
+    LeftOf(usize),
+    RightOf(usize),
+    Over(usize, usize),
+}
+
+/// A sequence diagram statement, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Message {
+        from: usize,
+        to: usize,
+        text: String,
+        dashed: bool,
+        end: Marker,
+        /// `+` activates the receiver, `-` deactivates the sender
+        activate: Option<bool>,
+    },
+    Note {
+        placement: NotePlacement,
+        text: String,
+    },
+    Activate(usize),
+    Deactivate(usize),
+    /// Start of a `loop`/`alt`/... block
+    BlockStart {
+        kind: String,
+        label: String,
+    },
+    /// `else`/`and`/`option` section inside a block
+    BlockSection {
+        label: String,
+    },
+    BlockEnd,
+}
+
+/// A parsed sequence diagram.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SequenceDiagram {
+    pub participants: Vec<Participant>,
+    pub events: Vec<Event>,
+}
+
+impl SequenceDiagram {
+    fn participant(&mut self, id: &str) -> usize {
+        if let Some(i) = self.participants.iter().position(|p| p.id == id) {
+            return i;
+        }
+        self.participants.push(Participant {
+            id: id.to_string(),
+            label: id.to_string(),
+            actor: false,
+        });
+        self.participants.len() - 1
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Parser
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Block keywords, with the section keywords each one accepts.
+const BLOCKS: &[(&str, &[&str])] = &[
+    ("loop", &[]),
+    ("alt", &["else"]),
+    ("opt", &[]),
+    ("par", &["and"]),
+    ("critical", &["option"]),
+    ("break", &[]),
+    ("rect", &[]),
+    ("box", &[]),
+];
+
+/// Message arrows, longest first.
+const ARROWS: &[(&str, bool, Marker)] = &[
+    ("-->>", true, Marker::Arrow),
+    ("->>", false, Marker::Arrow),
+    ("-->", true, Marker::None),
+    ("->", false, Marker::None),
+    ("--x", true, Marker::Cross),
+    ("-x", false, Marker::Cross),
+    ("--)", true, Marker::OpenArrow),
+    ("-)", false, Marker::OpenArrow),
+];
+
+/// Parse a sequence diagram from the lines after its header.
+pub fn parse(lines: &[(usize, &str)]) -> Result<SequenceDiagram, MermaidError> {
+    let mut diagram = SequenceDiagram::default();
+    // Open blocks: (kind, line it was opened on)
+    let mut open: Vec<(&str, usize)> = Vec::new();
+
+    for &(line_number, line) in lines {
+        let line = line.trim().trim_end_matches(';');
+        let error = |message: String| MermaidError {
+            line: line_number,
+            message,
+        };
+        let (keyword, rest) = match line.split_once(char::is_whitespace) {
+            Some((keyword, rest)) => (keyword, rest.trim()),
+            None => (line, ""),
+        };
+
+        match keyword {
+            "participant" | "actor" => {
+                if rest.is_empty() {
+                    return Err(error(format!("'{}' needs a name", keyword)));
+                }
+                let (id, label) = match rest.split_once(" as ") {
+                    Some((id, label)) => (id.trim(), label.trim()),
+                    None => (rest, rest),
+                };
+                let index = diagram.participant(id);
+                diagram.participants[index].label = label.to_string();
+                diagram.participants[index].actor = keyword == "actor";
+            }
+            "activate" | "deactivate" => {
+                let index = diagram.participant(rest);
+                diagram.events.push(if keyword == "activate" {
+                    Event::Activate(index)
+                } else {
+                    Event::Deactivate(index)
+                });
+            }
+            "autonumber" | "title" | "accTitle" | "accDescr" | "links" | "link" | "properties" => {}
+            "end" => {
+                if open.pop().is_none() {
+                    return Err(error("'end' without an open block".to_string()));
+                }
+                diagram.events.push(Event::BlockEnd);
+            }
+            _ if keyword.eq_ignore_ascii_case("note") => {
+                let event = parse_note(&mut diagram, rest).ok_or_else(|| {
+                    error(
+                        "expected 'Note left of', 'right of' or 'over' followed by ': text'"
+                            .to_string(),
+                    )
+                })?;
+                diagram.events.push(event);
+            }
+            _ => {
+                if let Some(&(kind, _)) = BLOCKS.iter().find(|(kind, _)| *kind == keyword) {
+                    open.push((kind, line_number));
+                    let label = if kind == "rect" || kind == "box" {
+                        String::new()
+                    } else {
+                        rest.to_string()
+                    };
+                    diagram.events.push(Event::BlockStart {
+                        kind: kind.to_string(),
+                        label,
+                    });
+                } else if BLOCKS
+                    .iter()
+                    .any(|(_, sections)| sections.contains(&keyword))
+                {
+                    let allowed = open.last().is_some_and(|(kind, _)| {
+                        BLOCKS
+                            .iter()
+                            .any(|(k, sections)| k == kind && sections.contains(&keyword))
+                    });
+                    if !allowed {
+                        return Err(error(format!("'{}' outside of a matching block", keyword)));
+                    }
+                    diagram.events.push(Event::BlockSection {
+                        label: rest.to_string(),
+                    });
+                } else {
+                    let event = parse_message(&mut diagram, line)
+                        .ok_or_else(|| error(format!("unrecognized statement '{}'", line)))?;
+                    diagram.events.push(event);
+                }
+            }
+        }
+    }
+
+    if let Some((kind, line)) = open.pop() {
+        return Err(MermaidError {
+            line,
+            message: format!("'{}' block is never closed with 'end'", kind),
+        });
+    }
+    Ok(diagram)
+}
+
+/// Parse `left of A: text`, `right of A: text` or `over A[,B]: text`.
+fn parse_note(diagram: &mut SequenceDiagram, rest: &str) -> Option<Event> {
+    let (target, text) = rest.split_once(':')?;
+    let target = target.trim();
+    let placement = if let Some(id) = target.strip_prefix("left of ") {
+        NotePlacement::LeftOf(diagram.participant(id.trim()))
+    } else if let Some(id) = target.strip_prefix("right of ") {
+        NotePlacement::RightOf(diagram.participant(id.trim()))
+    } else if let Some(ids) = target.strip_prefix("over ") {
+        let (first, second) = ids.split_once(',').unwrap_or((ids, ids));
+        let first = diagram.participant(first.trim());
+        let second = diagram.participant(second.trim());
+        NotePlacement::Over(first.min(second), first.max(second))
+    } else {
+        return None;
+    };
+    Some(Event::Note {
+        placement,
+        text: text.trim().to_string(),
+    })
+}
+
+/// Parse `A->>+B: text`.
+fn parse_message(diagram: &mut SequenceDiagram, line: &str) -> Option<Event> {
+    let (arrow_at, &(arrow, dashed, end)) = line.char_indices().find_map(|(i, _)| {
+        ARROWS
+            .iter()
+            .find(|(arrow, _, _)| line[i..].starts_with(arrow))
+            .map(|found| (i, found))
+    })?;
+    let from = line[..arrow_at].trim();
+    let rest = line[arrow_at + arrow.len()..].trim_start();
+    let (activate, rest) = match rest.chars().next() {
+        Some('+') => (Some(true), &rest[1..]),
+        Some('-') => (Some(false), &rest[1..]),
+        _ => (None, rest),
+    };
+    let (to, text) = rest.split_once(':').unwrap_or((rest, ""));
+    let to = to.trim();
+    let valid_id = |id: &str| !id.is_empty() && !id.contains(char::is_whitespace);
+    if !valid_id(from) || !valid_id(to) {
+        return None;
+    }
+    Some(Event::Message {
+        from: diagram.participant(from),
+        to: diagram.participant(to),
+        text: text.trim().to_string(),
+        dashed,
+        end,
+        activate,
+    })
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Layout
+// ─────────────────────────────────────────────────────────────────────────────
+
+const BOX_HEIGHT: f32 = 40.0;
+const ACTOR_HEIGHT: f32 = 58.0;
+const ACTIVATION_WIDTH: f32 = 10.0;
+const SELF_LOOP_WIDTH: f32 = 30.0;
+
+/// An open block while laying out.
+struct Frame {
+    kind: String,
+    label: String,
+    top: f32,
+    sections: Vec<(f32, String)>,
+    left: f32,
+    right: f32,
+}
+
+/// Lay out a sequence diagram.
+pub fn layout(diagram: &SequenceDiagram) -> Scene {
+    let xs = column_positions(diagram);
+    let n = xs.len();
+    let header = if diagram.participants.iter().any(|p| p.actor) {
+        ACTOR_HEIGHT
+    } else {
+        BOX_HEIGHT
+    };
+
+    // Frames and activations go below messages, so they are collected separately
+    let mut background = Scene::default();
+    let mut scene = Scene::default();
+    let mut active: Vec<Vec<f32>> = vec![Vec::new(); n];
+    let mut frames: Vec<Frame> = Vec::new();
+    let mut y = header + 20.0;
+
+    let depth = |active: &Vec<Vec<f32>>, i: usize| active[i].len() as f32;
+    let widen = |frames: &mut Vec<Frame>, left: f32, right: f32| {
+        if let Some(frame) = frames.last_mut() {
+            frame.left = frame.left.min(left);
+            frame.right = frame.right.max(right);
+        }
+    };
+
+    for event in &diagram.events {
+        match event {
+            Event::Message {
+                from,
+                to,
+                text,
+                dashed,
+                end,
+                activate,
+            } => {
+                let (from, to) = (*from, *to);
+                let text_height = if text.is_empty() {
+                    0.0
+                } else {
+                    label_size(text, FONT_SIZE).y
+                };
+                let x_from = xs[from];
+                let x_to = xs[to];
+                if from == to {
+                    let x = x_from + depth(&active, from) * ACTIVATION_WIDTH / 2.0;
+                    let top = y + 4.0;
+                    let right = x + SELF_LOOP_WIDTH;
+                    if !text.is_empty() {
+                        let width = label_size(text, FONT_SIZE).x;
+                        scene.label(pos2(right + 6.0 + width / 2.0, top + 10.0), text);
+                    }
+                    scene.connector(
+                        vec![
+                            pos2(x, top),
+                            pos2(right, top),
+                            pos2(right, top + 20.0),
+                            pos2(x, top + 20.0),
+                        ],
+                        *dashed,
+                        1.3,
+                        Marker::None,
+                        *end,
+                    );
+                    widen(&mut frames, x, right + label_size(text, FONT_SIZE).x + 10.0);
+                    y = top + 20.0 + 14.0;
+                } else {
+                    y += text_height + 6.0;
+                    if !text.is_empty() {
+                        scene.label(
+                            pos2((x_from + x_to) / 2.0, y - 4.0 - text_height / 2.0),
+                            text,
+                        );
+                    }
+                    let toward = if x_to > x_from { 1.0 } else { -1.0 };
+                    let inset = |i: usize, side: f32| {
+                        if active[i].is_empty() {
+                            0.0
+                        } else {
+                            side * ACTIVATION_WIDTH / 2.0
+                        }
+                    };
+                    let mut start = x_from + inset(from, toward);
+                    let mut stop = x_to + inset(to, -toward);
+                    if *activate == Some(true) {
+                        // The receiver's new activation starts at this message
+                        stop = x_to - toward * ACTIVATION_WIDTH / 2.0;
+                    }
+                    if (stop - start).abs() < 1.0 {
+                        start = x_from;
+                        stop = x_to;
+                    }
+                    scene.connector(
+                        vec![pos2(start, y), pos2(stop, y)],
+                        *dashed,
+                        1.3,
+                        Marker::None,
+                        *end,
+                    );
+                    widen(&mut frames, x_from.min(x_to), x_from.max(x_to));
+                    y += 16.0;
+                }
+                match activate {
+                    Some(true) => active[to].push(y - 16.0),
+                    Some(false) => {
+                        if let Some(top) = active[from].pop() {
+                            activation(
+                                &mut background,
+                                xs[from],
+                                top,
+                                y - 16.0,
+                                active[from].len(),
+                            );
+                        }
+                    }
+                    None => {}
+                }
+            }
+            Event::Note { placement, text } => {
+                let size = label_size(text, FONT_SIZE) + vec2(20.0, 12.0);
+                let (left, right) = match *placement {
+                    NotePlacement::LeftOf(i) => (xs[i] - 10.0 - size.x, xs[i] - 10.0),
+                    NotePlacement::RightOf(i) => (xs[i] + 10.0, xs[i] + 10.0 + size.x),
+                    NotePlacement::Over(a, b) => {
+                        let center = (xs[a] + xs[b]) / 2.0;
+                        let width = size.x.max(xs[b] - xs[a] + 40.0);
+                        (center - width / 2.0, center + width / 2.0)
+                    }
+                };
+                y += 6.0;
+                let rect = Rect::from_min_max(pos2(left, y), pos2(right, y + size.y));
+                scene.rect(rect, 0.0, Fill::Note);
+                scene.label(rect.center(), text);
+                widen(&mut frames, left, right);
+                y += size.y + 10.0;
+            }
+            Event::Activate(i) => active[*i].push(y),
+            Event::Deactivate(i) => {
+                if let Some(top) = active[*i].pop() {
+                    activation(&mut background, xs[*i], top, y, active[*i].len());
+                }
+            }
+            Event::BlockStart { kind, label } => {
+                y += 8.0;
+                frames.push(Frame {
+                    kind: kind.clone(),
+                    label: label.clone(),
+                    top: y,
+                    sections: Vec::new(),
+                    left: f32::INFINITY,
+                    right: f32::NEG_INFINITY,
+                });
+                y += if kind == "rect" || kind == "box" {
+                    8.0
+                } else {
+                    28.0
+                };
+            }
+            Event::BlockSection { label } => {
+                y += 6.0;
+                if let Some(frame) = frames.last_mut() {
+                    frame.sections.push((y, label.clone()));
+                }
+                y += 24.0;
+            }
+            Event::BlockEnd => {
+                let Some(mut frame) = frames.pop() else {
+                    continue;
+                };
+                if !frame.left.is_finite() {
+                    frame.left = xs.first().copied().unwrap_or(0.0);
+                    frame.right = xs.last().copied().unwrap_or(0.0);
+                }
+                let tag_width = text_width(&frame.kind, FONT_SIZE) + 16.0;
+                let label_width = text_width(&format!("[{}]", frame.label), FONT_SIZE);
+                frame.right = frame.right.max(frame.left + tag_width + label_width + 20.0);
+                let rect = Rect::from_min_max(
+                    pos2(frame.left - 16.0, frame.top),
+                    pos2(frame.right + 16.0, y + 4.0),
+                );
+                draw_frame(&mut background, &frame, rect);
+                widen(&mut frames, rect.left(), rect.right());
+                y += 12.0;
+            }
+        }
+    }
+
+    // Close activations that were never deactivated
+    for (i, tops) in active.iter().enumerate() {
+        for (level, &top) in tops.iter().enumerate() {
+            activation(&mut background, xs[i], top, y, level);
+        }
+    }
+
+    y += 10.0;
+    let mut lifelines = Scene::default();
+    for (participant, &x) in diagram.participants.iter().zip(&xs) {
+        lifelines.line(vec![pos2(x, header), pos2(x, y)], true, 1.0);
+        draw_participant(&mut scene, participant, x, 0.0, header);
+        draw_participant(&mut scene, participant, x, y, header);
+    }
+
+    lifelines.shapes.extend(background.shapes);
+    lifelines.shapes.extend(scene.shapes);
+    let mut scene = lifelines;
+    scene.fit(8.0);
+    scene
+}
+
+/// Horizontal centers of the lifelines, spread so every message label and
+/// note fits between them.
+fn column_positions(diagram: &SequenceDiagram) -> Vec<f32> {
+    let n = diagram.participants.len();
+    let widths: Vec<f32> = diagram
+        .participants
+        .iter()
+        .map(|p| (label_size(&p.label, FONT_SIZE).x + 24.0).max(80.0))
+        .collect();
+    let mut xs = Vec::with_capacity(n);
+    let mut x = 0.0;
+    for i in 0..n {
+        if i > 0 {
+            x += (widths[i - 1] + widths[i]) / 2.0 + 40.0;
+        }
+        xs.push(x);
+    }
+
+    // (left column, right column, space needed between them)
+    let mut needs: Vec<(usize, usize, f32)> = Vec::new();
+    for event in &diagram.events {
+        match event {
+            Event::Message { from, to, text, .. } => {
+                let width = label_size(text, FONT_SIZE).x;
+                if from == to {
+                    needs.push((*from, from + 1, width + SELF_LOOP_WIDTH + 30.0));
+                } else {
+                    needs.push(((*from).min(*to), (*from).max(*to), width + 30.0));
+                }
+            }
+            Event::Note { placement, text } => {
+                let width = label_size(text, FONT_SIZE).x + 40.0;
+                match *placement {
+                    NotePlacement::RightOf(i) => needs.push((i, i + 1, width)),
+                    NotePlacement::LeftOf(i) if i > 0 => needs.push((i - 1, i, width)),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    needs.sort_by_key(|(a, b, _)| b - a);
+    for (a, b, need) in needs {
+        if b >= n {
+            continue;
+        }
+        let deficit = need - (xs[b] - xs[a]);
+        if deficit > 0.0 {
+            xs[b..].iter_mut().for_each(|x| *x += deficit);
+        }
+    }
+    xs
+}
+
+fn activation(scene: &mut Scene, x: f32, top: f32, bottom: f32, level: usize) {
+    let x = x + level as f32 * ACTIVATION_WIDTH / 2.0;
+    scene.rect(
+        Rect::from_min_max(
+            pos2(x - ACTIVATION_WIDTH / 2.0, top),
+            pos2(x + ACTIVATION_WIDTH / 2.0, bottom.max(top + 8.0)),
+        ),
+        0.0,
+        Fill::Node,
+    );
+}
+
+fn draw_frame(scene: &mut Scene, frame: &Frame, rect: Rect) {
+    if frame.kind == "box" {
+        return;
+    }
+    if frame.kind == "rect" {
+        scene.shapes.push(Shape::Rect {
+            rect,
+            rounding: 0.0,
+            fill: Fill::Node,
+            stroke: false,
+            dashed: false,
+        });
+        return;
+    }
+    scene.shapes.push(Shape::Rect {
+        rect,
+        rounding: 0.0,
+        fill: Fill::None,
+        stroke: true,
+        dashed: false,
+    });
+    // Keyword tag in the top-left corner with a clipped corner
+    let tag_width = text_width(&frame.kind, FONT_SIZE) + 16.0;
+    let (l, t) = (rect.left(), rect.top());
+    scene.polygon(
+        vec![
+            pos2(l, t),
+            pos2(l + tag_width, t),
+            pos2(l + tag_width, t + 14.0),
+            pos2(l + tag_width - 6.0, t + 20.0),
+            pos2(l, t + 20.0),
+        ],
+        Fill::Node,
+    );
+    scene.text(pos2(l + 6.0, t + 10.0), &frame.kind, true, TextAlign::Left);
+    if !frame.label.is_empty() {
+        scene.text(
+            pos2(l + tag_width + 8.0, t + 10.0),
+            &format!("[{}]", frame.label),
+            false,
+            TextAlign::Left,
+        );
+    }
+    for (y, label) in &frame.sections {
+        scene.shapes.push(Shape::Line {
+            points: vec![pos2(rect.left(), *y), pos2(rect.right(), *y)],
+            dashed: true,
+            width: 1.0,
+        });
+        if !label.is_empty() {
+            scene.text(
+                pos2(rect.center().x, y + 12.0),
+                &format!("[{}]", label),
+                false,
+                TextAlign::Center,
+            );
+        }
+    }
+}
+
+fn draw_participant(scene: &mut Scene, participant: &Participant, x: f32, top: f32, height: f32) {
+    if participant.actor {
+        let head = pos2(x, top + 9.0);
+        scene.circle(head, 7.0, Fill::Node, true);
+        scene.line(vec![pos2(x, top + 16.0), pos2(x, top + 30.0)], false, 1.3);
+        scene.line(
+            vec![pos2(x - 11.0, top + 21.0), pos2(x + 11.0, top + 21.0)],
+            false,
+            1.3,
+        );
+        scene.line(
+            vec![
+                pos2(x - 9.0, top + 40.0),
+                pos2(x, top + 30.0),
+                pos2(x + 9.0, top + 40.0),
+            ],
+            false,
+            1.3,
+        );
+        scene.label(pos2(x, top + height - 9.0), &participant.label);
+        return;
+    }
+    let width = (label_size(&participant.label, FONT_SIZE).x + 24.0).max(80.0);
+    let rect = Rect::from_min_max(
+        pos2(x - width / 2.0, top + height - BOX_HEIGHT),
+        pos2(x + width / 2.0, top + height),
+    );
+    scene.rect(rect, 3.0, Fill::Node);
+    scene.label(rect.center(), &participant.label);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::mermaid::tests::lines;
+
+    #[test]
+    fn test_parse_messages_and_participants() {
+        let diagram = parse(&lines(
+            "participant A as Alice\nactor B as Bob\nA->>+B: Hello\nB-->>-A: Hi!\nA-)C: async\nC--xA: lost\nNote over A,B: chat",
+        ))
+        .unwrap();
+        let names: Vec<(&str, &str, bool)> = diagram
+            .participants
+            .iter()
+            .map(|p| (p.id.as_str(), p.label.as_str(), p.actor))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("A", "Alice", false), ("B", "Bob", true), ("C", "C", false)]
+        );
+        assert_eq!(
+            diagram.events[0],
+            Event::Message {
+                from: 0,
+                to: 1,
+                text: "Hello".to_string(),
+                dashed: false,
+                end: Marker::Arrow,
+                activate: Some(true),
+            }
+        );
+        assert!(matches!(
+            diagram.events[1],
+            Event::Message {
+                dashed: true,
+                activate: Some(false),
+                ..
+            }
+        ));
+        assert!(matches!(
+            diagram.events[2],
+            Event::Message {
+                end: Marker::OpenArrow,
+                ..
+            }
+        ));
+        assert!(matches!(
+            diagram.events[3],
+            Event::Message {
+                end: Marker::Cross,
+                dashed: true,
+                ..
+            }
+        ));
+        assert_eq!(
+            diagram.events[4],
+            Event::Note {
+                placement: NotePlacement::Over(0, 1),
+                text: "chat".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_blocks() {
+        let diagram = parse(&lines(
+            "loop Every minute\n  A->>B: ping\nend\nalt ok\n  B->>A: pong\nelse failed\n  B-xA: error\nend",
+        ))
+        .unwrap();
+        assert_eq!(
+            diagram.events[0],
+            Event::BlockStart {
+                kind: "loop".to_string(),
+                label: "Every minute".to_string()
+            }
+        );
+        assert_eq!(
+            diagram
+                .events
+                .iter()
+                .filter(|e| **e == Event::BlockEnd)
+                .count(),
+            2
+        );
+        assert!(diagram.events.contains(&Event::BlockSection {
+            label: "failed".to_string()
+        }));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse(&lines("A->>B: hi\nend")).unwrap_err();
+        assert_eq!(error.line, 3);
+
+        let error = parse(&lines("loop forever\nA->>B: hi")).unwrap_err();
+        assert_eq!(error.line, 2);
+        assert!(error.message.contains("never closed"));
+
+        let error = parse(&lines("A->>B: hi\nthis is not valid")).unwrap_err();
+        assert_eq!(error.line, 3);
+
+        assert!(parse(&lines("opt x\nelse y\nend")).is_err());
+    }
+
+    #[test]
+    fn test_layout_spaces_columns_for_labels() {
+        let diagram = parse(&lines(
+            "A->>B: a rather long message label that needs room\nB->>B: think\nNote right of B: done",
+        ))
+        .unwrap();
+        let xs = column_positions(&diagram);
+        let label = text_width("a rather long message label that needs room", FONT_SIZE);
+        assert!(xs[1] - xs[0] >= label);
+
+        let scene = layout(&diagram);
+        assert!(scene.width >= label);
+        // Participants are drawn at the top and the bottom
+        let labels = scene
+            .shapes
+            .iter()
+            .filter(|s| matches!(s, Shape::Text { text, .. } if text == "A"))
+            .count();
+        assert_eq!(labels, 2);
+    }
+}
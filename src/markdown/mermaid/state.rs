@@ -0,0 +1,432 @@
+//! State diagrams (`stateDiagram` / `stateDiagram-v2`)
+//!
+//! Supports transitions with labels, start and end states (`[*]`), state
+//! descriptions (`state "Long name" as S`, `S : description`), fork, join and
+//! choice pseudo-states, and composite states. Composite states are flattened:
+//! the composite state leads into its initial substate and each composite gets
+//! its own end state. Notes are accepted but not drawn.
+
+use eframe::egui::{pos2, vec2, Pos2, Rect, Vec2};
+
+use super::layout::{self, Direction};
+use super::scene::{label_size, Fill, Marker, Scene, FONT_SIZE, LINE_HEIGHT};
+use super::MermaidError;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Model
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Kind of state node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateKind {
+    State,
+    Start,
+    End,
+    Fork,
+    Choice,
+}
+
+/// A state node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateNode {
+    pub id: String,
+    pub label: String,
+    pub descriptions: Vec<String>,
+    pub kind: StateKind,
+}
+
+/// A transition between two states.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    pub from: usize,
+    pub to: usize,
+    pub label: Option<String>,
+}
+
+/// A parsed state diagram.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StateDiagram {
+    pub direction: Direction,
+    pub states: Vec<StateNode>,
+    pub transitions: Vec<Transition>,
+}
+
+impl StateDiagram {
+    fn state(&mut self, id: &str, kind: StateKind) -> usize {
+        if let Some(i) = self.states.iter().position(|s| s.id == id) {
+            return i;
+        }
+        self.states.push(StateNode {
+            id: id.to_string(),
+            label: id.to_string(),
+            descriptions: Vec::new(),
+            kind,
+        });
+        self.states.len() - 1
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Parser
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Parse a state diagram from the lines after its header.
+pub fn parse(lines: &[(usize, &str)]) -> Result<StateDiagram, MermaidError> {
+    let mut diagram = StateDiagram::default();
+    // Composite states currently open, with the line they were opened on
+    let mut scopes: Vec<(String, usize)> = Vec::new();
+    let mut in_note = false;
+
+    for &(line_number, line) in lines {
+        let line = line.trim().trim_end_matches(';');
+        let error = |message: String| MermaidError {
+            line: line_number,
+            message,
+        };
+        if in_note {
+            in_note = line != "end note";
+            continue;
+        }
+        if line == "--" || line.starts_with("hide ") || line.starts_with("scale ") {
+            continue;
+        }
+        let keyword = line.split_whitespace().next().unwrap_or("");
+        if matches!(keyword, "classDef" | "class" | "style") || line.starts_with("accTitle") {
+            continue;
+        }
+        if keyword == "direction" {
+            // Only the top-level direction is used
+            if scopes.is_empty() {
+                let value = line[keyword.len()..].trim();
+                diagram.direction = Direction::parse(value)
+                    .ok_or_else(|| error(format!("unknown direction '{}'", value)))?;
+            }
+            continue;
+        }
+        if keyword == "note" {
+            // Single-line notes contain ':'; otherwise the note runs to `end note`
+            in_note = !line.contains(':');
+            continue;
+        }
+        if line == "}" {
+            if scopes.pop().is_none() {
+                return Err(error("'}' without an open composite state".to_string()));
+            }
+            continue;
+        }
+        let scope = scopes.last().map(|(name, _)| name.clone());
+
+        if let Some(rest) = line.strip_prefix("state ") {
+            let rest = rest.trim();
+            let (declaration, opens) = match rest.strip_suffix('{') {
+                Some(declaration) => (declaration.trim(), true),
+                None => (rest, false),
+            };
+            let index = parse_state_declaration(&mut diagram, declaration)
+                .ok_or_else(|| error(format!("invalid state declaration '{}'", declaration)))?;
+            if opens {
+                scopes.push((diagram.states[index].id.clone(), line_number));
+            }
+            continue;
+        }
+
+        if let Some((left, right)) = line.split_once("-->") {
+            let (target, label) = match right.split_once(':') {
+                Some((target, label)) => (target.trim(), Some(label.trim().to_string())),
+                None => (right.trim(), None),
+            };
+            let source = left.trim();
+            if source.is_empty() || target.is_empty() {
+                return Err(error(
+                    "a transition needs a state on both sides of '-->'".to_string(),
+                ));
+            }
+            let from = endpoint(&mut diagram, source, scope.as_deref(), true);
+            let to = endpoint(&mut diagram, target, scope.as_deref(), false);
+            diagram.transitions.push(Transition {
+                from,
+                to,
+                label: label.filter(|l| !l.is_empty()),
+            });
+            continue;
+        }
+
+        if let Some((id, description)) = line.split_once(':') {
+            let id = id.trim();
+            if is_identifier(id) {
+                let index = diagram.state(id, StateKind::State);
+                diagram.states[index]
+                    .descriptions
+                    .push(description.trim().to_string());
+                continue;
+            }
+        }
+        if is_identifier(line) {
+            diagram.state(line, StateKind::State);
+            continue;
+        }
+        return Err(error(format!("unrecognized statement '{}'", line)));
+    }
+
+    if let Some((name, line)) = scopes.pop() {
+        return Err(MermaidError {
+            line,
+            message: format!("composite state '{}' is never closed with '}}'", name),
+        });
+    }
+    Ok(diagram)
+}
+
+fn is_identifier(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+/// Resolve a transition endpoint, mapping `[*]` to the start or end state of
+/// the current scope.
+fn endpoint(diagram: &mut StateDiagram, id: &str, scope: Option<&str>, is_source: bool) -> usize {
+    if id != "[*]" {
+        return diagram.state(id, StateKind::State);
+    }
+    match (scope, is_source) {
+        // A composite state leads into its initial substate
+        (Some(scope), true) => diagram.state(scope, StateKind::State),
+        (Some(scope), false) => diagram.state(&format!("[*] end {}", scope), StateKind::End),
+        (None, true) => diagram.state("[*] start", StateKind::Start),
+        (None, false) => diagram.state("[*] end", StateKind::End),
+    }
+}
+
+/// Parse the part after `state`: `Id`, `"Description" as Id`, `Id : desc`
+/// or `Id <<fork>>`.
+fn parse_state_declaration(diagram: &mut StateDiagram, declaration: &str) -> Option<usize> {
+    if let Some(quoted) = declaration.strip_prefix('"') {
+        let (label, rest) = quoted.split_once('"')?;
+        let id = rest.trim().strip_prefix("as")?.trim();
+        if !is_identifier(id) {
+            return None;
+        }
+        let index = diagram.state(id, StateKind::State);
+        diagram.states[index].label = label.to_string();
+        return Some(index);
+    }
+    if let Some((id, stereotype)) = declaration.split_once("<<") {
+        let id = id.trim();
+        let kind = match stereotype.trim_end_matches(">>").trim() {
+            "fork" | "join" => StateKind::Fork,
+            "choice" => StateKind::Choice,
+            _ => StateKind::State,
+        };
+        if !is_identifier(id) {
+            return None;
+        }
+        let index = diagram.state(id, kind);
+        diagram.states[index].kind = kind;
+        return Some(index);
+    }
+    if let Some((id, description)) = declaration.split_once(':') {
+        let id = id.trim();
+        if !is_identifier(id) {
+            return None;
+        }
+        let index = diagram.state(id, StateKind::State);
+        diagram.states[index]
+            .descriptions
+            .push(description.trim().to_string());
+        return Some(index);
+    }
+    is_identifier(declaration).then(|| diagram.state(declaration, StateKind::State))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Layout
+// ─────────────────────────────────────────────────────────────────────────────
+
+fn state_size(state: &StateNode, direction: Direction) -> Vec2 {
+    match state.kind {
+        StateKind::Start => Vec2::splat(18.0),
+        StateKind::End => Vec2::splat(22.0),
+        StateKind::Choice => Vec2::splat(28.0),
+        StateKind::Fork => match direction {
+            Direction::TopDown | Direction::BottomUp => vec2(80.0, 8.0),
+            Direction::LeftRight | Direction::RightLeft => vec2(8.0, 80.0),
+        },
+        StateKind::State => {
+            let title = label_size(&state.label, FONT_SIZE);
+            let width = state
+                .descriptions
+                .iter()
+                .map(|d| label_size(d, FONT_SIZE).x)
+                .fold(title.x, f32::max);
+            let descriptions = if state.descriptions.is_empty() {
+                0.0
+            } else {
+                state.descriptions.len() as f32 * LINE_HEIGHT + 10.0
+            };
+            vec2((width + 30.0).max(70.0), title.y + 18.0 + descriptions)
+        }
+    }
+}
+
+fn clip(state: &StateNode, center: Pos2, size: Vec2, toward: Pos2) -> Pos2 {
+    match state.kind {
+        StateKind::Start | StateKind::End => layout::clip_to_circle(center, size.x / 2.0, toward),
+        StateKind::Choice => layout::clip_to_diamond(center, size, toward),
+        _ => layout::clip_to_box(center, size, toward),
+    }
+}
+
+/// Lay out a state diagram.
+pub fn layout(diagram: &StateDiagram) -> Scene {
+    let sizes: Vec<Vec2> = diagram
+        .states
+        .iter()
+        .map(|s| state_size(s, diagram.direction))
+        .collect();
+    let edges: Vec<(usize, usize)> = diagram.transitions.iter().map(|t| (t.from, t.to)).collect();
+    let graph = layout::layered(&sizes, &edges, diagram.direction);
+
+    let mut scene = Scene::default();
+    let mut labels = Vec::new();
+    for (i, transition) in diagram.transitions.iter().enumerate() {
+        let (from, to) = (transition.from, transition.to);
+        let (a, b) = (graph.centers[from], graph.centers[to]);
+        let points = if from == to {
+            let right = a.x + sizes[from].x / 2.0;
+            let top = a.y - sizes[from].y / 2.0;
+            vec![
+                pos2(right, a.y),
+                pos2(right + 22.0, a.y),
+                pos2(right + 22.0, top - 16.0),
+                pos2(a.x + sizes[from].x / 4.0, top - 16.0),
+                pos2(a.x + sizes[from].x / 4.0, top),
+            ]
+        } else {
+            let bends = &graph.bends[i];
+            let first = bends.first().copied().unwrap_or(b);
+            let last = bends.last().copied().unwrap_or(a);
+            let mut points = vec![clip(&diagram.states[from], a, sizes[from], first)];
+            points.extend(bends.iter().copied());
+            points.push(clip(&diagram.states[to], b, sizes[to], last));
+            points
+        };
+        if let Some(label) = &transition.label {
+            labels.push((layout::midpoint(&points), label.clone()));
+        }
+        scene.connector(points, false, 1.3, Marker::None, Marker::Arrow);
+    }
+
+    for (state, (&center, &size)) in diagram.states.iter().zip(graph.centers.iter().zip(&sizes)) {
+        let rect = Rect::from_center_size(center, size);
+        match state.kind {
+            StateKind::Start => scene.circle(center, size.x / 2.0, Fill::Line, false),
+            StateKind::End => {
+                scene.circle(center, size.x / 2.0, Fill::Background, true);
+                scene.circle(center, size.x / 2.0 - 4.0, Fill::Line, false);
+            }
+            StateKind::Fork => scene.rect(rect, 2.0, Fill::Line),
+            StateKind::Choice => scene.polygon(
+                vec![
+                    pos2(center.x, rect.top()),
+                    pos2(rect.right(), center.y),
+                    pos2(center.x, rect.bottom()),
+                    pos2(rect.left(), center.y),
+                ],
+                Fill::Node,
+            ),
+            StateKind::State => {
+                scene.rect(rect, 10.0, Fill::Node);
+                if state.descriptions.is_empty() {
+                    scene.label(center, &state.label);
+                } else {
+                    let title_height = label_size(&state.label, FONT_SIZE).y + 14.0;
+                    let divider = rect.top() + title_height;
+                    scene.label(
+                        pos2(center.x, rect.top() + title_height / 2.0),
+                        &state.label,
+                    );
+                    scene.line(
+                        vec![pos2(rect.left(), divider), pos2(rect.right(), divider)],
+                        false,
+                        1.0,
+                    );
+                    let text = state.descriptions.join("\n");
+                    scene.label(pos2(center.x, (divider + rect.bottom()) / 2.0), &text);
+                }
+            }
+        }
+    }
+    for (center, label) in labels {
+        scene.boxed_label(center, &label);
+    }
+    scene.fit(8.0);
+    scene
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::mermaid::tests::lines;
+
+    #[test]
+    fn test_parse_transitions() {
+        let diagram = parse(&lines(
+            "[*] --> Still\nStill --> Moving : push\nMoving --> Crash\nstate \"Broken down\" as Crash\nCrash : needs repair\nCrash --> [*]\nstate split <<fork>>\nnote right of Still : a note",
+        ))
+        .unwrap();
+        let kinds: Vec<(&str, StateKind)> = diagram
+            .states
+            .iter()
+            .map(|s| (s.id.as_str(), s.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("[*] start", StateKind::Start),
+                ("Still", StateKind::State),
+                ("Moving", StateKind::State),
+                ("Crash", StateKind::State),
+                ("[*] end", StateKind::End),
+                ("split", StateKind::Fork),
+            ]
+        );
+        assert_eq!(diagram.transitions.len(), 4);
+        assert_eq!(diagram.transitions[1].label.as_deref(), Some("push"));
+        assert_eq!(diagram.states[3].label, "Broken down");
+        assert_eq!(
+            diagram.states[3].descriptions,
+            vec!["needs repair".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_composite_states_are_flattened() {
+        let diagram = parse(&lines(
+            "[*] --> Active\nstate Active {\n  [*] --> Idle\n  Idle --> [*]\n}\nnote left of Active\n  multi-line\nend note",
+        ))
+        .unwrap();
+        let active = diagram
+            .states
+            .iter()
+            .position(|s| s.id == "Active")
+            .unwrap();
+        let idle = diagram.states.iter().position(|s| s.id == "Idle").unwrap();
+        assert!(diagram
+            .transitions
+            .iter()
+            .any(|t| t.from == active && t.to == idle));
+        assert!(diagram.states.iter().any(|s| s.id == "[*] end Active"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse(&lines("state Open {\nA --> B")).unwrap_err();
+        assert_eq!(error.line, 2);
+        let error = parse(&lines("A --> B\n}")).unwrap_err();
+        assert_eq!(error.line, 3);
+        let error = parse(&lines("A --> B\nwhat is this?")).unwrap_err();
+        assert_eq!(error.line, 3);
+    }
+}
@@ -11,6 +11,10 @@
 //! - WYSIWYG editor widget for egui
 //! - Editable widgets for headings, paragraphs, and lists
 //! - Syntax highlighting for code blocks using syntect
+//! - Native LaTeX math layout and MathML output
+//! - Native Mermaid diagram rendering and SVG output
+//! - Inline local images with background decoding
+//! - Wiki-links between notes
 //!
 //! # Example
 //! ```ignore
@@ -39,14 +43,20 @@
 //! ```
 
 mod ast_ops;
+mod cache;
 mod editor;
 pub mod formatting;
+pub mod images;
+pub mod math;
+pub mod mermaid;
 mod parser;
 pub mod syntax;
 pub mod tree_viewer;
+pub mod wikilinks;
 mod widgets;
 
 // Only export what's actually used by the app
+pub use cache::DocumentCache;
 pub use editor::{EditorMode, MarkdownEditor};
 pub use formatting::{
     apply_raw_format, detect_raw_formatting_state, FormattingState, MarkdownFormatCommand,
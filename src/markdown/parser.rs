@@ -11,6 +11,8 @@ use comrak::{
     parse_document, Arena, Options,
 };
 
+use super::math;
+use super::wikilinks::{self, Segment, WikiLink};
 use crate::error::Result;
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -40,6 +42,11 @@ pub struct MarkdownOptions {
     pub safe_urls: bool,
     /// Generate GitHub-style heading IDs
     pub header_ids: Option<String>,
+    /// Enable LaTeX math ($inline$ and $$display$$)
+    pub math: bool,
+    /// Enable wiki-links ([[Note]], [[Note#Heading|alias]]), which only
+    /// resolve inside a workspace
+    pub wiki_links: bool,
 }
 
 impl Default for MarkdownOptions {
@@ -55,6 +62,8 @@ impl Default for MarkdownOptions {
             front_matter_delimiter: Some("---".to_string()),
             safe_urls: true,
             header_ids: Some(String::new()),
+            math: true,
+            wiki_links: false,
         }
     }
 }
@@ -74,6 +83,7 @@ impl MarkdownOptions {
         options.extension.description_lists = self.description_lists;
         options.extension.front_matter_delimiter = self.front_matter_delimiter.clone();
         options.extension.header_ids = self.header_ids.clone();
+        options.extension.math_dollars = self.math;
 
         // Render options
         options.render.unsafe_ = !self.safe_urls;
@@ -206,6 +216,10 @@ pub enum MarkdownNodeType {
     DescriptionDetails,
     /// Front matter (YAML/TOML)
     FrontMatter(String),
+    /// LaTeX math ($inline$ or $$display$$)
+    Math { latex: String, display: bool },
+    /// Wiki-link to another note ([[target#heading|alias]])
+    WikiLink(WikiLink),
 }
 
 /// A node in the markdown AST with position information.
@@ -249,6 +263,10 @@ impl MarkdownNode {
         match &self.node_type {
             MarkdownNodeType::Text(t) => output.push_str(t),
             MarkdownNodeType::Code(t) => output.push_str(t),
+            MarkdownNodeType::Math { latex, display } => {
+                output.push_str(&math::to_markdown(latex, *display))
+            }
+            MarkdownNodeType::WikiLink(link) => output.push_str(&link.to_markdown()),
             MarkdownNodeType::SoftBreak => output.push(' '),
             MarkdownNodeType::LineBreak => output.push('\n'),
             _ => {}
@@ -312,7 +330,7 @@ pub fn parse_markdown_with_options(
 
     // Convert comrak AST to our own structure
     let mut front_matter = None;
-    let converted_root = convert_node(root, &mut front_matter)?;
+    let converted_root = convert_node(root, options.wiki_links, &mut front_matter)?;
 
     Ok(MarkdownDocument {
         root: converted_root,
@@ -328,6 +346,7 @@ pub fn parse_markdown_with_options(
 /// Convert a comrak AST node to our MarkdownNode structure.
 fn convert_node<'a>(
     node: &'a AstNode<'a>,
+    wiki_links: bool,
     front_matter: &mut Option<String>,
 ) -> Result<MarkdownNode> {
     let ast = node.data.borrow();
@@ -343,15 +362,49 @@ fn convert_node<'a>(
         sourcepos.end.column,
     );
 
+    // Wiki-links are not a comrak extension; find them in text nodes, except
+    // inside link text where they would nest links
+    let split_links = wiki_links
+        && !matches!(
+            markdown_node.node_type,
+            MarkdownNodeType::Link { .. } | MarkdownNodeType::Image { .. }
+        );
+
     // Convert children
     for child in node.children() {
-        let child_node = convert_node(child, front_matter)?;
-        markdown_node.children.push(child_node);
+        let child_node = convert_node(child, wiki_links, front_matter)?;
+        match &child_node.node_type {
+            MarkdownNodeType::Text(text) if split_links && text.contains("[[") => {
+                markdown_node
+                    .children
+                    .extend(split_text_node(text, &child_node));
+            }
+            _ => markdown_node.children.push(child_node),
+        }
     }
 
     Ok(markdown_node)
 }
 
+/// Split a text node into text and wiki-link nodes on the same lines.
+fn split_text_node(text: &str, node: &MarkdownNode) -> Vec<MarkdownNode> {
+    wikilinks::split_wiki_links(text)
+        .into_iter()
+        .map(|segment| {
+            let node_type = match segment {
+                Segment::Text(text) => MarkdownNodeType::Text(text.to_string()),
+                Segment::Link(link) => MarkdownNodeType::WikiLink(link),
+            };
+            MarkdownNode {
+                node_type,
+                children: Vec::new(),
+                start_line: node.start_line,
+                end_line: node.end_line,
+            }
+        })
+        .collect()
+}
+
 /// Convert a comrak NodeValue to our MarkdownNodeType.
 fn convert_node_value(
     value: &NodeValue,
@@ -430,6 +483,10 @@ fn convert_node_value(
         NodeValue::DescriptionItem(_) => MarkdownNodeType::DescriptionItem,
         NodeValue::DescriptionTerm => MarkdownNodeType::DescriptionTerm,
         NodeValue::DescriptionDetails => MarkdownNodeType::DescriptionDetails,
+        NodeValue::Math(math) => MarkdownNodeType::Math {
+            latex: math.literal.clone(),
+            display: math.display_math,
+        },
         NodeValue::FrontMatter(fm) => {
             *front_matter = Some(fm.clone());
             MarkdownNodeType::FrontMatter(fm.clone())
@@ -813,6 +870,84 @@ mod tests {
         assert!(text.contains("world"));
     }
 
+    // ─────────────────────────────────────────────────────────────────────────
+    // Math Tests
+    // ─────────────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_math() {
+        let doc = parse_markdown("Energy $E = mc^2$ and\n\n$$\\frac{a}{b}$$").unwrap();
+
+        let inline = &doc.root.children[0].children[1];
+        assert_eq!(
+            inline.node_type,
+            MarkdownNodeType::Math {
+                latex: "E = mc^2".to_string(),
+                display: false,
+            }
+        );
+        let display = &doc.root.children[1].children[0];
+        assert_eq!(
+            display.node_type,
+            MarkdownNodeType::Math {
+                latex: "\\frac{a}{b}".to_string(),
+                display: true,
+            }
+        );
+
+        // Text content keeps the delimiters so edits round-trip
+        assert_eq!(doc.root.children[0].text_content(), "Energy $E = mc^2$ and");
+
+        // Prices are not math
+        let doc = parse_markdown("Costs $5 and $10").unwrap();
+        assert_eq!(doc.root.text_content(), "Costs $5 and $10");
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Wiki-Link Tests
+    // ─────────────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_wiki_links() {
+        let options = MarkdownOptions {
+            wiki_links: true,
+            ..Default::default()
+        };
+        let doc = parse_markdown_with_options(
+            "See [[Project Plan#Goals|goals]] and `[[code]]`",
+            &options,
+        )
+        .unwrap();
+        let paragraph = &doc.root.children[0];
+        assert_eq!(
+            paragraph.children[0].node_type,
+            MarkdownNodeType::Text("See ".to_string())
+        );
+        match &paragraph.children[1].node_type {
+            MarkdownNodeType::WikiLink(link) => {
+                assert_eq!(link.target, "Project Plan");
+                assert_eq!(link.heading.as_deref(), Some("Goals"));
+                assert_eq!(link.alias.as_deref(), Some("goals"));
+            }
+            other => panic!("expected wiki-link, got {:?}", other),
+        }
+        assert_eq!(
+            paragraph.children.last().unwrap().node_type,
+            MarkdownNodeType::Code("[[code]]".to_string())
+        );
+        assert_eq!(
+            paragraph.text_content(),
+            "See [[Project Plan#Goals|goals]] and [[code]]"
+        );
+
+        // Off by default, outside a workspace
+        let doc = parse_markdown("[[Note]]").unwrap();
+        assert_eq!(
+            doc.root.children[0].children[0].node_type,
+            MarkdownNodeType::Text("[[Note]]".to_string())
+        );
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Error Handling Tests
     // ─────────────────────────────────────────────────────────────────────────
@@ -23,8 +23,8 @@
 use eframe::egui::{Color32, FontId, RichText};
 use log::{debug, warn};
 use syntect::easy::HighlightLines;
-use syntect::highlighting::{Style, Theme, ThemeSet};
-use syntect::parsing::SyntaxSet;
+use syntect::highlighting::{HighlightState, Highlighter, Style, Theme, ThemeSet};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxSet};
 use syntect::util::LinesWithEndings;
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -117,6 +117,18 @@ impl HighlightedLine {
     }
 }
 
+/// Highlighting state between two lines of a code block.
+///
+/// Saving this after each line lets highlighting resume in the middle of a
+/// block, so an editor only re-highlights the lines that changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeLineState {
+    /// Parser state (syntax scopes still open)
+    parse: ParseState,
+    /// Theme state (styles of the open scopes)
+    highlight: HighlightState,
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Syntax Highlighter
 // ─────────────────────────────────────────────────────────────────────────────
@@ -276,6 +288,51 @@ impl SyntaxHighlighter {
         self.highlight_code(code, language, theme)
     }
 
+    /// Check if a language identifier is recognized.
+    pub fn supports_language(&self, language: &str) -> bool {
+        self.find_syntax_for_language(language).is_some()
+    }
+
+    /// Start highlighting a code block line by line.
+    ///
+    /// Returns `None` if the language is not recognized.
+    pub fn start_code_lines(&self, language: &str, theme: &Theme) -> Option<CodeLineState> {
+        let syntax = self.find_syntax_for_language(language)?;
+        Some(CodeLineState {
+            parse: ParseState::new(syntax),
+            highlight: HighlightState::new(&Highlighter::new(theme), ScopeStack::new()),
+        })
+    }
+
+    /// Highlight one line of a code block (without its line ending),
+    /// advancing `state` to the start of the next line.
+    pub fn highlight_code_line(
+        &self,
+        state: &mut CodeLineState,
+        line: &str,
+        theme: &Theme,
+    ) -> Vec<HighlightedSegment> {
+        let mut highlighter =
+            HighlightLines::from_state(theme, state.highlight.clone(), state.parse.clone());
+        // The bundled syntaxes expect each line to end with a newline
+        let with_newline = format!("{}\n", line);
+        let segments = match highlighter.highlight_line(&with_newline, &self.syntax_set) {
+            Ok(ranges) => ranges
+                .into_iter()
+                .map(|(style, text)| style_to_segment(style, text.trim_end_matches('\n')))
+                .filter(|segment| !segment.text.is_empty())
+                .collect(),
+            Err(e) => {
+                warn!("Failed to highlight line: {}", e);
+                let color = self.get_theme_foreground(theme).unwrap_or(Color32::GRAY);
+                vec![HighlightedSegment::new(line.to_string(), color)]
+            }
+        };
+        let (highlight, parse) = highlighter.state();
+        *state = CodeLineState { parse, highlight };
+        segments
+    }
+
     /// Find syntax definition for a language identifier.
     ///
     /// Tries multiple strategies:
@@ -537,13 +594,11 @@ mod tests {
             let syntax1 = highlighter.find_syntax_for_language(alias);
             let syntax2 = highlighter.find_syntax_for_language(canonical);
 
-            if syntax1.is_some() && syntax2.is_some() {
+            if let (Some(syntax1), Some(syntax2)) = (syntax1, syntax2) {
                 assert_eq!(
-                    syntax1.unwrap().name,
-                    syntax2.unwrap().name,
+                    syntax1.name, syntax2.name,
                     "Alias {} should map to same syntax as {}",
-                    alias,
-                    canonical
+                    alias, canonical
                 );
             }
         }
@@ -611,4 +666,26 @@ mod tests {
         let fg = highlighter.get_theme_foreground(theme);
         assert!(fg.is_some());
     }
+
+    #[test]
+    fn test_highlight_code_lines_resumes_from_state() {
+        let highlighter = get_highlighter();
+        let theme = highlighter.get_theme_for_mode(true);
+        assert!(highlighter.start_code_lines("no-such-lang", theme).is_none());
+
+        let mut state = highlighter.start_code_lines("rust", theme).unwrap();
+        let first = highlighter.highlight_code_line(&mut state, "/* open", theme);
+        assert_eq!(
+            first.iter().map(|s| s.text.as_str()).collect::<String>(),
+            "/* open"
+        );
+
+        // Still inside the block comment: the whole line gets one color
+        let saved = state.clone();
+        let second = highlighter.highlight_code_line(&mut state, "let x = 1;", theme);
+        let mut resumed = saved;
+        let again = highlighter.highlight_code_line(&mut resumed, "let x = 1;", theme);
+        assert_eq!(second.len(), again.len());
+        assert!(second.iter().all(|s| s.foreground == second[0].foreground));
+    }
 }
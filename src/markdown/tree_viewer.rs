@@ -195,23 +195,54 @@ impl TreeNode {
 // Parsing
 // ─────────────────────────────────────────────────────────────────────────────
 
-/// Parse error with optional line number.
+/// Parse error with optional line and column numbers (both 1-indexed).
 #[derive(Debug, Clone)]
 pub struct ParseError {
     pub message: String,
     pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl ParseError {
+    /// Build an error at a location, removing the parser's own
+    /// " at line X column Y" text from the message so it isn't repeated.
+    fn at(message: String, line: usize, column: usize) -> Self {
+        if line == 0 {
+            return Self {
+                message,
+                line: None,
+                column: None,
+            };
+        }
+        let location = format!(" at line {} column {}", line, column);
+        Self {
+            message: message.replacen(&location, "", 1),
+            line: Some(line),
+            column: (column > 0).then_some(column),
+        }
+    }
 }
 
 impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if let Some(line) = self.line {
-            write!(f, "Line {}: {}", line, self.message)
-        } else {
-            write!(f, "{}", self.message)
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => {
+                write!(f, "Line {}, column {}: {}", line, column, self.message)
+            }
+            (Some(line), None) => write!(f, "Line {}: {}", line, self.message),
+            _ => write!(f, "{}", self.message),
         }
     }
 }
 
+/// Convert a byte offset into 1-indexed line and column numbers.
+fn offset_to_line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
 /// Parse content into a TreeNode based on file type.
 pub fn parse_structured_content(
     content: &str,
@@ -226,14 +257,8 @@ pub fn parse_structured_content(
 
 /// Parse JSON content.
 fn parse_json(content: &str) -> Result<TreeNode, ParseError> {
-    let value: serde_json::Value = serde_json::from_str(content).map_err(|e| {
-        // Try to extract line number from error
-        let line = e.line();
-        ParseError {
-            message: e.to_string(),
-            line: if line > 0 { Some(line) } else { None },
-        }
-    })?;
+    let value: serde_json::Value = serde_json::from_str(content)
+        .map_err(|e| ParseError::at(e.to_string(), e.line(), e.column()))?;
     Ok(json_to_tree(&value))
 }
 
@@ -262,13 +287,11 @@ fn json_to_tree(value: &serde_json::Value) -> TreeNode {
 
 /// Parse YAML content.
 fn parse_yaml(content: &str) -> Result<TreeNode, ParseError> {
-    let value: serde_yaml::Value = serde_yaml::from_str(content).map_err(|e| {
-        // serde_yaml errors include location info in the message
-        ParseError {
-            message: e.to_string(),
-            line: None, // Location is in message
-        }
-    })?;
+    let value: serde_yaml::Value =
+        serde_yaml::from_str(content).map_err(|e| match e.location() {
+            Some(location) => ParseError::at(e.to_string(), location.line(), location.column()),
+            None => ParseError::at(e.to_string(), 0, 0),
+        })?;
     Ok(yaml_to_tree(&value))
 }
 
@@ -308,10 +331,14 @@ fn yaml_to_tree(value: &serde_yaml::Value) -> TreeNode {
 /// Parse TOML content.
 fn parse_toml(content: &str) -> Result<TreeNode, ParseError> {
     let value: toml::Value = toml::from_str(content).map_err(|e| {
-        // toml errors may include span info
-        ParseError {
-            message: e.to_string(),
-            line: None,
+        // The full toml message is a multi-line snippet; use the short one
+        let message = e.message().trim().lines().collect::<Vec<_>>().join("; ");
+        match e.span() {
+            Some(span) => {
+                let (line, column) = offset_to_line_column(content, span.start);
+                ParseError::at(message, line, column)
+            }
+            None => ParseError::at(message, 0, 0),
         }
     })?;
     Ok(toml_to_tree(&value))
@@ -1142,6 +1169,21 @@ enabled = true
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_parse_error_locations() {
+        let error = parse_json("{\n  \"a\": ,\n}").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(2), Some(8)));
+        assert!(!error.message.contains("at line"));
+
+        let error = parse_yaml("a: [1, 2\nb: 3").unwrap_err();
+        assert!(error.line.is_some());
+        assert!(error.column.is_some());
+
+        let error = parse_toml("a = 1\nb = \n").unwrap_err();
+        assert_eq!(error.line, Some(2));
+        assert!(!error.message.contains('\n'));
+    }
+
     #[test]
     fn test_tree_node_update() {
         let mut node = TreeNode::String("hello".to_string());
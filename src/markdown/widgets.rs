@@ -15,6 +15,8 @@
 #![allow(dead_code)]
 
 use crate::config::Theme;
+use crate::markdown::math;
+use crate::markdown::mermaid::{DiagramColors, MermaidView};
 use crate::markdown::parser::{HeadingLevel, ListType, MarkdownNode, MarkdownNodeType};
 use eframe::egui::{self, Color32, FontId, RichText, TextEdit, Ui};
 
@@ -757,6 +759,7 @@ pub fn serialize_node(node: &MarkdownNode) -> String {
         // Inline elements
         MarkdownNodeType::Text(text) => text.clone(),
         MarkdownNodeType::Code(code) => format!("`{}`", code),
+        MarkdownNodeType::Math { latex, display } => math::to_markdown(latex, *display),
         MarkdownNodeType::Emphasis => format!("*{}*", node.text_content()),
         MarkdownNodeType::Strong => format!("**{}**", node.text_content()),
         MarkdownNodeType::Strikethrough => format!("~~{}~~", node.text_content()),
@@ -776,6 +779,7 @@ pub fn serialize_node(node: &MarkdownNode) -> String {
                 format!("![{}]({} \"{}\")", alt, url, title)
             }
         }
+        MarkdownNodeType::WikiLink(link) => link.to_markdown(),
         MarkdownNodeType::SoftBreak => " ".to_string(),
         MarkdownNodeType::LineBreak => "  \n".to_string(),
 
@@ -798,6 +802,7 @@ fn serialize_inline_node(node: &MarkdownNode) -> String {
     match &node.node_type {
         MarkdownNodeType::Text(text) => text.clone(),
         MarkdownNodeType::Code(code) => format!("`{}`", code),
+        MarkdownNodeType::Math { latex, display } => math::to_markdown(latex, *display),
         MarkdownNodeType::Emphasis => {
             let inner = serialize_inline_content(node);
             format!("*{}*", inner)
@@ -826,6 +831,7 @@ fn serialize_inline_node(node: &MarkdownNode) -> String {
                 format!("![{}]({} \"{}\")", alt, url, title)
             }
         }
+        MarkdownNodeType::WikiLink(link) => link.to_markdown(),
         MarkdownNodeType::SoftBreak => " ".to_string(),
         MarkdownNodeType::LineBreak => "  \n".to_string(),
         MarkdownNodeType::HtmlInline(html) => html.clone(),
@@ -1698,6 +1704,7 @@ pub const SUPPORTED_LANGUAGES: &[&str] = &[
     "dockerfile",
     "makefile",
     "diff",
+    "mermaid",
 ];
 
 /// Get the display name for a language code.
@@ -1741,6 +1748,7 @@ pub fn language_display_name(lang: &str) -> &str {
         "dockerfile" | "docker" => "Dockerfile",
         "makefile" | "make" => "Makefile",
         "diff" | "patch" => "Diff",
+        "mermaid" => "Mermaid",
         other => other,
     }
 }
@@ -1787,6 +1795,7 @@ pub fn normalize_language(lang: &str) -> &'static str {
         "dockerfile" | "docker" => "dockerfile",
         "makefile" | "make" => "makefile",
         "diff" | "patch" => "diff",
+        "mermaid" => "mermaid",
         _ => "", // Unknown language falls back to plain text
     }
 }
@@ -2053,6 +2062,16 @@ impl<'a> EditableCodeBlock<'a> {
                             .desired_width(f32::INFINITY),
                     );
                     // No auto-exit - user must click "Done" button
+                } else if self.data.language.split_whitespace().next() == Some("mermaid") {
+                    // View mode: draw the diagram (or its syntax error) instead of the source
+                    MermaidView::new(&self.data.code)
+                        .font_size(self.font_size)
+                        .colors(DiagramColors::new(
+                            self.dark_mode,
+                            colors.text,
+                            code_block_bg,
+                        ))
+                        .show(ui);
                 } else {
                     // View mode: show syntax-highlighted code
                     let highlighted_lines =
@@ -0,0 +1,402 @@
+//! Wiki-Links
+//!
+//! Notes link to each other with `[[Note Name]]`, `[[note#Heading]]` and
+//! `[[note#Heading|alias]]`. The parser turns these into
+//! [`MarkdownNodeType::WikiLink`](super::parser::MarkdownNodeType) nodes; this
+//! module parses the link syntax, finds links in raw source (for backlinks),
+//! and resolves link targets to files.
+//!
+//! A target matches a markdown file by name, ignoring case and the extension.
+//! Targets containing `/` match the end of the file's path instead, which
+//! disambiguates notes with the same name in different folders.
+//!
+//! # Example
+//! ```ignore
+//! use crate::markdown::wikilinks::{NoteResolver, WikiLink};
+//!
+//! let link = WikiLink::parse("Project Plan#Goals|goals").unwrap();
+//! let resolver = NoteResolver::new(Some(root), &workspace.all_files());
+//! let path = resolver.resolve(&link.target);
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Extensions of files wiki-links can point at.
+const NOTE_EXTENSIONS: &[&str] = &["md", "markdown", "mdown", "mkd", "mkdn"];
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Link Syntax
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A parsed `[[target#heading|alias]]` link.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WikiLink {
+    /// Note name or path (empty for a link to a heading in the same note)
+    pub target: String,
+    /// Heading within the note
+    pub heading: Option<String>,
+    /// Text shown instead of the target
+    pub alias: Option<String>,
+}
+
+impl WikiLink {
+    /// Parse the text between `[[` and `]]`.
+    ///
+    /// Returns `None` if there is neither a target nor a heading.
+    pub fn parse(inner: &str) -> Option<Self> {
+        let (link, alias) = match inner.split_once('|') {
+            Some((link, alias)) => (link, Some(alias.trim()).filter(|a| !a.is_empty())),
+            None => (inner, None),
+        };
+        let (target, heading) = match link.split_once('#') {
+            Some((target, heading)) => (target, Some(heading.trim()).filter(|h| !h.is_empty())),
+            None => (link, None),
+        };
+        let target = target.trim();
+        if target.is_empty() && heading.is_none() {
+            return None;
+        }
+        Some(Self {
+            target: target.to_string(),
+            heading: heading.map(str::to_string),
+            alias: alias.map(str::to_string),
+        })
+    }
+
+    /// Text to show for the link: the alias, or the target and heading.
+    pub fn display_text(&self) -> String {
+        match (&self.alias, &self.heading) {
+            (Some(alias), _) => alias.clone(),
+            (None, Some(heading)) if self.target.is_empty() => heading.clone(),
+            (None, Some(heading)) => format!("{} › {}", self.target, heading),
+            (None, None) => self.target.clone(),
+        }
+    }
+
+    /// Write the link back as markdown.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("[[{}", self.target);
+        if let Some(heading) = &self.heading {
+            markdown.push('#');
+            markdown.push_str(heading);
+        }
+        if let Some(alias) = &self.alias {
+            markdown.push('|');
+            markdown.push_str(alias);
+        }
+        markdown.push_str("]]");
+        markdown
+    }
+}
+
+/// A piece of text split around wiki-links.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment<'a> {
+    /// Plain text
+    Text(&'a str),
+    /// A wiki-link
+    Link(WikiLink),
+}
+
+/// Split text into plain text and wiki-links.
+pub fn split_wiki_links(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = text;
+    let mut plain_start = 0;
+    let mut offset = 0;
+
+    while let Some(open) = rest.find("[[") {
+        let after_open = &rest[open + 2..];
+        let Some(close) = after_open.find("]]") else {
+            break;
+        };
+        let inner = &after_open[..close];
+        let consumed = open + 2 + close + 2;
+        // Brackets and line breaks can't appear inside a link
+        let link = if inner.contains(['[', ']', '\n']) {
+            None
+        } else {
+            WikiLink::parse(inner)
+        };
+        match link {
+            Some(link) => {
+                let start = offset + open;
+                if start > plain_start {
+                    segments.push(Segment::Text(&text[plain_start..start]));
+                }
+                segments.push(Segment::Link(link));
+                plain_start = offset + consumed;
+                offset += consumed;
+                rest = &rest[consumed..];
+            }
+            None => {
+                // Skip one bracket so `[[[a]]` still finds `[[a]]`
+                offset += open + 1;
+                rest = &rest[open + 1..];
+            }
+        }
+    }
+
+    if plain_start < text.len() {
+        segments.push(Segment::Text(&text[plain_start..]));
+    }
+    segments
+}
+
+/// Find the wiki-links in markdown source, with their 1-indexed line numbers.
+///
+/// Links inside fenced code blocks and inline code spans are skipped.
+pub fn find_wiki_links(source: &str) -> Vec<(usize, WikiLink)> {
+    let mut links = Vec::new();
+    let mut fence: Option<&str> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") {
+            fence = Some("```");
+            continue;
+        }
+        if trimmed.starts_with("~~~") {
+            fence = Some("~~~");
+            continue;
+        }
+
+        // Text outside `code spans` is at the even positions
+        for (i, part) in line.split('`').enumerate() {
+            if i % 2 == 1 {
+                continue;
+            }
+            for segment in split_wiki_links(part) {
+                if let Segment::Link(link) = segment {
+                    links.push((index + 1, link));
+                }
+            }
+        }
+    }
+    links
+}
+
+/// Find the 1-indexed line of an ATX heading with the given text (ignoring case).
+pub fn heading_line(source: &str, heading: &str) -> Option<usize> {
+    source
+        .lines()
+        .position(|line| {
+            let trimmed = line.trim_start();
+            let text = trimmed.trim_start_matches('#');
+            trimmed.starts_with('#')
+                && (text.is_empty() || text.starts_with(' '))
+                && text
+                    .trim()
+                    .trim_end_matches('#')
+                    .trim()
+                    .eq_ignore_ascii_case(heading)
+        })
+        .map(|i| i + 1)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Resolution
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Check if a path is a note wiki-links can point at.
+pub fn is_note(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| NOTE_EXTENSIONS.iter().any(|n| n.eq_ignore_ascii_case(ext)))
+}
+
+/// Normalize a link target or note path for comparison: lowercase, `/`
+/// separators, no note extension.
+fn note_key(name: &str) -> String {
+    let name = name.trim().replace('\\', "/").to_lowercase();
+    NOTE_EXTENSIONS
+        .iter()
+        .find_map(|ext| name.strip_suffix(&format!(".{}", ext)))
+        .map(str::to_string)
+        .unwrap_or(name)
+}
+
+/// Resolves wiki-link targets to note files.
+#[derive(Debug, Clone, Default)]
+pub struct NoteResolver {
+    /// Notes as (path key relative to the root, path), sorted by key
+    notes: Vec<(String, PathBuf)>,
+    /// Indices into `notes` by lowercase file stem
+    by_name: HashMap<String, Vec<usize>>,
+}
+
+impl NoteResolver {
+    /// Index the notes among `files`; paths are keyed relative to `root` if given.
+    pub fn new(root: Option<&Path>, files: &[PathBuf]) -> Self {
+        let mut notes: Vec<(String, PathBuf)> = files
+            .iter()
+            .filter(|path| is_note(path))
+            .map(|path| {
+                let relative = root
+                    .and_then(|root| path.strip_prefix(root).ok())
+                    .unwrap_or(path);
+                (note_key(&relative.to_string_lossy()), path.clone())
+            })
+            .collect();
+        // Shallower notes win when names collide
+        notes.sort_by(|(a, _), (b, _)| {
+            a.matches('/')
+                .count()
+                .cmp(&b.matches('/').count())
+                .then(a.cmp(b))
+        });
+
+        let mut by_name: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, (key, _)) in notes.iter().enumerate() {
+            let name = key.rsplit('/').next().unwrap_or(key);
+            by_name.entry(name.to_string()).or_default().push(index);
+        }
+        Self { notes, by_name }
+    }
+
+    /// Find the note a link target points at.
+    pub fn resolve(&self, target: &str) -> Option<&Path> {
+        let key = note_key(target);
+        let key = key.trim_start_matches('/');
+        if key.is_empty() {
+            return None;
+        }
+        if key.contains('/') {
+            let suffix = format!("/{}", key);
+            return self
+                .notes
+                .iter()
+                .find(|(note, _)| note == key || note.ends_with(&suffix))
+                .map(|(_, path)| path.as_path());
+        }
+        self.by_name
+            .get(key)
+            .and_then(|indices| indices.first())
+            .map(|&i| self.notes[i].1.as_path())
+    }
+}
+
+/// Path for a new note created from an unresolved link, inside `dir`.
+pub fn new_note_path(target: &str, dir: &Path) -> PathBuf {
+    let relative: PathBuf = target
+        .replace('\\', "/")
+        .split('/')
+        .map(str::trim)
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .collect();
+    let path = dir.join(relative);
+    if is_note(&path) {
+        path
+    } else {
+        let mut name = path.into_os_string();
+        name.push(".md");
+        PathBuf::from(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wiki_link() {
+        let link = WikiLink::parse("Project Plan#Goals|the goals").unwrap();
+        assert_eq!(link.target, "Project Plan");
+        assert_eq!(link.heading.as_deref(), Some("Goals"));
+        assert_eq!(link.alias.as_deref(), Some("the goals"));
+        assert_eq!(link.display_text(), "the goals");
+        assert_eq!(link.to_markdown(), "[[Project Plan#Goals|the goals]]");
+
+        let link = WikiLink::parse("note#Setup").unwrap();
+        assert_eq!(link.display_text(), "note › Setup");
+        assert_eq!(WikiLink::parse("#Setup").unwrap().display_text(), "Setup");
+        assert_eq!(WikiLink::parse("  "), None);
+        assert_eq!(WikiLink::parse("|alias"), None);
+    }
+
+    #[test]
+    fn test_split_wiki_links() {
+        let segments = split_wiki_links("See [[Note A]] and [[b|B]].");
+        assert_eq!(segments.len(), 5);
+        assert_eq!(segments[0], Segment::Text("See "));
+        assert!(matches!(&segments[1], Segment::Link(l) if l.target == "Note A"));
+        assert_eq!(segments[2], Segment::Text(" and "));
+        assert!(matches!(&segments[3], Segment::Link(l) if l.alias.as_deref() == Some("B")));
+        assert_eq!(segments[4], Segment::Text("."));
+
+        assert_eq!(
+            split_wiki_links("[[]] [[open"),
+            vec![Segment::Text("[[]] [[open")]
+        );
+        let segments = split_wiki_links("[[[a]]");
+        assert_eq!(segments[0], Segment::Text("["));
+        assert!(matches!(&segments[1], Segment::Link(l) if l.target == "a"));
+    }
+
+    #[test]
+    fn test_find_wiki_links_skips_code() {
+        let source = "# Title\n[[one]] and `[[not]]`\n```\n[[fenced]]\n```\nend [[two#H]]";
+        let links: Vec<_> = find_wiki_links(source)
+            .into_iter()
+            .map(|(line, link)| (line, link.target))
+            .collect();
+        assert_eq!(links, vec![(2, "one".to_string()), (6, "two".to_string())]);
+    }
+
+    #[test]
+    fn test_heading_line() {
+        let source = "intro\n## Setup Steps ##\n#hashtag\n# Goals";
+        assert_eq!(heading_line(source, "setup steps"), Some(2));
+        assert_eq!(heading_line(source, "Goals"), Some(4));
+        assert_eq!(heading_line(source, "hashtag"), None);
+    }
+
+    #[test]
+    fn test_resolve_notes() {
+        let root = Path::new("/kb");
+        let files: Vec<PathBuf> = [
+            "/kb/deep/nested/Index.md",
+            "/kb/Index.md",
+            "/kb/projects/Plan.markdown",
+            "/kb/archive/Plan.md",
+            "/kb/image.png",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        let resolver = NoteResolver::new(Some(root), &files);
+
+        assert_eq!(resolver.resolve("index"), Some(Path::new("/kb/Index.md")));
+        assert_eq!(
+            resolver.resolve("projects/plan"),
+            Some(Path::new("/kb/projects/Plan.markdown"))
+        );
+        assert_eq!(
+            resolver.resolve("Plan"),
+            Some(Path::new("/kb/archive/Plan.md"))
+        );
+        assert_eq!(resolver.resolve("Plan.md"), resolver.resolve("plan"));
+        assert_eq!(resolver.resolve("image"), None);
+        assert_eq!(resolver.resolve("missing"), None);
+    }
+
+    #[test]
+    fn test_new_note_path() {
+        let dir = Path::new("/kb/notes");
+        assert_eq!(
+            new_note_path("New Idea", dir),
+            PathBuf::from("/kb/notes/New Idea.md")
+        );
+        assert_eq!(
+            new_note_path("../ideas/x.md", dir),
+            PathBuf::from("/kb/notes/ideas/x.md")
+        );
+    }
+}
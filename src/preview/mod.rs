@@ -5,4 +5,4 @@
 
 mod sync_scroll;
 
-pub use sync_scroll::{ScrollOrigin, SyncScrollState};
+pub use sync_scroll::{BlockMapping, BlockType, ScrollOrigin, SyncScrollState};
@@ -3,7 +3,7 @@
 //! This module implements synchronized scrolling between the Raw markdown editor
 //! and the Rendered WYSIWYG view. It provides:
 
-// Allow dead code for infrastructure that will be used in future split-view implementation
+// Allow dead code - the visual indicator helpers aren't drawn by the split view yet
 // - double_ended_iterator_last: filter().last() is clearer for finding last matching element
 #![allow(dead_code)]
 #![allow(clippy::double_ended_iterator_last)]
@@ -188,6 +188,10 @@ pub struct SyncScrollState {
     source_line_count: usize,
     /// Total rendered height (for proportional fallback)
     rendered_total_height: f32,
+    /// Whether the Raw view was scrolled by an animation this frame
+    raw_driven: bool,
+    /// Whether the Rendered view was scrolled by an animation this frame
+    rendered_driven: bool,
 }
 
 impl Default for SyncScrollState {
@@ -214,6 +218,8 @@ impl SyncScrollState {
             animation_start_rendered: 0.0,
             source_line_count: 0,
             rendered_total_height: 0.0,
+            raw_driven: false,
+            rendered_driven: false,
         }
     }
 
@@ -356,11 +362,7 @@ impl SyncScrollState {
         }
 
         // Find the closest mapping before this line
-        let before = self
-            .mappings
-            .iter()
-            .filter(|m| m.source_lines.1 < line)
-            .next_back();
+        let before = self.mappings.iter().rfind(|m| m.source_lines.1 < line);
 
         // Find the closest mapping after this line
         let after = self.mappings.iter().find(|m| m.source_lines.0 > line);
@@ -410,8 +412,7 @@ impl SyncScrollState {
         let before = self
             .mappings
             .iter()
-            .filter(|m| m.rendered_range.1 < rendered_y)
-            .next_back();
+            .rfind(|m| m.rendered_range.1 < rendered_y);
 
         let after = self
             .mappings
@@ -480,6 +481,7 @@ impl SyncScrollState {
     /// Get the current animated Raw offset (or None if no animation).
     pub fn get_animated_raw_offset(&mut self) -> Option<f32> {
         let target = self.target_raw_offset?;
+        self.raw_driven = true;
 
         if !self.config.smooth_scrolling {
             let result = target;
@@ -508,6 +510,7 @@ impl SyncScrollState {
     /// Get the current animated Rendered offset (or None if no animation).
     pub fn get_animated_rendered_offset(&mut self) -> Option<f32> {
         let target = self.target_rendered_offset?;
+        self.rendered_driven = true;
 
         if !self.config.smooth_scrolling {
             let result = target;
@@ -575,6 +578,45 @@ impl SyncScrollState {
         (new_offset - old_offset).abs() >= self.config.min_scroll_delta
     }
 
+    /// Keep the two panes of the split view in sync after a frame.
+    ///
+    /// Takes the offsets both views ended the frame with. When the user
+    /// scrolled one view, the other is animated to the same source line;
+    /// offsets set by an animation (read with `get_animated_*_offset` this
+    /// frame) are not treated as user scrolls, so the views don't chase
+    /// each other.
+    pub fn sync_panes(&mut self, raw_offset: f32, rendered_offset: f32, line_height: f32) {
+        let raw_driven = std::mem::take(&mut self.raw_driven);
+        let rendered_driven = std::mem::take(&mut self.rendered_driven);
+
+        if raw_driven {
+            self.last_raw_offset = raw_offset;
+        } else if self.has_significant_delta(raw_offset, self.last_raw_offset)
+            && self.should_sync_from(ScrollOrigin::Raw)
+        {
+            self.mark_scroll(ScrollOrigin::Raw);
+            self.last_raw_offset = raw_offset;
+            self.last_rendered_offset = rendered_offset;
+            let line = self.raw_offset_to_line(raw_offset, line_height);
+            self.animate_rendered_to(self.line_to_rendered_offset(line));
+            return;
+        }
+
+        if rendered_driven {
+            self.last_rendered_offset = rendered_offset;
+        } else if self.has_significant_delta(rendered_offset, self.last_rendered_offset)
+            && self.should_sync_from(ScrollOrigin::Rendered)
+        {
+            self.mark_scroll(ScrollOrigin::Rendered);
+            self.last_rendered_offset = rendered_offset;
+            let line = self.rendered_offset_to_line(rendered_offset);
+            self.animate_raw_to(self.line_to_raw_offset(line, line_height));
+            return;
+        }
+
+        self.clear_origin();
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Visual Indicators
     // ─────────────────────────────────────────────────────────────────────────
@@ -756,4 +798,36 @@ mod tests {
         assert!(state.has_significant_delta(10.0, 3.0));
         assert!(state.has_significant_delta(0.0, 10.0));
     }
+
+    #[test]
+    fn test_sync_panes() {
+        let mut state = SyncScrollState::with_config(SyncScrollConfig {
+            smooth_scrolling: false,
+            debounce_duration: Duration::ZERO,
+            ..SyncScrollConfig::default()
+        });
+        state.build_mappings_from_blocks(vec![
+            BlockMapping::new(1, 10, 0.0, 400.0, BlockType::Paragraph),
+            BlockMapping::new(11, 20, 400.0, 600.0, BlockType::Paragraph),
+        ]);
+
+        // Scrolling the raw view to line 11 moves the preview to its block
+        state.sync_panes(200.0, 0.0, 20.0);
+        assert_eq!(state.get_animated_rendered_offset(), Some(400.0));
+
+        // The preview following along is not a user scroll
+        state.sync_panes(200.0, 400.0, 20.0);
+        assert_eq!(state.get_animated_raw_offset(), None);
+        assert!(!state.is_animating());
+
+        // Scrolling the preview to the middle of the block moves the raw view
+        state.sync_panes(200.0, 500.0, 20.0);
+        assert_eq!(state.get_animated_raw_offset(), Some(280.0));
+        state.sync_panes(280.0, 500.0, 20.0);
+        assert!(!state.is_animating());
+
+        // Small movements are ignored
+        state.sync_panes(283.0, 500.0, 20.0);
+        assert!(!state.is_animating());
+    }
 }
@@ -9,10 +9,23 @@
 #![allow(dead_code)]
 #![allow(clippy::redundant_closure)]
 
-use crate::config::{load_config, save_config_silent, Settings, TabInfo, ViewMode};
-use crate::workspaces::{filter_events, AppMode, Workspace, WorkspaceEvent, WorkspaceWatcher};
+use crate::config::{
+    load_config, save_config_silent, RecoveryEntry, RecoveryStore, RecoveryWriter, Settings,
+    TabInfo, ViewMode,
+};
+use crate::editor::{three_way_merge, CursorSnapshot, EditHistory, MergeResult};
+use crate::files::autosave::AutoSaveReport;
+use crate::files::encoding::{
+    read_text_file, write_text_file, FileFormat, LineEnding, TextEncoding,
+};
+use crate::files::save::BackupPolicy;
+use crate::workspaces::{
+    filter_events, AppMode, OpenFilesWatcher, Workspace, WorkspaceEvent, WorkspaceWatcher,
+};
 use log::{debug, info, warn};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
 
 // ─────────────────────────────────────────────────────────────────────────────
 // File Type Detection
@@ -106,19 +119,26 @@ pub struct Tab {
     pub scroll_offset: f32,
     /// View mode for this tab (raw or rendered)
     pub view_mode: ViewMode,
-    /// Undo history stack
-    undo_stack: Vec<String>,
-    /// Redo history stack
-    redo_stack: Vec<String>,
-    /// Maximum undo history size
-    max_undo_size: usize,
+    /// Undo/redo history (text deltas, limited by a byte budget)
+    history: EditHistory,
     /// Content version counter - incremented on undo/redo to signal
     /// external content changes to the editor widget
     content_version: u64,
+    /// Edit counter - incremented on every recorded change to `content`
+    edit_version: u64,
     /// Cached file type (computed from path, updated on path change)
     file_type: FileType,
     /// Whether the editor should request focus on next frame
     pub needs_focus: bool,
+    /// Modification time of the file on disk when it was last loaded or saved
+    disk_mtime: Option<SystemTime>,
+    /// New on-disk content (and its format) that conflicts with unsaved
+    /// edits, awaiting a Reload / Keep Mine / Compare decision
+    disk_conflict: Option<(String, FileFormat)>,
+    /// Encoding, BOM and line endings used when saving
+    format: FileFormat,
+    /// Format of the file as last loaded or saved (for modification tracking)
+    saved_format: FileFormat,
 }
 
 impl Tab {
@@ -136,12 +156,15 @@ impl Tab {
             selection: None,
             scroll_offset: 0.0,
             view_mode: ViewMode::Raw, // New documents default to raw mode
-            undo_stack: Vec::new(),
-            redo_stack: Vec::new(),
-            max_undo_size: 100,
+            history: EditHistory::new(),
             content_version: 0,
+            edit_version: 0,
             file_type: FileType::Markdown, // New tabs default to markdown
-            needs_focus: true, // Auto-focus new tabs
+            needs_focus: true,             // Auto-focus new tabs
+            disk_mtime: None,
+            disk_conflict: None,
+            format: FileFormat::default(),
+            saved_format: FileFormat::default(),
         }
     }
 
@@ -152,6 +175,7 @@ impl Tab {
     /// The editor will automatically receive focus on the next frame.
     pub fn with_file(id: usize, path: PathBuf, content: String) -> Self {
         let file_type = FileType::from_path(&path);
+        let disk_mtime = file_mtime(&path);
         Self {
             id,
             path: Some(path),
@@ -161,15 +185,25 @@ impl Tab {
             selection: None,
             scroll_offset: 0.0,
             view_mode: ViewMode::Raw, // Newly opened files default to raw mode
-            undo_stack: Vec::new(),
-            redo_stack: Vec::new(),
-            max_undo_size: 100,
+            history: EditHistory::new(),
             content_version: 0,
+            edit_version: 0,
             file_type,
             needs_focus: true, // Auto-focus newly opened files
+            disk_mtime,
+            disk_conflict: None,
+            format: FileFormat::default(),
+            saved_format: FileFormat::default(),
         }
     }
 
+    /// Set the on-disk format detected when the file was read.
+    pub fn with_format(mut self, format: FileFormat) -> Self {
+        self.format = format;
+        self.saved_format = format;
+        self
+    }
+
     /// Create a tab from saved session info.
     ///
     /// Restores the view mode from the saved session.
@@ -181,6 +215,7 @@ impl Tab {
             .as_ref()
             .map(|p| FileType::from_path(p))
             .unwrap_or(FileType::Markdown);
+        let disk_mtime = info.path.as_deref().and_then(file_mtime);
         Self {
             id,
             path: info.path.clone(),
@@ -190,18 +225,23 @@ impl Tab {
             selection: None,
             scroll_offset: info.scroll_offset,
             view_mode: info.view_mode, // Restore saved view mode
-            undo_stack: Vec::new(),
-            redo_stack: Vec::new(),
-            max_undo_size: 100,
+            history: EditHistory::new(),
             content_version: 0,
+            edit_version: 0,
             file_type,
             needs_focus: false, // Don't auto-focus restored tabs
+            disk_mtime,
+            disk_conflict: None,
+            format: info.format,
+            saved_format: info.format,
         }
     }
 
     /// Check if the tab has unsaved changes.
+    ///
+    /// A pending encoding or line-ending conversion counts as a change.
     pub fn is_modified(&self) -> bool {
-        self.content != self.original_content
+        self.content != self.original_content || self.format != self.saved_format
     }
 
     /// Get the display title for this tab.
@@ -221,33 +261,103 @@ impl Tab {
     }
 
     /// Mark the current content as saved (updates original_content).
+    ///
+    /// Also records the file's current modification time so later external
+    /// changes can be told apart from our own writes, and drops any pending
+    /// disk conflict, since the file now holds this tab's content.
     pub fn mark_saved(&mut self) {
         self.original_content = self.content.clone();
+        self.saved_format = self.format;
+        self.disk_mtime = self.path.as_deref().and_then(file_mtime);
+        self.disk_conflict = None;
+    }
+
+    /// Get the conflicting on-disk content, if the file changed underneath
+    /// unsaved edits.
+    pub fn disk_conflict(&self) -> Option<&str> {
+        self.disk_conflict
+            .as_ref()
+            .map(|(content, _)| content.as_str())
     }
 
-    /// Set new content and push current to undo stack.
+    /// Replace the content with a new on-disk version and mark it saved.
+    ///
+    /// Recorded as an undo step. The cursor and scroll position are kept
+    /// (the editor clamps them if the document got shorter).
+    pub fn reload_from_disk(&mut self, disk_content: String, format: FileFormat) {
+        self.set_content(disk_content);
+        self.format = format;
+        self.mark_saved();
+    }
+
+    /// Accept a new on-disk version as the base without touching the buffer.
+    ///
+    /// Used after "Keep Mine" or a merge: later modification checks and
+    /// merges compare against this version instead of the old one.
+    fn adopt_disk_version(&mut self, disk_content: String, format: FileFormat) {
+        self.original_content = disk_content;
+        self.saved_format = format;
+        self.disk_mtime = self.path.as_deref().and_then(file_mtime);
+        self.disk_conflict = None;
+    }
+
+    /// Get the on-disk format (encoding, BOM, line endings) used when saving.
+    pub fn format(&self) -> FileFormat {
+        self.format
+    }
+
+    /// Convert line endings on the next save.
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.format.line_ending = line_ending;
+    }
+
+    /// Convert the encoding on the next save.
+    ///
+    /// The BOM flag is ignored for encodings that can't carry one.
+    pub fn set_encoding(&mut self, encoding: TextEncoding, bom: bool) {
+        self.format.encoding = encoding;
+        self.format.bom = bom && encoding.supports_bom();
+    }
+
+    /// Check if the file on disk changed since it was last loaded or saved.
+    ///
+    /// Returns `false` for untitled tabs. A file that was deleted or created
+    /// behind our back also counts as an external change.
+    pub fn has_external_change(&self) -> bool {
+        match &self.path {
+            Some(path) => file_mtime(path) != self.disk_mtime,
+            None => false,
+        }
+    }
+
+    /// Set new content and record the change as its own undo step.
+    ///
+    /// Use this for programmatic edits (formatting, replace, etc.) that
+    /// should never merge with surrounding typing.
     pub fn set_content(&mut self, new_content: String) {
         if new_content != self.content {
-            // Push current state to undo stack
-            self.undo_stack.push(self.content.clone());
-            if self.undo_stack.len() > self.max_undo_size {
-                self.undo_stack.remove(0);
-            }
-            // Clear redo stack on new edit
-            self.redo_stack.clear();
+            self.history.record(
+                &self.content,
+                &new_content,
+                self.cursor_snapshot(),
+                false,
+                Instant::now(),
+            );
             self.content = new_content;
+            self.edit_version += 1;
         }
     }
 
     /// Undo the last edit.
     ///
-    /// Returns `true` if undo was performed.
+    /// Returns `true` if undo was performed. Restores the cursor and selection
+    /// from before the edit.
     /// Increments `content_version` to signal external content change to UI widgets.
     pub fn undo(&mut self) -> bool {
-        if let Some(previous) = self.undo_stack.pop() {
-            self.redo_stack.push(self.content.clone());
-            self.content = previous;
+        if let Some(snapshot) = self.history.undo(&mut self.content) {
+            self.restore_cursor(snapshot);
             self.content_version = self.content_version.wrapping_add(1);
+            self.edit_version += 1;
             true
         } else {
             false
@@ -256,13 +366,14 @@ impl Tab {
 
     /// Redo the last undone edit.
     ///
-    /// Returns `true` if redo was performed.
+    /// Returns `true` if redo was performed. Restores the cursor and selection
+    /// from after the edit.
     /// Increments `content_version` to signal external content change to UI widgets.
     pub fn redo(&mut self) -> bool {
-        if let Some(next) = self.redo_stack.pop() {
-            self.undo_stack.push(self.content.clone());
-            self.content = next;
+        if let Some(snapshot) = self.history.redo(&mut self.content) {
+            self.restore_cursor(snapshot);
             self.content_version = self.content_version.wrapping_add(1);
+            self.edit_version += 1;
             true
         } else {
             false
@@ -271,22 +382,32 @@ impl Tab {
 
     /// Check if undo is available.
     pub fn can_undo(&self) -> bool {
-        !self.undo_stack.is_empty()
+        self.history.can_undo()
     }
 
     /// Check if redo is available.
     pub fn can_redo(&self) -> bool {
-        !self.redo_stack.is_empty()
+        self.history.can_redo()
     }
 
-    /// Get the number of items in the undo stack.
+    /// Get the number of steps in the undo history.
     pub fn undo_count(&self) -> usize {
-        self.undo_stack.len()
+        self.history.undo_count()
     }
 
-    /// Get the number of items in the redo stack.
+    /// Get the number of steps in the redo history.
     pub fn redo_count(&self) -> usize {
-        self.redo_stack.len()
+        self.history.redo_count()
+    }
+
+    /// Get the approximate memory used by the undo/redo history in bytes.
+    pub fn history_bytes(&self) -> usize {
+        self.history.used_bytes()
+    }
+
+    /// Set the byte budget for the undo/redo history.
+    pub fn set_history_budget(&mut self, budget: usize) {
+        self.history.set_budget(budget);
     }
 
     /// Get the content version counter.
@@ -298,27 +419,46 @@ impl Tab {
         self.content_version
     }
 
+    /// Get the edit counter.
+    ///
+    /// This counter changes with every edit, undo, redo or reload, so
+    /// anything derived from the content (like search matches) can tell it
+    /// is out of date without comparing the text.
+    pub fn edit_version(&self) -> u64 {
+        self.edit_version
+    }
+
     /// Record that an edit was made externally (e.g., by egui's TextEdit).
     ///
     /// Call this AFTER content has been modified, passing the OLD content
-    /// before the modification. This is needed because TextEdit modifies
+    /// and the cursor from before the modification (see
+    /// [`Self::cursor_snapshot`]). This is needed because TextEdit modifies
     /// the content string directly, bypassing `set_content()`.
     ///
     /// This method:
-    /// - Pushes the old content to the undo stack
+    /// - Records the difference as a text delta in the undo history
+    /// - Merges consecutive typing/deleting into a single undo step
     /// - Clears the redo stack (new edits invalidate redo history)
-    /// - Enforces the maximum undo history size
-    pub fn record_edit(&mut self, old_content: String) {
-        // Only record if content actually changed
-        if old_content != self.content {
-            self.undo_stack.push(old_content);
-            if self.undo_stack.len() > self.max_undo_size {
-                self.undo_stack.remove(0);
-            }
-            self.redo_stack.clear();
+    pub fn record_edit(&mut self, old_content: String, before: CursorSnapshot) {
+        let recorded =
+            self.history
+                .record(&old_content, &self.content, before, true, Instant::now());
+        if recorded {
+            self.edit_version += 1;
         }
     }
 
+    /// Capture the current cursor and selection for the undo history.
+    pub fn cursor_snapshot(&self) -> CursorSnapshot {
+        CursorSnapshot::new(self.cursor_position, self.selection)
+    }
+
+    /// Restore cursor and selection from an undo history snapshot.
+    fn restore_cursor(&mut self, snapshot: CursorSnapshot) {
+        self.cursor_position = snapshot.cursor;
+        self.selection = snapshot.selection;
+    }
+
     /// Convert to TabInfo for session persistence.
     pub fn to_tab_info(&self) -> TabInfo {
         TabInfo {
@@ -327,6 +467,7 @@ impl Tab {
             cursor_position: self.cursor_position,
             scroll_offset: self.scroll_offset,
             view_mode: self.view_mode,
+            format: self.format,
         }
     }
 
@@ -346,6 +487,12 @@ impl Tab {
         self.view_mode
     }
 
+    /// Toggle the side-by-side split view on or off.
+    pub fn toggle_split_view(&mut self) -> ViewMode {
+        self.view_mode = self.view_mode.toggle_split();
+        self.view_mode
+    }
+
     /// Get the file type for this tab.
     ///
     /// Returns the cached file type, which is determined from the
@@ -370,6 +517,12 @@ impl Default for Tab {
     }
 }
 
+/// Read a file's modification time, or `None` if it doesn't exist or
+/// the platform doesn't report one.
+fn file_mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // UI State
 // ─────────────────────────────────────────────────────────────────────────────
@@ -409,6 +562,29 @@ pub struct UiState {
     pub toast_expires_at: Option<f64>,
     /// Whether the recent files popup is open
     pub show_recent_files_popup: bool,
+    /// Recovered tabs whose file changed on disk while Ferrite wasn't
+    /// running; each needs the user to pick a version
+    pub recovery_conflicts: Vec<RecoveryConflict>,
+}
+
+/// A recovered tab whose file was modified on disk after the snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecoveryConflict {
+    /// ID of the tab holding the recovered content
+    pub tab_id: usize,
+    /// Path of the file that changed on disk
+    pub path: PathBuf,
+}
+
+/// What happened when an open file was found changed on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalChange {
+    /// Nothing to do (our own write, identical content, or not open)
+    None,
+    /// The tab had no unsaved edits and was reloaded
+    Reloaded,
+    /// The tab has unsaved edits; the user must choose a version
+    Conflict,
 }
 
 /// Actions that may need confirmation before execution.
@@ -467,6 +643,16 @@ pub struct AppState {
     workspace_watcher: Option<WorkspaceWatcher>,
     /// Pending file events from the watcher that need to be processed
     pub pending_file_events: Vec<WorkspaceEvent>,
+    /// Hot-exit writer for unsaved tab contents (None if unavailable)
+    recovery_writer: Option<RecoveryWriter>,
+    /// Id and edit version of each dirty tab in the last recovery snapshot
+    /// (None before the first one)
+    recovery_versions: Option<Vec<(usize, u64)>>,
+    /// Watcher for open files outside the workspace (created on demand)
+    open_files_watcher: Option<OpenFilesWatcher>,
+    /// Whether this window owns the config, session and crash recovery; false for
+    /// an extra `--new-window` instance, which leaves both to the main one
+    persist_session: bool,
 }
 
 impl AppState {
@@ -478,6 +664,22 @@ impl AppState {
     /// 3. Creating an initial empty tab if no tabs were restored
     /// 4. Setting up default UI state
     pub fn new() -> Self {
+        Self::load(true)
+    }
+
+    /// Create a new AppState for an extra window (`--new-window`).
+    ///
+    /// The window starts without the last session's tabs and never touches
+    /// the session or the crash recovery snapshot, so it can't duplicate or
+    /// overwrite the main window's. Unsaved work left by a crash is restored
+    /// by the next normal launch.
+    pub fn new_without_session() -> Self {
+        Self::load(false)
+    }
+
+    /// Load settings and, for the main window, restore the previous session
+    /// and crash-recovered tabs.
+    fn load(persist_session: bool) -> Self {
         let settings = load_config();
         info!("AppState initialized with settings");
         debug!(
@@ -496,10 +698,38 @@ impl AppState {
             workspace: None,
             workspace_watcher: None,
             pending_file_events: Vec::new(),
+            recovery_writer: None,
+            recovery_versions: None,
+            open_files_watcher: None,
+            persist_session,
         };
 
         // Try to restore tabs from previous session
-        state.restore_session_tabs();
+        if persist_session {
+            state.restore_session_tabs();
+        }
+
+        // Bring back unsaved edits left behind by a crash or forced quit
+        let recovery_store = persist_session
+            .then(|| RecoveryStore::open_default().ok())
+            .flatten();
+        let recovered = recovery_store
+            .as_ref()
+            .map(|store| store.load())
+            .unwrap_or_default();
+        state.recovery_writer = recovery_store.and_then(|store| {
+            RecoveryWriter::spawn(store)
+                .map_err(|e| warn!("Failed to start recovery writer: {}", e))
+                .ok()
+        });
+        let recovered_count = state.restore_recovered_tabs(recovered);
+        if recovered_count > 0 {
+            state.show_toast(
+                format!("Recovered {} unsaved tab(s)", recovered_count),
+                0.0,
+                5.0,
+            );
+        }
 
         // If no tabs were restored, create an initial empty tab
         if state.tabs.is_empty() {
@@ -528,9 +758,10 @@ impl AppState {
         for tab_info in &tab_infos {
             if let Some(path) = &tab_info.path {
                 // Try to read the file
-                match std::fs::read_to_string(path) {
-                    Ok(content) => {
-                        let tab = Tab::from_tab_info(self.next_tab_id, tab_info, content);
+                match read_text_file(path) {
+                    Ok((content, format)) => {
+                        let tab = Tab::from_tab_info(self.next_tab_id, tab_info, content)
+                            .with_format(format);
                         self.next_tab_id += 1;
                         self.tabs.push(tab);
                         debug!("Restored tab: {}", path.display());
@@ -561,6 +792,142 @@ impl AppState {
         }
     }
 
+    /// Restore unsaved tab contents from a recovery snapshot.
+    ///
+    /// Entries for files already restored from the session replace that tab's
+    /// content; other files are reopened first. Untitled buffers become new
+    /// tabs. The recovered text is applied as an undoable edit on top of the
+    /// on-disk content, so the tab stays marked as modified. Files that
+    /// changed on disk after the snapshot are queued in
+    /// `ui.recovery_conflicts` for the user to resolve.
+    ///
+    /// Returns the number of tabs restored.
+    pub fn restore_recovered_tabs(&mut self, entries: Vec<RecoveryEntry>) -> usize {
+        let mut count = 0;
+
+        for entry in entries {
+            let index = match &entry.tab.path {
+                Some(path) => match self.find_tab_by_path(path) {
+                    Some(index) => index,
+                    None => {
+                        // A deleted file restores as a buffer with empty
+                        // original content, so it still counts as modified
+                        let (disk_content, format) =
+                            read_text_file(path).unwrap_or((String::new(), entry.tab.format));
+                        self.push_tab(
+                            Tab::from_tab_info(self.next_tab_id, &entry.tab, disk_content)
+                                .with_format(format),
+                        )
+                    }
+                },
+                None => self.push_tab(Tab::from_tab_info(
+                    self.next_tab_id,
+                    &entry.tab,
+                    String::new(),
+                )),
+            };
+
+            let tab = &mut self.tabs[index];
+            tab.set_content(entry.content);
+            tab.format = entry.tab.format;
+            tab.cursor_position = entry.tab.cursor_position;
+            tab.scroll_offset = entry.tab.scroll_offset;
+
+            if let Some(path) = &entry.tab.path {
+                let current = file_mtime(path);
+                if current.is_some() && current != entry.disk_mtime {
+                    info!(
+                        "Recovered tab conflicts with changes on disk: {}",
+                        path.display()
+                    );
+                    self.ui.recovery_conflicts.push(RecoveryConflict {
+                        tab_id: tab.id,
+                        path: path.clone(),
+                    });
+                }
+            }
+            count += 1;
+        }
+
+        if count > 0 {
+            info!("Restored {} tab(s) from recovery snapshot", count);
+        }
+        count
+    }
+
+    /// Resolve the first pending recovery conflict.
+    ///
+    /// If `keep_recovered` is true the recovered edits stay in the tab.
+    /// Otherwise the tab is reloaded from disk (the recovered text remains
+    /// reachable through undo).
+    pub fn resolve_recovery_conflict(&mut self, keep_recovered: bool) {
+        if self.ui.recovery_conflicts.is_empty() {
+            return;
+        }
+        let conflict = self.ui.recovery_conflicts.remove(0);
+        if keep_recovered {
+            return;
+        }
+
+        let Some(tab) = self.tabs.iter_mut().find(|t| t.id == conflict.tab_id) else {
+            return;
+        };
+        match read_text_file(&conflict.path) {
+            Ok((disk_content, format)) => {
+                tab.reload_from_disk(disk_content, format);
+                info!("Reloaded {} from disk", conflict.path.display());
+            }
+            Err(e) => {
+                self.show_error(format!(
+                    "Failed to reload '{}':\n{}",
+                    conflict.path.display(),
+                    e
+                ));
+            }
+        }
+    }
+
+    /// Collect recovery entries for every tab with unsaved changes.
+    pub fn recovery_entries(&self) -> Vec<RecoveryEntry> {
+        self.tabs
+            .iter()
+            .filter(|t| t.is_modified())
+            .map(|t| RecoveryEntry {
+                tab: t.to_tab_info(),
+                content: t.content.clone(),
+                disk_mtime: t.disk_mtime,
+            })
+            .collect()
+    }
+
+    /// Queue the current unsaved tab contents for the recovery store.
+    ///
+    /// Call this periodically; nothing is collected unless a dirty tab was
+    /// edited, saved or closed since the last snapshot.
+    pub fn snapshot_recovery(&mut self) {
+        let Some(writer) = &self.recovery_writer else {
+            return;
+        };
+        let versions: Vec<(usize, u64)> = self
+            .tabs
+            .iter()
+            .filter(|t| t.is_modified())
+            .map(|t| (t.id, t.edit_version))
+            .collect();
+        if self.recovery_versions.as_ref() == Some(&versions) {
+            return;
+        }
+        writer.write(self.recovery_entries());
+        self.recovery_versions = Some(versions);
+    }
+
+    /// Append a tab and advance the tab ID counter, returning its index.
+    fn push_tab(&mut self, tab: Tab) -> usize {
+        self.next_tab_id = self.next_tab_id.max(tab.id + 1);
+        self.tabs.push(tab);
+        self.tabs.len() - 1
+    }
+
     /// Create AppState with custom settings (useful for testing).
     ///
     /// This also restores tabs from `settings.last_open_tabs` if available.
@@ -576,6 +943,10 @@ impl AppState {
             workspace: None,
             workspace_watcher: None,
             pending_file_events: Vec::new(),
+            recovery_writer: None,
+            recovery_versions: None,
+            open_files_watcher: None,
+            persist_session: true,
         };
 
         // Try to restore tabs from session data
@@ -673,11 +1044,11 @@ impl AppState {
             return Ok(index);
         }
 
-        // Read file content
-        let content = std::fs::read_to_string(&path)?;
+        // Read and decode file content (binary files are refused)
+        let (content, format) = read_text_file(&path)?;
 
         // Create new tab
-        let tab = Tab::with_file(self.next_tab_id, path.clone(), content);
+        let tab = Tab::with_file(self.next_tab_id, path.clone(), content).with_format(format);
         self.next_tab_id += 1;
         self.tabs.push(tab);
         let new_index = self.tabs.len() - 1;
@@ -696,6 +1067,30 @@ impl AppState {
         Ok(new_index)
     }
 
+    /// Open a file, or a new empty tab for it if it doesn't exist yet.
+    ///
+    /// The file is created on the first save, which is what tools that use
+    /// Ferrite as `$EDITOR` expect.
+    pub fn open_or_create_file(&mut self, path: PathBuf) -> Result<usize, std::io::Error> {
+        if path.exists() || self.find_tab_by_path(&path).is_some() {
+            return self.open_file(path);
+        }
+
+        let index = self.new_tab();
+        self.tabs[index].set_path(path.clone());
+        info!("Opened new file: {}", path.display());
+        Ok(index)
+    }
+
+    /// Open an untitled tab with the given content (e.g. piped from stdin).
+    ///
+    /// The tab starts out modified since the content isn't saved anywhere.
+    pub fn open_untitled(&mut self, content: String) -> usize {
+        let index = self.new_tab();
+        self.tabs[index].set_content(content);
+        index
+    }
+
     /// Find a tab by file path.
     pub fn find_tab_by_path(&self, path: &PathBuf) -> Option<usize> {
         self.tabs.iter().position(|t| t.path.as_ref() == Some(path))
@@ -778,17 +1173,39 @@ impl AppState {
     ///
     /// Returns an error if the tab has no path (use `save_as` instead).
     pub fn save_active_tab(&mut self) -> Result<(), crate::error::Error> {
+        self.save_tab(self.active_tab_index)
+    }
+
+    /// Save the tab at `index` to its file path.
+    ///
+    /// Returns an error if the tab doesn't exist or has no path.
+    pub fn save_tab(&mut self, index: usize) -> Result<(), crate::error::Error> {
+        self.write_tab(index, true)
+    }
+
+    /// Write the tab at `index` to its file path, backing up the previous
+    /// version first when `backup` is set.
+    fn write_tab(&mut self, index: usize, backup: bool) -> Result<(), crate::error::Error> {
+        let backup_policy = self.backup_policy();
         let tab = self
-            .active_tab_mut()
+            .tabs
+            .get_mut(index)
             .ok_or_else(|| crate::error::Error::Application("No active tab".to_string()))?;
 
         let path = tab.path.clone().ok_or_else(|| {
             crate::error::Error::Application("No file path set. Use 'Save As' instead.".to_string())
         })?;
 
-        std::fs::write(&path, &tab.content).map_err(|e| crate::error::Error::FileWrite {
-            path: path.clone(),
-            source: e,
+        if backup {
+            if let Err(e) = backup_policy.backup(&path) {
+                warn!("Failed to back up {}: {}", path.display(), e);
+            }
+        }
+        write_text_file(&path, &tab.content, &tab.format).map_err(|e| {
+            crate::error::Error::FileWrite {
+                path: path.clone(),
+                source: e,
+            }
         })?;
 
         tab.mark_saved();
@@ -796,15 +1213,76 @@ impl AppState {
         Ok(())
     }
 
+    /// Get the backup policy for saves, including workspace overrides.
+    pub fn backup_policy(&self) -> BackupPolicy {
+        BackupPolicy::resolve(&self.settings, self.workspace.as_ref())
+    }
+
+    /// Rewrite a file that isn't open in a tab (e.g. for replace in files),
+    /// backing up the current version first like a save does.
+    pub fn rewrite_file(
+        &self,
+        path: &Path,
+        text: &str,
+        format: &FileFormat,
+    ) -> std::io::Result<()> {
+        if let Err(e) = self.backup_policy().backup(path) {
+            warn!("Failed to back up {}: {}", path.display(), e);
+        }
+        write_text_file(path, text, format)
+    }
+
+    /// Auto-save every modified tab that has a file path.
+    ///
+    /// Untitled tabs are skipped silently. Tabs whose file changed on disk
+    /// since it was last loaded or saved are left alone, so an auto-save never
+    /// overwrites an external edit; the user can still save explicitly.
+    /// Auto-saves don't make backups, which would otherwise rotate out the
+    /// versions kept by explicit saves within minutes.
+    pub fn auto_save_modified_tabs(&mut self) -> AutoSaveReport {
+        let mut report = AutoSaveReport::default();
+
+        for index in 0..self.tabs.len() {
+            let tab = &self.tabs[index];
+            let Some(path) = tab.path.clone() else {
+                continue;
+            };
+            if !tab.is_modified() {
+                continue;
+            }
+            if tab.has_external_change() {
+                debug!("Auto-save skipped (changed on disk): {}", path.display());
+                report.skipped_external.push(path);
+                continue;
+            }
+
+            match self.write_tab(index, false) {
+                Ok(()) => report.saved.push(path),
+                Err(e) => {
+                    warn!("Auto-save failed for {}: {}", path.display(), e);
+                    report.failed.push((path, e));
+                }
+            }
+        }
+
+        report
+    }
+
     /// Save the active tab to a new path.
     pub fn save_active_tab_as(&mut self, path: PathBuf) -> Result<(), crate::error::Error> {
+        let backup_policy = self.backup_policy();
         let tab = self
             .active_tab_mut()
             .ok_or_else(|| crate::error::Error::Application("No active tab".to_string()))?;
 
-        std::fs::write(&path, &tab.content).map_err(|e| crate::error::Error::FileWrite {
-            path: path.clone(),
-            source: e,
+        if let Err(e) = backup_policy.backup(&path) {
+            warn!("Failed to back up {}: {}", path.display(), e);
+        }
+        write_text_file(&path, &tab.content, &tab.format).map_err(|e| {
+            crate::error::Error::FileWrite {
+                path: path.clone(),
+                source: e,
+            }
         })?;
 
         tab.path = Some(path.clone());
@@ -894,10 +1372,12 @@ impl AppState {
         info!("Workspace closed, returned to single-file mode");
     }
 
-    /// Poll the file watcher for new events.
+    /// Poll the file watchers for new events.
     ///
     /// This should be called periodically (e.g., in the update loop).
-    /// Events are stored in pending_file_events for processing.
+    /// Events are stored in pending_file_events for processing. Open files
+    /// outside the workspace (or all open files in single-file mode) are
+    /// watched individually.
     pub fn poll_file_watcher(&mut self) {
         if let Some(watcher) = &self.workspace_watcher {
             if let Some(workspace) = &self.workspace {
@@ -909,6 +1389,130 @@ impl AppState {
                 }
             }
         }
+
+        self.sync_open_files_watcher();
+        if let Some(watcher) = &self.open_files_watcher {
+            self.pending_file_events.extend(watcher.poll_events());
+        }
+    }
+
+    /// Keep the open-files watcher in sync with the open tabs.
+    fn sync_open_files_watcher(&mut self) {
+        let root = self.workspace_root().cloned();
+        let files: HashSet<PathBuf> = self
+            .tabs
+            .iter()
+            .filter_map(|t| t.path.clone())
+            .filter(|p| root.as_ref().map_or(true, |r| !p.starts_with(r)))
+            .collect();
+
+        if self.open_files_watcher.is_none() {
+            if files.is_empty() {
+                return;
+            }
+            match OpenFilesWatcher::new() {
+                Ok(watcher) => self.open_files_watcher = Some(watcher),
+                Err(e) => {
+                    warn!("{}", e);
+                    return;
+                }
+            }
+        }
+        if let Some(watcher) = &mut self.open_files_watcher {
+            watcher.set_files(files);
+        }
+    }
+
+    /// React to an open file changing on disk.
+    ///
+    /// Clean tabs are reloaded in place. Tabs with unsaved edits get a
+    /// pending `disk_conflict` for the user to resolve. Notifications caused
+    /// by our own saves (or by touches that didn't change the content) are
+    /// ignored.
+    pub fn check_external_change(&mut self, path: &Path) -> ExternalChange {
+        let Some(index) = self
+            .tabs
+            .iter()
+            .position(|t| t.path.as_deref() == Some(path))
+        else {
+            return ExternalChange::None;
+        };
+        let tab = &mut self.tabs[index];
+        if tab.disk_conflict.is_none() && !tab.has_external_change() {
+            return ExternalChange::None;
+        }
+
+        // The file may be mid-write or gone; a later event will follow
+        let Ok((disk_content, format)) = read_text_file(path) else {
+            return ExternalChange::None;
+        };
+
+        if disk_content == tab.content {
+            // Both sides agree - nothing left to save or resolve
+            if tab.format == tab.saved_format {
+                tab.format = format;
+            }
+            tab.adopt_disk_version(disk_content, format);
+            ExternalChange::None
+        } else if !tab.is_modified() {
+            info!("Reloaded externally changed file: {}", path.display());
+            tab.reload_from_disk(disk_content, format);
+            ExternalChange::Reloaded
+        } else if disk_content == tab.original_content {
+            tab.adopt_disk_version(disk_content, format);
+            ExternalChange::None
+        } else {
+            info!(
+                "External change conflicts with unsaved edits: {}",
+                path.display()
+            );
+            tab.disk_conflict = Some((disk_content, format));
+            ExternalChange::Conflict
+        }
+    }
+
+    /// Resolve a disk conflict by reloading the on-disk version.
+    ///
+    /// The discarded edits remain reachable through undo.
+    pub fn reload_tab_from_disk(&mut self, index: usize) {
+        if let Some(tab) = self.tabs.get_mut(index) {
+            if let Some((disk_content, format)) = tab.disk_conflict.take() {
+                tab.reload_from_disk(disk_content, format);
+            }
+        }
+    }
+
+    /// Resolve a disk conflict by keeping the editor's version.
+    ///
+    /// The tab stays modified; the next save overwrites the disk version.
+    pub fn keep_tab_changes(&mut self, index: usize) {
+        if let Some(tab) = self.tabs.get_mut(index) {
+            if let Some((disk_content, format)) = tab.disk_conflict.take() {
+                tab.adopt_disk_version(disk_content, format);
+            }
+        }
+    }
+
+    /// Three-way merge the editor's version with a conflicting disk version,
+    /// using the content at the last load/save as the base.
+    pub fn merge_tab_with_disk(&self, index: usize) -> Option<MergeResult> {
+        let tab = self.tabs.get(index)?;
+        let disk_content = tab.disk_conflict()?;
+        Some(three_way_merge(
+            &tab.original_content,
+            &tab.content,
+            disk_content,
+        ))
+    }
+
+    /// Resolve a disk conflict by applying merged text (as an undo step).
+    pub fn apply_tab_merge(&mut self, index: usize, merged: String) {
+        if let Some(tab) = self.tabs.get_mut(index) {
+            if let Some((disk_content, format)) = tab.disk_conflict.take() {
+                tab.set_content(merged);
+                tab.adopt_disk_version(disk_content, format);
+            }
+        }
     }
 
     /// Take pending file events (clears the list).
@@ -972,8 +1576,14 @@ impl AppState {
 
     /// Save settings to config file if modified.
     ///
-    /// Returns `true` if settings were saved.
+    /// Returns `true` if settings were saved. An extra window never writes
+    /// the config, so the main window's recent files, window state and
+    /// session stay as it left them.
     pub fn save_settings_if_dirty(&mut self) -> bool {
+        if !self.persist_session {
+            self.settings_dirty = false;
+            return false;
+        }
         if self.settings_dirty {
             // Update session restoration data
             self.settings.last_open_tabs = self.tabs.iter().map(|t| t.to_tab_info()).collect();
@@ -1061,6 +1671,12 @@ impl AppState {
         }
 
         self.save_settings();
+
+        // A clean exit means unsaved changes were explicitly discarded
+        if let Some(writer) = self.recovery_writer.take() {
+            writer.clear();
+        }
+
         info!("AppState shutdown complete");
     }
 
@@ -1342,7 +1958,7 @@ mod tests {
         // Simulate external edit (like TextEdit does)
         let old_content = tab.content.clone();
         tab.content = "first edit".to_string();
-        tab.record_edit(old_content);
+        tab.record_edit(old_content, tab.cursor_snapshot());
 
         assert!(tab.can_undo());
         assert_eq!(tab.undo_count(), 1);
@@ -1350,7 +1966,7 @@ mod tests {
         // Simulate another edit
         let old_content = tab.content.clone();
         tab.content = "second edit".to_string();
-        tab.record_edit(old_content);
+        tab.record_edit(old_content, tab.cursor_snapshot());
 
         assert_eq!(tab.undo_count(), 2);
         assert!(!tab.can_redo());
@@ -1361,6 +1977,46 @@ mod tests {
         assert!(tab.can_redo());
     }
 
+    #[test]
+    fn test_tab_edit_version_counts_changes() {
+        let mut tab = Tab::new(0);
+        tab.set_content("one".to_string());
+        let version = tab.edit_version();
+
+        // No change, no new version
+        tab.set_content("one".to_string());
+        tab.record_edit("one".to_string(), tab.cursor_snapshot());
+        assert_eq!(tab.edit_version(), version);
+
+        let old_content = tab.content.clone();
+        tab.content = "two".to_string();
+        tab.record_edit(old_content, tab.cursor_snapshot());
+        assert_ne!(tab.edit_version(), version);
+
+        let version = tab.edit_version();
+        tab.undo();
+        assert_ne!(tab.edit_version(), version);
+        let version = tab.edit_version();
+        tab.redo();
+        assert_ne!(tab.edit_version(), version);
+    }
+
+    #[test]
+    fn test_tab_undo_after_unrecorded_edit() {
+        let mut tab = Tab::new(0);
+        tab.set_content("a longer line of text to undo into".to_string());
+        tab.set_content("a longer line of text to undo into, edited".to_string());
+
+        // Changed behind the history's back: the deltas no longer apply
+        tab.content = "short".to_string();
+        let version = tab.content_version();
+        assert!(!tab.undo());
+        assert_eq!(tab.content, "short");
+        assert_eq!(tab.content_version(), version);
+        assert!(!tab.can_undo());
+        assert!(!tab.can_redo());
+    }
+
     #[test]
     fn test_tab_record_edit_no_change() {
         let mut tab = Tab::new(0);
@@ -1368,7 +2024,7 @@ mod tests {
 
         // Recording with same content should not add to undo stack
         let old_content = tab.content.clone();
-        tab.record_edit(old_content);
+        tab.record_edit(old_content, tab.cursor_snapshot());
 
         assert!(!tab.can_undo());
         assert_eq!(tab.undo_count(), 0);
@@ -1386,7 +2042,7 @@ mod tests {
         // New edit via record_edit should clear redo
         let old_content = tab.content.clone();
         tab.content = "new edit".to_string();
-        tab.record_edit(old_content);
+        tab.record_edit(old_content, tab.cursor_snapshot());
 
         assert!(!tab.can_redo());
     }
@@ -1419,27 +2075,72 @@ mod tests {
     }
 
     #[test]
-    fn test_tab_max_undo_size() {
+    fn test_tab_history_byte_budget() {
         let mut tab = Tab::new(0);
-        // Max undo size is 100 by default
+        tab.set_history_budget(1024);
 
-        // Add 105 edits
-        for i in 0..105 {
-            tab.set_content(format!("edit {}", i));
+        // Each set_content is a separate step; large edits exceed the budget
+        for i in 0..50 {
+            tab.set_content(format!("{}{}", "x".repeat(100), i));
         }
 
-        // Should be capped at 100
-        assert_eq!(tab.undo_count(), 100);
+        assert!(tab.undo_count() < 50);
+        assert!(tab.history_bytes() <= 1024);
 
-        // Oldest edits should be dropped, so undoing 100 times
-        // should get us back to edit 4 (edits 0-4 were dropped)
-        for _ in 0..100 {
-            tab.undo();
+        // Undoing everything lands on the oldest kept state, not the empty doc
+        while tab.undo() {}
+        assert!(!tab.content.is_empty());
+        assert!(!tab.can_undo());
+    }
+
+    #[test]
+    fn test_tab_typing_merges_into_one_undo_step() {
+        let mut tab = Tab::new(0);
+        for ch in "hello".chars() {
+            let old_content = tab.content.clone();
+            tab.content.push(ch);
+            tab.record_edit(old_content, tab.cursor_snapshot());
         }
 
-        // After 100 undos, we should be at the oldest kept state
-        assert_eq!(tab.content, "edit 4");
-        assert!(!tab.can_undo());
+        assert_eq!(tab.undo_count(), 1);
+        tab.undo();
+        assert!(tab.content.is_empty());
+    }
+
+    #[test]
+    fn test_tab_undo_restores_cursor_and_selection() {
+        let mut tab = Tab::new(0);
+        tab.set_content("hello world".to_string());
+        tab.cursor_position = (0, 11);
+        tab.selection = Some((6, 11));
+
+        tab.set_content("hello there".to_string());
+        tab.cursor_position = (0, 0);
+        tab.selection = None;
+
+        let version = tab.content_version();
+        assert!(tab.undo());
+        assert_eq!(tab.content, "hello world");
+        assert_eq!(tab.cursor_position, (0, 11));
+        assert_eq!(tab.selection, Some((6, 11)));
+        assert_eq!(tab.content_version(), version + 1);
+    }
+
+    #[test]
+    fn test_tab_undo_restores_cursor_from_before_edit() {
+        let mut tab = Tab::new(0);
+        tab.set_content("hello".to_string());
+        tab.cursor_position = (0, 2);
+
+        // The editor moves the cursor along with the edit before it's recorded
+        let old_content = tab.content.clone();
+        let before = tab.cursor_snapshot();
+        tab.content = "heXllo".to_string();
+        tab.cursor_position = (0, 3);
+        tab.record_edit(old_content, before);
+
+        assert!(tab.undo());
+        assert_eq!(tab.cursor_position, (0, 2));
     }
 
     #[test]
@@ -1471,6 +2172,17 @@ mod tests {
         assert_eq!(tab.view_mode, ViewMode::Raw);
     }
 
+    #[test]
+    fn test_tab_split_view_toggle() {
+        let mut tab = Tab::new(0);
+        tab.view_mode = ViewMode::Rendered;
+
+        assert_eq!(tab.toggle_split_view(), ViewMode::Split);
+        let info = tab.to_tab_info();
+        assert_eq!(info.view_mode, ViewMode::Split);
+        assert_eq!(tab.toggle_split_view(), ViewMode::Raw);
+    }
+
     #[test]
     fn test_tab_view_mode_get_set() {
         let mut tab = Tab::new(0);
@@ -1493,9 +2205,11 @@ mod tests {
 
     #[test]
     fn test_appstate_with_custom_settings() {
-        let mut settings = Settings::default();
-        settings.theme = Theme::Dark;
-        settings.font_size = 18.0;
+        let settings = Settings {
+            theme: Theme::Dark,
+            font_size: 18.0,
+            ..Default::default()
+        };
 
         let state = AppState::with_settings(settings);
         assert_eq!(state.settings.theme, Theme::Dark);
@@ -1684,6 +2398,7 @@ mod tests {
             cursor_position: (10, 5),
             scroll_offset: 100.0,
             view_mode: ViewMode::Rendered, // Test restoring rendered mode
+            format: FileFormat::default(),
         };
         let content = "# Test Content".to_string();
 
@@ -1711,14 +2426,17 @@ mod tests {
     #[test]
     fn test_restore_session_tabs_with_missing_file() {
         // When a saved tab's file no longer exists, it should be skipped
-        let mut settings = Settings::default();
-        settings.last_open_tabs = vec![TabInfo {
-            path: Some(PathBuf::from("/nonexistent/file/that/does/not/exist.md")),
-            modified: false,
-            cursor_position: (0, 0),
-            scroll_offset: 0.0,
-            view_mode: ViewMode::Raw,
-        }];
+        let settings = Settings {
+            last_open_tabs: vec![TabInfo {
+                path: Some(PathBuf::from("/nonexistent/file/that/does/not/exist.md")),
+                modified: false,
+                cursor_position: (0, 0),
+                scroll_offset: 0.0,
+                view_mode: ViewMode::Raw,
+                format: FileFormat::default(),
+            }],
+            ..Default::default()
+        };
 
         let state = AppState::with_settings(settings);
 
@@ -1730,14 +2448,17 @@ mod tests {
     #[test]
     fn test_restore_session_tabs_skips_unsaved() {
         // Tabs without a path (unsaved) should be skipped during restore
-        let mut settings = Settings::default();
-        settings.last_open_tabs = vec![TabInfo {
-            path: None, // Unsaved tab
-            modified: true,
-            cursor_position: (5, 10),
-            scroll_offset: 50.0,
-            view_mode: ViewMode::Raw,
-        }];
+        let settings = Settings {
+            last_open_tabs: vec![TabInfo {
+                path: None, // Unsaved tab
+                modified: true,
+                cursor_position: (5, 10),
+                scroll_offset: 50.0,
+                view_mode: ViewMode::Raw,
+                format: FileFormat::default(),
+            }],
+            ..Default::default()
+        };
 
         let state = AppState::with_settings(settings);
 
@@ -1749,9 +2470,11 @@ mod tests {
     #[test]
     fn test_restore_session_tabs_active_index_clamped() {
         // Active tab index should be clamped to valid range
-        let mut settings = Settings::default();
-        settings.last_open_tabs = vec![]; // No tabs to restore
-        settings.active_tab_index = 100; // Invalid index
+        let settings = Settings {
+            last_open_tabs: vec![], // No tabs to restore
+            active_tab_index: 100,  // Invalid index
+            ..Default::default()
+        };
 
         let state = AppState::with_settings(settings);
 
@@ -1776,15 +2499,18 @@ mod tests {
         drop(file);
 
         // Set up settings with this file (with Rendered view mode)
-        let mut settings = Settings::default();
-        settings.last_open_tabs = vec![TabInfo {
-            path: Some(temp_file.clone()),
-            modified: false,
-            cursor_position: (1, 5),
-            scroll_offset: 25.0,
-            view_mode: ViewMode::Rendered, // Test restoring view mode
-        }];
-        settings.active_tab_index = 0;
+        let settings = Settings {
+            last_open_tabs: vec![TabInfo {
+                path: Some(temp_file.clone()),
+                modified: false,
+                cursor_position: (1, 5),
+                scroll_offset: 25.0,
+                view_mode: ViewMode::Rendered, // Test restoring view mode
+                format: FileFormat::default(),
+            }],
+            active_tab_index: 0,
+            ..Default::default()
+        };
 
         let state = AppState::with_settings(settings);
 
@@ -1820,24 +2546,28 @@ mod tests {
             .write_all(b"# File 2")
             .unwrap();
 
-        let mut settings = Settings::default();
-        settings.last_open_tabs = vec![
-            TabInfo {
-                path: Some(temp_file1.clone()),
-                modified: false,
-                cursor_position: (0, 0),
-                scroll_offset: 0.0,
-                view_mode: ViewMode::Raw, // First tab in raw mode
-            },
-            TabInfo {
-                path: Some(temp_file2.clone()),
-                modified: false,
-                cursor_position: (0, 0),
-                scroll_offset: 0.0,
-                view_mode: ViewMode::Rendered, // Second tab in rendered mode
-            },
-        ];
-        settings.active_tab_index = 1; // Second tab active
+        let settings = Settings {
+            last_open_tabs: vec![
+                TabInfo {
+                    path: Some(temp_file1.clone()),
+                    modified: false,
+                    cursor_position: (0, 0),
+                    scroll_offset: 0.0,
+                    view_mode: ViewMode::Raw, // First tab in raw mode
+                    format: FileFormat::default(),
+                },
+                TabInfo {
+                    path: Some(temp_file2.clone()),
+                    modified: false,
+                    cursor_position: (0, 0),
+                    scroll_offset: 0.0,
+                    view_mode: ViewMode::Rendered, // Second tab in rendered mode
+                    format: FileFormat::default(),
+                },
+            ],
+            active_tab_index: 1, // Second tab active
+            ..Default::default()
+        };
 
         let state = AppState::with_settings(settings);
 
@@ -1867,24 +2597,28 @@ mod tests {
             .write_all(b"# Existing File")
             .unwrap();
 
-        let mut settings = Settings::default();
-        settings.last_open_tabs = vec![
-            TabInfo {
-                path: Some(PathBuf::from("/nonexistent/file.md")),
-                modified: false,
-                cursor_position: (0, 0),
-                scroll_offset: 0.0,
-                view_mode: ViewMode::Raw,
-            },
-            TabInfo {
-                path: Some(temp_file.clone()),
-                modified: false,
-                cursor_position: (0, 0),
-                scroll_offset: 0.0,
-                view_mode: ViewMode::Rendered,
-            },
-        ];
-        settings.active_tab_index = 1;
+        let settings = Settings {
+            last_open_tabs: vec![
+                TabInfo {
+                    path: Some(PathBuf::from("/nonexistent/file.md")),
+                    modified: false,
+                    cursor_position: (0, 0),
+                    scroll_offset: 0.0,
+                    view_mode: ViewMode::Raw,
+                    format: FileFormat::default(),
+                },
+                TabInfo {
+                    path: Some(temp_file.clone()),
+                    modified: false,
+                    cursor_position: (0, 0),
+                    scroll_offset: 0.0,
+                    view_mode: ViewMode::Rendered,
+                    format: FileFormat::default(),
+                },
+            ],
+            active_tab_index: 1,
+            ..Default::default()
+        };
 
         let state = AppState::with_settings(settings);
 
@@ -2057,4 +2791,495 @@ mod tests {
         assert!(!state.settings.recent_files.is_empty());
         assert_eq!(state.settings.recent_files[0], temp_file);
     }
+
+    #[test]
+    fn test_auto_save_writes_modified_tabs_and_skips_untitled() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("auto.md");
+        std::fs::write(&path, "# Old").unwrap();
+
+        let mut state = AppState::with_settings(Settings::default());
+        let index = state.open_file(path.clone()).unwrap();
+        state.tab_mut(index).unwrap().content = "# New".to_string();
+        // The initial untitled tab is modified too but has nowhere to go
+        state.tab_mut(0).unwrap().content = "scratch".to_string();
+
+        let report = state.auto_save_modified_tabs();
+
+        assert_eq!(report.saved, vec![path.clone()]);
+        assert!(report.skipped_external.is_empty());
+        assert!(report.failed.is_empty());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# New");
+        assert!(!state.tab(index).unwrap().is_modified());
+        assert!(state.tab(0).unwrap().is_modified());
+    }
+
+    #[test]
+    fn test_only_explicit_saves_back_up() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("notes.md");
+        let backup = temp_dir.path().join("notes.md~");
+        std::fs::write(&path, "one").unwrap();
+
+        let settings = Settings {
+            backup_mode: crate::config::BackupMode::Sibling,
+            ..Default::default()
+        };
+        let mut state = AppState::with_settings(settings);
+        let index = state.open_file(path.clone()).unwrap();
+
+        state.tab_mut(index).unwrap().content = "two".to_string();
+        state.save_tab(index).unwrap();
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "one");
+
+        state.tab_mut(index).unwrap().content = "three".to_string();
+        let report = state.auto_save_modified_tabs();
+        assert_eq!(report.saved, vec![path.clone()]);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "three");
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "one");
+    }
+
+    #[test]
+    fn test_auto_save_skips_files_changed_on_disk() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("external.md");
+        std::fs::write(&path, "# Original").unwrap();
+
+        let mut state = AppState::with_settings(Settings::default());
+        let index = state.open_file(path.clone()).unwrap();
+        state.tab_mut(index).unwrap().content = "# Ours".to_string();
+
+        // Simulate another program writing the file
+        std::fs::write(&path, "# Theirs").unwrap();
+        let later = SystemTime::now() + std::time::Duration::from_secs(5);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+
+        let report = state.auto_save_modified_tabs();
+
+        assert!(report.saved.is_empty());
+        assert_eq!(report.skipped_external, vec![path.clone()]);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# Theirs");
+        assert!(state.tab(index).unwrap().is_modified());
+
+        // An explicit save still goes through and re-syncs the disk state
+        state.save_tab(index).unwrap();
+        assert!(!state.tab(index).unwrap().has_external_change());
+    }
+
+    #[test]
+    fn test_auto_save_reports_write_failures() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("missing_dir").join("gone.md");
+
+        let mut state = AppState::with_settings(Settings::default());
+        let mut tab = Tab::with_file(state.next_tab_id, path.clone(), String::new());
+        tab.content = "unsaved".to_string();
+        state.tabs.push(tab);
+
+        let report = state.auto_save_modified_tabs();
+
+        assert!(report.saved.is_empty());
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, path);
+    }
+
+    #[test]
+    fn test_recovery_entries_only_include_modified_tabs() {
+        let mut state = AppState::with_settings(Settings::default());
+        state.new_tab();
+        state.tab_mut(1).unwrap().content = "draft".to_string();
+
+        let entries = state.recovery_entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].content, "draft");
+        assert!(entries[0].tab.modified);
+        assert!(entries[0].tab.path.is_none());
+    }
+
+    #[test]
+    fn test_restore_recovered_untitled_tab() {
+        let mut state = AppState::with_settings(Settings::default());
+        let entry = RecoveryEntry {
+            tab: TabInfo {
+                cursor_position: (0, 3),
+                ..TabInfo::default()
+            },
+            content: "lost work".to_string(),
+            disk_mtime: None,
+        };
+
+        assert_eq!(state.restore_recovered_tabs(vec![entry]), 1);
+
+        let tab = state.tabs().last().unwrap();
+        assert_eq!(tab.content, "lost work");
+        assert!(tab.is_modified());
+        assert_eq!(tab.cursor_position, (0, 3));
+        assert!(state.ui.recovery_conflicts.is_empty());
+        // Tab IDs stay unique
+        assert_ne!(state.tabs()[0].id, tab.id);
+    }
+
+    #[test]
+    fn test_restore_recovered_file_tab_without_conflict() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("doc.md");
+        std::fs::write(&path, "# On disk").unwrap();
+
+        let mut state = AppState::with_settings(Settings::default());
+        let entry = RecoveryEntry {
+            tab: TabInfo {
+                path: Some(path.clone()),
+                modified: true,
+                ..TabInfo::default()
+            },
+            content: "# Unsaved".to_string(),
+            disk_mtime: file_mtime(&path),
+        };
+
+        state.restore_recovered_tabs(vec![entry]);
+
+        let index = state.find_tab_by_path(&path).unwrap();
+        let tab = state.tab_mut(index).unwrap();
+        assert_eq!(tab.content, "# Unsaved");
+        assert!(tab.is_modified());
+        assert!(state.ui.recovery_conflicts.is_empty());
+
+        // Undo goes back to the on-disk content
+        let tab = state.tab_mut(index).unwrap();
+        assert!(tab.undo());
+        assert_eq!(tab.content, "# On disk");
+    }
+
+    #[test]
+    fn test_restore_recovered_file_changed_on_disk_conflicts() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("doc.md");
+        std::fs::write(&path, "# Edited elsewhere").unwrap();
+
+        let mut state = AppState::with_settings(Settings::default());
+        let entry = RecoveryEntry {
+            tab: TabInfo {
+                path: Some(path.clone()),
+                modified: true,
+                ..TabInfo::default()
+            },
+            content: "# Recovered".to_string(),
+            disk_mtime: Some(SystemTime::UNIX_EPOCH),
+        };
+
+        state.restore_recovered_tabs(vec![entry]);
+        assert_eq!(state.ui.recovery_conflicts.len(), 1);
+        assert_eq!(state.ui.recovery_conflicts[0].path, path);
+
+        // Choosing the disk version reloads the tab and clears the conflict
+        state.resolve_recovery_conflict(false);
+        assert!(state.ui.recovery_conflicts.is_empty());
+        let index = state.find_tab_by_path(&path).unwrap();
+        let tab = state.tab(index).unwrap();
+        assert_eq!(tab.content, "# Edited elsewhere");
+        assert!(!tab.is_modified());
+    }
+
+    #[test]
+    fn test_resolve_recovery_conflict_keep_recovered() {
+        let mut state = AppState::with_settings(Settings::default());
+        let tab_id = state.tabs()[0].id;
+        state.tab_mut(0).unwrap().content = "mine".to_string();
+        state.ui.recovery_conflicts.push(RecoveryConflict {
+            tab_id,
+            path: PathBuf::from("/nonexistent/doc.md"),
+        });
+
+        state.resolve_recovery_conflict(true);
+
+        assert!(state.ui.recovery_conflicts.is_empty());
+        assert_eq!(state.tabs()[0].content, "mine");
+        assert!(!state.ui.show_error_modal);
+    }
+
+    /// Write `content` to `path` as another program would, with a distinct mtime.
+    fn write_externally(path: &Path, content: &str) {
+        std::fs::write(path, content).unwrap();
+        let later = SystemTime::now() + std::time::Duration::from_secs(10);
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_external_change_reloads_clean_tab_keeping_cursor() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("clean.md");
+        std::fs::write(&path, "line one\nline two\n").unwrap();
+
+        let mut state = AppState::with_settings(Settings::default());
+        let index = state.open_file(path.clone()).unwrap();
+        state.tab_mut(index).unwrap().cursor_position = (1, 4);
+        state.tab_mut(index).unwrap().scroll_offset = 20.0;
+
+        write_externally(&path, "line one\nline two changed\n");
+
+        assert_eq!(state.check_external_change(&path), ExternalChange::Reloaded);
+        let tab = state.tab(index).unwrap();
+        assert_eq!(tab.content, "line one\nline two changed\n");
+        assert!(!tab.is_modified());
+        assert_eq!(tab.cursor_position, (1, 4));
+        assert_eq!(tab.scroll_offset, 20.0);
+    }
+
+    #[test]
+    fn test_external_change_ignores_own_save() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("own.md");
+        std::fs::write(&path, "a").unwrap();
+
+        let mut state = AppState::with_settings(Settings::default());
+        let index = state.open_file(path.clone()).unwrap();
+        state.tab_mut(index).unwrap().content = "b".to_string();
+        state.save_tab(index).unwrap();
+
+        assert_eq!(state.check_external_change(&path), ExternalChange::None);
+        assert!(state.tab(index).unwrap().disk_conflict().is_none());
+    }
+
+    #[test]
+    fn test_external_change_on_dirty_tab_raises_conflict() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("dirty.md");
+        std::fs::write(&path, "base\n").unwrap();
+
+        let mut state = AppState::with_settings(Settings::default());
+        let index = state.open_file(path.clone()).unwrap();
+        state.tab_mut(index).unwrap().content = "mine\n".to_string();
+
+        write_externally(&path, "theirs\n");
+
+        assert_eq!(state.check_external_change(&path), ExternalChange::Conflict);
+        let tab = state.tab(index).unwrap();
+        assert_eq!(tab.content, "mine\n");
+        assert_eq!(tab.disk_conflict(), Some("theirs\n"));
+
+        // Saving overwrites the file on purpose and resolves the conflict
+        state.save_tab(index).unwrap();
+        assert!(state.tab(index).unwrap().disk_conflict().is_none());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "mine\n");
+
+        // Auto-save no longer skips the file
+        state.tab_mut(index).unwrap().content = "mine again\n".to_string();
+        let report = state.auto_save_modified_tabs();
+        assert_eq!(report.saved, vec![path.clone()]);
+        assert!(report.skipped_external.is_empty());
+    }
+
+    #[test]
+    fn test_external_touch_without_content_change_is_ignored() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("touch.md");
+        std::fs::write(&path, "same").unwrap();
+
+        let mut state = AppState::with_settings(Settings::default());
+        let index = state.open_file(path.clone()).unwrap();
+        state.tab_mut(index).unwrap().content = "edited".to_string();
+
+        write_externally(&path, "same");
+
+        assert_eq!(state.check_external_change(&path), ExternalChange::None);
+        let tab = state.tab(index).unwrap();
+        assert!(tab.disk_conflict().is_none());
+        assert!(!tab.has_external_change());
+        assert!(tab.is_modified());
+    }
+
+    #[test]
+    fn test_disk_conflict_reload_and_keep_mine() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("resolve.md");
+        std::fs::write(&path, "base\n").unwrap();
+
+        let mut state = AppState::with_settings(Settings::default());
+        let index = state.open_file(path.clone()).unwrap();
+
+        // Keep Mine: buffer untouched, stays modified, conflict acknowledged
+        state.tab_mut(index).unwrap().content = "mine\n".to_string();
+        write_externally(&path, "theirs\n");
+        state.check_external_change(&path);
+        state.keep_tab_changes(index);
+        let tab = state.tab(index).unwrap();
+        assert_eq!(tab.content, "mine\n");
+        assert!(tab.is_modified());
+        assert!(tab.disk_conflict().is_none());
+        assert!(!tab.has_external_change());
+
+        // Reload: buffer replaced by the disk version
+        write_externally(&path, "theirs again\n");
+        state.check_external_change(&path);
+        state.reload_tab_from_disk(index);
+        let tab = state.tab(index).unwrap();
+        assert_eq!(tab.content, "theirs again\n");
+        assert!(!tab.is_modified());
+        assert!(tab.disk_conflict().is_none());
+    }
+
+    #[test]
+    fn test_disk_conflict_three_way_merge() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("merge.md");
+        std::fs::write(&path, "# Title\n\nintro\n\nbody\n").unwrap();
+
+        let mut state = AppState::with_settings(Settings::default());
+        let index = state.open_file(path.clone()).unwrap();
+        state.tab_mut(index).unwrap().content = "# New Title\n\nintro\n\nbody\n".to_string();
+        write_externally(&path, "# Title\n\nintro\n\nbody\nappended\n");
+        state.check_external_change(&path);
+
+        let result = state.merge_tab_with_disk(index).unwrap();
+        assert!(result.is_clean());
+        assert_eq!(result.text, "# New Title\n\nintro\n\nbody\nappended\n");
+
+        state.apply_tab_merge(index, result.text);
+        let tab = state.tab(index).unwrap();
+        assert_eq!(tab.content, "# New Title\n\nintro\n\nbody\nappended\n");
+        assert!(tab.disk_conflict().is_none());
+        // Modified relative to the disk version we merged with
+        assert!(tab.is_modified());
+        assert!(!tab.has_external_change());
+    }
+
+    #[test]
+    fn test_save_preserves_encoding_bom_and_line_endings() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut state = AppState::with_settings(Settings::default());
+
+        let cases: [(&str, &[u8]); 3] = [
+            ("crlf.md", b"one\r\ntwo\r\n"),
+            ("bom.md", b"\xEF\xBB\xBF# Title\n"),
+            ("latin1.md", b"caf\xE9\r\n"),
+        ];
+        for (name, bytes) in cases {
+            let path = temp_dir.path().join(name);
+            std::fs::write(&path, bytes).unwrap();
+
+            let index = state.open_file(path.clone()).unwrap();
+            let tab = state.tab_mut(index).unwrap();
+            assert!(!tab.content.contains('\r'));
+            assert!(!tab.is_modified());
+
+            // Touch the content and save it back
+            tab.content.push('x');
+            tab.content.pop();
+            state.save_tab(index).unwrap();
+            assert_eq!(std::fs::read(&path).unwrap(), bytes, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_open_binary_file_is_refused() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("image.md");
+        std::fs::write(&path, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+
+        let mut state = AppState::with_settings(Settings::default());
+        let err = state.open_file(path).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(state.tab_count(), 1);
+    }
+
+    #[test]
+    fn test_format_change_marks_modified_and_converts_on_save() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("convert.md");
+        std::fs::write(&path, "a\nb\n").unwrap();
+
+        let mut state = AppState::with_settings(Settings::default());
+        let index = state.open_file(path.clone()).unwrap();
+        let tab = state.tab_mut(index).unwrap();
+        tab.set_line_ending(LineEnding::Crlf);
+        tab.set_encoding(TextEncoding::Utf8, true);
+        assert!(tab.is_modified());
+
+        state.save_tab(index).unwrap();
+        assert!(!state.tab(index).unwrap().is_modified());
+        assert_eq!(std::fs::read(&path).unwrap(), b"\xEF\xBB\xBFa\r\nb\r\n");
+    }
+
+    #[test]
+    fn test_rewrite_file_backs_up() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("notes.md");
+        std::fs::write(&path, "old").unwrap();
+
+        let settings = Settings {
+            backup_mode: crate::config::BackupMode::Sibling,
+            ..Default::default()
+        };
+        let state = AppState::with_settings(settings);
+        state
+            .rewrite_file(&path, "new", &FileFormat::default())
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("notes.md~")).unwrap(),
+            "old"
+        );
+    }
+
+    #[test]
+    fn test_open_or_create_missing_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("COMMIT_EDITMSG");
+
+        let mut state = AppState::with_settings(Settings::default());
+        let index = state.open_or_create_file(path.clone()).unwrap();
+        let tab = state.tab_mut(index).unwrap();
+        assert_eq!(tab.path.as_ref(), Some(&path));
+        assert!(!tab.is_modified());
+
+        tab.content = "Fix typo".to_string();
+        state.save_tab(index).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Fix typo");
+
+        // A second call reuses the tab
+        assert_eq!(state.open_or_create_file(path).unwrap(), index);
+    }
+
+    #[test]
+    fn test_extra_window_never_saves_settings() {
+        let mut state = AppState::with_settings(Settings::default());
+        state.persist_session = false;
+        state.open_untitled("scratch".to_string());
+        state.tabs[0].path = Some(PathBuf::from("/tmp/extra-window.md"));
+        state.mark_settings_dirty();
+        assert!(!state.save_settings_if_dirty());
+        assert!(!state.settings_dirty);
+        assert!(state.settings.last_open_tabs.is_empty());
+    }
+
+    #[test]
+    fn test_open_or_create_missing_file_type() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut state = AppState::with_settings(Settings::default());
+        let index = state
+            .open_or_create_file(temp_dir.path().join("new.json"))
+            .unwrap();
+        assert_eq!(state.tab(index).unwrap().file_type, FileType::Json);
+    }
+
+    #[test]
+    fn test_open_untitled_is_modified() {
+        let mut state = AppState::with_settings(Settings::default());
+        let index = state.open_untitled("piped\n".to_string());
+        let tab = state.tab(index).unwrap();
+        assert_eq!(tab.content, "piped\n");
+        assert!(tab.path.is_none());
+        assert!(tab.is_modified());
+        assert_eq!(state.active_tab_index(), index);
+    }
 }
@@ -108,6 +108,8 @@ fn get_shortcuts(category: ShortcutCategory) -> Vec<Shortcut> {
         ShortcutCategory::Workspace => vec![
             Shortcut::new("Ctrl+P", "Quick File Switcher"),
             Shortcut::new("Ctrl+Shift+F", "Search in Files"),
+            Shortcut::new("F4", "Next Search Result"),
+            Shortcut::new("Shift+F4", "Previous Search Result"),
             Shortcut::new("Ctrl+Shift+E", "Toggle File Tree"),
         ],
         ShortcutCategory::Navigation => vec![
@@ -0,0 +1,260 @@
+//! Backlinks Panel Component
+//!
+//! This module implements a side panel, shown next to the outline in
+//! workspace mode, that lists the notes linking to the active note with the
+//! line containing each link.
+
+use crate::config::OutlinePanelSide;
+use crate::workspaces::Backlink;
+use eframe::egui::{self, Color32, RichText, ScrollArea, Sense, Vec2};
+use std::path::{Path, PathBuf};
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Constants
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Minimum width for the backlinks panel.
+const MIN_PANEL_WIDTH: f32 = 120.0;
+
+/// Maximum width for the backlinks panel.
+const MAX_PANEL_WIDTH: f32 = 400.0;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// BacklinksPanelOutput
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Output from the backlinks panel indicating user actions.
+#[derive(Debug, Clone, Default)]
+pub struct BacklinksPanelOutput {
+    /// File and line (1-indexed) to open, if a backlink was clicked
+    pub open: Option<(PathBuf, usize)>,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// BacklinksPanel
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// The backlinks panel widget.
+#[derive(Debug, Clone)]
+pub struct BacklinksPanel {
+    /// Current panel width
+    width: f32,
+    /// Which side the panel is on
+    side: OutlinePanelSide,
+}
+
+impl Default for BacklinksPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BacklinksPanel {
+    /// Create a new backlinks panel.
+    pub fn new() -> Self {
+        Self {
+            width: 200.0,
+            side: OutlinePanelSide::Right,
+        }
+    }
+
+    /// Set the panel side (mutable reference version).
+    pub fn set_side(&mut self, side: OutlinePanelSide) {
+        self.side = side;
+    }
+
+    /// Render the backlinks panel.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The egui context
+    /// * `backlinks` - Links to the active note, ordered by file
+    /// * `root` - Workspace root, for showing file paths
+    /// * `is_dark` - Whether using dark theme
+    ///
+    /// # Returns
+    ///
+    /// Output indicating any user actions.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        backlinks: &[Backlink],
+        root: &Path,
+        is_dark: bool,
+    ) -> BacklinksPanelOutput {
+        let mut output = BacklinksPanelOutput::default();
+
+        // Panel colors (matching the outline panel)
+        let panel_bg = if is_dark {
+            Color32::from_rgb(35, 35, 35)
+        } else {
+            Color32::from_rgb(250, 250, 250)
+        };
+
+        let border_color = if is_dark {
+            Color32::from_rgb(60, 60, 60)
+        } else {
+            Color32::from_rgb(210, 210, 210)
+        };
+
+        let text_color = if is_dark {
+            Color32::from_rgb(200, 200, 200)
+        } else {
+            Color32::from_rgb(50, 50, 50)
+        };
+
+        let muted_color = if is_dark {
+            Color32::from_rgb(130, 130, 130)
+        } else {
+            Color32::from_rgb(120, 120, 120)
+        };
+
+        let hover_bg = if is_dark {
+            Color32::from_rgb(50, 50, 55)
+        } else {
+            Color32::from_rgb(235, 235, 240)
+        };
+
+        let panel = match self.side {
+            OutlinePanelSide::Left => egui::SidePanel::left("backlinks_panel"),
+            OutlinePanelSide::Right => egui::SidePanel::right("backlinks_panel"),
+        };
+
+        panel
+            .resizable(true)
+            .default_width(self.width)
+            .min_width(MIN_PANEL_WIDTH)
+            .max_width(MAX_PANEL_WIDTH)
+            .frame(
+                egui::Frame::none()
+                    .fill(panel_bg)
+                    .stroke(egui::Stroke::new(1.0, border_color)),
+            )
+            .show(ctx, |ui| {
+                self.width = ui.available_width();
+                ui.spacing_mut().item_spacing = Vec2::new(0.0, 2.0);
+
+                ui.horizontal(|ui| {
+                    ui.add_space(8.0);
+                    ui.label(
+                        RichText::new("🔗 Backlinks")
+                            .size(12.0)
+                            .strong()
+                            .color(text_color),
+                    );
+                });
+                ui.add_space(4.0);
+
+                if !backlinks.is_empty() {
+                    let mut sources: Vec<&Path> =
+                        backlinks.iter().map(|b| b.source.as_path()).collect();
+                    sources.dedup();
+                    let summary = format!("{} links from {} notes", backlinks.len(), sources.len());
+                    ui.horizontal(|ui| {
+                        ui.add_space(8.0);
+                        ui.label(RichText::new(summary).size(10.0).color(muted_color));
+                    });
+                    ui.add_space(4.0);
+                }
+
+                ui.separator();
+
+                ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        if backlinks.is_empty() {
+                            ui.add_space(20.0);
+                            ui.vertical_centered(|ui| {
+                                ui.label(
+                                    RichText::new("No backlinks")
+                                        .size(11.0)
+                                        .color(muted_color)
+                                        .italics(),
+                                );
+                                ui.add_space(8.0);
+                                ui.label(
+                                    RichText::new("Link here with [[Note Name]]")
+                                        .size(10.0)
+                                        .color(muted_color),
+                                );
+                            });
+                            return;
+                        }
+
+                        ui.add_space(4.0);
+                        let mut current_source: Option<&Path> = None;
+                        for backlink in backlinks {
+                            // File heading for each group of links
+                            if current_source != Some(backlink.source.as_path()) {
+                                current_source = Some(&backlink.source);
+                                let name = backlink
+                                    .source
+                                    .strip_prefix(root)
+                                    .unwrap_or(&backlink.source)
+                                    .display()
+                                    .to_string();
+                                ui.add_space(4.0);
+                                ui.horizontal(|ui| {
+                                    ui.add_space(8.0);
+                                    ui.label(
+                                        RichText::new(format!("📄 {}", name))
+                                            .size(11.0)
+                                            .strong()
+                                            .color(text_color),
+                                    );
+                                });
+                            }
+
+                            let response = Self::render_snippet(
+                                ui,
+                                backlink,
+                                text_color,
+                                muted_color,
+                                hover_bg,
+                            );
+                            if response.clicked() {
+                                output.open = Some((backlink.source.clone(), backlink.line));
+                            }
+                        }
+                        ui.add_space(8.0);
+                    });
+            });
+
+        output
+    }
+
+    /// Render one backlink's context line as a clickable row.
+    fn render_snippet(
+        ui: &mut egui::Ui,
+        backlink: &Backlink,
+        text_color: Color32,
+        muted_color: Color32,
+        hover_bg: Color32,
+    ) -> egui::Response {
+        let galley = ui.painter().layout(
+            backlink.snippet.clone(),
+            egui::FontId::proportional(11.0),
+            text_color,
+            (ui.available_width() - 24.0).max(40.0),
+        );
+        let line_label = format!("{}", backlink.line);
+        let size = Vec2::new(ui.available_width(), galley.size().y + 6.0);
+        let (rect, response) = ui.allocate_exact_size(size, Sense::click());
+
+        if response.hovered() {
+            ui.painter().rect_filled(rect, 3.0, hover_bg);
+            ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+        }
+        ui.painter().text(
+            rect.left_top() + Vec2::new(8.0, 3.0),
+            egui::Align2::LEFT_TOP,
+            line_label,
+            egui::FontId::monospace(9.0),
+            muted_color,
+        );
+        ui.painter()
+            .galley(rect.left_top() + Vec2::new(20.0, 3.0), galley, text_color);
+
+        response.on_hover_text(format!("Open at line {}", backlink.line))
+    }
+}
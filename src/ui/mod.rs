@@ -3,6 +3,7 @@
 //! This module contains reusable UI widgets and components.
 
 mod about;
+mod backlinks_panel;
 mod dialogs;
 mod file_tree;
 mod icons;
@@ -14,12 +15,13 @@ mod settings;
 mod window;
 
 pub use about::AboutPanel;
+pub use backlinks_panel::BacklinksPanel;
 pub use dialogs::{FileOperationDialog, FileOperationResult};
 pub use file_tree::{FileTreeContextAction, FileTreePanel};
 pub use icons::get_app_icon;
 pub use outline_panel::OutlinePanel;
 pub use quick_switcher::QuickSwitcher;
 pub use ribbon::{Ribbon, RibbonAction};
-pub use search::SearchPanel;
+pub use search::{FileReplace, ReplaceSummary, ReplacedFile, SearchPanel};
 pub use settings::SettingsPanel;
 pub use window::{handle_window_resize, WindowResizeState};
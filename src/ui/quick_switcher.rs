@@ -386,7 +386,7 @@ impl QuickSwitcher {
         }
 
         // Sort by score (descending)
-        scored.sort_by(|a, b| b.1.cmp(&a.1));
+        scored.sort_by_key(|b| std::cmp::Reverse(b.1));
 
         // Take top results
         for (path, score, is_recent) in scored.into_iter().take(MAX_RESULTS) {
@@ -62,6 +62,8 @@ pub enum RibbonAction {
     // View operations
     /// Toggle between Raw and Rendered view
     ToggleViewMode,
+    /// Toggle the side-by-side raw editor and preview
+    ToggleSplitView,
     /// Toggle line numbers visibility
     ToggleLineNumbers,
     /// Toggle sync scrolling between Raw and Rendered views
@@ -135,7 +137,7 @@ impl Ribbon {
     ///
     /// * `ui` - The egui UI context
     /// * `theme_colors` - Current theme colors for styling
-    /// * `view_mode` - Current view mode (Raw/Rendered)
+    /// * `view_mode` - Current view mode (Raw/Rendered/Split)
     /// * `show_line_numbers` - Whether line numbers are currently visible
     /// * `can_undo` - Whether undo is available
     /// * `can_redo` - Whether redo is available
@@ -516,6 +518,7 @@ impl Ribbon {
                 let view_icon = match view_mode {
                     ViewMode::Raw => "📝",
                     ViewMode::Rendered => "👁",
+                    ViewMode::Split => "◫",
                 };
                 let view_tooltip = match (file_type.is_structured(), view_mode) {
                     // For structured data, "Rendered" means tree viewer
@@ -524,12 +527,25 @@ impl Ribbon {
                     // For markdown
                     (false, ViewMode::Raw) => "Switch to Rendered View (Ctrl+E)",
                     (false, ViewMode::Rendered) => "Switch to Raw Editor (Ctrl+E)",
+                    (_, ViewMode::Split) => "Switch to Raw Editor (Ctrl+E)",
                 };
                 if icon_button(ui, view_icon, view_tooltip, true, is_dark).clicked() {
                     action = Some(RibbonAction::ToggleViewMode);
                 }
             }
 
+            // Split view toggle - only for markdown files
+            if file_type.is_markdown() {
+                let split_tooltip = if view_mode == ViewMode::Split {
+                    "Close Split View (Ctrl+\\)"
+                } else {
+                    "Split View: Editor and Preview (Ctrl+\\)"
+                };
+                if icon_button(ui, "◫", split_tooltip, true, is_dark).clicked() {
+                    action = Some(RibbonAction::ToggleSplitView);
+                }
+            }
+
             // Line numbers toggle (universal)
             let line_num_icon = if show_line_numbers { "🔢" } else { "#" };
             let line_num_tooltip = if show_line_numbers {
@@ -1,55 +1,78 @@
 //! Search-in-files panel for workspace mode.
 //!
-//! Provides Ctrl+Shift+F search functionality across all files in the workspace.
+//! Provides Ctrl+Shift+F search functionality across all files in the workspace,
+//! and replacing the matches: each file and match can be left out, and the
+//! results show a diff-style preview of every replacement.
+//!
+//! Searches run in the background ([`SearchJob`]); results appear as they are
+//! found, and editing the query or the include/exclude globs starts over.
+//!
+//! Opening a result ([`SearchLocation`]) carries the match's exact span, and
+//! [`SearchPanel::next_result`] steps through results across files (F4).
 
 // Allow dead code - includes ID counter and layout helpers for future search UI features
 #![allow(dead_code)]
 
+use crate::files::encoding::FileFormat;
 use crate::string_utils::floor_char_boundary;
+use crate::workspaces::{
+    content_hash, lines_with_offsets, parse_globs, FileSearchResults, Matcher, SearchJob,
+    SearchLocation, SearchMatch, SearchRequest,
+};
 use eframe::egui::{self, Color32, Key, RichText, ScrollArea, Sense, TextFormat};
-use std::path::PathBuf;
-
-/// Maximum number of results to show per file.
-const MAX_RESULTS_PER_FILE: usize = 10;
-
-/// Maximum number of files to show results for.
-const MAX_FILES_WITH_RESULTS: usize = 50;
-
-/// A single search match result.
-#[derive(Debug, Clone)]
-pub struct SearchMatch {
-    /// The line number (1-indexed)
-    pub line_number: usize,
-    /// The line content
-    pub line_content: String,
-    /// Start position of match in line
-    pub match_start: usize,
-    /// End position of match in line
-    pub match_end: usize,
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Result of applying the selected replacements to one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileReplace {
+    /// The new text, and the number of matches replaced
+    Replaced(String, usize),
+    /// Nothing in the file is selected for replacement
+    Unselected,
+    /// The text changed since the search, so the results no longer apply
+    Stale,
 }
 
-/// Results for a single file.
+/// A file changed by Replace, with what is needed to revert it.
 #[derive(Debug, Clone)]
-pub struct FileSearchResults {
-    /// Path to the file
+pub struct ReplacedFile {
+    /// Path of the file
     pub path: PathBuf,
-    /// Matches in this file
-    pub matches: Vec<SearchMatch>,
-    /// Whether there are more matches than shown
-    pub truncated: bool,
-    /// Whether this file's results are expanded in the UI
-    pub expanded: bool,
+    /// Text before the replacement
+    pub original: String,
+    /// Text after the replacement
+    pub replaced: String,
+    /// On-disk format the file was written in, or None if the replacement
+    /// was made in an open tab
+    pub format: Option<FileFormat>,
+}
+
+/// What the last Replace changed.
+#[derive(Debug, Clone, Default)]
+pub struct ReplaceSummary {
+    /// Number of matches replaced
+    pub replaced: usize,
+    /// Files that were changed
+    pub files: Vec<ReplacedFile>,
+    /// Files left alone because they changed since the search or could not
+    /// be written
+    pub skipped: Vec<PathBuf>,
 }
 
 /// Output from the search panel.
 #[derive(Debug, Default)]
 pub struct SearchPanelOutput {
-    /// File+line to navigate to (user clicked a result)
-    pub navigate_to: Option<(PathBuf, usize)>,
+    /// Match to open in the editor (user clicked a result)
+    pub navigate_to: Option<SearchLocation>,
     /// Whether the panel was closed
     pub closed: bool,
     /// Whether search should be triggered
     pub should_search: bool,
+    /// Whether the selected matches should be replaced
+    pub replace_requested: bool,
+    /// Whether the last replace should be reverted
+    pub revert_requested: bool,
 }
 
 /// State for the search panel.
@@ -58,18 +81,42 @@ pub struct SearchPanel {
     is_open: bool,
     /// Current search query
     query: String,
-    /// Previous query (to detect changes)
+    /// Query and options of the last search (to detect changes)
     last_query: String,
     /// Whether to use regex search
     use_regex: bool,
     /// Whether search is case-sensitive
     case_sensitive: bool,
+    /// Whether the replace field and preview are shown
+    replace_mode: bool,
+    /// Replacement text
+    replacement: String,
+    /// Globs of files to search (comma-separated, empty for all)
+    include: String,
+    /// Globs of files and folders to skip (comma-separated)
+    exclude: String,
+    /// Lines of context shown around each result
+    context_lines: usize,
+    /// Query the current results were found with
+    matcher: Option<Matcher>,
+    /// Search still running in the background
+    job: Option<SearchJob>,
     /// Current search results
     results: Vec<FileSearchResults>,
     /// Total match count across all files
     total_matches: usize,
+    /// Number of files searched by the last finished search
+    files_searched: usize,
+    /// Result opened last (file and match index), where F4 continues from
+    current: Option<(usize, usize)>,
+    /// Whether the results list should scroll to the current result
+    scroll_to_current: bool,
     /// Error message if search failed
     error_message: Option<String>,
+    /// What the last replace changed, until it is reverted
+    last_replace: Option<ReplaceSummary>,
+    /// Whether the query field should take focus
+    focus_query: bool,
     /// Counter for unique IDs
     id_counter: usize,
 }
@@ -89,9 +136,21 @@ impl SearchPanel {
             last_query: String::new(),
             use_regex: false,
             case_sensitive: false,
+            replace_mode: false,
+            replacement: String::new(),
+            include: String::new(),
+            exclude: String::new(),
+            context_lines: 0,
+            matcher: None,
+            job: None,
             results: Vec::new(),
             total_matches: 0,
+            files_searched: 0,
+            current: None,
+            scroll_to_current: false,
             error_message: None,
+            last_replace: None,
+            focus_query: false,
             id_counter: 0,
         }
     }
@@ -104,11 +163,18 @@ impl SearchPanel {
     /// Open the search panel.
     pub fn open(&mut self) {
         self.is_open = true;
+        self.focus_query = true;
     }
 
-    /// Close the search panel.
+    /// Close the search panel, stopping a running search.
     pub fn close(&mut self) {
         self.is_open = false;
+        self.job = None;
+    }
+
+    /// Check if a search is running.
+    pub fn is_searching(&self) -> bool {
+        self.job.is_some()
     }
 
     /// Toggle the search panel.
@@ -120,152 +186,215 @@ impl SearchPanel {
         }
     }
 
-    /// Perform search across workspace files.
-    pub fn search(&mut self, files: &[PathBuf], hidden_patterns: &[String]) {
+    /// Start searching the workspace in the background, cancelling any
+    /// search still running.
+    ///
+    /// Files open in tabs are searched in `open_documents` (their unsaved
+    /// text) instead of on disk. Results arrive through [`Self::poll`].
+    pub fn search(
+        &mut self,
+        root: &Path,
+        hidden_patterns: &[String],
+        open_documents: HashMap<PathBuf, String>,
+    ) {
+        self.job = None;
         self.results.clear();
         self.total_matches = 0;
+        self.files_searched = 0;
+        self.current = None;
         self.error_message = None;
-        self.last_query = self.query.clone();
+        self.matcher = None;
+        self.last_query = self.search_key();
 
         if self.query.is_empty() {
             return;
         }
 
-        let query = if self.case_sensitive {
-            self.query.clone()
-        } else {
-            self.query.to_lowercase()
+        let matcher = match Matcher::new(&self.query, self.use_regex, self.case_sensitive) {
+            Ok(matcher) => matcher,
+            Err(e) => {
+                self.error_message = Some(format!("Invalid regex: {}", e));
+                return;
+            }
         };
-
-        // Compile regex if needed
-        let regex = if self.use_regex {
-            match regex::RegexBuilder::new(&self.query)
-                .case_insensitive(!self.case_sensitive)
-                .build()
-            {
-                Ok(r) => Some(r),
-                Err(e) => {
-                    self.error_message = Some(format!("Invalid regex: {}", e));
-                    return;
-                }
+        let (include, exclude) = match (parse_globs(&self.include), parse_globs(&self.exclude)) {
+            (Ok(include), Ok(exclude)) => (include, exclude),
+            (Err(e), _) | (_, Err(e)) => {
+                self.error_message = Some(e);
+                return;
             }
-        } else {
-            None
         };
 
-        for file_path in files {
-            // Skip hidden files
-            let should_skip = file_path.components().any(|comp| {
-                if let std::path::Component::Normal(name) = comp {
-                    let name_str = name.to_string_lossy();
-                    hidden_patterns
-                        .iter()
-                        .any(|p| name_str.contains(p) || name_str == *p)
-                } else {
-                    false
-                }
-            });
-            if should_skip {
-                continue;
+        let request = SearchRequest {
+            root: root.to_path_buf(),
+            matcher: matcher.clone(),
+            include,
+            exclude,
+            hidden_patterns: hidden_patterns.to_vec(),
+            open_documents,
+            context_lines: self.context_lines,
+        };
+        match SearchJob::spawn(request) {
+            Ok(job) => {
+                self.job = Some(job);
+                self.matcher = Some(matcher);
             }
+            Err(e) => self.error_message = Some(format!("Could not start search: {}", e)),
+        }
+    }
 
-            // Only search text files
-            let ext = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
-            let is_text = matches!(
-                ext.to_lowercase().as_str(),
-                "md" | "markdown"
-                    | "txt"
-                    | "rs"
-                    | "toml"
-                    | "json"
-                    | "yaml"
-                    | "yml"
-                    | "js"
-                    | "ts"
-                    | "jsx"
-                    | "tsx"
-                    | "html"
-                    | "css"
-                    | "scss"
-                    | "py"
-                    | "go"
-                    | "java"
-                    | "c"
-                    | "cpp"
-                    | "h"
-                    | "hpp"
-                    | "sh"
-                    | "bash"
-                    | "zsh"
-                    | "xml"
-                    | "svg"
-            );
-            if !is_text {
-                continue;
-            }
+    /// Take in the results found since the last call.
+    ///
+    /// Returns true while the search is still running.
+    pub fn poll(&mut self) -> bool {
+        let Some(job) = &self.job else {
+            return false;
+        };
+        let known = self.results.len();
+        let finished = job.poll(&mut self.results);
+        self.total_matches += self.results[known..]
+            .iter()
+            .map(|file| file.match_count)
+            .sum::<usize>();
+        if let Some(files_searched) = finished {
+            self.files_searched = files_searched;
+            self.job = None;
+        }
+        self.job.is_some()
+    }
 
-            // Read file content
-            let content = match std::fs::read_to_string(file_path) {
-                Ok(c) => c,
-                Err(_) => continue, // Skip unreadable files
-            };
-
-            let mut file_results = FileSearchResults {
-                path: file_path.clone(),
-                matches: Vec::new(),
-                truncated: false,
-                expanded: true,
-            };
-
-            for (line_idx, line) in content.lines().enumerate() {
-                let line_number = line_idx + 1;
-
-                let matches_found: Vec<(usize, usize)> = if let Some(ref re) = regex {
-                    re.find_iter(line).map(|m| (m.start(), m.end())).collect()
-                } else {
-                    let search_line = if self.case_sensitive {
-                        line.to_string()
-                    } else {
-                        line.to_lowercase()
-                    };
-                    let mut positions = Vec::new();
-                    let mut start = 0;
-                    while let Some(pos) = search_line[start..].find(&query) {
-                        let abs_pos = start + pos;
-                        positions.push((abs_pos, abs_pos + query.len()));
-                        start = abs_pos + 1;
-                    }
-                    positions
-                };
+    /// The query, options and globs, to tell when a new search is needed.
+    fn search_key(&self) -> String {
+        format!(
+            "{}\0{}\0{}\0{}\0{}\0{}",
+            self.query,
+            self.use_regex,
+            self.case_sensitive,
+            self.include,
+            self.exclude,
+            self.context_lines
+        )
+    }
 
-                for (match_start, match_end) in matches_found {
-                    if file_results.matches.len() >= MAX_RESULTS_PER_FILE {
-                        file_results.truncated = true;
-                        break;
-                    }
+    /// Set the lines of context shown around results. An open panel
+    /// searches again when this changes.
+    pub fn set_context_lines(&mut self, lines: usize) {
+        self.context_lines = lines;
+    }
 
-                    file_results.matches.push(SearchMatch {
-                        line_number,
-                        line_content: line.to_string(),
-                        match_start,
-                        match_end,
-                    });
-                    self.total_matches += 1;
-                }
+    /// Step to the next (or previous) result, going on to the next file
+    /// after a file's last match and wrapping around at the end.
+    ///
+    /// Returns None if there are no results.
+    pub fn next_result(&mut self, forward: bool) -> Option<SearchLocation> {
+        let all: Vec<(usize, usize)> = self
+            .results
+            .iter()
+            .enumerate()
+            .flat_map(|(file, results)| (0..results.matches.len()).map(move |m| (file, m)))
+            .collect();
+        if all.is_empty() {
+            return None;
+        }
 
-                if file_results.truncated {
-                    break;
-                }
-            }
+        let position = self
+            .current
+            .and_then(|current| all.iter().position(|&result| result == current));
+        let next = match (position, forward) {
+            (Some(i), true) => (i + 1) % all.len(),
+            (Some(i), false) => (i + all.len() - 1) % all.len(),
+            (None, true) => 0,
+            (None, false) => all.len() - 1,
+        };
+        let (file, index) = all[next];
+        self.select_result(file, index)
+    }
 
-            if !file_results.matches.is_empty() {
-                self.results.push(file_results);
-            }
+    /// Make a result the current one, and return where it is.
+    fn select_result(&mut self, file: usize, index: usize) -> Option<SearchLocation> {
+        let results = self.results.get_mut(file)?;
+        results.expanded = true;
+        self.current = Some((file, index));
+        self.scroll_to_current = true;
+        results.location(index)
+    }
+
+    /// Files with matches selected for replacement.
+    pub fn replace_targets(&self) -> Vec<PathBuf> {
+        self.results
+            .iter()
+            .filter(|file| file.included)
+            .map(|file| file.path.clone())
+            .collect()
+    }
 
-            if self.results.len() >= MAX_FILES_WITH_RESULTS {
-                break;
+    /// Number of matches selected for replacement.
+    pub fn selected_count(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|file| file.included)
+            .map(|file| {
+                let shown = file.matches.iter().filter(|m| m.included).count();
+                shown + file.match_count - file.matches.len()
+            })
+            .sum()
+    }
+
+    /// Apply the selected replacements to the text of `path`.
+    ///
+    /// `content` must be the text that was searched; if it has changed since,
+    /// [`FileReplace::Stale`] is returned and nothing is replaced.
+    pub fn replace_in(&self, path: &Path, content: &str) -> FileReplace {
+        let (Some(file), Some(matcher)) = (
+            self.results.iter().find(|file| file.path == path),
+            &self.matcher,
+        ) else {
+            return FileReplace::Unselected;
+        };
+        if !file.included {
+            return FileReplace::Unselected;
+        }
+        if content_hash(content) != file.content_hash {
+            return FileReplace::Stale;
+        }
+
+        let template = matcher.template(&self.replacement);
+        let mut result = String::with_capacity(content.len());
+        let mut copied = 0;
+        let mut match_idx = 0;
+        let mut count = 0;
+        for (offset, line) in lines_with_offsets(content) {
+            for (start, end) in matcher.find(line) {
+                // Matches past the shown ones follow the file's checkbox
+                let included = file.matches.get(match_idx).map_or(true, |m| m.included);
+                match_idx += 1;
+                if !included {
+                    continue;
+                }
+                let Some(replacement) = matcher.replacement(line, start, &template) else {
+                    continue;
+                };
+                result.push_str(&content[copied..offset + start]);
+                result.push_str(&replacement);
+                copied = offset + end;
+                count += 1;
             }
         }
+        if count == 0 {
+            return FileReplace::Unselected;
+        }
+        result.push_str(&content[copied..]);
+        FileReplace::Replaced(result, count)
+    }
+
+    /// Record what a replace changed, so it can be shown and reverted.
+    pub fn set_last_replace(&mut self, summary: ReplaceSummary) {
+        self.last_replace = Some(summary);
+    }
+
+    /// Take the last replace, to revert it.
+    pub fn take_last_replace(&mut self) -> Option<ReplaceSummary> {
+        self.last_replace.take()
     }
 
     /// Show the search panel.
@@ -281,6 +410,11 @@ impl SearchPanel {
             return output;
         }
 
+        // Results found in the background since the last frame
+        if self.poll() {
+            ctx.request_repaint();
+        }
+
         // Handle escape key
         if ctx.input(|i| i.key_pressed(Key::Escape)) {
             output.closed = true;
@@ -331,6 +465,27 @@ impl SearchPanel {
             Color32::from_rgb(245, 247, 250)
         };
 
+        let current_bg = if is_dark {
+            Color32::from_rgb(50, 65, 90)
+        } else {
+            Color32::from_rgb(210, 225, 250)
+        };
+
+        let diff_colors = DiffColors {
+            text: text_color,
+            secondary: secondary_color,
+            removed_bg: if is_dark {
+                Color32::from_rgb(110, 40, 40)
+            } else {
+                Color32::from_rgb(255, 210, 210)
+            },
+            added_bg: if is_dark {
+                Color32::from_rgb(40, 90, 50)
+            } else {
+                Color32::from_rgb(200, 240, 205)
+            },
+        };
+
         egui::Window::new("🔍 Search in Files")
             .id(egui::Id::new("search_in_files_window"))
             .collapsible(false)
@@ -357,45 +512,168 @@ impl SearchPanel {
                     );
 
                     // Auto-focus on open
-                    response.request_focus();
+                    if self.focus_query {
+                        response.request_focus();
+                        self.focus_query = false;
+                    }
 
                     ui.checkbox(&mut self.use_regex, "Regex");
                     ui.checkbox(&mut self.case_sensitive, "Aa");
+                    ui.checkbox(&mut self.replace_mode, "Replace");
+                });
+
+                // File filter row
+                ui.horizontal(|ui| {
+                    ui.label("Files:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.include)
+                            .hint_text("Include, e.g. *.md, notes/**")
+                            .desired_width(170.0)
+                            .id(egui::Id::new("search_include_input")),
+                    )
+                    .on_hover_text("Only search files matching these globs (comma-separated)");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.exclude)
+                            .hint_text("Exclude, e.g. drafts, *.tmp")
+                            .desired_width(170.0)
+                            .id(egui::Id::new("search_exclude_input")),
+                    )
+                    .on_hover_text(
+                        "Skip files and folders matching these globs; .gitignore is always honoured",
+                    );
                 });
 
+                // Replace input row
+                if self.replace_mode {
+                    ui.horizontal(|ui| {
+                        ui.label("Replace:");
+                        let hint = if self.use_regex {
+                            "Replace with... ($1, ${name}, \\n)"
+                        } else {
+                            "Replace with..."
+                        };
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.replacement)
+                                .hint_text(hint)
+                                .desired_width(350.0)
+                                .id(egui::Id::new("search_replace_input")),
+                        );
+
+                        let selected = self.selected_count();
+                        let button = ui
+                            .add_enabled(
+                                selected > 0
+                                    && !self.is_searching()
+                                    && self.last_query == self.search_key(),
+                                egui::Button::new(format!("Replace {}", selected)),
+                            )
+                            .on_hover_text("Replace the checked matches")
+                            .on_disabled_hover_text("Search first, then check the matches to replace");
+                        if button.clicked() {
+                            output.replace_requested = true;
+                        }
+                    });
+                }
+
+                // Summary of the last replace, with a way back
+                if let Some(summary) = &self.last_replace {
+                    ui.horizontal(|ui| {
+                        let files = summary.files.len();
+                        let mut text = format!(
+                            "Replaced {} match{} in {} file{}",
+                            summary.replaced,
+                            if summary.replaced == 1 { "" } else { "es" },
+                            files,
+                            if files == 1 { "" } else { "s" }
+                        );
+                        if !summary.skipped.is_empty() {
+                            text.push_str(&format!(
+                                " • skipped {} changed or unwritable",
+                                summary.skipped.len()
+                            ));
+                        }
+                        ui.label(RichText::new(text).color(secondary_color));
+                        if ui
+                            .small_button("↶ Revert")
+                            .on_hover_text("Undo this replace in every file")
+                            .clicked()
+                        {
+                            output.revert_requested = true;
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        summary
+                            .skipped
+                            .iter()
+                            .map(|p| p.display().to_string())
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    );
+                }
+
                 ui.add_space(8.0);
 
-                // Check if search should be triggered (Enter pressed or query changed)
+                // Check if search should be triggered (Enter pressed or the
+                // query, options or globs changed)
                 let enter_pressed = ctx.input(|i| i.key_pressed(Key::Enter));
-                if enter_pressed && !self.query.is_empty() {
+                if (enter_pressed && !self.query.is_empty())
+                    || self.search_key() != self.last_query
+                {
                     output.should_search = true;
                 }
 
                 // Results summary
                 if !self.query.is_empty() {
+                    let file_count = self.results.len();
+                    let found = format!(
+                        "{} match{} in {} file{}",
+                        self.total_matches,
+                        if self.total_matches == 1 { "" } else { "es" },
+                        file_count,
+                        if file_count == 1 { "" } else { "s" }
+                    );
                     if let Some(error) = &self.error_message {
                         ui.colored_label(Color32::from_rgb(220, 80, 80), error);
-                    } else if self.results.is_empty() && self.last_query == self.query {
+                    } else if self.is_searching() {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label(
+                                RichText::new(format!("Searching... {}", found))
+                                    .color(secondary_color),
+                            );
+                        });
+                    } else if self.results.is_empty() {
                         ui.label(
-                            RichText::new("No results found")
-                                .color(secondary_color)
-                                .italics(),
+                            RichText::new(format!(
+                                "No results found ({} files searched)",
+                                self.files_searched
+                            ))
+                            .color(secondary_color)
+                            .italics(),
                         );
-                    } else if !self.results.is_empty() {
-                        let file_count = self.results.len();
+                    } else {
                         ui.label(format!(
-                            "{} match{} in {} file{}",
-                            self.total_matches,
-                            if self.total_matches == 1 { "" } else { "es" },
-                            file_count,
-                            if file_count == 1 { "" } else { "s" }
+                            "{} ({} files searched)",
+                            found, self.files_searched
                         ));
                     }
                 }
 
                 ui.separator();
 
+                // Replacement preview needs the query the results came from
+                let preview = match &self.matcher {
+                    Some(matcher) if self.replace_mode => {
+                        Some((matcher.clone(), matcher.template(&self.replacement)))
+                    }
+                    _ => None,
+                };
+
                 // Results list
+                let current = self.current;
+                let scroll_to_current = std::mem::take(&mut self.scroll_to_current);
+                let mut clicked = None;
                 ScrollArea::vertical()
                     .id_source("search_results_scroll")
                     .auto_shrink([false, false])
@@ -412,21 +690,32 @@ impl SearchPanel {
 
                             let file_id = egui::Id::new("search_file").with(file_idx);
 
-                            let header_response = ui.horizontal(|ui| {
-                                let arrow = if file_result.expanded { "▼" } else { "▶" };
-                                ui.label(RichText::new(arrow).size(10.0).color(secondary_color));
-                                ui.label(RichText::new("📄").size(14.0));
-                                ui.label(
-                                    RichText::new(relative_path.as_ref())
-                                        .color(text_color)
-                                        .strong(),
-                                );
-                                ui.label(
-                                    RichText::new(format!("({})", file_result.matches.len()))
-                                        .color(secondary_color)
-                                        .small(),
-                                );
-                            });
+                            let header_response = ui
+                                .horizontal(|ui| {
+                                    // Checkbox stays outside the clickable header
+                                    if preview.is_some() {
+                                        ui.checkbox(&mut file_result.included, "")
+                                            .on_hover_text("Replace in this file");
+                                    }
+                                    ui.horizontal(|ui| {
+                                        let arrow = if file_result.expanded { "▼" } else { "▶" };
+                                        ui.label(
+                                            RichText::new(arrow).size(10.0).color(secondary_color),
+                                        );
+                                        ui.label(RichText::new("📄").size(14.0));
+                                        ui.label(
+                                            RichText::new(relative_path.as_ref())
+                                                .color(text_color)
+                                                .strong(),
+                                        );
+                                        ui.label(
+                                            RichText::new(format!("({})", file_result.match_count))
+                                                .color(secondary_color)
+                                                .small(),
+                                        );
+                                    })
+                                })
+                                .inner;
 
                             let header_interact =
                                 ui.interact(header_response.response.rect, file_id, Sense::click());
@@ -442,128 +731,121 @@ impl SearchPanel {
                                 ui.add_space(2.0);
 
                                 for (match_idx, search_match) in
-                                    file_result.matches.iter().enumerate()
+                                    file_result.matches.iter_mut().enumerate()
                                 {
                                     let _match_id = file_id.with(match_idx);
 
-                                    // Build the display text
-                                    let line = &search_match.line_content;
-                                    let line_trimmed = line.trim_start();
-                                    let trim_offset = line.len() - line_trimmed.len();
-
-                                    // Adjust match positions for trimmed line
-                                    let adj_start =
-                                        search_match.match_start.saturating_sub(trim_offset);
-                                    let adj_end =
-                                        search_match.match_end.saturating_sub(trim_offset);
-
-                                    // Truncate display (use char boundary to avoid UTF-8 panic)
-                                    let max_len = 80;
-                                    let display_line = if line_trimmed.len() > max_len {
-                                        let safe_end = floor_char_boundary(line_trimmed, max_len);
-                                        format!("{}...", &line_trimmed[..safe_end])
-                                    } else {
-                                        line_trimmed.to_string()
-                                    };
-
-                                    // Create a layout job for highlighted text
-                                    let mut job = egui::text::LayoutJob::default();
-
-                                    // Line number
-                                    job.append(
-                                        &format!("{:>4}: ", search_match.line_number),
-                                        0.0,
-                                        TextFormat {
-                                            color: secondary_color,
-                                            font_id: egui::FontId::monospace(12.0),
-                                            ..Default::default()
-                                        },
-                                    );
-
-                                    // Ensure indices are on UTF-8 char boundaries
-                                    let safe_start =
-                                        floor_char_boundary(&display_line, adj_start);
-                                    let safe_end = floor_char_boundary(&display_line, adj_end);
-
-                                    // Text before match
-                                    if safe_start > 0 && safe_start <= display_line.len() {
-                                        job.append(
-                                            &display_line[..safe_start],
-                                            0.0,
-                                            TextFormat {
-                                                color: text_color,
-                                                font_id: egui::FontId::monospace(12.0),
-                                                ..Default::default()
-                                            },
-                                        );
+                                    // Replace mode: checkbox and a before/after diff
+                                    let replacement =
+                                        preview.as_ref().and_then(|(matcher, template)| {
+                                            matcher.replacement(
+                                                &search_match.line_content,
+                                                search_match.match_start,
+                                                template,
+                                            )
+                                        });
+                                    if let Some(replacement) = replacement {
+                                        let job = diff_job(search_match, &replacement, &diff_colors);
+                                        let enabled = file_result.included;
+                                        let response = ui
+                                            .horizontal(|ui| {
+                                                ui.add_enabled(
+                                                    enabled,
+                                                    egui::Checkbox::without_text(
+                                                        &mut search_match.included,
+                                                    ),
+                                                );
+                                                let galley = ui.fonts(|f| f.layout_job(job));
+                                                let desired_size = egui::vec2(
+                                                    ui.available_width(),
+                                                    galley.size().y + 4.0,
+                                                );
+                                                let (rect, response) = ui
+                                                    .allocate_exact_size(desired_size, Sense::click());
+                                                let bg = if response.hovered() {
+                                                    ui.ctx().set_cursor_icon(
+                                                        egui::CursorIcon::PointingHand,
+                                                    );
+                                                    hover_bg
+                                                } else if current == Some((file_idx, match_idx)) {
+                                                    current_bg
+                                                } else {
+                                                    result_bg
+                                                };
+                                                ui.painter().rect_filled(rect, 3.0, bg);
+                                                ui.painter().galley(
+                                                    rect.left_top() + egui::vec2(8.0, 2.0),
+                                                    galley,
+                                                    text_color,
+                                                );
+                                                response
+                                            })
+                                            .inner;
+                                        if response.clicked() {
+                                            clicked = Some((file_idx, match_idx));
+                                        }
+                                        continue;
                                     }
 
-                                    // Highlighted match
-                                    if safe_start < display_line.len()
-                                        && safe_end <= display_line.len()
-                                        && safe_start < safe_end
-                                    {
-                                        job.append(
-                                            &display_line[safe_start..safe_end],
-                                            0.0,
-                                            TextFormat {
-                                                color: Color32::BLACK,
-                                                background: highlight_color,
-                                                font_id: egui::FontId::monospace(12.0),
-                                                ..Default::default()
-                                            },
-                                        );
-                                    }
-
-                                    // Text after match
-                                    if safe_end < display_line.len() {
-                                        job.append(
-                                            &display_line[safe_end..],
-                                            0.0,
-                                            TextFormat {
-                                                color: text_color,
-                                                font_id: egui::FontId::monospace(12.0),
-                                                ..Default::default()
-                                            },
-                                        );
-                                    }
+                                    // The match's line between its context lines
+                                    let job = result_job(
+                                        search_match,
+                                        text_color,
+                                        secondary_color,
+                                        highlight_color,
+                                    );
+                                    let galley = ui.fonts(|f| f.layout_job(job));
 
                                     // Draw result row
-                                    let _row_rect = ui.available_rect_before_wrap();
-                                    let desired_size = egui::vec2(ui.available_width(), 20.0);
+                                    let desired_size = egui::vec2(
+                                        ui.available_width(),
+                                        (galley.size().y + 4.0).max(20.0),
+                                    );
                                     let (rect, response) =
                                         ui.allocate_exact_size(desired_size, Sense::click());
+                                    let is_current = current == Some((file_idx, match_idx));
 
                                     // Background on hover
                                     if response.hovered() {
                                         ui.painter().rect_filled(rect, 3.0, hover_bg);
                                         // Show pointer cursor for clickable results
                                         ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+                                    } else if is_current {
+                                        ui.painter().rect_filled(rect, 3.0, current_bg);
                                     } else {
                                         ui.painter().rect_filled(rect, 3.0, result_bg);
                                     }
 
                                     // Draw the text
-                                    let galley = ui.fonts(|f| f.layout_job(job));
                                     ui.painter().galley(
                                         rect.left_top() + egui::vec2(8.0, 2.0),
                                         galley,
                                         text_color,
                                     );
 
+                                    if is_current && scroll_to_current {
+                                        response.scroll_to_me(Some(egui::Align::Center));
+                                    }
+
                                     // Handle click
                                     if response.clicked() {
-                                        output.navigate_to = Some((
-                                            file_result.path.clone(),
-                                            search_match.line_number,
-                                        ));
+                                        clicked = Some((file_idx, match_idx));
                                         output.closed = true;
                                     }
                                 }
 
                                 if file_result.truncated {
+                                    let hidden = file_result.match_count - file_result.matches.len();
+                                    let text = if preview.is_some() {
+                                        format!(
+                                            "    ... {} more matches not shown (replaced with the file)",
+                                            hidden
+                                        )
+                                    } else {
+                                        format!("    ... {} more matches not shown", hidden)
+                                    };
                                     ui.label(
-                                        RichText::new("    ... more matches not shown")
+                                        RichText::new(text)
                                             .color(secondary_color)
                                             .small()
                                             .italics(),
@@ -575,12 +857,16 @@ impl SearchPanel {
                         }
                     });
 
+                if let Some((file, index)) = clicked {
+                    output.navigate_to = self.select_result(file, index);
+                }
+
                 // Keyboard hints
                 ui.separator();
                 ui.horizontal(|ui| {
                     ui.label(
                         RichText::new(
-                            "Press Enter to search • Click result to open • Esc to close",
+                            "Press Enter to search • Click result to open • F4 for next result • Check matches to replace • Esc to close",
                         )
                         .color(secondary_color)
                         .small(),
@@ -596,9 +882,149 @@ impl SearchPanel {
     }
 }
 
+/// Lay out a result: the match's line, with the match highlighted, between
+/// its context lines. Indentation the lines share is trimmed and long lines
+/// are cut.
+fn result_job(
+    search_match: &SearchMatch,
+    text_color: Color32,
+    secondary_color: Color32,
+    highlight_color: Color32,
+) -> egui::text::LayoutJob {
+    const MAX_LEN: usize = 80;
+
+    let lines: Vec<&str> = search_match
+        .context_before
+        .iter()
+        .map(String::as_str)
+        .chain(std::iter::once(search_match.line_content.as_str()))
+        .chain(search_match.context_after.iter().map(String::as_str))
+        .collect();
+    let indent_of = |line: &str| line.len() - line.trim_start().len();
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indent_of(line))
+        .min()
+        .unwrap_or(0);
+    let first_line = search_match.line_number - search_match.context_before.len();
+
+    let mut job = egui::text::LayoutJob::default();
+    let format = |color: Color32, background: Color32| TextFormat {
+        color,
+        background,
+        font_id: egui::FontId::monospace(12.0),
+        ..Default::default()
+    };
+    let plain = |color: Color32| format(color, Color32::TRANSPARENT);
+
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            job.append("\n", 0.0, plain(secondary_color));
+        }
+        let number = first_line + i;
+
+        // Trim the shared indentation and cut at a char boundary
+        let offset = floor_char_boundary(line, indent.min(indent_of(line)));
+        let rest = &line[offset..];
+        let shown = &rest[..floor_char_boundary(rest, MAX_LEN)];
+        let ellipsis = if shown.len() < rest.len() { "..." } else { "" };
+
+        if number != search_match.line_number {
+            job.append(&format!("{:>4}  ", number), 0.0, plain(secondary_color));
+            job.append(shown, 0.0, plain(secondary_color));
+            job.append(ellipsis, 0.0, plain(secondary_color));
+            continue;
+        }
+
+        let start = floor_char_boundary(shown, search_match.match_start.saturating_sub(offset));
+        let end =
+            floor_char_boundary(shown, search_match.match_end.saturating_sub(offset)).max(start);
+        job.append(&format!("{:>4}: ", number), 0.0, plain(secondary_color));
+        job.append(&shown[..start], 0.0, plain(text_color));
+        job.append(
+            &shown[start..end],
+            0.0,
+            format(Color32::BLACK, highlight_color),
+        );
+        job.append(&shown[end..], 0.0, plain(text_color));
+        job.append(ellipsis, 0.0, plain(text_color));
+    }
+    job
+}
+
+/// Colors for the replace preview.
+struct DiffColors {
+    text: Color32,
+    secondary: Color32,
+    removed_bg: Color32,
+    added_bg: Color32,
+}
+
+/// Lay out a match as a two-line diff: the line with the match struck out,
+/// then the line with its replacement.
+fn diff_job(
+    search_match: &SearchMatch,
+    replacement: &str,
+    colors: &DiffColors,
+) -> egui::text::LayoutJob {
+    let line = &search_match.line_content;
+    let start = floor_char_boundary(line, search_match.match_start);
+    let end = floor_char_boundary(line, search_match.match_end);
+    let (before, matched, after) = (&line[..start], &line[start..end], &line[end..]);
+
+    // Keep long lines readable: trim indentation and cut both sides
+    let before = before.trim_start();
+    let before = match before.char_indices().rev().nth(40) {
+        Some((i, _)) => format!("...{}", &before[i..]),
+        None => before.to_string(),
+    };
+    let after = match after.char_indices().nth(40) {
+        Some((i, _)) => format!("{}...", &after[..i]),
+        None => after.to_string(),
+    };
+
+    let mut job = egui::text::LayoutJob::default();
+    let format = |color: Color32, background: Color32, strikethrough: bool| TextFormat {
+        color,
+        background,
+        strikethrough: if strikethrough {
+            egui::Stroke::new(1.0, color)
+        } else {
+            egui::Stroke::NONE
+        },
+        font_id: egui::FontId::monospace(12.0),
+        ..Default::default()
+    };
+    let plain = format(colors.text, Color32::TRANSPARENT, false);
+
+    job.append(
+        &format!("{:>4} - ", search_match.line_number),
+        0.0,
+        format(colors.secondary, Color32::TRANSPARENT, false),
+    );
+    job.append(&before, 0.0, plain.clone());
+    job.append(matched, 0.0, format(colors.text, colors.removed_bg, true));
+    job.append(&after, 0.0, plain.clone());
+    job.append(
+        "\n     + ",
+        0.0,
+        format(colors.secondary, Color32::TRANSPARENT, false),
+    );
+    job.append(&before, 0.0, plain.clone());
+    job.append(
+        replacement,
+        0.0,
+        format(colors.text, colors.added_bg, false),
+    );
+    job.append(&after, 0.0, plain);
+    job
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::TempDir;
 
     #[test]
     fn test_search_panel_new() {
@@ -626,8 +1052,141 @@ mod tests {
             line_content: "Hello world".to_string(),
             match_start: 6,
             match_end: 11,
+            context_before: Vec::new(),
+            context_after: Vec::new(),
+            included: true,
         };
         assert_eq!(m.line_number, 10);
         assert_eq!(&m.line_content[m.match_start..m.match_end], "world");
     }
+
+    /// Search files with the given text and wait for the results.
+    fn search_documents(panel: &mut SearchPanel, documents: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let mut open = HashMap::new();
+        for (name, content) in documents {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            // As open tabs, so line endings are searched as written
+            open.insert(path, content.to_string());
+        }
+        panel.search(dir.path(), &[], open);
+        while panel.poll() {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        dir
+    }
+
+    #[test]
+    fn test_search_streams_into_panel() {
+        let mut panel = SearchPanel::new();
+        panel.query = "aa".to_string();
+        panel.include = "*.md".to_string();
+        search_documents(
+            &mut panel,
+            &[("a.md", "aa aa"), ("b.md", "aa"), ("c.txt", "aa")],
+        );
+
+        assert!(!panel.is_searching());
+        assert_eq!(panel.results.len(), 2);
+        assert_eq!(panel.total_matches, 3);
+        assert_eq!(panel.files_searched, 2);
+        assert_eq!(panel.selected_count(), 3);
+        assert_eq!(panel.last_query, panel.search_key());
+
+        panel.exclude = "[".to_string();
+        search_documents(&mut panel, &[]);
+        assert!(panel.error_message.is_some());
+    }
+
+    #[test]
+    fn test_next_result_steps_across_files() {
+        let mut panel = SearchPanel::new();
+        panel.query = "aa".to_string();
+        search_documents(&mut panel, &[("a.md", "aa\nx aa"), ("b.md", "y\n aa")]);
+        let step = |panel: &mut SearchPanel, forward: bool| {
+            panel.next_result(forward).map(|location| {
+                let name = location.path.file_name().unwrap().to_string_lossy();
+                (name.to_string(), location.line_number, location.match_start)
+            })
+        };
+
+        assert_eq!(step(&mut panel, true), Some(("a.md".to_string(), 1, 0)));
+        assert_eq!(step(&mut panel, true), Some(("a.md".to_string(), 2, 2)));
+        assert_eq!(step(&mut panel, true), Some(("b.md".to_string(), 2, 1)));
+        // Wraps around both ways
+        assert_eq!(step(&mut panel, true), Some(("a.md".to_string(), 1, 0)));
+        assert_eq!(step(&mut panel, false), Some(("b.md".to_string(), 2, 1)));
+
+        // A new search starts over, and the context lines come along
+        panel.set_context_lines(1);
+        search_documents(&mut panel, &[("c.md", "y\n aa")]);
+        assert_eq!(panel.current, None);
+        assert_eq!(panel.results[0].matches[0].context_before, vec!["y"]);
+        panel.results.clear();
+        assert_eq!(step(&mut panel, false), None);
+    }
+
+    #[test]
+    fn test_replace_in_respects_selection() {
+        let mut panel = SearchPanel::new();
+        panel.query = "cat".to_string();
+        panel.replacement = "dog".to_string();
+        let content = "cat cat\r\nno\ncat";
+        let dir = search_documents(&mut panel, &[("a.md", content)]);
+        panel.results[0].matches[1].included = false;
+
+        let path = &dir.path().join("a.md");
+        assert_eq!(
+            panel.replace_in(path, content),
+            FileReplace::Replaced("dog cat\r\nno\ndog".to_string(), 2)
+        );
+        assert_eq!(panel.selected_count(), 2);
+
+        panel.results[0].included = false;
+        assert_eq!(panel.replace_targets(), Vec::<PathBuf>::new());
+        assert_eq!(panel.replace_in(path, content), FileReplace::Unselected);
+
+        // Edits since the search make the results stale
+        panel.results[0].included = true;
+        assert_eq!(panel.replace_in(path, "cat"), FileReplace::Stale);
+    }
+
+    #[test]
+    fn test_replace_hidden_matches_follow_file() {
+        let mut panel = SearchPanel::new();
+        panel.query = "x".to_string();
+        panel.replacement = "y".to_string();
+        let content = "x".repeat(100);
+        let dir = search_documents(&mut panel, &[("a.md", &content)]);
+        assert!(panel.results[0].truncated);
+        panel.results[0].matches[0].included = false;
+
+        let expected = format!("x{}", "y".repeat(99));
+        assert_eq!(
+            panel.replace_in(&dir.path().join("a.md"), &content),
+            FileReplace::Replaced(expected, 99)
+        );
+    }
+
+    #[test]
+    fn test_regex_replace_expands_captures() {
+        let mut panel = SearchPanel::new();
+        panel.query = r"(\w+)@(\w+)".to_string();
+        panel.use_regex = true;
+        panel.replacement = r"$2\t${1}".to_string();
+        let content = "mail ann@example and bob@test";
+        let dir = search_documents(&mut panel, &[("a.md", content)]);
+        let path = &dir.path().join("a.md");
+
+        assert_eq!(
+            panel.replace_in(path, content),
+            FileReplace::Replaced("mail example\tann and test\tbob".to_string(), 2)
+        );
+
+        panel.query = "(".to_string();
+        search_documents(&mut panel, &[("a.md", content)]);
+        assert!(panel.error_message.is_some());
+        assert_eq!(panel.replace_in(path, content), FileReplace::Unselected);
+    }
 }
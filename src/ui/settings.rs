@@ -3,7 +3,7 @@
 //! This module implements a modal settings panel that allows users to configure
 //! appearance, editor behavior, and file handling options with live preview.
 
-use crate::config::{EditorFont, Settings, Theme};
+use crate::config::{BackupMode, EditorFont, Settings, Theme};
 use eframe::egui::{self, Color32, RichText, Ui};
 
 /// Settings panel sections for navigation.
@@ -436,12 +436,106 @@ impl SettingsPanel {
                     }
                 }
             });
+
+            ui.add_space(4.0);
+
+            if ui
+                .checkbox(
+                    &mut settings.auto_save_on_focus_loss,
+                    "Save when window loses focus",
+                )
+                .changed()
+            {
+                changed = true;
+            }
+
+            if ui
+                .checkbox(
+                    &mut settings.auto_save_on_tab_switch,
+                    "Save when switching tabs",
+                )
+                .changed()
+            {
+                changed = true;
+            }
+        });
+
+        ui.add_space(16.0);
+        ui.separator();
+        ui.add_space(8.0);
+
+        // Backup copies of the previous version
+        ui.label(RichText::new("Backups").strong());
+        ui.add_space(4.0);
+
+        for mode in BackupMode::all() {
+            ui.horizontal(|ui| {
+                if ui
+                    .selectable_value(&mut settings.backup_mode, *mode, mode.label())
+                    .changed()
+                {
+                    changed = true;
+                }
+                ui.label(RichText::new(mode.description()).weak().small());
+            });
+        }
+
+        ui.add_enabled_ui(settings.backup_mode == BackupMode::Folder, |ui| {
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                ui.label("Keep versions:");
+                ui.add_space(8.0);
+                ui.label(format!("{}", settings.backup_generations));
+            });
+            let generations_slider = ui.add(
+                egui::Slider::new(
+                    &mut settings.backup_generations,
+                    1..=Settings::MAX_BACKUP_GENERATIONS,
+                )
+                .show_value(false),
+            );
+            if generations_slider.changed() {
+                changed = true;
+            }
         });
 
         ui.add_space(16.0);
         ui.separator();
         ui.add_space(8.0);
 
+        // Context around search in files results
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Search in Files").strong());
+            ui.add_space(8.0);
+            ui.label(format!(
+                "{} context line{}",
+                settings.search_context_lines,
+                if settings.search_context_lines == 1 {
+                    ""
+                } else {
+                    "s"
+                }
+            ));
+        });
+        ui.add_space(4.0);
+
+        let context_slider = ui
+            .add(
+                egui::Slider::new(
+                    &mut settings.search_context_lines,
+                    0..=Settings::MAX_SEARCH_CONTEXT_LINES,
+                )
+                .show_value(false),
+            )
+            .on_hover_text("Lines shown before and after each result");
+        if context_slider.changed() {
+            changed = true;
+        }
+
+        ui.add_space(16.0);
+        ui.separator();
+        ui.add_space(8.0);
+
         // Recent files count
         ui.horizontal(|ui| {
             ui.label(RichText::new("Recent Files").strong());
@@ -0,0 +1,353 @@
+//! Backlinks index for workspace notes
+//!
+//! Records the wiki-links in every markdown file of a workspace so the
+//! backlinks panel can list the notes linking to the active one. The index is
+//! built once when first needed and then updated file by file from
+//! [`WorkspaceWatcher`](super::WorkspaceWatcher) events.
+//!
+//! In the app the index lives on a background thread ([`BacklinkJob`]), so
+//! reading the workspace's notes never holds up the UI.
+
+use crate::markdown::wikilinks::{find_wiki_links, is_note, NoteResolver, WikiLink};
+use log::debug;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Longest context snippet shown for a backlink, in characters
+const MAX_SNIPPET_CHARS: usize = 120;
+
+/// A note that links to another note.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backlink {
+    /// File containing the link
+    pub source: PathBuf,
+    /// Line of the link (1-indexed)
+    pub line: usize,
+    /// The line's text, for context
+    pub snippet: String,
+}
+
+/// A link found in a note, with the text of its line.
+#[derive(Debug, Clone)]
+struct IndexedLink {
+    line: usize,
+    link: WikiLink,
+    snippet: String,
+}
+
+/// Wiki-links of every note in a workspace.
+#[derive(Debug, Clone)]
+pub struct BacklinkIndex {
+    /// Workspace root
+    root: PathBuf,
+    /// Links by the note containing them
+    links: HashMap<PathBuf, Vec<IndexedLink>>,
+    /// Resolves link targets among the indexed notes
+    resolver: NoteResolver,
+}
+
+impl BacklinkIndex {
+    /// Index the notes among `files` (typically `Workspace::all_files`).
+    pub fn build(root: &Path, files: &[PathBuf]) -> Self {
+        let mut index = Self {
+            root: root.to_path_buf(),
+            links: HashMap::new(),
+            resolver: NoteResolver::default(),
+        };
+        for path in files.iter().filter(|path| is_note(path)) {
+            index.read_file(path);
+        }
+        index.rebuild_resolver();
+        debug!("Indexed wiki-links in {} notes", index.links.len());
+        index
+    }
+
+    /// Re-read a created or modified file.
+    pub fn update_file(&mut self, path: &Path) {
+        if !is_note(path) || !path.starts_with(&self.root) {
+            return;
+        }
+        let is_new = !self.links.contains_key(path);
+        self.read_file(path);
+        if is_new {
+            self.rebuild_resolver();
+        }
+    }
+
+    /// Forget a deleted file.
+    pub fn remove_file(&mut self, path: &Path) {
+        if self.links.remove(path).is_some() {
+            self.rebuild_resolver();
+        }
+    }
+
+    /// Find the notes linking to `note`, ordered by file and line.
+    ///
+    /// Links from the note to itself are left out.
+    pub fn backlinks_to(&self, note: &Path) -> Vec<Backlink> {
+        let mut backlinks: Vec<Backlink> = self
+            .links
+            .iter()
+            .filter(|(source, _)| source.as_path() != note)
+            .flat_map(|(source, links)| {
+                links
+                    .iter()
+                    .filter(|indexed| self.resolver.resolve(&indexed.link.target) == Some(note))
+                    .map(move |indexed| Backlink {
+                        source: source.clone(),
+                        line: indexed.line,
+                        snippet: indexed.snippet.clone(),
+                    })
+            })
+            .collect();
+        backlinks.sort_by(|a, b| a.source.cmp(&b.source).then(a.line.cmp(&b.line)));
+        backlinks
+    }
+
+    fn read_file(&mut self, path: &Path) {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) => {
+                debug!("Skipping {} in backlinks index: {}", path.display(), e);
+                self.links.remove(path);
+                return;
+            }
+        };
+        let lines: Vec<&str> = source.lines().collect();
+        let links = find_wiki_links(&source)
+            .into_iter()
+            .map(|(line, link)| IndexedLink {
+                line,
+                link,
+                snippet: snippet(lines.get(line - 1).copied().unwrap_or_default()),
+            })
+            .collect();
+        self.links.insert(path.to_path_buf(), links);
+    }
+
+    fn rebuild_resolver(&mut self) {
+        let notes: Vec<PathBuf> = self.links.keys().cloned().collect();
+        self.resolver = NoteResolver::new(Some(&self.root), &notes);
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Background Index
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Message to a backlinks thread.
+#[derive(Debug)]
+enum BacklinkCommand {
+    /// Re-read a created or modified file
+    Update(PathBuf),
+    /// Forget a deleted file
+    Remove(PathBuf),
+    /// Report the backlinks of this note, now and whenever they may change
+    Show(PathBuf),
+}
+
+/// A backlinks index built and updated on a background thread.
+///
+/// The thread reports the backlinks of the note last passed to
+/// [`Self::show`], again after every change to the index. Dropping the job
+/// stops the thread.
+#[derive(Debug)]
+pub struct BacklinkJob {
+    root: PathBuf,
+    /// Note last passed to `show`
+    shown: Option<PathBuf>,
+    sender: Sender<BacklinkCommand>,
+    receiver: Receiver<(PathBuf, Vec<Backlink>)>,
+}
+
+impl BacklinkJob {
+    /// Start indexing the notes among `files` on a new thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the thread can't be started.
+    pub fn spawn(root: &Path, files: Vec<PathBuf>) -> io::Result<Self> {
+        let (sender, commands) = mpsc::channel();
+        let (results, receiver) = mpsc::channel();
+        let thread_root = root.to_path_buf();
+
+        std::thread::Builder::new()
+            .name("backlinks-index".to_string())
+            .spawn(move || {
+                let mut index = BacklinkIndex::build(&thread_root, &files);
+                let mut shown: Option<PathBuf> = None;
+                // Handle commands in batches, answering once per batch
+                while let Ok(command) = commands.recv() {
+                    for command in std::iter::once(command).chain(commands.try_iter()) {
+                        match command {
+                            BacklinkCommand::Update(path) => index.update_file(&path),
+                            BacklinkCommand::Remove(path) => index.remove_file(&path),
+                            BacklinkCommand::Show(note) => shown = Some(note),
+                        }
+                    }
+                    if let Some(note) = &shown {
+                        let backlinks = index.backlinks_to(note);
+                        if results.send((note.clone(), backlinks)).is_err() {
+                            break;
+                        }
+                    }
+                }
+                debug!("Backlinks index for {} stopped", thread_root.display());
+            })?;
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            shown: None,
+            sender,
+            receiver,
+        })
+    }
+
+    /// The workspace root this index covers.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Re-read a created or modified file.
+    pub fn update_file(&self, path: &Path) {
+        let _ = self
+            .sender
+            .send(BacklinkCommand::Update(path.to_path_buf()));
+    }
+
+    /// Forget a deleted file.
+    pub fn remove_file(&self, path: &Path) {
+        let _ = self
+            .sender
+            .send(BacklinkCommand::Remove(path.to_path_buf()));
+    }
+
+    /// Ask for the backlinks of `note`, unless it's already the shown note.
+    pub fn show(&mut self, note: &Path) {
+        if self.shown.as_deref() != Some(note) {
+            self.shown = Some(note.to_path_buf());
+            let _ = self.sender.send(BacklinkCommand::Show(note.to_path_buf()));
+        }
+    }
+
+    /// Take the latest backlinks reported since the last call, with the
+    /// note they link to.
+    pub fn poll(&self) -> Option<(PathBuf, Vec<Backlink>)> {
+        self.receiver.try_iter().last()
+    }
+}
+
+/// Trim a line of source for display, shortening long lines.
+fn snippet(line: &str) -> String {
+    let line = line.trim();
+    if line.chars().count() <= MAX_SNIPPET_CHARS {
+        return line.to_string();
+    }
+    let mut short: String = line.chars().take(MAX_SNIPPET_CHARS - 1).collect();
+    short.push('…');
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, name: &str, content: &str) -> PathBuf {
+        let path = root.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_backlinks_to() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let plan = write(root, "Plan.md", "# Plan\nSee [[#Plan]] and [[Plan]].");
+        let daily = write(
+            root,
+            "notes/daily.md",
+            "intro\n  Discussed [[plan#Goals|goals]]  ",
+        );
+        let other = write(root, "other.md", "Links to [[daily]] only");
+        let files = vec![plan.clone(), daily.clone(), other.clone()];
+
+        let index = BacklinkIndex::build(root, &files);
+        let backlinks = index.backlinks_to(&plan);
+        assert_eq!(
+            backlinks,
+            vec![Backlink {
+                source: daily.clone(),
+                line: 2,
+                snippet: "Discussed [[plan#Goals|goals]]".to_string(),
+            }]
+        );
+        assert_eq!(index.backlinks_to(&daily).len(), 1);
+        assert!(index.backlinks_to(&other).is_empty());
+    }
+
+    #[test]
+    fn test_update_and_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let target = write(root, "target.md", "# Target");
+        let mut index = BacklinkIndex::build(root, std::slice::from_ref(&target));
+        assert!(index.backlinks_to(&target).is_empty());
+
+        let source = write(root, "source.md", "[[Target]]");
+        index.update_file(&source);
+        assert_eq!(index.backlinks_to(&target)[0].source, source);
+
+        fs::write(&source, "no links").unwrap();
+        index.update_file(&source);
+        assert!(index.backlinks_to(&target).is_empty());
+
+        // A link to a note that doesn't exist yet resolves once it's created
+        fs::write(&source, "[[Later]]").unwrap();
+        index.update_file(&source);
+        let later = write(root, "Later.md", "");
+        index.update_file(&later);
+        assert_eq!(index.backlinks_to(&later).len(), 1);
+
+        index.remove_file(&source);
+        assert!(index.backlinks_to(&later).is_empty());
+    }
+
+    #[test]
+    fn test_backlink_job_reports_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let target = write(root, "target.md", "# Target");
+        let source = write(root, "source.md", "[[Target]]");
+        let mut job = BacklinkJob::spawn(root, vec![target.clone(), source.clone()]).unwrap();
+
+        fn wait(job: &BacklinkJob) -> (PathBuf, Vec<Backlink>) {
+            loop {
+                if let Some(result) = job.poll() {
+                    return result;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+        }
+
+        job.show(&target);
+        let (note, backlinks) = wait(&job);
+        assert_eq!(note, target);
+        assert_eq!(backlinks[0].source, source);
+
+        // Changes to the index report the shown note's backlinks again
+        job.remove_file(&source);
+        assert!(wait(&job).1.is_empty());
+    }
+
+    #[test]
+    fn test_snippet_truncates() {
+        let long = "x".repeat(200);
+        let short = snippet(&long);
+        assert_eq!(short.chars().count(), MAX_SNIPPET_CHARS);
+        assert!(short.ends_with('…'));
+    }
+}
@@ -4,6 +4,8 @@
 //! - File tree data structures and scanning
 //! - Workspace settings and state persistence
 //! - File watching for external changes
+//! - Backlinks index for wiki-links between notes
+//! - Background search in files
 
 // Allow dead code - workspace module contains complete API for settings
 // persistence and tree operations that may not all be used yet
@@ -11,15 +13,22 @@
 #![allow(dead_code)]
 #![allow(clippy::only_used_in_recursion)]
 
+mod backlinks;
 mod file_tree;
 mod persistence;
+mod search;
 mod settings;
 mod watcher;
 
+pub use backlinks::{Backlink, BacklinkJob};
 pub use file_tree::{FileTreeNode, FileTreeNodeKind};
 pub use persistence::{load_workspace_state, save_workspace_state, WorkspaceState};
+pub use search::{
+    content_hash, lines_with_offsets, parse_globs, FileSearchResults, Matcher, SearchJob,
+    SearchLocation, SearchMatch, SearchRequest,
+};
 pub use settings::{load_workspace_settings, save_workspace_settings, WorkspaceSettings};
-pub use watcher::{filter_events, WorkspaceEvent, WorkspaceWatcher};
+pub use watcher::{filter_events, OpenFilesWatcher, WorkspaceEvent, WorkspaceWatcher};
 
 use std::path::PathBuf;
 
@@ -31,12 +40,13 @@ use std::path::PathBuf;
 ///
 /// Determines whether the app is in single-file editing mode or
 /// workspace/folder mode with full project management features.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub enum AppMode {
     /// Single file mode - traditional editor behavior
     ///
     /// The app operates on individual files without workspace features.
     /// This is the default mode when opening files directly.
+    #[default]
     SingleFile,
 
     /// Workspace mode - folder-based project management
@@ -51,12 +61,6 @@ pub enum AppMode {
     },
 }
 
-impl Default for AppMode {
-    fn default() -> Self {
-        Self::SingleFile
-    }
-}
-
 impl AppMode {
     /// Check if currently in workspace mode.
     pub fn is_workspace(&self) -> bool {
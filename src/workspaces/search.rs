@@ -0,0 +1,630 @@
+//! Search in files for workspaces
+//!
+//! Searches run on a background thread that walks the workspace folder and
+//! sends results back file by file, so the search panel fills in while the
+//! search is running and the UI never waits for it. Starting a new search
+//! cancels the previous one.
+//!
+//! Which files are searched:
+//! - hidden folders (the workspace's hidden patterns) are skipped
+//! - Ferrite's own data (`.ferrite` folders and `~` backups) is always
+//!   skipped
+//! - paths ignored by `.gitignore` files are skipped
+//! - optional include/exclude globs narrow the search further
+//! - binary files are detected from their content and skipped; any other
+//!   file is searched whatever its extension
+
+use crate::editor::unescape_replacement;
+use crate::files::encoding::read_text_file_limited;
+use crate::files::gitignore::GitIgnore;
+use crate::files::glob::Glob;
+use crate::files::save::is_ferrite_data;
+use crate::string_utils::{byte_index_to_char_index, floor_char_boundary};
+use log::debug;
+use regex::{Regex, RegexBuilder};
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use walkdir::{DirEntry, WalkDir};
+
+/// Maximum number of results to keep per file (all matches are counted).
+pub const MAX_RESULTS_PER_FILE: usize = 10;
+
+/// Files larger than this (in bytes) are left out of the search.
+pub const MAX_SEARCH_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Results
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A single search match result.
+#[derive(Debug, Clone)]
+pub struct SearchMatch {
+    /// The line number (1-indexed)
+    pub line_number: usize,
+    /// The line content
+    pub line_content: String,
+    /// Start position of match in line
+    pub match_start: usize,
+    /// End position of match in line
+    pub match_end: usize,
+    /// Lines just before the match's line, for context
+    pub context_before: Vec<String>,
+    /// Lines just after the match's line, for context
+    pub context_after: Vec<String>,
+    /// Whether the match is replaced by Replace
+    pub included: bool,
+}
+
+/// Results for a single file.
+#[derive(Debug, Clone)]
+pub struct FileSearchResults {
+    /// Path to the file
+    pub path: PathBuf,
+    /// Matches in this file
+    pub matches: Vec<SearchMatch>,
+    /// Number of matches in the file, including those not shown
+    pub match_count: usize,
+    /// Whether there are more matches than shown
+    pub truncated: bool,
+    /// Whether this file's results are expanded in the UI
+    pub expanded: bool,
+    /// Whether Replace changes this file (matches not shown are replaced
+    /// along with the file)
+    pub included: bool,
+    /// Hash of the searched text, to detect edits made since the search
+    pub content_hash: u64,
+}
+
+impl FileSearchResults {
+    /// Where the match at `index` is, for opening it in the editor.
+    pub fn location(&self, index: usize) -> Option<SearchLocation> {
+        self.matches.get(index).map(|m| SearchLocation {
+            path: self.path.clone(),
+            line_number: m.line_number,
+            match_start: m.match_start,
+            match_end: m.match_end,
+        })
+    }
+}
+
+/// A match's place in a file: the file, line and span to select.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchLocation {
+    /// Path to the file
+    pub path: PathBuf,
+    /// The line number (1-indexed)
+    pub line_number: usize,
+    /// Start of the match in the line (byte offset)
+    pub match_start: usize,
+    /// End of the match in the line (byte offset)
+    pub match_end: usize,
+}
+
+impl SearchLocation {
+    /// The match as a character range in `content`, for selecting it.
+    ///
+    /// The text may have changed since the search: a span past the end of
+    /// the line is cut short, and None is returned if the line is gone.
+    pub fn char_range(&self, content: &str) -> Option<(usize, usize)> {
+        let (offset, line) = lines_with_offsets(content).nth(self.line_number.checked_sub(1)?)?;
+        let start = floor_char_boundary(line, self.match_start);
+        let end = floor_char_boundary(line, self.match_end).max(start);
+        let start_char = byte_index_to_char_index(content, offset + start);
+        Some((start_char, start_char + line[start..end].chars().count()))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Matcher
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Compiled search query.
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    /// The query as a regex (escaped for plain-text searches)
+    regex: Regex,
+    /// Whether replacements expand capture groups
+    use_regex: bool,
+}
+
+impl Matcher {
+    /// Compile a query.
+    ///
+    /// # Errors
+    ///
+    /// Returns the regex error for an invalid regex query.
+    pub fn new(query: &str, use_regex: bool, case_sensitive: bool) -> Result<Self, regex::Error> {
+        let pattern = if use_regex {
+            Cow::Borrowed(query)
+        } else {
+            Cow::Owned(regex::escape(query))
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!case_sensitive)
+            .build()?;
+        Ok(Self { regex, use_regex })
+    }
+
+    /// Find the (non-overlapping) matches in a line.
+    pub fn find(&self, line: &str) -> Vec<(usize, usize)> {
+        self.regex
+            .find_iter(line)
+            .map(|m| (m.start(), m.end()))
+            .collect()
+    }
+
+    /// Turn the Replace field into the template used by [`Self::replacement`].
+    pub fn template(&self, replacement: &str) -> String {
+        if self.use_regex {
+            unescape_replacement(replacement)
+        } else {
+            replacement.to_string()
+        }
+    }
+
+    /// The text replacing the match at `start` in `line`, or `None` if the
+    /// query doesn't match there.
+    pub fn replacement(&self, line: &str, start: usize, template: &str) -> Option<String> {
+        if !self.use_regex {
+            return Some(template.to_string());
+        }
+        // `captures_at` sees the whole line, so anchors and `\b` behave as
+        // they did when searching, but it can find a later match
+        let caps = Some(start)
+            .filter(|&start| start <= line.len())
+            .and_then(|start| self.regex.captures_at(line, start))
+            .filter(|caps| caps.get(0).is_some_and(|m| m.start() == start))?;
+        let mut expanded = String::new();
+        caps.expand(template, &mut expanded);
+        Some(expanded)
+    }
+
+    /// Search one file's text, keeping up to `context_lines` lines before
+    /// and after each shown match.
+    pub fn search_text(
+        &self,
+        path: &Path,
+        content: &str,
+        context_lines: usize,
+    ) -> Option<FileSearchResults> {
+        let mut results = FileSearchResults {
+            path: path.to_path_buf(),
+            matches: Vec::new(),
+            match_count: 0,
+            truncated: false,
+            expanded: true,
+            included: true,
+            content_hash: content_hash(content),
+        };
+
+        let lines: Vec<&str> = lines_with_offsets(content).map(|(_, line)| line).collect();
+        let context = |range: std::ops::Range<usize>| -> Vec<String> {
+            lines[range].iter().map(|line| line.to_string()).collect()
+        };
+
+        for (line_idx, line) in lines.iter().enumerate() {
+            for (match_start, match_end) in self.find(line) {
+                results.match_count += 1;
+                if results.matches.len() >= MAX_RESULTS_PER_FILE {
+                    results.truncated = true;
+                    continue;
+                }
+                results.matches.push(SearchMatch {
+                    line_number: line_idx + 1,
+                    line_content: line.to_string(),
+                    match_start,
+                    match_end,
+                    context_before: context(line_idx.saturating_sub(context_lines)..line_idx),
+                    context_after: context(
+                        line_idx + 1..(line_idx + 1 + context_lines).min(lines.len()),
+                    ),
+                    included: true,
+                });
+            }
+        }
+
+        (results.match_count > 0).then_some(results)
+    }
+}
+
+/// Split text into lines like [`str::lines`], with each line's byte offset.
+pub fn lines_with_offsets(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    content.split_inclusive('\n').scan(0, |offset, raw| {
+        let start = *offset;
+        *offset += raw.len();
+        let line = raw.strip_suffix('\n').unwrap_or(raw);
+        Some((start, line.strip_suffix('\r').unwrap_or(line)))
+    })
+}
+
+/// Hash a file's text, to tell if it changed since it was searched.
+pub fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Parse a comma-separated list of globs, e.g. `*.md, docs/**`.
+///
+/// # Errors
+///
+/// Returns a message for the first malformed pattern.
+pub fn parse_globs(list: &str) -> Result<Vec<Glob>, String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .map(Glob::new)
+        .collect()
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Search Request
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// What to search for, and where.
+#[derive(Debug, Clone)]
+pub struct SearchRequest {
+    /// Workspace folder to search
+    pub root: PathBuf,
+    /// The query
+    pub matcher: Matcher,
+    /// Only files matching one of these are searched (all if empty)
+    pub include: Vec<Glob>,
+    /// Files and folders matching any of these are skipped
+    pub exclude: Vec<Glob>,
+    /// Workspace hidden patterns (folder and file names to skip)
+    pub hidden_patterns: Vec<String>,
+    /// Text of files open in tabs, searched instead of the saved file
+    pub open_documents: HashMap<PathBuf, String>,
+    /// Lines of context kept around each match
+    pub context_lines: usize,
+}
+
+impl SearchRequest {
+    /// Search the workspace on the calling thread.
+    ///
+    /// `on_file` is called for each file with matches, in path order. Stops
+    /// early once `cancelled` is set. Returns the number of files searched.
+    pub fn run(&self, cancelled: &AtomicBool, mut on_file: impl FnMut(FileSearchResults)) -> usize {
+        let mut gitignore = GitIgnore::new(&self.root);
+        let mut files_searched = 0;
+
+        let walker = WalkDir::new(&self.root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| entry.depth() == 0 || !self.skips(entry, &mut gitignore));
+
+        for entry in walker.filter_map(Result::ok) {
+            if cancelled.load(Ordering::Relaxed) {
+                break;
+            }
+            if !entry.file_type().is_file() || !self.includes(entry.path()) {
+                continue;
+            }
+
+            let path = entry.path();
+            let content = match self.open_documents.get(path) {
+                Some(content) => Cow::Borrowed(content.as_str()),
+                // Binary, undecodable and very large files fail to read and
+                // are skipped
+                None => match read_text_file_limited(path, MAX_SEARCH_FILE_SIZE) {
+                    Ok((content, _)) => Cow::Owned(content),
+                    Err(_) => continue,
+                },
+            };
+
+            files_searched += 1;
+            if let Some(results) = self.matcher.search_text(path, &content, self.context_lines) {
+                on_file(results);
+            }
+        }
+        files_searched
+    }
+
+    /// Check if a walked file or folder is left out entirely.
+    fn skips(&self, entry: &DirEntry, gitignore: &mut GitIgnore) -> bool {
+        let name = entry.file_name().to_string_lossy();
+        let hidden = self
+            .hidden_patterns
+            .iter()
+            .any(|p| name.contains(p.as_str()) || name == *p);
+        let relative = entry
+            .path()
+            .strip_prefix(&self.root)
+            .unwrap_or(entry.path());
+        hidden
+            || is_ferrite_data(&name, entry.file_type().is_dir())
+            || self.exclude.iter().any(|glob| glob.is_match(relative))
+            || gitignore.is_ignored(entry.path(), entry.file_type().is_dir())
+    }
+
+    /// Check if a file passes the include globs.
+    fn includes(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        self.include.is_empty() || self.include.iter().any(|glob| glob.is_match(relative))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Background Search
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Message from a search thread.
+#[derive(Debug)]
+enum SearchEvent {
+    /// Results for one file
+    File(FileSearchResults),
+    /// The search finished, after searching this many files
+    Done(usize),
+}
+
+/// A search running on a background thread.
+///
+/// Dropping the job cancels the search.
+#[derive(Debug)]
+pub struct SearchJob {
+    receiver: Receiver<SearchEvent>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl SearchJob {
+    /// Start searching on a new thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the thread can't be started.
+    pub fn spawn(request: SearchRequest) -> io::Result<Self> {
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancelled);
+
+        std::thread::Builder::new()
+            .name("workspace-search".to_string())
+            .spawn(move || {
+                let files_searched = request.run(&flag, |results| {
+                    let _ = sender.send(SearchEvent::File(results));
+                });
+                if flag.load(Ordering::Relaxed) {
+                    debug!("Workspace search cancelled");
+                } else {
+                    let _ = sender.send(SearchEvent::Done(files_searched));
+                }
+            })?;
+
+        Ok(Self {
+            receiver,
+            cancelled,
+        })
+    }
+
+    /// Move the results found since the last call into `results`.
+    ///
+    /// Returns the number of files searched once the search has finished.
+    pub fn poll(&self, results: &mut Vec<FileSearchResults>) -> Option<usize> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(SearchEvent::File(file)) => results.push(file),
+                Ok(SearchEvent::Done(files_searched)) => return Some(files_searched),
+                Err(TryRecvError::Empty) => return None,
+                // The thread is gone without finishing (it panicked)
+                Err(TryRecvError::Disconnected) => return Some(0),
+            }
+        }
+    }
+
+    /// Stop the search.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for SearchJob {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn request(root: &Path, query: &str) -> SearchRequest {
+        SearchRequest {
+            root: root.to_path_buf(),
+            matcher: Matcher::new(query, false, false).unwrap(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            hidden_patterns: vec!["node_modules".to_string()],
+            open_documents: HashMap::new(),
+            context_lines: 0,
+        }
+    }
+
+    fn found(request: &SearchRequest, root: &Path) -> Vec<String> {
+        let mut paths = Vec::new();
+        request.run(&AtomicBool::new(false), |results| {
+            let relative = results.path.strip_prefix(root).unwrap();
+            paths.push(relative.to_string_lossy().replace('\\', "/"));
+        });
+        paths
+    }
+
+    fn workspace() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("notes/drafts")).unwrap();
+        fs::create_dir_all(root.join("node_modules")).unwrap();
+        fs::create_dir_all(root.join("build")).unwrap();
+        fs::create_dir_all(root.join(".ferrite/backups/notes")).unwrap();
+        fs::write(root.join(".gitignore"), "build/\n*.bak\n").unwrap();
+        let files: [(&str, &[u8]); 11] = [
+            ("a.md", b"needle"),
+            ("Makefile", b"needle: all"),
+            ("image.png", b"\x89PNG\r\n\x1a\n\0\0needle"),
+            ("notes/b.md", b"a needle"),
+            ("notes/drafts/c.md", b"needle"),
+            ("notes/old.bak", b"needle"),
+            ("build/out.md", b"needle"),
+            ("node_modules/d.md", b"needle"),
+            ("a.md~", b"needle"),
+            (".ferrite/state.json", b"{\"needle\": 1}"),
+            (".ferrite/backups/notes/b.md.1", b"needle"),
+        ];
+        for (name, content) in files {
+            fs::write(root.join(name), content).unwrap();
+        }
+        temp_dir
+    }
+
+    #[test]
+    fn test_search_skips_ignored_and_binary() {
+        let temp_dir = workspace();
+        let root = temp_dir.path();
+        let request = request(root, "NEEDLE");
+        // Files without a known extension are searched; binaries, ignored
+        // and hidden paths and Ferrite's own files are not
+        assert_eq!(
+            found(&request, root),
+            vec!["Makefile", "a.md", "notes/b.md", "notes/drafts/c.md"]
+        );
+    }
+
+    #[test]
+    fn test_search_globs() {
+        let temp_dir = workspace();
+        let root = temp_dir.path();
+        let mut request = request(root, "needle");
+        request.include = parse_globs("*.md").unwrap();
+        request.exclude = parse_globs(" drafts , a.md").unwrap();
+        assert_eq!(found(&request, root), vec!["notes/b.md"]);
+
+        request.include = parse_globs("notes/**").unwrap();
+        request.exclude.clear();
+        assert_eq!(
+            found(&request, root),
+            vec!["notes/b.md", "notes/drafts/c.md"]
+        );
+
+        assert!(parse_globs("*.md, [oops").is_err());
+    }
+
+    #[test]
+    fn test_search_open_documents_and_cancel() {
+        let temp_dir = workspace();
+        let root = temp_dir.path();
+        let mut request = request(root, "needle");
+        request
+            .open_documents
+            .insert(root.join("a.md"), "edited".to_string());
+        assert!(!found(&request, root).contains(&"a.md".to_string()));
+
+        let mut count = 0;
+        let searched = request.run(&AtomicBool::new(true), |_| count += 1);
+        assert_eq!((searched, count), (0, 0));
+    }
+
+    #[test]
+    fn test_search_job_streams_results() {
+        let temp_dir = workspace();
+        let job = SearchJob::spawn(request(temp_dir.path(), "needle")).unwrap();
+        let mut results = Vec::new();
+        let files_searched = loop {
+            if let Some(files) = job.poll(&mut results) {
+                break files;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        };
+        // .gitignore is searched too (it has no match)
+        assert_eq!(files_searched, 5);
+        assert_eq!(results.len(), 4);
+    }
+
+    #[test]
+    fn test_search_text_counts_all_matches() {
+        let matcher = Matcher::new("aa", false, false).unwrap();
+        let long = "aa\n".repeat(MAX_RESULTS_PER_FILE + 5);
+        let results = matcher.search_text(Path::new("a.md"), &long, 0).unwrap();
+        assert!(results.truncated);
+        assert_eq!(results.matches.len(), MAX_RESULTS_PER_FILE);
+        assert_eq!(results.match_count, MAX_RESULTS_PER_FILE + 5);
+
+        // Non-overlapping and case-insensitive, after non-ASCII text
+        let results = matcher
+            .search_text(Path::new("b.md"), "AAAA ÄÄaa", 0)
+            .unwrap();
+        assert_eq!(results.match_count, 3);
+        assert!(matcher.search_text(Path::new("c.md"), "a a", 0).is_none());
+    }
+
+    #[test]
+    fn test_search_text_context() {
+        let matcher = Matcher::new("x", false, false).unwrap();
+        let results = matcher
+            .search_text(Path::new("a.md"), "1\n2\nx\n4\n5\n6\nx", 2)
+            .unwrap();
+        let first = &results.matches[0];
+        assert_eq!(first.context_before, vec!["1", "2"]);
+        assert_eq!(first.context_after, vec!["4", "5"]);
+        let last = &results.matches[1];
+        assert_eq!(last.context_before, vec!["5", "6"]);
+        assert!(last.context_after.is_empty());
+    }
+
+    #[test]
+    fn test_location_char_range() {
+        let matcher = Matcher::new("needle", false, false).unwrap();
+        let content = "ÄÖ\r\nsay needle here";
+        let results = matcher.search_text(Path::new("a.md"), content, 0).unwrap();
+        let location = results.location(0).unwrap();
+        assert_eq!((location.line_number, location.match_start), (2, 4));
+        // Characters, not bytes: "ÄÖ\r\nsay " is 8 characters
+        assert_eq!(location.char_range(content), Some((8, 14)));
+        assert!(results.location(1).is_none());
+
+        // Edited since the search: the span is cut to the line, or dropped
+        assert_eq!(location.char_range("x\nsay ne"), Some((6, 8)));
+        assert_eq!(location.char_range("one line"), None);
+    }
+
+    #[test]
+    fn test_replacement_requires_match_at_start() {
+        let matcher = Matcher::new(r"(\w)b", true, true).unwrap();
+        let template = matcher.template("[$1]");
+        assert_eq!(
+            matcher.replacement("ab cb", 3, &template).as_deref(),
+            Some("[c]")
+        );
+        // The regex matches again later on the line, but not at `start`
+        assert_eq!(matcher.replacement("ab cb", 1, &template), None);
+        assert_eq!(matcher.replacement("ab cb", 9, &template), None);
+
+        // `\b` sees the text before `start`, so the later match isn't used
+        let matcher = Matcher::new(r"\b(a)", true, true).unwrap();
+        let template = matcher.template("<$1>");
+        assert_eq!(matcher.find("ba a"), vec![(3, 4)]);
+        assert_eq!(matcher.replacement("ba a", 1, &template), None);
+        assert_eq!(
+            matcher.replacement("ba a", 3, &template).as_deref(),
+            Some("<a>")
+        );
+    }
+
+    #[test]
+    fn test_lines_with_offsets() {
+        let lines: Vec<(usize, &str)> = lines_with_offsets("a\r\nbc\n\nd").collect();
+        assert_eq!(lines, vec![(0, "a"), (3, "bc"), (6, ""), (7, "d")]);
+    }
+}
@@ -4,6 +4,8 @@
 // workspace configuration features
 #![allow(dead_code)]
 
+use crate::config::BackupMode;
+use crate::files::assets::DEFAULT_ASSETS_DIR;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
@@ -38,6 +40,16 @@ pub struct WorkspaceSettings {
     /// Custom file associations (extension -> language)
     #[serde(default)]
     pub file_associations: std::collections::HashMap<String, String>,
+
+    /// Backup mode override for this workspace (None = use global setting)
+    pub backup_mode: Option<BackupMode>,
+
+    /// Backup generations override for this workspace
+    pub backup_generations: Option<u32>,
+
+    /// Folder pasted and dropped images are stored in, relative to the
+    /// document's folder (or absolute)
+    pub assets_dir: String,
 }
 
 impl Default for WorkspaceSettings {
@@ -55,6 +67,9 @@ impl Default for WorkspaceSettings {
                 "mkd".to_string(),
             ],
             file_associations: std::collections::HashMap::new(),
+            backup_mode: None,
+            backup_generations: None,
+            assets_dir: DEFAULT_ASSETS_DIR.to_string(),
         }
     }
 }
@@ -146,6 +161,7 @@ mod tests {
         let settings = WorkspaceSettings::default();
         assert!(settings.hidden_folders.is_empty());
         assert!(settings.theme_override.is_none());
+        assert_eq!(settings.assets_dir, "assets");
         assert!(settings.is_markdown_extension("md"));
         assert!(settings.is_markdown_extension("MD"));
         assert!(settings.is_markdown_extension("markdown"));
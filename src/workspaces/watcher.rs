@@ -8,6 +8,7 @@
 #![allow(dead_code)]
 
 use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::time::Duration;
@@ -116,6 +117,101 @@ impl WorkspaceWatcher {
     }
 }
 
+/// Watches individually opened files (used outside workspace mode).
+///
+/// Each file's parent directory is watched non-recursively rather than the
+/// file itself, so editors that save by writing a temp file and renaming it
+/// over the original are still picked up. Events for other files in those
+/// directories are dropped.
+#[derive(Debug)]
+pub struct OpenFilesWatcher {
+    /// The internal notify watcher
+    watcher: RecommendedWatcher,
+    /// Receiver for file system events
+    receiver: Receiver<WorkspaceEvent>,
+    /// Files we report events for
+    files: HashSet<PathBuf>,
+    /// Parent directories currently being watched
+    dirs: HashSet<PathBuf>,
+}
+
+impl OpenFilesWatcher {
+    /// Create a watcher with no files.
+    ///
+    /// Returns an error if the watcher cannot be created.
+    pub fn new() -> Result<Self, String> {
+        let (tx, rx) = channel();
+        let watcher = RecommendedWatcher::new(
+            move |result: Result<Event, notify::Error>| {
+                WorkspaceWatcher::handle_event(result, &tx);
+            },
+            Config::default().with_poll_interval(Duration::from_millis(500)),
+        )
+        .map_err(|e| format!("Failed to create file watcher: {}", e))?;
+
+        Ok(Self {
+            watcher,
+            receiver: rx,
+            files: HashSet::new(),
+            dirs: HashSet::new(),
+        })
+    }
+
+    /// Replace the set of watched files.
+    ///
+    /// Directories no longer needed are unwatched; new ones are watched.
+    /// Failures to watch a directory are logged and otherwise ignored.
+    pub fn set_files(&mut self, files: HashSet<PathBuf>) {
+        if files == self.files {
+            return;
+        }
+
+        let dirs: HashSet<PathBuf> = files
+            .iter()
+            .filter_map(|f| f.parent().map(Path::to_path_buf))
+            .collect();
+
+        for dir in self.dirs.difference(&dirs) {
+            let _ = self.watcher.unwatch(dir);
+        }
+        for dir in dirs.difference(&self.dirs) {
+            if let Err(e) = self.watcher.watch(dir, RecursiveMode::NonRecursive) {
+                log::warn!("Failed to watch {}: {}", dir.display(), e);
+            }
+        }
+
+        self.files = files;
+        self.dirs = dirs;
+    }
+
+    /// Get the set of watched files.
+    pub fn files(&self) -> &HashSet<PathBuf> {
+        &self.files
+    }
+
+    /// Poll for pending events on watched files.
+    ///
+    /// This is non-blocking.
+    pub fn poll_events(&self) -> Vec<WorkspaceEvent> {
+        let mut events = Vec::new();
+        while let Ok(event) = self.receiver.try_recv() {
+            let keep = match &event {
+                WorkspaceEvent::FileModified(p)
+                | WorkspaceEvent::FileCreated(p)
+                | WorkspaceEvent::FileDeleted(p) => self.files.contains(p),
+                WorkspaceEvent::FileRenamed(from, to) => {
+                    self.files.contains(from) || self.files.contains(to)
+                }
+                WorkspaceEvent::Error(_) => true,
+            };
+            if keep {
+                events.push(event);
+            }
+        }
+        events
+    }
+}
+
 /// Filter events to exclude hidden/ignored paths.
 pub fn filter_events(
     events: Vec<WorkspaceEvent>,
@@ -192,4 +288,42 @@ mod tests {
         let filtered = filter_events(events, &hidden);
         assert_eq!(filtered.len(), 1);
     }
+
+    #[test]
+    fn test_open_files_watcher_reports_only_watched_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let watched = temp_dir.path().join("open.md");
+        let other = temp_dir.path().join("other.md");
+        std::fs::write(&watched, "a").unwrap();
+        std::fs::write(&other, "a").unwrap();
+
+        let mut watcher = OpenFilesWatcher::new().unwrap();
+        watcher.set_files(HashSet::from([watched.clone()]));
+        assert!(watcher.files().contains(&watched));
+
+        std::fs::write(&other, "b").unwrap();
+        std::fs::write(&watched, "b").unwrap();
+
+        // Events arrive asynchronously; give the backend a moment
+        let mut events = Vec::new();
+        for _ in 0..40 {
+            events.extend(watcher.poll_events());
+            if !events.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        std::thread::sleep(Duration::from_millis(100));
+        events.extend(watcher.poll_events());
+
+        assert!(!events.is_empty());
+        for event in &events {
+            match event {
+                WorkspaceEvent::FileModified(p)
+                | WorkspaceEvent::FileCreated(p)
+                | WorkspaceEvent::FileDeleted(p) => assert_eq!(p, &watched),
+                other => panic!("Unexpected event: {:?}", other),
+            }
+        }
+    }
 }